@@ -0,0 +1,330 @@
+//! Reference lobby server for `cn_chess_tui --online`
+//!
+//! Speaks the protocol described in `cn_chess_tui::online`: players log in,
+//! post and accept seeks, and moves are checked against the rules before
+//! being passed to the opponent and to anyone watching. Everything is kept
+//! in memory.
+//!
+//! ```text
+//! cargo run --features online --example online_server -- 127.0.0.1:9000
+//! cargo run --features online -- --online ws://127.0.0.1:9000 alice
+//! cargo run --features online -- --spectate ws://127.0.0.1:9000
+//! ```
+
+use cn_chess_tui::online::{ClientMessage, GameInfo, Seek, ServerMessage};
+use cn_chess_tui::{iccs_to_move, Color, Game, GameResult};
+use std::collections::HashMap;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+use tungstenite::{Error, Message, WebSocket};
+
+struct Player {
+    socket: WebSocket<TcpStream>,
+    name: Option<String>,
+}
+
+struct ServerGame {
+    red: usize,
+    black: usize,
+    game: Game,
+    /// Start position, and the moves since in ICCS, for spectators who
+    /// join late
+    fen: String,
+    moves: Vec<String>,
+    spectators: Vec<usize>,
+}
+
+impl ServerGame {
+    fn color_of(&self, player: usize) -> Option<Color> {
+        match player {
+            p if p == self.red => Some(Color::Red),
+            p if p == self.black => Some(Color::Black),
+            _ => None,
+        }
+    }
+
+    fn player(&self, color: Color) -> usize {
+        match color {
+            Color::Red => self.red,
+            Color::Black => self.black,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Lobby {
+    players: HashMap<usize, Player>,
+    /// Open seeks with the player who posted them
+    seeks: Vec<(Seek, usize)>,
+    games: HashMap<u64, ServerGame>,
+    next_id: u64,
+}
+
+impl Lobby {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn send(&mut self, player: usize, message: &ServerMessage) {
+        let Some(p) = self.players.get_mut(&player) else {
+            return;
+        };
+        let text = serde_json::to_string(message).expect("messages serialize");
+        // A send that would block is queued and flushed on a later pass
+        let _ = p.socket.send(Message::Text(text));
+    }
+
+    fn name(&self, player: usize) -> String {
+        self.players
+            .get(&player)
+            .and_then(|p| p.name.clone())
+            .unwrap_or_default()
+    }
+
+    fn refuse(&mut self, player: usize, message: &str) {
+        let message = ServerMessage::Error {
+            message: message.to_string(),
+        };
+        self.send(player, &message);
+    }
+
+    fn handle(&mut self, player: usize, message: ClientMessage) {
+        let logged_in = self.players[&player].name.clone();
+        match (message, logged_in) {
+            (ClientMessage::Login { name, .. }, None) => {
+                if name.trim().is_empty() {
+                    return self.refuse(player, "name must not be empty");
+                }
+                self.players.get_mut(&player).unwrap().name = Some(name.clone());
+                self.send(player, &ServerMessage::Welcome { name });
+            }
+            (ClientMessage::Login { .. }, Some(_)) => self.refuse(player, "already logged in"),
+            (ClientMessage::ListGames, _) => {
+                let mut games: Vec<GameInfo> = self
+                    .games
+                    .iter()
+                    .map(|(&id, g)| GameInfo {
+                        id,
+                        red: self.name(g.red),
+                        black: self.name(g.black),
+                    })
+                    .collect();
+                games.sort_by_key(|g| g.id);
+                self.send(player, &ServerMessage::Games { games });
+            }
+            (ClientMessage::Watch { game }, _) => self.watch(player, game),
+            (_, None) => self.refuse(player, "log in first"),
+            (ClientMessage::ListSeeks, Some(_)) => {
+                let seeks = self.seeks.iter().map(|(seek, _)| seek.clone()).collect();
+                self.send(player, &ServerMessage::Seeks { seeks });
+            }
+            (ClientMessage::Seek { color }, Some(name)) => {
+                let id = self.next_id();
+                self.seeks.retain(|(_, p)| *p != player);
+                let seek = Seek {
+                    id,
+                    player: name,
+                    color,
+                };
+                self.seeks.push((seek, player));
+            }
+            (ClientMessage::Accept { seek }, Some(_)) => self.accept(player, seek),
+            (ClientMessage::Move { game, mv }, Some(_)) => self.play(player, game, &mv),
+            (ClientMessage::Resign { game }, Some(_)) => {
+                let Some(color) = self.games.get(&game).and_then(|g| g.color_of(player)) else {
+                    return self.refuse(player, "not your game");
+                };
+                let result = match color {
+                    Color::Red => "0-1",
+                    Color::Black => "1-0",
+                };
+                self.end(game, result, "resignation");
+            }
+        }
+    }
+
+    fn accept(&mut self, player: usize, seek: u64) {
+        let Some(index) = self.seeks.iter().position(|(s, _)| s.id == seek) else {
+            return self.refuse(player, "no such seek");
+        };
+        let (seek, seeker) = self.seeks.remove(index);
+        if seeker == player {
+            return self.refuse(player, "cannot accept your own seek");
+        }
+        self.seeks.retain(|(_, p)| *p != player);
+
+        let (red, black) = match seek.color {
+            Some(Color::Black) => (player, seeker),
+            _ => (seeker, player),
+        };
+        let id = self.next_id();
+        let game = Game::new();
+        let fen = game.to_fen();
+        let game = ServerGame {
+            red,
+            black,
+            game,
+            fen: fen.clone(),
+            moves: Vec::new(),
+            spectators: Vec::new(),
+        };
+        self.games.insert(id, game);
+        for (me, them, color) in [(red, black, Color::Red), (black, red, Color::Black)] {
+            let opponent = self.players[&them].name.clone().unwrap_or_default();
+            let message = ServerMessage::GameStart {
+                game: id,
+                color,
+                opponent,
+                fen: fen.clone(),
+            };
+            self.send(me, &message);
+        }
+    }
+
+    fn watch(&mut self, player: usize, id: u64) {
+        let Some(game) = self.games.get_mut(&id) else {
+            return self.refuse(player, "no such game");
+        };
+        if !game.spectators.contains(&player) {
+            game.spectators.push(player);
+        }
+        let (red, black, fen, moves) = (game.red, game.black, game.fen.clone(), game.moves.clone());
+        let message = ServerMessage::Spectate {
+            game: id,
+            red: self.name(red),
+            black: self.name(black),
+            fen,
+            moves,
+        };
+        self.send(player, &message);
+    }
+
+    fn play(&mut self, player: usize, id: u64, mv: &str) {
+        let Some(game) = self.games.get_mut(&id) else {
+            return self.refuse(player, "no such game");
+        };
+        if game.color_of(player) != Some(game.game.turn()) {
+            return self.refuse(player, "not your turn");
+        }
+        let Some((from, to)) = iccs_to_move(mv) else {
+            return self.refuse(player, "not an ICCS move");
+        };
+        if let Err(e) = game.game.make_move(from, to) {
+            return self.refuse(player, &e.to_string());
+        }
+        game.moves.push(mv.to_string());
+        let opponent = game.player(game.game.turn());
+        let state = game.game.state();
+        let spectators = game.spectators.clone();
+        let message = ServerMessage::Move {
+            game: id,
+            mv: mv.to_string(),
+        };
+        for to in std::iter::once(opponent).chain(spectators) {
+            self.send(to, &message);
+        }
+        let result = match state.result() {
+            None => return,
+            Some(GameResult::RedWins) => "1-0",
+            Some(GameResult::BlackWins) => "0-1",
+            Some(GameResult::Draw) => "1/2-1/2",
+        };
+        self.end(id, result, &state.to_string());
+    }
+
+    fn end(&mut self, id: u64, result: &str, reason: &str) {
+        let Some(game) = self.games.remove(&id) else {
+            return;
+        };
+        for player in [game.red, game.black].into_iter().chain(game.spectators) {
+            let message = ServerMessage::GameEnd {
+                game: id,
+                result: result.to_string(),
+                reason: Some(reason.to_string()),
+            };
+            self.send(player, &message);
+        }
+    }
+
+    fn disconnect(&mut self, player: usize) {
+        self.players.remove(&player);
+        self.seeks.retain(|(_, p)| *p != player);
+        for game in self.games.values_mut() {
+            game.spectators.retain(|&p| p != player);
+        }
+        let games: Vec<(u64, Color)> = self
+            .games
+            .iter()
+            .filter_map(|(id, g)| Some((*id, g.color_of(player)?)))
+            .collect();
+        for (id, color) in games {
+            let result = match color {
+                Color::Red => "0-1",
+                Color::Black => "1-0",
+            };
+            self.end(id, result, "disconnect");
+        }
+    }
+
+    /// Read every player's waiting messages
+    fn poll(&mut self) {
+        let ids: Vec<usize> = self.players.keys().copied().collect();
+        for id in ids {
+            while let Some(player) = self.players.get_mut(&id) {
+                let _ = player.socket.flush();
+                match player.socket.read() {
+                    Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                        Ok(message) => self.handle(id, message),
+                        Err(e) => self.refuse(id, &format!("bad message: {}", e)),
+                    },
+                    Ok(Message::Close(_)) => {
+                        self.disconnect(id);
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(_) => {
+                        self.disconnect(id);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:9000".to_string());
+    let listener = TcpListener::bind(&address)?;
+    listener.set_nonblocking(true)?;
+    println!("Listening on ws://{}", address);
+
+    let mut lobby = Lobby::default();
+    let mut next_player = 0;
+    loop {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                stream.set_nonblocking(false)?;
+                match tungstenite::accept(stream) {
+                    Ok(socket) => {
+                        socket.get_ref().set_nonblocking(true)?;
+                        println!("{} connected", peer);
+                        next_player += 1;
+                        let player = Player { socket, name: None };
+                        lobby.players.insert(next_player, player);
+                    }
+                    Err(e) => eprintln!("Handshake with {} failed: {}", peer, e),
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+        lobby.poll();
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,193 @@
+//! Static position evaluation
+//!
+//! Scores are material plus a piece-square bonus. Tables are written from
+//! Red's point of view with row 0 being Black's back rank; Black's bonus is
+//! looked up on the mirrored square.
+
+use crate::board::Board;
+use crate::endgame::{classify, EndgameClass};
+use crate::types::{Color, Piece, PieceType, Position};
+
+type PieceSquareTable = [[i32; 9]; 10];
+
+#[rustfmt::skip]
+const SOLDIER_TABLE: PieceSquareTable = [
+    [ 0,  3,  6,  9, 12,  9,  6,  3,  0],
+    [18, 36, 56, 80, 90, 80, 56, 36, 18],
+    [14, 26, 42, 60, 70, 60, 42, 26, 14],
+    [10, 20, 30, 34, 40, 34, 30, 20, 10],
+    [ 6, 12, 18, 18, 20, 18, 18, 12,  6],
+    [ 2,  0,  8,  0,  8,  0,  8,  0,  2],
+    [ 0,  0, -2,  0,  4,  0, -2,  0,  0],
+    [ 0,  0,  0,  0,  0,  0,  0,  0,  0],
+    [ 0,  0,  0,  0,  0,  0,  0,  0,  0],
+    [ 0,  0,  0,  0,  0,  0,  0,  0,  0],
+];
+
+#[rustfmt::skip]
+const HORSE_TABLE: PieceSquareTable = [
+    [ 4,  8, 16, 12,  4, 12, 16,  8,  4],
+    [ 4, 10, 28, 16,  8, 16, 28, 10,  4],
+    [12, 14, 16, 20, 18, 20, 16, 14, 12],
+    [ 8, 24, 18, 24, 20, 24, 18, 24,  8],
+    [ 6, 16, 14, 18, 16, 18, 14, 16,  6],
+    [ 4, 12, 16, 14, 12, 14, 16, 12,  4],
+    [ 2,  6,  8,  6, 10,  6,  8,  6,  2],
+    [ 4,  2,  8,  8,  4,  8,  8,  2,  4],
+    [ 0,  2,  4,  4, -2,  4,  4,  2,  0],
+    [ 0, -4,  0,  0,  0,  0,  0, -4,  0],
+];
+
+#[rustfmt::skip]
+const CHARIOT_TABLE: PieceSquareTable = [
+    [14, 14, 12, 18, 16, 18, 12, 14, 14],
+    [16, 20, 18, 24, 26, 24, 18, 20, 16],
+    [12, 12, 12, 18, 18, 18, 12, 12, 12],
+    [12, 18, 16, 22, 22, 22, 16, 18, 12],
+    [12, 14, 12, 18, 18, 18, 12, 14, 12],
+    [12, 16, 14, 20, 20, 20, 14, 16, 12],
+    [ 6, 10,  8, 14, 14, 14,  8, 10,  6],
+    [ 4,  8,  6, 14, 12, 14,  6,  8,  4],
+    [ 8,  4,  8, 16,  8, 16,  8,  4,  8],
+    [-2, 10,  6, 14, 12, 14,  6, 10, -2],
+];
+
+#[rustfmt::skip]
+const CANNON_TABLE: PieceSquareTable = [
+    [ 6,  4,  0, -10, -12, -10,  0,  4,  6],
+    [ 2,  2,  0,  -4, -14,  -4,  0,  2,  2],
+    [ 2,  2,  0, -10,  -8, -10,  0,  2,  2],
+    [ 0,  0, -2,   4,  10,   4, -2,  0,  0],
+    [ 0,  0,  0,   2,   8,   2,  0,  0,  0],
+    [-2,  0,  4,   2,   6,   2,  4,  0, -2],
+    [ 0,  0,  0,   2,   4,   2,  0,  0,  0],
+    [ 4,  0,  8,   6,  10,   6,  8,  0,  4],
+    [ 0,  2,  4,   6,   6,   6,  4,  2,  0],
+    [ 0,  0,  2,   6,   6,   6,  2,  0,  0],
+];
+
+/// Material value of a piece type
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::ai::piece_value;
+/// use cn_chess_tui::types::PieceType;
+///
+/// assert!(piece_value(PieceType::Chariot) > piece_value(PieceType::Horse));
+/// ```
+pub fn piece_value(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::General => 6000,
+        PieceType::Advisor => 120,
+        PieceType::Elephant => 120,
+        PieceType::Horse => 270,
+        PieceType::Chariot => 600,
+        PieceType::Cannon => 285,
+        PieceType::Soldier => 30,
+    }
+}
+
+/// Total material value of one side's pieces, not counting the general
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::ai::material;
+/// use cn_chess_tui::{Board, Color};
+///
+/// let board = Board::new();
+/// assert_eq!(material(&board, Color::Red), material(&board, Color::Black));
+/// ```
+pub fn material(board: &Board, color: Color) -> i32 {
+    board
+        .pieces_of_color(color)
+        .filter(|(_, piece)| piece.piece_type != PieceType::General)
+        .map(|(_, piece)| piece_value(piece.piece_type))
+        .sum()
+}
+
+/// Piece-square bonus for a piece standing on `pos`
+fn positional_bonus(piece: Piece, pos: Position) -> i32 {
+    let table = match piece.piece_type {
+        PieceType::Soldier => &SOLDIER_TABLE,
+        PieceType::Horse => &HORSE_TABLE,
+        PieceType::Chariot => &CHARIOT_TABLE,
+        PieceType::Cannon => &CANNON_TABLE,
+        _ => return 0,
+    };
+
+    match piece.color {
+        Color::Red => table[pos.y][pos.x],
+        Color::Black => table[9 - pos.y][8 - pos.x],
+    }
+}
+
+/// Pieces on the board, generals included, below which endings are looked
+/// up with [`classify`]
+const ENDGAME_PIECES: usize = 12;
+
+/// Bonus for reaching an ending [`classify`] knows to be won
+const KNOWN_WIN_BONUS: i32 = 300;
+
+/// Evaluate a board from `side`'s point of view
+///
+/// Positive scores favour `side`, negative scores favour the opponent.
+/// Once few pieces are left, endings with a known result are scored for it:
+/// a material lead counts for little in a drawn ending, and a won ending
+/// gets a bonus, so the AI trades into wins and away from draws.
+pub fn evaluate(board: &Board, side: Color) -> i32 {
+    let mut pieces = 0;
+    let score = board
+        .pieces()
+        .map(|(pos, piece)| {
+            pieces += 1;
+            let score = piece_value(piece.piece_type) + positional_bonus(piece, pos);
+            if piece.color == side {
+                score
+            } else {
+                -score
+            }
+        })
+        .sum();
+    if pieces > ENDGAME_PIECES {
+        return score;
+    }
+    match classify(board) {
+        EndgameClass::Draw => score / 8,
+        EndgameClass::Win(color) if color == side => score + KNOWN_WIN_BONUS,
+        EndgameClass::Win(_) => score - KNOWN_WIN_BONUS,
+        EndgameClass::Unknown => score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_position_is_balanced() {
+        let board = Board::new();
+        assert_eq!(evaluate(&board, Color::Red), 0);
+        assert_eq!(evaluate(&board, Color::Black), 0);
+    }
+
+    #[test]
+    fn test_material_advantage() {
+        let mut board = Board::new();
+        board.remove_piece(Position::from_xy(0, 0)); // Black chariot
+        assert!(evaluate(&board, Color::Red) > 0);
+        assert!(evaluate(&board, Color::Black) < 0);
+    }
+
+    #[test]
+    fn test_known_endings_shape_the_score() {
+        use crate::game::Game;
+        let board = |fen| Game::from_fen(fen).unwrap().board().clone();
+
+        // A chariot up, but against all four defenders: a draw
+        let drawn = board("2bak4/4a4/4b4/9/9/9/9/9/9/R3K4 w - - 0 1");
+        let won = board("3ak4/4a4/9/9/9/9/9/9/9/R3K4 w - - 0 1");
+        assert!(evaluate(&drawn, Color::Red) < 200);
+        assert!(evaluate(&won, Color::Red) > 600 + KNOWN_WIN_BONUS - 250);
+        assert_eq!(evaluate(&won, Color::Black), -evaluate(&won, Color::Red));
+    }
+}
@@ -0,0 +1,10 @@
+//! Built-in AI opponent
+//!
+//! A native alpha-beta search with a material + piece-square evaluation,
+//! so the computer can play without an external UCCI engine binary.
+
+pub mod eval;
+pub mod search;
+
+pub use eval::{evaluate, material, piece_value};
+pub use search::{SearchResult, Searcher, DEFAULT_SEARCH_DEPTH};
@@ -0,0 +1,364 @@
+//! Alpha-beta search
+//!
+//! Negamax with alpha-beta pruning over the board's legal move generator,
+//! followed by a captures-only quiescence search at the horizon. Captures are
+//! tried first (most valuable victim, least valuable attacker) to make
+//! pruning effective.
+
+use crate::ai::eval::{evaluate, piece_value};
+use crate::board::Board;
+use crate::game::Move;
+use crate::rng::RandomSource;
+use crate::types::{Color, Position};
+
+/// Search depth used when none is configured
+pub const DEFAULT_SEARCH_DEPTH: u32 = 3;
+
+/// Score for delivering checkmate (adjusted by ply so faster mates win)
+const MATE_SCORE: i32 = 30_000;
+const INFINITY: i32 = MATE_SCORE + 1;
+
+/// Outcome of a search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+    /// Best move found
+    pub mv: Move,
+    /// Score from the searching side's point of view
+    pub score: i32,
+    /// Depth searched (in plies)
+    pub depth: u32,
+    /// Number of positions visited
+    pub nodes: u64,
+}
+
+/// Native alpha-beta searcher
+#[derive(Debug, Clone)]
+pub struct Searcher {
+    depth: u32,
+    nodes: u64,
+    banned: Vec<Move>,
+}
+
+impl Searcher {
+    /// Create a searcher for the given depth in plies (at least 1)
+    pub fn new(depth: u32) -> Self {
+        Self {
+            depth: depth.max(1),
+            nodes: 0,
+            banned: Vec::new(),
+        }
+    }
+
+    /// Leave `moves` out at the root, as UCCI's `banmoves` asks
+    ///
+    /// A position whose only legal moves are banned has no best move.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::ai::Searcher;
+    /// use cn_chess_tui::board::Board;
+    /// use cn_chess_tui::game::Move;
+    /// use cn_chess_tui::types::Color;
+    ///
+    /// let board = Board::new();
+    /// let best = Searcher::new(1).search(&board, Color::Red).unwrap().mv;
+    /// let mut searcher = Searcher::new(1);
+    /// searcher.ban_moves(vec![best]);
+    /// assert_ne!(searcher.search(&board, Color::Red).unwrap().mv, best);
+    /// ```
+    pub fn ban_moves(&mut self, moves: Vec<Move>) {
+        self.banned = moves;
+    }
+
+    /// Get the configured search depth
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Find the best move for `side` on `board`
+    ///
+    /// Returns None if `side` has no legal moves.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::ai::Searcher;
+    /// use cn_chess_tui::board::Board;
+    /// use cn_chess_tui::types::Color;
+    ///
+    /// let board = Board::new();
+    /// let result = Searcher::new(1).search(&board, Color::Red).unwrap();
+    /// assert!(board.is_legal_move(result.mv.from, result.mv.to).is_ok());
+    /// ```
+    pub fn search(&mut self, board: &Board, side: Color) -> Option<SearchResult> {
+        self.search_root(board, side, 0, None)
+    }
+
+    /// Find a good move, picking at random among near-best moves
+    ///
+    /// Every root move scoring within `margin` centipawns of the best is a
+    /// candidate, and `rng` picks one of them. A margin of 0 plays the best
+    /// move as [`search`](Self::search) does, still breaking exact ties at
+    /// random. The same seed gives the same choice.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::ai::Searcher;
+    /// use cn_chess_tui::board::Board;
+    /// use cn_chess_tui::rng::SeededRng;
+    /// use cn_chess_tui::types::Color;
+    ///
+    /// let board = Board::new();
+    /// let a = Searcher::new(1).search_with(&board, Color::Red, 30, &mut SeededRng::new(3));
+    /// let b = Searcher::new(1).search_with(&board, Color::Red, 30, &mut SeededRng::new(3));
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn search_with(
+        &mut self,
+        board: &Board,
+        side: Color,
+        margin: i32,
+        rng: &mut dyn RandomSource,
+    ) -> Option<SearchResult> {
+        self.search_root(board, side, margin.max(0), Some(rng))
+    }
+
+    /// Like [`search_with`](Self::search_with), but `blunder_rate` percent
+    /// of the time any legal move may be picked, however bad
+    ///
+    /// Whether to blunder is drawn from `rng` as well, so the same seed
+    /// gives the same moves. A rate of 0 draws nothing extra and plays as
+    /// `search_with` does.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::ai::Searcher;
+    /// use cn_chess_tui::board::Board;
+    /// use cn_chess_tui::rng::SeededRng;
+    /// use cn_chess_tui::types::Color;
+    ///
+    /// let board = Board::new();
+    /// let mut rng = SeededRng::new(3);
+    /// let result = Searcher::new(1).search_with_blunders(&board, Color::Red, 0, 100, &mut rng);
+    /// assert!(result.is_some());
+    /// ```
+    pub fn search_with_blunders(
+        &mut self,
+        board: &Board,
+        side: Color,
+        margin: i32,
+        blunder_rate: u32,
+        rng: &mut dyn RandomSource,
+    ) -> Option<SearchResult> {
+        let blunder = blunder_rate > 0 && rng.below(100) < blunder_rate as usize;
+        // Every score lies within twice the mate score of the best one
+        let margin = if blunder { 2 * INFINITY } else { margin };
+        self.search_with(board, side, margin, rng)
+    }
+
+    fn search_root(
+        &mut self,
+        board: &Board,
+        side: Color,
+        margin: i32,
+        rng: Option<&mut dyn RandomSource>,
+    ) -> Option<SearchResult> {
+        self.nodes = 0;
+
+        let mut alpha = -INFINITY;
+        let mut scored = Vec::new();
+
+        for (from, to) in ordered_moves(board, side) {
+            if self.banned.contains(&Move::new(from, to)) {
+                continue;
+            }
+            let mut child = board.clone();
+            child.move_piece(from, to);
+            // When choosing at random, widen the window past the margin so
+            // every candidate score is exact and weaker moves fall below it
+            let floor = match rng {
+                Some(_) => alpha - margin - 1,
+                None => alpha,
+            };
+            let score = -self.negamax(
+                &child,
+                side.opponent(),
+                self.depth - 1,
+                -INFINITY,
+                -floor,
+                1,
+            );
+
+            if scored.is_empty() || score > alpha {
+                alpha = score;
+            }
+            scored.push((Move::new(from, to), score));
+        }
+
+        if scored.is_empty() {
+            return None;
+        }
+
+        // Without a random source, the first best move in search order wins
+        let candidates: Vec<(Move, i32)> = match rng {
+            Some(_) => scored
+                .into_iter()
+                .filter(|&(_, score)| score >= alpha - margin)
+                .collect(),
+            None => scored
+                .into_iter()
+                .find(|&(_, score)| score == alpha)
+                .into_iter()
+                .collect(),
+        };
+        let (mv, score) = match rng {
+            Some(rng) => candidates[rng.below(candidates.len())],
+            None => *candidates.first()?,
+        };
+
+        Some(SearchResult {
+            mv,
+            score,
+            depth: self.depth,
+            nodes: self.nodes,
+        })
+    }
+
+    fn negamax(
+        &mut self,
+        board: &Board,
+        side: Color,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> i32 {
+        self.nodes += 1;
+
+        if depth == 0 {
+            return self.quiesce(board, side, alpha, beta);
+        }
+
+        let moves = ordered_moves(board, side);
+        if moves.is_empty() {
+            // No legal moves: checkmate if in check, otherwise stalemate
+            return if board.is_in_check(side) {
+                -MATE_SCORE + ply
+            } else {
+                0
+            };
+        }
+
+        for (from, to) in moves {
+            let mut child = board.clone();
+            child.move_piece(from, to);
+            let score = -self.negamax(&child, side.opponent(), depth - 1, -beta, -alpha, ply + 1);
+
+            if score >= beta {
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        alpha
+    }
+}
+
+impl Searcher {
+    /// Resolve pending captures so the static evaluation isn't taken mid-exchange
+    fn quiesce(&mut self, board: &Board, side: Color, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+
+        let stand_pat = evaluate(board, side);
+        if stand_pat >= beta {
+            return beta;
+        }
+        if stand_pat > alpha {
+            alpha = stand_pat;
+        }
+
+        let mut captures = board.legal_captures(side);
+        sort_by_mvv_lva(board, &mut captures);
+        for (from, to) in captures {
+            let mut child = board.clone();
+            child.move_piece(from, to);
+            let score = -self.quiesce(&child, side.opponent(), -beta, -alpha);
+
+            if score >= beta {
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        alpha
+    }
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEARCH_DEPTH)
+    }
+}
+
+/// Legal moves for `side`, captures first
+fn ordered_moves(board: &Board, side: Color) -> Vec<(Position, Position)> {
+    let mut moves = board.legal_moves(side);
+    sort_by_mvv_lva(board, &mut moves);
+    moves
+}
+
+/// Order captures by most valuable victim, then least valuable attacker
+fn sort_by_mvv_lva(board: &Board, moves: &mut [(Position, Position)]) {
+    moves.sort_by_key(|&(from, to)| match (board.get(to), board.get(from)) {
+        (Some(victim), Some(attacker)) => {
+            -(piece_value(victim.piece_type) * 10 - piece_value(attacker.piece_type))
+        }
+        _ => 0,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::fen_to_board;
+    use crate::rng::SeededRng;
+
+    #[test]
+    fn test_captures_hanging_chariot() {
+        // Red chariot on a4 can take the undefended black chariot on a0
+        let (board, turn) = fen_to_board("r4k3/9/9/9/9/9/R8/9/9/3K5 w - - 0 1").unwrap();
+        let result = Searcher::new(2).search(&board, turn).unwrap();
+        assert_eq!(result.mv.to, Position::from_xy(0, 0));
+    }
+
+    #[test]
+    fn test_full_blunder_rate_plays_weak_moves() {
+        // Always blundering sometimes passes up the hanging chariot
+        let (board, turn) = fen_to_board("r4k3/9/9/9/9/9/R8/9/9/3K5 w - - 0 1").unwrap();
+        let best = Searcher::new(2).search(&board, turn).unwrap().mv;
+        let mut rng = SeededRng::new(1);
+        let moves: Vec<Move> = (0..20)
+            .map(|_| {
+                Searcher::new(2)
+                    .search_with_blunders(&board, turn, 0, 100, &mut rng)
+                    .unwrap()
+                    .mv
+            })
+            .collect();
+        assert!(moves.iter().any(|&mv| mv != best));
+
+        let mut rng = SeededRng::new(1);
+        let never = Searcher::new(2).search_with_blunders(&board, turn, 0, 0, &mut rng);
+        assert_eq!(never.unwrap().mv, best);
+    }
+
+    #[test]
+    fn test_no_moves_returns_none() {
+        // Black general is checkmated by two chariots: no legal moves
+        let (board, _) = fen_to_board("R3k4/R8/9/9/9/9/9/9/9/3K5 b - - 0 1").unwrap();
+        assert!(Searcher::new(2).search(&board, Color::Black).is_none());
+    }
+}
@@ -0,0 +1,384 @@
+//! Engine annotations for whole games
+//!
+//! [`annotate`] evaluates every position of a game with an [`Evaluator`],
+//! either an external engine or the built-in search, and writes the results
+//! into the move comments: each move gets an `[%eval]` tag from Red's point
+//! of view, as read by [`diagram::parse_eval`](crate::diagram::parse_eval),
+//! and the move the evaluator preferred when it differs. Moves that lose
+//! more than a threshold are marked as blunders.
+
+use crate::ai::Searcher;
+use crate::diagram::{format_eval, MATE_SCORE};
+use crate::fen::FenError;
+use crate::game::Game;
+use crate::notation::format::{MoveFormatter, MoveStyle};
+use crate::notation::iccs::move_to_iccs;
+use crate::pgn::PgnGame;
+use crate::pgn_import::{import_pgn, ImportMode};
+use crate::types::Color;
+use crate::ucci::engine::EngineError;
+use crate::ucci::{EngineProtocol, MoveResult, UcciClient};
+use std::fmt::{self, Display, Formatter};
+
+/// An evaluator's verdict on a position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evaluation {
+    /// Centipawns from the side to move's point of view; mates are scored
+    /// as in [`MATE_SCORE`]
+    pub score: i32,
+    /// Best move in ICCS, None when there is no legal move
+    pub best_move: Option<String>,
+}
+
+/// Something that evaluates positions
+pub trait Evaluator {
+    /// Name written as the game's annotator
+    fn name(&self) -> String;
+
+    fn evaluate(&mut self, game: &Game) -> Result<Evaluation, EngineError>;
+}
+
+/// Score of a lost position: the side to move has no legal move
+fn lost() -> Evaluation {
+    Evaluation {
+        score: -MATE_SCORE,
+        best_move: None,
+    }
+}
+
+/// The built-in alpha-beta search at a fixed depth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NativeEvaluator {
+    pub depth: u32,
+}
+
+impl NativeEvaluator {
+    pub fn new(depth: u32) -> Self {
+        Self { depth }
+    }
+}
+
+impl Evaluator for NativeEvaluator {
+    fn name(&self) -> String {
+        format!("cn_chess_tui depth {}", self.depth)
+    }
+
+    fn evaluate(&mut self, game: &Game) -> Result<Evaluation, EngineError> {
+        let Some(result) = Searcher::new(self.depth).search(game.board(), game.turn()) else {
+            return Ok(lost());
+        };
+        // The search scores a mate as 30000 less the plies to it
+        let score = match 30_000 - result.score.abs() {
+            plies if plies < 1000 => result.score.signum() * (MATE_SCORE - (plies + 1) / 2),
+            _ => result.score,
+        };
+        Ok(Evaluation {
+            score,
+            best_move: Some(move_to_iccs(result.mv.from, result.mv.to)),
+        })
+    }
+}
+
+/// An external engine searching each position for a fixed time
+pub struct EngineEvaluator {
+    client: UcciClient,
+    movetime_ms: u64,
+}
+
+impl EngineEvaluator {
+    /// Start an engine speaking `protocol`
+    pub fn start(
+        path: &str,
+        protocol: EngineProtocol,
+        movetime_ms: u64,
+    ) -> Result<Self, EngineError> {
+        let mut client = UcciClient::new(path)?;
+        client.initialize_with(protocol)?;
+        Ok(Self {
+            client,
+            movetime_ms,
+        })
+    }
+
+    /// Stop the engine process
+    pub fn shutdown(self) -> Result<(), EngineError> {
+        self.client.shutdown()
+    }
+}
+
+impl Evaluator for EngineEvaluator {
+    fn name(&self) -> String {
+        self.client.engine_info().name.clone()
+    }
+
+    fn evaluate(&mut self, game: &Game) -> Result<Evaluation, EngineError> {
+        if game.board().legal_moves(game.turn()).is_empty() {
+            return Ok(lost());
+        }
+        self.client.set_position(&game.to_fen(), &[])?;
+        self.client.go_time(self.movetime_ms)?;
+        let best_move = match self.client.wait()? {
+            MoveResult::Move(mv, _) => Some(mv),
+            MoveResult::NoMove | MoveResult::Draw | MoveResult::Resign => None,
+        };
+        let score = self
+            .client
+            .read_info()
+            .into_iter()
+            .rev()
+            .find_map(|info| info.score)
+            .unwrap_or(0);
+        Ok(Evaluation { score, best_move })
+    }
+}
+
+/// How a game is annotated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnnotateOptions {
+    /// Centipawns a move must lose against the best one to be a blunder
+    pub threshold: i32,
+    /// Notation the preferred moves are written in
+    pub notation: MoveStyle,
+}
+
+impl Default for AnnotateOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 200,
+            notation: MoveStyle::Chinese,
+        }
+    }
+}
+
+/// What the evaluator made of one move
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveAnnotation {
+    /// The move played, in ICCS
+    pub played: String,
+    /// The evaluator's choice in the position before the move
+    pub best_move: Option<String>,
+    /// Evaluation after the move, from Red's point of view
+    pub eval: i32,
+    /// Centipawns the move lost against the best one
+    pub loss: i32,
+    pub blunder: bool,
+}
+
+/// An annotated game
+#[derive(Debug, Clone)]
+pub struct Annotated {
+    pub pgn: PgnGame,
+    /// One entry per move of the main line
+    pub moves: Vec<MoveAnnotation>,
+}
+
+impl Annotated {
+    pub fn blunders(&self) -> usize {
+        self.moves.iter().filter(|mv| mv.blunder).count()
+    }
+}
+
+/// Error annotating a game
+#[derive(Debug)]
+pub enum AnnotateError {
+    /// The game's start position is invalid
+    Fen(FenError),
+    /// A move of the game can't be played
+    IllegalMove(String),
+    Engine(EngineError),
+}
+
+impl Display for AnnotateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AnnotateError::Fen(e) => write!(f, "{}", e),
+            AnnotateError::IllegalMove(issue) => write!(f, "{}", issue),
+            AnnotateError::Engine(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for AnnotateError {}
+
+impl From<FenError> for AnnotateError {
+    fn from(e: FenError) -> Self {
+        AnnotateError::Fen(e)
+    }
+}
+
+impl From<EngineError> for AnnotateError {
+    fn from(e: EngineError) -> Self {
+        AnnotateError::Engine(e)
+    }
+}
+
+/// Evaluate every position of a game and comment its moves
+///
+/// `progress` is called with the number of positions evaluated and their
+/// total before each evaluation and once at the end. Comments already in
+/// the game are kept, apart from `[%eval]` tags, which are replaced.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::annotate::{annotate, AnnotateOptions, NativeEvaluator};
+/// use cn_chess_tui::pgn::PgnGame;
+///
+/// let pgn = PgnGame::parse("1. h7e7 h0g2").unwrap();
+/// let annotated = annotate(
+///     &pgn,
+///     &mut NativeEvaluator::new(2),
+///     &AnnotateOptions::default(),
+///     &mut |_, _| {},
+/// )
+/// .unwrap();
+/// assert!(annotated.pgn.moves[0].comment.as_deref().unwrap().contains("[%eval"));
+/// assert_eq!(annotated.pgn.get_tag("Annotator").unwrap(), "cn_chess_tui depth 2");
+/// ```
+pub fn annotate(
+    pgn: &PgnGame,
+    evaluator: &mut dyn Evaluator,
+    options: &AnnotateOptions,
+    progress: &mut dyn FnMut(usize, usize),
+) -> Result<Annotated, AnnotateError> {
+    let report = import_pgn(pgn, ImportMode::Strict)?;
+    if let Some(issue) = report.issues.first() {
+        return Err(AnnotateError::IllegalMove(issue.to_string()));
+    }
+    let game = report.game;
+    let moves = game.get_moves().to_vec();
+
+    let total = moves.len() + 1;
+    let mut evaluations = Vec::with_capacity(total);
+    for ply in 0..total {
+        progress(ply, total);
+        evaluations.push(evaluator.evaluate(&game.at_ply(ply))?);
+    }
+    progress(total, total);
+
+    let formatter = MoveFormatter::new(options.notation);
+    let mut annotated = pgn.clone();
+    let mut annotations = Vec::with_capacity(moves.len());
+    for (ply, mv) in moves.iter().enumerate() {
+        let position = game.at_ply(ply);
+        let (before, after) = (&evaluations[ply], &evaluations[ply + 1]);
+        let played = move_to_iccs(mv.from, mv.to);
+        // Both from the mover's point of view
+        let loss = (before.score + after.score).max(0);
+        let blunder = loss >= options.threshold;
+        let eval = match position.turn() {
+            Color::Red => -after.score,
+            Color::Black => after.score,
+        };
+
+        let mut notes = Vec::new();
+        if blunder {
+            notes.push("Blunder.".to_string());
+        }
+        if let Some(best) = before.best_move.as_deref().filter(|best| *best != played) {
+            notes.push(format!("Best {}", formatter.format_iccs(&position, best)));
+        }
+        notes.push(format!("[%eval {}]", format_eval(eval)));
+        if let Some(pgn_move) = annotated.moves.get_mut(ply) {
+            let old = pgn_move.comment.as_deref().map(without_eval);
+            pgn_move.comment = Some(
+                old.into_iter()
+                    .filter(|old| !old.is_empty())
+                    .chain(notes)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+        }
+
+        annotations.push(MoveAnnotation {
+            played,
+            best_move: before.best_move.clone(),
+            eval,
+            loss,
+            blunder,
+        });
+    }
+    annotated.set_tag("Annotator", evaluator.name());
+
+    Ok(Annotated {
+        pgn: annotated,
+        moves: annotations,
+    })
+}
+
+/// A comment with its `[%eval]` tags removed
+fn without_eval(comment: &str) -> String {
+    let mut text = String::new();
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%eval") {
+        text.push_str(&rest[..start]);
+        rest = match rest[start..].find(']') {
+            Some(end) => &rest[start + end + 1..],
+            None => "",
+        };
+    }
+    text.push_str(rest);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::parse_eval;
+
+    #[test]
+    fn test_blunders_are_marked() {
+        // The chariot steps into the horse's reach
+        let pgn = PgnGame::parse(
+            "[FEN \"4k4/9/9/9/9/4r4/3N5/9/9/3K5 b - - 0 1\"]\n\n\
+             1... e5e4 {Careless [%eval 9.9]} 2. d6e4",
+        )
+        .unwrap();
+        let annotated = annotate(
+            &pgn,
+            &mut NativeEvaluator::new(2),
+            &AnnotateOptions::default(),
+            &mut |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(annotated.moves.len(), 2);
+        assert!(annotated.moves[0].blunder);
+        assert!(!annotated.moves[1].blunder);
+        assert_eq!(annotated.blunders(), 1);
+
+        let comment = annotated.pgn.moves[0].comment.as_deref().unwrap();
+        assert!(
+            comment.starts_with("Careless Blunder. Best "),
+            "{}",
+            comment
+        );
+        assert_eq!(comment.matches("[%eval").count(), 1);
+        // Red is winning after it
+        assert!(parse_eval(comment).unwrap() > 300);
+        assert_eq!(annotated.moves[0].eval, parse_eval(comment).unwrap());
+        // The capture was the best move, so only the evaluation is noted
+        let reply = annotated.pgn.moves[1].comment.as_deref().unwrap();
+        assert!(reply.starts_with("[%eval"), "{}", reply);
+    }
+
+    #[test]
+    fn test_progress_counts_positions() {
+        let pgn = PgnGame::parse("1. h7e7 h0g2").unwrap();
+        let mut calls = Vec::new();
+        annotate(
+            &pgn,
+            &mut NativeEvaluator::new(1),
+            &AnnotateOptions::default(),
+            &mut |done, total| calls.push((done, total)),
+        )
+        .unwrap();
+        assert_eq!(calls, [(0, 3), (1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn test_without_eval_keeps_other_tags() {
+        assert_eq!(
+            without_eval("Good [%eval 0.3] move [%clk 0:10:00]"),
+            "Good move [%clk 0:10:00]"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Game archives: multi-game PGN files read one game at a time
+//!
+//! Archives can run to hundreds of megabytes, so [`ArchiveReader`] never
+//! loads a whole file. Each call to [`ArchiveReader::iter`] streams the file
+//! from the start and parses a game only when the iterator reaches it, so
+//! memory use is bounded by the largest single game.
+
+use crate::pgn::{PgnGame, PgnReader};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+/// A PGN archive on disk
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::archive::ArchiveReader;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("games.pgn");
+/// std::fs::write(&path, "[Event \"One\"]\n\nh2e2 1-0\n\n[Event \"Two\"]\n\nb2e2 0-1\n").unwrap();
+///
+/// let archive = ArchiveReader::open(&path).unwrap();
+/// let events: Vec<String> = archive
+///     .iter()
+///     .unwrap()
+///     .map(|game| game.unwrap().get_tag("Event").unwrap().clone())
+///     .collect();
+/// assert_eq!(events, ["One", "Two"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveReader {
+    path: PathBuf,
+}
+
+/// Lazy iterator over the games of an [`ArchiveReader`]
+pub type ArchiveIter = PgnReader<BufReader<File>>;
+
+impl ArchiveReader {
+    /// Open an archive, checking that the file can be read
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        File::open(&path)?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Iterate over the games from the start of the file
+    ///
+    /// A game is only parsed when the iterator reaches it. Read errors,
+    /// such as invalid UTF-8, are reported for the game they occur in;
+    /// iteration can continue with the next game.
+    pub fn iter(&self) -> io::Result<ArchiveIter> {
+        Ok(PgnReader::new(BufReader::new(File::open(&self.path)?)))
+    }
+
+    /// Number of games in the archive, without keeping any of them
+    ///
+    /// Games that cannot be read are skipped and counted apart; only
+    /// failing to open the file is an error.
+    pub fn count(&self) -> io::Result<ArchiveCount> {
+        let mut count = ArchiveCount::default();
+        for game in self.iter()? {
+            match game {
+                Ok(_) => count.games += 1,
+                Err(_) => count.unreadable += 1,
+            }
+        }
+        Ok(count)
+    }
+
+    /// Read every game into memory
+    ///
+    /// Games that cannot be read are skipped, and their errors returned
+    /// alongside the others. Convenient for small collections; prefer
+    /// [`iter`](Self::iter) for archives of unknown size.
+    pub fn read_all(&self) -> io::Result<ArchiveGames> {
+        let mut all = ArchiveGames::default();
+        for (index, game) in self.iter()?.enumerate() {
+            match game {
+                Ok(game) => all.games.push(game),
+                Err(e) => all.errors.push((index, e)),
+            }
+        }
+        Ok(all)
+    }
+}
+
+/// How many games of an archive could and could not be read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArchiveCount {
+    pub games: usize,
+    pub unreadable: usize,
+}
+
+/// The games of an archive, and why the others could not be read
+#[derive(Debug, Default)]
+pub struct ArchiveGames {
+    pub games: Vec<PgnGame>,
+    /// Zero-based position of each unreadable game in the archive, with
+    /// its error
+    pub errors: Vec<(usize, io::Error)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn write_archive(dir: &TempDir, games: usize) -> PathBuf {
+        let path = dir.path().join("archive.pgn");
+        let mut file = File::create(&path).unwrap();
+        for i in 0..games {
+            writeln!(
+                file,
+                "[Event \"Game {}\"]\n[Red \"R{}\"]\n\nh2e2 h9g7\n1-0\n",
+                i, i
+            )
+            .unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_iter_is_lazy_and_repeatable() {
+        let dir = TempDir::new().unwrap();
+        let path = write_archive(&dir, 500);
+        let archive = ArchiveReader::open(&path).unwrap();
+
+        let third = archive.iter().unwrap().nth(2).unwrap().unwrap();
+        assert_eq!(third.get_tag("Red").unwrap(), "R2");
+        assert_eq!(third.moves.len(), 2);
+
+        // A fresh iterator starts over
+        let first = archive.iter().unwrap().next().unwrap().unwrap();
+        assert_eq!(first.get_tag("Event").unwrap(), "Game 0");
+        assert_eq!(
+            archive.count().unwrap(),
+            ArchiveCount {
+                games: 500,
+                unreadable: 0
+            }
+        );
+        assert_eq!(archive.read_all().unwrap().games.len(), 500);
+    }
+
+    #[test]
+    fn test_read_error_skips_one_game() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("archive.pgn");
+        let mut bytes = b"[Event \"A\"]\n\nh2e2 1-0\n\n[Event \"B\"]\n\n".to_vec();
+        bytes.extend_from_slice(&[0xff, 0xfe, b'\n']);
+        bytes.extend_from_slice(b"\n[Event \"C\"]\n\nb2e2 0-1\n");
+        std::fs::write(&path, bytes).unwrap();
+
+        let archive = ArchiveReader::open(&path).unwrap();
+        let results: Vec<_> = archive.iter().unwrap().collect();
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        let last = results.last().unwrap().as_ref().unwrap();
+        assert_eq!(last.get_tag("Event").unwrap(), "C");
+        assert_eq!(archive.count().unwrap().unreadable, 1);
+    }
+
+    #[test]
+    fn test_corrupt_game_in_the_middle_is_skipped() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("archive.pgn");
+        std::fs::write(
+            &path,
+            "[Event \"A\"]\n\nh2e2 1-0\n\n\
+             [Event \"B\n\nb2e2 0-1\n\n\
+             [Event \"C\"]\n\nh7e7 1/2-1/2\n",
+        )
+        .unwrap();
+
+        let archive = ArchiveReader::open(&path).unwrap();
+        assert_eq!(
+            archive.count().unwrap(),
+            ArchiveCount {
+                games: 2,
+                unreadable: 1
+            }
+        );
+
+        let all = archive.read_all().unwrap();
+        let events: Vec<&String> = all
+            .games
+            .iter()
+            .map(|game| game.get_tag("Event").unwrap())
+            .collect();
+        assert_eq!(events, ["A", "C"]);
+        assert_eq!(all.errors.len(), 1);
+        assert_eq!(all.errors[0].0, 1);
+        assert_eq!(all.errors[0].1.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_open_missing_file() {
+        assert!(ArchiveReader::open("/nonexistent/archive.pgn").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! Tune engine options by playing matches over a grid of option values
+//!
+//! Every combination of the `--option` values is played against a baseline
+//! (the same engine with its default options, another engine, or the
+//! built-in search) and scored, with an optional SPRT per configuration.
+
+use cn_chess_tui::tournament::{
+    play_match, EnginePlayer, MatchOptions, MatchScore, NativePlayer, Player, SearchLimit, Sprt,
+    SprtVerdict,
+};
+use cn_chess_tui::Game;
+use std::process;
+
+const USAGE: &str = "\
+Usage: engine_sweep --engine <path> [options]
+
+  --engine <path>           UCCI engine to tune
+  --option <name>=<v1,v2>   option values to try (repeatable; the grid is
+                            every combination)
+  --baseline <path|native>  opponent: another engine, or `native[:depth]`
+                            for the built-in search (default: the tuned
+                            engine with its default options)
+  --games <n>               games per configuration (default 20)
+  --depth <n>               engine search depth per move (default 8)
+  --movetime <ms>           search time per move instead of a depth
+  --nodes <n>               positions to search per move instead
+  --max-plies <n>           adjudicate a draw after n plies (default 300)
+  --play-endings            play elementary endings out instead of
+                            scoring them by their known result
+  --sprt <elo0>,<elo1>      stop each match once the SPRT decides
+  --fen <fen>               start position (default: the initial position)";
+
+enum Baseline {
+    SameEngine,
+    Engine(String),
+    Native(u32),
+}
+
+struct Sweep {
+    engine: String,
+    options: Vec<(String, Vec<String>)>,
+    baseline: Baseline,
+    limit: SearchLimit,
+    match_options: MatchOptions,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let sweep = parse_args(&args).unwrap_or_else(|e| {
+        eprintln!("Error: {}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+    if let Err(e) = run(&sweep) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Sweep, String> {
+    let mut engine = None;
+    let mut options = Vec::new();
+    let mut baseline = Baseline::SameEngine;
+    let mut limit = SearchLimit::Depth(8);
+    let mut match_options = MatchOptions {
+        games: 20,
+        max_plies: 300,
+        ..MatchOptions::default()
+    };
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--engine" => engine = Some(value()?),
+            "--option" => {
+                let spec = value()?;
+                let (name, values) = spec
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected <name>=<values>, got '{}'", spec))?;
+                let values: Vec<String> = values.split(',').map(|v| v.trim().to_string()).collect();
+                options.push((name.trim().to_string(), values));
+            }
+            "--baseline" => {
+                let spec = value()?;
+                baseline = match spec.strip_prefix("native") {
+                    Some("") => Baseline::Native(cn_chess_tui::ai::DEFAULT_SEARCH_DEPTH),
+                    Some(depth) => Baseline::Native(parse_number(
+                        depth.trim_start_matches(':'),
+                        "--baseline native depth",
+                    )?),
+                    None => Baseline::Engine(spec),
+                };
+            }
+            "--games" => match_options.games = parse_number(&value()?, "--games")?,
+            "--depth" => limit = SearchLimit::Depth(parse_number(&value()?, "--depth")?),
+            "--movetime" => limit = SearchLimit::Time(parse_number(&value()?, "--movetime")?),
+            "--nodes" => limit = SearchLimit::Nodes(parse_number(&value()?, "--nodes")?),
+            "--max-plies" => match_options.max_plies = parse_number(&value()?, "--max-plies")?,
+            "--play-endings" => match_options.endgames = false,
+            "--sprt" => {
+                let spec = value()?;
+                let (elo0, elo1) = spec
+                    .split_once(',')
+                    .ok_or_else(|| format!("expected <elo0>,<elo1>, got '{}'", spec))?;
+                match_options.sprt = Some(Sprt::new(
+                    parse_number(elo0, "--sprt")?,
+                    parse_number(elo1, "--sprt")?,
+                ));
+            }
+            "--fen" => {
+                let fen = value()?;
+                match_options.start =
+                    Game::from_fen(&fen).map_err(|e| format!("invalid FEN: {}", e))?;
+            }
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    Ok(Sweep {
+        engine: engine.ok_or("--engine is required")?,
+        options,
+        baseline,
+        limit,
+        match_options,
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, flag: &str) -> Result<T, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("{}: '{}' is not a valid number", flag, text))
+}
+
+/// Every combination of option values, in the order given
+fn grid(options: &[(String, Vec<String>)]) -> Vec<Vec<(String, String)>> {
+    options
+        .iter()
+        .fold(vec![Vec::new()], |configs, (name, values)| {
+            configs
+                .iter()
+                .flat_map(|config| {
+                    values.iter().map(move |value| {
+                        let mut config = config.clone();
+                        config.push((name.clone(), value.clone()));
+                        config
+                    })
+                })
+                .collect()
+        })
+}
+
+fn run(sweep: &Sweep) -> Result<(), Box<dyn std::error::Error>> {
+    let mut baseline: Box<dyn Player> = match &sweep.baseline {
+        Baseline::SameEngine => Box::new(EnginePlayer::start(&sweep.engine, &[], sweep.limit)?),
+        Baseline::Engine(path) => Box::new(EnginePlayer::start(path, &[], sweep.limit)?),
+        Baseline::Native(depth) => Box::new(NativePlayer::new(*depth)),
+    };
+
+    let configs = grid(&sweep.options);
+    println!(
+        "{} configuration(s) vs {}, up to {} games each at {}",
+        configs.len(),
+        baseline.name(),
+        sweep.match_options.games,
+        sweep.limit
+    );
+
+    let mut results: Vec<(String, MatchScore, Option<SprtVerdict>)> = Vec::new();
+    for config in &configs {
+        let mut player = EnginePlayer::start(&sweep.engine, config, sweep.limit)?;
+        let name = player.name();
+        println!("\n== {} ==", name);
+        let score = play_match(
+            &mut player,
+            baseline.as_mut(),
+            &sweep.match_options,
+            |record, score| {
+                println!(
+                    "  game {:>3}: {} ({:?}, {} plies)  {}",
+                    score.games(),
+                    record.result,
+                    record.ending,
+                    record.game.get_moves().len(),
+                    score
+                );
+            },
+        )?;
+        player.shutdown()?;
+
+        let verdict = sweep.match_options.sprt.map(|sprt| sprt.verdict(&score));
+        print_score(&score, sweep.match_options.sprt.as_ref());
+        results.push((name, score, verdict));
+    }
+
+    results.sort_by(|a, b| b.1.fraction().total_cmp(&a.1.fraction()));
+    println!(
+        "\n{:<40} {:>6} {:>6} {:>6} {:>7} {:>8}  SPRT",
+        "Configuration", "W", "D", "L", "Score", "Elo"
+    );
+    for (name, score, verdict) in &results {
+        println!(
+            "{:<40} {:>6} {:>6} {:>6} {:>6.1}% {:>8}  {}",
+            name,
+            score.wins,
+            score.draws,
+            score.losses,
+            score.fraction() * 100.0,
+            format_elo(score),
+            verdict
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+    Ok(())
+}
+
+fn print_score(score: &MatchScore, sprt: Option<&Sprt>) {
+    println!("  result: {}  Elo {}", score, format_elo(score));
+    if let Some(sprt) = sprt {
+        let (lower, upper) = sprt.bounds();
+        println!(
+            "  SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2}) {}",
+            sprt.elo0,
+            sprt.elo1,
+            score.llr(sprt.elo0, sprt.elo1),
+            lower,
+            upper,
+            sprt.verdict(score)
+        );
+    }
+}
+
+fn format_elo(score: &MatchScore) -> String {
+    match score.elo() {
+        Some(elo) => format!("{:+.0}", elo),
+        None if score.games() == 0 => "-".to_string(),
+        None if score.fraction() >= 1.0 => "+inf".to_string(),
+        None => "-inf".to_string(),
+    }
+}
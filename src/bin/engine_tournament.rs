@@ -0,0 +1,263 @@
+//! Play a round-robin or gauntlet tournament between engines
+//!
+//! Each pairing plays a match with alternating colors, optionally stopped
+//! early by an SPRT. Prints the standings and can save every game to a PGN
+//! archive.
+
+use cn_chess_tui::tournament::{
+    play_tournament, EnginePlayer, MatchOptions, MatchScore, NativePlayer, Player, SearchLimit,
+    Sprt, TournamentFormat, TournamentResult,
+};
+use cn_chess_tui::Game;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::process;
+
+const USAGE: &str = "\
+Usage: engine_tournament --player <spec> --player <spec> [options]
+
+  --player <spec>           an entrant (repeatable, at least two):
+                            `native[:depth]` for the built-in search, or an
+                            engine path with options, `<path>[,name=value...]`
+  --gauntlet                the first player meets each of the others, who
+                            don't meet (default: round robin)
+  --games <n>               games per pairing (default 2)
+  --depth <n>               engine search depth per move (default 8)
+  --movetime <ms>           search time per move instead of a depth
+  --nodes <n>               positions to search per move instead
+  --max-plies <n>           adjudicate a draw after n plies (default 300)
+  --play-endings            play elementary endings out instead of
+                            scoring them by their known result
+  --sprt <elo0>,<elo1>      stop each pairing once the SPRT decides
+  --fen <fen>               start position (default: the initial position)
+  --pgn <path>              save every game to a PGN archive";
+
+enum Entrant {
+    Native(u32),
+    Engine {
+        path: String,
+        options: Vec<(String, String)>,
+    },
+}
+
+struct Tournament {
+    entrants: Vec<Entrant>,
+    format: TournamentFormat,
+    limit: SearchLimit,
+    match_options: MatchOptions,
+    pgn: Option<String>,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let tournament = parse_args(&args).unwrap_or_else(|e| {
+        eprintln!("Error: {}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+    if let Err(e) = run(&tournament) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Tournament, String> {
+    let mut entrants = Vec::new();
+    let mut format = TournamentFormat::RoundRobin;
+    let mut limit = SearchLimit::Depth(8);
+    let mut match_options = MatchOptions {
+        games: 2,
+        max_plies: 300,
+        ..MatchOptions::default()
+    };
+    let mut pgn = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--player" => entrants.push(parse_entrant(&value()?)?),
+            "--gauntlet" => format = TournamentFormat::Gauntlet,
+            "--games" => match_options.games = parse_number(&value()?, "--games")?,
+            "--depth" => limit = SearchLimit::Depth(parse_number(&value()?, "--depth")?),
+            "--movetime" => limit = SearchLimit::Time(parse_number(&value()?, "--movetime")?),
+            "--nodes" => limit = SearchLimit::Nodes(parse_number(&value()?, "--nodes")?),
+            "--max-plies" => match_options.max_plies = parse_number(&value()?, "--max-plies")?,
+            "--play-endings" => match_options.endgames = false,
+            "--sprt" => {
+                let spec = value()?;
+                let (elo0, elo1) = spec
+                    .split_once(',')
+                    .ok_or_else(|| format!("expected <elo0>,<elo1>, got '{}'", spec))?;
+                match_options.sprt = Some(Sprt::new(
+                    parse_number(elo0, "--sprt")?,
+                    parse_number(elo1, "--sprt")?,
+                ));
+            }
+            "--fen" => {
+                let fen = value()?;
+                match_options.start =
+                    Game::from_fen(&fen).map_err(|e| format!("invalid FEN: {}", e))?;
+            }
+            "--pgn" => pgn = Some(value()?),
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+    if entrants.len() < 2 {
+        return Err("at least two --player entrants are needed".to_string());
+    }
+
+    Ok(Tournament {
+        entrants,
+        format,
+        limit,
+        match_options,
+        pgn,
+    })
+}
+
+fn parse_entrant(spec: &str) -> Result<Entrant, String> {
+    if let Some(depth) = spec.strip_prefix("native") {
+        return Ok(match depth {
+            "" => Entrant::Native(cn_chess_tui::ai::DEFAULT_SEARCH_DEPTH),
+            depth => Entrant::Native(parse_number(
+                depth.trim_start_matches(':'),
+                "--player native depth",
+            )?),
+        });
+    }
+    let mut parts = spec.split(',');
+    let path = parts.next().unwrap_or_default().trim().to_string();
+    let options = parts
+        .map(|option| {
+            option
+                .split_once('=')
+                .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                .ok_or_else(|| format!("expected <name>=<value>, got '{}'", option))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Entrant::Engine { path, options })
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, flag: &str) -> Result<T, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("{}: '{}' is not a valid number", flag, text))
+}
+
+fn run(tournament: &Tournament) -> Result<(), Box<dyn std::error::Error>> {
+    let mut players: Vec<Box<dyn Player>> = Vec::new();
+    for entrant in &tournament.entrants {
+        players.push(match entrant {
+            Entrant::Native(depth) => Box::new(NativePlayer::new(*depth)),
+            Entrant::Engine { path, options } => {
+                Box::new(EnginePlayer::start(path, options, tournament.limit)?)
+            }
+        });
+    }
+
+    let pairings = tournament.format.pairings(players.len()).len();
+    println!(
+        "{} players, {} pairing(s), up to {} games each at {}",
+        players.len(),
+        pairings,
+        tournament.match_options.games,
+        tournament.limit
+    );
+    let names: Vec<String> = players.iter().map(|player| player.name()).collect();
+    let result = play_tournament(
+        &mut players,
+        tournament.format,
+        &tournament.match_options,
+        |game, score| {
+            println!(
+                "  {}.{:<3} {} - {}: {} ({:?}, {} plies)  {}",
+                game.pairing + 1,
+                game.number,
+                names[game.red],
+                names[game.black],
+                game.record.result,
+                game.record.ending,
+                game.record.game.get_moves().len(),
+                score
+            );
+        },
+    )?;
+
+    print_pairings(&result, tournament.match_options.sprt.as_ref());
+    print_standings(&result);
+
+    if let Some(path) = &tournament.pgn {
+        let mut out = BufWriter::new(File::create(path)?);
+        for (i, game) in result.to_pgn("Engine tournament").iter().enumerate() {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            game.write_pgn(&mut out)?;
+        }
+        out.flush()?;
+        println!("\n{} games saved to {}", result.games.len(), path);
+    }
+    Ok(())
+}
+
+fn print_pairings(result: &TournamentResult, sprt: Option<&Sprt>) {
+    println!();
+    for pairing in &result.pairings {
+        let mut line = format!(
+            "{} vs {}: {}  Elo {}",
+            result.names[pairing.first],
+            result.names[pairing.second],
+            pairing.score,
+            format_elo(&pairing.score)
+        );
+        if let (Some(sprt), Some(verdict)) = (sprt, pairing.verdict) {
+            line.push_str(&format!(
+                "  SPRT [{}, {}]: LLR {:.2} {}",
+                sprt.elo0,
+                sprt.elo1,
+                pairing.score.llr(sprt.elo0, sprt.elo1),
+                verdict
+            ));
+        }
+        println!("{}", line);
+    }
+}
+
+fn print_standings(result: &TournamentResult) {
+    println!(
+        "\n{:>4}  {:<40} {:>5} {:>5} {:>5} {:>5} {:>7} {:>7} {:>6}",
+        "Rank", "Player", "Games", "W", "D", "L", "Points", "Score", "Elo"
+    );
+    for (rank, standing) in result.standings().iter().enumerate() {
+        let score = &standing.score;
+        println!(
+            "{:>4}  {:<40} {:>5} {:>5} {:>5} {:>5} {:>7.1} {:>6.1}% {:>6}",
+            rank + 1,
+            standing.name,
+            score.games(),
+            score.wins,
+            score.draws,
+            score.losses,
+            score.points(),
+            score.fraction() * 100.0,
+            format_elo(score)
+        );
+    }
+}
+
+fn format_elo(score: &MatchScore) -> String {
+    match score.elo() {
+        Some(elo) => format!("{:+.0}", elo),
+        None if score.games() == 0 => "-".to_string(),
+        None if score.fraction() >= 1.0 => "+inf".to_string(),
+        None => "-inf".to_string(),
+    }
+}
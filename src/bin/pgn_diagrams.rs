@@ -0,0 +1,150 @@
+//! Write a diagram for every key moment of an annotated PGN game
+//!
+//! A key moment is a position after a commented move, or after a move whose
+//! `[%eval]` swings by at least the threshold. Diagrams are named by move
+//! number, e.g. `012r.svg` for Red's 12th move, ready to drop into a write-up.
+
+use cn_chess_tui::archive::ArchiveReader;
+use cn_chess_tui::diagram::{format_eval, key_moments, DiagramFormat, KeyMoment};
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+Usage: pgn_diagrams <file.pgn> [options]
+
+  --out <dir>               directory for the diagrams (default: current)
+  --format <svg|text|both>  diagram format (default svg)
+  --threshold <pawns>       evaluation swing that makes a key moment
+                            (default 1.0)
+  --game <n>                only the nth game of a multi-game file";
+
+struct Options {
+    input: PathBuf,
+    out: PathBuf,
+    formats: Vec<DiagramFormat>,
+    threshold: i32,
+    game: Option<usize>,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = parse_args(&args).unwrap_or_else(|e| {
+        eprintln!("Error: {}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+    if let Err(e) = run(&options) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut input = None;
+    let mut out = PathBuf::from(".");
+    let mut formats = vec![DiagramFormat::Svg];
+    let mut threshold = 100;
+    let mut game = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--out" => out = PathBuf::from(value()?),
+            "--format" => {
+                formats = match value()?.as_str() {
+                    "both" => vec![DiagramFormat::Svg, DiagramFormat::Text],
+                    format => vec![format.parse()?],
+                }
+            }
+            "--threshold" => {
+                let text = value()?;
+                let pawns: f64 = text
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("--threshold: '{}' is not a valid number", text))?;
+                threshold = (pawns * 100.0).round() as i32;
+            }
+            "--game" => {
+                let text = value()?;
+                let n: usize = text
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("--game: '{}' is not a game number", text))?;
+                game = Some(n);
+            }
+            other if other.starts_with("--") => {
+                return Err(format!("unknown argument '{}'", other))
+            }
+            other => input = Some(PathBuf::from(other)),
+        }
+    }
+
+    Ok(Options {
+        input: input.ok_or("a PGN file is required")?,
+        out,
+        formats,
+        threshold,
+        game,
+    })
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let archive = ArchiveReader::open(&options.input)?;
+    // Several games in one file get a game number in front of each name
+    let prefixed = options.game.is_none() && archive.iter()?.nth(1).is_some();
+    std::fs::create_dir_all(&options.out)?;
+
+    let mut written = 0;
+    for (index, pgn) in archive.iter()?.enumerate() {
+        let number = index + 1;
+        if options.game.is_some_and(|n| n != number) {
+            continue;
+        }
+        let pgn = pgn?;
+        let moments =
+            key_moments(&pgn, options.threshold).map_err(|e| format!("game {}: {}", number, e))?;
+
+        for moment in &moments {
+            let stem = if prefixed {
+                format!("g{:02}-{}", number, moment.file_stem())
+            } else {
+                moment.file_stem()
+            };
+            for &format in &options.formats {
+                let path = options.out.join(format!("{}.{}", stem, format.extension()));
+                std::fs::write(&path, moment.render(format))?;
+                written += 1;
+            }
+            println!("{}", describe(&stem, moment));
+        }
+    }
+
+    println!(
+        "{} diagram(s) written to {}",
+        written,
+        options.out.display()
+    );
+    Ok(())
+}
+
+/// One line per moment: name, move, swing and comment
+fn describe(stem: &str, moment: &KeyMoment) -> String {
+    let mut line = format!("{:<12} {}", stem, moment.caption());
+    if let Some(swing) = moment.swing {
+        line.push_str(&format!("  swing {}", format_eval(swing)));
+    }
+    if let Some(comment) = &moment.comment {
+        line.push_str(&format!("  {}", comment));
+    }
+    line
+}
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
-use cn_chess_tui::ucci::UcciClient;
+use cn_chess_tui::ucci::{bench, UcciClient};
 
 #[derive(Parser)]
 #[command(name = "ucci_client")]
@@ -46,6 +46,17 @@ enum Commands {
         moves: u32,
     },
 
+    /// Search a fixed suite of test positions and report speed and solutions
+    Bench {
+        /// Search depth
+        #[arg(short, long, default_value_t = 8)]
+        depth: u32,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Interactive mode
     Interactive,
 }
@@ -79,6 +90,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Commands::Play { time, moves } => {
             play_game(&mut client, time, moves)?;
         }
+        Commands::Bench { depth, json } => {
+            let report = bench::run_bench(&mut client, bench::SUITE, depth)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report.to_text());
+            }
+        }
         Commands::Interactive => {
             interactive_mode(&mut client)?;
         }
@@ -1,12 +1,144 @@
+//! The board and the rules of movement
+//!
+//! Pieces are kept in a mailbox: one slot per square, indexed by
+//! `y * 9 + x`. Moves are generated per piece (sliding chariots and
+//! cannons, hobbled horses, blocked elephants, palace and river limits),
+//! and whether a move leaves its own general attacked is checked by looking
+//! outward from the general on a view of the board after the move, without
+//! copying the board.
+
 use crate::types::{Color, Piece, PieceType, Position};
+use crate::zobrist::piece_key;
 use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
 
 const BOARD_WIDTH: usize = 9;
 const BOARD_HEIGHT: usize = 10;
+const SQUARES: usize = BOARD_WIDTH * BOARD_HEIGHT;
+
+/// Steps along files and ranks
+const ORTHOGONAL: [(isize, isize); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+/// Steps along diagonals
+const DIAGONAL: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+/// A horse's jumps: two squares one way and one square across
+const HORSE_JUMPS: [(isize, isize); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+/// Why [`Board::is_legal_move`] rejects a move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalMoveReason {
+    /// There is no piece on the starting square
+    NoPiece,
+    /// The destination holds a piece of the same side
+    OwnPiece,
+    /// The destination is not one the piece's kind of move can reach
+    WrongPattern(PieceType),
+    /// Generals and advisors must stay in their palace
+    LeavesPalace,
+    /// The point next to a horse in the direction of its jump is occupied
+    HorseLegBlocked,
+    /// The point an elephant steps over is occupied
+    ElephantEyeBlocked,
+    /// Elephants cannot cross the river
+    ElephantCrossesRiver,
+    /// Soldiers never move back
+    SoldierBackward,
+    /// Soldiers only move sideways once across the river
+    SoldierSidewaysBeforeRiver,
+    /// A chariot, or a cannon not capturing, has pieces in its way
+    PathBlocked,
+    /// A cannon captures by jumping exactly one piece
+    CannonNeedsScreen,
+    /// The move would leave the two generals facing on an open file
+    FlyingGeneral,
+    /// The move would leave the mover's general in check
+    LeavesGeneralInCheck,
+}
+
+impl Display for IllegalMoveReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            IllegalMoveReason::NoPiece => write!(f, "There is no piece to move"),
+            IllegalMoveReason::OwnPiece => write!(f, "You cannot capture your own piece"),
+            IllegalMoveReason::WrongPattern(piece_type) => {
+                let rule = match piece_type {
+                    PieceType::General => "The general moves one point along a file or rank",
+                    PieceType::Advisor => "An advisor moves one point diagonally",
+                    PieceType::Elephant => "An elephant moves two points diagonally",
+                    PieceType::Horse => "A horse moves one point straight, then one diagonally",
+                    PieceType::Chariot => "A chariot moves along a file or rank",
+                    PieceType::Cannon => "A cannon moves along a file or rank",
+                    PieceType::Soldier => "A soldier moves one point at a time",
+                };
+                write!(f, "{}", rule)
+            }
+            IllegalMoveReason::LeavesPalace => write!(f, "The piece cannot leave the palace"),
+            IllegalMoveReason::HorseLegBlocked => write!(f, "The horse's leg is blocked (蹩马腿)"),
+            IllegalMoveReason::ElephantEyeBlocked => {
+                write!(f, "The elephant's eye is blocked (塞象眼)")
+            }
+            IllegalMoveReason::ElephantCrossesRiver => {
+                write!(f, "Elephants cannot cross the river")
+            }
+            IllegalMoveReason::SoldierBackward => write!(f, "Soldiers cannot move backward"),
+            IllegalMoveReason::SoldierSidewaysBeforeRiver => {
+                write!(f, "Soldiers move sideways only after crossing the river")
+            }
+            IllegalMoveReason::PathBlocked => write!(f, "The path is blocked"),
+            IllegalMoveReason::CannonNeedsScreen => {
+                write!(f, "A cannon captures by jumping exactly one piece")
+            }
+            IllegalMoveReason::FlyingGeneral => {
+                write!(f, "The generals cannot face each other (对脸将)")
+            }
+            IllegalMoveReason::LeavesGeneralInCheck => {
+                write!(f, "The move would leave your general in check")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IllegalMoveReason {}
+
+/// How many pieces of one side attack each point, from
+/// [`Board::attacked_squares`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttackMap {
+    counts: [u8; SQUARES],
+}
+
+impl AttackMap {
+    /// Number of pieces attacking `pos`
+    pub fn count(&self, pos: Position) -> u8 {
+        index(pos).map_or(0, |i| self.counts[i])
+    }
+
+    pub fn is_attacked(&self, pos: Position) -> bool {
+        self.count(pos) > 0
+    }
+
+    /// The attacked points, rank by rank from Black's side
+    pub fn squares(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..SQUARES)
+            .filter(|&i| self.counts[i] > 0)
+            .map(|i| Position::from_xy(i % BOARD_WIDTH, i / BOARD_WIDTH))
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Board {
-    pieces: HashMap<Position, Piece>,
+    squares: [Option<Piece>; SQUARES],
+    /// Zobrist hash of the pieces, kept up to date as they are placed,
+    /// moved and removed
+    hash: u64,
 }
 
 impl Default for Board {
@@ -15,10 +147,47 @@ impl Default for Board {
     }
 }
 
+/// Slot of a square in the mailbox, None off the board
+fn index(pos: Position) -> Option<usize> {
+    pos.is_valid().then(|| pos.y * BOARD_WIDTH + pos.x)
+}
+
+/// The square `(dx, dy)` away from `pos`, if it is on the board
+fn offset(pos: Position, dx: isize, dy: isize) -> Option<Position> {
+    let x = pos.x.checked_add_signed(dx)?;
+    let y = pos.y.checked_add_signed(dy)?;
+    Position::new(x, y)
+}
+
+/// Whether a square is on `color`'s side of the river
+fn own_side(pos: Position, color: Color) -> bool {
+    match color {
+        Color::Red => pos.y >= 5,
+        Color::Black => pos.y <= 4,
+    }
+}
+
+/// The rank direction a side's soldiers advance in
+fn forward(color: Color) -> isize {
+    match color {
+        Color::Red => -1,
+        Color::Black => 1,
+    }
+}
+
 impl Board {
     /// Create a board from a pieces HashMap (for FEN loading)
+    ///
+    /// Pieces on squares off the board are left out.
     pub fn from_pieces(pieces: HashMap<Position, Piece>) -> Self {
-        Self { pieces }
+        let mut board = Self {
+            squares: [None; SQUARES],
+            hash: 0,
+        };
+        for (pos, piece) in pieces {
+            board.place_piece(pos, piece);
+        }
+        board
     }
 
     pub fn new() -> Self {
@@ -66,16 +235,11 @@ impl Board {
             pieces.insert(Position::from_xy(x, 3), Piece::black(PieceType::Soldier));
         }
 
-        Self { pieces }
+        Self::from_pieces(pieces)
     }
 
     pub fn get(&self, pos: Position) -> Option<&Piece> {
-        self.pieces.get(&pos)
-    }
-
-    #[allow(dead_code)]
-    pub fn get_mut(&mut self, pos: Position) -> Option<&mut Piece> {
-        self.pieces.get_mut(&pos)
+        self.squares[index(pos)?].as_ref()
     }
 
     #[allow(dead_code)]
@@ -84,43 +248,78 @@ impl Board {
     }
 
     pub fn is_empty(&self, pos: Position) -> bool {
-        !self.pieces.contains_key(&pos)
+        self.get(pos).is_none()
     }
 
     pub fn is_empty_xy(&self, x: usize, y: usize) -> bool {
         self.is_empty(Position::from_xy(x, y))
     }
 
+    /// Put a piece on a square, replacing any piece there
+    ///
+    /// Squares off the board are ignored.
     pub fn place_piece(&mut self, pos: Position, piece: Piece) {
-        self.pieces.insert(pos, piece);
+        let Some(i) = index(pos) else {
+            return;
+        };
+        if let Some(replaced) = self.squares[i].replace(piece) {
+            self.hash ^= piece_key(replaced, pos);
+        }
+        self.hash ^= piece_key(piece, pos);
     }
 
     pub fn remove_piece(&mut self, pos: Position) -> Option<Piece> {
-        self.pieces.remove(&pos)
+        let piece = self.squares[index(pos)?].take()?;
+        self.hash ^= piece_key(piece, pos);
+        Some(piece)
     }
 
     pub fn move_piece(&mut self, from: Position, to: Position) -> Option<Piece> {
-        let piece = self.pieces.remove(&from)?;
-        self.pieces.insert(to, piece);
-        self.pieces.get(&to).copied()
+        let piece = self.remove_piece(from)?;
+        self.place_piece(to, piece);
+        Some(piece)
+    }
+
+    /// Zobrist hash of the pieces on the board
+    ///
+    /// Boards with the same pieces on the same squares hash equal, however
+    /// they were reached. The hash is updated as pieces move, so reading it
+    /// is free. Whose turn it is is not part of a board; see
+    /// [`crate::zobrist::hash`].
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Board, Position};
+    ///
+    /// // Two move orders reaching the same position
+    /// let mut a = Board::new();
+    /// a.move_piece(Position::from_xy(7, 7), Position::from_xy(4, 7));
+    /// a.move_piece(Position::from_xy(7, 9), Position::from_xy(6, 7));
+    /// let mut b = Board::new();
+    /// b.move_piece(Position::from_xy(7, 9), Position::from_xy(6, 7));
+    /// b.move_piece(Position::from_xy(7, 7), Position::from_xy(4, 7));
+    /// assert_eq!(a.hash(), b.hash());
+    /// assert_ne!(a.hash(), Board::new().hash());
+    /// ```
+    pub fn hash(&self) -> u64 {
+        self.hash
     }
 
+    /// Every piece with its square, rank by rank from Black's back rank
     pub fn pieces(&self) -> impl Iterator<Item = (Position, Piece)> + '_ {
-        self.pieces.iter().map(|(p, piece)| (*p, *piece))
+        self.squares.iter().enumerate().filter_map(|(i, piece)| {
+            piece.map(|piece| (Position::from_xy(i % BOARD_WIDTH, i / BOARD_WIDTH), piece))
+        })
     }
 
     pub fn pieces_of_color(&self, color: Color) -> impl Iterator<Item = (Position, Piece)> + '_ {
-        self.pieces
-            .iter()
-            .filter(move |(_, p)| p.color == color)
-            .map(|(p, piece)| (*p, *piece))
+        self.pieces().filter(move |(_, p)| p.color == color)
     }
 
     pub fn find_general(&self, color: Color) -> Option<Position> {
-        self.pieces
-            .iter()
+        self.pieces()
             .find(|(_, p)| p.color == color && p.piece_type == PieceType::General)
-            .map(|(p, _)| *p)
+            .map(|(pos, _)| pos)
     }
 
     /// Count pieces between two positions (exclusive) on the same file/rank
@@ -175,199 +374,383 @@ impl Board {
     }
 
     pub fn is_in_check(&self, color: Color) -> bool {
-        let general_pos = match self.find_general(color) {
-            Some(pos) => pos,
-            None => return false, // General captured - game over
-        };
-
-        let enemy_color = match color {
-            Color::Red => Color::Black,
-            Color::Black => Color::Red,
-        };
-
-        // Check if any enemy piece can capture the general
-        for (pos, piece) in self.pieces_of_color(enemy_color) {
-            if self.is_valid_move(pos, general_pos, piece) {
-                return true;
-            }
-        }
-
-        false
-    }
-
-    fn is_valid_move(&self, from: Position, to: Position, piece: Piece) -> bool {
-        match piece.piece_type {
-            PieceType::General => self.can_general_move(from, to, piece.color),
-            PieceType::Advisor => self.can_advisor_move(from, to, piece.color),
-            PieceType::Elephant => self.can_elephant_move(from, to, piece.color),
-            PieceType::Horse => self.can_horse_move(from, to, piece.color),
-            PieceType::Chariot => self.can_chariot_move(from, to, piece.color),
-            PieceType::Cannon => self.can_cannon_move(from, to, piece.color),
-            PieceType::Soldier => self.can_soldier_move(from, to, piece.color),
+        match self.find_general(color) {
+            Some(general) => general_attacked(|pos| self.get(pos).copied(), general, color),
+            None => false, // General captured - game over
         }
     }
 
-    fn can_general_move(&self, from: Position, to: Position, color: Color) -> bool {
-        if !to.in_palace(color) {
-            return false;
+    /// Check if a move is legal according to all rules, and if not, why
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::board::{Board, IllegalMoveReason};
+    /// use cn_chess_tui::Position;
+    ///
+    /// let board = Board::new();
+    /// assert!(board
+    ///     .is_legal_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+    ///     .is_ok());
+    /// assert_eq!(
+    ///     board.is_legal_move(Position::from_xy(1, 9), Position::from_xy(3, 8)),
+    ///     Err(IllegalMoveReason::HorseLegBlocked)
+    /// );
+    /// ```
+    pub fn is_legal_move(&self, from: Position, to: Position) -> Result<(), IllegalMoveReason> {
+        let Some(&piece) = self.get(from) else {
+            return Err(IllegalMoveReason::NoPiece);
+        };
+        let mut targets = Vec::new();
+        self.push_targets(from, piece, &mut targets);
+        if !targets.contains(&to) {
+            return Err(self.unreachable_reason(from, to, piece));
         }
-        if from.chebyshev_distance(to) != 1 {
-            return false;
+        if self.keeps_general_safe(from, to, piece) {
+            return Ok(());
         }
-        // Must move along file or rank (not diagonal)
-        from.on_same_file(to) || from.on_same_rank(to)
-    }
 
-    fn can_advisor_move(&self, from: Position, to: Position, color: Color) -> bool {
-        if !to.in_palace(color) {
-            return false;
+        let mut after = self.clone();
+        after.move_piece(from, to);
+        if after.generals_facing() {
+            Err(IllegalMoveReason::FlyingGeneral)
+        } else {
+            Err(IllegalMoveReason::LeavesGeneralInCheck)
         }
-        // Diagonal move only
-        from.file_distance(to) == 1 && from.rank_distance(to) == 1
     }
 
-    fn can_elephant_move(&self, from: Position, to: Position, color: Color) -> bool {
-        // Cannot cross river
-        let valid_y = match color {
-            Color::Red => to.y >= 5,
-            Color::Black => to.y <= 4,
+    /// Why `piece` on `from` cannot reach `to`, for a destination its moves
+    /// leave out
+    ///
+    /// Rules of where a piece may go come first, then capturing its own
+    /// side, then pieces in the way.
+    fn unreachable_reason(&self, from: Position, to: Position, piece: Piece) -> IllegalMoveReason {
+        let dx = to.x as isize - from.x as isize;
+        let dy = to.y as isize - from.y as isize;
+        let (adx, ady) = (dx.abs(), dy.abs());
+        let color = piece.color;
+
+        let shape_ok = match piece.piece_type {
+            PieceType::General | PieceType::Soldier => adx + ady == 1,
+            PieceType::Advisor => adx == 1 && ady == 1,
+            PieceType::Elephant => adx == 2 && ady == 2,
+            PieceType::Horse => (adx, ady) == (1, 2) || (adx, ady) == (2, 1),
+            PieceType::Chariot | PieceType::Cannon => (dx == 0) != (dy == 0),
         };
-        if !valid_y {
-            return false;
+        if !shape_ok {
+            return IllegalMoveReason::WrongPattern(piece.piece_type);
         }
-        // Diagonal 2 squares
-        if from.file_distance(to) != 2 || from.rank_distance(to) != 2 {
-            return false;
+        match piece.piece_type {
+            PieceType::General | PieceType::Advisor if !to.in_palace(color) => {
+                return IllegalMoveReason::LeavesPalace
+            }
+            PieceType::Elephant if !own_side(to, color) => {
+                return IllegalMoveReason::ElephantCrossesRiver
+            }
+            PieceType::Soldier if dy == -forward(color) => {
+                return IllegalMoveReason::SoldierBackward
+            }
+            PieceType::Soldier if dy == 0 && own_side(from, color) => {
+                return IllegalMoveReason::SoldierSidewaysBeforeRiver
+            }
+            _ => {}
         }
-        // Check blocking eye
-        let eye_x = (from.x + to.x) / 2;
-        let eye_y = (from.y + to.y) / 2;
-        self.is_empty_xy(eye_x, eye_y)
-    }
-
-    fn can_horse_move(&self, from: Position, to: Position, _color: Color) -> bool {
-        let dx = from.x as isize - to.x as isize;
-        let dy = from.y as isize - to.y as isize;
 
-        // Horse moves in "L" shape: 2 in one direction, 1 in perpendicular
-        let (abs_dx, abs_dy) = (dx.abs(), dy.abs());
-        if !((abs_dx == 2 && abs_dy == 1) || (abs_dx == 1 && abs_dy == 2)) {
-            return false;
+        let target = self.get(to);
+        if target.is_some_and(|p| p.color == color) {
+            return IllegalMoveReason::OwnPiece;
+        }
+        match piece.piece_type {
+            PieceType::Elephant => IllegalMoveReason::ElephantEyeBlocked,
+            PieceType::Horse => IllegalMoveReason::HorseLegBlocked,
+            PieceType::Cannon if target.is_some() => IllegalMoveReason::CannonNeedsScreen,
+            PieceType::Chariot | PieceType::Cannon => IllegalMoveReason::PathBlocked,
+            // Every other move of the right shape is allowed
+            _ => IllegalMoveReason::WrongPattern(piece.piece_type),
         }
+    }
 
-        // Check hobbling (blocking leg)
-        let (block_x, block_y) = if abs_dx == 2 {
-            (from.x as isize - dx.signum(), from.y as isize)
+    /// Whether `piece` moving from `from` to `to` leaves its general out of
+    /// check and not facing the other general
+    fn keeps_general_safe(&self, from: Position, to: Position, piece: Piece) -> bool {
+        let general = if piece.piece_type == PieceType::General {
+            to
         } else {
-            (from.x as isize, from.y as isize - dy.signum())
+            match self.find_general(piece.color) {
+                Some(pos) => pos,
+                None => return true,
+            }
         };
-
-        if (0..9).contains(&block_x)
-            && (0..10).contains(&block_y)
-            && !self.is_empty_xy(block_x as usize, block_y as usize)
-        {
-            return false;
-        }
-
-        true
+        // The board as it would be after the move
+        let after = |pos: Position| {
+            if pos == to {
+                Some(piece)
+            } else if pos == from {
+                None
+            } else {
+                self.get(pos).copied()
+            }
+        };
+        !general_attacked(after, general, piece.color)
     }
 
-    fn can_chariot_move(&self, from: Position, to: Position, _color: Color) -> bool {
-        // Must be on same file or rank
-        if !from.on_same_file(to) && !from.on_same_rank(to) {
-            return false;
-        }
-        // Path must be clear
-        self.count_between(from, to) == 0
-    }
+    /// Squares the piece on `from` can move to, ignoring whether its own
+    /// general is left in check
+    fn push_targets(&self, from: Position, piece: Piece, targets: &mut Vec<Position>) {
+        let color = piece.color;
+        let open = |pos: Position| self.get(pos).is_none_or(|p| p.color != color);
+        let mut step = |dx: isize, dy: isize, allowed: &dyn Fn(Position) -> bool| {
+            if let Some(to) = offset(from, dx, dy).filter(|&to| allowed(to) && open(to)) {
+                targets.push(to);
+            }
+        };
 
-    fn can_cannon_move(&self, from: Position, to: Position, _color: Color) -> bool {
-        // Must be on same file or rank
-        if !from.on_same_file(to) && !from.on_same_rank(to) {
-            return false;
+        match piece.piece_type {
+            PieceType::General => {
+                for (dx, dy) in ORTHOGONAL {
+                    step(dx, dy, &|to| to.in_palace(color));
+                }
+            }
+            PieceType::Advisor => {
+                for (dx, dy) in DIAGONAL {
+                    step(dx, dy, &|to| to.in_palace(color));
+                }
+            }
+            PieceType::Elephant => {
+                for (dx, dy) in DIAGONAL {
+                    let eye_open = offset(from, dx, dy).is_some_and(|eye| self.is_empty(eye));
+                    step(dx * 2, dy * 2, &|to| eye_open && own_side(to, color));
+                }
+            }
+            PieceType::Horse => {
+                for (dx, dy) in HORSE_JUMPS {
+                    let leg_open =
+                        offset(from, dx / 2, dy / 2).is_some_and(|leg| self.is_empty(leg));
+                    step(dx, dy, &|_| leg_open);
+                }
+            }
+            PieceType::Soldier => {
+                step(0, forward(color), &|_| true);
+                if !own_side(from, color) {
+                    step(-1, 0, &|_| true);
+                    step(1, 0, &|_| true);
+                }
+            }
+            PieceType::Chariot | PieceType::Cannon => {
+                let cannon = piece.piece_type == PieceType::Cannon;
+                for (dx, dy) in ORTHOGONAL {
+                    let mut screened = false;
+                    let mut pos = from;
+                    while let Some(next) = offset(pos, dx, dy) {
+                        pos = next;
+                        match (self.get(pos), cannon, screened) {
+                            (None, _, false) => targets.push(pos),
+                            (None, _, true) => {}
+                            (Some(p), false, _) | (Some(p), true, true) => {
+                                if p.color != color {
+                                    targets.push(pos);
+                                }
+                                break;
+                            }
+                            (Some(_), true, false) => screened = true,
+                        }
+                    }
+                }
+            }
         }
+    }
 
-        let pieces_between = self.count_between(from, to);
-        let target_is_occupied = self.get(to).is_some();
+    /// Points the piece on `from` attacks: those it could capture on if an
+    /// enemy piece stood there, so pieces of its own side it defends are
+    /// included
+    fn push_attacks(&self, from: Position, piece: Piece, targets: &mut Vec<Position>) {
+        let color = piece.color;
+        let mut step = |dx: isize, dy: isize, allowed: &dyn Fn(Position) -> bool| {
+            if let Some(to) = offset(from, dx, dy).filter(|&to| allowed(to)) {
+                targets.push(to);
+            }
+        };
 
-        match (target_is_occupied, pieces_between) {
-            // Moving to empty square: need 0 pieces between
-            (false, 0) => true,
-            // Capturing: need exactly 1 piece between (the screen)
-            (true, 1) => true,
-            _ => false,
+        match piece.piece_type {
+            PieceType::General => {
+                for (dx, dy) in ORTHOGONAL {
+                    step(dx, dy, &|to| to.in_palace(color));
+                }
+            }
+            PieceType::Advisor => {
+                for (dx, dy) in DIAGONAL {
+                    step(dx, dy, &|to| to.in_palace(color));
+                }
+            }
+            PieceType::Elephant => {
+                for (dx, dy) in DIAGONAL {
+                    let eye_open = offset(from, dx, dy).is_some_and(|eye| self.is_empty(eye));
+                    step(dx * 2, dy * 2, &|to| eye_open && own_side(to, color));
+                }
+            }
+            PieceType::Horse => {
+                for (dx, dy) in HORSE_JUMPS {
+                    let leg_open =
+                        offset(from, dx / 2, dy / 2).is_some_and(|leg| self.is_empty(leg));
+                    step(dx, dy, &|_| leg_open);
+                }
+            }
+            PieceType::Soldier => {
+                step(0, forward(color), &|_| true);
+                if !own_side(from, color) {
+                    step(-1, 0, &|_| true);
+                    step(1, 0, &|_| true);
+                }
+            }
+            PieceType::Chariot | PieceType::Cannon => {
+                // A chariot strikes up to the first piece in its way, a
+                // cannon from its screen up to the next piece
+                let screens = usize::from(piece.piece_type == PieceType::Cannon);
+                for (dx, dy) in ORTHOGONAL {
+                    let mut passed = 0;
+                    let mut pos = from;
+                    while let Some(next) = offset(pos, dx, dy) {
+                        pos = next;
+                        if passed == screens {
+                            targets.push(pos);
+                        }
+                        if !self.is_empty(pos) {
+                            if passed == screens {
+                                break;
+                            }
+                            passed += 1;
+                        }
+                    }
+                }
+            }
         }
     }
 
-    fn can_soldier_move(&self, from: Position, to: Position, color: Color) -> bool {
-        // Soldiers move 1 square
-        if from.chebyshev_distance(to) != 1 {
-            return false;
+    /// Count the pieces of `color` attacking each point
+    ///
+    /// A point is attacked when a piece could capture there if an enemy
+    /// piece stood on it. Pins are not taken into account, so a piece that
+    /// may not move because its general would be left in check still
+    /// attacks.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Board, Color, Position};
+    ///
+    /// let attacks = Board::new().attacked_squares(Color::Red);
+    /// // The general and both advisors guard the palace's centre
+    /// assert_eq!(attacks.count(Position::from_xy(4, 8)), 3);
+    /// // The cannon on h7 strikes Black's horse over Black's cannon
+    /// assert!(attacks.is_attacked(Position::from_xy(7, 0)));
+    /// assert!(!attacks.is_attacked(Position::from_xy(4, 4)));
+    /// ```
+    pub fn attacked_squares(&self, color: Color) -> AttackMap {
+        let mut counts = [0u8; SQUARES];
+        let mut targets = Vec::new();
+        for (from, piece) in self.pieces_of_color(color) {
+            targets.clear();
+            self.push_attacks(from, piece, &mut targets);
+            for &to in &targets {
+                if let Some(i) = index(to) {
+                    counts[i] += 1;
+                }
+            }
         }
+        AttackMap { counts }
+    }
 
-        // Determine if soldier has crossed river
-        let crossed_river = match color {
-            Color::Red => from.y <= 4,
-            Color::Black => from.y >= 5,
+    /// Generate all legal destinations for the piece at `from`
+    pub fn legal_moves_from(&self, from: Position) -> Vec<Position> {
+        let Some(&piece) = self.get(from) else {
+            return Vec::new();
         };
+        let mut targets = Vec::new();
+        self.push_targets(from, piece, &mut targets);
+        targets.retain(|&to| self.keeps_general_safe(from, to, piece));
+        targets
+    }
 
-        // Check if move is forward
-        let forward = match color {
-            Color::Red => to.y < from.y,   // Red moves up (decreasing Y)
-            Color::Black => to.y > from.y, // Black moves down (increasing Y)
-        };
+    /// Count the legal moves of the piece at `from`
+    pub fn mobility_at(&self, from: Position) -> usize {
+        self.legal_moves_from(from).len()
+    }
 
-        // Check if move is sideways
-        let sideways = from.on_same_rank(to);
+    /// Whether a side has any legal move
+    pub fn has_legal_move(&self, color: Color) -> bool {
+        let mut targets = Vec::new();
+        self.pieces_of_color(color).any(|(from, piece)| {
+            targets.clear();
+            self.push_targets(from, piece, &mut targets);
+            targets
+                .iter()
+                .any(|&to| self.keeps_general_safe(from, to, piece))
+        })
+    }
 
-        if crossed_river {
-            // After crossing river: can move forward or sideways, not backward
-            forward || sideways
-        } else {
-            // Before crossing river: can only move forward
-            forward && !sideways
-        }
+    /// Generate all legal moves for a side as (from, to) pairs
+    ///
+    /// Moves are sorted by board coordinates so the result is deterministic.
+    pub fn legal_moves(&self, color: Color) -> Vec<(Position, Position)> {
+        self.generate_moves(color, false)
     }
 
-    /// Check if a move is legal according to all rules
-    pub fn is_legal_move(&self, from: Position, to: Position) -> bool {
-        let piece = match self.get(from) {
-            Some(p) => *p,
-            None => return false,
-        };
+    /// Generate only the legal capturing moves for a side
+    pub fn legal_captures(&self, color: Color) -> Vec<(Position, Position)> {
+        self.generate_moves(color, true)
+    }
 
-        // Target must be valid position
-        if !to.is_valid() {
-            return false;
+    fn generate_moves(&self, color: Color, captures_only: bool) -> Vec<(Position, Position)> {
+        let mut moves = Vec::new();
+        let mut targets = Vec::new();
+        for (from, piece) in self.pieces_of_color(color) {
+            targets.clear();
+            self.push_targets(from, piece, &mut targets);
+            moves.extend(
+                targets
+                    .iter()
+                    .filter(|&&to| !captures_only || self.get(to).is_some())
+                    .filter(|&&to| self.keeps_general_safe(from, to, piece))
+                    .map(|&to| (from, to)),
+            );
         }
+        moves.sort_by_key(|(from, to)| (from.y, from.x, to.y, to.x));
+        moves
+    }
 
-        // Cannot capture own piece
-        if let Some(target) = self.get(to) {
-            if target.color == piece.color {
-                return false;
-            }
-        }
+    /// Count the move sequences of `depth` plies with `color` to move
+    ///
+    /// Counting these leaf nodes and comparing with published values is the
+    /// usual check that move generation follows the rules.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::board::Board;
+    /// use cn_chess_tui::types::Color;
+    ///
+    /// assert_eq!(Board::new().perft(Color::Red, 2), 1920);
+    /// ```
+    pub fn perft(&self, color: Color, depth: u32) -> u64 {
+        self.clone().count_leaves(color, depth)
+    }
 
-        // Check piece-specific movement rules
-        if !self.is_valid_move(from, to, piece) {
-            return false;
+    fn count_leaves(&mut self, color: Color, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
         }
-
-        // Simulate move to check if it leaves king in check
-        let mut test_board = self.clone();
-        test_board.move_piece(from, to);
-
-        // Check for flying general violation
-        if test_board.generals_facing() {
-            return false;
+        let moves = self.legal_moves(color);
+        if depth == 1 {
+            return moves.len() as u64;
         }
-
-        // Cannot leave own general in check
-        if test_board.is_in_check(piece.color) {
-            return false;
+        let mut nodes = 0;
+        for (from, to) in moves {
+            let captured = self.get(to).copied();
+            self.move_piece(from, to);
+            nodes += self.count_leaves(color.opponent(), depth - 1);
+            self.move_piece(to, from);
+            if let Some(piece) = captured {
+                self.place_piece(to, piece);
+            }
         }
-
-        true
+        nodes
     }
 
     pub fn width(&self) -> usize {
@@ -378,3 +761,66 @@ impl Board {
         BOARD_HEIGHT
     }
 }
+
+/// Whether `color`'s general on `general` is attacked, reading squares
+/// through `get`
+///
+/// Facing the other general on an open file counts as attacked.
+fn general_attacked(
+    get: impl Fn(Position) -> Option<Piece>,
+    general: Position,
+    color: Color,
+) -> bool {
+    let enemy = color.opponent();
+    let is_enemy = |pos: Position, piece_type: PieceType| {
+        get(pos).is_some_and(|p| p.color == enemy && p.piece_type == piece_type)
+    };
+
+    // Chariots and the other general strike the first piece along a line,
+    // cannons the second
+    for (dx, dy) in ORTHOGONAL {
+        let mut screened = false;
+        let mut pos = general;
+        while let Some(next) = offset(pos, dx, dy) {
+            pos = next;
+            let Some(piece) = get(pos) else {
+                continue;
+            };
+            if screened {
+                if piece.color == enemy && piece.piece_type == PieceType::Cannon {
+                    return true;
+                }
+                break;
+            }
+            if piece.color == enemy
+                && (piece.piece_type == PieceType::Chariot
+                    || (piece.piece_type == PieceType::General && dx == 0))
+            {
+                return true;
+            }
+            screened = true;
+        }
+    }
+
+    // Horses whose leg, next to the horse on the long side, is free
+    for (dx, dy) in HORSE_JUMPS {
+        let Some(horse) = offset(general, dx, dy) else {
+            continue;
+        };
+        if is_enemy(horse, PieceType::Horse)
+            && offset(horse, -dx / 2, -dy / 2).is_some_and(|leg| get(leg).is_none())
+        {
+            return true;
+        }
+    }
+
+    // Soldiers from in front, or beside once across the river
+    let ahead = offset(general, 0, -forward(enemy));
+    if ahead.is_some_and(|pos| is_enemy(pos, PieceType::Soldier)) {
+        return true;
+    }
+    [-1, 1]
+        .into_iter()
+        .filter_map(|dx| offset(general, dx, 0))
+        .any(|pos| is_enemy(pos, PieceType::Soldier) && !own_side(pos, enemy))
+}
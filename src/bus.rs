@@ -0,0 +1,178 @@
+//! The events that drive the game's main loop
+//!
+//! Everything the interface reacts to arrives as an [`AppEvent`] on one
+//! channel: keys, mouse and resizes from a thread reading the terminal,
+//! ticks and clock ticks from timer threads, the AI's moves from the
+//! channel its controller announces them on, and what the server sends
+//! from a thread polling the connection. The loop only waits on
+//! [`EventBus::next`], so a subsystem never stalls it, and each kind of
+//! event can be sent by hand in tests.
+//!
+//! ```
+//! use cn_chess_tui::bus::{AppEvent, EventBus};
+//! use cn_chess_tui::Position;
+//! use std::time::Duration;
+//!
+//! let bus = EventBus::new();
+//! let sender = bus.sender();
+//! sender.send(AppEvent::Tick).unwrap();
+//! sender
+//!     .send(AppEvent::EngineMove {
+//!         from: Position::from_xy(7, 7),
+//!         to: Position::from_xy(4, 7),
+//!     })
+//!     .unwrap();
+//!
+//! assert_eq!(bus.next(Duration::ZERO), Some(AppEvent::Tick));
+//! assert!(matches!(bus.pending().next(), Some(AppEvent::EngineMove { .. })));
+//! assert_eq!(bus.next(Duration::ZERO), None);
+//! ```
+
+use crate::online::{OnlineClient, OnlineEvent, Transport};
+use crate::types::Position;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, MouseEvent};
+use std::sync::mpsc::{self, Receiver, Sender, TryIter};
+use std::sync::{Mutex, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Something for the interface to act on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    /// A key was pressed
+    Key(KeyCode),
+    /// The mouse was clicked, dragged or scrolled
+    Mouse(MouseEvent),
+    /// The terminal now has this many columns and rows
+    Resize(u16, u16),
+    /// Time to poll the analysis and draw again
+    Tick,
+    /// The AI settled on a move, not played yet
+    EngineMove { from: Position, to: Position },
+    /// The server sent something about the online game
+    NetMove(OnlineEvent),
+    /// The connection to the server failed
+    NetError(String),
+    /// Time to check whether the side to move ran out of time
+    ClockTick,
+}
+
+/// The channel the main loop takes its events from
+pub struct EventBus {
+    sender: Sender<AppEvent>,
+    receiver: Receiver<AppEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    /// A bus with no producers yet
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        EventBus { sender, receiver }
+    }
+
+    /// A handle for sending events onto the bus, from any thread
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.sender.clone()
+    }
+
+    /// Read the terminal's keys, mouse and resizes on a thread of their
+    /// own; the thread ends at the first event after the bus is dropped
+    pub fn spawn_input(&self) -> JoinHandle<()> {
+        let sender = self.sender();
+        thread::spawn(move || loop {
+            let app_event = match event::read() {
+                // Only presses: Windows reports releases as well
+                Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => AppEvent::Key(key.code),
+                Ok(Event::Mouse(mouse)) => AppEvent::Mouse(mouse),
+                Ok(Event::Resize(width, height)) => AppEvent::Resize(width, height),
+                Ok(_) => continue,
+                Err(_) => return,
+            };
+            if sender.send(app_event).is_err() {
+                return;
+            }
+        })
+    }
+
+    /// Send `event` every `interval` on a thread of its own, until the bus
+    /// is dropped
+    pub fn spawn_ticker(&self, interval: Duration, event: AppEvent) -> JoinHandle<()> {
+        let sender = self.sender();
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if sender.send(event.clone()).is_err() {
+                return;
+            }
+        })
+    }
+
+    /// Pass what arrives on `receiver` onto the bus, made into events by
+    /// `event`, on a thread of its own; the thread ends when either side
+    /// is dropped
+    pub fn forward<T: Send + 'static>(
+        &self,
+        receiver: Receiver<T>,
+        event: impl Fn(T) -> AppEvent + Send + 'static,
+    ) -> JoinHandle<()> {
+        let sender = self.sender();
+        thread::spawn(move || {
+            for item in receiver {
+                if sender.send(event(item)).is_err() {
+                    return;
+                }
+            }
+        })
+    }
+
+    /// Poll `client` every `interval` on a thread of its own and send what
+    /// the server says as [`AppEvent::NetMove`]s
+    ///
+    /// The thread ends when the client or the bus is dropped, or after
+    /// reporting a failed connection as [`AppEvent::NetError`].
+    pub fn spawn_online<T: Transport + Send + 'static>(
+        &self,
+        client: Weak<Mutex<OnlineClient<T>>>,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let sender = self.sender();
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            let Some(client) = client.upgrade() else {
+                return;
+            };
+            let polled = match client.lock() {
+                Ok(mut client) => client.poll(),
+                Err(_) => return,
+            };
+            match polled {
+                Ok(events) => {
+                    for event in events {
+                        if sender.send(AppEvent::NetMove(event)).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ = sender.send(AppEvent::NetError(e.to_string()));
+                    return;
+                }
+            }
+        })
+    }
+
+    /// The next event, waiting up to `timeout` for one
+    pub fn next(&self, timeout: Duration) -> Option<AppEvent> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// The events already waiting, without blocking
+    pub fn pending(&self) -> TryIter<'_, AppEvent> {
+        self.receiver.try_iter()
+    }
+}
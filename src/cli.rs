@@ -0,0 +1,227 @@
+//! Headless play on standard input and output
+//!
+//! `cn_chess_tui --cli` plays a game without the TUI: moves are typed one
+//! per line in ICCS (`h7e7`), WXF (`C2.5`) or Chinese notation (`炮二平五`),
+//! and the board is printed after each one. Plain lines in and out suit
+//! piping, scripts and screen readers.
+//!
+//! # Examples
+//! ```
+//! use cn_chess_tui::cli;
+//! use cn_chess_tui::notation::format::MoveStyle;
+//! use cn_chess_tui::GameController;
+//!
+//! let mut controller = GameController::new();
+//! let mut output = Vec::new();
+//! cli::run(&mut controller, MoveStyle::Chinese, "h7e7\n马8进7\n".as_bytes(), &mut output).unwrap();
+//! let output = String::from_utf8(output).unwrap();
+//! assert!(output.contains("1. 炮二平五 (h7e7)"));
+//! assert!(output.contains("1... 马8进7 (h0g2)"));
+//! assert_eq!(controller.get_moves().len(), 2);
+//! ```
+
+use crate::fen_print::write_board_ascii;
+use crate::game::{AiMode, Game, GameController, GameState};
+use crate::notation::format::{parse_move, MoveFormatter, MoveStyle};
+use crate::types::{Color, Position};
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+
+const HELP: &str = "\
+Type a move in ICCS (h7e7), WXF (C2.5) or Chinese notation (炮二平五).
+Commands:
+  board               Print the board
+  fen                 Print the position as FEN
+  moves               List the legal moves
+  undo                Take back the last move
+  ai <red|black|off>  Let the AI play a side
+  help                Show this help
+  quit                Leave";
+
+/// Play moves read from `input` until it ends or `quit` is typed
+///
+/// Moves are echoed in `style` and problems are reported as lines starting
+/// with `Error:`; a bad line never ends the session. Lines starting with
+/// `#` are ignored, so scripts can carry comments.
+pub fn run<R: BufRead, W: Write>(
+    controller: &mut GameController,
+    style: MoveStyle,
+    input: R,
+    output: &mut W,
+) -> io::Result<()> {
+    let formatter = MoveFormatter::new(style);
+    write_position(controller, output)?;
+
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        match (words.next().unwrap_or_default(), words.next()) {
+            ("quit" | "exit", None) => break,
+            ("help", None) => writeln!(output, "{}", HELP)?,
+            ("board", None) => write_position(controller, output)?,
+            ("fen", None) => writeln!(output, "{}", controller.to_fen())?,
+            ("moves", None) => {
+                let game = controller.game();
+                let moves: Vec<String> = game
+                    .board()
+                    .legal_moves(game.turn())
+                    .into_iter()
+                    .map(|(from, to)| formatter.format_move(game, from, to))
+                    .collect();
+                writeln!(output, "{}", moves.join(", "))?;
+            }
+            ("undo", None) => {
+                if !controller.undo_move() {
+                    writeln!(output, "Error: no move to take back")?;
+                    continue;
+                }
+                // Take the AI's reply back too, so it is the user's turn
+                if ai_to_move(controller) {
+                    controller.undo_move();
+                }
+                write_position(controller, output)?;
+                play_ai_move(controller, &formatter, output)?;
+            }
+            ("ai", Some(side)) => {
+                let mode = match side {
+                    "red" => AiMode::PlaysRed,
+                    "black" => AiMode::PlaysBlack,
+                    "off" => AiMode::Off,
+                    _ => {
+                        writeln!(output, "Error: ai takes red, black or off")?;
+                        continue;
+                    }
+                };
+                controller.set_ai_mode(mode);
+                writeln!(output, "AI: {}", side)?;
+                play_ai_move(controller, &formatter, output)?;
+            }
+            _ => {
+                play_typed_move(controller, &formatter, line, output)?;
+                play_ai_move(controller, &formatter, output)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn ai_to_move(controller: &GameController) -> bool {
+    matches!(
+        (controller.ai_mode(), controller.turn()),
+        (AiMode::PlaysRed, Color::Red)
+            | (AiMode::PlaysBlack, Color::Black)
+            | (AiMode::PlaysBoth, _)
+    )
+}
+
+/// Play a move typed by the user, reporting why it can't be played
+fn play_typed_move<W: Write>(
+    controller: &mut GameController,
+    formatter: &MoveFormatter,
+    text: &str,
+    output: &mut W,
+) -> io::Result<()> {
+    let Some((from, to)) = parse_move(controller.game(), text) else {
+        return match MoveStyle::detect(text) {
+            Some(style) => writeln!(
+                output,
+                "Error: no legal {} move matches {}",
+                style.name(),
+                text
+            ),
+            None => writeln!(output, "Error: not a move or command: {} (try help)", text),
+        };
+    };
+    play_move(controller, formatter, from, to, output)
+}
+
+/// Let the AI move while it is its turn
+///
+/// An external engine is waited on; the built-in AI answers at once.
+fn play_ai_move<W: Write>(
+    controller: &mut GameController,
+    formatter: &MoveFormatter,
+    output: &mut W,
+) -> io::Result<()> {
+    loop {
+        if let Err(e) = controller.trigger_ai_move() {
+            return writeln!(output, "Error: AI failed: {}", e);
+        }
+        if !controller.is_engine_thinking() {
+            return Ok(());
+        }
+        // Name the move before it is played
+        let game = controller.game().clone();
+        match controller.check_engine_response() {
+            Ok(Some((from, to))) => {
+                write_move(&game, formatter, from, to, output)?;
+                write_position(controller, output)?;
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(e) => return writeln!(output, "Error: AI failed: {}", e),
+        }
+    }
+}
+
+fn play_move<W: Write>(
+    controller: &mut GameController,
+    formatter: &MoveFormatter,
+    from: Position,
+    to: Position,
+    output: &mut W,
+) -> io::Result<()> {
+    let game = controller.game().clone();
+    match controller.human_move(from, to) {
+        Ok(()) => {
+            write_move(&game, formatter, from, to, output)?;
+            write_position(controller, output)
+        }
+        Err(e) => writeln!(
+            output,
+            "Error: {}: {}",
+            formatter.format_move(&game, from, to),
+            e
+        ),
+    }
+}
+
+/// Write a move about to be played in `game`, numbered `1.` for Red and
+/// `1...` for Black
+fn write_move<W: Write>(
+    game: &Game,
+    formatter: &MoveFormatter,
+    from: Position,
+    to: Position,
+    output: &mut W,
+) -> io::Result<()> {
+    let number = game.get_moves().len() / 2 + 1;
+    let dots = match game.turn() {
+        Color::Red => ".",
+        Color::Black => "...",
+    };
+    writeln!(
+        output,
+        "{}{} {}",
+        number,
+        dots,
+        formatter.format_move(game, from, to)
+    )
+}
+
+/// Write the board and whose turn it is, or how the game ended
+fn write_position<W: Write>(controller: &GameController, output: &mut W) -> io::Result<()> {
+    write_board_ascii(output, controller.board())?;
+    match controller.state() {
+        GameState::Playing if controller.is_in_check() => {
+            writeln!(output, "{} to move, in check", controller.turn())
+        }
+        GameState::Playing => writeln!(output, "{} to move", controller.turn()),
+        state => writeln!(output, "Game over: {}", state),
+    }
+}
@@ -0,0 +1,130 @@
+//! Copy text to and from the system clipboard
+//!
+//! Copying uses the OSC 52 escape sequence, which most modern terminals
+//! (and tmux with `set-clipboard on`) forward to the clipboard. It needs no
+//! platform clipboard library and also works over SSH.
+//!
+//! Terminals don't let programs read the clipboard, so pasting needs the
+//! `clipboard` feature, which talks to the platform clipboard directly (and
+//! is tried first when copying). Without it [`paste`] fails with an
+//! [`io::ErrorKind::Unsupported`] error whose message says so.
+
+use std::io::{self, Write};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding
+fn base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// The OSC 52 sequence that puts `text` on the clipboard
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::clipboard::osc52;
+///
+/// assert_eq!(osc52("hi"), "\x1b]52;c;aGk=\x07");
+/// ```
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Copy `text` to the clipboard
+///
+/// With the `clipboard` feature the platform clipboard is tried first;
+/// otherwise, or if it can't be reached, the terminal is asked to copy.
+pub fn copy(text: &str) -> io::Result<()> {
+    #[cfg(feature = "clipboard")]
+    if system::copy(text).is_ok() {
+        return Ok(());
+    }
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52(text).as_bytes())?;
+    stdout.flush()
+}
+
+/// Read text from the clipboard
+pub fn paste() -> io::Result<String> {
+    #[cfg(feature = "clipboard")]
+    return system::paste();
+
+    #[cfg(not(feature = "clipboard"))]
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "pasting needs a build with the clipboard feature",
+    ))
+}
+
+/// The platform clipboard
+#[cfg(feature = "clipboard")]
+mod system {
+    use arboard::Clipboard;
+    use std::cell::RefCell;
+    use std::io;
+
+    thread_local! {
+        // Kept open: on X11 copied text is served by its owner, so it
+        // would be lost with a clipboard dropped right after copying
+        static CLIPBOARD: RefCell<Option<Clipboard>> = const { RefCell::new(None) };
+    }
+
+    fn with_clipboard<T>(
+        f: impl FnOnce(&mut Clipboard) -> Result<T, arboard::Error>,
+    ) -> io::Result<T> {
+        CLIPBOARD.with_borrow_mut(|clipboard| {
+            if clipboard.is_none() {
+                *clipboard = Some(Clipboard::new().map_err(io::Error::other)?);
+            }
+            f(clipboard.as_mut().expect("opened above")).map_err(io::Error::other)
+        })
+    }
+
+    pub(super) fn copy(text: &str) -> io::Result<()> {
+        with_clipboard(|clipboard| clipboard.set_text(text))
+    }
+
+    pub(super) fn paste() -> io::Result<String> {
+        with_clipboard(|clipboard| clipboard.get_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64("炮".as_bytes()), "54Ku");
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    #[test]
+    fn test_paste_needs_feature() {
+        let error = paste().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+        assert!(error.to_string().contains("clipboard feature"));
+    }
+}
@@ -0,0 +1,221 @@
+//! Commands typed at the `:` prompt in the TUI
+//!
+//! ```text
+//! :fen <fen>              load a position
+//! :export <format> <path> save the game as pgn, xml or fen
+//! :engine <path>          load a UCCI or UCI engine
+//! :flip                   turn the board around
+//! :theme <name>           switch the color theme
+//! :accessible             describe the game in words for screen readers
+//! :describe [<path>]      copy or save the position as plain text
+//! :quit                   leave the game
+//! :h7e7, :C2.5, :炮二平五  play a move
+//! ```
+
+use crate::notation::format::MoveStyle;
+use crate::state::FileKind;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Command names, for completion
+pub const COMMANDS: [&str; 8] = [
+    "accessible",
+    "describe",
+    "engine",
+    "export",
+    "fen",
+    "flip",
+    "quit",
+    "theme",
+];
+
+/// A command typed at the `:` prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Load a position from a FEN string
+    Fen(String),
+    /// Save the game to a file
+    Export {
+        kind: FileKind,
+        path: PathBuf,
+    },
+    /// Load an engine from a path
+    Engine(String),
+    /// Turn the board around
+    Flip,
+    /// Switch to a color theme by name
+    Theme(String),
+    /// Turn the accessible mode on or off
+    Accessible,
+    /// Write the position out in words, to a file or else the clipboard
+    Describe(Option<PathBuf>),
+    Quit,
+    /// Play a move written in ICCS, WXF or Chinese notation
+    Move(String),
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    /// Parse a command line without its leading `:`
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::command::Command;
+    ///
+    /// assert_eq!("flip".parse(), Ok(Command::Flip));
+    /// assert_eq!(
+    ///     " theme  light ".parse(),
+    ///     Ok(Command::Theme("light".to_string()))
+    /// );
+    /// assert!("fly".parse::<Command>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, arg) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let arg = arg.trim();
+        let required = |usage: &str| {
+            if arg.is_empty() {
+                Err(format!("usage: :{}", usage))
+            } else {
+                Ok(arg.to_string())
+            }
+        };
+
+        match name {
+            "fen" => required("fen <fen>").map(Command::Fen),
+            "export" => {
+                let usage = "usage: :export pgn|xml|fen <path>";
+                let (format, path) = arg.split_once(char::is_whitespace).ok_or(usage)?;
+                let kind = match format.to_lowercase().as_str() {
+                    "pgn" => FileKind::Pgn,
+                    "xml" => FileKind::Xml,
+                    "fen" => FileKind::Fen,
+                    _ => return Err(format!("cannot export as '{}'", format)),
+                };
+                Ok(Command::Export {
+                    kind,
+                    path: PathBuf::from(path.trim()),
+                })
+            }
+            "engine" => required("engine <path>").map(Command::Engine),
+            "flip" => Ok(Command::Flip),
+            "theme" => required("theme <name>").map(Command::Theme),
+            "accessible" => Ok(Command::Accessible),
+            "describe" => Ok(Command::Describe(
+                (!arg.is_empty()).then(|| PathBuf::from(arg)),
+            )),
+            "q" | "quit" => Ok(Command::Quit),
+            "" => Err("no command".to_string()),
+            _ if MoveStyle::detect(s).is_some() => Ok(Command::Move(s.to_string())),
+            _ => Err(format!("unknown command '{}'", name)),
+        }
+    }
+}
+
+/// Complete the command name being typed
+///
+/// Returns the input with the name completed as far as the matching names
+/// agree, followed by a space once only one matches, and the names that
+/// match. Input that already has an argument is returned as it is.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::command::complete;
+///
+/// assert_eq!(complete("fl"), ("flip ".to_string(), vec!["flip"]));
+/// assert_eq!(complete("e"), ("e".to_string(), vec!["engine", "export"]));
+/// ```
+pub fn complete(input: &str) -> (String, Vec<&'static str>) {
+    let typed = input.trim_start();
+    if typed.contains(char::is_whitespace) {
+        return (input.to_string(), Vec::new());
+    }
+    let matches: Vec<&'static str> = COMMANDS
+        .into_iter()
+        .filter(|name| name.starts_with(typed))
+        .collect();
+    let completed = match matches.as_slice() {
+        [] => input.to_string(),
+        [name] => format!("{} ", name),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.len(), |len, name| {
+                first
+                    .bytes()
+                    .zip(name.bytes())
+                    .take(len)
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            });
+            first[..common].to_string()
+        }
+    };
+    (completed, matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let fen = "4k4/9/9/9/9/9/9/9/9/4K4 w - - 0 1";
+        assert_eq!(
+            format!("fen {}", fen).parse(),
+            Ok(Command::Fen(fen.to_string()))
+        );
+        assert_eq!(
+            "export PGN games/mine.pgn".parse(),
+            Ok(Command::Export {
+                kind: FileKind::Pgn,
+                path: PathBuf::from("games/mine.pgn"),
+            })
+        );
+        assert_eq!(
+            "engine /opt/engines/pikafish".parse(),
+            Ok(Command::Engine("/opt/engines/pikafish".to_string()))
+        );
+        assert_eq!("q".parse(), Ok(Command::Quit));
+        assert_eq!("accessible".parse(), Ok(Command::Accessible));
+        assert_eq!("describe".parse(), Ok(Command::Describe(None)));
+        assert_eq!(
+            "describe pos.txt".parse(),
+            Ok(Command::Describe(Some(PathBuf::from("pos.txt"))))
+        );
+        assert_eq!("h7e7".parse(), Ok(Command::Move("h7e7".to_string())));
+        assert_eq!(
+            " 炮二平五".parse(),
+            Ok(Command::Move("炮二平五".to_string()))
+        );
+        assert_eq!("C2.5".parse(), Ok(Command::Move("C2.5".to_string())));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "fen".parse::<Command>(),
+            Err("usage: :fen <fen>".to_string())
+        );
+        assert!("export pgn".parse::<Command>().is_err());
+        assert_eq!(
+            "export xqf a.xqf".parse::<Command>(),
+            Err("cannot export as 'xqf'".to_string())
+        );
+        assert_eq!(
+            "undo".parse::<Command>(),
+            Err("unknown command 'undo'".to_string())
+        );
+        assert!("".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(complete("th"), ("theme ".to_string(), vec!["theme"]));
+        assert_eq!(complete("e"), ("e".to_string(), vec!["engine", "export"]));
+        assert_eq!(complete("f"), ("f".to_string(), vec!["fen", "flip"]));
+        assert_eq!(complete("ex"), ("export ".to_string(), vec!["export"]));
+        assert_eq!(complete("x"), ("x".to_string(), Vec::<&str>::new()));
+        // Arguments are left alone
+        assert_eq!(complete("theme li"), ("theme li".to_string(), Vec::new()));
+    }
+}
@@ -1,46 +1,377 @@
-//! Configuration file support for AI engine settings
+//! Configuration file support for the engine, rules, key bindings and colors
 
-use dirs::config_dir;
+use crate::game::{
+    Adjudication, AiConfig, Difficulty, SearchLimit, SideConfig, StalemateRule, TimeControl,
+    DEFAULT_MOVE_LIMIT,
+};
+use crate::i18n::Lang;
+use crate::notation::format::MoveStyle;
+use crate::paths::Paths;
+use crate::ucci::kibitz::KibitzEngine;
+use crate::ucci::EngineProtocol;
+#[cfg(feature = "tui")]
+use crate::ui::{PieceStyle, Theme};
 use serde::Deserialize;
 use std::path::PathBuf;
 
-/// Engine configuration from TOML file
-#[derive(Debug, Deserialize)]
-pub struct EngineConfig {
-    /// Path to the UCCI engine executable
+/// Settings from `config.toml`
+///
+/// Every field is optional; missing fields fall back to the built-in
+/// defaults.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::config::Config;
+///
+/// let config: Config = toml::from_str("search_depth = 3\n[keys]\nundo = 'z'").unwrap();
+/// assert_eq!(config.ai_config().search_depth, 3);
+/// assert_eq!(config.keys.undo, 'z');
+/// assert_eq!(config.keys.quit, 'q');
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    /// Path to the UCCI or UCI engine executable
     pub engine_path: Option<PathBuf>,
+    /// Protocol the engine speaks: auto, ucci or uci
+    #[serde(default)]
+    pub engine_protocol: EngineProtocol,
+    /// Whether the engine thinks on the player's time
+    pub ponder: Option<bool>,
     /// Whether to show engine thinking output
     pub show_thinking: Option<bool>,
+    /// Whether to mark the engine's expected moves on the board
+    pub show_pv: Option<bool>,
+    /// How many of the best moves analysis and hints show
+    pub multipv: Option<u32>,
+    /// Whether to ring the terminal bell on captures and checks
+    pub bell: Option<bool>,
+    /// Search depth (in plies) for the built-in AI
+    pub search_depth: Option<u32>,
+    /// Depth (in plies) the engine searches for each move
+    pub engine_depth: Option<u32>,
+    /// Time (in milliseconds) the engine searches for each move; wins over
+    /// `engine_nodes` and `engine_depth`
+    pub engine_movetime: Option<u64>,
+    /// Positions the engine searches for each move; wins over
+    /// `engine_depth`
+    pub engine_nodes: Option<u64>,
+    /// Seconds after which a thinking engine is stopped
+    pub engine_timeout: Option<u64>,
+    /// Moves per side without a capture before a draw (0 disables the rule)
+    pub move_limit: Option<u32>,
+    /// Seed for every random choice, for reproducible games
+    pub seed: Option<u64>,
+    /// Centipawn margin within which the built-in AI picks moves at random
+    pub ai_variety: Option<i32>,
+    /// Percent of moves the built-in AI picks from every legal move
+    pub ai_blunder_rate: Option<u32>,
+    /// Whether the built-in AI plays from the opening book
+    pub opening_book: Option<bool>,
+    /// AI strength preset: beginner, intermediate, advanced or max; when set
+    /// it replaces `search_depth`, `ai_variety` and the engine limits
+    pub difficulty: Option<Difficulty>,
+    /// Whether a stalemate is a draw or a loss for the stalemated side
+    #[serde(default)]
+    pub stalemate: StalemateRule,
+    /// Color theme name: classic, high-contrast, light, colorblind or mono
+    pub theme: Option<String>,
+    /// Notation for moves in the move history, engine lines and analysis
+    #[serde(default)]
+    pub notation: MoveStyle,
+    /// Notation of the moves in exported PGN and XML files
+    #[serde(default)]
+    pub export_notation: MoveStyle,
+    /// How pieces are written: chinese or letters; detected from the
+    /// terminal if not set
+    #[cfg(feature = "tui")]
+    pub pieces: Option<PieceStyle>,
+    /// Whether to label the board's files and ranks in ICCS coordinates
+    pub coordinates: Option<bool>,
+    /// Whether to describe the game in words for screen readers
+    pub accessible: Option<bool>,
+    /// Language of the interface: bilingual (the default), en or zh
+    #[serde(default)]
+    pub lang: Lang,
+    /// Whether finished games are saved to the game library
+    pub auto_save: Option<bool>,
+    /// Directory of the game library; `archives` in the data directory if
+    /// not set
+    pub library_dir: Option<PathBuf>,
+    /// Key bindings for game commands
+    #[serde(default)]
+    pub keys: KeyBindings,
+    /// Settings for the side Red, in place of the shared ones
+    #[serde(default)]
+    pub red: SideSettings,
+    /// Settings for the side Black, in place of the shared ones
+    #[serde(default)]
+    pub black: SideSettings,
+    /// When a game the AI plays against itself is ended early, from the
+    /// `[adjudication]` table
+    #[serde(default)]
+    pub adjudication: Adjudication,
+    /// Engines that analyze the game side by side, from the `[[kibitz]]`
+    /// tables
+    #[serde(default)]
+    pub kibitz: Vec<KibitzEngine>,
+}
+
+/// Settings for one side, from the `[red]` or `[black]` table, for games
+/// where the two sides get different engines, strengths or clocks
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::config::Config;
+/// use cn_chess_tui::game::{SearchLimit, TimeControl};
+/// use cn_chess_tui::Color;
+///
+/// let config: Config =
+///     toml::from_str("[red]\ntime_control = '10'\n[black]\nengine_movetime = 1000").unwrap();
+/// assert_eq!(config.time_controls(), [Some(TimeControl::minutes(10, 0)), None]);
+/// let ai = config.ai_config().for_side(Color::Black);
+/// assert_eq!(ai.effective_engine_limit(), SearchLimit::Time(1000));
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SideSettings {
+    /// Engine that plays this side instead of `engine_path`
+    pub engine_path: Option<PathBuf>,
+    /// Search depth (in plies) for the built-in AI
+    pub search_depth: Option<u32>,
+    /// Depth (in plies) the engine searches for each move
+    pub engine_depth: Option<u32>,
+    /// Time (in milliseconds) the engine searches for each move
+    pub engine_movetime: Option<u64>,
+    /// Positions the engine searches for each move
+    pub engine_nodes: Option<u64>,
+    /// AI strength preset for this side
+    pub difficulty: Option<Difficulty>,
+    /// This side's clock as minutes+seconds, e.g. `10+5`; a side without
+    /// one plays without a clock
+    pub time_control: Option<TimeControl>,
+}
+
+impl SideSettings {
+    /// The overrides for [`AiConfig::red`] or [`AiConfig::black`]
+    pub fn side_config(&self) -> SideConfig {
+        SideConfig {
+            engine_path: self.engine_path.clone(),
+            search_depth: self.search_depth,
+            engine_limit: engine_limit(self.engine_movetime, self.engine_nodes, self.engine_depth),
+            difficulty: self.difficulty,
+        }
+    }
+}
+
+/// The search limit the first of `movetime`, `nodes` and `depth` set gives
+fn engine_limit(
+    movetime: Option<u64>,
+    nodes: Option<u64>,
+    depth: Option<u32>,
+) -> Option<SearchLimit> {
+    match (movetime, nodes, depth) {
+        (Some(time), _, _) => Some(SearchLimit::Time(time)),
+        (None, Some(nodes), _) => Some(SearchLimit::Nodes(nodes)),
+        (None, None, Some(depth)) => Some(SearchLimit::Depth(depth.max(1))),
+        (None, None, None) => None,
+    }
+}
+
+/// Keys for the game commands that can be remapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub undo: char,
+    pub redo: char,
+    pub restart: char,
+    pub quit: char,
+}
+
+impl KeyBindings {
+    /// `u` to undo, `y` to redo, `r` to restart, `q` to quit
+    pub const DEFAULT: KeyBindings = KeyBindings {
+        undo: 'u',
+        redo: 'y',
+        restart: 'r',
+        quit: 'q',
+    };
+
+    /// Whether `key` is bound to one of these commands, which then takes
+    /// the key over from any fixed shortcut on it
+    pub fn is_bound(&self, key: char) -> bool {
+        [self.undo, self.redo, self.restart, self.quit].contains(&key)
+    }
 }
 
-impl EngineConfig {
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Config {
     /// Load configuration from the default config file location
     ///
-    /// Config file is looked for in:
-    /// - Linux: `~/.config/cn_chess_tui/config.toml`
-    /// - macOS: `~/Library/Application Support/cn_chess_tui/config.toml`
-    /// - Windows: `%APPDATA%\cn_chess_tui\config.toml`
+    /// The file is `config.toml` in the config directory given by
+    /// [`Paths`]; on Linux `~/.config/cn_chess_tui/config.toml`.
     ///
     /// Returns None if config file doesn't exist or is invalid
     pub fn load() -> Option<Self> {
-        let config_dir = config_dir()?.join("cn_chess_tui");
-        let config_path = config_dir.join("config.toml");
+        Self::load_from(&Paths::resolve()?.config_file())
+    }
 
-        let contents = std::fs::read_to_string(config_path).ok()?;
+    /// Load configuration from a specific file
+    ///
+    /// Returns None if the file doesn't exist or is invalid
+    pub fn load_from(path: &std::path::Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(path).ok()?;
         toml::from_str(&contents).ok()
     }
 
+    /// Load the config file, or the defaults if there is none
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_default()
+    }
+
     /// Get AI engine path from config file
     pub fn get_engine_path(&self) -> Option<PathBuf> {
         self.engine_path.clone()
     }
 
+    /// Get ponder setting from config
+    ///
+    /// Returns false if not set
+    pub fn get_ponder(&self) -> bool {
+        self.ponder.unwrap_or(false)
+    }
+
     /// Get show_thinking setting from config
     ///
     /// Returns false if not set
     pub fn get_show_thinking(&self) -> bool {
         self.show_thinking.unwrap_or(false)
     }
+
+    /// Get show_pv setting from config
+    ///
+    /// Returns true if not set
+    pub fn get_show_pv(&self) -> bool {
+        self.show_pv.unwrap_or(true)
+    }
+
+    /// Get bell setting from config
+    ///
+    /// Returns false if not set
+    pub fn get_bell(&self) -> bool {
+        self.bell.unwrap_or(false)
+    }
+
+    /// Get opening_book setting from config
+    ///
+    /// Returns true if not set
+    pub fn get_opening_book(&self) -> bool {
+        self.opening_book.unwrap_or(true)
+    }
+
+    /// Get the natural-move limit for [`Game::set_move_limit`](crate::game::Game::set_move_limit)
+    ///
+    /// Returns the default of 60 if not set, and None if set to 0
+    pub fn get_move_limit(&self) -> Option<u32> {
+        match self.move_limit.unwrap_or(DEFAULT_MOVE_LIMIT) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+
+    /// Get the color theme
+    ///
+    /// Returns the classic theme if not set or the name is unknown
+    #[cfg(feature = "tui")]
+    pub fn get_theme(&self) -> Theme {
+        self.theme
+            .as_deref()
+            .and_then(Theme::by_name)
+            .unwrap_or_default()
+    }
+
+    /// Get the piece style, detecting what the terminal can show if not set
+    #[cfg(feature = "tui")]
+    pub fn get_piece_style(&self) -> PieceStyle {
+        self.pieces.unwrap_or_else(PieceStyle::detect)
+    }
+
+    /// Get coordinates setting from config
+    ///
+    /// Returns false if not set
+    pub fn get_coordinates(&self) -> bool {
+        self.coordinates.unwrap_or(false)
+    }
+
+    /// Get accessible setting from config
+    ///
+    /// Returns false if not set
+    pub fn get_accessible(&self) -> bool {
+        self.accessible.unwrap_or(false)
+    }
+
+    /// Get auto_save setting from config
+    ///
+    /// Returns false if not set
+    pub fn get_auto_save(&self) -> bool {
+        self.auto_save.unwrap_or(false)
+    }
+
+    /// Get the game library directory
+    ///
+    /// Returns the default archive directory if not set, or None if there
+    /// is no home directory to find it in
+    pub fn get_library_dir(&self) -> Option<PathBuf> {
+        self.library_dir
+            .clone()
+            .or_else(|| Some(Paths::resolve()?.archive_dir()))
+    }
+
+    /// How far the engine searches for each move
+    ///
+    /// `engine_movetime` wins over `engine_nodes`, which wins over
+    /// `engine_depth`; with none of them set the engine searches 10 plies.
+    pub fn get_engine_limit(&self) -> SearchLimit {
+        engine_limit(self.engine_movetime, self.engine_nodes, self.engine_depth).unwrap_or_default()
+    }
+
+    /// The time controls Red and Black play under; a side with no
+    /// `time_control` in its table has no clock
+    pub fn time_controls(&self) -> [Option<TimeControl>; 2] {
+        [self.red.time_control, self.black.time_control]
+    }
+
+    /// AI settings for [`GameController`](crate::game::GameController)
+    pub fn ai_config(&self) -> AiConfig {
+        let defaults = AiConfig::default();
+        AiConfig {
+            engine_path: self.get_engine_path(),
+            engine_protocol: self.engine_protocol,
+            ponder: self.get_ponder(),
+            show_thinking: self.get_show_thinking(),
+            show_pv: self.get_show_pv(),
+            search_depth: self.search_depth.unwrap_or(defaults.search_depth),
+            engine_limit: self.get_engine_limit(),
+            engine_timeout_ms: self
+                .engine_timeout
+                .map_or(defaults.engine_timeout_ms, |secs| secs.saturating_mul(1000)),
+            variety: self.ai_variety.unwrap_or(defaults.variety),
+            blunder_rate: self
+                .ai_blunder_rate
+                .unwrap_or(defaults.blunder_rate)
+                .min(100),
+            seed: self.seed,
+            opening_book: self.get_opening_book(),
+            difficulty: self.difficulty,
+            red: self.red.side_config(),
+            black: self.black.side_config(),
+            adjudication: self.adjudication,
+            multipv: self.multipv.unwrap_or(defaults.multipv).max(1),
+        }
+    }
 }
 
 /// Get AI engine path from config file
@@ -48,18 +379,41 @@ impl EngineConfig {
 /// This is a convenience function that loads the config and extracts the engine path.
 /// Returns None if config file doesn't exist or engine_path is not set.
 pub fn get_engine_path_from_config() -> Option<PathBuf> {
-    EngineConfig::load()?.get_engine_path()
+    Config::load()?.get_engine_path()
 }
 
 /// Get show_thinking setting from config
 ///
 /// Returns false if config file doesn't exist or show_thinking is not set.
 pub fn get_show_thinking_from_config() -> bool {
-    EngineConfig::load()
+    Config::load()
         .map(|cfg| cfg.get_show_thinking())
         .unwrap_or(false)
 }
 
+/// Get the random seed from config
+///
+/// Returns None if config file doesn't exist or seed is not set.
+pub fn get_seed_from_config() -> Option<u64> {
+    Config::load()?.seed
+}
+
+/// Get the built-in AI's move variety from config
+///
+/// Returns 0 (always play the best move) if not set.
+pub fn get_ai_variety_from_config() -> i32 {
+    Config::load().and_then(|cfg| cfg.ai_variety).unwrap_or(0)
+}
+
+/// Get the natural-move limit from config
+///
+/// Returns the default limit if config file doesn't exist or move_limit is not set.
+pub fn get_move_limit_from_config() -> Option<u32> {
+    Config::load()
+        .map(|cfg| cfg.get_move_limit())
+        .unwrap_or(Some(DEFAULT_MOVE_LIMIT))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,7 +427,7 @@ mod tests {
             show_thinking = true
         "#;
 
-        let config: EngineConfig = toml::from_str(toml_content).unwrap();
+        let config: Config = toml::from_str(toml_content).unwrap();
         assert_eq!(config.engine_path, Some(PathBuf::from("/usr/bin/pikafish")));
         assert_eq!(config.show_thinking, Some(true));
     }
@@ -81,26 +435,61 @@ mod tests {
     #[test]
     fn test_parse_config_empty() {
         let toml_content = "";
-        let config: EngineConfig = toml::from_str(toml_content).unwrap();
+        let config: Config = toml::from_str(toml_content).unwrap();
         assert_eq!(config.engine_path, None);
         assert_eq!(config.show_thinking, None);
     }
 
+    #[test]
+    fn test_parse_adjudication() {
+        let config: Config =
+            toml::from_str("[adjudication]\nmax_plies = 300\nresign_plies = 6").unwrap();
+        let rules = config.ai_config().adjudication;
+        assert_eq!(rules.max_plies, Some(300));
+        assert_eq!(rules.resign_plies, Some(6));
+        assert_eq!(rules.resign_score, Adjudication::default().resign_score);
+        assert_eq!(rules.draw_plies, None);
+    }
+
+    #[test]
+    fn test_parse_multipv() {
+        let config: Config = toml::from_str("multipv = 3").unwrap();
+        assert_eq!(config.ai_config().multipv, 3);
+        let config: Config = toml::from_str("multipv = 0").unwrap();
+        assert_eq!(config.ai_config().multipv, 1);
+        assert_eq!(Config::default().ai_config().multipv, 1);
+    }
+
+    #[test]
+    fn test_parse_kibitz_engines() {
+        let config: Config = toml::from_str(
+            "[[kibitz]]\npath = '/usr/bin/pikafish'\nprotocol = 'uci'\nmultipv = 3\n\
+             [[kibitz]]\npath = 'eleeye'",
+        )
+        .unwrap();
+        assert_eq!(config.kibitz.len(), 2);
+        assert_eq!(config.kibitz[0].protocol, EngineProtocol::Uci);
+        assert_eq!(config.kibitz[0].multipv, Some(3));
+        assert_eq!(config.kibitz[1].protocol, EngineProtocol::Auto);
+        assert_eq!(config.kibitz[1].multipv, None);
+    }
+
     #[test]
     fn test_parse_config_partial() {
         let toml_content = r#"
             engine_path = "/usr/bin/pikafish"
         "#;
-        let config: EngineConfig = toml::from_str(toml_content).unwrap();
+        let config: Config = toml::from_str(toml_content).unwrap();
         assert_eq!(config.engine_path, Some(PathBuf::from("/usr/bin/pikafish")));
         assert_eq!(config.show_thinking, None);
     }
 
     #[test]
     fn test_get_engine_path() {
-        let config = EngineConfig {
+        let config = Config {
             engine_path: Some(PathBuf::from("/usr/bin/pikafish")),
             show_thinking: Some(true),
+            ..Config::default()
         };
         assert_eq!(
             config.get_engine_path(),
@@ -110,31 +499,218 @@ mod tests {
 
     #[test]
     fn test_get_engine_path_none() {
-        let config = EngineConfig {
+        let config = Config {
             engine_path: None,
             show_thinking: None,
+            ..Config::default()
         };
         assert_eq!(config.get_engine_path(), None);
     }
 
     #[test]
     fn test_get_show_thinking() {
-        let config = EngineConfig {
+        let config = Config {
             engine_path: None,
             show_thinking: Some(true),
+            ..Config::default()
         };
         assert!(config.get_show_thinking());
     }
 
     #[test]
     fn test_get_show_thinking_default() {
-        let config = EngineConfig {
+        let config = Config {
             engine_path: None,
             show_thinking: None,
+            ..Config::default()
         };
         assert!(!config.get_show_thinking());
     }
 
+    #[test]
+    fn test_get_bell() {
+        assert!(!Config::default().get_bell());
+        let config: Config = toml::from_str("bell = true").unwrap();
+        assert!(config.get_bell());
+    }
+
+    #[test]
+    fn test_get_coordinates() {
+        assert!(!Config::default().get_coordinates());
+        let config: Config = toml::from_str("coordinates = true").unwrap();
+        assert!(config.get_coordinates());
+    }
+
+    #[test]
+    fn test_get_opening_book() {
+        assert!(Config::default().ai_config().opening_book);
+        let config: Config = toml::from_str("opening_book = false").unwrap();
+        assert!(!config.get_opening_book());
+        assert!(!config.ai_config().opening_book);
+    }
+
+    #[test]
+    fn test_get_show_pv() {
+        assert!(Config::default().get_show_pv());
+        let config: Config = toml::from_str("show_pv = false").unwrap();
+        assert!(!config.ai_config().show_pv);
+    }
+
+    #[test]
+    fn test_get_move_limit() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.get_move_limit(), Some(DEFAULT_MOVE_LIMIT));
+
+        let config: Config = toml::from_str("move_limit = 50").unwrap();
+        assert_eq!(config.get_move_limit(), Some(50));
+
+        let config: Config = toml::from_str("move_limit = 0").unwrap();
+        assert_eq!(config.get_move_limit(), None);
+    }
+
+    #[test]
+    fn test_parse_stalemate_rule() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.stalemate, StalemateRule::Draw);
+
+        let config: Config = toml::from_str("stalemate = \"loss\"").unwrap();
+        assert_eq!(config.stalemate, StalemateRule::Loss);
+    }
+
+    #[test]
+    fn test_parse_random_settings() {
+        let config: Config = toml::from_str("seed = 1234\nai_variety = 25").unwrap();
+        assert_eq!(config.seed, Some(1234));
+        assert_eq!(config.ai_variety, Some(25));
+    }
+
+    #[test]
+    fn test_parse_key_bindings() {
+        let config: Config = toml::from_str("[keys]\nundo = \"z\"\nquit = \"x\"").unwrap();
+        assert_eq!(
+            config.keys,
+            KeyBindings {
+                undo: 'z',
+                redo: 'y',
+                restart: 'r',
+                quit: 'x',
+            }
+        );
+
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.keys, KeyBindings::DEFAULT);
+
+        assert!(toml::from_str::<Config>("[keys]\nundo = \"zz\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_notation() {
+        let config: Config = toml::from_str("notation = \"wxf\"").unwrap();
+        assert_eq!(config.notation, MoveStyle::Wxf);
+
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.notation, MoveStyle::Chinese);
+    }
+
+    #[test]
+    fn test_parse_engine_protocol() {
+        let config: Config = toml::from_str("engine_protocol = \"uci\"").unwrap();
+        assert_eq!(config.ai_config().engine_protocol, EngineProtocol::Uci);
+
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.engine_protocol, EngineProtocol::Auto);
+    }
+
+    #[test]
+    fn test_parse_ponder() {
+        let config: Config = toml::from_str("ponder = true").unwrap();
+        assert!(config.ai_config().ponder);
+
+        let config: Config = toml::from_str("").unwrap();
+        assert!(!config.ai_config().ponder);
+    }
+
+    #[test]
+    #[cfg(feature = "tui")]
+    fn test_get_theme() {
+        let config: Config = toml::from_str("theme = \"mono\"").unwrap();
+        assert_eq!(config.get_theme(), Theme::MONO);
+
+        let config: Config = toml::from_str("theme = \"unknown\"").unwrap();
+        assert_eq!(config.get_theme(), Theme::CLASSIC);
+    }
+
+    #[test]
+    fn test_library_settings() {
+        let config = Config::default();
+        assert!(!config.get_auto_save());
+
+        let config: Config =
+            toml::from_str("auto_save = true\nlibrary_dir = \"/srv/games\"").unwrap();
+        assert!(config.get_auto_save());
+        assert_eq!(config.get_library_dir(), Some(PathBuf::from("/srv/games")));
+    }
+
+    #[test]
+    #[cfg(feature = "tui")]
+    fn test_get_piece_style() {
+        let config: Config = toml::from_str("pieces = \"letters\"").unwrap();
+        assert_eq!(config.get_piece_style(), PieceStyle::Letters);
+
+        assert!(toml::from_str::<Config>("pieces = \"emoji\"").is_err());
+    }
+
+    #[test]
+    fn test_ai_config() {
+        let config: Config = toml::from_str(
+            r#"
+            engine_path = "/usr/bin/pikafish"
+            search_depth = 2
+            ai_variety = 30
+            ai_blunder_rate = 10
+            seed = 9
+        "#,
+        )
+        .unwrap();
+        let ai = config.ai_config();
+        assert_eq!(ai.engine_path, Some(PathBuf::from("/usr/bin/pikafish")));
+        assert_eq!(ai.search_depth, 2);
+        assert_eq!(ai.variety, 30);
+        assert_eq!(ai.blunder_rate, 10);
+        assert_eq!(ai.seed, Some(9));
+
+        let ai = Config::default().ai_config();
+        assert_eq!(ai.search_depth, AiConfig::default().search_depth);
+        assert_eq!(ai.variety, 0);
+        assert_eq!(ai.blunder_rate, 0);
+    }
+
+    #[test]
+    fn test_get_engine_limit() {
+        let ai = Config::default().ai_config();
+        assert_eq!(ai.engine_limit, SearchLimit::Depth(10));
+        assert_eq!(ai.engine_timeout_ms, crate::game::DEFAULT_ENGINE_TIMEOUT_MS);
+
+        let config: Config = toml::from_str("engine_depth = 6\nengine_nodes = 50000").unwrap();
+        assert_eq!(config.get_engine_limit(), SearchLimit::Nodes(50_000));
+        let config: Config =
+            toml::from_str("engine_movetime = 2000\nengine_depth = 6\nengine_timeout = 5").unwrap();
+        let ai = config.ai_config();
+        assert_eq!(ai.engine_limit, SearchLimit::Time(2000));
+        assert_eq!(ai.engine_timeout_ms, 5000);
+    }
+
+    #[test]
+    fn test_difficulty() {
+        assert_eq!(Config::default().ai_config().difficulty, None);
+        let config: Config = toml::from_str("difficulty = \"beginner\"\nsearch_depth = 5").unwrap();
+        let ai = config.ai_config();
+        assert_eq!(ai.difficulty, Some(Difficulty::Beginner));
+        assert_eq!(ai.effective_search_depth(), 1);
+        assert_eq!(ai.effective_engine_limit(), SearchLimit::Depth(1));
+        assert!(toml::from_str::<Config>("difficulty = \"expert\"").is_err());
+    }
+
     #[test]
     fn test_load_from_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -148,7 +724,12 @@ mod tests {
         "#;
         fs::write(&config_path, toml_content).unwrap();
 
-        // Note: This test documents the structure but can't fully test
-        // due to dirs::config_dir() being a global function
+        let config = Config::load_from(&config_path).unwrap();
+        assert_eq!(config.engine_path, Some(PathBuf::from("/usr/bin/pikafish")));
+        assert!(config.get_show_thinking());
+
+        fs::write(&config_path, "search_depth = \"deep\"").unwrap();
+        assert!(Config::load_from(&config_path).is_none());
+        assert!(Config::load_from(&config_dir.join("missing.toml")).is_none());
     }
 }
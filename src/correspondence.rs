@@ -0,0 +1,467 @@
+//! Correspondence play: one move at a time, by turn files sent by email
+//!
+//! After each move the game is written to a short text file that can be
+//! attached to an email or pasted into one:
+//!
+//! ```text
+//! cn_chess_tui turn file
+//! Game: 5f3a9c0e12ab34cd
+//! Red: alice
+//! Black: bob
+//! Start: rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1
+//! Moves: h7e7 h0g2
+//! Position: rnbakab1r/9/1c4nc1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR w - - 2 2
+//! Checksum: f1decab936d38ed0
+//! ```
+//!
+//! Moves are in ICCS, a dozen to a line so mail programs don't wrap them.
+//! Text around the file, blank lines and `>` quote markers are ignored, so
+//! a file quoted in a reply still reads.
+//!
+//! Reading a file checks that it is whole and consistent: the checksum
+//! catches a file damaged on the way, and the moves are replayed from the
+//! start and must be legal and reach the position given. A reply is then
+//! checked against the file sent ([`TurnFile::reply_to`]): same game, same
+//! players, the moves already played unchanged and exactly one new move.
+//! The checksum only catches accidents; it is the comparison with the file
+//! sent that stops an opponent from rewriting the game.
+
+use crate::fen::FenError;
+use crate::game::Game;
+use crate::notation::iccs::iccs_to_move;
+use crate::rng::{RandomSource, SeededRng};
+use crate::types::{Color, Position};
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// First line of every turn file
+const HEADER: &str = "cn_chess_tui turn file";
+
+/// Moves per `Moves:` line
+const MOVES_PER_LINE: usize = 12;
+
+/// Errors reading a turn file or checking a reply
+#[derive(Debug)]
+pub enum TurnError {
+    Io(std::io::Error),
+    /// The text is not a turn file
+    Parse(String),
+    /// The file was changed or damaged after it was written
+    Checksum,
+    /// The starting position is invalid
+    Fen(FenError),
+    /// A move can't be replayed; `ply` counts from 1
+    IllegalMove {
+        ply: usize,
+        mv: String,
+    },
+    /// The moves don't reach the position the file gives
+    PositionMismatch,
+    /// The reply is for another game
+    WrongGame {
+        expected: String,
+        found: String,
+    },
+    /// A player's name differs from the file sent
+    PlayerChanged(Color),
+    /// The game differs from the file sent from `ply` on (0 for the start
+    /// position)
+    HistoryChanged {
+        ply: usize,
+    },
+    /// The reply adds this many moves instead of one
+    NotOneMove(usize),
+}
+
+impl Display for TurnError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TurnError::Io(e) => write!(f, "{}", e),
+            TurnError::Parse(e) => write!(f, "Invalid turn file: {}", e),
+            TurnError::Checksum => write!(f, "Turn file checksum does not match its contents"),
+            TurnError::Fen(e) => write!(f, "Invalid turn file position: {}", e),
+            TurnError::IllegalMove { ply, mv } => {
+                write!(f, "Turn file move {} ({}) is illegal", ply, mv)
+            }
+            TurnError::PositionMismatch => {
+                write!(f, "Turn file moves do not reach the position it gives")
+            }
+            TurnError::WrongGame { expected, found } => {
+                write!(f, "Turn file is for game {}, not {}", found, expected)
+            }
+            TurnError::PlayerChanged(color) => {
+                write!(f, "Turn file changes the {} player's name", color)
+            }
+            TurnError::HistoryChanged { ply: 0 } => {
+                write!(f, "Turn file changes the starting position")
+            }
+            TurnError::HistoryChanged { ply } => {
+                write!(f, "Turn file changes move {} of the game", ply)
+            }
+            TurnError::NotOneMove(0) => write!(f, "Turn file adds no move"),
+            TurnError::NotOneMove(n) => write!(f, "Turn file adds {} moves instead of one", n),
+        }
+    }
+}
+
+impl std::error::Error for TurnError {}
+
+impl From<std::io::Error> for TurnError {
+    fn from(e: std::io::Error) -> Self {
+        TurnError::Io(e)
+    }
+}
+
+/// A correspondence game as sent after a move
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::correspondence::TurnFile;
+/// use cn_chess_tui::{Game, Position};
+///
+/// // Red opens and sends the file
+/// let mut game = Game::new();
+/// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+/// let sent = TurnFile::new("5f3a9c0e12ab34cd", "alice", "", &game);
+///
+/// // Black reads it, replies and sends it back
+/// let mut reply = TurnFile::parse(&sent.to_text()).unwrap().to_game().unwrap();
+/// reply.make_move(Position::from_xy(7, 0), Position::from_xy(6, 2)).unwrap();
+/// let mut returned = sent.advance(&reply);
+/// returned.black = "bob".to_string();
+///
+/// // Red checks the reply against the file sent
+/// let received = TurnFile::parse(&returned.to_text()).unwrap();
+/// let mv = received.reply_to(&sent).unwrap();
+/// assert_eq!(mv, (Position::from_xy(7, 0), Position::from_xy(6, 2)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnFile {
+    /// Identifies the game, so a reply to another game isn't taken for one
+    pub game: String,
+    /// Player names; empty until the player first moves
+    pub red: String,
+    pub black: String,
+    /// FEN of the position the game started from
+    pub start_fen: String,
+    /// Moves played from the start, in ICCS
+    pub moves: Vec<String>,
+    /// FEN after the last move
+    pub position: String,
+}
+
+impl TurnFile {
+    /// Capture a game under the id `game`
+    pub fn new(game: &str, red: &str, black: &str, played: &Game) -> Self {
+        Self {
+            game: game.to_string(),
+            red: red.to_string(),
+            black: black.to_string(),
+            start_fen: played.at_ply(0).to_fen(),
+            moves: played.get_moves_with_iccs(),
+            position: played.to_fen(),
+        }
+    }
+
+    /// A fresh game id, sixteen hex digits
+    pub fn new_id() -> String {
+        format!("{:016x}", SeededRng::from_entropy().next_u64())
+    }
+
+    /// The same game, with the moves played in `played` since
+    pub fn advance(&self, played: &Game) -> Self {
+        Self::new(&self.game, &self.red, &self.black, played)
+    }
+
+    /// The name of the player of `color`
+    pub fn player(&self, color: Color) -> &str {
+        match color {
+            Color::Red => &self.red,
+            Color::Black => &self.black,
+        }
+    }
+
+    /// Rebuild the game by replaying every move from the start position
+    pub fn to_game(&self) -> Result<Game, TurnError> {
+        let mut game = Game::from_fen(&self.start_fen).map_err(TurnError::Fen)?;
+        for (i, mv) in self.moves.iter().enumerate() {
+            let illegal = || TurnError::IllegalMove {
+                ply: i + 1,
+                mv: mv.clone(),
+            };
+            let (from, to) = iccs_to_move(mv).ok_or_else(illegal)?;
+            game.make_move(from, to).map_err(|_| illegal())?;
+        }
+        Ok(game)
+    }
+
+    /// The file's lines before the checksum
+    fn body(&self) -> String {
+        let mut body = format!(
+            "{}\nGame: {}\nRed: {}\nBlack: {}\nStart: {}\n",
+            HEADER, self.game, self.red, self.black, self.start_fen
+        );
+        for line in self.moves.chunks(MOVES_PER_LINE) {
+            body.push_str(&format!("Moves: {}\n", line.join(" ")));
+        }
+        body.push_str(&format!("Position: {}\n", self.position));
+        body
+    }
+
+    /// The file's text, checksum included
+    pub fn to_text(&self) -> String {
+        let body = self.body();
+        format!("{}Checksum: {:016x}\n", body, checksum(&body))
+    }
+
+    /// Read a turn file, checking its checksum and replaying its moves
+    pub fn parse(text: &str) -> Result<Self, TurnError> {
+        let mut lines = text
+            .lines()
+            .map(|line| line.trim_start_matches(['>', ' ', '\t']).trim_end())
+            .filter(|line| !line.is_empty())
+            .skip_while(|line| *line != HEADER)
+            .skip(1);
+
+        let mut fields: [Option<String>; 5] = Default::default();
+        let mut moves = Vec::new();
+        let found = loop {
+            let Some(line) = lines.next() else {
+                return Err(TurnError::Parse("no checksum".to_string()));
+            };
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| TurnError::Parse(format!("unexpected line: {}", line)))?;
+            let value = value.trim().to_string();
+            let slot = match key {
+                "Checksum" => break value,
+                "Moves" => {
+                    moves.extend(value.split_whitespace().map(String::from));
+                    continue;
+                }
+                "Game" => 0,
+                "Red" => 1,
+                "Black" => 2,
+                "Start" => 3,
+                "Position" => 4,
+                _ => return Err(TurnError::Parse(format!("unknown field {}", key))),
+            };
+            if fields[slot].replace(value).is_some() {
+                return Err(TurnError::Parse(format!("{} given twice", key)));
+            }
+        };
+        let [game, red, black, start_fen, position] = fields;
+        let missing = |key: &str| TurnError::Parse(format!("no {}", key));
+        let file = Self {
+            game: game.ok_or_else(|| missing("Game"))?,
+            red: red.ok_or_else(|| missing("Red"))?,
+            black: black.ok_or_else(|| missing("Black"))?,
+            start_fen: start_fen.ok_or_else(|| missing("Start"))?,
+            moves,
+            position: position.ok_or_else(|| missing("Position"))?,
+        };
+
+        if u64::from_str_radix(&found, 16).ok() != Some(checksum(&file.body())) {
+            return Err(TurnError::Checksum);
+        }
+        if file.to_game()?.to_fen() != file.position {
+            return Err(TurnError::PositionMismatch);
+        }
+        Ok(file)
+    }
+
+    /// Check this file as the reply to `sent` and return the move it adds
+    ///
+    /// A player's name may be filled in, but not changed.
+    pub fn reply_to(&self, sent: &TurnFile) -> Result<(Position, Position), TurnError> {
+        if self.game != sent.game {
+            return Err(TurnError::WrongGame {
+                expected: sent.game.clone(),
+                found: self.game.clone(),
+            });
+        }
+        for color in [Color::Red, Color::Black] {
+            let before = sent.player(color);
+            if !before.is_empty() && self.player(color) != before {
+                return Err(TurnError::PlayerChanged(color));
+            }
+        }
+        if self.start_fen != sent.start_fen {
+            return Err(TurnError::HistoryChanged { ply: 0 });
+        }
+        if let Some(ply) = sent
+            .moves
+            .iter()
+            .enumerate()
+            .position(|(i, mv)| self.moves.get(i) != Some(mv))
+        {
+            return Err(TurnError::HistoryChanged { ply: ply + 1 });
+        }
+        match &self.moves[sent.moves.len()..] {
+            [mv] => iccs_to_move(mv).ok_or_else(|| TurnError::IllegalMove {
+                ply: self.moves.len(),
+                mv: mv.clone(),
+            }),
+            added => Err(TurnError::NotOneMove(added.len())),
+        }
+    }
+
+    /// Load a turn file, checking it as [`parse`](Self::parse) does
+    pub fn load(path: &Path) -> Result<Self, TurnError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Write the turn file, creating parent directories
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_text())
+    }
+
+    /// Where the copy of the file last sent in this game is kept under
+    /// `dir` (see [`Paths::turns_dir`](crate::paths::Paths::turns_dir))
+    pub fn sent_copy(&self, dir: &Path) -> PathBuf {
+        // Ids come from other people's files; keep them to a file name
+        let name: String = self
+            .game
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+        dir.join(format!("{}.turn", name))
+    }
+}
+
+/// FNV-1a over the text
+fn checksum(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |acc, byte| {
+        (acc ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for mv in moves {
+            let (from, to) = iccs_to_move(mv).unwrap();
+            game.make_move(from, to).unwrap();
+        }
+    }
+
+    fn sent() -> TurnFile {
+        let mut game = Game::new();
+        play(&mut game, &["h7e7", "h0g2", "b9c7"]);
+        TurnFile::new("00ff", "alice", "bob", &game)
+    }
+
+    #[test]
+    fn test_round_trip_through_file() {
+        let mut game = Game::new();
+        let moves = [
+            "h7e7", "h0g2", "h9g7", "i0h0", "i9h9", "b0c2", "b9c7", "a0b0", "a9b9", "h2i2", "b7b3",
+            "c3c4", "g6g5",
+        ];
+        play(&mut game, &moves);
+        let file = TurnFile::new("00ff", "alice", "", &game);
+        let text = file.to_text();
+        // The thirteenth move goes on a second line
+        assert_eq!(text.lines().filter(|l| l.starts_with("Moves:")).count(), 2);
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("game.turn");
+        file.save(&path).unwrap();
+        let loaded = TurnFile::load(&path).unwrap();
+        assert_eq!(loaded, file);
+        assert_eq!(loaded.to_game().unwrap().to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn test_reads_file_quoted_in_an_email() {
+        let file = sent();
+        let quoted: String = file
+            .to_text()
+            .lines()
+            .map(|line| format!("> {}\n", line))
+            .collect();
+        let email = format!(
+            "Your move!\n\nOn Monday alice wrote:\n{}\n-- \nbob\n",
+            quoted
+        );
+        assert_eq!(TurnFile::parse(&email).unwrap(), file);
+    }
+
+    #[test]
+    fn test_rejects_damaged_and_inconsistent_files() {
+        let text = sent().to_text();
+        assert!(matches!(TurnFile::parse("hello"), Err(TurnError::Parse(_))));
+        assert!(matches!(
+            TurnFile::parse(&text.replace("bob", "eve")),
+            Err(TurnError::Checksum)
+        ));
+        assert!(matches!(
+            TurnFile::parse(&text.replace("Red: alice\n", "")),
+            Err(TurnError::Parse(_))
+        ));
+
+        // Edited with the checksum recomputed: the moves must still replay
+        // to the position given
+        let mut edited = sent();
+        edited.moves[2] = "b9a7".to_string();
+        assert!(matches!(
+            TurnFile::parse(&edited.to_text()),
+            Err(TurnError::PositionMismatch)
+        ));
+        edited.moves[2] = "b9b1".to_string();
+        assert!(matches!(
+            TurnFile::parse(&edited.to_text()),
+            Err(TurnError::IllegalMove { ply: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_reply_must_continue_the_game_sent() {
+        let sent = sent();
+        let mut game = sent.to_game().unwrap();
+        play(&mut game, &["b0c2"]);
+        let reply = sent.advance(&game);
+        assert_eq!(
+            reply.reply_to(&sent).unwrap(),
+            iccs_to_move("b0c2").unwrap()
+        );
+
+        assert!(matches!(
+            sent.reply_to(&sent),
+            Err(TurnError::NotOneMove(0))
+        ));
+        let mut two = game.clone();
+        play(&mut two, &["a9a8"]);
+        assert!(matches!(
+            sent.advance(&two).reply_to(&sent),
+            Err(TurnError::NotOneMove(2))
+        ));
+
+        let mut other = reply.clone();
+        other.game = "beef".to_string();
+        assert!(matches!(
+            other.reply_to(&sent),
+            Err(TurnError::WrongGame { .. })
+        ));
+        let mut renamed = reply.clone();
+        renamed.red = "mallory".to_string();
+        assert!(matches!(
+            renamed.reply_to(&sent),
+            Err(TurnError::PlayerChanged(Color::Red))
+        ));
+
+        // Taking back Red's last move and playing another
+        let mut rewritten = Game::new();
+        play(&mut rewritten, &["h7e7", "h0g2", "h9g7", "b0c2"]);
+        assert!(matches!(
+            sent.advance(&rewritten).reply_to(&sent),
+            Err(TurnError::HistoryChanged { ply: 3 })
+        ));
+    }
+}
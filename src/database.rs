@@ -0,0 +1,473 @@
+//! Searching PGN collections for positions and duplicate games
+//!
+//! [`search`] replays every game in a PGN file, or in every `.pgn` file of
+//! a directory, and lists the games that reach a position. A
+//! [`PositionQuery`] is either an exact position, found by comparing the
+//! Zobrist hash of each position in a game (so transpositions count), or a
+//! material signature such as `RCPPrcp`, matched by the pieces left on the
+//! board whatever their squares.
+//!
+//! Games are streamed with [`ArchiveReader`], so collections of any size
+//! can be searched. Games that can't be read or replayed are counted and
+//! skipped.
+//!
+//! [`find_duplicates`] goes through a collection the same way, telling
+//! games apart by their [`Fingerprint`], and [`remove_duplicates`] rewrites
+//! the files without the copies it found.
+
+use crate::archive::ArchiveReader;
+use crate::board::Board;
+use crate::fen::{fen_to_board, parse_piece, FenError};
+use crate::pgn::PgnGame;
+use crate::pgn_import::{import_pgn, ImportMode};
+use crate::types::{Color, Piece, PieceType};
+use crate::zobrist;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Piece counts for both sides, generals left out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Material([u8; 12]);
+
+impl Material {
+    /// Count the pieces on a board
+    pub fn of(board: &Board) -> Self {
+        let mut material = Self::default();
+        for (_, piece) in board.pieces() {
+            material.add(piece);
+        }
+        material
+    }
+
+    /// Parse a signature of FEN piece letters, uppercase for Red
+    ///
+    /// Each letter is one piece; generals (`K`, `k`) may be given or left
+    /// out, and the order does not matter.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::database::Material;
+    ///
+    /// let rook_ending = Material::parse("KRPkrp").unwrap();
+    /// assert_eq!(Material::parse("prRP").unwrap(), rook_ending);
+    /// assert!(Material::parse("RX").is_err());
+    /// ```
+    pub fn parse(signature: &str) -> Result<Self, FenError> {
+        let mut material = Self::default();
+        for c in signature.chars() {
+            material.add(parse_piece(c).ok_or(FenError::InvalidPiece(c))?);
+        }
+        Ok(material)
+    }
+
+    fn add(&mut self, piece: Piece) {
+        if let Some(slot) = Self::slot(piece) {
+            self.0[slot] = self.0[slot].saturating_add(1);
+        }
+    }
+
+    fn slot(piece: Piece) -> Option<usize> {
+        let kind = match piece.piece_type {
+            PieceType::General => return None,
+            PieceType::Advisor => 0,
+            PieceType::Elephant => 1,
+            PieceType::Horse => 2,
+            PieceType::Chariot => 3,
+            PieceType::Cannon => 4,
+            PieceType::Soldier => 5,
+        };
+        Some(match piece.color {
+            Color::Red => kind,
+            Color::Black => kind + 6,
+        })
+    }
+}
+
+/// What [`search`] looks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionQuery {
+    /// This position with this side to move, by Zobrist hash
+    Position(u64),
+    /// Exactly this material, on any squares
+    Material(Material),
+}
+
+impl PositionQuery {
+    /// Read a FEN as a position, or anything else as a material signature
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::database::PositionQuery;
+    ///
+    /// let start = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+    /// assert!(matches!(PositionQuery::parse(start), Ok(PositionQuery::Position(_))));
+    /// assert!(matches!(PositionQuery::parse("RCrc"), Ok(PositionQuery::Material(_))));
+    /// ```
+    pub fn parse(query: &str) -> Result<Self, FenError> {
+        if query.contains('/') {
+            let (board, turn) = fen_to_board(query)?;
+            Ok(PositionQuery::Position(zobrist::hash(&board, turn)))
+        } else {
+            Material::parse(query).map(PositionQuery::Material)
+        }
+    }
+
+    /// The first ply at which a game matches, counting the start as 0
+    ///
+    /// Moves the game can't replay end the search at the last good one.
+    pub fn first_match(&self, pgn: &PgnGame) -> Result<Option<usize>, FenError> {
+        let game = import_pgn(pgn, ImportMode::Strict)?.game;
+        match self {
+            PositionQuery::Position(hash) => {
+                Ok(game.position_hash_history().iter().position(|h| h == hash))
+            }
+            PositionQuery::Material(material) => {
+                let mut board = game.at_ply(0).board().clone();
+                if Material::of(&board) == *material {
+                    return Ok(Some(0));
+                }
+                for (i, mv) in game.get_moves().into_iter().enumerate() {
+                    board.move_piece(mv.from, mv.to);
+                    if Material::of(&board) == *material {
+                        return Ok(Some(i + 1));
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// A game that reaches the searched position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionMatch {
+    pub path: PathBuf,
+    /// Index of the game in its file, from 0
+    pub index: usize,
+    /// Moves played before the position is first reached
+    pub ply: usize,
+    pub game: PgnGame,
+}
+
+/// Results of a [`search`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchResults {
+    pub matches: Vec<PositionMatch>,
+    /// Games read and replayed
+    pub searched: usize,
+    /// Games that could not be read or replayed
+    pub skipped: usize,
+}
+
+/// A PGN file, or every `.pgn` file directly in a directory in name order
+fn pgn_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    Ok(if path.is_dir() {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| {
+                file.is_file()
+                    && file
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("pgn"))
+            })
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    })
+}
+
+/// Search a PGN file, or every `.pgn` file directly in a directory, for
+/// games reaching `query`
+///
+/// Files are searched in name order and games in file order. Only a path
+/// or file that can't be opened is an error.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::database::{search, PositionQuery};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// std::fs::write(dir.path().join("a.pgn"), "1. h7e7 h0g2 2. b9c7 *").unwrap();
+/// std::fs::write(dir.path().join("b.pgn"), "1. b9c7 h0g2 2. h7e7 *").unwrap();
+///
+/// // Both move orders reach the same position
+/// let fen = "rnbakab1r/9/1c4nc1/p1p1p1p1p/9/9/P1P1P1P1P/1CN1C4/9/R1BAKABNR b - - 3 2";
+/// let results = search(dir.path(), &PositionQuery::parse(fen).unwrap()).unwrap();
+/// assert_eq!(results.searched, 2);
+/// assert_eq!(results.matches.len(), 2);
+/// assert_eq!(results.matches[0].ply, 3);
+/// ```
+pub fn search(path: &Path, query: &PositionQuery) -> io::Result<SearchResults> {
+    let mut results = SearchResults::default();
+    for file in pgn_files(path)? {
+        for (index, game) in ArchiveReader::open(&file)?.iter()?.enumerate() {
+            let Ok(game) = game else {
+                results.skipped += 1;
+                continue;
+            };
+            match query.first_match(&game) {
+                Ok(found) => {
+                    results.searched += 1;
+                    if let Some(ply) = found {
+                        results.matches.push(PositionMatch {
+                            path: file.clone(),
+                            index,
+                            ply,
+                            game,
+                        });
+                    }
+                }
+                Err(_) => results.skipped += 1,
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// What makes two games the same: the players and the moves
+///
+/// The moves are compared by a hash of the Zobrist hashes of every
+/// position in the game, so the same moves from the same start match
+/// however they are written (ICCS, WXF or Chinese). Player names are
+/// compared after trimming; a missing tag counts as an empty name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    pub red: String,
+    pub black: String,
+    pub moves: u64,
+}
+
+impl Fingerprint {
+    /// Replay a game and fingerprint it; None unless every move can be
+    /// played as written
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::database::Fingerprint;
+    /// use cn_chess_tui::pgn::PgnGame;
+    ///
+    /// let iccs = PgnGame::parse("[Red \"Hu\"]\n\n1. h7e7 h0g2 *").unwrap();
+    /// let chinese = PgnGame::parse("[Red \" Hu \"]\n\n1. 炮二平五 马8进7 1-0").unwrap();
+    /// let other = PgnGame::parse("[Red \"Xu\"]\n\n1. h7e7 h0g2 *").unwrap();
+    /// assert_eq!(Fingerprint::of(&iccs), Fingerprint::of(&chinese));
+    /// assert!(Fingerprint::of(&iccs).is_some());
+    /// assert_ne!(Fingerprint::of(&iccs), Fingerprint::of(&other));
+    /// ```
+    pub fn of(pgn: &PgnGame) -> Option<Self> {
+        let report = import_pgn(pgn, ImportMode::Strict).ok()?;
+        if !report.is_clean() {
+            return None;
+        }
+        let game = report.game;
+        // FNV-1a over the position hashes, so the order of moves counts
+        let moves = game
+            .position_hash_history()
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |acc, &hash| {
+                (acc ^ hash).wrapping_mul(0x0100_0000_01b3)
+            });
+        let player = |key: &str| {
+            pgn.get_tag(key)
+                .map(|name| name.trim().to_string())
+                .unwrap_or_default()
+        };
+        Some(Self {
+            red: player("Red"),
+            black: player("Black"),
+            moves,
+        })
+    }
+}
+
+/// A game in a collection
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameLocation {
+    pub path: PathBuf,
+    /// Index of the game in its file, from 0
+    pub index: usize,
+}
+
+/// A game found again later in the collection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    pub location: GameLocation,
+    /// The first copy, which is kept
+    pub original: GameLocation,
+    pub fingerprint: Fingerprint,
+}
+
+/// Results of [`find_duplicates`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DuplicateResults {
+    pub duplicates: Vec<Duplicate>,
+    /// Games read and replayed
+    pub checked: usize,
+    /// Games that could not be read or replayed, which are never duplicates
+    pub skipped: usize,
+}
+
+/// Find the games of a PGN file, or of every `.pgn` file directly in a
+/// directory, that repeat an earlier game
+///
+/// Files are read in name order and games in file order; the first copy of
+/// a game is the original and every later one a duplicate. Only the
+/// fingerprints are kept in memory.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::database::find_duplicates;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// std::fs::write(dir.path().join("a.pgn"), "1. h7e7 h0g2 *").unwrap();
+/// std::fs::write(
+///     dir.path().join("b.pgn"),
+///     "[Event \"1\"]\n\n1. b9c7 *\n\n[Event \"2\"]\n\n1. C2.5 h8+7 *",
+/// )
+/// .unwrap();
+///
+/// let results = find_duplicates(dir.path()).unwrap();
+/// assert_eq!(results.checked, 3);
+/// assert_eq!(results.duplicates.len(), 1);
+/// assert_eq!(results.duplicates[0].location.index, 1);
+/// assert!(results.duplicates[0].original.path.ends_with("a.pgn"));
+/// ```
+pub fn find_duplicates(path: &Path) -> io::Result<DuplicateResults> {
+    let mut seen: HashMap<Fingerprint, GameLocation> = HashMap::new();
+    let mut results = DuplicateResults::default();
+    for file in pgn_files(path)? {
+        for (index, game) in ArchiveReader::open(&file)?.iter()?.enumerate() {
+            let Some(fingerprint) = game.ok().and_then(|game| Fingerprint::of(&game)) else {
+                results.skipped += 1;
+                continue;
+            };
+            results.checked += 1;
+            let location = GameLocation {
+                path: file.clone(),
+                index,
+            };
+            match seen.get(&fingerprint) {
+                Some(original) => results.duplicates.push(Duplicate {
+                    location,
+                    original: original.clone(),
+                    fingerprint,
+                }),
+                None => {
+                    seen.insert(fingerprint, location);
+                }
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// Rewrite the files holding the duplicates in `results` without them,
+/// returning the number of games removed
+///
+/// Each file is written to a temporary file beside it, then moved over the
+/// original, so a failure leaves it as it was. The games kept are written
+/// back from their parsed form: tags, moves, comments and variations
+/// survive, but not the original layout. A file with a game that can't be
+/// read is an error, since that game could not be written back.
+pub fn remove_duplicates(results: &DuplicateResults) -> io::Result<usize> {
+    let mut by_file: HashMap<&Path, HashSet<usize>> = HashMap::new();
+    for duplicate in &results.duplicates {
+        by_file
+            .entry(&duplicate.location.path)
+            .or_default()
+            .insert(duplicate.location.index);
+    }
+
+    let mut removed = 0;
+    for (path, drop) in by_file {
+        let temp = path.with_extension("pgn.tmp");
+        let written = (|| {
+            let mut out = BufWriter::new(File::create(&temp)?);
+            let mut first = true;
+            for (index, game) in ArchiveReader::open(path)?.iter()?.enumerate() {
+                let game = game?;
+                if drop.contains(&index) {
+                    continue;
+                }
+                if !first {
+                    writeln!(out)?;
+                }
+                game.write_pgn(&mut out)?;
+                first = false;
+            }
+            out.flush()
+        })();
+        if let Err(e) = written.and_then(|()| std::fs::rename(&temp, path)) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+        removed += drop.len();
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material_search_finds_first_ply() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("games.pgn");
+        // The cannon takes a horse on move 1 and is taken back on move 2
+        std::fs::write(
+            &path,
+            "[Event \"1\"]\n\n1. h7h0 i0h0 *\n\n[Event \"2\"]\n\n1. h7e7 *\n\n[Event \"3\"]\n\n1. x9x9 *\n",
+        )
+        .unwrap();
+
+        let start = Material::of(&Board::new());
+        let mut one_horse_down = start;
+        one_horse_down.0[Material::slot(Piece::black(PieceType::Horse)).unwrap()] -= 1;
+
+        let query = PositionQuery::Material(one_horse_down);
+        let results = search(&path, &query).unwrap();
+        assert_eq!(results.searched, 3);
+        assert_eq!(results.matches.len(), 1);
+        assert_eq!((results.matches[0].index, results.matches[0].ply), (0, 1));
+
+        let results = search(&path, &PositionQuery::Material(start)).unwrap();
+        assert_eq!(results.matches.len(), 3);
+        assert!(results.matches.iter().all(|m| m.ply == 0));
+    }
+
+    #[test]
+    fn test_remove_duplicates_keeps_first_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("games.pgn");
+        std::fs::write(
+            &path,
+            "[Event \"A\"]\n[Red \"Hu\"]\n\n1. h7e7 *\n\n\
+             [Event \"B\"]\n[Red \"Xu\"]\n\n1. h7e7 *\n\n\
+             [Event \"C\"]\n[Red \"Hu\"]\n\n1. C2.5 1-0\n\n\
+             [Event \"D\"]\n\n1. x9x9 *\n",
+        )
+        .unwrap();
+
+        let results = find_duplicates(&path).unwrap();
+        assert_eq!((results.checked, results.skipped), (3, 1));
+        assert_eq!(results.duplicates.len(), 1);
+        assert_eq!(results.duplicates[0].location.index, 2);
+        assert_eq!(results.duplicates[0].original.index, 0);
+        assert_eq!(results.duplicates[0].fingerprint.red, "Hu");
+
+        assert_eq!(remove_duplicates(&results).unwrap(), 1);
+        let events: Vec<String> = ArchiveReader::open(&path)
+            .unwrap()
+            .iter()
+            .unwrap()
+            .map(|game| game.unwrap().get_tag("Event").unwrap().clone())
+            .collect();
+        assert_eq!(events, ["A", "B", "D"]);
+        assert!(find_duplicates(&path).unwrap().duplicates.is_empty());
+        assert!(!path.with_extension("pgn.tmp").exists());
+    }
+}
@@ -0,0 +1,193 @@
+//! Positions and moves described in words
+//!
+//! Screen readers can't make sense of the drawn board, so the accessible
+//! mode keeps a [`status_line`] under it saying what just happened and what
+//! is under the cursor, and [`position`] writes the whole position out as
+//! plain text. Squares are named in ICCS coordinates, as the board labels
+//! them.
+
+use crate::game::{Game, GameState};
+use crate::notation::iccs::position_to_iccs;
+use crate::tutor::piece_rules;
+use crate::types::{Color, Piece, PieceType, Position};
+
+/// Kinds of pieces in the order [`position`] lists them
+const PIECE_ORDER: [PieceType; 7] = [
+    PieceType::General,
+    PieceType::Advisor,
+    PieceType::Elephant,
+    PieceType::Horse,
+    PieceType::Chariot,
+    PieceType::Cannon,
+    PieceType::Soldier,
+];
+
+/// Lowercase English name of a kind of piece
+fn type_name(piece_type: PieceType) -> String {
+    piece_rules(piece_type).name.to_lowercase()
+}
+
+/// A piece's color and name, e.g. "Red cannon"
+pub fn piece_name(piece: Piece) -> String {
+    format!("{} {}", piece.color, type_name(piece.piece_type))
+}
+
+/// A square and what stands on it
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::describe::square;
+/// use cn_chess_tui::{Game, Position};
+///
+/// let game = Game::new();
+/// assert_eq!(square(&game, Position::from_xy(7, 7)), "h7, Red cannon");
+/// assert_eq!(square(&game, Position::from_xy(4, 4)), "e4, empty");
+/// ```
+pub fn square(game: &Game, pos: Position) -> String {
+    match game.board().get(pos) {
+        Some(piece) => format!("{}, {}", position_to_iccs(pos), piece_name(*piece)),
+        None => format!("{}, empty", position_to_iccs(pos)),
+    }
+}
+
+/// The last move played, with what it took and whether it gave check
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::describe::last_move;
+/// use cn_chess_tui::{Game, Position};
+///
+/// let mut game = Game::new();
+/// assert_eq!(last_move(&game), None);
+/// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+/// assert_eq!(last_move(&game).unwrap(), "Red cannon h7 to e7");
+/// game.make_move(Position::from_xy(7, 2), Position::from_xy(4, 2)).unwrap();
+/// game.make_move(Position::from_xy(4, 7), Position::from_xy(4, 3)).unwrap();
+/// assert_eq!(
+///     last_move(&game).unwrap(),
+///     "Red cannon e7 to e3, takes Black soldier, check"
+/// );
+/// ```
+pub fn last_move(game: &Game) -> Option<String> {
+    let mv = game.last_move()?;
+    let before = game.at_ply(game.get_moves().len() - 1);
+    let piece = before.board().get(mv.from)?;
+    let mut text = format!(
+        "{} {} to {}",
+        piece_name(*piece),
+        position_to_iccs(mv.from),
+        position_to_iccs(mv.to)
+    );
+    if let Some(captured) = before.board().get(mv.to) {
+        text.push_str(&format!(", takes {}", piece_name(*captured)));
+    }
+    if game.is_in_check() {
+        text.push_str(", check");
+    }
+    Some(text)
+}
+
+/// Whose move it is and whether they are in check, or how the game ended
+pub fn game_state(game: &Game) -> String {
+    match game.state() {
+        GameState::Playing if game.is_in_check() => {
+            format!("{} to move, in check", game.turn())
+        }
+        GameState::Playing => format!("{} to move", game.turn()),
+        state => state.to_string(),
+    }
+}
+
+/// One line for the accessible mode's status bar: the last move, the
+/// state of the game, the cursor square and the selected piece
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::describe::status_line;
+/// use cn_chess_tui::{Game, Position};
+///
+/// let game = Game::new();
+/// let line = status_line(&game, Position::from_xy(4, 3), Some(Position::from_xy(7, 7)));
+/// assert_eq!(
+///     line,
+///     "No moves yet. Red to move. Cursor on e3, Black soldier. Selected h7, Red cannon"
+/// );
+/// ```
+pub fn status_line(game: &Game, cursor: Position, selected: Option<Position>) -> String {
+    let last = match last_move(game) {
+        Some(mv) => format!("Last move: {}", mv),
+        None => "No moves yet".to_string(),
+    };
+    let mut line = format!(
+        "{}. {}. Cursor on {}",
+        last,
+        game_state(game),
+        square(game, cursor)
+    );
+    if let Some(selected) = selected {
+        line.push_str(&format!(". Selected {}", square(game, selected)));
+    }
+    line
+}
+
+/// The whole position as plain text: the state of the game, the last move,
+/// each side's pieces by kind and the FEN
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::describe::position;
+/// use cn_chess_tui::Game;
+///
+/// let text = position(&Game::new());
+/// assert!(text.starts_with("Red to move\n"));
+/// assert!(text.contains("Red: general e9; advisors d9, f9;"));
+/// assert!(text.contains("Black: general e0;"));
+/// assert!(text.ends_with("RNBAKABNR w - - 0 1\n"));
+/// ```
+pub fn position(game: &Game) -> String {
+    let mut text = format!("{}\n", game_state(game));
+    if let Some(mv) = last_move(game) {
+        text.push_str(&format!("Last move: {}\n", mv));
+    }
+    for color in [Color::Red, Color::Black] {
+        let groups: Vec<String> = PIECE_ORDER
+            .into_iter()
+            .filter_map(|piece_type| {
+                let squares: Vec<String> = game
+                    .board()
+                    .pieces_of_color(color)
+                    .filter(|(_, piece)| piece.piece_type == piece_type)
+                    .map(|(pos, _)| position_to_iccs(pos))
+                    .collect();
+                let plural = if squares.len() > 1 { "s" } else { "" };
+                (!squares.is_empty())
+                    .then(|| format!("{}{} {}", type_name(piece_type), plural, squares.join(", ")))
+            })
+            .collect();
+        text.push_str(&format!("{}: {}\n", color, groups.join("; ")));
+    }
+    text.push_str(&format!("FEN: {}\n", game.to_fen()));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_over_is_described() {
+        // Black's general is mated by the two chariots
+        let mut game = Game::from_fen("3k5/R8/9/9/9/9/9/9/9/4K3R w - - 0 1").unwrap();
+        game.make_move(Position::from_xy(8, 9), Position::from_xy(8, 0))
+            .unwrap();
+        assert_eq!(game_state(&game), "Checkmate - Red Wins");
+        assert_eq!(last_move(&game).unwrap(), "Red chariot i9 to i0, check");
+        let text = position(&game);
+        assert!(text.contains("Black: general d0\n"), "{}", text);
+        assert!(
+            text.contains("Red: general e9; chariots i0, a1\n"),
+            "{}",
+            text
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Board diagrams for annotating games
+//!
+//! Writing up a game means drawing a diagram for every position worth
+//! discussing. [`key_moments`] finds them in an annotated PGN game: the
+//! position after each commented move, and after each move where the
+//! evaluation recorded in the comments swings by at least a threshold.
+//! Evaluations use the `[%eval 1.25]` comment tag, in pawns from Red's point
+//! of view (`[%eval #3]` for a mate). Each moment renders as SVG or as plain
+//! text.
+
+use crate::board::Board;
+use crate::fen::{piece_to_fen, FenError};
+use crate::game::{Game, Move};
+use crate::notation::chinese::{move_to_chinese_with_style, NumeralStyle};
+use crate::notation::iccs::move_to_iccs;
+use crate::pgn::PgnGame;
+use crate::pgn_import::{import_pgn, ImportMode};
+use crate::render::svg::board_svg;
+use crate::types::{Color, Position};
+use std::str::FromStr;
+
+/// Centipawn score given to a mate in an `[%eval]` tag
+pub const MATE_SCORE: i32 = 10_000;
+
+/// Output format of a diagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Svg,
+    Text,
+}
+
+impl DiagramFormat {
+    /// File extension for diagrams in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            DiagramFormat::Svg => "svg",
+            DiagramFormat::Text => "txt",
+        }
+    }
+}
+
+impl FromStr for DiagramFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "svg" => Ok(DiagramFormat::Svg),
+            "text" | "txt" => Ok(DiagramFormat::Text),
+            _ => Err(format!("unknown diagram format '{}'", s)),
+        }
+    }
+}
+
+/// A position worth a diagram
+#[derive(Debug, Clone)]
+pub struct KeyMoment {
+    /// Moves played to reach the position, counting from 1
+    pub ply: usize,
+    /// Move number of the move just played
+    pub move_number: usize,
+    /// Side that played the move
+    pub side: Color,
+    /// The move in Chinese notation
+    pub notation: String,
+    pub iccs: String,
+    /// The move's comment with any `[%eval]` tag removed
+    pub comment: Option<String>,
+    /// Evaluation after the move, in centipawns from Red's point of view
+    pub eval: Option<i32>,
+    /// Change from the previous recorded evaluation
+    pub swing: Option<i32>,
+    /// The game up to and including the move
+    pub game: Game,
+}
+
+impl KeyMoment {
+    /// File name for the diagram without extension, e.g. `012b` for
+    /// Black's 12th move
+    pub fn file_stem(&self) -> String {
+        let side = match self.side {
+            Color::Red => 'r',
+            Color::Black => 'b',
+        };
+        format!("{:03}{}", self.move_number, side)
+    }
+
+    /// Short title, e.g. `12. 炮二平五 (+1.25)`
+    pub fn caption(&self) -> String {
+        let dots = match self.side {
+            Color::Red => ".",
+            Color::Black => "...",
+        };
+        let mut caption = format!("{}{} {}", self.move_number, dots, self.notation);
+        if let Some(eval) = self.eval {
+            caption.push_str(&format!(" ({})", format_eval(eval)));
+        }
+        caption
+    }
+
+    /// The move just played
+    pub fn last_move(&self) -> Option<Move> {
+        self.game.get_moves().last().copied()
+    }
+
+    /// Draw the position in a format
+    pub fn render(&self, format: DiagramFormat) -> String {
+        let caption = self.caption();
+        match format {
+            DiagramFormat::Svg => svg_diagram(self.game.board(), self.last_move(), &caption),
+            DiagramFormat::Text => text_diagram(self.game.board(), self.last_move(), &caption),
+        }
+    }
+}
+
+/// Format a centipawn evaluation in pawns, e.g. `+1.25` or `#-3`
+pub fn format_eval(eval: i32) -> String {
+    if eval.abs() > MATE_SCORE - 1000 {
+        let moves = MATE_SCORE - eval.abs();
+        return format!("#{}", if eval > 0 { moves } else { -moves });
+    }
+    format!("{:+.2}", eval as f64 / 100.0)
+}
+
+/// Read the `[%eval ...]` tag of a comment, in centipawns
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::diagram::{parse_eval, MATE_SCORE};
+///
+/// assert_eq!(parse_eval("Good move [%eval -0.75]"), Some(-75));
+/// assert_eq!(parse_eval("[%eval #2]"), Some(MATE_SCORE - 2));
+/// assert_eq!(parse_eval("no evaluation"), None);
+/// ```
+pub fn parse_eval(comment: &str) -> Option<i32> {
+    let (_, rest) = comment.split_once("[%eval")?;
+    let (value, _) = rest.split_once(']')?;
+    let value = value.trim();
+    if let Some(mate) = value.strip_prefix('#') {
+        let moves: i32 = mate.parse().ok()?;
+        return Some(if moves < 0 {
+            -MATE_SCORE - moves
+        } else {
+            MATE_SCORE - moves
+        });
+    }
+    let pawns: f64 = value.parse().ok()?;
+    Some((pawns * 100.0).round() as i32)
+}
+
+/// A comment with `[%...]` command tags removed
+pub(crate) fn comment_text(comment: &str) -> String {
+    let mut text = String::new();
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%") {
+        text.push_str(&rest[..start]);
+        rest = match rest[start..].find(']') {
+            Some(end) => &rest[start + end + 1..],
+            None => "",
+        };
+    }
+    text.push_str(rest);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Find the positions of a game worth a diagram
+///
+/// A move is a key moment if its comment has any text besides an
+/// `[%eval]` tag, or if its evaluation differs from the previous recorded
+/// one by at least `threshold` centipawns. Moves are read as ICCS or Chinese
+/// notation; the search stops at the first move that can't be played.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::diagram::key_moments;
+/// use cn_chess_tui::pgn::PgnGame;
+///
+/// let pgn = PgnGame::parse(
+///     "1. h7e7 {[%eval 0.2]} h0g2 {[%eval 0.3]} 2. b9c7 {Sharp! [%eval 1.8]} b0c2 {[%eval 1.7]}",
+/// )
+/// .unwrap();
+/// let moments = key_moments(&pgn, 100).unwrap();
+/// assert_eq!(moments.len(), 1);
+/// assert_eq!(moments[0].file_stem(), "002r");
+/// assert_eq!(moments[0].comment.as_deref(), Some("Sharp!"));
+/// assert_eq!(moments[0].swing, Some(150));
+/// ```
+pub fn key_moments(pgn: &PgnGame, threshold: i32) -> Result<Vec<KeyMoment>, FenError> {
+    let report = import_pgn(pgn, ImportMode::Strict)?;
+    let game = report.game;
+    let moves = game.get_moves();
+    let start = game.at_ply(0);
+    // Move numbers count from 1 with Red; a game starting with Black to
+    // move begins halfway through move 1
+    let offset = usize::from(start.turn() == Color::Black);
+
+    let mut moments = Vec::new();
+    let mut previous_eval = None;
+    let mut before = start;
+    for (i, (mv, pgn_move)) in moves.iter().zip(&pgn.moves).enumerate() {
+        let after = game.at_ply(i + 1);
+        let comment = pgn_move.comment.as_deref().unwrap_or("");
+        let eval = parse_eval(comment);
+        let swing = match (previous_eval, eval) {
+            (Some(previous), Some(eval)) => Some(eval - previous),
+            _ => None,
+        };
+        if eval.is_some() {
+            previous_eval = eval;
+        }
+
+        let text = comment_text(comment);
+        let swung = swing.is_some_and(|swing: i32| swing.abs() >= threshold);
+        if !text.is_empty() || swung {
+            let notation = match before.board().get(mv.from) {
+                Some(&piece) => move_to_chinese_with_style(
+                    &before,
+                    piece,
+                    mv.from,
+                    mv.to,
+                    NumeralStyle::default(),
+                ),
+                None => move_to_iccs(mv.from, mv.to),
+            };
+            moments.push(KeyMoment {
+                ply: i + 1,
+                move_number: (i + offset) / 2 + 1,
+                side: before.turn(),
+                notation,
+                iccs: move_to_iccs(mv.from, mv.to),
+                comment: (!text.is_empty()).then_some(text),
+                eval,
+                swing,
+                game: after.clone(),
+            });
+        }
+        before = after;
+    }
+    Ok(moments)
+}
+
+/// Draw a position as a standalone SVG image
+///
+/// Red is at the bottom. The squares of `last_move` are marked, and the
+/// caption is written under the board.
+pub fn svg_diagram(board: &Board, last_move: Option<Move>, caption: &str) -> String {
+    board_svg(board, last_move, Some(caption))
+}
+
+/// Draw a position as plain text
+///
+/// Pieces use FEN letters, uppercase for Red, so the two sides can be told
+/// apart without color. The square `last_move` came from is marked `*`.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::diagram::text_diagram;
+/// use cn_chess_tui::Board;
+///
+/// let text = text_diagram(&Board::new(), None, "Start");
+/// assert!(text.starts_with("Start\n"));
+/// assert!(text.contains("9 R N B A K A B N R"));
+/// ```
+pub fn text_diagram(board: &Board, last_move: Option<Move>, caption: &str) -> String {
+    let mut text = format!("{}\n\n  a b c d e f g h i\n", caption);
+    for y in 0..10 {
+        if y == 5 {
+            text.push_str("  -----------------\n");
+        }
+        text.push_str(&y.to_string());
+        for x in 0..9 {
+            let pos = Position::from_xy(x, y);
+            let symbol = match board.get(pos) {
+                Some(&piece) => piece_to_fen(piece),
+                None if last_move.is_some_and(|mv| mv.from == pos) => '*',
+                None => '.',
+            };
+            text.push(' ');
+            text.push(symbol);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_eval_forms() {
+        assert_eq!(parse_eval("[%eval 1.25]"), Some(125));
+        assert_eq!(parse_eval("[%eval  -3 ]"), Some(-300));
+        assert_eq!(parse_eval("[%eval #-4]"), Some(-MATE_SCORE + 4));
+        assert_eq!(parse_eval("[%eval abc]"), None);
+        assert_eq!(parse_eval("[%eval 1.0"), None);
+
+        assert_eq!(format_eval(125), "+1.25");
+        assert_eq!(format_eval(-MATE_SCORE + 4), "#-4");
+        assert_eq!(
+            comment_text("Strong [%eval 1.2] move [%clk 0:10:00]"),
+            "Strong move"
+        );
+    }
+
+    #[test]
+    fn test_moments_from_comments_and_swings() {
+        let pgn = PgnGame::parse(
+            "1. h7e7 {Central cannon} h0g2 {[%eval 0.1]} 2. h9g7 {[%eval 0.2]} \
+             b0c2 {[%eval -1.1]} 3. i9h9 {[%eval -1.0]}",
+        )
+        .unwrap();
+        let moments = key_moments(&pgn, 100).unwrap();
+        let stems: Vec<String> = moments.iter().map(KeyMoment::file_stem).collect();
+        assert_eq!(stems, ["001r", "002b"]);
+
+        assert_eq!(moments[0].notation, "炮二平五");
+        assert_eq!(moments[0].eval, None);
+        assert_eq!(moments[0].game.get_moves().len(), 1);
+
+        assert_eq!(moments[1].swing, Some(-130));
+        assert_eq!(moments[1].comment, None);
+        assert_eq!(moments[1].caption(), "2... 马2进3 (-1.10)");
+        assert_eq!(moments[1].ply, 4);
+
+        assert_eq!(key_moments(&pgn, 500).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_numbering_when_black_starts() {
+        let mut pgn = PgnGame::parse("h2e2 {Black first} h7e7 b2b6 {Third}").unwrap();
+        pgn.set_tag(
+            "FEN",
+            "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR b - - 0 1",
+        );
+        let moments = key_moments(&pgn, 100).unwrap();
+        assert_eq!(moments[0].file_stem(), "001b");
+        assert_eq!(moments[1].file_stem(), "002b");
+    }
+
+    #[test]
+    fn test_diagrams_mark_last_move() {
+        let pgn = PgnGame::parse("h7e7 {Cannon to the center}").unwrap();
+        let moment = &key_moments(&pgn, 100).unwrap()[0];
+
+        let svg = moment.render(DiagramFormat::Svg);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 32);
+        assert_eq!(svg.matches("stroke-dasharray").count(), 2);
+        assert!(svg.contains("1. 炮二平五"));
+
+        let text = moment.render(DiagramFormat::Text);
+        assert!(text.contains("7 . C . . C . . * ."));
+        assert_eq!(
+            svg_diagram(&Board::new(), None, "a < b")
+                .matches("&lt;")
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,594 @@
+//! Position editor with its own edit history
+//!
+//! [`BoardEditor`] holds a position being set up by hand. Every placement,
+//! move, removal, clear and revert is recorded, so edits can be undone and redone
+//! independently of a game's move history. [`BoardEditor::validate`] checks
+//! that the position could arise in a game before play starts from it.
+
+use crate::board::Board;
+use crate::fen::{board_to_fen, FenError};
+use crate::game::Game;
+use crate::notation::iccs::position_to_iccs;
+use crate::types::{Color, Piece, PieceType, Position};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+/// One recorded change to the editor's board
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Edit {
+    /// A single square changed
+    Square {
+        pos: Position,
+        before: Option<Piece>,
+        after: Option<Piece>,
+    },
+    /// A piece was dragged to another square, replacing what was there
+    Move {
+        from: Position,
+        to: Position,
+        piece: Piece,
+        captured: Option<Piece>,
+    },
+    /// The whole board was replaced (clear or revert)
+    Board {
+        before: Box<Board>,
+        after: Box<Board>,
+    },
+}
+
+/// Why an edited position can't be played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupError {
+    /// A side has no general, or more than one
+    GeneralCount(Color, usize),
+    /// A piece stands on a point it can never reach
+    IllegalSquare(Position, Piece),
+    /// A side has more of a piece than it starts with
+    TooMany(Piece, usize),
+    /// The generals face each other on an open file
+    GeneralsFacing,
+    /// The side that is not to move is in check
+    OpponentInCheck(Color),
+}
+
+impl Display for SetupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::GeneralCount(color, 0) => write!(f, "{} has no general", color),
+            SetupError::GeneralCount(color, n) => write!(f, "{} has {} generals", color, n),
+            SetupError::IllegalSquare(pos, piece) => write!(
+                f,
+                "{} {} can't stand on {}",
+                piece.color,
+                piece,
+                position_to_iccs(*pos)
+            ),
+            SetupError::TooMany(piece, n) => write!(f, "{} has {} {}", piece.color, n, piece),
+            SetupError::GeneralsFacing => write!(f, "The generals face each other"),
+            SetupError::OpponentInCheck(color) => {
+                write!(f, "{} is in check but it is not {}'s move", color, color)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+/// A position being edited, with undo and redo
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::editor::BoardEditor;
+/// use cn_chess_tui::{Board, Color, Piece, PieceType, Position};
+///
+/// let mut editor = BoardEditor::new(Board::new(), Color::Red);
+/// editor.clear();
+/// editor.place(Position::from_xy(4, 9), Piece::red(PieceType::General));
+/// editor.place(Position::from_xy(3, 0), Piece::black(PieceType::General));
+/// assert_eq!(editor.to_fen(), "3k5/9/9/9/9/9/9/9/9/4K4 w - - 0 1");
+///
+/// editor.undo();
+/// editor.undo();
+/// editor.undo();
+/// assert_eq!(editor.board(), &Board::new());
+/// assert!(editor.redo());
+/// assert_eq!(editor.board().pieces().count(), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BoardEditor {
+    board: Board,
+    initial: Board,
+    turn: Color,
+    undo_stack: Vec<Edit>,
+    redo_stack: Vec<Edit>,
+}
+
+impl BoardEditor {
+    /// Start editing a position
+    pub fn new(board: Board, turn: Color) -> Self {
+        Self {
+            initial: board.clone(),
+            board,
+            turn,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    /// Start editing a game's current position
+    pub fn from_game(game: &Game) -> Self {
+        Self::new(game.board().clone(), game.turn())
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Side to move in the edited position
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn set_turn(&mut self, turn: Color) {
+        self.turn = turn;
+    }
+
+    /// Put a piece on a square, replacing whatever was there
+    ///
+    /// Returns false if the square already held that piece.
+    pub fn place(&mut self, pos: Position, piece: Piece) -> bool {
+        self.set_square(pos, Some(piece))
+    }
+
+    /// Remove the piece on a square
+    ///
+    /// Returns false if the square was already empty.
+    pub fn remove(&mut self, pos: Position) -> bool {
+        self.set_square(pos, None)
+    }
+
+    /// Move the piece on `from` to `to`, replacing whatever was there, as
+    /// one edit
+    ///
+    /// Any piece may go to any square; the move rules don't apply while
+    /// setting up. Returns false if `from` is empty or the squares are the
+    /// same.
+    pub fn move_piece(&mut self, from: Position, to: Position) -> bool {
+        let Some(&piece) = self.board.get(from) else {
+            return false;
+        };
+        if from == to {
+            return false;
+        }
+        let captured = self.board.get(to).copied();
+        self.write_square(from, None);
+        self.write_square(to, Some(piece));
+        self.record(Edit::Move {
+            from,
+            to,
+            piece,
+            captured,
+        });
+        true
+    }
+
+    /// Remove every piece from the board
+    pub fn clear(&mut self) {
+        self.set_board(Board::from_pieces(HashMap::new()));
+    }
+
+    /// Replace the whole board, e.g. with the initial position
+    pub fn load(&mut self, board: Board) {
+        self.set_board(board);
+    }
+
+    /// Go back to the position the editor started with
+    ///
+    /// Reverting is itself recorded, so it can be undone.
+    pub fn revert(&mut self) {
+        self.set_board(self.initial.clone());
+    }
+
+    /// Undo the last edit; returns false if there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.undo_stack.pop() else {
+            return false;
+        };
+        match &edit {
+            Edit::Square { pos, before, .. } => self.write_square(*pos, *before),
+            Edit::Move {
+                from,
+                to,
+                piece,
+                captured,
+            } => {
+                self.write_square(*to, *captured);
+                self.write_square(*from, Some(*piece));
+            }
+            Edit::Board { before, .. } => self.board = (**before).clone(),
+        }
+        self.redo_stack.push(edit);
+        true
+    }
+
+    /// Redo the last undone edit; returns false if there is nothing to redo
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.redo_stack.pop() else {
+            return false;
+        };
+        match &edit {
+            Edit::Square { pos, after, .. } => self.write_square(*pos, *after),
+            Edit::Move {
+                from, to, piece, ..
+            } => {
+                self.write_square(*from, None);
+                self.write_square(*to, Some(*piece));
+            }
+            Edit::Board { after, .. } => self.board = (**after).clone(),
+        }
+        self.undo_stack.push(edit);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// FEN of the edited position
+    pub fn to_fen(&self) -> String {
+        board_to_fen(&self.board, self.turn, 0, 1)
+    }
+
+    /// Start a game from the edited position
+    ///
+    /// Fails if the position is not playable, e.g. a general is missing.
+    pub fn to_game(&self) -> Result<Game, FenError> {
+        Game::from_fen(&self.to_fen())
+    }
+
+    /// Check that the position could arise in a game
+    ///
+    /// Each side needs exactly one general, every piece must stand on a
+    /// point it can reach, no side may have more pieces of a kind than it
+    /// starts with, and the side that just moved can't be in check.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::editor::{BoardEditor, SetupError};
+    /// use cn_chess_tui::{Board, Color, Piece, PieceType, Position};
+    ///
+    /// let mut editor = BoardEditor::new(Board::new(), Color::Red);
+    /// assert_eq!(editor.validate(), Ok(()));
+    ///
+    /// // A soldier can't stand behind its starting rank
+    /// let soldier = Piece::red(PieceType::Soldier);
+    /// editor.place(Position::from_xy(0, 8), soldier);
+    /// assert_eq!(
+    ///     editor.validate(),
+    ///     Err(SetupError::IllegalSquare(Position::from_xy(0, 8), soldier))
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), SetupError> {
+        match position_problems(&self.board, self.turn).into_iter().next() {
+            Some(problem) => Err(problem),
+            None => Ok(()),
+        }
+    }
+
+    fn set_square(&mut self, pos: Position, piece: Option<Piece>) -> bool {
+        let before = self.board.get(pos).copied();
+        if before == piece {
+            return false;
+        }
+        self.write_square(pos, piece);
+        self.record(Edit::Square {
+            pos,
+            before,
+            after: piece,
+        });
+        true
+    }
+
+    fn set_board(&mut self, board: Board) {
+        if board == self.board {
+            return;
+        }
+        let before = std::mem::replace(&mut self.board, board.clone());
+        self.record(Edit::Board {
+            before: Box::new(before),
+            after: Box::new(board),
+        });
+    }
+
+    fn write_square(&mut self, pos: Position, piece: Option<Piece>) {
+        match piece {
+            Some(piece) => self.board.place_piece(pos, piece),
+            None => {
+                self.board.remove_piece(pos);
+            }
+        }
+    }
+
+    /// Record a new edit; any undone edits can no longer be redone
+    fn record(&mut self, edit: Edit) {
+        self.undo_stack.push(edit);
+        self.redo_stack.clear();
+    }
+}
+
+/// Everything that stops a position from arising in a game, with `turn`
+/// to move
+///
+/// Problems are listed in the order [`BoardEditor::validate`] checks them:
+/// general counts, pieces on points they can't reach (by rank, then file),
+/// pieces a side has too many of, generals facing, and the side that just
+/// moved being in check. An empty list means the position is playable.
+pub fn position_problems(board: &Board, turn: Color) -> Vec<SetupError> {
+    let mut problems = Vec::new();
+    for color in [Color::Red, Color::Black] {
+        let generals = board
+            .pieces_of_color(color)
+            .filter(|(_, piece)| piece.piece_type == PieceType::General)
+            .count();
+        if generals != 1 {
+            problems.push(SetupError::GeneralCount(color, generals));
+        }
+    }
+
+    let mut pieces: Vec<(Position, Piece)> = board.pieces().collect();
+    pieces.sort_by_key(|(pos, _)| (pos.y, pos.x));
+    for &(pos, piece) in &pieces {
+        if !can_stand(pos, piece) {
+            problems.push(SetupError::IllegalSquare(pos, piece));
+        }
+    }
+
+    // Generals were counted above
+    let mut counted = Vec::new();
+    for &(_, piece) in &pieces {
+        if piece.piece_type == PieceType::General || counted.contains(&piece) {
+            continue;
+        }
+        counted.push(piece);
+        let n = pieces.iter().filter(|(_, other)| *other == piece).count();
+        if n > max_count(piece.piece_type) {
+            problems.push(SetupError::TooMany(piece, n));
+        }
+    }
+
+    if board.generals_facing() {
+        problems.push(SetupError::GeneralsFacing);
+    }
+    let waiting = turn.opponent();
+    if board.is_in_check(waiting) {
+        problems.push(SetupError::OpponentInCheck(waiting));
+    }
+    problems
+}
+
+/// Whether a piece can ever stand on a point
+fn can_stand(pos: Position, piece: Piece) -> bool {
+    // Ranks counted from the piece's own back rank
+    let rank = match piece.color {
+        Color::Red => 9 - pos.y,
+        Color::Black => pos.y,
+    };
+    match piece.piece_type {
+        PieceType::General => pos.in_palace(piece.color),
+        PieceType::Advisor => pos.in_palace(piece.color) && pos.x.abs_diff(4) == rank.abs_diff(1),
+        PieceType::Elephant => matches!((pos.x, rank), (2 | 6, 0 | 4) | (0 | 4 | 8, 2)),
+        // Soldiers never retreat and only leave their file after crossing
+        PieceType::Soldier => rank >= 5 || (rank >= 3 && matches!(pos.x, 0 | 2 | 4 | 6 | 8)),
+        PieceType::Horse | PieceType::Chariot | PieceType::Cannon => true,
+    }
+}
+
+/// Pieces of a kind each side starts with
+fn max_count(piece_type: PieceType) -> usize {
+    match piece_type {
+        PieceType::General => 1,
+        PieceType::Soldier => 5,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PieceType;
+
+    #[test]
+    fn test_undo_redo_placement() {
+        let mut editor = BoardEditor::new(Board::new(), Color::Red);
+        let pos = Position::from_xy(4, 4);
+        assert!(editor.place(pos, Piece::red(PieceType::Chariot)));
+        assert!(!editor.place(pos, Piece::red(PieceType::Chariot)));
+        assert!(editor.place(pos, Piece::black(PieceType::Horse)));
+
+        assert!(editor.undo());
+        assert_eq!(
+            editor.board().get(pos),
+            Some(&Piece::red(PieceType::Chariot))
+        );
+        assert!(editor.undo());
+        assert_eq!(editor.board().get(pos), None);
+        assert!(!editor.undo());
+
+        assert!(editor.redo());
+        assert!(editor.redo());
+        assert_eq!(
+            editor.board().get(pos),
+            Some(&Piece::black(PieceType::Horse))
+        );
+        assert!(!editor.redo());
+    }
+
+    #[test]
+    fn test_move_is_one_edit() {
+        let mut editor = BoardEditor::new(Board::new(), Color::Red);
+        let chariot = Position::from_xy(0, 9);
+        let horse = Position::from_xy(1, 9);
+        assert!(editor.move_piece(chariot, horse));
+        assert!(!editor.move_piece(chariot, horse));
+        assert_eq!(
+            editor.board().get(horse),
+            Some(&Piece::red(PieceType::Chariot))
+        );
+
+        assert!(editor.undo());
+        assert_eq!(editor.board(), &Board::new());
+        assert!(editor.redo());
+        assert_eq!(editor.board().get(chariot), None);
+        assert_eq!(
+            editor.board().get(horse),
+            Some(&Piece::red(PieceType::Chariot))
+        );
+    }
+
+    #[test]
+    fn test_new_edit_drops_redo() {
+        let mut editor = BoardEditor::new(Board::new(), Color::Red);
+        let cannon = Position::from_xy(1, 7);
+        assert!(editor.remove(cannon));
+        assert!(!editor.remove(cannon));
+        editor.undo();
+        assert!(editor.can_redo());
+
+        editor.remove(Position::from_xy(7, 7));
+        assert!(!editor.can_redo());
+        assert!(editor.board().get(cannon).is_some());
+    }
+
+    #[test]
+    fn test_clear_and_revert_are_undoable() {
+        let mut editor = BoardEditor::new(Board::new(), Color::Red);
+        editor.remove(Position::from_xy(0, 9));
+        editor.clear();
+        assert_eq!(editor.board().pieces().count(), 0);
+
+        editor.revert();
+        assert_eq!(editor.board(), &Board::new());
+
+        editor.undo();
+        assert_eq!(editor.board().pieces().count(), 0);
+        editor.undo();
+        assert_eq!(editor.board().pieces().count(), 31);
+
+        // Nothing to record when the board is already in that state
+        editor.redo();
+        editor.redo();
+        editor.revert();
+        assert!(!editor.can_redo());
+        let undo_depth = editor.undo_stack.len();
+        editor.revert();
+        assert_eq!(editor.undo_stack.len(), undo_depth);
+    }
+
+    #[test]
+    fn test_validate_piece_squares() {
+        for (pos, piece) in Board::new().pieces() {
+            assert!(can_stand(pos, piece), "{:?} on {:?}", piece, pos);
+        }
+
+        // Advisors and elephants keep to their points
+        assert!(!can_stand(
+            Position::from_xy(4, 9),
+            Piece::red(PieceType::Advisor)
+        ));
+        assert!(can_stand(
+            Position::from_xy(4, 8),
+            Piece::red(PieceType::Advisor)
+        ));
+        assert!(can_stand(
+            Position::from_xy(4, 2),
+            Piece::black(PieceType::Elephant)
+        ));
+        assert!(!can_stand(
+            Position::from_xy(4, 6),
+            Piece::black(PieceType::Elephant)
+        ));
+        assert!(!can_stand(
+            Position::from_xy(4, 0),
+            Piece::black(PieceType::Advisor)
+        ));
+
+        // A soldier that crossed the river may leave its file
+        assert!(can_stand(
+            Position::from_xy(1, 4),
+            Piece::red(PieceType::Soldier)
+        ));
+        assert!(!can_stand(
+            Position::from_xy(1, 5),
+            Piece::red(PieceType::Soldier)
+        ));
+        assert!(!can_stand(
+            Position::from_xy(4, 2),
+            Piece::black(PieceType::Soldier)
+        ));
+
+        let mut editor = BoardEditor::new(Board::new(), Color::Red);
+        editor.place(Position::from_xy(0, 4), Piece::red(PieceType::Soldier));
+        assert_eq!(
+            editor.validate(),
+            Err(SetupError::TooMany(Piece::red(PieceType::Soldier), 6))
+        );
+    }
+
+    #[test]
+    fn test_position_problems_lists_everything() {
+        let mut board = Board::new();
+        board.remove_piece(Position::from_xy(4, 9));
+        board.place_piece(Position::from_xy(0, 8), Piece::red(PieceType::Soldier));
+        board.remove_piece(Position::from_xy(4, 6));
+        board.remove_piece(Position::from_xy(4, 3));
+        board.place_piece(Position::from_xy(4, 4), Piece::red(PieceType::Chariot));
+
+        assert_eq!(
+            position_problems(&board, Color::Red),
+            [
+                SetupError::GeneralCount(Color::Red, 0),
+                SetupError::IllegalSquare(Position::from_xy(0, 8), Piece::red(PieceType::Soldier)),
+                SetupError::TooMany(Piece::red(PieceType::Chariot), 3),
+                SetupError::OpponentInCheck(Color::Black),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_generals_and_check() {
+        let mut editor = BoardEditor::new(Board::new(), Color::Red);
+        editor.clear();
+        assert_eq!(
+            editor.validate(),
+            Err(SetupError::GeneralCount(Color::Red, 0))
+        );
+        editor.place(Position::from_xy(4, 9), Piece::red(PieceType::General));
+        editor.place(Position::from_xy(4, 0), Piece::black(PieceType::General));
+        assert_eq!(editor.validate(), Err(SetupError::GeneralsFacing));
+
+        editor.place(Position::from_xy(4, 5), Piece::red(PieceType::Chariot));
+        assert_eq!(
+            editor.validate(),
+            Err(SetupError::OpponentInCheck(Color::Black))
+        );
+        editor.set_turn(Color::Black);
+        assert_eq!(editor.validate(), Ok(()));
+        assert!(editor.to_game().is_ok());
+    }
+
+    #[test]
+    fn test_to_game_validates() {
+        let mut editor = BoardEditor::new(Board::new(), Color::Black);
+        assert_eq!(editor.to_game().unwrap().turn(), Color::Black);
+
+        editor.remove(Position::from_xy(4, 0));
+        assert_eq!(
+            editor.to_game().unwrap_err(),
+            FenError::MissingGeneral(Color::Black)
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Knowledge of elementary endings
+//!
+//! Once few pieces are left, many endings have a known outcome that a
+//! shallow search can't see: a lone chariot beats a bare general, but not
+//! one with both advisors and both elephants; a lone cannon can't mate
+//! without a screen. [`classify`] recognizes these from the material on
+//! the board. Matches use it to score such games without playing them out,
+//! and the built-in AI to avoid trading into endings it can't win.
+
+use crate::board::Board;
+use crate::game::GameResult;
+use crate::types::{Color, PieceType};
+
+/// Outcome of an ending with correct play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndgameClass {
+    /// The side can force a win
+    Win(Color),
+    /// Neither side can force a win
+    Draw,
+    /// Not an ending [`classify`] knows
+    Unknown,
+}
+
+impl EndgameClass {
+    /// The game result the ending leads to, if it is known
+    pub fn result(self) -> Option<GameResult> {
+        match self {
+            EndgameClass::Win(Color::Red) => Some(GameResult::RedWins),
+            EndgameClass::Win(Color::Black) => Some(GameResult::BlackWins),
+            EndgameClass::Draw => Some(GameResult::Draw),
+            EndgameClass::Unknown => None,
+        }
+    }
+}
+
+/// One side's pieces, counted by kind
+#[derive(Debug, Default)]
+struct Army {
+    general: bool,
+    advisors: u8,
+    elephants: u8,
+    horses: u8,
+    chariots: u8,
+    cannons: u8,
+    soldiers: u8,
+    /// Soldiers on the enemy's back rank, which can only move sideways
+    /// and never reach a general that steps up
+    bottom_soldiers: u8,
+}
+
+impl Army {
+    fn of(board: &Board, color: Color) -> Self {
+        let back_rank = match color {
+            Color::Red => 0,
+            Color::Black => 9,
+        };
+        let mut army = Army::default();
+        for (pos, piece) in board.pieces_of_color(color) {
+            match piece.piece_type {
+                PieceType::General => army.general = true,
+                PieceType::Advisor => army.advisors += 1,
+                PieceType::Elephant => army.elephants += 1,
+                PieceType::Horse => army.horses += 1,
+                PieceType::Chariot => army.chariots += 1,
+                PieceType::Cannon => army.cannons += 1,
+                PieceType::Soldier => {
+                    army.soldiers += 1;
+                    if pos.y == back_rank {
+                        army.bottom_soldiers += 1;
+                    }
+                }
+            }
+        }
+        army
+    }
+
+    /// Pieces that can cross the river
+    fn attackers(&self) -> u8 {
+        self.horses + self.chariots + self.cannons + self.soldiers
+    }
+
+    fn defenders(&self) -> u8 {
+        self.advisors + self.elephants
+    }
+
+    /// Both advisors and both elephants (士象全)
+    fn full_defence(&self) -> bool {
+        self.advisors == 2 && self.elephants == 2
+    }
+
+    fn only_chariot(&self) -> bool {
+        self.chariots == 1 && self.attackers() == 1
+    }
+}
+
+/// Classify an elementary ending by the material on the board
+///
+/// Only the material counts, except that soldiers on the enemy's back rank
+/// are told apart, so the result holds for quiet positions: one where a
+/// piece is about to be captured may end otherwise. Known endings are:
+///
+/// - no attacking pieces on either side, or a chariot each: a draw
+/// - against a bare general: any chariot, horse or soldier short of the
+///   back rank wins, as does a cannon with an advisor for a screen; a lone
+///   cannon or back-rank soldiers draw
+/// - a lone chariot against advisors and elephants wins unless they are
+///   all there (单车难胜士象全); a chariot with more attackers wins
+/// - back-rank soldiers alone can't win against anything
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::endgame::{classify, EndgameClass};
+/// use cn_chess_tui::{Color, Game};
+///
+/// let board = |fen| Game::from_fen(fen).unwrap().board().clone();
+/// // A chariot against a bare general
+/// assert_eq!(classify(&board("3k5/9/9/9/9/9/9/9/9/R3K4 w - - 0 1")), EndgameClass::Win(Color::Red));
+/// // ... but not against a general with all its defenders
+/// assert_eq!(classify(&board("2bak4/4a4/4b4/9/9/9/9/9/9/R3K4 w - - 0 1")), EndgameClass::Draw);
+/// assert_eq!(classify(&board(&Game::new().to_fen())), EndgameClass::Unknown);
+/// ```
+pub fn classify(board: &Board) -> EndgameClass {
+    let red = Army::of(board, Color::Red);
+    let black = Army::of(board, Color::Black);
+    if !red.general || !black.general {
+        return EndgameClass::Unknown;
+    }
+
+    let one_sided =
+        |color: Color, attacker: &Army, defender: &Army| match one_sided(attacker, defender) {
+            Some(true) => EndgameClass::Win(color),
+            Some(false) => EndgameClass::Draw,
+            None => EndgameClass::Unknown,
+        };
+    match (red.attackers(), black.attackers()) {
+        (0, 0) => EndgameClass::Draw,
+        (_, 0) => one_sided(Color::Red, &red, &black),
+        (0, _) => one_sided(Color::Black, &black, &red),
+        _ if red.only_chariot() && black.only_chariot() => EndgameClass::Draw,
+        _ => EndgameClass::Unknown,
+    }
+}
+
+/// Whether `attacker` can force a win against `defender`, which has no
+/// attacking pieces; None when the ending isn't one [`classify`] knows
+fn one_sided(attacker: &Army, defender: &Army) -> Option<bool> {
+    let high_soldiers = attacker.soldiers - attacker.bottom_soldiers;
+    if attacker.attackers() == attacker.bottom_soldiers {
+        return Some(false);
+    }
+    if defender.defenders() == 0 {
+        if attacker.chariots + attacker.horses + high_soldiers > 0 {
+            return Some(true);
+        }
+        if attacker.cannons > 0 && attacker.advisors > 0 {
+            return Some(true);
+        }
+        // A cannon with nothing to jump over
+        if attacker.cannons == 1 && attacker.attackers() == 1 && attacker.defenders() == 0 {
+            return Some(false);
+        }
+        return None;
+    }
+    if attacker.only_chariot() {
+        return Some(!defender.full_defence());
+    }
+    if attacker.chariots > 0 {
+        return Some(true);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    fn classify_fen(fen: &str) -> EndgameClass {
+        classify(Game::from_fen(fen).unwrap().board())
+    }
+
+    #[test]
+    fn test_bare_general_endings() {
+        // Horse, and a soldier short of the back rank, win
+        assert_eq!(
+            classify_fen("3k5/9/9/9/9/9/9/9/9/4K1N2 w - - 0 1"),
+            EndgameClass::Win(Color::Red)
+        );
+        assert_eq!(
+            classify_fen("3k5/9/4P4/9/9/9/9/9/9/4K4 w - - 0 1"),
+            EndgameClass::Win(Color::Red)
+        );
+        // On the back rank it can't
+        assert_eq!(
+            classify_fen("3kP4/9/9/9/9/9/9/9/9/4K4 w - - 0 1"),
+            EndgameClass::Draw
+        );
+        // A cannon needs an advisor to jump over
+        assert_eq!(
+            classify_fen("3k5/9/9/9/4c4/9/9/9/9/4K4 b - - 0 1"),
+            EndgameClass::Draw
+        );
+        assert_eq!(
+            classify_fen("3k5/4a4/9/9/4c4/9/9/9/9/4K4 b - - 0 1"),
+            EndgameClass::Win(Color::Black)
+        );
+        assert_eq!(
+            classify_fen("2bk5/9/9/9/4c4/9/9/9/9/4K4 b - - 0 1"),
+            EndgameClass::Unknown
+        );
+    }
+
+    #[test]
+    fn test_chariot_endings() {
+        assert_eq!(
+            classify_fen("3ak4/4a4/9/9/9/9/9/9/9/R3K4 w - - 0 1"),
+            EndgameClass::Win(Color::Red)
+        );
+        assert_eq!(
+            classify_fen("2bak4/4a4/4b4/9/9/9/9/9/4P4/R3K4 w - - 0 1"),
+            EndgameClass::Win(Color::Red)
+        );
+        assert_eq!(
+            classify_fen("r2ak4/4a4/9/9/9/9/9/9/4A4/R3K4 w - - 0 1"),
+            EndgameClass::Draw
+        );
+        assert_eq!(
+            classify_fen("3ak4/4a4/9/9/9/9/9/9/9/3AKA3 w - - 0 1"),
+            EndgameClass::Draw
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Error type for the crate's public API
+//!
+//! Functions that can fail in more than one way, such as
+//! [`GameController::init_engine`](crate::game::GameController::init_engine),
+//! return [`Error`], which keeps the underlying error so callers can match
+//! on it instead of reading messages.
+
+use crate::fen::FenError;
+use crate::game::MoveError;
+use crate::ucci::engine::EngineError;
+use crate::ucci::parser::ParseError;
+use std::io;
+use std::path::PathBuf;
+
+/// Error type for the crate's public API
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::{Error, GameController};
+///
+/// let mut controller = GameController::new();
+/// assert!(matches!(controller.start_analysis(), Err(Error::NoEngine)));
+///
+/// let err = cn_chess_tui::load_fen_file("missing.fen").unwrap_err();
+/// assert!(matches!(err, Error::Io(_)));
+/// ```
+#[derive(Debug)]
+pub enum Error {
+    Fen(FenError),
+    Move(MoveError),
+    /// The engine sent something that could not be read
+    Parse(ParseError),
+    Engine(EngineError),
+    Io(io::Error),
+    /// The engine executable does not exist
+    EngineNotFound(PathBuf),
+    /// An engine is needed and none is loaded
+    NoEngine,
+    /// The engine is busy with a search
+    EngineBusy,
+    /// Analysis is running but has no line yet
+    NoAnalysisLine,
+}
+
+/// Result type for the crate's public API
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Fen(e) => write!(f, "{}", e),
+            Error::Move(e) => write!(f, "{}", e),
+            Error::Parse(e) => write!(f, "{}", e),
+            Error::Engine(e) => write!(f, "{}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::EngineNotFound(path) => {
+                write!(f, "Engine path does not exist: {}", path.display())
+            }
+            Error::NoEngine => write!(f, "No UCCI engine loaded"),
+            Error::EngineBusy => write!(f, "Engine is busy"),
+            Error::NoAnalysisLine => write!(f, "Analysis has no line yet"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Fen(e) => Some(e),
+            Error::Move(e) => Some(e),
+            Error::Parse(e) => Some(e),
+            Error::Engine(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<FenError> for Error {
+    fn from(e: FenError) -> Self {
+        Error::Fen(e)
+    }
+}
+
+impl From<MoveError> for Error {
+    fn from(e: MoveError) -> Self {
+        Error::Move(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<EngineError> for Error {
+    fn from(e: EngineError) -> Self {
+        Error::Engine(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
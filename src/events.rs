@@ -0,0 +1,79 @@
+//! Notifications of what happens in a game
+//!
+//! Code embedding the crate can follow a [`Game`](crate::Game) without
+//! polling it: [`Game::subscribe`](crate::Game::subscribe) (or
+//! [`GameController::subscribe`](crate::GameController::subscribe)) returns
+//! a channel that receives a [`GameEvent`] for every move, capture, check,
+//! game end and undo. Receivers can live on another thread, and dropping
+//! one unsubscribes it.
+//!
+//! ```
+//! use cn_chess_tui::events::GameEvent;
+//! use cn_chess_tui::{Game, Position};
+//!
+//! let mut game = Game::new();
+//! let events = game.subscribe();
+//! game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+//! game.undo_move();
+//!
+//! let events: Vec<GameEvent> = events.try_iter().collect();
+//! assert!(matches!(events[0], GameEvent::Moved { captured: None, .. }));
+//! assert!(matches!(events[1], GameEvent::Undone(_)));
+//! ```
+
+use crate::game::{GameState, Move};
+use crate::types::{Color, Piece};
+use std::fmt::{self, Debug, Formatter};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Something that happened in a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A move was played, or redone
+    Moved {
+        mv: Move,
+        piece: Piece,
+        captured: Option<Piece>,
+    },
+    /// The move just played took a piece; sent after its `Moved`
+    Captured { mv: Move, piece: Piece },
+    /// The side to move is in check after the last move
+    Check(Color),
+    /// The last move ended the game
+    GameOver(GameState),
+    /// A move was taken back
+    Undone(Move),
+}
+
+/// The channels a game sends its events to
+///
+/// Copies of a game start with no observers, so the positions the AI and
+/// the reports try out are not reported as moves.
+#[derive(Default)]
+pub(crate) struct Observers(Vec<Sender<GameEvent>>);
+
+impl Observers {
+    /// Add an observer
+    pub(crate) fn subscribe(&mut self) -> Receiver<GameEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.0.push(sender);
+        receiver
+    }
+
+    /// Send an event to every observer, forgetting those that were dropped
+    pub(crate) fn emit(&mut self, event: GameEvent) {
+        self.0.retain(|sender| sender.send(event).is_ok());
+    }
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl Debug for Observers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
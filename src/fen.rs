@@ -46,6 +46,7 @@
 //! - Lower case: Black (r=车, n=马, b=相/象, a=仕/士, k=将, c=炮, p=卒)
 
 use crate::board::Board;
+use crate::editor::{position_problems, SetupError};
 use crate::types::{Color, Piece, PieceType, Position};
 use std::collections::HashMap;
 
@@ -67,6 +68,8 @@ pub enum FenError {
     EmptyMovesList,
     #[allow(dead_code)]
     InvalidMoveInHistory(String),
+    /// The position has no general for this color
+    MissingGeneral(Color),
 }
 
 impl std::fmt::Display for FenError {
@@ -82,6 +85,7 @@ impl std::fmt::Display for FenError {
             FenError::MissingMovesKeyword => write!(f, "Missing 'moves' keyword in FEN with moves"),
             FenError::EmptyMovesList => write!(f, "Empty moves list in FEN with moves"),
             FenError::InvalidMoveInHistory(mv) => write!(f, "Invalid move in history: {}", mv),
+            FenError::MissingGeneral(color) => write!(f, "Missing {} general", color),
         }
     }
 }
@@ -201,19 +205,62 @@ pub fn fen_to_board(fen: &str) -> Result<(Board, Color), FenError> {
     // Parts 2 and 3 are always "-" for Chinese Chess (no castling, no en passant)
     // We don't need to validate them
 
-    // Parse move counts (optional validation)
-    if parts[4].parse::<u32>().is_err() {
-        return Err(FenError::InvalidMoveCount);
-    }
-    if parts[5].parse::<u32>().is_err() {
-        return Err(FenError::InvalidMoveCount);
-    }
+    fen_move_counts(fen)?;
 
     let board = Board::from_pieces(pieces);
 
     Ok((board, turn))
 }
 
+/// Check that a FEN describes a position that could arise in a game
+///
+/// A FEN that can't be read is an error. Otherwise every problem found is
+/// returned, in the order given by [`position_problems`]: general counts and
+/// palaces, pieces on points they can never reach, more pieces of a kind
+/// than a side starts with, facing generals, and the side that just moved
+/// being in check. An empty list means the position is valid.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::editor::SetupError;
+/// use cn_chess_tui::fen::validate_position;
+/// use cn_chess_tui::Color;
+///
+/// let start = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+/// assert!(validate_position(start).unwrap().is_empty());
+///
+/// let problems = validate_position("4k4/9/9/9/9/9/9/9/9/9 w - - 0 1").unwrap();
+/// assert_eq!(problems, [SetupError::GeneralCount(Color::Red, 0)]);
+/// ```
+pub fn validate_position(fen: &str) -> Result<Vec<SetupError>, FenError> {
+    let (board, turn) = fen_to_board(fen)?;
+    Ok(position_problems(&board, turn))
+}
+
+/// Parse the halfmove clock and fullmove number from a FEN string
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::fen::fen_move_counts;
+///
+/// let fen = "4k4/9/9/9/9/9/9/9/9/4K4 w - - 17 40";
+/// assert_eq!(fen_move_counts(fen).unwrap(), (17, 40));
+/// ```
+pub fn fen_move_counts(fen: &str) -> Result<(u32, u32), FenError> {
+    let parts: Vec<&str> = fen.split_whitespace().collect();
+    if parts.len() != 6 {
+        return Err(FenError::InvalidFormat);
+    }
+
+    let half_move = parts[4]
+        .parse::<u32>()
+        .map_err(|_| FenError::InvalidMoveCount)?;
+    let full_move = parts[5]
+        .parse::<u32>()
+        .map_err(|_| FenError::InvalidMoveCount)?;
+    Ok((half_move, full_move))
+}
+
 /// Convert a Board position to FEN string format
 ///
 /// Arguments:
@@ -2,6 +2,7 @@
 //!
 //! Provides functions to read and write .fen files
 
+use crate::error::Error;
 use std::fs;
 use std::path::Path;
 
@@ -43,11 +44,11 @@ pub fn write_fen_file<P: AsRef<Path>>(path: P, fen: &str) -> Result<(), std::io:
 /// (Board, Color) tuple
 ///
 /// # Errors
-/// Returns io::Error for file errors, or FenError for parsing errors
-#[allow(dead_code)]
+/// Returns [`Error::Io`] for file errors, or [`Error::Fen`] for parsing
+/// errors
 pub fn load_fen_file<P: AsRef<Path>>(
     path: P,
-) -> Result<(crate::board::Board, crate::types::Color), Box<dyn std::error::Error>> {
+) -> Result<(crate::board::Board, crate::types::Color), Error> {
     let fen = read_fen_file(path)?;
     let (board, turn) = crate::fen::fen_to_board(&fen)?;
     Ok((board, turn))
@@ -1,44 +1,264 @@
 //! Terminal position printing without entering game loop
 
 use crate::board::Board;
+use crate::fen::piece_to_fen;
 use crate::game::Game;
-use crate::types::{move_to_simple_notation, Position};
+use crate::types::{move_to_simple_notation, Color, Piece, Position};
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+/// Empty point in a board thumbnail
+const EMPTY_POINT: &str = "·";
 
 /// Print a board position to stdout using ASCII art
 ///
 /// This function prints a simplified text representation of the board
 /// without using the full TUI framework
 pub fn print_board_ascii(board: &Board) {
-    println!("┌─────┬─────┬─────┬─────┬─────┬─────┬─────┬─────┬─────┐");
+    write_board_ascii(&mut io::stdout().lock(), board).expect("failed printing to stdout");
+}
+
+/// Write the board drawn by [`print_board_ascii`] to any output
+pub fn write_board_ascii<W: Write>(out: &mut W, board: &Board) -> io::Result<()> {
+    writeln!(
+        out,
+        "┌─────┬─────┬─────┬─────┬─────┬─────┬─────┬─────┬─────┐"
+    )?;
 
     for y in 0..10 {
         if y > 0 {
             // Print river separator between ranks 4 and 5
             if y == 5 {
-                println!("├─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┤");
-                println!("│  楚河  │     │     │     │     │     │     │     │  汉界  │");
-                println!("├─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┤");
+                writeln!(
+                    out,
+                    "├─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┤"
+                )?;
+                writeln!(
+                    out,
+                    "│  楚河  │     │     │     │     │     │     │     │  汉界  │"
+                )?;
+                writeln!(
+                    out,
+                    "├─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┤"
+                )?;
             } else {
-                println!("├─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┤");
+                writeln!(
+                    out,
+                    "├─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┼─────┤"
+                )?;
             }
         }
 
-        print!("│");
+        write!(out, "│")?;
         for x in 0..9 {
             let pos = Position::from_xy(x, y);
             match board.get(pos) {
                 Some(piece) => {
-                    print!("  {}  │", piece);
+                    write!(out, "  {}  │", piece)?;
                 }
                 None => {
-                    print!("     │");
+                    write!(out, "     │")?;
+                }
+            }
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(
+        out,
+        "└─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┘"
+    )
+}
+
+/// How `--print` draws the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrintStyle {
+    /// Boxed squares with a piece in each, as [`write_board_ascii`]
+    #[default]
+    Ascii,
+    /// Pieces on the points of a box-drawing grid, as [`write_board_unicode`]
+    Unicode,
+}
+
+impl PrintStyle {
+    /// Parse a style name as given to `--print-style`
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ascii" => Some(PrintStyle::Ascii),
+            "unicode" => Some(PrintStyle::Unicode),
+            _ => None,
+        }
+    }
+
+    /// Write a board in this style
+    pub fn write_board<W: Write>(self, out: &mut W, board: &Board) -> io::Result<()> {
+        match self {
+            PrintStyle::Ascii => write_board_ascii(out, board),
+            PrintStyle::Unicode => write_board_unicode(out, board),
+        }
+    }
+}
+
+/// The river drawn by [`write_board_unicode`], as wide as the grid
+const UNICODE_RIVER: &str = "│     楚  河         汉  界     │";
+
+/// Write a board the way it is drawn on paper
+///
+/// Pieces sit on the points where box-drawing lines cross, with the river
+/// between the two halves and the diagonals of both palaces.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::board::Board;
+/// use cn_chess_tui::fen_print::write_board_unicode;
+///
+/// let mut out = Vec::new();
+/// write_board_unicode(&mut out, &Board::new()).unwrap();
+/// let text = String::from_utf8(out).unwrap();
+/// assert_eq!(text.lines().next(), Some("车──马──象──士──将──士──象──马──车"));
+/// assert!(text.contains("楚  河"));
+/// ```
+pub fn write_board_unicode<W: Write>(out: &mut W, board: &Board) -> io::Result<()> {
+    for y in 0..10 {
+        let mut line = String::new();
+        for x in 0..9 {
+            match board.get(Position::from_xy(x, y)) {
+                Some(piece) => line.push_str(&piece.to_string()),
+                None => {
+                    line.push(grid_point(x, y));
+                    if x < 8 {
+                        line.push('─');
+                    }
+                }
+            }
+            if x < 8 {
+                line.push_str("──");
+            }
+        }
+        writeln!(out, "{}", line)?;
+
+        match y {
+            4 => writeln!(out, "{}", UNICODE_RIVER)?,
+            9 => {}
+            _ => {
+                let mut line = String::new();
+                for x in 0..9 {
+                    line.push('│');
+                    if x < 8 {
+                        line.push(' ');
+                        line.push(palace_diagonal(x, y).unwrap_or(' '));
+                        line.push(' ');
+                    }
                 }
+                writeln!(out, "{}", line)?;
             }
         }
-        println!();
     }
+    Ok(())
+}
+
+/// The box-drawing character for an empty point
+fn grid_point(x: usize, y: usize) -> char {
+    match (x, y) {
+        (0, 0) => '┌',
+        (8, 0) => '┐',
+        (0, 9) => '└',
+        (8, 9) => '┘',
+        (_, 0) | (_, 5) if x != 0 && x != 8 => '┬',
+        (_, 9) | (_, 4) if x != 0 && x != 8 => '┴',
+        (0, _) => '├',
+        (8, _) => '┤',
+        _ => '┼',
+    }
+}
 
-    println!("└─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┘");
+/// The palace diagonal between file `x` and the next, below rank `y`
+fn palace_diagonal(x: usize, y: usize) -> Option<char> {
+    // The upper half of a palace slopes in towards its center
+    let upper = match y {
+        0 | 7 => true,
+        1 | 8 => false,
+        _ => return None,
+    };
+    match (x, upper) {
+        (3, true) | (4, false) => Some('╲'),
+        (4, true) | (3, false) => Some('╱'),
+        _ => None,
+    }
+}
+
+/// A point of a board thumbnail
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThumbnailCell {
+    /// The point's text, padded to the thumbnail's cell width
+    pub text: String,
+    /// Color of the piece on the point, None if it is empty
+    pub color: Option<Color>,
+}
+
+/// Draw a board as 10 rows of 9 cells, each `cell_width` columns wide
+///
+/// `piece_text` writes a piece (e.g. as a Chinese character or a FEN
+/// letter); empty points are `·`. Cells are padded on the right to
+/// `cell_width`, counting Chinese characters as two columns. This is the
+/// renderer behind [`write_board_compact`] and the previews in the game
+/// picker and library, so callers can color pieces as they need.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::board::Board;
+/// use cn_chess_tui::fen_print::board_thumbnail;
+///
+/// let rows = board_thumbnail(&Board::new(), 2, |piece| piece.to_string());
+/// let top: String = rows[0].iter().map(|cell| cell.text.as_str()).collect();
+/// assert_eq!(top, "车马象士将士象马车");
+/// let third: String = rows[2].iter().map(|cell| cell.text.as_str()).collect();
+/// assert_eq!(third, "· 炮· · · · · 炮· ");
+/// ```
+pub fn board_thumbnail(
+    board: &Board,
+    cell_width: usize,
+    piece_text: impl Fn(Piece) -> String,
+) -> Vec<Vec<ThumbnailCell>> {
+    (0..10)
+        .map(|y| {
+            (0..9)
+                .map(|x| {
+                    let piece = board.get(Position::from_xy(x, y)).copied();
+                    let text = piece.map_or_else(|| EMPTY_POINT.to_string(), &piece_text);
+                    let pad = cell_width.saturating_sub(text.width());
+                    ThumbnailCell {
+                        text: format!("{}{}", text, " ".repeat(pad)),
+                        color: piece.map(|piece| piece.color),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Write a board as 10 lines of FEN letters, one column per point
+///
+/// Red pieces are uppercase and Black lowercase, so the position reads
+/// without color or CJK fonts.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::board::Board;
+/// use cn_chess_tui::fen_print::write_board_compact;
+///
+/// let mut out = Vec::new();
+/// write_board_compact(&mut out, &Board::new()).unwrap();
+/// let text = String::from_utf8(out).unwrap();
+/// assert_eq!(text.lines().next(), Some("rnbakabnr"));
+/// assert_eq!(text.lines().last(), Some("RNBAKABNR"));
+/// ```
+pub fn write_board_compact<W: Write>(out: &mut W, board: &Board) -> io::Result<()> {
+    for row in board_thumbnail(board, 1, |piece| piece_to_fen(piece).to_string()) {
+        let line: String = row.iter().map(|cell| cell.text.as_str()).collect();
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
 }
 
 /// Print complete game state with FEN, turn, and move history
@@ -46,27 +266,35 @@ pub fn print_board_ascii(board: &Board) {
 /// This function provides a comprehensive view of the game state
 /// useful for debugging, testing, and FEN preview
 pub fn print_game_state(game: &Game) {
-    println!("FEN: {}", game.to_fen());
-    println!("Turn: {} | State: {}", game.turn(), game.state());
+    write_game_state(&mut io::stdout().lock(), game, PrintStyle::Ascii)
+        .expect("failed printing to stdout");
+}
+
+/// Write the game state printed by [`print_game_state`], drawing the board
+/// in `style`
+pub fn write_game_state<W: Write>(out: &mut W, game: &Game, style: PrintStyle) -> io::Result<()> {
+    writeln!(out, "FEN: {}", game.to_fen())?;
+    writeln!(out, "Turn: {} | State: {}", game.turn(), game.state())?;
 
     if game.is_in_check() {
-        println!("★ CHECK!");
+        writeln!(out, "★ CHECK!")?;
     }
 
-    println!();
-    print_board_ascii(game.board());
+    writeln!(out)?;
+    style.write_board(out, game.board())?;
 
     // Print move history
     let moves = game.get_notated_moves();
     if !moves.is_empty() {
-        println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("            着法记录 Move History");
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        writeln!(out, "\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━")?;
+        writeln!(out, "            着法记录 Move History")?;
+        writeln!(out, "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━")?;
         for (i, (piece, mv)) in moves.iter().enumerate() {
             let notation = move_to_simple_notation(*piece, mv.from, mv.to);
-            println!("  {:2}. {}", i + 1, notation);
+            writeln!(out, "  {:2}. {}", i + 1, notation)?;
         }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -80,4 +308,27 @@ mod tests {
         // Just verify it doesn't panic
         print_board_ascii(&board);
     }
+
+    #[test]
+    fn test_unicode_board_lines_up() {
+        let mut out = Vec::new();
+        write_board_unicode(&mut out, &Board::new()).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 19);
+        assert_eq!(lines[1], "│   │   │   │ ╲ │ ╱ │   │   │   │");
+        assert_eq!(lines[9], UNICODE_RIVER);
+        assert_eq!(lines[10], "├───┬───┬───┬───┬───┬───┬───┬───┤");
+        // A piece on the last file sticks out one column
+        for line in lines {
+            assert!((33..=34).contains(&line.width()), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_print_style_names() {
+        assert_eq!(PrintStyle::by_name("Unicode"), Some(PrintStyle::Unicode));
+        assert_eq!(PrintStyle::by_name("ascii"), Some(PrintStyle::Ascii));
+        assert_eq!(PrintStyle::by_name("svg"), None);
+    }
 }
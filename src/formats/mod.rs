@@ -0,0 +1,6 @@
+//! Readers for game files written by other xiangqi programs
+//!
+//! Each reader turns a file into a [`PgnGame`](crate::pgn::PgnGame), so the
+//! game can be replayed, browsed and saved like one loaded from PGN.
+
+pub mod xqf;
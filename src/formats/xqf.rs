@@ -0,0 +1,473 @@
+//! XQF game files, as written by 象棋演播室 (XQStudio)
+//!
+//! An XQF file is a 1024-byte header followed by the move tree. The header
+//! holds the game's details as length-prefixed GBK strings and the start
+//! position as the squares of the 32 pieces. The tree is stored depth
+//! first, each node a move with flags saying whether a next move and a
+//! variation follow it, so the main line is the run of nodes up to the
+//! first one without a next move. Files from version 11 on are encrypted
+//! with keys kept in the header.
+//!
+//! Only the main line is read; variations are skipped.
+
+use crate::board::Board;
+use crate::fen::board_to_fen;
+use crate::notation::iccs::move_to_iccs;
+use crate::pgn::{PgnGame, PgnGameResult, PgnMove};
+use crate::types::{Color, Piece, PieceType, Position};
+use encoding_rs::GBK;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+
+/// Size of the header before the move tree
+const HEADER_LEN: usize = 1024;
+
+/// Text the stream keys of encrypted files are taken from
+const KEY_TEXT: &[u8; 32] = b"[(C) Copyright Mr. Dong Shiwei.]";
+
+/// Pieces in the order their squares are listed, for each side
+const PIECE_ORDER: [PieceType; 16] = [
+    PieceType::Chariot,
+    PieceType::Horse,
+    PieceType::Elephant,
+    PieceType::Advisor,
+    PieceType::General,
+    PieceType::Advisor,
+    PieceType::Elephant,
+    PieceType::Horse,
+    PieceType::Chariot,
+    PieceType::Cannon,
+    PieceType::Cannon,
+    PieceType::Soldier,
+    PieceType::Soldier,
+    PieceType::Soldier,
+    PieceType::Soldier,
+    PieceType::Soldier,
+];
+
+// Header layout: offsets of single bytes, and offset and size of strings
+const VERSION: usize = 0x02;
+const KEY_MASK: usize = 0x03;
+const KEY_OR: usize = 0x08;
+const KEY_SUM: usize = 0x0c;
+const KEY_XY: usize = 0x0d;
+const KEY_XYF: usize = 0x0e;
+const KEY_XYT: usize = 0x0f;
+const PIECES: usize = 0x10;
+const WHO_PLAYS: usize = 0x32;
+const RESULT: usize = 0x33;
+const TITLE: (usize, usize) = (0x50, 64);
+const EVENT: (usize, usize) = (0xd0, 64);
+const DATE: (usize, usize) = (0x110, 16);
+const SITE: (usize, usize) = (0x120, 16);
+const RED: (usize, usize) = (0x130, 16);
+const BLACK: (usize, usize) = (0x140, 16);
+
+/// Errors reading an XQF file
+#[derive(Debug)]
+pub enum XqfError {
+    Io(std::io::Error),
+    /// The data does not start with the `XQ` signature
+    NotXqf,
+    /// The data ends inside the header or a move
+    Truncated,
+    /// A square is off the board or holds two pieces
+    InvalidSquare(u8),
+}
+
+impl Display for XqfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            XqfError::Io(e) => write!(f, "{}", e),
+            XqfError::NotXqf => write!(f, "Not an XQF file"),
+            XqfError::Truncated => write!(f, "XQF file is truncated"),
+            XqfError::InvalidSquare(square) => {
+                write!(f, "Invalid square in XQF file: {}", square)
+            }
+        }
+    }
+}
+
+impl std::error::Error for XqfError {}
+
+impl From<std::io::Error> for XqfError {
+    fn from(e: std::io::Error) -> Self {
+        XqfError::Io(e)
+    }
+}
+
+/// Read the main line of an XQF file
+pub fn read_xqf_file(path: impl AsRef<Path>) -> Result<PgnGame, XqfError> {
+    parse_xqf(&std::fs::read(path)?)
+}
+
+/// Read the main line of a game in XQF format
+///
+/// The start position becomes a `FEN` tag unless it is the standard one,
+/// and moves are written in ICCS with their comments.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::formats::xqf::{parse_xqf, XqfError};
+///
+/// assert!(matches!(parse_xqf(b"PK\x03\x04"), Err(XqfError::NotXqf)));
+/// ```
+pub fn parse_xqf(data: &[u8]) -> Result<PgnGame, XqfError> {
+    if !data.starts_with(b"XQ") {
+        return Err(XqfError::NotXqf);
+    }
+    let header = data.get(..HEADER_LEN).ok_or(XqfError::Truncated)?;
+    let version = header[VERSION];
+    let keys = Keys::from_header(header);
+    let board = read_board(header, version, &keys)?;
+
+    let mut tree = TreeReader {
+        data,
+        pos: HEADER_LEN,
+        version,
+        keys: &keys,
+    };
+    let mut moves = Vec::new();
+    // The first node holds no move, only the comment on the start position
+    let mut node = tree.next_node()?;
+    while node.has_next {
+        node = tree.next_node()?;
+        moves.push((square(node.from)?, square(node.to)?, node.comment.take()));
+    }
+
+    let turn = match moves.first() {
+        Some((from, _, _)) => board.get(*from).map_or(Color::Red, |p| p.color),
+        None if header[WHO_PLAYS] == 0 => Color::Red,
+        None => Color::Black,
+    };
+
+    let mut game = PgnGame::new();
+    let title = read_string(header, TITLE);
+    let event = read_string(header, EVENT);
+    let tags = [
+        ("Event", if event.is_empty() { title } else { event }),
+        ("Site", read_string(header, SITE)),
+        ("Date", read_string(header, DATE)),
+        ("Red", read_string(header, RED)),
+        ("Black", read_string(header, BLACK)),
+    ];
+    for (key, value) in tags {
+        if !value.is_empty() {
+            game.set_tag(key, value);
+        }
+    }
+    game.result = match header[RESULT] {
+        1 => PgnGameResult::RedWins,
+        2 => PgnGameResult::BlackWins,
+        3 => PgnGameResult::Draw,
+        _ => PgnGameResult::Unknown,
+    };
+    game.set_tag("Result", game.result.to_pgn_string());
+    let fen = board_to_fen(&board, turn, 0, 1);
+    if fen != board_to_fen(&Board::new(), Color::Red, 0, 1) {
+        game.set_tag("FEN", fen);
+    }
+
+    for (ply, (from, to, comment)) in moves.into_iter().enumerate() {
+        let mut mv = PgnMove::new(move_to_iccs(from, to)).with_move_number(ply / 2 + 1);
+        if let Some(comment) = comment {
+            mv = mv.with_comment(comment);
+        }
+        game.moves.push(mv);
+    }
+    Ok(game)
+}
+
+/// Decryption keys, all zero before version 11
+#[derive(Debug, Default)]
+struct Keys {
+    /// Subtracted from the piece squares
+    pieces: u8,
+    /// Subtracted from the source square of each move
+    from: u8,
+    /// Subtracted from the destination square of each move
+    to: u8,
+    /// Subtracted from the length of each comment
+    comment_len: u32,
+    /// Subtracted from the bytes of the move tree, by offset
+    stream: [u8; 32],
+}
+
+impl Keys {
+    fn from_header(header: &[u8]) -> Self {
+        if header[VERSION] <= 10 {
+            return Self::default();
+        }
+        let mask = header[KEY_MASK];
+        let seeds = [
+            header[KEY_SUM],
+            header[KEY_XY],
+            header[KEY_XYF],
+            header[KEY_XYT],
+        ];
+
+        let pieces = derive_key(header[KEY_XY], header[KEY_XY]);
+        let from = derive_key(header[KEY_XYF], pieces);
+        let to = derive_key(header[KEY_XYT], from);
+        let mut stream = [0; 32];
+        for (i, key) in stream.iter_mut().enumerate() {
+            *key = KEY_TEXT[i] & ((seeds[i % 4] & mask) | header[KEY_OR + i % 4]);
+        }
+        Self {
+            pieces,
+            from,
+            to,
+            comment_len: (header[KEY_SUM] as u32 * 256 + header[KEY_XY] as u32) % 32000 + 767,
+            stream,
+        }
+    }
+}
+
+fn derive_key(seed: u8, factor: u8) -> u8 {
+    let seed = seed as u32;
+    ((seed * seed * 54 + 221) * factor as u32) as u8
+}
+
+/// Place the 32 pieces; a square off the board means a captured piece
+fn read_board(header: &[u8], version: u8, keys: &Keys) -> Result<Board, XqfError> {
+    let stored = &header[PIECES..PIECES + 32];
+    let mut squares = [0u8; 32];
+    for (i, &square) in stored.iter().enumerate() {
+        // Version 12 on also rotates the list
+        let slot = if version >= 12 {
+            (keys.pieces as usize + 1 + i) % 32
+        } else {
+            i
+        };
+        squares[slot] = square.wrapping_sub(keys.pieces);
+    }
+
+    let mut pieces = HashMap::new();
+    for (i, &sq) in squares.iter().enumerate() {
+        if sq >= 90 {
+            continue;
+        }
+        let color = if i < 16 { Color::Red } else { Color::Black };
+        let piece = Piece::new(PIECE_ORDER[i % 16], color);
+        if pieces.insert(square(sq)?, piece).is_some() {
+            return Err(XqfError::InvalidSquare(sq));
+        }
+    }
+    Ok(Board::from_pieces(pieces))
+}
+
+/// A square numbered file * 10 + rank, counted from Red's bottom left
+fn square(sq: u8) -> Result<Position, XqfError> {
+    if sq >= 90 {
+        return Err(XqfError::InvalidSquare(sq));
+    }
+    Ok(Position::from_xy(
+        (sq / 10) as usize,
+        9 - (sq % 10) as usize,
+    ))
+}
+
+/// Read a length-prefixed GBK string from a header field
+fn read_string(header: &[u8], (offset, size): (usize, usize)) -> String {
+    let len = (header[offset] as usize).min(size - 1);
+    let bytes = &header[offset + 1..offset + 1 + len];
+    GBK.decode(bytes).0.trim_matches(['\0', ' ']).to_string()
+}
+
+/// One node of the move tree
+struct Node {
+    from: u8,
+    to: u8,
+    has_next: bool,
+    comment: Option<String>,
+}
+
+/// Reads move tree nodes one after another, decrypting as it goes
+struct TreeReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    version: u8,
+    keys: &'a Keys,
+}
+
+impl TreeReader<'_> {
+    fn read(&mut self, len: usize) -> Result<Vec<u8>, XqfError> {
+        let end = self.pos.checked_add(len).ok_or(XqfError::Truncated)?;
+        let bytes = self.data.get(self.pos..end).ok_or(XqfError::Truncated)?;
+        let decoded = bytes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| b.wrapping_sub(self.keys.stream[(self.pos + i) % 32]))
+            .collect();
+        self.pos = end;
+        Ok(decoded)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, XqfError> {
+        let bytes = self.read(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn next_node(&mut self) -> Result<Node, XqfError> {
+        let step = self.read(4)?;
+        let (has_next, comment_len) = if self.version <= 10 {
+            // Every node has a comment length, often 0
+            (step[2] & 0xf0 != 0, self.read_u32()?)
+        } else {
+            let len = if step[2] & 0x20 != 0 {
+                self.read_u32()?.wrapping_sub(self.keys.comment_len)
+            } else {
+                0
+            };
+            (step[2] & 0x80 != 0, len)
+        };
+        let comment = self.read(comment_len as usize)?;
+        let comment = GBK.decode(&comment).0.trim().to_string();
+
+        Ok(Node {
+            from: step[0].wrapping_sub(0x18).wrapping_sub(self.keys.from),
+            to: step[1].wrapping_sub(0x20).wrapping_sub(self.keys.to),
+            has_next,
+            comment: (!comment.is_empty()).then_some(comment),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Squares of the pieces in the standard start position
+    const START: [u8; 32] = [
+        0, 10, 20, 30, 40, 50, 60, 70, 80, 12, 72, 3, 23, 43, 63, 83, //
+        9, 19, 29, 39, 49, 59, 69, 79, 89, 17, 77, 6, 26, 46, 66, 86,
+    ];
+
+    fn pascal(header: &mut [u8], (offset, _): (usize, usize), text: &str) {
+        let bytes = GBK.encode(text).0;
+        header[offset] = bytes.len() as u8;
+        header[offset + 1..offset + 1 + bytes.len()].copy_from_slice(&bytes);
+    }
+
+    /// A version 10 (unencrypted) file; each move is (from, to, comment)
+    fn sample(pieces: [u8; 32], moves: &[(u8, u8, &str)]) -> Vec<u8> {
+        let mut data = vec![0u8; HEADER_LEN];
+        data[..3].copy_from_slice(b"XQ\x0a");
+        data[PIECES..PIECES + 32].copy_from_slice(&pieces);
+        data[RESULT] = 1;
+        pascal(&mut data, EVENT, "全国象棋个人赛");
+        pascal(&mut data, RED, "胡荣华");
+        pascal(&mut data, BLACK, "柳大华");
+
+        let mut node = |from: u8, to: u8, next: bool, comment: &str| {
+            let comment = GBK.encode(comment).0;
+            data.extend([from + 0x18, to + 0x20, if next { 0xf0 } else { 0 }, 0]);
+            data.extend((comment.len() as u32).to_le_bytes());
+            data.extend(comment.iter());
+        };
+        node(0, 0, !moves.is_empty(), "开局");
+        for (i, (from, to, comment)) in moves.iter().enumerate() {
+            node(*from, *to, i + 1 < moves.len(), comment);
+        }
+        data
+    }
+
+    #[test]
+    fn test_reads_header_and_main_line() {
+        // Central cannon, answered by the horse, with a comment
+        let data = sample(START, &[(72, 42, ""), (79, 67, "屏风马")]);
+        let game = parse_xqf(&data).unwrap();
+
+        assert_eq!(game.get_tag("Event").unwrap(), "全国象棋个人赛");
+        assert_eq!(game.get_tag("Red").unwrap(), "胡荣华");
+        assert_eq!(game.get_tag("Black").unwrap(), "柳大华");
+        assert_eq!(game.get_tag("FEN"), None);
+        assert_eq!(game.result, PgnGameResult::RedWins);
+        let moves: Vec<&str> = game.moves.iter().map(|m| m.notation.as_str()).collect();
+        assert_eq!(moves, ["h7e7", "h0g2"]);
+        assert_eq!(game.moves[1].comment.as_deref(), Some("屏风马"));
+    }
+
+    #[test]
+    fn test_setup_position_becomes_fen() {
+        // Generals and one Red chariot; everything else captured
+        let mut pieces = [0xff; 32];
+        pieces[0] = 5;
+        pieces[4] = 40;
+        pieces[20] = 49;
+        let game = parse_xqf(&sample(pieces, &[(5, 8, "")])).unwrap();
+        assert_eq!(
+            game.get_tag("FEN").unwrap(),
+            "4k4/9/9/9/R8/9/9/9/9/4K4 w - - 0 1"
+        );
+        assert_eq!(game.moves[0].notation, "a4a1");
+    }
+
+    #[test]
+    fn test_variations_are_skipped() {
+        let mut data = sample(START, &[(72, 42, "")]);
+        // Mark the first move as having a variation, which follows it
+        let first = HEADER_LEN + 8 + 4;
+        data[first + 2] = 0x0f;
+        data.extend([12 + 0x18, 42 + 0x20, 0, 0, 0, 0, 0, 0]);
+        let game = parse_xqf(&data).unwrap();
+        assert_eq!(game.moves.len(), 1);
+    }
+
+    #[test]
+    fn test_decrypts_version_18() {
+        let plain = sample(START, &[(72, 42, "炮二平五")]);
+
+        // Encrypt it the way version 18 files are
+        let mut data = plain.clone();
+        data[VERSION] = 18;
+        data[KEY_MASK] = 0xa5;
+        data[KEY_OR..KEY_OR + 4].copy_from_slice(&[1, 2, 3, 4]);
+        data[KEY_SUM] = 7;
+        data[KEY_XY] = 11;
+        data[KEY_XYF] = 13;
+        data[KEY_XYT] = 17;
+        let keys = Keys::from_header(&data);
+        for (i, square) in START.iter().enumerate() {
+            let slot = (i as isize - keys.pieces as isize - 1).rem_euclid(32) as usize;
+            data[PIECES + slot] = square.wrapping_add(keys.pieces);
+        }
+        let comment = GBK.encode("炮二平五").0;
+        data.truncate(HEADER_LEN);
+        data.extend([0x18, 0x20, 0x80, 0]);
+        data.extend([
+            (72 + 0x18u8).wrapping_add(keys.from),
+            (42 + 0x20u8).wrapping_add(keys.to),
+            0x20,
+            0,
+        ]);
+        data.extend((comment.len() as u32 + keys.comment_len).to_le_bytes());
+        data.extend(comment.iter());
+        for (pos, byte) in data.iter_mut().enumerate().skip(HEADER_LEN) {
+            *byte = byte.wrapping_add(keys.stream[pos % 32]);
+        }
+
+        let game = parse_xqf(&data).unwrap();
+        assert_eq!(game.moves.len(), 1);
+        assert_eq!(game.moves[0].notation, "h7e7");
+        assert_eq!(game.moves[0].comment.as_deref(), Some("炮二平五"));
+        assert_eq!(game.get_tag("FEN"), None);
+    }
+
+    #[test]
+    fn test_rejects_bad_files() {
+        assert!(matches!(parse_xqf(b"XQ\x0a"), Err(XqfError::Truncated)));
+
+        let mut data = sample(START, &[(72, 42, "")]);
+        data.truncate(data.len() - 2);
+        assert!(matches!(parse_xqf(&data), Err(XqfError::Truncated)));
+
+        let mut pieces = START;
+        pieces[1] = pieces[0];
+        assert!(matches!(
+            parse_xqf(&sample(pieces, &[])),
+            Err(XqfError::InvalidSquare(0))
+        ));
+    }
+}
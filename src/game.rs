@@ -1,12 +1,25 @@
-use crate::board::Board;
+use crate::ai::{Searcher, DEFAULT_SEARCH_DEPTH};
+use crate::board::{Board, IllegalMoveReason};
+use crate::config::Config;
+use crate::error::Error;
+use crate::events::{GameEvent, Observers};
 use crate::fen::FenError;
+use crate::notation::format::MoveStyle;
 use crate::notation::iccs;
-use crate::notation::move_to_chinese_with_context;
-use crate::pgn::{PgnGame, PgnGameResult};
-use crate::types::{Color, Position};
-use crate::ucci::UcciClient;
+use crate::notation::{move_to_chinese_with_style, move_to_wxf, NumeralStyle};
+use crate::opening::OpeningBook;
+use crate::pgn::{move_time_command, PgnGame, PgnGameResult, PgnMove};
+use crate::rng::{RandomSource, SeededRng};
+use crate::types::{Color, Piece, PieceType, Position};
+use crate::ucci::search::EngineSearch;
+use crate::ucci::{EngineProtocol, Info, MoveResult, PopHashInfo, PvLine, UcciClient};
+use crate::zobrist;
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display, Formatter};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
 
 /// Result of a completed game
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,12 +39,90 @@ impl Display for GameResult {
     }
 }
 
+/// Number of times a position must occur for the repetition rules to apply
+pub const REPETITION_LIMIT: usize = 3;
+
+/// Default natural-move limit: moves per side without a capture before the
+/// game is drawn (the 60-move rule)
+pub const DEFAULT_MOVE_LIMIT: u32 = 60;
+
+/// Deepest search of the built-in AI for a hint
+const HINT_DEPTH: u32 = 3;
+
+/// Time an engine is given to find a hint, in milliseconds
+const HINT_TIME_MS: u64 = 500;
+
+/// Longest an engine may think about a move, in milliseconds, before it is
+/// told to stop
+pub const DEFAULT_ENGINE_TIMEOUT_MS: u64 = 30_000;
+
+/// How long an engine gets to answer `stop` before a random move is played
+const STOP_GRACE_MS: u64 = 1_000;
+
+/// How a stalemate (no legal move while not in check) is scored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StalemateRule {
+    /// The game is drawn
+    #[default]
+    Draw,
+    /// The stalemated side loses (困毙), as in the standard Asian rules
+    Loss,
+}
+
 /// Current state of the game
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameState {
     Playing,
+    /// The given color won by checkmate
     Checkmate(Color),
+    /// No legal move while not in check, scored as a draw
     Stalemate,
+    /// The given color won because the other side was stalemated (困毙),
+    /// under [`StalemateRule::Loss`]
+    StalemateWin(Color),
+    /// The same position occurred [`REPETITION_LIMIT`] times
+    DrawByRepetition,
+    /// The given color won because the other side checked perpetually
+    PerpetualCheck(Color),
+    /// The natural-move limit passed without a capture
+    DrawByMoveLimit,
+    /// The given color has no general on the board and has lost
+    ///
+    /// Only reachable from positions loaded with
+    /// [`Game::from_fen_permissive`] or by capturing a general in free
+    /// analysis. If both generals are missing, the side to move is reported.
+    MissingGeneral(Color),
+    /// The given color won because the other side ran out of time
+    Timeout(Color),
+    /// An AI-vs-AI game was ended by the [`Adjudication`] rules, won by the
+    /// given color or drawn
+    Adjudicated(Option<Color>),
+}
+
+impl GameState {
+    /// The result of a finished game, or None while still playing
+    pub fn result(self) -> Option<GameResult> {
+        match self {
+            GameState::Playing => None,
+            GameState::Checkmate(Color::Red)
+            | GameState::StalemateWin(Color::Red)
+            | GameState::PerpetualCheck(Color::Red)
+            | GameState::Timeout(Color::Red)
+            | GameState::Adjudicated(Some(Color::Red))
+            | GameState::MissingGeneral(Color::Black) => Some(GameResult::RedWins),
+            GameState::Checkmate(Color::Black)
+            | GameState::StalemateWin(Color::Black)
+            | GameState::PerpetualCheck(Color::Black)
+            | GameState::Timeout(Color::Black)
+            | GameState::Adjudicated(Some(Color::Black))
+            | GameState::MissingGeneral(Color::Red) => Some(GameResult::BlackWins),
+            GameState::Stalemate
+            | GameState::DrawByRepetition
+            | GameState::DrawByMoveLimit
+            | GameState::Adjudicated(None) => Some(GameResult::Draw),
+        }
+    }
 }
 
 impl Display for GameState {
@@ -40,12 +131,23 @@ impl Display for GameState {
             GameState::Playing => write!(f, "Playing"),
             GameState::Checkmate(color) => write!(f, "Checkmate - {} Wins", color),
             GameState::Stalemate => write!(f, "Stalemate"),
+            GameState::StalemateWin(color) => write!(f, "Stalemate - {} Wins", color),
+            GameState::DrawByRepetition => write!(f, "Draw by Repetition"),
+            GameState::PerpetualCheck(color) => {
+                write!(f, "Perpetual Check - {} Wins", color)
+            }
+            GameState::DrawByMoveLimit => write!(f, "Draw by Move Limit"),
+            GameState::MissingGeneral(color) => write!(f, "{} Has No General", color),
+            GameState::Timeout(color) => write!(f, "Time Forfeit - {} Wins", color),
+            GameState::Adjudicated(Some(color)) => write!(f, "Adjudicated - {} Wins", color),
+            GameState::Adjudicated(None) => write!(f, "Adjudicated Draw"),
         }
     }
 }
 
 /// AI mode for game controller
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum AiMode {
     Off,        // Player vs Player
     PlaysRed,   // AI plays Red
@@ -53,11 +155,487 @@ pub enum AiMode {
     PlaysBoth,  // AI vs AI (spectator mode)
 }
 
+impl AiMode {
+    /// The same mode with the AI playing the other color
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::AiMode;
+    ///
+    /// assert_eq!(AiMode::PlaysBlack.swapped(), AiMode::PlaysRed);
+    /// assert_eq!(AiMode::Off.swapped(), AiMode::Off);
+    /// ```
+    pub fn swapped(self) -> Self {
+        match self {
+            AiMode::PlaysRed => AiMode::PlaysBlack,
+            AiMode::PlaysBlack => AiMode::PlaysRed,
+            other => other,
+        }
+    }
+}
+
+/// Time each side gets for the game, with a bonus after each move
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::game::TimeControl;
+///
+/// let blitz = TimeControl::minutes(5, 3);
+/// assert_eq!(blitz.base_ms, 300_000);
+/// assert_eq!(blitz.to_string(), "5+3");
+/// assert_eq!("5+3".parse(), Ok(blitz));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct TimeControl {
+    /// Time on each side's clock at the start
+    pub base_ms: u64,
+    /// Time added to a side's clock after each of its moves
+    pub increment_ms: u64,
+}
+
+impl TimeControl {
+    /// Time controls offered when starting a new game
+    pub const PRESETS: [TimeControl; 4] = [
+        TimeControl::minutes(5, 3),
+        TimeControl::minutes(10, 5),
+        TimeControl::minutes(15, 10),
+        TimeControl::minutes(30, 0),
+    ];
+
+    /// A time control of `base` minutes plus `increment` seconds a move
+    pub const fn minutes(base: u64, increment: u64) -> Self {
+        Self {
+            base_ms: base * 60_000,
+            increment_ms: increment * 1000,
+        }
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{}", self.base_ms / 60_000, self.increment_ms / 1000)
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    /// Read `minutes+seconds`, e.g. `10+5`; a bare number has no increment
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (base, increment) = s.trim().split_once('+').unwrap_or((s.trim(), "0"));
+        // Values too large for a clock in milliseconds are refused too
+        let base_ms = base
+            .parse::<u64>()
+            .ok()
+            .filter(|&base| base > 0)
+            .and_then(|base| base.checked_mul(60_000));
+        let increment_ms = increment
+            .parse::<u64>()
+            .ok()
+            .and_then(|increment| increment.checked_mul(1000));
+        match (base_ms, increment_ms) {
+            (Some(base_ms), Some(increment_ms)) => Ok(Self {
+                base_ms,
+                increment_ms,
+            }),
+            _ => Err(format!(
+                "Invalid time control (expected minutes+seconds, e.g. 10+5): {}",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for TimeControl {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// How far an engine searches for each move, the AI's or a match's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchLimit {
+    /// Search to a depth in plies
+    Depth(u32),
+    /// Search for a time in milliseconds
+    Time(u64),
+    /// Search a number of positions
+    Nodes(u64),
+}
+
+impl Default for SearchLimit {
+    fn default() -> Self {
+        SearchLimit::Depth(10)
+    }
+}
+
+impl Display for SearchLimit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchLimit::Depth(depth) => write!(f, "depth {}", depth),
+            SearchLimit::Time(ms) => write!(f, "{} ms/move", ms),
+            SearchLimit::Nodes(nodes) => write!(f, "{} nodes/move", nodes),
+        }
+    }
+}
+
+/// Preset strength for the AI, in place of its search settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Advanced,
+    Max,
+}
+
+impl Difficulty {
+    /// All levels, weakest first
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Beginner,
+        Difficulty::Intermediate,
+        Difficulty::Advanced,
+        Difficulty::Max,
+    ];
+
+    /// The level's name, as written in `config.toml`
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Intermediate => "intermediate",
+            Difficulty::Advanced => "advanced",
+            Difficulty::Max => "max",
+        }
+    }
+
+    /// Find a level by name, ignoring case
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::game::Difficulty;
+    ///
+    /// assert_eq!(Difficulty::by_name("Beginner"), Some(Difficulty::Beginner));
+    /// assert_eq!(Difficulty::by_name("grandmaster"), None);
+    /// ```
+    pub fn by_name(name: &str) -> Option<Difficulty> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Search depth in plies for the built-in AI
+    pub fn search_depth(self) -> u32 {
+        match self {
+            Difficulty::Beginner => 1,
+            Difficulty::Intermediate => 2,
+            Difficulty::Advanced => 3,
+            Difficulty::Max => 4,
+        }
+    }
+
+    /// Centipawn margin within which the built-in AI picks at random; the
+    /// lower levels miss the best move now and then
+    pub fn variety(self) -> i32 {
+        match self {
+            Difficulty::Beginner => 150,
+            Difficulty::Intermediate => 40,
+            Difficulty::Advanced | Difficulty::Max => 0,
+        }
+    }
+
+    /// How far an engine searches for each move
+    pub fn engine_limit(self) -> SearchLimit {
+        match self {
+            Difficulty::Beginner => SearchLimit::Depth(1),
+            Difficulty::Intermediate => SearchLimit::Depth(4),
+            Difficulty::Advanced => SearchLimit::Depth(10),
+            Difficulty::Max => SearchLimit::Time(10_000),
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Intermediate => "Intermediate",
+            Difficulty::Advanced => "Advanced",
+            Difficulty::Max => "Max",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Settings for one side that replace the shared ones in [`AiConfig`],
+/// for games where Red and Black get different engines or strengths
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SideConfig {
+    /// Engine that plays this side instead of the shared one
+    pub engine_path: Option<PathBuf>,
+    /// Search depth (in plies) for the built-in AI
+    pub search_depth: Option<u32>,
+    /// How far the engine searches for each move
+    pub engine_limit: Option<SearchLimit>,
+    /// Preset strength for this side
+    pub difficulty: Option<Difficulty>,
+}
+
+impl SideConfig {
+    /// Whether the side plays with the shared settings
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Rules that end an AI-vs-AI game early, for spectating engines that
+/// would otherwise play out dead positions
+///
+/// Evaluations are those the AI reported for its own moves, so a rule
+/// counts only plies it searched. Every rule is off unless its ply count
+/// is set.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::game::{Adjudication, GameState, MoveStats};
+/// use cn_chess_tui::Color;
+///
+/// let rules = Adjudication { resign_plies: Some(2), resign_score: 500, ..Adjudication::default() };
+/// let eval = |eval| MoveStats { eval: Some(eval), time_ms: None };
+/// assert_eq!(rules.verdict(&[eval(-100), eval(-600)]), None);
+/// assert_eq!(
+///     rules.verdict(&[eval(-600), eval(-700)]),
+///     Some(GameState::Adjudicated(Some(Color::Black)))
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Adjudication {
+    /// Plies after which the game is drawn
+    pub max_plies: Option<usize>,
+    /// Consecutive plies evaluated within `draw_score` of level after which
+    /// the game is drawn
+    pub draw_plies: Option<usize>,
+    /// Largest evaluation, in centipawns either way, that counts as level
+    pub draw_score: i32,
+    /// Consecutive plies evaluated at least `resign_score` for one side
+    /// after which the other side resigns
+    pub resign_plies: Option<usize>,
+    /// Smallest lead, in centipawns, that makes the losing side resign
+    pub resign_score: i32,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Self {
+            max_plies: None,
+            draw_plies: None,
+            draw_score: 20,
+            resign_plies: None,
+            resign_score: 1000,
+        }
+    }
+}
+
+impl Adjudication {
+    /// How the rules end a game whose moves so far have `stats`, or None
+    /// if it plays on
+    ///
+    /// A resignation is checked first, then a level position, then the
+    /// ply limit.
+    pub fn verdict(&self, stats: &[MoveStats]) -> Option<GameState> {
+        let last_evals = |plies: usize| -> Option<Vec<i32>> {
+            if plies == 0 || stats.len() < plies {
+                return None;
+            }
+            stats[stats.len() - plies..]
+                .iter()
+                .map(|s| s.eval)
+                .collect()
+        };
+        if let Some(evals) = self.resign_plies.and_then(last_evals) {
+            if evals.iter().all(|&e| e >= self.resign_score) {
+                return Some(GameState::Adjudicated(Some(Color::Red)));
+            }
+            if evals.iter().all(|&e| e <= -self.resign_score) {
+                return Some(GameState::Adjudicated(Some(Color::Black)));
+            }
+        }
+        if let Some(evals) = self.draw_plies.and_then(last_evals) {
+            if evals.iter().all(|e| e.abs() <= self.draw_score) {
+                return Some(GameState::Adjudicated(None));
+            }
+        }
+        match self.max_plies {
+            Some(max) if stats.len() >= max => Some(GameState::Adjudicated(None)),
+            _ => None,
+        }
+    }
+}
+
 /// AI configuration
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct AiConfig {
     pub engine_path: Option<PathBuf>,
+    /// Protocol the engine speaks; detected when `Auto`
+    pub engine_protocol: EngineProtocol,
+    /// Let the engine search on the player's time, on the reply it expects
+    pub ponder: bool,
     pub show_thinking: bool,
+    /// Mark the first moves of the engine's principal variation on the board
+    pub show_pv: bool,
+    /// Search depth (in plies) for the built-in AI
+    pub search_depth: u32,
+    /// How far the engine searches for each move
+    pub engine_limit: SearchLimit,
+    /// Wall-clock time in milliseconds after which the engine is stopped,
+    /// whatever `engine_limit` says
+    pub engine_timeout_ms: u64,
+    /// Built-in AI plays a random move within this many centipawns of the
+    /// best one; 0 always plays the best move
+    pub variety: i32,
+    /// Percent of its moves the built-in AI picks from every legal move
+    /// instead of the near-best ones; 0 never blunders
+    pub blunder_rate: u32,
+    /// Seed for the AI's random choices; None seeds from the clock
+    pub seed: Option<u64>,
+    /// Built-in AI plays a random book move while the game is in the
+    /// opening book
+    pub opening_book: bool,
+    /// Preset strength; when set it decides the search depth, variety and
+    /// engine limit instead of the fields above
+    pub difficulty: Option<Difficulty>,
+    /// Overrides for the AI when it plays Red
+    pub red: SideConfig,
+    /// Overrides for the AI when it plays Black
+    pub black: SideConfig,
+    /// When a game the AI plays against itself is ended early
+    pub adjudication: Adjudication,
+    /// How many of the best moves analysis and hints show; engines search
+    /// that many lines at once if they have a `MultiPV` option
+    pub multipv: u32,
+}
+
+impl AiConfig {
+    /// The overrides for `color`
+    pub fn side(&self, color: Color) -> &SideConfig {
+        match color {
+            Color::Red => &self.red,
+            Color::Black => &self.black,
+        }
+    }
+
+    /// The overrides for `color`, to change
+    pub fn side_mut(&mut self, color: Color) -> &mut SideConfig {
+        match color {
+            Color::Red => &mut self.red,
+            Color::Black => &mut self.black,
+        }
+    }
+
+    /// The settings the AI plays `color` with: the shared ones, with that
+    /// side's overrides applied
+    ///
+    /// A side given its own depth or engine limit but no difficulty
+    /// doesn't take the shared difficulty, which would override them.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::game::{AiConfig, Difficulty};
+    /// use cn_chess_tui::Color;
+    ///
+    /// let mut config = AiConfig { difficulty: Some(Difficulty::Max), ..AiConfig::default() };
+    /// config.red.search_depth = Some(2);
+    /// assert_eq!(config.for_side(Color::Red).effective_search_depth(), 2);
+    /// assert_eq!(config.for_side(Color::Black).difficulty, Some(Difficulty::Max));
+    /// ```
+    pub fn for_side(&self, color: Color) -> AiConfig {
+        let side = self.side(color);
+        let mut config = AiConfig {
+            red: SideConfig::default(),
+            black: SideConfig::default(),
+            ..self.clone()
+        };
+        if side.search_depth.is_some() || side.engine_limit.is_some() {
+            config.difficulty = None;
+        }
+        if let Some(path) = &side.engine_path {
+            config.engine_path = Some(path.clone());
+        }
+        if let Some(depth) = side.search_depth {
+            config.search_depth = depth;
+        }
+        if let Some(limit) = side.engine_limit {
+            config.engine_limit = limit;
+        }
+        if side.difficulty.is_some() {
+            config.difficulty = side.difficulty;
+        }
+        config
+    }
+
+    /// Search depth for the built-in AI, from the difficulty if one is set
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::game::{AiConfig, Difficulty};
+    ///
+    /// let mut config = AiConfig { search_depth: 5, ..AiConfig::default() };
+    /// assert_eq!(config.effective_search_depth(), 5);
+    /// config.difficulty = Some(Difficulty::Beginner);
+    /// assert_eq!(config.effective_search_depth(), 1);
+    /// ```
+    pub fn effective_search_depth(&self) -> u32 {
+        self.difficulty
+            .map_or(self.search_depth, Difficulty::search_depth)
+    }
+
+    /// Random-choice margin for the built-in AI, from the difficulty if
+    /// one is set
+    pub fn effective_variety(&self) -> i32 {
+        self.difficulty.map_or(self.variety, Difficulty::variety)
+    }
+
+    /// Engine search limit, from the difficulty if one is set
+    pub fn effective_engine_limit(&self) -> SearchLimit {
+        self.difficulty
+            .map_or(self.engine_limit, Difficulty::engine_limit)
+    }
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self {
+            engine_path: None,
+            engine_protocol: EngineProtocol::Auto,
+            ponder: false,
+            show_thinking: false,
+            show_pv: true,
+            search_depth: DEFAULT_SEARCH_DEPTH,
+            engine_limit: SearchLimit::default(),
+            engine_timeout_ms: DEFAULT_ENGINE_TIMEOUT_MS,
+            variety: 0,
+            blunder_rate: 0,
+            seed: None,
+            opening_book: true,
+            difficulty: None,
+            red: SideConfig::default(),
+            black: SideConfig::default(),
+            adjudication: Adjudication::default(),
+            multipv: 1,
+        }
+    }
+}
+
+/// A move suggested by a hint, with its score for the side to move in
+/// centipawns when the search gave one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    pub from: Position,
+    pub to: Position,
+    pub score: Option<i32>,
 }
 
 /// A single move record with from and to positions
@@ -73,14 +651,78 @@ impl Move {
     }
 }
 
+/// Legal move counts for one side's pieces
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mobility {
+    /// Each piece with its number of legal moves, in board order
+    pub pieces: Vec<(Position, Piece, usize)>,
+}
+
+impl Mobility {
+    /// Total number of legal moves
+    pub fn total(&self) -> usize {
+        self.pieces.iter().map(|&(_, _, n)| n).sum()
+    }
+
+    /// Legal moves available to all pieces of one type
+    pub fn by_type(&self, piece_type: PieceType) -> usize {
+        self.pieces
+            .iter()
+            .filter(|(_, piece, _)| piece.piece_type == piece_type)
+            .map(|&(_, _, n)| n)
+            .sum()
+    }
+
+    /// Legal moves of the piece at a position (0 if there is none)
+    pub fn at(&self, pos: Position) -> usize {
+        self.pieces
+            .iter()
+            .find(|(p, _, _)| *p == pos)
+            .map_or(0, |&(_, _, n)| n)
+    }
+}
+
+/// What the controller saw of one move as it was played
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MoveStats {
+    /// Evaluation of the position after the move, in centipawns from Red's
+    /// side; known when the engine or built-in AI searched it
+    pub eval: Option<i32>,
+    /// Time the side took to play the move
+    pub time_ms: Option<u64>,
+}
+
+/// How [`Game::to_pgn_with`] writes a game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PgnExportOptions {
+    /// Notation of the moves, recorded in the `Format` tag
+    pub notation: MoveStyle,
+}
+
+/// Index of `color`'s clock in [`GameController`]
+fn side_index(color: Color) -> usize {
+    match color {
+        Color::Red => 0,
+        Color::Black => 1,
+    }
+}
+
+/// A score from `side`'s point of view, turned to Red's
+fn red_score(score: i32, side: Color) -> i32 {
+    match side {
+        Color::Red => score,
+        Color::Black => -score,
+    }
+}
+
 /// Errors that can occur during move operations
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MoveError {
     NoPieceAtPosition,
     WrongTurn(Color),
     InvalidMove,
-    #[allow(dead_code)]
-    WouldLeaveInCheck,
+    /// The move breaks a rule, for the reason given
+    Illegal(IllegalMoveReason),
     GameOver(GameResult),
 }
 
@@ -90,7 +732,7 @@ impl Display for MoveError {
             MoveError::NoPieceAtPosition => write!(f, "No piece at the specified position"),
             MoveError::WrongTurn(color) => write!(f, "It is {}'s turn", color),
             MoveError::InvalidMove => write!(f, "Invalid move according to chess rules"),
-            MoveError::WouldLeaveInCheck => write!(f, "Move would leave your general in check"),
+            MoveError::Illegal(reason) => write!(f, "{}", reason),
             MoveError::GameOver(result) => write!(f, "Game is over: {}", result),
         }
     }
@@ -105,6 +747,23 @@ pub struct Game {
     turn: Color,
     move_history: Vec<MoveRecord>,
     state: GameState,
+    /// Analysis mode: either side may move regardless of whose turn it is
+    free_turn: bool,
+    /// Zobrist hash of the starting position and of the position after
+    /// each move
+    positions: Vec<u64>,
+    /// Halfmove clock of the starting position, from its FEN
+    start_halfmove_clock: u32,
+    /// Fullmove number of the starting position, from its FEN
+    start_fullmove: u32,
+    /// Moves per side without a capture before a draw; None disables the rule
+    move_limit: Option<u32>,
+    stalemate_rule: StalemateRule,
+    /// Moves taken back with undo, with their comments and variations,
+    /// the most recently undone last
+    redo_stack: Vec<MoveRecord>,
+    /// Channels following the game's events
+    observers: Observers,
 }
 
 /// Internal record for move history (includes captured piece info)
@@ -113,6 +772,39 @@ struct MoveRecord {
     mv: Move,
     piece: crate::types::Piece,
     captured: Option<crate::types::Piece>,
+    /// Whether the move put the opponent in check
+    gave_check: bool,
+    /// The move in Chinese notation, written before it was played so
+    /// pieces sharing a file are told apart (前/后)
+    chinese: String,
+    /// The move in WXF notation
+    wxf: String,
+    /// Annotation written after the move
+    comment: Option<String>,
+    /// Wall-clock time the side took to play the move, in milliseconds
+    time_ms: Option<u64>,
+    /// Lines played instead of this move, in ICCS
+    variations: Vec<Vec<PgnMove>>,
+}
+
+impl MoveRecord {
+    /// The comment written after the move in PGN, led by its time if known
+    fn pgn_comment(&self) -> Option<String> {
+        let time = self.time_ms.map(move_time_command);
+        match (time, &self.comment) {
+            (Some(time), Some(comment)) => Some(format!("{} {}", time, comment)),
+            (time, comment) => time.or_else(|| comment.clone()),
+        }
+    }
+
+    /// The move written in `style`
+    fn notation(&self, style: MoveStyle) -> String {
+        match style {
+            MoveStyle::Chinese => self.chinese.clone(),
+            MoveStyle::Wxf => self.wxf.clone(),
+            MoveStyle::Iccs => iccs::move_to_iccs(self.mv.from, self.mv.to),
+        }
+    }
 }
 
 /// Game controller with AI support
@@ -120,8 +812,82 @@ pub struct GameController {
     game: Game,
     ai_mode: AiMode,
     ai_client: Option<UcciClient>,
+    /// Engines that play Red and Black instead of `ai_client`
+    side_engines: [Option<UcciClient>; 2],
     ai_config: AiConfig,
     engine_thinking: bool,
+    /// Search info for the AI's last move, with the ply it was played at
+    last_search: Option<(usize, Info)>,
+    /// The AI's move, found but not yet played, with the reply its engine
+    /// expects
+    engine_move: Option<(Move, Option<String>)>,
+    /// An engine searching for the AI's move
+    search: Option<Search>,
+    /// Where the AI's moves are announced before they are played
+    move_observers: Vec<Sender<Move>>,
+    /// The reply the engine is pondering on
+    pondering: Option<Move>,
+    rng: SeededRng,
+    analysis: Option<Analysis>,
+    /// Statistics for each move played through the controller, by ply
+    stats: Vec<MoveStats>,
+    /// When the side to move started thinking
+    turn_started: Instant,
+    /// When the engine was asked for the AI's move
+    search_started: Instant,
+    /// Time controls for Red and Black; a side without one has no clock
+    time_controls: [Option<TimeControl>; 2],
+    /// Time left for Red and Black when the side to move started thinking
+    clocks: [u64; 2],
+}
+
+/// An infinite engine search on the current position
+struct Analysis {
+    /// FEN of the position being analyzed
+    fen: String,
+    /// The latest info with a principal variation
+    info: Option<Info>,
+    /// The latest info for each of the engine's lines, best first
+    lines: Vec<PvLine>,
+    /// What the engine's hash table held for the position before the
+    /// search, if it answers `probe`
+    hash: Option<PopHashInfo>,
+}
+
+/// An engine searching for the AI's move on a thread of its own
+struct Search {
+    engine: EngineSearch,
+    /// The side whose own engine searches, or None for the shared one
+    slot: Option<Color>,
+    /// The move played if the engine gives none
+    fallback: Option<(Position, Position)>,
+    /// Moves played before the search
+    ply: usize,
+}
+
+/// `side`, if a move by it after one by `last` breaks the alternation of
+/// turns PGN numbering assumes, as free analysis allows
+fn side_out_of_turn(last: Option<Color>, side: Color) -> Option<Color> {
+    let expected = match last {
+        Some(Color::Red) => Color::Black,
+        _ => Color::Red,
+    };
+    (side != expected).then_some(side)
+}
+
+/// The first move of each engine line, with its score
+fn candidates_from_lines(lines: &[PvLine]) -> Vec<Candidate> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let (from, to) = iccs::iccs_to_move(line.pv.first()?)?;
+            Some(Candidate {
+                from,
+                to,
+                score: line.score,
+            })
+        })
+        .collect()
 }
 
 impl Default for GameController {
@@ -139,10 +905,18 @@ impl Game {
         let state = GameState::Playing;
 
         Self {
+            positions: vec![zobrist::hash(&board, turn)],
             board,
             turn,
             move_history,
             state,
+            free_turn: false,
+            start_halfmove_clock: 0,
+            start_fullmove: 1,
+            move_limit: Some(DEFAULT_MOVE_LIMIT),
+            stalemate_rule: StalemateRule::default(),
+            redo_stack: Vec::new(),
+            observers: Observers::default(),
         }
     }
 
@@ -161,16 +935,324 @@ impl Game {
         self.state
     }
 
+    /// Half-moves played since the last capture
+    ///
+    /// Starts from the FEN's halfmove field for games loaded from FEN.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::from_fen("4k4/9/9/9/9/9/9/9/9/3K5 w - - 10 6").unwrap();
+    /// game.make_move(Position::from_xy(3, 9), Position::from_xy(3, 8)).unwrap();
+    /// assert_eq!(game.halfmove_clock(), 11);
+    /// assert!(game.to_fen().contains(" b - - 11 "));
+    /// ```
+    pub fn halfmove_clock(&self) -> u32 {
+        match self.move_history.iter().rposition(|r| r.captured.is_some()) {
+            Some(last_capture) => (self.move_history.len() - 1 - last_capture) as u32,
+            None => self.start_halfmove_clock + self.move_history.len() as u32,
+        }
+    }
+
+    /// Moves per side without a capture before the game is drawn
+    pub fn move_limit(&self) -> Option<u32> {
+        self.move_limit
+    }
+
+    /// Set the natural-move limit, or None to play without it
+    ///
+    /// The limit counts moves per side, so the default of 60 draws the game
+    /// after 120 half-moves without a capture.
+    pub fn set_move_limit(&mut self, limit: Option<u32>) {
+        self.move_limit = limit;
+    }
+
+    /// How a stalemate is scored
+    pub fn stalemate_rule(&self) -> StalemateRule {
+        self.stalemate_rule
+    }
+
+    /// Choose how a stalemate is scored
+    ///
+    /// A game that already ended in stalemate is rescored under the new
+    /// rule.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Color, Game, GameResult, GameState, StalemateRule};
+    ///
+    /// // Black's general can't move and Black has no other piece
+    /// let mut game = Game::from_fen("3k5/4R4/9/9/9/9/9/9/9/4K4 b - - 0 1").unwrap();
+    /// assert_eq!(game.state(), GameState::Stalemate);
+    ///
+    /// game.set_stalemate_rule(StalemateRule::Loss);
+    /// assert_eq!(game.state(), GameState::StalemateWin(Color::Red));
+    /// assert_eq!(game.state().result(), Some(GameResult::RedWins));
+    /// ```
+    pub fn set_stalemate_rule(&mut self, rule: StalemateRule) {
+        self.stalemate_rule = rule;
+        if matches!(
+            self.state,
+            GameState::Stalemate | GameState::StalemateWin(_)
+        ) {
+            self.update_state();
+        }
+    }
+
+    /// Whether the game began from the standard initial position
+    pub fn starts_from_initial_position(&self) -> bool {
+        self.positions[0] == zobrist::hash(&Board::new(), Color::Red)
+    }
+
+    /// Whether turn alternation is suspended for free analysis
+    pub fn free_turn(&self) -> bool {
+        self.free_turn
+    }
+
+    /// Suspend or restore turn alternation
+    ///
+    /// With free turn on, a piece of either color may move, so one side can
+    /// play several moves in a row. Piece movement rules and check are still
+    /// enforced. After a move it is the other side's turn as usual.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Color, Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.set_free_turn(true);
+    /// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    /// game.make_move(Position::from_xy(7, 9), Position::from_xy(6, 7)).unwrap();
+    /// assert_eq!(game.turn(), Color::Black);
+    /// ```
+    pub fn set_free_turn(&mut self, free: bool) {
+        self.free_turn = free;
+    }
+
     /// Get a reference to the move history as a Vec
     pub fn get_moves(&self) -> Vec<Move> {
         self.move_history.iter().map(|r| r.mv).collect()
     }
 
+    /// The most recent move, if any
+    pub fn last_move(&self) -> Option<Move> {
+        self.move_history.last().map(|r| r.mv)
+    }
+
+    /// Whether the most recent move captured a piece or gave check
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// assert!(!game.last_move_was_forcing());
+    /// // Cannon takes the knight on b0
+    /// game.make_move(Position::from_xy(1, 7), Position::from_xy(1, 0)).unwrap();
+    /// assert!(game.last_move_was_forcing());
+    /// ```
+    pub fn last_move_was_forcing(&self) -> bool {
+        self.move_history
+            .last()
+            .is_some_and(|r| r.captured.is_some() || r.gave_check)
+    }
+
     /// Get move history with piece information for notation display
     pub fn get_notated_moves(&self) -> Vec<(crate::types::Piece, Move)> {
         self.move_history.iter().map(|r| (r.piece, r.mv)).collect()
     }
 
+    /// Number of moves played
+    pub fn move_count(&self) -> usize {
+        self.move_history.len()
+    }
+
+    /// The move at `index` (0 is the first move) with the piece that moved
+    pub fn notated_move(&self, index: usize) -> Option<(crate::types::Piece, Move)> {
+        self.move_history.get(index).map(|r| (r.piece, r.mv))
+    }
+
+    /// The moves played, written in `style`
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::notation::format::MoveStyle;
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    /// assert_eq!(game.move_notations(MoveStyle::Chinese), ["炮二平五"]);
+    /// assert_eq!(game.move_notations(MoveStyle::Wxf), ["C2.5"]);
+    /// assert_eq!(game.move_notations(MoveStyle::Iccs), ["h7e7"]);
+    /// ```
+    pub fn move_notations(&self, style: MoveStyle) -> Vec<String> {
+        self.move_history
+            .iter()
+            .map(|r| r.notation(style))
+            .collect()
+    }
+
+    /// The comment on the move at `index` (0 is the first move), if any
+    pub fn comment(&self, index: usize) -> Option<&str> {
+        self.move_history.get(index)?.comment.as_deref()
+    }
+
+    /// Annotate the move at `index` (0 is the first move)
+    ///
+    /// A blank comment removes the annotation. Returns false if there is
+    /// no such move.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    /// assert!(game.set_comment(0, Some(" Central cannon ".to_string())));
+    /// assert_eq!(game.comment(0), Some("Central cannon"));
+    /// assert!(game.set_comment(0, Some(String::new())));
+    /// assert_eq!(game.comment(0), None);
+    /// assert!(!game.set_comment(1, None));
+    /// ```
+    pub fn set_comment(&mut self, index: usize, comment: Option<String>) -> bool {
+        let Some(record) = self.move_history.get_mut(index) else {
+            return false;
+        };
+        record.comment = comment
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty());
+        true
+    }
+
+    /// Time in milliseconds the side took to play the move at `index`,
+    /// if it was timed
+    pub fn move_time(&self, index: usize) -> Option<u64> {
+        self.move_history.get(index)?.time_ms
+    }
+
+    /// Record how long the side took to play the move at `index`
+    ///
+    /// Returns false if there is no such move.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    /// assert!(game.set_move_time(0, Some(5_000)));
+    /// assert_eq!(game.move_time(0), Some(5_000));
+    /// let pgn = game.to_pgn();
+    /// assert_eq!(pgn.moves[0].comment.as_deref(), Some("[%emt 0:00:05]"));
+    /// ```
+    pub fn set_move_time(&mut self, index: usize, time_ms: Option<u64>) -> bool {
+        match self.move_history.get_mut(index) {
+            Some(record) => {
+                record.time_ms = time_ms;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Lines played instead of the move at `index`, in ICCS
+    pub fn variations(&self, index: usize) -> &[Vec<PgnMove>] {
+        self.move_history
+            .get(index)
+            .map_or(&[], |r| r.variations.as_slice())
+    }
+
+    /// Record a line played instead of the move at `index`
+    ///
+    /// Moves are ICCS and may carry comments and variations of their own.
+    /// Returns false if there is no such move.
+    pub fn add_variation(&mut self, index: usize, moves: Vec<PgnMove>) -> bool {
+        match self.move_history.get_mut(index) {
+            Some(record) => {
+                record.variations.push(moves);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The game along a line through its variations
+    ///
+    /// Each step of `path` is the index of a move in the line so far and
+    /// which of its variations to take instead; the line then runs to the
+    /// end of that variation. An empty path is the game itself. Returns
+    /// None if a step names a variation that does not exist.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::pgn::PgnMove;
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    /// game.make_move(Position::from_xy(7, 0), Position::from_xy(6, 2)).unwrap();
+    /// // Black could have mirrored the central cannon
+    /// game.add_variation(1, vec![PgnMove::new("h2e2"), PgnMove::new("h9g7")]);
+    ///
+    /// let line = game.line(&[(1, 0)]).unwrap();
+    /// assert_eq!(line.get_moves_with_iccs(), ["h7e7", "h2e2", "h9g7"]);
+    /// assert!(game.line(&[(0, 0)]).is_none());
+    /// ```
+    pub fn line(&self, path: &[(usize, usize)]) -> Option<Game> {
+        let mut game = self.clone();
+        for &(index, variation) in path {
+            let moves = game.variations(index).get(variation)?.clone();
+            game = game.at_ply(index);
+            game.redo_stack.clear();
+            game.play_line(&moves);
+        }
+        Some(game)
+    }
+
+    /// Play ICCS moves with their comments and variations, stopping at the
+    /// first one that is not legal
+    fn play_line(&mut self, moves: &[PgnMove]) {
+        for pgn_move in moves {
+            let Some((from, to)) = iccs::iccs_to_move(&pgn_move.notation) else {
+                return;
+            };
+            if self.make_move(from, to).is_err() {
+                return;
+            }
+            if let Some(record) = self.move_history.last_mut() {
+                record.comment = pgn_move.comment.clone();
+                record.variations = pgn_move.variations.clone();
+            }
+        }
+    }
+
+    /// Zobrist hashes (see [`zobrist::hash`]) of the starting position
+    /// and of the position after each move
+    ///
+    /// Equal hashes mean the same pieces on the same squares with the same
+    /// side to move, so repetitions and transpositions can be found by
+    /// comparing them.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Position::from_xy(1, 9), Position::from_xy(2, 7)).unwrap();
+    /// game.make_move(Position::from_xy(1, 0), Position::from_xy(2, 2)).unwrap();
+    /// game.make_move(Position::from_xy(2, 7), Position::from_xy(1, 9)).unwrap();
+    /// game.make_move(Position::from_xy(2, 2), Position::from_xy(1, 0)).unwrap();
+    ///
+    /// let hashes = game.position_hash_history();
+    /// assert_eq!(hashes.len(), 5);
+    /// assert_eq!(hashes[0], hashes[4]);
+    /// assert_ne!(hashes[0], hashes[2]);
+    /// ```
+    pub fn position_hash_history(&self) -> &[u64] {
+        &self.positions
+    }
+
     /// Get move history in ICCS notation format
     #[allow(dead_code)]
     pub fn get_moves_with_iccs(&self) -> Vec<String> {
@@ -183,13 +1265,7 @@ impl Game {
     /// Make a move on the board
     pub fn make_move(&mut self, from: Position, to: Position) -> Result<(), MoveError> {
         // Check if game is already over
-        if !matches!(self.state, GameState::Playing) {
-            let result = match self.state {
-                GameState::Checkmate(Color::Red) => GameResult::RedWins,
-                GameState::Checkmate(Color::Black) => GameResult::BlackWins,
-                GameState::Stalemate => GameResult::Draw,
-                _ => return Err(MoveError::GameOver(GameResult::Draw)),
-            };
+        if let Some(result) = self.state.result() {
             return Err(MoveError::GameOver(result));
         }
 
@@ -200,40 +1276,88 @@ impl Game {
         };
 
         // Check if it's the correct turn
-        if piece.color != self.turn {
+        if piece.color != self.turn && !self.free_turn {
             return Err(MoveError::WrongTurn(self.turn));
         }
 
         // Check if the move is legal
-        if !self.board.is_legal_move(from, to) {
-            return Err(MoveError::InvalidMove);
-        }
+        self.board
+            .is_legal_move(from, to)
+            .map_err(MoveError::Illegal)?;
 
-        // Record the captured piece if any
+        // Record the captured piece and the notation, which needs the
+        // position before the move
         let captured = self.board.get(to).copied();
+        let chinese = move_to_chinese_with_style(self, piece, from, to, NumeralStyle::default());
+        let wxf = move_to_wxf(piece, from, to);
 
         // Make the move
         self.board.move_piece(from, to);
 
+        // Switch turns
+        self.turn = match piece.color {
+            Color::Red => Color::Black,
+            Color::Black => Color::Red,
+        };
+
         // Record the move in history
         self.move_history.push(MoveRecord {
             mv: Move::new(from, to),
             piece,
             captured,
+            gave_check: self.board.is_in_check(self.turn),
+            chinese,
+            wxf,
+            comment: None,
+            time_ms: None,
+            variations: Vec::new(),
         });
-
-        // Switch turns
-        self.turn = match self.turn {
-            Color::Red => Color::Black,
-            Color::Black => Color::Red,
-        };
+        self.positions.push(zobrist::hash(&self.board, self.turn));
+
+        // Replaying the next undone move keeps the rest redoable, and its
+        // comment and variations; any other move starts a new line
+        if self.redo_stack.last().map(|r| r.mv) == Some(Move::new(from, to)) {
+            if let (Some(undone), Some(record)) =
+                (self.redo_stack.pop(), self.move_history.last_mut())
+            {
+                record.comment = undone.comment;
+                record.time_ms = undone.time_ms;
+                record.variations = undone.variations;
+            }
+        } else {
+            self.redo_stack.clear();
+        }
 
         // Update game state (check for checkmate/stalemate)
         self.update_state();
 
+        let mv = Move::new(from, to);
+        self.observers.emit(GameEvent::Moved {
+            mv,
+            piece,
+            captured,
+        });
+        if let Some(piece) = captured {
+            self.observers.emit(GameEvent::Captured { mv, piece });
+        }
+        if self.is_in_check() {
+            self.observers.emit(GameEvent::Check(self.turn));
+        }
+        if self.state.result().is_some() {
+            self.observers.emit(GameEvent::GameOver(self.state));
+        }
+
         Ok(())
     }
 
+    /// Follow the game's moves, captures, checks, end and undos
+    ///
+    /// Every event from now on is sent to the returned receiver until it is
+    /// dropped. Redone moves are sent as moves. See [`crate::events`].
+    pub fn subscribe(&mut self) -> Receiver<GameEvent> {
+        self.observers.subscribe()
+    }
+
     /// Undo the last move
     pub fn undo_move(&mut self) -> bool {
         if let Some(record) = self.move_history.pop() {
@@ -251,21 +1375,49 @@ impl Game {
                 self.board.place_piece(record.mv.to, captured);
             }
 
-            // Switch turn back
-            self.turn = match self.turn {
-                Color::Red => Color::Black,
-                Color::Black => Color::Red,
-            };
+            // Give the turn back to the side that moved
+            self.turn = record.piece.color;
+            self.positions.pop();
 
             // Reset state to playing
             self.state = GameState::Playing;
 
+            self.observers.emit(GameEvent::Undone(record.mv));
+            self.redo_stack.push(record);
             true
         } else {
             false
         }
     }
 
+    /// Replay the most recently undone move
+    ///
+    /// Undone moves can be redone until a different move is made.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    /// game.undo_move();
+    /// assert!(game.can_redo());
+    /// assert!(game.redo_move());
+    /// assert_eq!(game.get_moves().len(), 1);
+    /// assert!(!game.redo_move());
+    /// ```
+    pub fn redo_move(&mut self) -> bool {
+        match self.redo_stack.last().map(|r| r.mv) {
+            Some(mv) => self.make_move(mv.from, mv.to).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Whether there is an undone move to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
     /// Check if the current player is in check
     pub fn is_in_check(&self) -> bool {
         self.board.is_in_check(self.turn)
@@ -279,6 +1431,15 @@ impl Game {
 
     /// Update the game state based on current position
     fn update_state(&mut self) {
+        // A side without a general has lost; nothing else is meaningful
+        if let Some(color) = [self.turn, self.turn.opponent()]
+            .into_iter()
+            .find(|&c| self.board.find_general(c).is_none())
+        {
+            self.state = GameState::MissingGeneral(color);
+            return;
+        }
+
         // First, check if current player is in check
         let in_check = self.is_in_check();
 
@@ -293,31 +1454,103 @@ impl Game {
                 self.state = GameState::Checkmate(winner);
             } else {
                 // No legal moves but not in check = stalemate
-                self.state = GameState::Stalemate;
+                self.state = match self.stalemate_rule {
+                    StalemateRule::Draw => GameState::Stalemate,
+                    StalemateRule::Loss => GameState::StalemateWin(self.turn.opponent()),
+                };
             }
         } else {
-            self.state = GameState::Playing;
+            self.state = self
+                .repetition_state()
+                .or_else(|| self.move_limit_state())
+                .unwrap_or(GameState::Playing);
+        }
+    }
+
+    /// Draw once the halfmove clock reaches the natural-move limit
+    fn move_limit_state(&self) -> Option<GameState> {
+        let limit = self.move_limit?;
+        (self.halfmove_clock() >= limit * 2).then_some(GameState::DrawByMoveLimit)
+    }
+
+    /// Apply the repetition rules to the current position
+    ///
+    /// Once the position has occurred [`REPETITION_LIMIT`] times, the moves
+    /// since its previous occurrence form the repeating cycle. A side that
+    /// gave check with every one of its moves in the cycle loses; otherwise
+    /// (including when both sides check perpetually) the game is drawn.
+    /// Perpetual chasing of unprotected pieces is not detected.
+    fn repetition_state(&self) -> Option<GameState> {
+        let current = *self.positions.last()?;
+        let occurrences: Vec<usize> = self
+            .positions
+            .iter()
+            .enumerate()
+            .filter(|&(_, &hash)| hash == current)
+            .map(|(i, _)| i)
+            .collect();
+        if occurrences.len() < REPETITION_LIMIT {
+            return None;
         }
+
+        // positions[i] is the position after i moves
+        let cycle = &self.move_history[occurrences[occurrences.len() - 2]..];
+        let checks_perpetually = |color: Color| {
+            let mut moves = cycle.iter().filter(|r| r.piece.color == color).peekable();
+            moves.peek().is_some() && moves.all(|r| r.gave_check)
+        };
+
+        Some(
+            match (
+                checks_perpetually(Color::Red),
+                checks_perpetually(Color::Black),
+            ) {
+                (true, false) => GameState::PerpetualCheck(Color::Black),
+                (false, true) => GameState::PerpetualCheck(Color::Red),
+                _ => GameState::DrawByRepetition,
+            },
+        )
+    }
+
+    /// Per-piece legal move counts for a side
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Color, Game, PieceType};
+    ///
+    /// let game = Game::new();
+    /// let mobility = game.mobility(Color::Red);
+    /// assert_eq!(mobility.total(), 44);
+    /// assert_eq!(mobility.by_type(PieceType::Horse), 4);
+    /// ```
+    pub fn mobility(&self, color: Color) -> Mobility {
+        let mut pieces: Vec<(Position, Piece, usize)> = self
+            .board
+            .pieces_of_color(color)
+            .map(|(pos, piece)| (pos, piece, self.board.mobility_at(pos)))
+            .collect();
+        pieces.sort_by_key(|(pos, _, _)| (pos.y, pos.x));
+        Mobility { pieces }
+    }
+
+    /// Count the move sequences of `depth` plies from the current position
+    ///
+    /// See [`Board::perft`].
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.board.perft(self.turn, depth)
+    }
+
+    /// Number of legal moves available to a side
+    pub fn legal_move_count(&self, color: Color) -> usize {
+        self.board
+            .pieces_of_color(color)
+            .map(|(pos, _)| self.board.mobility_at(pos))
+            .sum()
     }
 
     /// Check if a player has any legal moves
     fn has_legal_moves(&self, color: Color) -> bool {
-        // Get all pieces of the current color
-        for (pos, _piece) in self.board.pieces_of_color(color) {
-            // Check all possible destination squares
-            for y in 0..self.board.height() {
-                for x in 0..self.board.width() {
-                    let dest = Position::from_xy(x, y);
-                    if dest == pos {
-                        continue;
-                    }
-                    if self.board.is_legal_move(pos, dest) {
-                        return true;
-                    }
-                }
-            }
-        }
-        false
+        self.board.has_legal_move(color)
     }
 
     /// Get a mutable reference to the board (use with caution)
@@ -326,6 +1559,27 @@ impl Game {
         &mut self.board
     }
 
+    /// End the game with `loser` out of time
+    ///
+    /// Does nothing once the game is over.
+    pub fn lose_on_time(&mut self, loser: Color) {
+        if self.state == GameState::Playing {
+            self.state = GameState::Timeout(loser.opponent());
+            self.observers.emit(GameEvent::GameOver(self.state));
+        }
+    }
+
+    /// End the game as the adjudication rules decided, won by `winner` or
+    /// drawn
+    ///
+    /// Does nothing once the game is over.
+    pub fn adjudicate(&mut self, winner: Option<Color>) {
+        if self.state == GameState::Playing {
+            self.state = GameState::Adjudicated(winner);
+            self.observers.emit(GameEvent::GameOver(self.state));
+        }
+    }
+
     #[allow(dead_code)]
     /// Force a game state for testing purposes
     pub fn force_state_for_testing(&mut self, state: GameState) {
@@ -333,15 +1587,53 @@ impl Game {
     }
 
     /// Create a game from a FEN string
+    ///
+    /// Both generals must be on the board; positions without one are
+    /// rejected with [`FenError::MissingGeneral`]. Use
+    /// [`Game::from_fen_permissive`] to load such positions anyway.
     pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let game = Self::from_fen_permissive(fen)?;
+        if let GameState::MissingGeneral(color) = game.state {
+            return Err(FenError::MissingGeneral(color));
+        }
+        Ok(game)
+    }
+
+    /// Create a game from a FEN string that may lack a general
+    ///
+    /// Meant for editor and puzzle positions. A position without a general
+    /// loads as finished with [`GameState::MissingGeneral`] instead of
+    /// failing.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Color, Game, GameState};
+    ///
+    /// let fen = "9/9/9/9/9/9/9/9/9/4K4 w - - 0 1";
+    /// assert!(Game::from_fen(fen).is_err());
+    /// let game = Game::from_fen_permissive(fen).unwrap();
+    /// assert_eq!(game.state(), GameState::MissingGeneral(Color::Black));
+    /// ```
+    pub fn from_fen_permissive(fen: &str) -> Result<Self, FenError> {
         let (board, turn) = crate::fen::fen_to_board(fen)?;
+        let (halfmove_clock, fullmove) = crate::fen::fen_move_counts(fen)?;
 
-        Ok(Self {
+        let mut game = Self {
+            positions: vec![zobrist::hash(&board, turn)],
             board,
             turn,
             move_history: Vec::new(),
             state: GameState::Playing,
-        })
+            free_turn: false,
+            start_halfmove_clock: halfmove_clock,
+            start_fullmove: fullmove,
+            move_limit: Some(DEFAULT_MOVE_LIMIT),
+            stalemate_rule: StalemateRule::default(),
+            redo_stack: Vec::new(),
+            observers: Observers::default(),
+        };
+        game.update_state();
+        Ok(game)
     }
 
     /// Create a game from FEN with moves format
@@ -354,11 +1646,19 @@ impl Game {
 
     /// Export the current game state to FEN format
     pub fn to_fen(&self) -> String {
-        // Calculate full move number from history
-        // Each full move = two half-moves (one by each side)
-        let full_move_count = (self.move_history.len() / 2) + 1;
-
-        crate::fen::board_to_fen(&self.board, self.turn, 0, full_move_count as u32)
+        // The fullmove number goes up after each of Black's moves
+        let black_moves = self
+            .move_history
+            .iter()
+            .filter(|record| record.piece.color == Color::Black)
+            .count();
+
+        crate::fen::board_to_fen(
+            &self.board,
+            self.turn,
+            self.halfmove_clock(),
+            self.start_fullmove + black_moves as u32,
+        )
     }
 
     /// Export the current game state to FEN with moves format
@@ -369,9 +1669,56 @@ impl Game {
         crate::fen::game_to_fen_with_moves(self)
     }
 
+    /// FEN after every half-move, paired with the ICCS move that led to it
+    ///
+    /// The starting position is not included. Positions are rebuilt by
+    /// undoing moves on a copy, so games loaded from FEN work too.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    /// let history = game.fen_history();
+    /// assert_eq!(history[0].0, "h7e7");
+    /// assert_eq!(history[0].1, game.to_fen());
+    /// ```
+    pub fn fen_history(&self) -> Vec<(String, String)> {
+        let mut replay = self.clone();
+        let mut history = Vec::with_capacity(self.move_history.len());
+        while let Some(record) = replay.move_history.last() {
+            let iccs = iccs::move_to_iccs(record.mv.from, record.mv.to);
+            history.push((iccs, replay.to_fen()));
+            replay.undo_move();
+        }
+        history.reverse();
+        history
+    }
+
+    /// The game as it stood after its first `ply` moves
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    /// assert_eq!(game.at_ply(0).to_fen(), Game::new().to_fen());
+    /// assert_eq!(game.at_ply(5).get_moves().len(), 1);
+    /// ```
+    pub fn at_ply(&self, ply: usize) -> Game {
+        let mut replay = self.clone();
+        while replay.move_history.len() > ply {
+            replay.undo_move();
+        }
+        replay
+    }
+
     /// Export the game to PGN format
     ///
-    /// Creates a PgnGame with standard tags and move history in Chinese notation.
+    /// Creates a PgnGame with standard tags and move history in Chinese
+    /// notation; see [`Game::to_pgn_with`] for other notations.
     ///
     /// # Examples
     /// ```
@@ -386,6 +1733,32 @@ impl Game {
     /// ```
     #[allow(dead_code)]
     pub fn to_pgn(&self) -> PgnGame {
+        self.to_pgn_with(PgnExportOptions::default())
+    }
+
+    /// Export the game to PGN, writing moves in the notation of `options`
+    ///
+    /// The notation is named in a `Format` tag (`ICCS`, `WXF` or
+    /// `Chinese`) so other software knows how to read the moves. Games in
+    /// the built-in opening book get `Opening` and `ECO` tags naming it.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::game::PgnExportOptions;
+    /// use cn_chess_tui::notation::format::MoveStyle;
+    /// use cn_chess_tui::{Game, Position};
+    ///
+    /// let mut game = Game::new();
+    /// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    ///
+    /// let pgn = game.to_pgn_with(PgnExportOptions { notation: MoveStyle::Iccs });
+    /// assert_eq!(pgn.get_tag("Format").unwrap(), "ICCS");
+    /// assert_eq!(pgn.moves[0].notation, "h7e7");
+    /// assert_eq!(pgn.get_tag("Opening").unwrap(), "中炮");
+    /// assert_eq!(pgn.get_tag("ECO").unwrap(), "B00");
+    /// ```
+    pub fn to_pgn_with(&self, options: PgnExportOptions) -> PgnGame {
+        let style = options.notation;
         let mut pgn_game = PgnGame::new();
 
         // Set standard tags
@@ -395,31 +1768,93 @@ impl Game {
 
         // Set result based on game state
         let result = match self.state {
-            GameState::Checkmate(Color::Red) => PgnGameResult::RedWins,
-            GameState::Checkmate(Color::Black) => PgnGameResult::BlackWins,
-            GameState::Stalemate => PgnGameResult::Draw,
             GameState::Playing => PgnGameResult::Unknown,
+            state => match state.result() {
+                Some(GameResult::RedWins) => PgnGameResult::RedWins,
+                Some(GameResult::BlackWins) => PgnGameResult::BlackWins,
+                _ => PgnGameResult::Draw,
+            },
         };
         pgn_game.set_tag("Result", result.to_pgn_string());
+        if let GameState::Adjudicated(_) = self.state {
+            pgn_game.set_tag("Termination", "adjudication");
+        }
 
         // Set date to today (using placeholder format)
         pgn_game.set_tag("Date", "????.??.??");
+        pgn_game.set_tag("Format", style.pgn_format());
+        if let Some(opening) = OpeningBook::builtin().identify(self) {
+            pgn_game.set_tag("Opening", opening.name);
+            pgn_game.set_tag("ECO", opening.eco);
+        }
 
-        // Add move history using Chinese notation with context
-        for record in &self.move_history {
-            let chinese_notation =
-                move_to_chinese_with_context(self, record.piece, record.mv.from, record.mv.to);
-            pgn_game.add_move(chinese_notation);
+        // A game from another position records it, with the handicap it
+        // gives if it is one
+        if !self.starts_from_initial_position() {
+            pgn_game.set_tag("FEN", self.at_ply(0).to_fen());
+            if let Some(handicap) = crate::handicap::Handicap::of_game(self) {
+                pgn_game.set_tag("Handicap", handicap.chinese);
+            }
+        }
+
+        // Add move history in the notation recorded as each move was
+        // played, with its comment and variations
+        for (i, record) in self.move_history.iter().enumerate() {
+            pgn_game.add_move(record.notation(style));
+            let variations = if record.variations.is_empty() {
+                Vec::new()
+            } else {
+                let before = self.at_ply(i);
+                record
+                    .variations
+                    .iter()
+                    .map(|line| before.pgn_line(line, style))
+                    .collect()
+            };
+            if let Some(mv) = pgn_game.moves.last_mut() {
+                let last = i.checked_sub(1).map(|i| self.move_history[i].piece.color);
+                mv.side = side_out_of_turn(last, record.piece.color);
+                mv.comment = record.pgn_comment();
+                mv.variations = variations;
+            }
         }
 
         pgn_game.result = result;
         pgn_game
     }
 
+    /// A line of ICCS moves played from this position, written in `style`
+    fn pgn_line(&self, moves: &[PgnMove], style: MoveStyle) -> Vec<PgnMove> {
+        let mut game = self.clone();
+        game.play_line(moves);
+        let played = &game.move_history[self.move_history.len()..];
+        played
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                let last = match i {
+                    0 => self.move_history.last(),
+                    _ => played.get(i - 1),
+                };
+                let mut mv = PgnMove::new(record.notation(style));
+                mv.side = side_out_of_turn(last.map(|r| r.piece.color), record.piece.color);
+                mv.comment = record.comment.clone();
+                if !record.variations.is_empty() {
+                    let before = game.at_ply(self.move_history.len() + i);
+                    mv.variations = record
+                        .variations
+                        .iter()
+                        .map(|line| before.pgn_line(line, style))
+                        .collect();
+                }
+                mv
+            })
+            .collect()
+    }
+
     /// Get information about whether each move in history was a capture
     ///
     /// Returns a Vec of booleans, where true = capture
-    #[allow(dead_code)]
     pub fn get_capture_history(&self) -> Vec<bool> {
         self.move_history
             .iter()
@@ -450,7 +1885,7 @@ impl Game {
             board.move_piece(record.mv.from, record.mv.to);
 
             // Switch turn
-            turn = match turn {
+            turn = match record.piece.color {
                 Color::Red => Color::Black,
                 Color::Black => Color::Red,
             };
@@ -473,8 +1908,21 @@ impl GameController {
             game: Game::new(),
             ai_mode: AiMode::Off,
             ai_client: None,
+            side_engines: [None, None],
             ai_config: AiConfig::default(),
             engine_thinking: false,
+            last_search: None,
+            engine_move: None,
+            search: None,
+            move_observers: Vec::new(),
+            pondering: None,
+            rng: SeededRng::from_entropy(),
+            analysis: None,
+            stats: Vec::new(),
+            turn_started: Instant::now(),
+            search_started: Instant::now(),
+            time_controls: [None; 2],
+            clocks: [0; 2],
         }
     }
 
@@ -484,8 +1932,21 @@ impl GameController {
             game: Game::from_fen(fen)?,
             ai_mode: AiMode::Off,
             ai_client: None,
+            side_engines: [None, None],
             ai_config: AiConfig::default(),
             engine_thinking: false,
+            last_search: None,
+            engine_move: None,
+            search: None,
+            move_observers: Vec::new(),
+            pondering: None,
+            rng: SeededRng::from_entropy(),
+            analysis: None,
+            stats: Vec::new(),
+            turn_started: Instant::now(),
+            search_started: Instant::now(),
+            time_controls: [None; 2],
+            clocks: [0; 2],
         })
     }
 
@@ -495,8 +1956,145 @@ impl GameController {
             game,
             ai_mode: AiMode::Off,
             ai_client: None,
+            side_engines: [None, None],
             ai_config: AiConfig::default(),
             engine_thinking: false,
+            last_search: None,
+            engine_move: None,
+            search: None,
+            move_observers: Vec::new(),
+            pondering: None,
+            rng: SeededRng::from_entropy(),
+            analysis: None,
+            stats: Vec::new(),
+            turn_started: Instant::now(),
+            search_started: Instant::now(),
+            time_controls: [None; 2],
+            clocks: [0; 2],
+        }
+    }
+
+    /// Create a controller for a game, with rule, AI and clock settings
+    /// from `config.toml`
+    pub fn from_config(mut game: Game, config: &Config) -> Self {
+        game.set_move_limit(config.get_move_limit());
+        game.set_stalemate_rule(config.stalemate);
+        let mut controller = Self::from_game(game);
+        controller.set_ai_config(config.ai_config());
+        controller.set_time_controls(config.time_controls());
+        controller
+    }
+
+    /// Follow the events of the controller's game, including games loaded
+    /// into it later
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::events::GameEvent;
+    /// use cn_chess_tui::{GameController, Position};
+    ///
+    /// let mut controller = GameController::new();
+    /// let events = controller.subscribe();
+    /// controller.human_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+    /// assert!(matches!(events.try_recv(), Ok(GameEvent::Moved { .. })));
+    /// ```
+    pub fn subscribe(&mut self) -> Receiver<GameEvent> {
+        self.game.subscribe()
+    }
+
+    /// Switch to another game, keeping the AI mode, settings and engine
+    ///
+    /// With a seed set, the AI's random choices start again from it, so
+    /// every game from the same position plays the same.
+    pub fn load_game(&mut self, mut game: Game) {
+        self.stop_pondering();
+        self.stop_search();
+        if self.ai_config.seed.is_some() {
+            self.rng = SeededRng::from_seed(self.ai_config.seed);
+        }
+        game.observers = std::mem::take(&mut self.game.observers);
+        self.game = game;
+        self.engine_thinking = false;
+        self.last_search = None;
+        self.engine_move = None;
+        self.stats.clear();
+        self.turn_started = Instant::now();
+        self.reset_clocks();
+    }
+
+    /// Play with `time_control`, or without clocks, starting both clocks
+    /// afresh
+    pub fn set_time_control(&mut self, time_control: Option<TimeControl>) {
+        self.set_time_controls([time_control; 2]);
+    }
+
+    /// Play with a time control each for Red and Black, starting both
+    /// clocks afresh
+    ///
+    /// A side given None has no clock and never loses on time, as for an
+    /// engine held to a fixed time per move against a player on the clock.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::game::TimeControl;
+    /// use cn_chess_tui::{Color, GameController};
+    ///
+    /// let mut controller = GameController::new();
+    /// controller.set_time_controls([Some(TimeControl::minutes(10, 0)), None]);
+    /// assert_eq!(controller.remaining_ms(Color::Red), Some(600_000));
+    /// assert_eq!(controller.remaining_ms(Color::Black), None);
+    /// assert_eq!(controller.time_control(), None);
+    /// ```
+    pub fn set_time_controls(&mut self, time_controls: [Option<TimeControl>; 2]) {
+        self.time_controls = time_controls;
+        self.turn_started = Instant::now();
+        self.reset_clocks();
+    }
+
+    /// The time control both sides play under, or None if there is none
+    /// or each side has its own
+    pub fn time_control(&self) -> Option<TimeControl> {
+        match self.time_controls {
+            [red, black] if red == black => red,
+            _ => None,
+        }
+    }
+
+    /// The time control `color` plays under
+    pub fn side_time_control(&self, color: Color) -> Option<TimeControl> {
+        self.time_controls[side_index(color)]
+    }
+
+    fn reset_clocks(&mut self) {
+        self.clocks = self.time_controls.map(|tc| tc.map_or(0, |tc| tc.base_ms));
+    }
+
+    /// Time left on `color`'s clock, or None if that side has no time
+    /// control
+    ///
+    /// The clock of the side to move runs while the game is being played.
+    pub fn remaining_ms(&self, color: Color) -> Option<u64> {
+        self.side_time_control(color)?;
+        let left = self.clocks[side_index(color)];
+        if color == self.game.turn() && self.game.state() == GameState::Playing {
+            Some(left.saturating_sub(self.turn_started.elapsed().as_millis() as u64))
+        } else {
+            Some(left)
+        }
+    }
+
+    /// End the game if the side to move has run out of time
+    ///
+    /// Returns true if it did. Call this regularly while a game with a
+    /// time control is being played.
+    pub fn check_clock(&mut self) -> bool {
+        let turn = self.game.turn();
+        if self.remaining_ms(turn) == Some(0) {
+            self.stop_pondering();
+            self.game.lose_on_time(turn);
+            true
+        } else {
+            false
         }
     }
 
@@ -524,6 +2122,11 @@ impl GameController {
         self.game.get_notated_moves()
     }
 
+    /// Annotate the move at `index`; see [`Game::set_comment`]
+    pub fn set_comment(&mut self, index: usize, comment: Option<String>) -> bool {
+        self.game.set_comment(index, comment)
+    }
+
     pub fn to_fen(&self) -> String {
         self.game.to_fen()
     }
@@ -532,6 +2135,14 @@ impl GameController {
         self.game.is_in_check()
     }
 
+    /// Suspend or restore turn alternation for free analysis
+    ///
+    /// The AI does not move while free turn is on.
+    pub fn set_free_turn(&mut self, free: bool) {
+        self.stop_pondering();
+        self.game.set_free_turn(free);
+    }
+
     /// Get current AI mode
     pub fn ai_mode(&self) -> AiMode {
         self.ai_mode
@@ -539,36 +2150,143 @@ impl GameController {
 
     /// Set AI mode
     pub fn set_ai_mode(&mut self, mode: AiMode) {
+        self.stop_pondering();
         self.ai_mode = mode;
     }
 
+    /// Search info for the AI's last move
+    ///
+    /// Returns the number of moves played before the search and the info;
+    /// its principal variation starts from that position.
+    pub fn last_search(&self) -> Option<(usize, &Info)> {
+        self.last_search.as_ref().map(|(ply, info)| (*ply, info))
+    }
+
+    /// The moves the engine expects next, starting from the current position
+    ///
+    /// Comes from the running analysis, or else from the AI's last search
+    /// for as long as the game follows its principal variation. Empty when
+    /// there is no such line.
+    pub fn expected_line(&self) -> Vec<(Position, Position)> {
+        if let Some(analysis) = self.analysis.as_ref() {
+            return match &analysis.info {
+                Some(info) if analysis.fen == self.game.to_fen() => {
+                    iccs::iccs_line_to_moves(&info.pv)
+                }
+                _ => Vec::new(),
+            };
+        }
+        let Some((ply, info)) = self.last_search() else {
+            return Vec::new();
+        };
+        let played = self.game.get_moves_with_iccs();
+        match played.get(ply..) {
+            Some(since) if info.pv.starts_with(since) => {
+                iccs::iccs_line_to_moves(&info.pv[since.len()..])
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Statistics for every move of the game, by ply
+    ///
+    /// Moves the controller did not see played, such as those of a loaded
+    /// game, have no evaluation, and only the time the game recorded.
+    pub fn move_stats(&self) -> Vec<MoveStats> {
+        let mut stats = self.stats.clone();
+        stats.resize(self.game.get_moves().len(), MoveStats::default());
+        for (i, stat) in stats.iter_mut().enumerate() {
+            stat.time_ms = stat.time_ms.or(self.game.move_time(i));
+        }
+        stats
+    }
+
+    /// Record the move just played, with its evaluation from the side that
+    /// played it if known
+    fn record_move(&mut self, score: Option<i32>) {
+        let ply = self.game.get_moves().len() - 1;
+        let mover = self.game.turn().opponent();
+        let elapsed = self.turn_started.elapsed().as_millis() as u64;
+        let stats = MoveStats {
+            eval: score.map(|score| red_score(score, mover)),
+            time_ms: Some(elapsed),
+        };
+        if let Some(tc) = self.side_time_control(mover) {
+            let clock = &mut self.clocks[side_index(mover)];
+            *clock = clock.saturating_sub(elapsed) + tc.increment_ms;
+        }
+        self.stats.resize(ply, MoveStats::default());
+        self.stats.push(stats);
+        self.game.set_move_time(ply, Some(elapsed));
+        self.turn_started = Instant::now();
+        self.check_adjudication();
+    }
+
+    /// End an AI-vs-AI game the adjudication rules call
+    ///
+    /// Returns true if it did.
+    fn check_adjudication(&mut self) -> bool {
+        if self.ai_mode != AiMode::PlaysBoth || self.game.state() != GameState::Playing {
+            return false;
+        }
+        match self.ai_config.adjudication.verdict(&self.move_stats()) {
+            Some(GameState::Adjudicated(winner)) => {
+                self.stop_pondering();
+                self.game.adjudicate(winner);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The score of the AI's search for the move just played
+    fn last_search_score(&self) -> Option<i32> {
+        let ply = self.game.get_moves().len().checked_sub(1)?;
+        match &self.last_search {
+            Some((searched, info)) if *searched == ply => info.score,
+            _ => None,
+        }
+    }
+
     /// Check if engine is currently thinking
     pub fn is_engine_thinking(&self) -> bool {
         self.engine_thinking
     }
 
+    /// Check if the engine is thinking on the player's time
+    pub fn is_pondering(&self) -> bool {
+        self.pondering.is_some()
+    }
+
     /// Get AI config
     pub fn ai_config(&self) -> &AiConfig {
         &self.ai_config
     }
 
     /// Set AI config
+    ///
+    /// A changed seed restarts the AI's random choices from that seed.
     pub fn set_ai_config(&mut self, config: AiConfig) {
+        if config.seed.is_some() && config.seed != self.ai_config.seed {
+            self.rng = SeededRng::from_seed(config.seed);
+        }
         self.ai_config = config;
     }
 
     /// Initialize AI engine with given path
-    pub fn init_engine(&mut self, engine_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    ///
+    /// The engine is spoken to in the AI config's protocol.
+    pub fn init_engine(&mut self, engine_path: &str) -> Result<(), Error> {
         // Check if path exists
         if !Path::new(engine_path).exists() {
-            return Err("Engine path does not exist".into());
+            return Err(Error::EngineNotFound(PathBuf::from(engine_path)));
         }
 
         // Create client
         let mut client = UcciClient::new(engine_path)?;
 
         // Initialize engine
-        client.initialize()?;
+        client.initialize_with(self.ai_config.engine_protocol)?;
 
         self.ai_client = Some(client);
         self.ai_config.engine_path = Some(PathBuf::from(engine_path));
@@ -576,9 +2294,83 @@ impl GameController {
         Ok(())
     }
 
-    /// Check if engine is initialized
+    /// Start an engine to play `color` in place of the shared one
+    ///
+    /// The engine is spoken to in the AI config's protocol.
+    pub fn init_side_engine(&mut self, color: Color, engine_path: &str) -> Result<(), Error> {
+        if !Path::new(engine_path).exists() {
+            return Err(Error::EngineNotFound(PathBuf::from(engine_path)));
+        }
+        let mut client = UcciClient::new(engine_path)?;
+        client.initialize_with(self.ai_config.engine_protocol)?;
+
+        self.side_engines[side_index(color)] = Some(client);
+        self.ai_config.side_mut(color).engine_path = Some(PathBuf::from(engine_path));
+        Ok(())
+    }
+
+    /// Start the engines named in the AI config, the shared one and each
+    /// side's, unless they are already running
+    ///
+    /// Returns whether an engine was started. If one fails to start, its
+    /// path is cleared so the side falls back to the shared engine or the
+    /// built-in AI.
+    pub fn init_configured_engine(&mut self) -> Result<bool, Error> {
+        let mut started = false;
+        if self.ai_client.is_none() {
+            if let Some(path) = self.ai_config.engine_path.take() {
+                self.init_engine(&path.to_string_lossy())?;
+                started = true;
+            }
+        }
+        for color in [Color::Red, Color::Black] {
+            if self.side_engines[side_index(color)].is_some() {
+                continue;
+            }
+            if let Some(path) = self.ai_config.side_mut(color).engine_path.take() {
+                self.init_side_engine(color, &path.to_string_lossy())?;
+                started = true;
+            }
+        }
+        Ok(started)
+    }
+
+    /// Check if an engine is initialized, the shared one or a side's
     pub fn has_engine(&self) -> bool {
         self.ai_client.is_some()
+            || self.side_engines.iter().any(Option::is_some)
+            || self.search.is_some()
+    }
+
+    /// The protocol the loaded engine speaks
+    pub fn engine_protocol(&self) -> Option<EngineProtocol> {
+        self.ai_client
+            .iter()
+            .chain(self.side_engines.iter().flatten())
+            .next()
+            .map(UcciClient::protocol)
+            .or(self.search.as_ref().map(|search| search.engine.protocol()))
+    }
+
+    /// Check if the built-in AI is used (no external engine loaded)
+    pub fn uses_native_ai(&self) -> bool {
+        !self.has_engine()
+    }
+
+    /// The engine that plays `color`: its own, or else the shared one
+    fn engine(&self, color: Color) -> Option<&UcciClient> {
+        self.side_engines[side_index(color)]
+            .as_ref()
+            .or(self.ai_client.as_ref())
+    }
+
+    fn engine_mut(&mut self, color: Color) -> Option<&mut UcciClient> {
+        let index = side_index(color);
+        if self.side_engines[index].is_some() {
+            self.side_engines[index].as_mut()
+        } else {
+            self.ai_client.as_mut()
+        }
     }
 
     /// Make a move as a human player (not AI)
@@ -588,7 +2380,23 @@ impl GameController {
             return Err(MoveError::InvalidMove);
         }
 
-        self.game.make_move(from, to)
+        self.game.make_move(from, to)?;
+        self.record_move(None);
+        if self.pondering == Some(Move { from, to }) && self.should_ai_move() {
+            // The engine has been searching this position already
+            let turn = self.game.turn();
+            if let Some(client) = self.engine_mut(turn) {
+                if client.ponder_hit().is_ok() {
+                    self.pondering = None;
+                    self.engine_thinking = true;
+                    self.search_started = Instant::now();
+                    self.follow_search(turn);
+                    return Ok(());
+                }
+            }
+        }
+        self.stop_pondering();
+        Ok(())
     }
 
     /// Undo the last move
@@ -596,12 +2404,73 @@ impl GameController {
         if self.engine_thinking {
             return false; // Don't allow undo while AI is thinking
         }
+        self.stop_pondering();
+        self.turn_started = Instant::now();
         self.game.undo_move()
     }
 
+    /// Redo the last undone move
+    pub fn redo_move(&mut self) -> bool {
+        if self.engine_thinking {
+            return false;
+        }
+        self.stop_pondering();
+        self.turn_started = Instant::now();
+        self.game.redo_move()
+    }
+
+    /// Start pondering on the reply the engine expects to the move it just
+    /// played, if pondering is on and it is the player's turn
+    fn start_pondering(&mut self, reply: &str) {
+        if !self.ai_config.ponder
+            || self.ai_mode == AiMode::Off
+            || self.game.free_turn()
+            || self.should_ai_move()
+            || !matches!(self.game.state(), GameState::Playing)
+        {
+            return;
+        }
+        let Ok((from, to)) = crate::notation::parse_iccs_move(reply) else {
+            return;
+        };
+        if self.game.clone().make_move(from, to).is_err() {
+            return;
+        }
+        let fen = self.game.to_fen();
+        // The AI just moved; its engine ponders
+        let Some(client) = self.engine_mut(self.game.turn().opponent()) else {
+            return;
+        };
+        let started = client
+            .set_position(&fen, &[reply.to_string()])
+            .and_then(|_| client.go_ponder_depth(10));
+        if started.is_ok() {
+            self.pondering = Some(Move { from, to });
+        }
+    }
+
+    /// Stop pondering, if the engine is
+    ///
+    /// The pondering search's result and info are thrown away.
+    fn stop_pondering(&mut self) {
+        if self.pondering.take().is_none() {
+            return;
+        }
+        let engines = self
+            .ai_client
+            .iter_mut()
+            .chain(self.side_engines.iter_mut().flatten());
+        for client in engines {
+            if client.is_pondering() {
+                let _ = client.stop();
+            }
+            client.read_info();
+        }
+    }
+
     /// Check if AI should make the next move
     fn should_ai_move(&self) -> bool {
-        if matches!(self.game.state(), GameState::Playing) {
+        if matches!(self.game.state(), GameState::Playing) && !self.game.free_turn() {
             match self.ai_mode {
                 AiMode::Off => false,
                 AiMode::PlaysRed => self.game.turn() == Color::Red,
@@ -613,67 +2482,498 @@ impl GameController {
         }
     }
 
+    /// Start analyzing the current position with the engine
+    ///
+    /// The engine searches until [`stop_analysis`](Self::stop_analysis);
+    /// follow its progress with [`poll_analysis`](Self::poll_analysis). The
+    /// AI does not move while analysis is running.
+    pub fn start_analysis(&mut self) -> Result<(), Error> {
+        if self.engine_thinking {
+            return Err(Error::EngineBusy);
+        }
+        self.stop_pondering();
+        self.stop_analysis()?;
+        let client = self.ai_client.as_mut().ok_or(Error::NoEngine)?;
+
+        let fen = self.game.to_fen();
+        let hash = client.probe(&fen, &[]).ok();
+        if self.ai_config.multipv > 1 {
+            client.set_multipv(self.ai_config.multipv)?;
+        }
+        client.set_position(&fen, &[])?;
+        client.go_infinite()?;
+        self.analysis = Some(Analysis {
+            fen,
+            info: None,
+            lines: Vec::new(),
+            hash,
+        });
+        Ok(())
+    }
+
+    /// Stop analysis, if it is running
+    pub fn stop_analysis(&mut self) -> Result<(), Error> {
+        if self.analysis.take().is_none() {
+            return Ok(());
+        }
+        if let Some(client) = self.ai_client.as_mut() {
+            if client.is_thinking() {
+                client.stop()?;
+            }
+            client.read_info();
+            // The AI's own searches need only its best line
+            if self.ai_config.multipv > 1 {
+                client.set_multipv(1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if the engine is analyzing the position
+    pub fn is_analyzing(&self) -> bool {
+        self.analysis.is_some()
+    }
+
+    /// The latest analysis line for the current position
+    ///
+    /// Its principal variation starts from the current position.
+    pub fn analysis(&self) -> Option<&Info> {
+        self.analysis.as_ref()?.info.as_ref()
+    }
+
+    /// The latest info for each line the engine analyzes, best first
+    ///
+    /// More than one only with [`AiConfig::multipv`] above 1 and an engine
+    /// that searches several lines.
+    pub fn analysis_lines(&self) -> &[PvLine] {
+        self.analysis
+            .as_ref()
+            .map_or(&[], |analysis| analysis.lines.as_slice())
+    }
+
+    /// What the engine's hash table held for the analyzed position when the
+    /// analysis started
+    ///
+    /// None for engines that don't answer `probe`, such as UCI ones.
+    pub fn analysis_hash(&self) -> Option<&PopHashInfo> {
+        self.analysis.as_ref()?.hash.as_ref()
+    }
+
+    /// Collect the engine's latest analysis without blocking
+    ///
+    /// When the position has changed since the search started, the engine
+    /// is restarted on the new position. Returns whether the analysis shown
+    /// should be redrawn.
+    pub fn poll_analysis(&mut self) -> Result<bool, Error> {
+        let Some(analysis) = self.analysis.as_ref() else {
+            return Ok(false);
+        };
+        if analysis.fen != self.game.to_fen() {
+            self.start_analysis()?;
+            return Ok(true);
+        }
+
+        let client = self.ai_client.as_mut().ok_or(Error::NoEngine)?;
+        // An engine may end an infinite search early, e.g. on finding mate;
+        // its last line stays on show
+        if !client.is_thinking() {
+            return Ok(false);
+        }
+        let mut changed = false;
+        client.poll()?;
+        let infos = client.read_info();
+        let lines = client.pv_lines().to_vec();
+        if let Some(analysis) = self.analysis.as_mut() {
+            if analysis.lines != lines {
+                analysis.lines = lines;
+                changed = true;
+            }
+        }
+        // With several lines searched, the best one is the first
+        let latest = infos
+            .into_iter()
+            .rev()
+            .find(|i| !i.pv.is_empty() && i.multipv.unwrap_or(1) <= 1);
+        match (latest, self.analysis.as_mut()) {
+            (Some(info), Some(analysis)) => {
+                // The analysis evaluates the position the last move reached
+                let ply = self.game.get_moves().len();
+                if let Some(score) = info.score.filter(|_| ply > 0) {
+                    if self.stats.len() < ply {
+                        self.stats.resize(ply, MoveStats::default());
+                    }
+                    self.stats[ply - 1].eval = Some(red_score(score, self.game.turn()));
+                }
+                analysis.info = Some(info);
+                Ok(true)
+            }
+            _ => Ok(changed),
+        }
+    }
+
     /// Trigger AI to make a move
-    pub fn trigger_ai_move(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if !self.should_ai_move() {
+    ///
+    /// Uses the side to move's engine, or the shared one, if loaded,
+    /// otherwise the built-in search, with that side's settings from
+    /// [`AiConfig::for_side`]. The engine searches as far as
+    /// [`AiConfig::effective_engine_limit`] allows; see [`check_engine_response`](Self::check_engine_response)
+    /// for its reply.
+    pub fn trigger_ai_move(&mut self) -> Result<(), Error> {
+        self.collect_search()?;
+        if self.engine_thinking || self.analysis.is_some() || !self.should_ai_move() {
             return Ok(());
         }
 
-        let client = self.ai_client.as_mut().ok_or("AI engine not initialized")?;
+        let turn = self.game.turn();
+        let config = self.ai_config.for_side(turn);
+        if self.engine(turn).is_none() {
+            if config.opening_book && self.play_book_move() {
+                return Ok(());
+            }
+
+            // Built-in search runs synchronously; the move is applied by
+            // check_engine_response like an engine reply
+            let mut searcher = Searcher::new(config.effective_search_depth());
+            let variety = config.effective_variety();
+            let result = if variety > 0 || config.blunder_rate > 0 {
+                searcher.search_with_blunders(
+                    self.game.board(),
+                    self.game.turn(),
+                    variety,
+                    config.blunder_rate,
+                    &mut self.rng,
+                )
+            } else {
+                searcher.search(self.game.board(), self.game.turn())
+            };
+            let ply = self.game.get_moves().len();
+            self.last_search = result.as_ref().map(|result| {
+                let info = Info {
+                    time_ms: None,
+                    nodes: Some(result.nodes),
+                    depth: Some(result.depth),
+                    score: Some(result.score),
+                    multipv: None,
+                    pv: vec![iccs::move_to_iccs(result.mv.from, result.mv.to)],
+                    currmove: None,
+                    message: None,
+                };
+                (ply, info)
+            });
+            self.engine_move = result.map(|result| (result.mv, None));
+            self.engine_thinking = self.engine_move.is_some();
+            self.announce_ai_move();
+            return Ok(());
+        }
 
+        self.stop_pondering();
         // Sync engine with current position
         let fen = self.game.to_fen();
         let moves = self.game.get_moves_with_iccs();
+        let client = self.engine_mut(turn).ok_or(Error::NoEngine)?;
         client.set_position(&fen, &moves)?;
 
-        // Start depth search (depth 10)
-        client.go_depth(10)?;
+        match config.effective_engine_limit() {
+            SearchLimit::Depth(depth) => client.go_depth(depth)?,
+            SearchLimit::Time(time_ms) => client.go_time(time_ms)?,
+            SearchLimit::Nodes(nodes) => client.go_nodes(nodes)?,
+        }
 
         self.engine_thinking = true;
+        self.search_started = Instant::now();
+        self.follow_search(turn);
         Ok(())
     }
 
-    /// Check if engine has responded, apply move if ready
-    pub fn check_engine_response(
-        &mut self,
-    ) -> Result<Option<(Position, Position)>, Box<dyn std::error::Error>> {
-        if !self.engine_thinking {
-            return Ok(None);
+    /// Suggest a move for the side to move, without playing it
+    ///
+    /// The best of [`hint_candidates`](Self::hint_candidates). Returns None
+    /// once the game is over.
+    pub fn hint(&mut self) -> Result<Option<(Position, Position)>, Error> {
+        let candidates = self.hint_candidates()?;
+        Ok(candidates.first().map(|c| (c.from, c.to)))
+    }
+
+    /// Suggest up to [`AiConfig::multipv`] moves for the side to move, best
+    /// first, without playing any
+    ///
+    /// A short search separate from the AI's turns: the engine, if loaded,
+    /// gets half a second and the built-in AI searches at most three plies.
+    /// An engine without a `MultiPV` option suggests only its best move.
+    /// While analysis runs, its current lines are given instead. Empty once
+    /// the game is over.
+    pub fn hint_candidates(&mut self) -> Result<Vec<Candidate>, Error> {
+        if self.game.state() != GameState::Playing {
+            return Ok(Vec::new());
+        }
+        if self.engine_thinking {
+            return Err(Error::EngineBusy);
         }
+        let count = self.ai_config.multipv.max(1) as usize;
+        if self.analysis.is_some() {
+            let mut candidates = candidates_from_lines(self.analysis_lines());
+            if candidates.is_empty() {
+                // An engine that numbers no lines still gives its best one
+                candidates = self
+                    .analysis()
+                    .and_then(|info| {
+                        let (from, to) = iccs::iccs_to_move(info.pv.first()?)?;
+                        Some(Candidate {
+                            from,
+                            to,
+                            score: info.score,
+                        })
+                    })
+                    .into_iter()
+                    .collect();
+            }
+            if candidates.is_empty() {
+                return Err(Error::NoAnalysisLine);
+            }
+            candidates.truncate(count);
+            return Ok(candidates);
+        }
+
+        if self.ai_client.is_none() {
+            // Each further candidate is the best move left once the better
+            // ones are banned
+            let mut searcher =
+                Searcher::new(self.ai_config.effective_search_depth().min(HINT_DEPTH));
+            let mut candidates = Vec::new();
+            let mut banned = Vec::new();
+            while candidates.len() < count {
+                searcher.ban_moves(banned.clone());
+                let Some(result) = searcher.search(self.game.board(), self.game.turn()) else {
+                    break;
+                };
+                candidates.push(Candidate {
+                    from: result.mv.from,
+                    to: result.mv.to,
+                    score: Some(result.score),
+                });
+                banned.push(result.mv);
+            }
+            return Ok(candidates);
+        }
+
+        self.stop_pondering();
+        let client = self.ai_client.as_mut().ok_or(Error::NoEngine)?;
+        if count > 1 {
+            client.set_multipv(count as u32)?;
+        }
+        client.set_position(&self.game.to_fen(), &[])?;
+        client.go_time(HINT_TIME_MS)?;
+        let result = client.wait();
+        let mut candidates = candidates_from_lines(client.pv_lines());
+        // The hint's search lines are not the AI's
+        client.read_info();
+        if count > 1 {
+            client.set_multipv(1)?;
+        }
+        let best = match result? {
+            MoveResult::Move(mv, _) => iccs::iccs_to_move(&mv),
+            _ => None,
+        };
+        let Some((from, to)) = best else {
+            return Ok(Vec::new());
+        };
+        // The move the engine settles on leads, whatever its lines said
+        match candidates.iter().position(|c| (c.from, c.to) == (from, to)) {
+            Some(i) => {
+                let best = candidates.remove(i);
+                candidates.insert(0, best);
+            }
+            None => candidates.insert(
+                0,
+                Candidate {
+                    from,
+                    to,
+                    score: None,
+                },
+            ),
+        }
+        candidates.truncate(count);
+        Ok(candidates)
+    }
 
-        let client = self.ai_client.as_mut().ok_or("AI engine not initialized")?;
+    /// Pick a book move for the built-in AI, if the game is still in the book
+    fn play_book_move(&mut self) -> bool {
+        let book = OpeningBook::builtin();
+        let Some(&mv) = self.rng.choose(&book.book_moves(&self.game)) else {
+            return false;
+        };
+        let mut moves = self.game.get_moves();
+        moves.push(mv);
+        let info = Info {
+            time_ms: None,
+            nodes: None,
+            depth: None,
+            score: None,
+            multipv: None,
+            pv: vec![iccs::move_to_iccs(mv.from, mv.to)],
+            currmove: None,
+            message: book
+                .lookup(&moves)
+                .map(|opening| format!("开局库 Book: {}", opening.name)),
+        };
+        self.last_search = Some((moves.len() - 1, info));
+        self.engine_move = Some((mv, None));
+        self.engine_thinking = true;
+        self.announce_ai_move();
+        true
+    }
 
-        // Check if engine is ready
-        if !client.is_ready()? {
+    /// Check if engine has responded, apply move if ready
+    ///
+    /// An engine still searching after [`AiConfig::engine_timeout_ms`] is
+    /// told to stop. If it then gives no move, or one that can't be read,
+    /// a random legal move is played instead so the game goes on.
+    pub fn check_engine_response(&mut self) -> Result<Option<(Position, Position)>, Error> {
+        self.collect_search()?;
+        let Some((mv, _)) = self.engine_move else {
             return Ok(None);
+        };
+        self.play_engine_move(mv.from, mv.to)?;
+        Ok(Some((mv.from, mv.to)))
+    }
+
+    /// Announce each move the AI settles on, before it is played
+    ///
+    /// An engine's move is sent from the thread following its search, so
+    /// the receiver need not poll for it; the built-in AI's as soon as it
+    /// is found. Play the move with
+    /// [`play_engine_move`](Self::play_engine_move).
+    pub fn subscribe_ai_moves(&mut self) -> Receiver<Move> {
+        let (sender, receiver) = mpsc::channel();
+        self.move_observers.push(sender);
+        receiver
+    }
+
+    /// Play the AI's move from `from` to `to`, once announced
+    ///
+    /// Returns false, playing nothing, if it is not the move the AI is
+    /// waiting to play, as when the announcement came before a new game.
+    pub fn play_engine_move(&mut self, from: Position, to: Position) -> Result<bool, Error> {
+        self.collect_search()?;
+        let reply = match self.engine_move.take() {
+            Some((mv, reply)) if mv == (Move { from, to }) => reply,
+            other => {
+                self.engine_move = other;
+                return Ok(false);
+            }
+        };
+        self.engine_thinking = false;
+        self.game.make_move(from, to)?;
+        self.record_move(self.last_search_score());
+        if let Some(reply) = reply {
+            self.start_pondering(&reply);
+        }
+        Ok(true)
+    }
+
+    /// Send the AI's move to those following it
+    fn announce_ai_move(&mut self) {
+        if let Some((mv, _)) = self.engine_move {
+            self.move_observers.retain(|sender| sender.send(mv).is_ok());
         }
+    }
 
-        // Get the move
-        let result = client.stop()?;
-        let mv = match result {
-            crate::ucci::MoveResult::Move(mv_str, _) => {
-                match crate::notation::parse_iccs_move(&mv_str) {
-                    Ok(pos) => pos,
-                    Err(_) => {
-                        self.engine_thinking = false;
-                        return Ok(None);
-                    }
+    /// Hand the engine playing `color`, just told to search, to a thread
+    /// that reads its output
+    fn follow_search(&mut self, color: Color) {
+        let slot = self.side_engines[side_index(color)]
+            .is_some()
+            .then_some(color);
+        let client = match slot {
+            Some(color) => self.side_engines[side_index(color)].take(),
+            None => self.ai_client.take(),
+        };
+        let Some(client) = client else {
+            return;
+        };
+        let moves = self.game.board().legal_moves(self.game.turn());
+        let fallback = self.rng.choose(&moves).copied();
+        let observers = self.move_observers.clone();
+        let deadline =
+            self.search_started + Duration::from_millis(self.ai_config.engine_timeout_ms);
+        let engine = EngineSearch::spawn(client, deadline, STOP_GRACE_MS, move |result| {
+            // The same choice collect_search makes
+            let mv = match result {
+                Some(MoveResult::Move(mv, _)) => {
+                    crate::notation::parse_iccs_move(&mv).ok().or(fallback)
+                }
+                Some(MoveResult::NoMove) => fallback,
+                _ => None,
+            };
+            if let Some((from, to)) = mv {
+                for sender in &observers {
+                    let _ = sender.send(Move { from, to });
                 }
             }
-            crate::ucci::MoveResult::NoMove => {
-                self.engine_thinking = false;
-                return Ok(None);
-            }
-            crate::ucci::MoveResult::Draw | crate::ucci::MoveResult::Resign => {
-                self.engine_thinking = false;
-                return Ok(None);
-            }
+        });
+        self.search = Some(Search {
+            engine,
+            slot,
+            fallback,
+            ply: self.game.get_moves().len(),
+        });
+    }
+
+    /// Take the engine back once its search is over, with the move it found
+    ///
+    /// If it gave no move, or one that can't be read, a random legal move
+    /// is the AI's instead so the game goes on.
+    fn collect_search(&mut self) -> Result<(), Error> {
+        let Some(end) = self.search.as_ref().and_then(|s| s.engine.try_finish()) else {
+            return Ok(());
         };
+        let Some(search) = self.search.take() else {
+            return Ok(());
+        };
+        self.return_engine(search.slot, end.client);
+        self.engine_thinking = false;
+        if let Some(info) = end.info {
+            self.last_search = Some((search.ply, info));
+        }
+        let found = match end.result? {
+            MoveResult::Move(mv, reply) => crate::notation::parse_iccs_move(&mv)
+                .ok()
+                .map(|(from, to)| (Move { from, to }, reply)),
+            MoveResult::NoMove => None,
+            MoveResult::Draw | MoveResult::Resign => return Ok(()),
+        };
+        self.engine_move = found.or_else(|| {
+            // The engine's last line was not for this move
+            self.last_search = None;
+            search.fallback.map(|(from, to)| (Move { from, to }, None))
+        });
+        self.engine_thinking = self.engine_move.is_some();
+        Ok(())
+    }
 
-        // Apply the move to the game
-        self.game.make_move(mv.0, mv.1)?;
+    /// Stop the engine's search, if one is running, and take the engine
+    /// back; its move is thrown away
+    fn stop_search(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        if let Some(end) = search.engine.stop() {
+            self.return_engine(search.slot, end.client);
+        }
+    }
 
-        self.engine_thinking = false;
-        Ok(Some(mv))
+    /// Put an engine back where it searched from
+    fn return_engine(&mut self, slot: Option<Color>, client: UcciClient) {
+        match slot {
+            Some(color) => self.side_engines[side_index(color)] = Some(client),
+            None => self.ai_client = Some(client),
+        }
+    }
+}
+
+impl Drop for GameController {
+    fn drop(&mut self) {
+        // The engine goes with the controller, not when its search ends
+        self.stop_search();
     }
 }
@@ -0,0 +1,135 @@
+//! Handicap games (让子棋)
+//!
+//! The stronger player takes Red and starts without some of their pieces.
+//! Each handicap in [`HANDICAPS`] is known by a short spec for
+//! `--handicap <spec>`, and a game from one of its positions is tagged with
+//! the handicap's name when saved as PGN.
+
+use crate::board::Board;
+use crate::fen::board_to_fen;
+use crate::game::Game;
+use crate::types::{Color, Position};
+
+/// A handicap: Red's pieces left off the initial position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handicap {
+    /// Name for `--handicap`, e.g. `horse`
+    pub spec: &'static str,
+    /// Chinese name, used for the PGN `Handicap` tag
+    pub chinese: &'static str,
+    pub english: &'static str,
+    /// Squares of the pieces Red gives up
+    removed: &'static [(usize, usize)],
+}
+
+/// The handicaps on offer, smallest first
+pub const HANDICAPS: [Handicap; 4] = [
+    Handicap {
+        spec: "horse",
+        chinese: "让单马",
+        english: "One horse",
+        removed: &[(1, 9)],
+    },
+    Handicap {
+        spec: "two-horses",
+        chinese: "让双马",
+        english: "Two horses",
+        removed: &[(1, 9), (7, 9)],
+    },
+    Handicap {
+        spec: "chariot",
+        chinese: "让单车",
+        english: "One chariot",
+        removed: &[(0, 9)],
+    },
+    Handicap {
+        spec: "chariot-horse",
+        chinese: "让车马",
+        english: "Chariot and horse",
+        removed: &[(0, 9), (1, 9)],
+    },
+];
+
+impl Handicap {
+    /// Find a handicap by its spec, ignoring case
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::handicap::Handicap;
+    ///
+    /// assert_eq!(Handicap::by_spec("Horse").unwrap().chinese, "让单马");
+    /// assert!(Handicap::by_spec("queen").is_none());
+    /// ```
+    pub fn by_spec(spec: &str) -> Option<&'static Handicap> {
+        HANDICAPS
+            .iter()
+            .find(|handicap| handicap.spec.eq_ignore_ascii_case(spec))
+    }
+
+    /// The handicap whose start position a game begins from, if any
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::handicap::Handicap;
+    /// use cn_chess_tui::Game;
+    ///
+    /// let handicap = Handicap::by_spec("chariot").unwrap();
+    /// assert_eq!(Handicap::of_game(&handicap.game()), Some(handicap));
+    /// assert_eq!(Handicap::of_game(&Game::new()), None);
+    /// ```
+    pub fn of_game(game: &Game) -> Option<&'static Handicap> {
+        let start = game.at_ply(0).to_fen();
+        HANDICAPS.iter().find(|handicap| handicap.fen() == start)
+    }
+
+    /// The initial position without Red's handicap pieces
+    pub fn board(&self) -> Board {
+        let mut board = Board::new();
+        for &(x, y) in self.removed {
+            board.remove_piece(Position::from_xy(x, y));
+        }
+        board
+    }
+
+    /// The start position as FEN, Red to move
+    pub fn fen(&self) -> String {
+        board_to_fen(&self.board(), Color::Red, 0, 1)
+    }
+
+    /// A new game from the start position
+    pub fn game(&self) -> Game {
+        Game::from_fen(&self.fen()).expect("handicap positions keep both generals")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PieceType;
+
+    #[test]
+    fn test_handicaps_remove_red_pieces() {
+        let full = Board::new();
+        for handicap in &HANDICAPS {
+            let board = handicap.board();
+            for &(x, y) in handicap.removed {
+                let pos = Position::from_xy(x, y);
+                let piece = full.get(pos).unwrap();
+                assert_eq!(piece.color, Color::Red, "{}", handicap.spec);
+                assert!(matches!(
+                    piece.piece_type,
+                    PieceType::Horse | PieceType::Chariot
+                ));
+                assert_eq!(board.get(pos), None);
+            }
+            assert_eq!(handicap.game().turn(), Color::Red);
+        }
+    }
+
+    #[test]
+    fn test_specs_are_unique() {
+        for (i, handicap) in HANDICAPS.iter().enumerate() {
+            assert_eq!(Handicap::by_spec(handicap.spec), Some(&HANDICAPS[i]));
+        }
+    }
+}
@@ -0,0 +1,1061 @@
+//! Languages for the interface
+//!
+//! Every piece of text the TUI shows is a [`Text`], written in the chosen
+//! [`Lang`] from the table in [`Text::translations`]. The default,
+//! [`Lang::Bilingual`], is the mix of Chinese and English the interface has
+//! always used: Chinese labels with an English gloss where there is room,
+//! and English messages.
+//!
+//! Texts holding values have `{}` placeholders, filled in order by
+//! [`Lang::format`].
+
+use serde::Deserialize;
+use std::fmt::Display;
+
+/// Language of the interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    /// Chinese labels with English glosses, and English messages
+    #[default]
+    Bilingual,
+    /// English only
+    En,
+    /// Chinese only
+    Zh,
+}
+
+impl Lang {
+    /// The language named `name`: `bilingual`, `en` or `zh`
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::i18n::Lang;
+    ///
+    /// assert_eq!(Lang::by_name("en"), Some(Lang::En));
+    /// assert_eq!(Lang::by_name("fr"), None);
+    /// ```
+    pub fn by_name(name: &str) -> Option<Lang> {
+        match name {
+            "bilingual" => Some(Lang::Bilingual),
+            "en" => Some(Lang::En),
+            "zh" => Some(Lang::Zh),
+            _ => None,
+        }
+    }
+
+    /// `text` in this language
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::i18n::{Lang, Text};
+    ///
+    /// assert_eq!(Lang::Bilingual.text(Text::History), "着法记录 History");
+    /// assert_eq!(Lang::En.text(Text::History), "History");
+    /// assert_eq!(Lang::Zh.text(Text::History), "着法记录");
+    /// ```
+    pub fn text(self, text: Text) -> &'static str {
+        let [zh, en, bilingual] = text.translations();
+        match self {
+            Lang::Bilingual => bilingual,
+            Lang::En => en,
+            Lang::Zh => zh,
+        }
+    }
+
+    /// `text` in this language with its `{}` placeholders filled with
+    /// `args`, in order
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::i18n::{Lang, Text};
+    ///
+    /// assert_eq!(Lang::En.format(Text::MoveCount, &[&12]), "Moves: 12");
+    /// assert_eq!(Lang::Zh.format(Text::MoveCount, &[&12]), "着法: 12");
+    /// ```
+    pub fn format(self, text: Text, args: &[&dyn Display]) -> String {
+        let mut parts = self.text(text).split("{}");
+        let mut out = parts.next().unwrap_or_default().to_string();
+        for (i, part) in parts.enumerate() {
+            if let Some(arg) = args.get(i) {
+                out.push_str(&arg.to_string());
+            }
+            out.push_str(part);
+        }
+        out
+    }
+
+    /// The name of `color`'s side, e.g. `红方` or `Red`
+    pub fn side(self, color: crate::types::Color) -> &'static str {
+        match color {
+            crate::types::Color::Red => self.text(Text::RedSide),
+            crate::types::Color::Black => self.text(Text::BlackSide),
+        }
+    }
+}
+
+/// A piece of text the interface shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    // Title and help bars
+    AppTitle,
+    AppSubtitle,
+    Check,
+    RedSide,
+    BlackSide,
+    CurrentTurn,
+    CurrentTurnLabel,
+    MoveCount,
+    TitleKey,
+    TitleArrows,
+    HelpTitle,
+    MoveCursor,
+    Select,
+    Quit,
+    Restart,
+    Undo,
+    Redo,
+    Open,
+    Hint,
+    GameRecord,
+    Theme,
+    Rematch,
+    SavePgn,
+    CopyFen,
+    Review,
+    // Panels
+    BoardTitle,
+    InfoTitle,
+    GameInfoTitle,
+    History,
+    NoMoves,
+    HistoryScroll,
+    TurnLabel,
+    MovesLabel,
+    TotalMovesLabel,
+    CheckLabel,
+    StateLabel,
+    MobilityLabel,
+    OpeningLabel,
+    NotInCheck,
+    // Game states
+    FreeAnalysis,
+    Playing,
+    RedWinsShort,
+    BlackWinsShort,
+    DrawShort,
+    StalemateRedWins,
+    StalemateBlackWins,
+    RepetitionDraw,
+    MoveLimitDraw,
+    NoRedGeneral,
+    NoBlackGeneral,
+    PerpetualRedWins,
+    PerpetualBlackWins,
+    TimeoutRedWins,
+    TimeoutBlackWins,
+    AdjudicatedRedWins,
+    AdjudicatedBlackWins,
+    AdjudicatedDrawShort,
+    // Why the game ended
+    Checkmate,
+    Stalemate,
+    Stalemated,
+    Repetition,
+    PerpetualCheck,
+    MoveLimitAfter,
+    MoveLimit,
+    MissingGeneral,
+    OutOfTime,
+    AdjudicatedLoss,
+    AdjudicatedDraw,
+    // Game over popup
+    RedWins,
+    RedWinsSubtitle,
+    BlackWins,
+    BlackWinsSubtitle,
+    Draw,
+    DrawSubtitle,
+    Material,
+    // Review and setup
+    ReviewBar,
+    Variation,
+    SetupRed,
+    SetupBlack,
+    SetupReady,
+    ToMove,
+    SetupKeys,
+    SetupTitle,
+    Palette,
+    Remove,
+    PiecePicker,
+    // AI menu
+    AiMenuTitle,
+    AiOff,
+    AiPlaysBlack,
+    AiPlaysRed,
+    AiPlaysBoth,
+    ShowThinking,
+    DifficultyField,
+    Custom,
+    Beginner,
+    Intermediate,
+    Advanced,
+    Max,
+    MenuKeys,
+    // Full-screen lists
+    HistoryBrowserTitle,
+    HistoryBrowserKeys,
+    SelectGameTitle,
+    SelectGameKeys,
+    LibraryTitle,
+    LibraryKeys,
+    NoPreview,
+    FinalTitle,
+    // Stats screen
+    StatsTitle,
+    StatsKeys,
+    NoStats,
+    StatsRating,
+    StatsTotal,
+    StatsStreak,
+    StatsWin,
+    StatsLoss,
+    StatsDraw,
+    StatsOpponents,
+    StatsOpenings,
+    // New game dialog
+    NewGameTitle,
+    PlayField,
+    AiField,
+    DifficultyLabel,
+    ClockField,
+    StartField,
+    PlayRed,
+    PlayBlack,
+    PlayBoth,
+    On,
+    Off,
+    NoClock,
+    Minutes,
+    Standard,
+    NewGameKeys,
+    // Recent files
+    RecentFiles,
+    NoRecentFiles,
+    OpenKeys,
+    // Status bar
+    ModePvp,
+    ModeAiRed,
+    ModeAiBlack,
+    ModeAiBoth,
+    BuiltIn,
+    Status,
+    StatusThinking,
+    StatusClocks,
+    // Thinking, analysis, learning and evaluation panels
+    ThinkingTitle,
+    ThinkingDisabled,
+    EngineCalculating,
+    Depth,
+    Score,
+    Nodes,
+    Pv,
+    NoSearch,
+    AnalysisTitle,
+    KibitzTitle,
+    AnalysisDepth,
+    AnalysisScore,
+    AnalysisNodes,
+    EngineAnalyzing,
+    Hash,
+    HashMiss,
+    LearnTitle,
+    CannotMove,
+    ReachesOne,
+    ReachesMany,
+    KindMobility,
+    LearnHint,
+    EvalTitle,
+    RedShort,
+    BlackShort,
+    Captures,
+    NoEvaluations,
+    CommentTitle,
+    CommentKeys,
+    NoticeTitle,
+    // Messages
+    MoveUndone,
+    NoMovesToUndo,
+    MoveRedone,
+    NoMovesToRedo,
+    NotAvailableRemote,
+    WaitForAi,
+    PositionSetUp,
+    CannotPlayPosition,
+    AnalysisOn,
+    AnalysisOff,
+    AnalysisError,
+    Resumed,
+    ErrorMessage,
+    Saved,
+    SaveFailed,
+    Copied,
+    CopyFailed,
+    CopiedPgn,
+    NotYourPiece,
+    NoPieceHere,
+    MoveMade,
+    InvalidMove,
+    Spectating,
+    HintMessage,
+    NoHint,
+    HintUnavailable,
+    AiModeMessage,
+    AiDifficulty,
+    ThinkingToggled,
+    OpenFailed,
+    Opened,
+    OpenedWithWarnings,
+    EngineLoaded,
+    EngineLoadFailed,
+    RanOutOfTime,
+    AiError,
+    AiPlayed,
+    RematchMessage,
+    NewGame,
+    Enabled,
+    Disabled,
+    RemoveSaveFailed,
+    ThinkingDisplay,
+    FreeAnalysisMessage,
+    PvOnBoard,
+    ThemeMessage,
+    BlindfoldMessage,
+    AttackMapMessage,
+    LearningMode,
+    CoordinatesMessage,
+    PieceStyleMessage,
+    MoveHistoryStyle,
+    FenCopyFailed,
+    KibitzOff,
+    NoKibitzEngines,
+    KibitzFailed,
+    KibitzEngines,
+    KibitzStopped,
+    ResumeFailed,
+    FenParseFailed,
+    PositionName,
+    PastedPosition,
+    BoardFlipped,
+    UnknownTheme,
+    AccessibleMode,
+    DescriptionCopied,
+    PgnSaveFailed,
+    PgnCopyFailed,
+    FenPasteFailed,
+    WaitingForOpponent,
+    InvalidMoveLesson,
+    PlayingOnline,
+    BadServerPosition,
+    TurnFileGameOver,
+    TurnFileToMove,
+    TurnWritten,
+    TurnFileFailed,
+    WatchingOnline,
+    OnlineMessage,
+    AnalysisStopped,
+    OpponentPlayed,
+    OpponentMoveRejected,
+    SidePlayed,
+    OnlineMoveRejected,
+    OnlineGameOverReason,
+    OnlineGameOver,
+    LoadFailed,
+    NoLibraryDirectory,
+    LibraryReadFailed,
+    LibrarySaved,
+    LibrarySaveFailed,
+    SavedGameIgnored,
+}
+
+impl Text {
+    /// The text in Chinese, in English and as the bilingual interface
+    /// shows it
+    pub fn translations(self) -> [&'static str; 3] {
+        match self {
+            Text::AppTitle => [" 中国象棋 ", " Chinese Chess ", " 中国象棋 "],
+            Text::AppSubtitle => ["", "", "Chinese Chess "],
+            Text::Check => ["将军!", "Check!", "将军!"],
+            Text::RedSide => ["红方", "Red", "红方"],
+            Text::BlackSide => ["黑方", "Black", "黑方"],
+            Text::CurrentTurn => ["当前回合: ", "To move: ", "当前回合: "],
+            Text::CurrentTurnLabel => ["当前回合:", "To move:", "当前回合:"],
+            Text::MoveCount => ["着法: {}", "Moves: {}", "着法: {}"],
+            Text::TitleKey => [" {}:{} ", " {}:{} ", " {}:{} "],
+            Text::TitleArrows => [
+                " 方向键:移动 Enter:选择 ",
+                " Arrows:Move Enter:Select ",
+                " 方向键:移动 Enter:选择 ",
+            ],
+            Text::HelpTitle => [" 快捷键 ", " Help ", " 快捷键 Help "],
+            Text::MoveCursor => ["移动光标", "Move", "移动光标"],
+            Text::Select => ["选择/确认", "Select", "选择/确认"],
+            Text::Quit => ["退出", "Quit", "退出"],
+            Text::Restart => ["重开", "Restart", "重开"],
+            Text::Undo => ["撤销", "Undo", "撤销"],
+            Text::Redo => ["重做", "Redo", "重做"],
+            Text::Open => ["打开", "Open", "打开"],
+            Text::Hint => ["提示", "Hint", "提示"],
+            Text::GameRecord => ["棋谱", "Record", "棋谱"],
+            Text::Theme => ["配色", "Colors", "配色"],
+            Text::Rematch => ["换边再战", "Rematch", "换边再战"],
+            Text::SavePgn => ["存PGN", "Save PGN", "存PGN"],
+            Text::CopyFen => ["复制FEN", "Copy FEN", "复制FEN"],
+            Text::Review => ["复盘", "Review", "复盘"],
+
+            Text::BoardTitle => ["棋盘", "Board", "棋盘 Board"],
+            Text::InfoTitle => ["信息", "Info", "信息 Info"],
+            Text::GameInfoTitle => ["游戏信息", "Game Info", "游戏信息 Info"],
+            Text::History => ["着法记录", "History", "着法记录 History"],
+            Text::NoMoves => ["暂无着法", "No moves yet", "暂无着法"],
+            Text::HistoryScroll => [
+                " [j/k]滚动 [Esc] ",
+                " [j/k] Scroll [Esc] ",
+                " [j/k]滚动 Scroll [Esc] ",
+            ],
+            Text::TurnLabel => ["回合:", "Turn:", "回合:"],
+            Text::MovesLabel => ["步数:", "Moves:", "步数:"],
+            Text::TotalMovesLabel => ["总步数:", "Moves:", "总步数:"],
+            Text::CheckLabel => ["将军状态:", "Check:", "将军状态:"],
+            Text::StateLabel => ["游戏状态:", "State:", "游戏状态:"],
+            Text::MobilityLabel => ["可走步数:", "Legal moves:", "可走步数:"],
+            Text::OpeningLabel => ["开局:", "Opening:", "开局:"],
+            Text::NotInCheck => ["正常", "No", "正常"],
+
+            Text::FreeAnalysis => ["自由分析", "Free analysis", "自由分析"],
+            Text::Playing => ["进行中", "Playing", "进行中"],
+            Text::RedWinsShort => ["红胜!", "Red wins!", "红胜!"],
+            Text::BlackWinsShort => ["黑胜!", "Black wins!", "黑胜!"],
+            Text::DrawShort => ["和棋", "Draw", "和棋"],
+            Text::StalemateRedWins => ["困毙 红胜", "Stalemate, Red wins", "困毙 红胜"],
+            Text::StalemateBlackWins => ["困毙 黑胜", "Stalemate, Black wins", "困毙 黑胜"],
+            Text::RepetitionDraw => ["重复和棋", "Repetition draw", "重复和棋"],
+            Text::MoveLimitDraw => ["限着和棋", "Move limit draw", "限着和棋"],
+            Text::NoRedGeneral => ["缺帅 黑胜", "No general, Black wins", "缺帅 黑胜"],
+            Text::NoBlackGeneral => ["缺将 红胜", "No general, Red wins", "缺将 红胜"],
+            Text::PerpetualRedWins => ["长将 红胜", "Perpetual, Red wins", "长将 红胜"],
+            Text::PerpetualBlackWins => ["长将 黑胜", "Perpetual, Black wins", "长将 黑胜"],
+            Text::TimeoutRedWins => ["超时 红胜", "Time, Red wins", "超时 红胜"],
+            Text::TimeoutBlackWins => ["超时 黑胜", "Time, Black wins", "超时 黑胜"],
+            Text::AdjudicatedRedWins => ["判定 红胜", "Adjudicated, Red wins", "判定 红胜"],
+            Text::AdjudicatedBlackWins => ["判定 黑胜", "Adjudicated, Black wins", "判定 黑胜"],
+            Text::AdjudicatedDrawShort => ["判和", "Adjudicated draw", "判和"],
+
+            Text::Checkmate => ["绝杀", "Checkmate", "绝杀 Checkmate"],
+            Text::Stalemate => ["无子可动", "Stalemate", "无子可动 Stalemate"],
+            Text::Stalemated => ["{}困毙", "{} is stalemated", "{}困毙 Stalemated"],
+            Text::Repetition => ["三次重复局面", "Repetition", "三次重复局面 Repetition"],
+            Text::PerpetualCheck => [
+                "{}长将判负",
+                "{} loses by perpetual check",
+                "{}长将判负 Perpetual check",
+            ],
+            Text::MoveLimitAfter => [
+                "{}回合未吃子",
+                "{} moves without a capture",
+                "{}回合未吃子 Move limit",
+            ],
+            Text::MoveLimit => ["限着", "Move limit", "限着 Move limit"],
+            Text::MissingGeneral => [
+                "{}缺少将帅",
+                "{} has no general",
+                "{}缺少将帅 Missing general",
+            ],
+            Text::OutOfTime => ["{}超时", "{} ran out of time", "{}超时 Out of time"],
+            Text::AdjudicatedLoss => ["{}判负", "{} loses by adjudication", "{}判负 Adjudicated"],
+            Text::AdjudicatedDraw => ["判和", "Adjudicated draw", "判和 Adjudicated draw"],
+
+            Text::RedWins => ["★ 红方胜利!", "★ Red Wins!", "★ 红方胜利!"],
+            Text::RedWinsSubtitle => ["", "", "Red Wins!"],
+            Text::BlackWins => ["★ 黑方胜利!", "★ Black Wins!", "★ 黑方胜利!"],
+            Text::BlackWinsSubtitle => ["", "", "Black Wins!"],
+            Text::Draw => ["♦ 和棋!", "♦ Draw!", "♦ 和棋!"],
+            Text::DrawSubtitle => ["", "", "Draw"],
+            Text::Material => [
+                "子力 红 {} : 黑 {}",
+                "Material Red {} : Black {}",
+                "子力 红 {} : 黑 {}",
+            ],
+
+            Text::ReviewBar => [
+                " 复盘 {}/{}{}  [↑↓]步进  [→←]进出变着  [Home/End]首/末  [c]注释  [Esc]返回 ",
+                " Review {}/{}{}  [↑↓] Step  [→←] Variation in/out  [Home/End] First/Last  \
+                 [c] Comment  [Esc] Back ",
+                " 复盘 Review {}/{}{}  [↑↓] Step  [→←] Variation in/out  [Home/End] First/Last  \
+                 [c] Comment  [Esc] Back ",
+            ],
+            Text::Variation => [
+                "  变着 {}/{}",
+                "  Variation {}/{}",
+                "  变着 Variation {}/{}",
+            ],
+            Text::SetupRed => ["红方", "Red", "红方 Red"],
+            Text::SetupBlack => ["黑方", "Black", "黑方 Black"],
+            Text::SetupReady => ["✓ 局面合法", "✓ Ready", "✓ 局面合法 Ready"],
+            Text::ToMove => ["先走: ", "To move: ", "先走 To move: "],
+            Text::SetupKeys => [
+                "[Enter]选子 [RNBAKCP/rnbakcp]放子 [x]移除 [X]清空 [i]初始 [o]原局 [Tab]换先 \
+                 [{}/{}]撤销/重做 [f]FEN [s]开始 [Esc]取消 [鼠标]拖放/右键移除",
+                "[Enter] Pick [RNBAKCP/rnbakcp] Place [x] Remove [X] Clear [i] Initial \
+                 [o] Original [Tab] Side [{}/{}] Undo/Redo [f] FEN [s] Play [Esc] Cancel \
+                 [Mouse] Drag/right-click removes",
+                "[Enter]选子 Pick [RNBAKCP/rnbakcp]放子 Place [x]移除 Remove [X]清空 Clear \
+                 [i]初始 Initial [o]原局 Original [Tab]换先 Side [{}/{}]撤销/重做 Undo/Redo \
+                 [f]FEN [s]开始 Play [Esc]取消 Cancel [鼠标]拖放/右键移除 Mouse",
+            ],
+            Text::SetupTitle => ["摆棋", "Setup", "摆棋 Setup"],
+            Text::Palette => ["棋子", "Pieces", "棋子"],
+            Text::Remove => ["移除", "Remove", "移除 Remove"],
+            Text::PiecePicker => ["选子", "Pieces", "选子 Pieces"],
+
+            Text::AiMenuTitle => [" AI 模式 ", " AI Mode Selection ", " AI Mode Selection "],
+            Text::AiOff => [
+                "关闭 (双人对弈)",
+                "Off (Player vs Player)",
+                "Off (Player vs Player)",
+            ],
+            Text::AiPlaysBlack => ["AI 执黑", "AI plays Black", "AI plays Black"],
+            Text::AiPlaysRed => ["AI 执红", "AI plays Red", "AI plays Red"],
+            Text::AiPlaysBoth => [
+                "AI 双方 (观战)",
+                "AI plays Both (spectate)",
+                "AI plays Both (spectate)",
+            ],
+            Text::ShowThinking => ["显示思考", "Show thinking output", "Show thinking output"],
+            Text::DifficultyField => ["难度: ◀ {} ▶", "Difficulty: ◀ {} ▶", "Difficulty: ◀ {} ▶"],
+            Text::Custom => ["自定义", "Custom", "Custom"],
+            Text::Beginner => ["入门", "Beginner", "Beginner"],
+            Text::Intermediate => ["中级", "Intermediate", "Intermediate"],
+            Text::Advanced => ["高级", "Advanced", "Advanced"],
+            Text::Max => ["最强", "Max", "Max"],
+            Text::MenuKeys => [
+                "[↑↓] 选择  [Enter] 确定  [Esc] 取消",
+                "[↑↓] Navigate  [Enter] Select  [Esc] Cancel",
+                "[↑↓] Navigate  [Enter] Select  [Esc] Cancel",
+            ],
+
+            Text::HistoryBrowserTitle => [
+                " 着法记录 - {} 步 - 第 {}/{} 页 ",
+                " History - {} moves - page {}/{} ",
+                " 着法记录 History - {} moves - page {}/{} ",
+            ],
+            Text::HistoryBrowserKeys => [
+                "[↑↓] 滚动  [PgUp/PgDn] 翻页  [Home/End] 首/末  [Esc] 关闭",
+                "[↑↓] Scroll  [PgUp/PgDn] Page  [Home/End] First/Last  [Esc] Close",
+                "[↑↓] Scroll  [PgUp/PgDn] Page  [Home/End] First/Last  [Esc] Close",
+            ],
+            Text::SelectGameTitle => [
+                " 选择对局 - {} ({} 局) ",
+                " Select Game - {} ({} games) ",
+                " 选择对局 Select Game - {} ({} games) ",
+            ],
+            Text::SelectGameKeys => [
+                "[↑↓] 选择  [PgUp/PgDn] 翻页  [Enter] 载入  [Esc] 取消",
+                "[↑↓] Navigate  [PgUp/PgDn] Page  [Enter] Load  [Esc] Cancel",
+                "[↑↓] Navigate  [PgUp/PgDn] Page  [Enter] Load  [Esc] Cancel",
+            ],
+            Text::LibraryTitle => [
+                " 棋谱库 - {} ({} 局) ",
+                " Game Library - {} ({} games) ",
+                " 棋谱库 Game Library - {} ({} games) ",
+            ],
+            Text::LibraryKeys => [
+                "[↑↓] 选择  [PgUp/PgDn] 翻页  [Enter] 复盘  [Esc] 关闭",
+                "[↑↓] Navigate  [PgUp/PgDn] Page  [Enter] Replay  [Esc] Close",
+                "[↑↓] Navigate  [PgUp/PgDn] Page  [Enter] Replay  [Esc] Close",
+            ],
+            Text::NoPreview => ["无预览", "No preview", "No preview"],
+            Text::FinalTitle => ["终局", "Final", "终局 Final"],
+
+            Text::StatsTitle => [" 战绩 ", " Stats ", " 战绩 Stats "],
+            Text::StatsKeys => ["[Esc] 关闭", "[Esc] Close", "[Esc] Close"],
+            Text::NoStats => [
+                "还没有与 AI 下完的对局",
+                "No finished games against the AI yet",
+                "No finished games against the AI yet",
+            ],
+            Text::StatsRating => [
+                "等级分 {} (最高 {})",
+                "Rating {} (peak {})",
+                "等级分 Rating {} (peak {})",
+            ],
+            Text::StatsTotal => ["{} 局 {}", "{} games {}", "{} games {}"],
+            Text::StatsStreak => [
+                "连续{} {} 局 | 最长连胜 {} 局",
+                "Streak: {} x{} | longest winning streak {}",
+                "Streak: {} x{} | longest winning streak {}",
+            ],
+            Text::StatsWin => ["胜", "win", "win"],
+            Text::StatsLoss => ["负", "loss", "loss"],
+            Text::StatsDraw => ["和", "draw", "draw"],
+            Text::StatsOpponents => ["对手", "Opponents", "对手 Opponents"],
+            Text::StatsOpenings => ["开局", "Openings", "开局 Openings"],
+
+            Text::NewGameTitle => ["新局", "New Game", "新局 New Game"],
+            Text::PlayField => ["执子", "Play", "Play"],
+            Text::AiField => ["AI", "AI", "AI"],
+            Text::DifficultyLabel => ["难度", "Difficulty", "Difficulty"],
+            Text::ClockField => ["时限", "Clock", "Clock"],
+            Text::StartField => ["开局", "Start", "Start"],
+            Text::PlayRed => ["红", "Red", "红 Red"],
+            Text::PlayBlack => ["黑", "Black", "黑 Black"],
+            Text::PlayBoth => ["双方", "Both", "Both"],
+            Text::On => ["开", "On", "On"],
+            Text::Off => ["关", "Off", "Off"],
+            Text::NoClock => ["无", "None", "None"],
+            Text::Minutes => ["{} 分钟", "{} min", "{} min"],
+            Text::Standard => ["标准", "Standard", "Standard"],
+            Text::NewGameKeys => [
+                "[↑↓] 选项  [←→] 更改  [Enter] 开始  [Esc] 取消",
+                "[↑↓] Field  [←→] Change  [Enter] Start  [Esc] Cancel",
+                "[↑↓] Field  [←→] Change  [Enter] Start  [Esc] Cancel",
+            ],
+
+            Text::RecentFiles => ["最近文件", "Recent Files", "最近文件 Recent Files"],
+            Text::NoRecentFiles => [
+                "没有最近打开的文件",
+                "No recently opened files",
+                "No recently opened files",
+            ],
+            Text::OpenKeys => [
+                "[↑↓] 选择  [Enter] 打开  [Esc] 取消",
+                "[↑↓] Navigate  [Enter] Open  [Esc] Cancel",
+                "[↑↓] Navigate  [Enter] Open  [Esc] Cancel",
+            ],
+
+            Text::ModePvp => ["双人", "PvP", "PvP"],
+            Text::ModeAiRed => ["AI(红)", "AI(Red)", "AI(Red)"],
+            Text::ModeAiBlack => ["AI(黑)", "AI(Black)", "AI(Black)"],
+            Text::ModeAiBoth => ["AI 对 AI", "AI vs AI", "AI vs AI"],
+            Text::BuiltIn => ["内置", "Built-in", "Built-in"],
+            Text::Status => [
+                "模式: {} | 引擎: {}",
+                "Mode: {} | Engine: {}",
+                "Mode: {} | Engine: {}",
+            ],
+            Text::StatusThinking => [
+                "模式: {} | AI 思考中... | 引擎: {}",
+                "Mode: {} | AI thinking... | Engine: {}",
+                "Mode: {} | AI thinking... | Engine: {}",
+            ],
+            Text::StatusClocks => [" | 红 {} 黑 {}", " | Red {} Black {}", " | 红 {} 黑 {}"],
+
+            Text::ThinkingTitle => ["思考", "Thinking", "思考 Thinking"],
+            Text::ThinkingDisabled => [
+                "思考显示已关闭",
+                "Thinking display disabled",
+                "Thinking display disabled",
+            ],
+            Text::EngineCalculating => [
+                "引擎计算中...",
+                "Engine is calculating...",
+                "Engine is calculating...",
+            ],
+            Text::Depth => ["深度 {}", "Depth {}", "深度 Depth {}"],
+            Text::Score => ["分数 {}", "Score {}", "分数 Score {}"],
+            Text::Nodes => ["节点 {}", "Nodes {}", "节点 Nodes {}"],
+            Text::Pv => ["变着: ", "PV: ", "变着 PV: "],
+            Text::NoSearch => ["尚未搜索", "No search yet", "No search yet"],
+            Text::AnalysisTitle => ["分析", "Analysis", "分析 Analysis"],
+            Text::KibitzTitle => ["旁观: {}", "Kibitz: {}", "旁观 Kibitz: {}"],
+            Text::AnalysisDepth => ["深度 {}", "Depth {}", "深度 {}"],
+            Text::AnalysisScore => ["分数 {}", "Score {}", "分数 {}"],
+            Text::AnalysisNodes => ["节点 {}", "Nodes {}", "节点 {}"],
+            Text::EngineAnalyzing => [
+                "引擎分析中...",
+                "Engine is analyzing...",
+                "Engine is analyzing...",
+            ],
+            Text::Hash => ["哈希", "Hash", "哈希"],
+            Text::HashMiss => ["未命中", "miss", "未命中 miss"],
+            Text::LearnTitle => ["学习", "Learn", "学习 Learn"],
+            Text::CannotMove => ["现在不能走", "cannot move now", "cannot move now"],
+            Text::ReachesOne => ["可到 1 个点", "can reach 1 point", "can reach 1 point"],
+            Text::ReachesMany => ["可到 {} 个点", "can reach {} points", "can reach {} points"],
+            Text::KindMobility => [
+                "同类棋子共 {} 步, 全方共 {} 步",
+                "Its kind: {} of the side's {} moves",
+                "Its kind: {} of the side's {} moves",
+            ],
+            Text::LearnHint => [
+                "把光标移到棋子上查看走法",
+                "Move the cursor onto a piece to see how it moves",
+                "Move the cursor onto a piece to see how it moves",
+            ],
+            Text::EvalTitle => ["局势", "Evaluation", "局势 Evaluation"],
+            Text::RedShort => ["红", "Red", "红"],
+            Text::BlackShort => ["黑", "Black", "黑"],
+            Text::Captures => ["{} 吃{}", "{} took {}", "{} 吃{}"],
+            Text::NoEvaluations => [
+                "暂无评估",
+                "No evaluations yet",
+                "暂无评估 No evaluations yet",
+            ],
+            Text::CommentTitle => [" 注释: {} ", " Comment: {} ", " 注释 Comment: {} "],
+            Text::CommentKeys => [
+                "[Enter] 保存  [Esc] 取消  留空即删除",
+                "[Enter] Save  [Esc] Cancel  Empty removes",
+                "[Enter] Save  [Esc] Cancel  Empty removes",
+            ],
+            Text::NoticeTitle => ["提示", "Info", "提示 Info"],
+
+            Text::MoveUndone => ["已撤销", "Move undone", "Move undone"],
+            Text::NoMovesToUndo => ["没有可撤销的着法", "No moves to undo", "No moves to undo"],
+            Text::MoveRedone => ["已重做: {}", "Move redone: {}", "Move redone: {}"],
+            Text::NoMovesToRedo => ["没有可重做的着法", "No moves to redo", "No moves to redo"],
+            Text::NotAvailableRemote => [
+                "网络或通信对局中不可用",
+                "Not available during an online or correspondence game",
+                "Not available during an online or correspondence game",
+            ],
+            Text::WaitForAi => [
+                "请等待 AI 走棋",
+                "Wait for the AI to move",
+                "Wait for the AI to move",
+            ],
+            Text::PositionSetUp => ["局面已摆好", "Position set up", "Position set up"],
+            Text::CannotPlayPosition => [
+                "无法使用此局面: {}",
+                "Can't play this position: {}",
+                "Can't play this position: {}",
+            ],
+            Text::AnalysisOn => ["分析: 开", "Analysis: on", "Analysis: on"],
+            Text::AnalysisOff => ["分析: 关", "Analysis: off", "Analysis: off"],
+            Text::AnalysisError => ["分析出错: {}", "Analysis error: {}", "Analysis error: {}"],
+            Text::Resumed => [
+                "已恢复存档对局 ({} 步)",
+                "Resumed saved game ({} moves)",
+                "Resumed saved game ({} moves)",
+            ],
+            Text::ErrorMessage => ["错误: {}", "Error: {}", "Error: {}"],
+            Text::Saved => ["已保存 {}", "Saved {}", "Saved {}"],
+            Text::SaveFailed => [
+                "保存 {} 出错: {}",
+                "Error saving {}: {}",
+                "Error saving {}: {}",
+            ],
+            Text::Copied => ["已复制 {}", "Copied {}", "Copied {}"],
+            Text::CopyFailed => ["复制出错: {}", "Copy failed: {}", "Copy failed: {}"],
+            Text::CopiedPgn => [
+                "已复制 PGN ({} 步)",
+                "Copied PGN ({} moves)",
+                "Copied PGN ({} moves)",
+            ],
+            Text::NotYourPiece => [
+                "不是你的棋子 - 轮到{}走",
+                "Not your piece - it's {}'s turn",
+                "Not your piece - it's {}'s turn",
+            ],
+            Text::NoPieceHere => [
+                "此处没有棋子",
+                "No piece at this position",
+                "No piece at this position",
+            ],
+            Text::MoveMade => ["走棋成功", "Move successful", "Move successful"],
+            Text::InvalidMove => ["不合法的着法: {}", "Invalid move: {}", "Invalid move: {}"],
+            Text::Spectating => [
+                "观战中: 着法来自服务器",
+                "Spectating: moves come from the server",
+                "Spectating: moves come from the server",
+            ],
+            Text::HintMessage => ["提示: {}", "Hint: {}", "Hint: {}"],
+            Text::NoHint => [
+                "没有可提示的着法",
+                "No move to suggest",
+                "No move to suggest",
+            ],
+            Text::HintUnavailable => [
+                "无法提示: {}",
+                "Hint unavailable: {}",
+                "Hint unavailable: {}",
+            ],
+            Text::AiModeMessage => ["AI 模式: {}", "AI mode: {}", "AI mode: {}"],
+            Text::AiDifficulty => ["AI 难度: {}", "AI difficulty: {}", "AI difficulty: {}"],
+            Text::ThinkingToggled => [
+                "已切换思考显示",
+                "Thinking display toggled",
+                "Thinking display toggled",
+            ],
+            Text::OpenFailed => [
+                "打开文件出错: {}",
+                "Error opening file: {}",
+                "Error opening file: {}",
+            ],
+            Text::Opened => ["已打开 {}", "Opened {}", "Opened {}"],
+            Text::OpenedWithWarnings => [
+                "已打开 {} ({} 个警告)",
+                "Opened {} ({} warnings)",
+                "Opened {} ({} warnings)",
+            ],
+            Text::EngineLoaded => [
+                "引擎已载入 ({})",
+                "Engine loaded ({})",
+                "Engine loaded ({})",
+            ],
+            Text::EngineLoadFailed => [
+                "载入引擎出错: {}",
+                "Error loading engine: {}",
+                "Error loading engine: {}",
+            ],
+            Text::RanOutOfTime => ["{}超时", "{} ran out of time", "{} ran out of time"],
+            Text::AiError => ["AI 出错: {}", "AI error: {}", "AI error: {}"],
+            Text::AiPlayed => ["AI 走: {}", "AI played: {}", "AI played: {}"],
+            Text::RematchMessage => [
+                "换边再战 - AI 模式: {}",
+                "Rematch - AI mode: {}",
+                "Rematch - AI mode: {}",
+            ],
+            Text::NewGame => ["新局: {}", "New game: {}", "New game: {}"],
+            Text::Enabled => ["开", "on", "on"],
+            Text::Disabled => ["关", "off", "off"],
+            Text::RemoveSaveFailed => [
+                "删除存档出错: {}",
+                "Error removing saved game: {}",
+                "Error removing saved game: {}",
+            ],
+            Text::ThinkingDisplay => [
+                "思考显示: {}",
+                "Thinking display: {}",
+                "Thinking display: {}",
+            ],
+            Text::FreeAnalysisMessage => [
+                "自由分析 (双方均可走): {}",
+                "Free analysis (either side moves): {}",
+                "Free analysis (either side moves): {}",
+            ],
+            Text::PvOnBoard => [
+                "棋盘上的引擎着法: {}",
+                "Engine line on board: {}",
+                "Engine line on board: {}",
+            ],
+            Text::ThemeMessage => ["主题: {}", "Theme: {}", "Theme: {}"],
+            Text::BlindfoldMessage => ["盲棋: {}", "Blindfold: {}", "Blindfold: {}"],
+            Text::AttackMapMessage => ["攻击图: {}", "Attack map: {}", "Attack map: {}"],
+            Text::LearningMode => ["学习模式: {}", "Learning mode: {}", "Learning mode: {}"],
+            Text::CoordinatesMessage => ["坐标: {}", "Coordinates: {}", "Coordinates: {}"],
+            Text::PieceStyleMessage => ["棋子: {}", "Pieces: {}", "Pieces: {}"],
+            Text::MoveHistoryStyle => ["着法记录: {}", "Move history: {}", "Move history: {}"],
+            Text::FenCopyFailed => [
+                "复制 FEN 出错: {}",
+                "Error copying FEN: {}",
+                "Error copying FEN: {}",
+            ],
+            Text::KibitzOff => ["旁观引擎: 关", "Kibitz: off", "Kibitz: off"],
+            Text::NoKibitzEngines => [
+                "没有旁观引擎: 请在 config.toml 中添加 [[kibitz]] 表",
+                "No kibitz engines: add [[kibitz]] tables to config.toml",
+                "No kibitz engines: add [[kibitz]] tables to config.toml",
+            ],
+            Text::KibitzFailed => [
+                "旁观引擎 {} 出错: {}",
+                "Kibitz engine {} failed: {}",
+                "Kibitz engine {} failed: {}",
+            ],
+            Text::KibitzEngines => [
+                "旁观引擎: {} 个",
+                "Kibitz: {} engines",
+                "Kibitz: {} engines",
+            ],
+            Text::KibitzStopped => [
+                "旁观引擎 {} 已停止: {}",
+                "Kibitz engine {} stopped: {}",
+                "Kibitz engine {} stopped: {}",
+            ],
+            Text::ResumeFailed => [
+                "恢复对局出错: {}",
+                "Error resuming game: {}",
+                "Error resuming game: {}",
+            ],
+            Text::FenParseFailed => [
+                "解析 FEN 出错: {}",
+                "Error parsing FEN: {}",
+                "Error parsing FEN: {}",
+            ],
+            Text::PositionName => ["局面", "position", "position"],
+            Text::PastedPosition => ["粘贴的局面", "pasted position", "pasted position"],
+            Text::BoardFlipped => [
+                "棋盘已翻转: {}在上方",
+                "Board flipped: {} at the top",
+                "Board flipped: {} at the top",
+            ],
+            Text::UnknownTheme => [
+                "错误: 未知主题 '{}'",
+                "Error: unknown theme '{}'",
+                "Error: unknown theme '{}'",
+            ],
+            Text::AccessibleMode => [
+                "无障碍模式: {}",
+                "Accessible mode: {}",
+                "Accessible mode: {}",
+            ],
+            Text::DescriptionCopied => [
+                "已复制局面描述",
+                "Position description copied",
+                "Position description copied",
+            ],
+            Text::PgnSaveFailed => [
+                "保存 PGN 出错: {}",
+                "Error saving PGN: {}",
+                "Error saving PGN: {}",
+            ],
+            Text::PgnCopyFailed => [
+                "复制 PGN 出错: {}",
+                "Error copying PGN: {}",
+                "Error copying PGN: {}",
+            ],
+            Text::FenPasteFailed => [
+                "粘贴 FEN 出错: {}",
+                "Error pasting FEN: {}",
+                "Error pasting FEN: {}",
+            ],
+            Text::WaitingForOpponent => [
+                "等待 {} 走棋",
+                "Waiting for {} to move",
+                "Waiting for {} to move",
+            ],
+            Text::InvalidMoveLesson => [
+                "不合法的着法: {}。{}",
+                "Invalid move: {}. {}",
+                "Invalid move: {}. {}",
+            ],
+            Text::PlayingOnline => [
+                "执{}对阵 {}",
+                "Playing {} against {}",
+                "Playing {} against {}",
+            ],
+            Text::BadServerPosition => [
+                "服务器发来的局面有误: {}",
+                "Server sent a bad position: {}",
+                "Server sent a bad position: {}",
+            ],
+            Text::TurnFileGameOver => ["对局结束: {}", "Game over: {}", "Game over: {}"],
+            Text::TurnFileToMove => [
+                "轮到{}走; 走后写出回合文件",
+                "{} to move; the turn file is written after it",
+                "{} to move; the turn file is written after it",
+            ],
+            Text::TurnWritten => [
+                "回合已写入 {}: 请发给对手",
+                "Turn written to {}: send it to your opponent",
+                "Turn written to {}: send it to your opponent",
+            ],
+            Text::TurnFileFailed => [
+                "写入回合文件出错: {}",
+                "Error writing turn file: {}",
+                "Error writing turn file: {}",
+            ],
+            Text::WatchingOnline => [
+                "观战 {} 对 {}",
+                "Watching {} against {}",
+                "Watching {} against {}",
+            ],
+            Text::OnlineMessage => ["联机: {}", "Online: {}", "Online: {}"],
+            Text::AnalysisStopped => [
+                "分析已停止: {}",
+                "Analysis stopped: {}",
+                "Analysis stopped: {}",
+            ],
+            Text::OpponentPlayed => ["对手走: {}", "Opponent played: {}", "Opponent played: {}"],
+            Text::OpponentMoveRejected => [
+                "联机: 对手的着法被拒绝: {}",
+                "Online: opponent move rejected: {}",
+                "Online: opponent move rejected: {}",
+            ],
+            Text::SidePlayed => ["{}走: {}", "{} played: {}", "{} played: {}"],
+            Text::OnlineMoveRejected => [
+                "联机: 着法被拒绝: {}",
+                "Online: move rejected: {}",
+                "Online: move rejected: {}",
+            ],
+            Text::OnlineGameOverReason => [
+                "联机对局结束: {} ({})",
+                "Online game over: {} ({})",
+                "Online game over: {} ({})",
+            ],
+            Text::OnlineGameOver => [
+                "联机对局结束: {}",
+                "Online game over: {}",
+                "Online game over: {}",
+            ],
+            Text::LoadFailed => [
+                "载入对局出错: {}",
+                "Error loading game: {}",
+                "Error loading game: {}",
+            ],
+            Text::NoLibraryDirectory => [
+                "没有棋谱库的数据目录",
+                "No data directory for the game library",
+                "No data directory for the game library",
+            ],
+            Text::LibraryReadFailed => [
+                "读取棋谱库出错: {}",
+                "Error reading game library: {}",
+                "Error reading game library: {}",
+            ],
+            Text::LibrarySaved => [
+                "已存入棋谱库: {}",
+                "Saved to library: {}",
+                "Saved to library: {}",
+            ],
+            Text::LibrarySaveFailed => [
+                "存入棋谱库出错: {}",
+                "Error saving to library: {}",
+                "Error saving to library: {}",
+            ],
+            Text::SavedGameIgnored => [
+                "已忽略存档: {}",
+                "Saved game ignored: {}",
+                "Saved game ignored: {}",
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_match_across_languages() {
+        // Spot-check a range of texts: every translation takes the same
+        // values
+        for text in [
+            Text::MoveCount,
+            Text::Stalemated,
+            Text::ReviewBar,
+            Text::SetupKeys,
+            Text::HistoryBrowserTitle,
+            Text::Material,
+            Text::OpenedWithWarnings,
+            Text::SaveFailed,
+            Text::KibitzFailed,
+            Text::OnlineGameOverReason,
+        ] {
+            let counts = text.translations().map(|s| s.matches("{}").count());
+            assert!(counts.iter().all(|&n| n == counts[0]), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_format_fills_placeholders_in_order() {
+        assert_eq!(
+            Lang::Zh.format(Text::OpenedWithWarnings, &[&"a.pgn", &2]),
+            "已打开 a.pgn (2 个警告)"
+        );
+        assert_eq!(
+            Lang::En.format(Text::Stalemated, &[&"Black"]),
+            "Black is stalemated"
+        );
+    }
+}
@@ -1,21 +1,66 @@
+pub mod ai;
+pub mod annotate;
+pub mod archive;
 pub mod board;
+#[cfg(feature = "tui")]
+pub mod bus;
+pub mod cli;
+pub mod clipboard;
+pub mod command;
 pub mod config;
+pub mod correspondence;
+pub mod database;
+pub mod describe;
+pub mod diagram;
+pub mod editor;
+pub mod endgame;
+pub mod error;
+pub mod events;
 pub mod fen;
 pub mod fen_io;
 pub mod fen_print;
+pub mod formats;
 pub mod game;
+pub mod handicap;
+pub mod i18n;
+pub mod library;
 pub mod notation;
+pub mod online;
+pub mod opening;
+pub mod paths;
+pub mod persistence;
 pub mod pgn;
+pub mod pgn_import;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod render;
+pub mod report;
+pub mod rng;
+pub mod state;
+pub mod stats;
+pub mod tournament;
+pub mod tutor;
 pub mod types;
 pub mod ucci;
+#[cfg(feature = "tui")]
 pub mod ui;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "tui")]
+pub mod widget;
 pub mod xml;
+pub mod zobrist;
 
-pub use board::Board;
+pub use board::{AttackMap, Board, IllegalMoveReason};
+pub use error::{Error, Result};
 pub use fen::{board_to_fen, fen_to_board, FenError};
 pub use fen_io::{load_fen_file, read_fen_file, write_fen_file};
 pub use fen_print::{print_board_ascii, print_game_state};
-pub use game::{AiConfig, AiMode, Game, GameController, GameResult, GameState, Move, MoveError};
+pub use game::{
+    AiConfig, AiMode, Candidate, Difficulty, Game, GameController, GameResult, GameState, Mobility,
+    Move, MoveError, MoveStats, PgnExportOptions, SearchLimit, StalemateRule, DEFAULT_MOVE_LIMIT,
+    REPETITION_LIMIT,
+};
 pub use pgn::{PgnGame, PgnGameResult, PgnMove, PgnTag};
 // Re-export PgnGameResult as PgnResult for convenience
 pub use pgn::PgnGameResult as PgnResult;
@@ -23,7 +68,10 @@ pub use types::{move_to_simple_notation, Color, Piece, PieceType, Position};
 pub use xml::{pgn_to_xml, save_content, xml_to_pgn};
 
 // Re-export UI for testing
+#[cfg(feature = "tui")]
 pub use ui::UI;
+#[cfg(feature = "tui")]
+pub use widget::{BoardState, BoardWidget};
 
 // Re-export notation types
 pub use notation::iccs::{iccs_to_move, iccs_to_position, move_to_iccs, position_to_iccs};
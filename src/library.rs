@@ -0,0 +1,202 @@
+//! Game library: finished games kept one PGN file per game
+//!
+//! Games are saved in a directory, by default `archives` in the data
+//! directory (see [`Paths::archive_dir`](crate::paths::Paths::archive_dir)),
+//! under names made from the time they ended, e.g.
+//! `2026-10-17_142501.pgn`. Times are UTC, so the names sort in the order
+//! the games were played.
+
+use crate::archive::ArchiveReader;
+use crate::game::Game;
+use crate::pgn::PgnGame;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A game in the library
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    pub game: PgnGame,
+}
+
+impl LibraryEntry {
+    /// When the game was saved, e.g. "2026-10-17 14:25", taken from the
+    /// file name
+    pub fn saved_at(&self) -> Option<String> {
+        let stem = self.path.file_stem()?.to_str()?;
+        let (date, time) = stem.split_once('_')?;
+        let time = time
+            .get(..4)
+            .filter(|t| t.bytes().all(|b| b.is_ascii_digit()))?;
+        Some(format!("{} {}:{}", date, &time[..2], &time[2..]))
+    }
+}
+
+/// Save a game in the library directory, creating it if needed
+///
+/// The game is tagged with the date it was saved, and with its start
+/// position when that is not the usual one. Returns the new file's path.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::library::{archive_game, list_games};
+/// use cn_chess_tui::{Game, Position};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let mut game = Game::new();
+/// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+/// archive_game(dir.path(), &game).unwrap();
+///
+/// let games = list_games(dir.path()).unwrap();
+/// assert_eq!(games.len(), 1);
+/// assert_eq!(games[0].game.moves.len(), 1);
+/// ```
+pub fn archive_game(dir: &Path, game: &Game) -> io::Result<PathBuf> {
+    archive_game_at(dir, game, SystemTime::now())
+}
+
+/// [`archive_game`] with the time the game is saved at
+pub fn archive_game_at(dir: &Path, game: &Game, time: SystemTime) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_date(secs / 86_400);
+    let seconds = secs % 86_400;
+
+    let mut pgn = game.to_pgn();
+    pgn.set_tag("Date", format!("{:04}.{:02}.{:02}", year, month, day));
+    let start = game.at_ply(0).to_fen();
+    if start != Game::new().to_fen() {
+        pgn.set_tag("FEN", start);
+    }
+
+    let stem = format!(
+        "{:04}-{:02}-{:02}_{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    // Games ending in the same second get a numbered suffix
+    let mut path = dir.join(format!("{}.pgn", stem));
+    let mut n = 1;
+    while path.exists() {
+        n += 1;
+        path = dir.join(format!("{}_{}.pgn", stem, n));
+    }
+    std::fs::write(&path, pgn.to_string())?;
+    Ok(path)
+}
+
+/// The games in a library directory, most recent first
+///
+/// Files that are not PGN or hold no readable game are left out. A missing
+/// directory is an empty library.
+pub fn list_games(dir: &Path) -> io::Result<Vec<LibraryEntry>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let is_pgn = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("pgn"));
+        if is_pgn && path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort_by(|a, b| b.cmp(a));
+
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let game = ArchiveReader::open(&path).ok()?.iter().ok()?.next()?.ok()?;
+            Some(LibraryEntry { path, game })
+        })
+        .collect())
+}
+
+/// Year, month and day of a count of days since 1970-01-01
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Howard Hinnant's days-to-civil algorithm, for dates after 1970
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Position;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(59), (1970, 3, 1));
+        // 2000 was a leap year, 2100 will not be
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(civil_date(47_540), (2100, 2, 28));
+        assert_eq!(civil_date(47_541), (2100, 3, 1));
+    }
+
+    #[test]
+    fn test_archive_names_sort_by_time() {
+        let dir = TempDir::new().unwrap();
+        let time = UNIX_EPOCH + Duration::from_secs(1_792_247_101);
+        let game = Game::new();
+
+        let first = archive_game_at(dir.path(), &game, time).unwrap();
+        assert_eq!(first.file_name().unwrap(), "2026-10-17_142501.pgn");
+        let same_second = archive_game_at(dir.path(), &game, time).unwrap();
+        assert_eq!(same_second.file_name().unwrap(), "2026-10-17_142501_2.pgn");
+        let later = archive_game_at(dir.path(), &game, time + Duration::from_secs(60)).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a game").unwrap();
+
+        let games = list_games(dir.path()).unwrap();
+        let paths: Vec<&Path> = games.iter().map(|e| e.path.as_path()).collect();
+        assert_eq!(paths, [later.as_path(), &same_second, &first]);
+        assert_eq!(games[2].saved_at().as_deref(), Some("2026-10-17 14:25"));
+        assert_eq!(
+            games[0].game.get_tag("Date").map(String::as_str),
+            Some("2026.10.17")
+        );
+    }
+
+    #[test]
+    fn test_archive_keeps_custom_start_position() {
+        let dir = TempDir::new().unwrap();
+        let fen = "3k5/9/9/9/9/9/9/9/4R4/4K4 w - - 0 1";
+        let mut game = Game::from_fen(fen).unwrap();
+        game.make_move(Position::from_xy(4, 8), Position::from_xy(3, 8))
+            .unwrap();
+        let path = archive_game(dir.path(), &game).unwrap();
+
+        let saved = &list_games(dir.path()).unwrap()[0];
+        assert_eq!(saved.path, path);
+        assert_eq!(saved.game.get_tag("FEN").map(String::as_str), Some(fen));
+    }
+
+    #[test]
+    fn test_missing_library_is_empty() {
+        let dir = TempDir::new().unwrap();
+        assert!(list_games(&dir.path().join("none")).unwrap().is_empty());
+    }
+}
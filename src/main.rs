@@ -1,21 +1,52 @@
-mod board;
-mod fen;
-mod fen_io;
-mod fen_print;
-mod game;
-mod notation;
-mod pgn;
-mod types;
-mod ucci;
-mod ui;
-
-use crate::fen::FenError;
-use crate::game::{AiMode, Game, GameController};
-use crate::types::Position;
-use crate::ucci::Info;
-use crate::ui::AiMenuState;
+use cn_chess_tui::annotate::{AnnotateOptions, EngineEvaluator, NativeEvaluator};
+use cn_chess_tui::archive::ArchiveReader;
+use cn_chess_tui::board::Board;
+use cn_chess_tui::bus::{AppEvent, EventBus};
+use cn_chess_tui::cli;
+use cn_chess_tui::clipboard;
+use cn_chess_tui::command::{self, Command};
+use cn_chess_tui::config::{Config, SideSettings};
+use cn_chess_tui::correspondence::TurnFile;
+use cn_chess_tui::describe;
+use cn_chess_tui::editor::BoardEditor;
+use cn_chess_tui::fen::{self, FenError};
+use cn_chess_tui::fen_print::{self, PrintStyle};
+use cn_chess_tui::formats::xqf;
+use cn_chess_tui::game::{
+    AiMode, Difficulty, Game, GameController, GameState, MoveError, PgnExportOptions,
+};
+use cn_chess_tui::handicap::{Handicap, HANDICAPS};
+use cn_chess_tui::i18n::{Lang, Text};
+use cn_chess_tui::library;
+use cn_chess_tui::notation::{self, format::MoveFormatter};
+use cn_chess_tui::online::{OnlineClient, OnlineEvent, OnlineGame, Transport, WatchedGame};
+use cn_chess_tui::opening::OpeningBook;
+use cn_chess_tui::paths::Paths;
+use cn_chess_tui::persistence::{self, Session};
+use cn_chess_tui::pgn::PgnGame;
+use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+use cn_chess_tui::render::report::{ReportFormat, ReportOptions};
+use cn_chess_tui::render::svg::board_svg;
+use cn_chess_tui::report::{self, ErrorCode, ErrorReport};
+use cn_chess_tui::rng::SeededRng;
+use cn_chess_tui::state::{self, AppState, FileKind, RecentFile};
+use cn_chess_tui::stats::{Outcome, Stats};
+use cn_chess_tui::tutor;
+use cn_chess_tui::types::{Color, Position};
+use cn_chess_tui::ucci::engine::EngineError;
+use cn_chess_tui::ucci::kibitz::Kibitz;
+use cn_chess_tui::ucci::{self, EngineProtocol};
+use cn_chess_tui::ui::{
+    self, AiMenuState, CommandLineState, CommentEditorState, GamePickerState, HistoryBrowserState,
+    LibraryState, MoveListState, NewGameMenuState, OpenMenuState, PieceStyle, PlayerSide,
+    ReviewState, SetupState, Theme,
+};
+use cn_chess_tui::xml::xml_to_pgn;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    cursor,
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyCode, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -27,30 +58,852 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
-use std::io;
+use std::io::{self, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::{Arc, Mutex, Once};
 use std::time::{Duration, Instant};
 
 fn print_usage() {
     println!("Chinese Chess TUI - Usage:");
     println!("  cn_chess_tui                    Start new game (PvP)");
-    println!("  cn_chess_tui --engine <path>    Start with AI engine");
+    println!("  cn_chess_tui --engine <path> [protocol]  Start with an engine (auto/ucci/uci)");
+    println!("  cn_chess_tui --resume           Resume the game saved on quit");
     println!("  cn_chess_tui --ai <color>       Set AI color (red/black/off)");
     println!("  cn_chess_tui --print <fen>      Print FEN position");
+    println!(
+        "      [--print-style ascii|unicode] [--print-svg <out.svg>]  Board style, or save an SVG"
+    );
+    println!("  cn_chess_tui --validate-fen <fen>       List what makes a position impossible");
+    println!("  cn_chess_tui --best-move <fen> [depth]  Search the best move");
+    println!("  cn_chess_tui --check-move <fen> <move>  Check an ICCS move (e.g. h2e2)");
+    println!("  cn_chess_tui --analyze <fen> [depth]    Evaluate and list legal moves");
+    println!("  cn_chess_tui --stdin [--depth <n> | --engine <path> [--time <s>]]");
+    println!("                                  Read FENs from stdin, write a JSON line for each");
+    println!(
+        "  cn_chess_tui --convert <in> <out>       Convert between .pgn, .xml, .fen and .json"
+    );
+    println!(
+        "  cn_chess_tui --convert <dir> <out-dir> --to <format>  Convert every game in a directory"
+    );
+    println!("  cn_chess_tui --fen-history <in> <out>   Write the FEN after every move");
+    println!(
+        "  cn_chess_tui --search-position <fen|material> <pgn-dir>  List games reaching a position"
+    );
+    println!(
+        "  cn_chess_tui --dedupe <pgn-dir> [--remove]  List repeated games, or delete the copies"
+    );
+    println!("  cn_chess_tui --perft <depth> [--fen <fen>]  Count move sequences (start position by default)");
+    println!(
+        "  cn_chess_tui --export-report <in> <out.md|out.html> [--every <n>]  Write a game report with diagrams"
+    );
+    println!(
+        "  cn_chess_tui --annotate <in> <out.pgn> [--engine <path>] [--time <s>] [--threshold <cp>]"
+    );
+    println!("                                  Comment every move with engine evaluations");
     println!("  cn_chess_tui --fen <fen>        Load from FEN");
+    println!("  cn_chess_tui --handicap <spec>  Start a handicap game (horse, two-horses, chariot, chariot-horse)");
+    println!("  cn_chess_tui --setup [fen]      Set up a position by hand");
+    println!("  cn_chess_tui --cli [fen]        Play by typing moves, without the TUI");
+    println!("  cn_chess_tui --ucci-serve       Run the built-in AI as a UCCI engine");
     println!("  cn_chess_tui --file <path>      Load from file");
-    println!("  cn_chess_tui --pgn <path>       Load from PGN");
+    println!("  cn_chess_tui --pgn <path> [--lenient]  Load from PGN (auto-correct typos)");
+    println!("  cn_chess_tui --xqf <path>       Load the main line of an XQF file");
+    println!("  cn_chess_tui --online <url> <name>  Play on an online server (ws://...)");
+    println!("  cn_chess_tui --spectate <url>   Watch a game in progress on an online server");
+    println!("  cn_chess_tui --turn <file> [name]  Play one move of a correspondence game");
+    println!("  cn_chess_tui --library          Browse and replay saved games");
+    println!("  cn_chess_tui --stats            Show your results and rating against the AI");
     println!("  cn_chess_tui --export-pgn       Export current game to PGN (not yet implemented)");
-    println!("  cn_chess_tui --export-xml       Export current game to XML (not yet implemented)");
+    println!("  cn_chess_tui --export-xml <in> <out>  Write a PGN, FEN or JSON game as XML");
+    println!("  cn_chess_tui --import-xml <path>  Replay an XML game from its start");
     println!("  cn_chess_tui --help             Show this help");
+    println!();
+    println!("Add --json to --print, --validate-fen, --best-move, --check-move, --analyze,");
+    println!("--convert, --fen-history, --search-position, --dedupe, --perft,");
+    println!("--export-report, --annotate or --export-xml for machine-readable output.");
+    println!();
+    println!("  --variety <cp>   Built-in AI picks among moves within <cp> of the best");
+    println!("  --blunder-rate <pct>  Built-in AI plays any legal move <pct> percent of the time");
+    println!("  --seed <n>       Seed random choices, for reproducible games");
+    println!("  --difficulty <level>  AI strength: beginner, intermediate, advanced or max");
+    println!("  --theme <name>   Color theme: classic, high-contrast, light, colorblind, mono");
+    println!("  --pieces <style> Piece characters: chinese or letters (for terminals without CJK)");
+    println!("  --lang <lang>    Interface language: bilingual, en or zh");
+    println!("  --red-engine <path>, --black-engine <path>  Engine for one side only");
+    println!("  --red-depth <n>, --black-depth <n>          Search depth for one side's AI");
+    println!("  --red-movetime <ms>, --black-movetime <ms>  Engine time per move for one side");
+    println!("  --red-clock <m+s>, --black-clock <m+s>      Clock for one side only, e.g. 10+5");
+}
+
+/// Override config.toml settings with the --seed, --variety, --blunder-rate,
+/// --theme, --difficulty, --pieces and --lang flags and the per-side flags,
+/// removing them from the argument list
+fn apply_cli_overrides(args: &mut Vec<String>, config: &mut Config) -> Result<(), ErrorReport> {
+    apply_side_overrides(args, "red", &mut config.red)?;
+    apply_side_overrides(args, "black", &mut config.black)?;
+    if let Some(s) = take_flag_value(args, "--seed")? {
+        let seed = s.parse().map_err(|_| {
+            ErrorReport::new(ErrorCode::InvalidArgument, format!("Invalid seed: {}", s))
+        })?;
+        config.seed = Some(seed);
+    }
+    if let Some(s) = take_flag_value(args, "--variety")? {
+        let variety = s.parse().map_err(|_| {
+            ErrorReport::new(
+                ErrorCode::InvalidArgument,
+                format!("Invalid variety: {}", s),
+            )
+        })?;
+        config.ai_variety = Some(variety);
+    }
+    if let Some(s) = take_flag_value(args, "--blunder-rate")? {
+        let rate = s.parse().ok().filter(|rate| *rate <= 100).ok_or_else(|| {
+            ErrorReport::new(
+                ErrorCode::InvalidArgument,
+                format!("Invalid blunder rate: {} (expected 0 to 100)", s),
+            )
+        })?;
+        config.ai_blunder_rate = Some(rate);
+    }
+    if let Some(name) = take_flag_value(args, "--theme")? {
+        if Theme::by_name(&name).is_none() {
+            let names: Vec<&str> = Theme::ALL.iter().map(|theme| theme.name).collect();
+            return Err(ErrorReport::new(
+                ErrorCode::InvalidArgument,
+                format!("Unknown theme: {} (expected {})", name, names.join(", ")),
+            ));
+        }
+        config.theme = Some(name);
+    }
+    if let Some(name) = take_flag_value(args, "--difficulty")? {
+        let difficulty = Difficulty::by_name(&name).ok_or_else(|| {
+            ErrorReport::new(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "Unknown difficulty: {} (expected beginner, intermediate, advanced or max)",
+                    name
+                ),
+            )
+        })?;
+        config.difficulty = Some(difficulty);
+    }
+    if let Some(name) = take_flag_value(args, "--pieces")? {
+        config.pieces = Some(match name.as_str() {
+            "chinese" => PieceStyle::Chinese,
+            "letters" => PieceStyle::Letters,
+            _ => {
+                return Err(ErrorReport::new(
+                    ErrorCode::InvalidArgument,
+                    format!(
+                        "Unknown piece style: {} (expected chinese or letters)",
+                        name
+                    ),
+                ))
+            }
+        });
+    }
+    if let Some(name) = take_flag_value(args, "--lang")? {
+        let lang = Lang::by_name(&name).ok_or_else(|| {
+            ErrorReport::new(
+                ErrorCode::InvalidArgument,
+                format!("Unknown language: {} (expected bilingual, en or zh)", name),
+            )
+        })?;
+        config.lang = lang;
+    }
+    Ok(())
+}
+
+/// Override a side's `[red]` or `[black]` settings with the
+/// --<side>-engine, --<side>-depth, --<side>-movetime and --<side>-clock
+/// flags
+fn apply_side_overrides(
+    args: &mut Vec<String>,
+    side: &str,
+    settings: &mut SideSettings,
+) -> Result<(), ErrorReport> {
+    let invalid = |flag: &str, value: &str| {
+        ErrorReport::new(
+            ErrorCode::InvalidArgument,
+            format!("Invalid value for --{}-{}: {}", side, flag, value),
+        )
+    };
+    if let Some(path) = take_flag_value(args, &format!("--{}-engine", side))? {
+        settings.engine_path = Some(PathBuf::from(path));
+    }
+    if let Some(s) = take_flag_value(args, &format!("--{}-depth", side))? {
+        let depth = s.parse().map_err(|_| invalid("depth", &s))?;
+        settings.search_depth = Some(depth);
+        settings.engine_depth = Some(depth);
+    }
+    if let Some(s) = take_flag_value(args, &format!("--{}-movetime", side))? {
+        settings.engine_movetime = Some(s.parse().map_err(|_| invalid("movetime", &s))?);
+    }
+    if let Some(s) = take_flag_value(args, &format!("--{}-clock", side))? {
+        let time_control = s
+            .parse()
+            .map_err(|e: String| ErrorReport::new(ErrorCode::InvalidArgument, e))?;
+        settings.time_control = Some(time_control);
+    }
+    Ok(())
+}
+
+/// A message's text in the interface's language
+fn tr(text: Text) -> &'static str {
+    ui::UI::lang().text(text)
+}
+
+/// A message in the interface's language with its `{}`s filled in
+fn trf(text: Text, args: &[&dyn std::fmt::Display]) -> String {
+    ui::UI::lang().format(text, args)
+}
+
+/// `on` or `off` in the interface's language
+fn on_off(on: bool) -> &'static str {
+    tr(if on { Text::Enabled } else { Text::Disabled })
+}
+
+/// A side's name for messages: `Red` or `Black`, or `红方`/`黑方` in Chinese
+fn side(color: Color) -> String {
+    match ui::UI::lang() {
+        Lang::Zh => Lang::Zh.side(color).to_string(),
+        _ => color.to_string(),
+    }
+}
+
+/// Remove `flag` and the value after it from the argument list
+fn take_flag_value(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, ErrorReport> {
+    let Some(index) = args.iter().position(|a| a == flag) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        return Err(ErrorReport::new(
+            ErrorCode::MissingArgument,
+            format!("{} requires a value", flag),
+        ));
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+/// Print a report as pretty JSON
+fn print_json<T: serde::Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(s) => println!("{}", s),
+        Err(e) => eprintln!("Error serializing output: {}", e),
+    }
 }
 
-fn print_fen_position(fen: &str) -> Result<(), FenError> {
-    let game = Game::from_fen(fen)?;
-    fen_print::print_game_state(&game);
+/// Fetch a required positional argument for a query command
+fn required_arg<'a>(args: &'a [String], index: usize, what: &str) -> Result<&'a str, ErrorReport> {
+    args.get(index).map(String::as_str).ok_or_else(|| {
+        ErrorReport::new(
+            ErrorCode::MissingArgument,
+            format!("{} requires {}", args[0], what),
+        )
+    })
+}
+
+/// Parse the optional search depth argument
+fn depth_arg(args: &[String], index: usize, default: u32) -> Result<u32, ErrorReport> {
+    match args.get(index) {
+        Some(s) => s.parse().map_err(|_| {
+            ErrorReport::new(
+                ErrorCode::InvalidArgument,
+                format!("Invalid search depth: {}", s),
+            )
+        }),
+        None => Ok(default),
+    }
+}
+
+/// Run a non-interactive query command, printing text or JSON
+///
+/// `args[0]` is the command name.
+fn run_query(args: &[String], json: bool, config: &Config) -> Result<(), ErrorReport> {
+    let ai = config.ai_config();
+    match args[0].as_str() {
+        "--print" => {
+            let game = report::game_from_fen(required_arg(args, 1, "a FEN string")?)?;
+            let mut style = PrintStyle::default();
+            let mut index = 2;
+            while let Some(arg) = args.get(index) {
+                let value = required_arg(args, index + 1, "a value after each option")?;
+                match arg.as_str() {
+                    "--print-style" => {
+                        style = PrintStyle::by_name(value).ok_or_else(|| {
+                            ErrorReport::new(
+                                ErrorCode::InvalidArgument,
+                                format!("Unknown print style: {} (ascii or unicode)", value),
+                            )
+                        })?;
+                    }
+                    "--print-svg" => {
+                        std::fs::write(value, board_svg(game.board(), None, None)).map_err(
+                            |e| ErrorReport::new(ErrorCode::Io, format!("{}: {}", value, e)),
+                        )?;
+                    }
+                    other => {
+                        return Err(ErrorReport::new(
+                            ErrorCode::UnknownArgument,
+                            format!("Unknown argument: {}", other),
+                        ))
+                    }
+                }
+                index += 2;
+            }
+            if json {
+                print_json(&report::position_report(&game));
+            } else {
+                fen_print::write_game_state(&mut io::stdout().lock(), &game, style)
+                    .map_err(|e| ErrorReport::new(ErrorCode::Io, format!("stdout: {}", e)))?;
+            }
+        }
+        "--validate-fen" => {
+            let result = report::validation_report(required_arg(args, 1, "a FEN string")?)?;
+            if json {
+                print_json(&result);
+            } else if result.valid {
+                println!("valid");
+            } else {
+                for problem in &result.problems {
+                    println!("invalid: {}", problem.message);
+                }
+            }
+        }
+        "--best-move" => {
+            let game = report::game_from_fen(required_arg(args, 1, "a FEN string")?)?;
+            let depth = depth_arg(args, 2, ai.effective_search_depth())?;
+            let variety = ai.effective_variety();
+            let result = if variety > 0 || ai.blunder_rate > 0 {
+                let mut rng = SeededRng::from_seed(ai.seed);
+                report::varied_move_report(&game, depth, variety, ai.blunder_rate, &mut rng)
+            } else {
+                report::best_move_report(&game, depth)
+            };
+            if json {
+                print_json(&result);
+            } else {
+                match (&result.best_move, result.score) {
+                    (Some(mv), Some(score)) => println!("bestmove {} score {}", mv, score),
+                    _ => println!("nobestmove"),
+                }
+            }
+        }
+        "--check-move" => {
+            let game = report::game_from_fen(required_arg(args, 1, "a FEN string")?)?;
+            let mv = required_arg(args, 2, "a move")?;
+            let result = report::check_move_report(&game, mv)?;
+            if json {
+                print_json(&result);
+            } else if result.legal {
+                println!("{} is legal", result.mv);
+            } else {
+                println!(
+                    "{} is illegal: {}",
+                    result.mv,
+                    result.reason.as_deref().unwrap_or("unknown reason")
+                );
+            }
+        }
+        "--analyze" => {
+            let game = report::game_from_fen(required_arg(args, 1, "a FEN string")?)?;
+            let result =
+                report::analysis_report(&game, depth_arg(args, 2, ai.effective_search_depth())?);
+            if json {
+                print_json(&result);
+            } else {
+                fen_print::print_game_state(&game);
+                println!("Eval: {}", result.eval);
+                println!("Legal moves: {}", result.legal_moves.join(" "));
+                match result.best_move.best_move {
+                    Some(mv) => println!(
+                        "Best move: {}",
+                        MoveFormatter::new(config.notation).format_iccs(&game, &mv)
+                    ),
+                    None => println!("Best move: none"),
+                }
+            }
+        }
+        "--search-position" => {
+            let query = required_arg(args, 1, "a FEN or material signature")?;
+            let path = required_arg(args, 2, "a PGN file or directory")?;
+            let result = report::search_position_report(query, path)?;
+            if json {
+                print_json(&result);
+            } else {
+                for found in &result.matches {
+                    println!(
+                        "{} #{} ply {}: {} - {} {}",
+                        found.file,
+                        found.game + 1,
+                        found.ply,
+                        found.red.as_deref().unwrap_or("?"),
+                        found.black.as_deref().unwrap_or("?"),
+                        found.result.as_deref().unwrap_or("*")
+                    );
+                }
+                println!(
+                    "{} of {} games reach the position",
+                    result.matches.len(),
+                    result.searched
+                );
+                if result.skipped > 0 {
+                    println!("{} games could not be read", result.skipped);
+                }
+            }
+        }
+        "--dedupe" => {
+            let path = required_arg(args, 1, "a PGN file or directory")?;
+            let remove = match args.get(2).map(String::as_str) {
+                Some("--remove") => true,
+                Some(other) => {
+                    return Err(ErrorReport::new(
+                        ErrorCode::UnknownArgument,
+                        format!("Unknown argument: {}", other),
+                    ))
+                }
+                None => false,
+            };
+            let result = report::dedupe_report(path, remove)?;
+            if json {
+                print_json(&result);
+            } else {
+                for duplicate in &result.duplicates {
+                    println!(
+                        "{} #{} repeats {} #{}: {} - {}",
+                        duplicate.file,
+                        duplicate.game + 1,
+                        duplicate.original_file,
+                        duplicate.original_game + 1,
+                        duplicate.red.as_deref().unwrap_or("?"),
+                        duplicate.black.as_deref().unwrap_or("?")
+                    );
+                }
+                println!(
+                    "{} duplicates among {} games",
+                    result.duplicates.len(),
+                    result.checked
+                );
+                if result.skipped > 0 {
+                    println!("{} games could not be read", result.skipped);
+                }
+                if remove {
+                    println!("Removed {} games", result.removed);
+                }
+            }
+        }
+        "--convert" if Path::new(required_arg(args, 1, "an input path")?).is_dir() => {
+            let output = required_arg(args, 2, "an output directory")?;
+            let format = match args.get(3).map(String::as_str) {
+                Some("--to") => required_arg(args, 4, "a format after --to")?,
+                _ => {
+                    return Err(ErrorReport::new(
+                        ErrorCode::MissingArgument,
+                        "Converting a directory requires --to <pgn|xml|fen|json>",
+                    ))
+                }
+            };
+            let result = report::convert_directory(&args[1], output, format)?;
+            if json {
+                print_json(&result);
+            } else {
+                for converted in &result.converted {
+                    println!("{} -> {}", converted.input, converted.output);
+                }
+                for failed in &result.failed {
+                    eprintln!("Failed {}: {}", failed.input, failed.error);
+                }
+                println!(
+                    "Converted {} of {} files",
+                    result.converted.len(),
+                    result.converted.len() + result.failed.len()
+                );
+            }
+            if !result.failed.is_empty() {
+                process::exit(1);
+            }
+        }
+        "--convert" => {
+            let input = required_arg(args, 1, "an input path")?;
+            let output = required_arg(args, 2, "an output path")?;
+            let result = report::convert_file(input, output)?;
+            if json {
+                print_json(&result);
+            } else {
+                let games = match result.games {
+                    1 => String::new(),
+                    games => format!("{} games, ", games),
+                };
+                println!(
+                    "Converted {} ({}) to {} ({}), {}{} moves",
+                    result.input, result.from, result.output, result.to, games, result.moves
+                );
+            }
+        }
+        "--fen-history" => {
+            let input = required_arg(args, 1, "an input path")?;
+            let output = required_arg(args, 2, "an output path")?;
+            let result = report::export_fen_history(input, output)?;
+            if json {
+                print_json(&result);
+            } else {
+                println!("Wrote {} positions to {}", result.positions, result.output);
+            }
+        }
+        "--export-report" => {
+            let input = required_arg(args, 1, "an input path")?;
+            let output = required_arg(args, 2, "an output path")?;
+            let every = match args.get(3).map(String::as_str) {
+                Some("--every") => {
+                    let n = required_arg(args, 4, "a number of moves")?;
+                    let n: usize = n.parse().map_err(|_| {
+                        ErrorReport::new(
+                            ErrorCode::InvalidArgument,
+                            format!("Invalid number of moves: {}", n),
+                        )
+                    })?;
+                    (n > 0).then_some(n)
+                }
+                Some(other) => {
+                    return Err(ErrorReport::new(
+                        ErrorCode::UnknownArgument,
+                        format!("Unknown argument: {}", other),
+                    ))
+                }
+                None => ReportOptions::new(ReportFormat::Markdown).every,
+            };
+            let result = report::export_game_report(input, output, every)?;
+            if json {
+                print_json(&result);
+            } else {
+                println!(
+                    "Wrote a report of {} moves with {} diagrams to {}",
+                    result.moves, result.diagrams, result.output
+                );
+            }
+        }
+        "--annotate" => {
+            let input = required_arg(args, 1, "an input path")?;
+            let output = required_arg(args, 2, "an output path")?;
+            let mut engine = ai.engine_path.clone();
+            let mut movetime_ms = 1000;
+            let mut options = AnnotateOptions {
+                notation: config.notation,
+                ..AnnotateOptions::default()
+            };
+            let mut index = 3;
+            while let Some(arg) = args.get(index) {
+                let value = required_arg(args, index + 1, "a value after each option")?;
+                let invalid = || {
+                    ErrorReport::new(
+                        ErrorCode::InvalidArgument,
+                        format!("Invalid value for {}: {}", arg, value),
+                    )
+                };
+                match arg.as_str() {
+                    "--engine" => engine = Some(PathBuf::from(value)),
+                    "--time" => {
+                        let seconds: f64 = value.parse().map_err(|_| invalid())?;
+                        if !seconds.is_finite() || seconds <= 0.0 {
+                            return Err(invalid());
+                        }
+                        movetime_ms = (seconds * 1000.0).round() as u64;
+                    }
+                    "--threshold" => options.threshold = value.parse().map_err(|_| invalid())?,
+                    other => {
+                        return Err(ErrorReport::new(
+                            ErrorCode::UnknownArgument,
+                            format!("Unknown argument: {}", other),
+                        ))
+                    }
+                }
+                index += 2;
+            }
+
+            let mut progress = |done: usize, total: usize| {
+                eprint!("\rAnalyzing position {}/{}", done, total);
+                if done == total {
+                    eprintln!();
+                }
+            };
+            let result = match engine {
+                Some(path) => {
+                    let engine_error =
+                        |e: EngineError| ErrorReport::new(ErrorCode::Engine, e.to_string());
+                    let mut evaluator = EngineEvaluator::start(
+                        &path.to_string_lossy(),
+                        ai.engine_protocol,
+                        movetime_ms,
+                    )
+                    .map_err(engine_error)?;
+                    let result = report::annotate_game_file(
+                        input,
+                        output,
+                        &mut evaluator,
+                        &options,
+                        &mut progress,
+                    );
+                    evaluator.shutdown().map_err(engine_error)?;
+                    result?
+                }
+                None => report::annotate_game_file(
+                    input,
+                    output,
+                    &mut NativeEvaluator::new(ai.effective_search_depth()),
+                    &options,
+                    &mut progress,
+                )?,
+            };
+            if json {
+                print_json(&result);
+            } else {
+                println!(
+                    "Annotated {} moves with {} ({} blunders) to {}",
+                    result.moves, result.annotator, result.blunders, result.output
+                );
+            }
+        }
+        "--stdin" => {
+            let mut depth = None;
+            let mut engine = None;
+            let mut movetime_ms = 1000;
+            let mut index = 1;
+            while let Some(arg) = args.get(index) {
+                let value = required_arg(args, index + 1, "a value after each option")?;
+                let invalid = || {
+                    ErrorReport::new(
+                        ErrorCode::InvalidArgument,
+                        format!("Invalid value for {}: {}", arg, value),
+                    )
+                };
+                match arg.as_str() {
+                    "--depth" => depth = Some(value.parse().map_err(|_| invalid())?),
+                    "--engine" => engine = Some(value.to_string()),
+                    "--time" => {
+                        let seconds: f64 = value.parse().map_err(|_| invalid())?;
+                        if !seconds.is_finite() || seconds <= 0.0 {
+                            return Err(invalid());
+                        }
+                        movetime_ms = (seconds * 1000.0).round() as u64;
+                    }
+                    other => {
+                        return Err(ErrorReport::new(
+                            ErrorCode::UnknownArgument,
+                            format!("Unknown argument: {}", other),
+                        ))
+                    }
+                }
+                index += 2;
+            }
+
+            let io_error = |e: io::Error| ErrorReport::new(ErrorCode::Io, e.to_string());
+            let (input, output) = (io::stdin().lock(), io::stdout().lock());
+            let errors = match (engine, depth) {
+                (Some(path), _) => {
+                    let engine_error =
+                        |e: EngineError| ErrorReport::new(ErrorCode::Engine, e.to_string());
+                    let mut evaluator =
+                        EngineEvaluator::start(&path, ai.engine_protocol, movetime_ms)
+                            .map_err(engine_error)?;
+                    let errors = report::analyze_lines(input, output, Some(&mut evaluator));
+                    evaluator.shutdown().map_err(engine_error)?;
+                    errors.map_err(io_error)?
+                }
+                (None, Some(depth)) => {
+                    let mut evaluator = NativeEvaluator::new(depth);
+                    report::analyze_lines(input, output, Some(&mut evaluator)).map_err(io_error)?
+                }
+                (None, None) => report::analyze_lines(input, output, None).map_err(io_error)?,
+            };
+            if errors > 0 {
+                process::exit(1);
+            }
+        }
+        "--export-xml" => {
+            let input = required_arg(args, 1, "an input path")?;
+            let output = required_arg(args, 2, "an output path")?;
+            let result = report::export_xml(input, output)?;
+            if json {
+                print_json(&result);
+            } else {
+                println!(
+                    "Exported {} ({} moves) to {} as XML",
+                    result.input, result.moves, result.output
+                );
+            }
+        }
+        "--perft" => {
+            let depth = required_arg(args, 1, "a depth")?;
+            let depth = depth.parse().map_err(|_| {
+                ErrorReport::new(
+                    ErrorCode::InvalidArgument,
+                    format!("Invalid perft depth: {}", depth),
+                )
+            })?;
+            let game = match args.get(2).map(String::as_str) {
+                Some("--fen") => report::game_from_fen(required_arg(args, 3, "a FEN string")?)?,
+                Some(other) => {
+                    return Err(ErrorReport::new(
+                        ErrorCode::UnknownArgument,
+                        format!("Unknown argument: {}", other),
+                    ))
+                }
+                None => Game::new(),
+            };
+            let result = report::perft_report(&game, depth);
+            if json {
+                print_json(&result);
+            } else {
+                for mv in &result.moves {
+                    println!("{} {}", mv.mv, mv.nodes);
+                }
+                println!("perft {} nodes {}", result.depth, result.nodes);
+            }
+        }
+        other => {
+            return Err(ErrorReport::new(
+                ErrorCode::UnknownArgument,
+                format!("Unknown argument: {}", other),
+            ))
+        }
+    }
     Ok(())
 }
 
+/// Read every game in a PGN, XML or XQF file
+fn read_game_file(path: &Path, kind: FileKind) -> Result<Vec<PgnGame>, Box<dyn std::error::Error>> {
+    let games = match kind {
+        FileKind::Xml => {
+            let content = std::fs::read_to_string(path)?;
+            vec![xml_to_pgn(&content).ok_or("Failed to parse XML file")?]
+        }
+        FileKind::Xqf => vec![xqf::read_xqf_file(path)?],
+        _ => {
+            // Skip games that can't be read, unless none can
+            let mut games = Vec::new();
+            let mut first_error = None;
+            for game in ArchiveReader::open(path)?.iter()? {
+                match game {
+                    Ok(game) => games.push(game),
+                    Err(e) => {
+                        first_error.get_or_insert(e);
+                    }
+                }
+            }
+            match first_error {
+                Some(e) if games.is_empty() => return Err(e.into()),
+                _ => games,
+            }
+        }
+    };
+    if games.is_empty() {
+        return Err("No games found in file".into());
+    }
+    Ok(games)
+}
+
+/// Apply a PGN game's moves, checking each against the rules
+///
+/// Returns the game along with any warnings about bad moves.
+fn import_game(
+    pgn_game: &PgnGame,
+    mode: ImportMode,
+) -> Result<(Game, Vec<String>), Box<dyn std::error::Error>> {
+    let report = import_pgn(pgn_game, mode)?;
+    let mut warnings: Vec<String> = report.issues.iter().map(|i| i.to_string()).collect();
+    if report.stopped {
+        warnings.push(format!(
+            "stopped after {} of {} moves (use --lenient to auto-correct)",
+            report.applied,
+            pgn_game.moves.len()
+        ));
+    }
+    Ok((report.game, warnings))
+}
+
+/// A client of an online server, which a thread of the event bus polls
+type Connection = OnlineClient<Box<dyn Transport + Send>>;
+
+/// A connection to an online server and the game picked in its lobby
+type OnlineStart = (Connection, OnlineGame);
+
+/// Connect to an online server and pick a game in the text lobby
+///
+/// Returns None if the user leaves the lobby.
+#[cfg(feature = "online")]
+fn connect_online(
+    url: &str,
+    name: &str,
+) -> Result<Option<OnlineStart>, Box<dyn std::error::Error>> {
+    use cn_chess_tui::online::{lobby, ws::WebSocketTransport};
+
+    let transport: Box<dyn Transport + Send> = Box::new(WebSocketTransport::connect(url)?);
+    let mut client = OnlineClient::new(transport);
+    let game = lobby::run(&mut client, name, io::stdin().lock(), &mut io::stdout())?;
+    Ok(game.map(|game| (client, game)))
+}
+
+#[cfg(not(feature = "online"))]
+fn connect_online(
+    _url: &str,
+    _name: &str,
+) -> Result<Option<OnlineStart>, Box<dyn std::error::Error>> {
+    Err("built without online play (rebuild with --features online)".into())
+}
+
+/// A connection to an online server and the game picked to watch
+type SpectateStart = (Connection, WatchedGame);
+
+/// Connect to an online server and pick a game to watch
+///
+/// Returns None if the user leaves the list.
+#[cfg(feature = "online")]
+fn connect_spectate(url: &str) -> Result<Option<SpectateStart>, Box<dyn std::error::Error>> {
+    use cn_chess_tui::online::{lobby, ws::WebSocketTransport};
+
+    let transport: Box<dyn Transport + Send> = Box::new(WebSocketTransport::connect(url)?);
+    let mut client = OnlineClient::new(transport);
+    let game = lobby::spectate(&mut client, io::stdin().lock(), &mut io::stdout())?;
+    Ok(game.map(|game| (client, game)))
+}
+
+#[cfg(not(feature = "online"))]
+fn connect_spectate(_url: &str) -> Result<Option<SpectateStart>, Box<dyn std::error::Error>> {
+    Err("built without online play (rebuild with --features online)".into())
+}
+
+/// A correspondence game: one move is played, then the turn file written
+struct Correspondence {
+    path: PathBuf,
+    /// The game as received, or new
+    file: TurnFile,
+    /// Name to fill in for the side moving, if it has none yet
+    name: Option<String>,
+    /// Whether this turn's move has been played and the file written
+    sent: bool,
+}
+
+/// Read a turn file, checking a reply against the copy of the file sent
+///
+/// A file that doesn't exist starts a new game.
+fn open_turn_file(path: &Path) -> Result<TurnFile, Box<dyn std::error::Error>> {
+    if !path.exists() {
+        return Ok(TurnFile::new(&TurnFile::new_id(), "", "", &Game::new()));
+    }
+    let received = TurnFile::load(path)?;
+    if let Some(dir) = Paths::resolve().map(|paths| paths.turns_dir()) {
+        let copy = received.sent_copy(&dir);
+        if copy.exists() {
+            received.reply_to(&TurnFile::load(&copy)?)?;
+        }
+    }
+    Ok(received)
+}
+
 /// Selection state for piece movement
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SelectionState {
@@ -60,6 +913,7 @@ enum SelectionState {
 
 /// Main application state
 struct App {
+    config: Config,
     controller: GameController,
     cursor: Position,
     selection: SelectionState,
@@ -68,13 +922,51 @@ struct App {
     running: bool,
     ai_menu_active: bool,
     ai_menu_state: AiMenuState,
-    _thinking_info: Vec<Info>,
+    open_menu_active: bool,
+    new_game_menu: Option<NewGameMenuState>,
+    open_menu_state: OpenMenuState,
+    history_active: bool,
+    history_state: HistoryBrowserState,
+    /// The player's stats, while the stats screen is open
+    stats_screen: Option<Stats>,
+    /// The engines analyzing the game side by side, while kibitzing
+    kibitz: Option<Kibitz>,
+    /// Move history side panel; focused to scroll it with the keys
+    move_list: MoveListState,
+    game_picker_active: bool,
+    game_picker_state: GamePickerState,
+    library_active: bool,
+    library_state: LibraryState,
+    /// Whether the finished game on the board is already in the library,
+    /// or was loaded rather than played
+    archived: bool,
+    review_active: bool,
+    review_state: ReviewState,
+    /// Comment being written for the reviewed move
+    comment_editor: Option<CommentEditorState>,
+    /// Command being typed at the `:` prompt
+    command_line: Option<CommandLineState>,
+    setup_active: bool,
+    setup_state: SetupState,
+    /// Saved game offered for resuming at startup
+    resume_prompt: Option<Session>,
+    import_mode: ImportMode,
+    /// Connection to an online server, relaying moves of the game there
+    online: Option<Arc<Mutex<Connection>>>,
+    /// Correspondence game whose turn file is written after the next move
+    correspondence: Option<Correspondence>,
+    /// Suggested move, marked on the board while the position it was given
+    /// for (as FEN) is on the board
+    hint: Option<(String, Position, Position)>,
+    /// Size of the terminal, as of the last resize
+    screen: Rect,
 }
 
 impl App {
-    fn new() -> Self {
+    fn new(config: Config) -> Self {
         Self {
-            controller: GameController::new(),
+            controller: GameController::from_config(Game::new(), &config),
+            config,
             cursor: Position::from_xy(4, 9), // Start at Red General's position
             selection: SelectionState::SelectingSource,
             message: None,
@@ -82,195 +974,497 @@ impl App {
             running: true,
             ai_menu_active: false,
             ai_menu_state: AiMenuState::default(),
-            _thinking_info: Vec::new(),
+            open_menu_active: false,
+            new_game_menu: None,
+            open_menu_state: OpenMenuState::default(),
+            history_active: false,
+            stats_screen: None,
+            kibitz: None,
+            history_state: HistoryBrowserState::default(),
+            move_list: MoveListState::default(),
+            game_picker_active: false,
+            game_picker_state: GamePickerState::default(),
+            library_active: false,
+            library_state: LibraryState::default(),
+            archived: true,
+            review_active: false,
+            review_state: ReviewState::default(),
+            comment_editor: None,
+            command_line: None,
+            setup_active: false,
+            setup_state: SetupState::default(),
+            resume_prompt: None,
+            import_mode: ImportMode::default(),
+            online: None,
+            correspondence: None,
+            hint: None,
+            screen: Rect::default(),
         }
     }
 
-    fn from_fen(fen: &str) -> Result<Self, FenError> {
-        Ok(Self {
-            controller: GameController::from_fen(fen)?,
-            cursor: Position::from_xy(4, 9),
-            selection: SelectionState::SelectingSource,
-            message: None,
-            message_time: Instant::now(),
-            running: true,
-            ai_menu_active: false,
-            ai_menu_state: AiMenuState::default(),
-            _thinking_info: Vec::new(),
-        })
+    fn from_fen(fen: &str, config: Config) -> Result<Self, FenError> {
+        let mut app = Self::new(config);
+        app.controller = GameController::from_config(Game::from_fen(fen)?, &app.config);
+        Ok(app)
     }
 
-    fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let fen = crate::fen_io::read_fen_file(path)?;
-        let controller = GameController::from_fen(&fen)?;
-        Ok(Self {
-            controller,
-            cursor: Position::from_xy(4, 9),
-            selection: SelectionState::SelectingSource,
-            message: None,
-            message_time: Instant::now(),
-            running: true,
-            ai_menu_active: false,
-            ai_menu_state: AiMenuState::default(),
-            _thinking_info: Vec::new(),
-        })
+    fn from_file(path: &str, config: Config) -> Result<Self, cn_chess_tui::Error> {
+        let fen = cn_chess_tui::fen_io::read_fen_file(path)?;
+        let mut app = Self::new(config);
+        app.controller = GameController::from_config(Game::from_fen(&fen)?, &app.config);
+        Ok(app)
     }
 
-    fn from_pgn(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        // Read PGN file
-        let pgn_content = std::fs::read_to_string(path)?;
+    fn from_pgn(
+        path: &str,
+        kind: FileKind,
+        mode: ImportMode,
+        config: Config,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut games = read_game_file(Path::new(path), kind)?;
+        let mut app = Self::new(config);
+        app.import_mode = mode;
 
-        // Parse PGN
-        let pgn_game =
-            crate::pgn::PgnGame::parse(&pgn_content).ok_or("Failed to parse PGN file")?;
-
-        // Create game and apply moves from PGN
-        let mut game = if let Some(fen) = pgn_game.get_tag("FEN") {
-            if !fen.is_empty() {
-                Game::from_fen(fen)?
-            } else {
-                Game::new()
-            }
+        if games.len() > 1 {
+            // Let the user pick a game from the collection
+            app.game_picker_state = GamePickerState::new(path, games);
+            app.game_picker_active = true;
         } else {
-            Game::new()
-        };
-
-        // Apply all moves from the PGN
-        for pgn_move in &pgn_game.moves {
-            // Parse the move notation (assuming ICCS format)
-            let notation = &pgn_move.notation;
-
-            // ICCS notation is 4 characters: from_x, from_y, to_x, to_y
-            // Example: "h2e2" means from h2 to e2
-            if notation.len() >= 4 {
-                let chars: Vec<char> = notation.chars().collect();
-
-                // Parse from position (e.g., "h2" -> x=7, y=1)
-                // Files: a=0, b=1, ..., h=7, i=8
-                // Ranks: 0=0, 1=1, ..., 9=9
-                let from_file = (chars[0] as i8) - (b'a' as i8);
-                let from_rank = (chars[1] as i8) - (b'0' as i8) - 1;
-
-                // Parse to position (e.g., "e2" -> x=4, y=1)
-                let to_file = (chars[2] as i8) - (b'a' as i8);
-                let to_rank = (chars[3] as i8) - (b'0' as i8) - 1;
-
-                // Validate coordinates are within board bounds
-                if (0..9).contains(&from_file)
-                    && (0..10).contains(&from_rank)
-                    && (0..9).contains(&to_file)
-                    && (0..10).contains(&to_rank)
-                {
-                    let from = Position::from_xy(from_file as usize, from_rank as usize);
-                    let to = Position::from_xy(to_file as usize, to_rank as usize);
-
-                    // Attempt to make the move
-                    if game.make_move(from, to).is_err() {
-                        // If move fails, continue with next move
-                        // This allows partially loading games with invalid moves
-                        eprintln!("Warning: Failed to apply move {}", notation);
-                    }
-                }
+            let (game, warnings) = import_game(&games.remove(0), mode)?;
+            for warning in &warnings {
+                eprintln!("Warning: {}", warning);
             }
+            app.controller = GameController::from_config(game, &app.config);
         }
 
-        // Wrap the game in a controller
-        let controller = GameController::from_game(game);
+        Ok(app)
+    }
 
-        Ok(Self {
-            controller,
-            cursor: Position::from_xy(4, 9),
-            selection: SelectionState::SelectingSource,
-            message: None,
-            message_time: Instant::now(),
-            running: true,
-            ai_menu_active: false,
-            ai_menu_state: AiMenuState::default(),
-            _thinking_info: Vec::new(),
-        })
+    /// Follow a change in the terminal's size
+    ///
+    /// Below the minimum size the game is replaced by a placeholder screen
+    /// and only the quit key works, so no move is made without seeing the
+    /// board. The command line is closed, as it may no longer fit.
+    fn handle_resize(&mut self, width: u16, height: u16) {
+        self.screen = Rect::new(0, 0, width, height);
+        if !ui::UI::fits(self.screen) {
+            self.command_line = None;
+        }
     }
 
     fn handle_key(&mut self, key: KeyCode) {
-        // Handle menu navigation if menu is active
-        if self.ai_menu_active {
+        if !ui::UI::fits(self.screen) {
+            if key == KeyCode::Char(self.config.keys.quit) {
+                self.running = false;
+            }
+            return;
+        }
+
+        if let Some(session) = self.resume_prompt.take() {
             match key {
-                KeyCode::Up => {
-                    if self.ai_menu_state.selected > 0 {
-                        self.ai_menu_state.selected -= 1;
-                    }
-                }
-                KeyCode::Down => {
-                    if self.ai_menu_state.selected < 4 {
-                        self.ai_menu_state.selected += 1;
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => self.resume(session),
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    if let Err(e) = persistence::clear_session() {
+                        self.show_message(trf(Text::RemoveSaveFailed, &[&e]));
                     }
                 }
+                _ => self.resume_prompt = Some(session),
+            }
+            return;
+        }
+
+        if self.setup_active {
+            self.handle_setup_key(key);
+            return;
+        }
+
+        if self.game_picker_active {
+            let page = self.game_picker_state.page_rows.max(1) as isize;
+            match key {
+                KeyCode::Up => self.game_picker_state.move_selection(-1),
+                KeyCode::Down => self.game_picker_state.move_selection(1),
+                KeyCode::PageUp => self.game_picker_state.move_selection(-page),
+                KeyCode::PageDown => self.game_picker_state.move_selection(page),
+                KeyCode::Home => self.game_picker_state.selected = 0,
+                KeyCode::End => self.game_picker_state.move_selection(isize::MAX),
                 KeyCode::Enter => {
-                    self.apply_ai_menu_selection();
+                    self.load_picked_game();
+                    self.game_picker_active = false;
                 }
                 KeyCode::Esc => {
-                    self.ai_menu_active = false;
+                    self.game_picker_active = false;
                 }
                 _ => {}
             }
             return;
         }
 
-        // Normal key handlers
-        match key {
-            KeyCode::Char('q') | KeyCode::Esc => {
-                if self.ai_menu_active {
-                    self.ai_menu_active = false;
-                } else {
-                    self.running = false;
+        if self.library_active {
+            let page = self.library_state.page_rows.max(1) as isize;
+            match key {
+                KeyCode::Up => self.library_state.move_selection(-1),
+                KeyCode::Down => self.library_state.move_selection(1),
+                KeyCode::PageUp => self.library_state.move_selection(-page),
+                KeyCode::PageDown => self.library_state.move_selection(page),
+                KeyCode::Home => self.library_state.selected = 0,
+                KeyCode::End => self.library_state.move_selection(isize::MAX),
+                KeyCode::Enter => {
+                    self.replay_library_game();
+                    self.library_active = false;
                 }
-            }
-            KeyCode::Char('m') | KeyCode::Char('M') => {
-                if !self.ai_menu_active {
-                    self.ai_menu_active = true;
-                    self.ai_menu_state = AiMenuState::default();
-                    self.ai_menu_state.show_thinking = self.controller.ai_config().show_thinking;
+                KeyCode::Esc => {
+                    self.library_active = false;
                 }
+                _ => {}
             }
-            KeyCode::Char('t') | KeyCode::Char('T') => {
-                if !self.ai_menu_active {
-                    let mut config = self.controller.ai_config().clone();
-                    config.show_thinking = !config.show_thinking;
-                    let status = if config.show_thinking { "on" } else { "off" };
-                    self.controller.set_ai_config(config);
-                    self.show_message(format!("Thinking display: {}", status));
+            return;
+        }
+
+        if let Some(line) = &mut self.command_line {
+            match key {
+                KeyCode::Char(c) => line.text.push(c),
+                // Backspace on an empty line closes it, as in vim
+                KeyCode::Backspace if line.text.is_empty() => self.command_line = None,
+                KeyCode::Backspace => {
+                    line.text.pop();
                 }
-            }
-            KeyCode::Char('r') => {
-                // Restart the game
-                *self = Self::new();
-            }
-            KeyCode::Char('u') => {
-                if self.controller.undo_move() {
-                    self.show_message("Move undone".to_string());
-                } else {
-                    self.show_message("No moves to undo".to_string());
+                KeyCode::Tab => {
+                    let (text, completions) = command::complete(&line.text);
+                    line.text = text;
+                    line.completions = completions;
                 }
-                self.selection = SelectionState::SelectingSource;
-            }
-            KeyCode::Up => {
-                if self.cursor.y > 0 {
-                    self.cursor.y -= 1;
+                KeyCode::Enter => {
+                    let line = self.command_line.take().unwrap_or_default();
+                    self.run_command(&line.text);
                 }
+                KeyCode::Esc => self.command_line = None,
+                _ => {}
             }
-            KeyCode::Down => {
-                if self.cursor.y < 9 {
-                    self.cursor.y += 1;
+            return;
+        }
+
+        if let Some(editor) = &mut self.comment_editor {
+            match key {
+                KeyCode::Char(c) => editor.text.push(c),
+                KeyCode::Backspace => {
+                    editor.text.pop();
                 }
-            }
-            KeyCode::Left => {
-                if self.cursor.x > 0 {
-                    self.cursor.x -= 1;
+                KeyCode::Enter => {
+                    let editor = self.comment_editor.take().unwrap_or_default();
+                    self.controller.set_comment(editor.index, Some(editor.text));
                 }
+                KeyCode::Esc => self.comment_editor = None,
+                _ => {}
             }
-            KeyCode::Right => {
-                if self.cursor.x < 8 {
-                    self.cursor.x += 1;
-                }
+            return;
+        }
+
+        if self.review_active {
+            match key {
+                KeyCode::Up => self.review_state.step(-1),
+                KeyCode::Down => self.review_state.step(1),
+                // Out of or into a variation where there is one, else a step
+                KeyCode::Left if !self.review_state.leave_variation(self.controller.game()) => {
+                    self.review_state.step(-1)
+                }
+                KeyCode::Right if !self.review_state.enter_variation(self.controller.game()) => {
+                    self.review_state.step(1)
+                }
+                KeyCode::PageUp => self.review_state.step(-10),
+                KeyCode::PageDown => self.review_state.step(10),
+                KeyCode::Home => self.review_state.ply = 0,
+                KeyCode::End => self.review_state.ply = self.review_state.total,
+                KeyCode::Char('c') | KeyCode::Char('C') => self.open_comment_editor(),
+                KeyCode::Esc | KeyCode::Char('v') => {
+                    self.review_active = false;
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if self.stats_screen.is_some() {
+            match key {
+                KeyCode::Esc | KeyCode::Char('x') | KeyCode::Char('X') => self.stats_screen = None,
+                KeyCode::Char(c) if c == self.config.keys.quit => self.stats_screen = None,
+                _ => {}
+            }
+            return;
+        }
+
+        if self.history_active {
+            let total_rows = HistoryBrowserState::total_rows(self.controller.get_moves().len());
+            match key {
+                KeyCode::Up => self.history_state.scroll(-1, total_rows),
+                KeyCode::Down => self.history_state.scroll(1, total_rows),
+                KeyCode::PageUp => self.history_state.page_up(total_rows),
+                KeyCode::PageDown => self.history_state.page_down(total_rows),
+                KeyCode::Home => self.history_state.top = 0,
+                KeyCode::End => self.history_state.end(total_rows),
+                KeyCode::Esc | KeyCode::Char('h') | KeyCode::Char('H') => {
+                    self.history_active = false;
+                }
+                KeyCode::Char(c) if c == self.config.keys.quit => {
+                    self.history_active = false;
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(menu) = self.new_game_menu.as_mut() {
+            match key {
+                KeyCode::Up if menu.selected > 0 => menu.selected -= 1,
+                KeyCode::Down if menu.selected + 1 < NewGameMenuState::FIELDS => {
+                    menu.selected += 1;
+                }
+                KeyCode::Left => menu.change(false),
+                KeyCode::Right | KeyCode::Char(' ') => menu.change(true),
+                KeyCode::Enter => {
+                    let menu = *menu;
+                    self.new_game_menu = None;
+                    self.start_new_game(&menu);
+                }
+                KeyCode::Esc => self.new_game_menu = None,
+                _ => {}
+            }
+            return;
+        }
+
+        if self.open_menu_active {
+            match key {
+                KeyCode::Up if self.open_menu_state.selected > 0 => {
+                    self.open_menu_state.selected -= 1;
+                }
+                KeyCode::Down
+                    if self.open_menu_state.selected + 1 < self.open_menu_state.entries.len() =>
+                {
+                    self.open_menu_state.selected += 1;
+                }
+                KeyCode::Enter => {
+                    if let Some(file) = self.open_menu_state.selected_file().cloned() {
+                        self.open_recent_file(&file);
+                    }
+                    self.open_menu_active = false;
+                }
+                KeyCode::Esc => {
+                    self.open_menu_active = false;
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        // Handle menu navigation if menu is active
+        if self.ai_menu_active {
+            match key {
+                KeyCode::Up if self.ai_menu_state.selected > 0 => {
+                    self.ai_menu_state.selected -= 1;
+                }
+                KeyCode::Down if self.ai_menu_state.selected < 5 => {
+                    self.ai_menu_state.selected += 1;
+                }
+                KeyCode::Left if self.ai_menu_state.selected == 5 => {
+                    self.cycle_difficulty(false);
+                }
+                KeyCode::Right if self.ai_menu_state.selected == 5 => {
+                    self.cycle_difficulty(true);
+                }
+                KeyCode::Enter => {
+                    self.apply_ai_menu_selection();
+                }
+                KeyCode::Esc => {
+                    self.ai_menu_active = false;
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if self.move_list.focused {
+            let total_rows = HistoryBrowserState::total_rows(self.controller.get_moves().len());
+            match key {
+                KeyCode::Up | KeyCode::Char('k') => self.move_list.scroll(-1, total_rows),
+                KeyCode::Down | KeyCode::Char('j') => self.move_list.scroll(1, total_rows),
+                KeyCode::PageUp => self.move_list.page_up(total_rows),
+                KeyCode::PageDown => self.move_list.page_down(total_rows),
+                KeyCode::Home => self.move_list.home(total_rows),
+                KeyCode::End => self.move_list.end(),
+                KeyCode::Esc | KeyCode::Tab => self.move_list.focused = false,
+                _ => {}
+            }
+            return;
+        }
+
+        // Shortcuts from the game over popup
+        if self.controller.state() != GameState::Playing && self.handle_game_over_key(key) {
+            return;
+        }
+
+        // Normal key handlers; remappable keys take precedence
+        let keys = self.config.keys;
+        match key {
+            KeyCode::Char(c) if c == keys.quit => {
+                self.running = false;
+            }
+            // Keys that would change the game locally, out of step with
+            // the server
+            KeyCode::Char(c)
+                if (self.online_game().is_some()
+                    || self.spectating()
+                    || self.correspondence.is_some())
+                    && ([keys.undo, keys.redo, keys.restart].contains(&c)
+                        || matches!(c.to_ascii_lowercase(), 'a' | 'm' | 'o' | 's')
+                        || c == 'N'
+                        || c == 'P') =>
+            {
+                self.show_message(tr(Text::NotAvailableRemote).to_string());
+            }
+            KeyCode::Char(c) if c == keys.restart => {
+                self.open_new_game_menu();
+            }
+            KeyCode::Char(c) if c == keys.undo => {
+                if self.controller.undo_move() {
+                    self.show_message(tr(Text::MoveUndone).to_string());
+                } else {
+                    self.show_message(tr(Text::NoMovesToUndo).to_string());
+                }
+                self.selection = SelectionState::SelectingSource;
+            }
+            KeyCode::Char(c) if c == keys.redo => {
+                if self.controller.redo_move() {
+                    self.show_message(trf(Text::MoveRedone, &[&self.describe_last_move()]));
+                } else {
+                    self.show_message(tr(Text::NoMovesToRedo).to_string());
+                }
+                self.selection = SelectionState::SelectingSource;
+            }
+            KeyCode::Esc => {
+                self.running = false;
+            }
+            KeyCode::Char('m') | KeyCode::Char('M') if !self.ai_menu_active => {
+                self.ai_menu_active = true;
+                self.ai_menu_state = AiMenuState::default();
+                self.ai_menu_state.show_thinking = self.controller.ai_config().show_thinking;
+                self.ai_menu_state.difficulty = self.controller.ai_config().difficulty;
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') if !self.ai_menu_active => {
+                let mut config = self.controller.ai_config().clone();
+                config.show_thinking = !config.show_thinking;
+                let status = on_off(config.show_thinking);
+                self.controller.set_ai_config(config);
+                self.show_message(trf(Text::ThinkingDisplay, &[&status]));
+            }
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                let free = !self.controller.game().free_turn();
+                self.controller.set_free_turn(free);
+                self.selection = SelectionState::SelectingSource;
+                let status = on_off(free);
+                self.show_message(trf(Text::FreeAnalysisMessage, &[&status]));
+            }
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                self.toggle_analysis();
+            }
+            KeyCode::Char('k') | KeyCode::Char('K') => {
+                self.toggle_kibitz();
+            }
+            KeyCode::Char('l') | KeyCode::Char('L') => {
+                let mut config = self.controller.ai_config().clone();
+                config.show_pv = !config.show_pv;
+                let status = on_off(config.show_pv);
+                self.controller.set_ai_config(config);
+                self.show_message(trf(Text::PvOnBoard, &[&status]));
+            }
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                let theme = ui::UI::theme().next();
+                ui::UI::set_theme(theme);
+                self.config.theme = Some(theme.name.to_string());
+                self.show_message(trf(Text::ThemeMessage, &[&theme.name]));
+            }
+            KeyCode::Char('h') => {
+                self.show_hint();
+            }
+            KeyCode::Char('b') | KeyCode::Char('B') => {
+                let blindfold = !ui::UI::blindfold();
+                ui::UI::set_blindfold(blindfold);
+                let status = on_off(blindfold);
+                self.show_message(trf(Text::BlindfoldMessage, &[&status]));
+            }
+            KeyCode::Char('w') | KeyCode::Char('W') => {
+                let overlay = ui::UI::attack_overlay().next();
+                ui::UI::set_attack_overlay(overlay);
+                self.show_message(trf(Text::AttackMapMessage, &[&overlay.name()]));
+            }
+            KeyCode::Char('?') => {
+                let tutor = !ui::UI::tutor();
+                ui::UI::set_tutor(tutor);
+                let status = on_off(tutor);
+                self.show_message(trf(Text::LearningMode, &[&status]));
+            }
+            KeyCode::Char(':') => {
+                self.command_line = Some(CommandLineState::default());
+            }
+            KeyCode::Char('i') | KeyCode::Char('I') => {
+                let coordinates = !ui::UI::coordinates();
+                ui::UI::set_coordinates(coordinates);
+                let status = on_off(coordinates);
+                self.show_message(trf(Text::CoordinatesMessage, &[&status]));
+            }
+            KeyCode::Char('g') | KeyCode::Char('G') => {
+                let style = ui::UI::piece_style().next();
+                ui::UI::set_piece_style(style);
+                self.config.pieces = Some(style);
+                self.show_message(trf(Text::PieceStyleMessage, &[&style.name()]));
+            }
+            KeyCode::Char('x') | KeyCode::Char('X') => {
+                self.stats_screen = Some(Stats::load());
+            }
+            KeyCode::Char('H') => {
+                // Open at the most recent moves
+                self.history_state.top = usize::MAX;
+                self.history_active = true;
+            }
+            KeyCode::Tab => {
+                self.move_list.focused = true;
+            }
+            KeyCode::Char('N') => {
+                self.open_new_game_menu();
+            }
+            KeyCode::Char('n') => {
+                self.move_list.style = self.move_list.style.next();
+                let style = self.move_list.style.name();
+                self.show_message(trf(Text::MoveHistoryStyle, &[&style]));
+            }
+            KeyCode::PageUp => {
+                let total_rows = HistoryBrowserState::total_rows(self.controller.get_moves().len());
+                self.move_list.page_up(total_rows);
+            }
+            KeyCode::PageDown => {
+                let total_rows = HistoryBrowserState::total_rows(self.controller.get_moves().len());
+                self.move_list.page_down(total_rows);
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                self.review_state = ReviewState::new(self.controller.get_moves().len());
+                self.review_active = true;
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                self.start_setup(BoardEditor::from_game(self.controller.game()));
+            }
+            KeyCode::Char('f') | KeyCode::Char('F') => {
+                self.copy_fen();
+            }
+            KeyCode::Char('p') => {
+                self.copy_pgn();
+            }
+            KeyCode::Char('P') => {
+                self.paste_fen();
+            }
+            KeyCode::Char('o') | KeyCode::Char('O') => {
+                self.open_menu_state = OpenMenuState::new(AppState::load().recent_files);
+                self.open_menu_active = true;
+            }
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right => {
+                self.move_cursor(key);
             }
             KeyCode::Enter => {
                 self.handle_selection();
@@ -279,37 +1473,784 @@ impl App {
         }
     }
 
+    /// Handle a popup shortcut once the game is over
+    ///
+    /// Returns false if `key` is not one of the shortcuts, or is bound to
+    /// a game command in the config.
+    fn handle_game_over_key(&mut self, key: KeyCode) -> bool {
+        let KeyCode::Char(c) = key else {
+            return false;
+        };
+        if self.config.keys.is_bound(c) {
+            return false;
+        }
+        match c {
+            'p' => self.export_pgn(),
+            'f' => self.copy_fen(),
+            'n' => {
+                // Rematch with the AI taking the other color
+                let mode = self.controller.ai_mode().swapped();
+                self.restart();
+                self.controller.set_ai_mode(mode);
+                self.show_message(trf(Text::RematchMessage, &[&ui::UI::mode_name(mode)]));
+            }
+            'v' => {
+                self.review_state = ReviewState::new(self.controller.get_moves().len());
+                self.review_active = true;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Annotate the move that led to the reviewed position
+    ///
+    /// Only moves of the game itself can be annotated, not variations.
+    fn open_comment_editor(&mut self) {
+        let Some(index) = self.review_state.ply.checked_sub(1) else {
+            return;
+        };
+        if !self.review_state.path.is_empty() {
+            return;
+        }
+        let game = self.controller.game();
+        let notation = game.move_notations(self.move_list.style).swap_remove(index);
+        let dots = if index % 2 == 0 { "." } else { "..." };
+        self.comment_editor = Some(CommentEditorState::new(
+            index,
+            format!("{}{} {}", index / 2 + 1, dots, notation),
+            game.comment(index),
+        ));
+    }
+
+    /// Enter setup mode on a position
+    fn start_setup(&mut self, editor: BoardEditor) {
+        if self.controller.is_engine_thinking() {
+            self.show_message(tr(Text::WaitForAi).to_string());
+            return;
+        }
+        self.setup_state = SetupState::new(editor);
+        self.setup_active = true;
+        self.selection = SelectionState::SelectingSource;
+    }
+
+    /// Handle a mouse event: drag and drop in setup mode, and a click on
+    /// the board in play, which acts as Enter on the clicked point
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        if !ui::UI::fits(self.screen) || self.resume_prompt.is_some() {
+            return;
+        }
+        let square = ui::UI::board_position_at(mouse.column, mouse.row);
+        if self.setup_active {
+            self.handle_setup_mouse(mouse, square);
+            return;
+        }
+        let board_in_play = !self.game_picker_active
+            && !self.library_active
+            && self.command_line.is_none()
+            && self.comment_editor.is_none()
+            && !self.review_active
+            && !self.history_active
+            && self.stats_screen.is_none()
+            && self.new_game_menu.is_none()
+            && !self.open_menu_active
+            && !self.ai_menu_active
+            && self.controller.state() == GameState::Playing;
+        if let (MouseEventKind::Down(MouseButton::Left), Some(pos), true) =
+            (mouse.kind, square, board_in_play)
+        {
+            self.move_list.focused = false;
+            self.cursor = pos;
+            self.handle_selection();
+        }
+    }
+
+    /// Handle a mouse event in setup mode
+    ///
+    /// Pieces are dragged between points, from the palette onto the board,
+    /// or off the board to remove them; a right click removes the piece
+    /// under the mouse.
+    fn handle_setup_mouse(&mut self, mouse: MouseEvent, square: Option<Position>) {
+        let state = &mut self.setup_state;
+        if state.picker.is_some() {
+            return;
+        }
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(pos) = square {
+                    self.cursor = pos;
+                    state.drag_from_square(pos);
+                } else if let Some(piece) = ui::UI::palette_piece_at(mouse.column, mouse.row) {
+                    state.drag_from_palette(piece);
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(pos) = square {
+                    self.cursor = pos;
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                if let Some(pos) = square {
+                    self.cursor = pos;
+                }
+                state.drop_on(square);
+            }
+            MouseEventKind::Down(MouseButton::Right) => {
+                if let Some(pos) = square {
+                    self.cursor = pos;
+                    state.drag = None;
+                    state.editor.remove(pos);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle a key in setup mode
+    fn handle_setup_key(&mut self, key: KeyCode) {
+        let state = &mut self.setup_state;
+        if state.picker.is_some() {
+            match key {
+                KeyCode::Up => state.move_picker(-2),
+                KeyCode::Down => state.move_picker(2),
+                KeyCode::Left => state.move_picker(-1),
+                KeyCode::Right => state.move_picker(1),
+                KeyCode::Enter => {
+                    match state.picked() {
+                        Some(piece) => state.editor.place(self.cursor, piece),
+                        None => state.editor.remove(self.cursor),
+                    };
+                    state.picker = None;
+                }
+                KeyCode::Esc => state.picker = None,
+                _ => {}
+            }
+            return;
+        }
+
+        let keys = self.config.keys;
+        let editor = &mut state.editor;
+        match key {
+            KeyCode::Char(c) if c == keys.undo => {
+                editor.undo();
+            }
+            KeyCode::Char(c) if c == keys.redo => {
+                editor.redo();
+            }
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right => self.move_cursor(key),
+            KeyCode::Enter | KeyCode::Char(' ') => state.open_picker(self.cursor),
+            KeyCode::Delete | KeyCode::Backspace | KeyCode::Char('x') => {
+                editor.remove(self.cursor);
+            }
+            KeyCode::Char('X') => editor.clear(),
+            KeyCode::Char('i') => editor.load(Board::new()),
+            KeyCode::Char('o') => editor.revert(),
+            KeyCode::Tab => editor.set_turn(editor.turn().opponent()),
+            KeyCode::Char('f') => {
+                let fen = editor.to_fen();
+                match clipboard::copy(&fen) {
+                    Ok(()) => self.show_message(trf(Text::Copied, &[&fen])),
+                    Err(e) => self.show_message(trf(Text::FenCopyFailed, &[&e])),
+                }
+            }
+            KeyCode::Char('s') => self.finish_setup(),
+            KeyCode::Esc => self.setup_active = false,
+            KeyCode::Char(c) => {
+                if let Some(piece) = fen::parse_piece(c) {
+                    editor.place(self.cursor, piece);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Start playing from the position set up, if it is playable
+    fn finish_setup(&mut self) {
+        let editor = &self.setup_state.editor;
+        if let Err(e) = editor.validate() {
+            self.show_message(trf(Text::CannotPlayPosition, &[&e]));
+            return;
+        }
+        match editor.to_game() {
+            Ok(game) => {
+                let game = self.configured_game(game);
+                self.controller.load_game(game);
+                self.setup_active = false;
+                self.show_message(tr(Text::PositionSetUp).to_string());
+            }
+            Err(e) => self.show_message(trf(Text::CannotPlayPosition, &[&e])),
+        }
+    }
+
+    /// Restart the game, keeping AI settings and the engine
+    fn restart(&mut self) {
+        self.new_game(Game::new());
+    }
+
+    /// Open the new-game dialog, filled in from the current game
+    fn open_new_game_menu(&mut self) {
+        self.new_game_menu = Some(NewGameMenuState::new(
+            self.controller.ai_mode(),
+            self.controller.ai_config().difficulty,
+            self.controller.time_control(),
+        ));
+    }
+
+    /// Start the game chosen in the new-game dialog
+    fn start_new_game(&mut self, menu: &NewGameMenuState) {
+        let game = menu
+            .selected_handicap()
+            .map_or_else(Game::new, |h| h.game());
+        self.new_game(game);
+
+        let mut config = self.controller.ai_config().clone();
+        config.difficulty = menu.difficulty;
+        self.controller.set_ai_config(config);
+        self.controller.set_ai_mode(menu.ai_mode());
+        // Per-side clocks show as no clock in the dialog and are kept
+        // unless another is chosen
+        if menu.time_control != self.controller.time_control() {
+            self.controller.set_time_control(menu.time_control);
+        }
+        // Whoever plays Black alone sits at the bottom of the board
+        ui::UI::set_flipped(menu.side == PlayerSide::Black);
+
+        let mut summary = ui::UI::mode_name(menu.ai_mode()).to_string();
+        if let Some(tc) = menu.time_control {
+            summary.push_str(", ");
+            summary.push_str(&trf(Text::Minutes, &[&tc]));
+        }
+        if let Some(handicap) = menu.selected_handicap() {
+            summary.push_str(", ");
+            summary.push_str(&ui::UI::handicap_name(handicap));
+        }
+        let summary = trf(Text::NewGame, &[&summary]);
+        self.show_message(summary);
+    }
+
+    /// Start `game` afresh, keeping AI settings and the engine
+    fn new_game(&mut self, game: Game) {
+        let mut controller = std::mem::take(&mut self.controller);
+        controller.load_game(self.configured_game(game));
+        *self = Self::new(std::mem::take(&mut self.config));
+        self.controller = controller;
+    }
+
+    /// Start or stop engine analysis of the current position
+    fn toggle_analysis(&mut self) {
+        if self.controller.is_analyzing() {
+            match self.controller.stop_analysis() {
+                Ok(()) => self.show_message(tr(Text::AnalysisOff).to_string()),
+                Err(e) => self.show_message(trf(Text::AnalysisError, &[&e])),
+            }
+            return;
+        }
+        match self.controller.start_analysis() {
+            Ok(()) => self.show_message(tr(Text::AnalysisOn).to_string()),
+            Err(e) => self.show_message(trf(Text::AnalysisError, &[&e])),
+        }
+    }
+
+    /// Start the engines in the config's `[[kibitz]]` tables, or stop
+    /// them
+    fn toggle_kibitz(&mut self) {
+        if self.kibitz.take().is_some() {
+            return self.show_message(tr(Text::KibitzOff).to_string());
+        }
+        if self.config.kibitz.is_empty() {
+            return self.show_message(tr(Text::NoKibitzEngines).to_string());
+        }
+        let (kibitz, failed) = Kibitz::start(&self.config.kibitz);
+        match failed.first() {
+            Some((path, e)) => self.show_message(trf(Text::KibitzFailed, &[&path.display(), &e])),
+            None => self.show_message(trf(Text::KibitzEngines, &[&kibitz.kibitzers().len()])),
+        }
+        if !kibitz.is_empty() {
+            self.kibitz = Some(kibitz);
+        }
+    }
+
+    /// Collect the kibitz engines' lines, dropping any that fail
+    fn poll_kibitz(&mut self) {
+        let Some(kibitz) = self.kibitz.as_mut() else {
+            return;
+        };
+        let (_, failed) = kibitz.poll(&self.controller.game().to_fen());
+        if kibitz.is_empty() {
+            self.kibitz = None;
+        }
+        if let Some((name, e)) = failed.first() {
+            self.show_message(trf(Text::KibitzStopped, &[&name, &e]));
+        }
+    }
+
+    /// Continue a game saved by a previous run
+    fn resume(&mut self, session: Session) {
+        match session.to_game() {
+            Ok(game) => {
+                let moves = game.get_moves().len();
+                self.controller.load_game(self.configured_game(game));
+                self.controller.set_ai_mode(session.ai_mode);
+                self.archived = true;
+                self.selection = SelectionState::SelectingSource;
+                self.show_message(trf(Text::Resumed, &[&moves]));
+            }
+            Err(e) => self.show_message(trf(Text::ResumeFailed, &[&e])),
+        }
+    }
+
+    /// Move the cursor one point with an arrow key, the way it points on
+    /// screen even when the board is flipped
+    fn move_cursor(&mut self, key: KeyCode) {
+        let (dx, dy): (isize, isize) = match key {
+            KeyCode::Up => (0, -1),
+            KeyCode::Down => (0, 1),
+            KeyCode::Left => (-1, 0),
+            KeyCode::Right => (1, 0),
+            _ => return,
+        };
+        let (dx, dy) = if ui::UI::flipped() {
+            (-dx, -dy)
+        } else {
+            (dx, dy)
+        };
+        self.cursor.x = self.cursor.x.saturating_add_signed(dx).min(8);
+        self.cursor.y = self.cursor.y.saturating_add_signed(dy).min(9);
+    }
+
+    /// Run a command typed at the `:` prompt
+    fn run_command(&mut self, text: &str) {
+        let command = match text.parse::<Command>() {
+            Ok(command) => command,
+            Err(e) => return self.show_message(trf(Text::ErrorMessage, &[&e])),
+        };
+        match command {
+            Command::Fen(fen) => match Game::from_fen(&fen) {
+                Ok(game) => self.replace_game(game, tr(Text::PositionName), 0),
+                Err(e) => self.show_message(trf(Text::FenParseFailed, &[&e])),
+            },
+            Command::Export { kind, path } => {
+                let game = self.controller.game();
+                let pgn = game.to_pgn_with(self.export_options());
+                let result = match kind {
+                    FileKind::Fen => cn_chess_tui::write_fen_file(&path, &game.to_fen()),
+                    FileKind::Xml => std::fs::write(&path, cn_chess_tui::pgn_to_xml(&pgn)),
+                    _ => std::fs::write(&path, pgn.to_string()),
+                };
+                match result {
+                    Ok(()) => {
+                        state::remember_recent_file(&path);
+                        self.show_message(trf(Text::Saved, &[&path.display()]));
+                    }
+                    Err(e) => self.show_message(trf(Text::SaveFailed, &[&path.display(), &e])),
+                }
+            }
+            Command::Engine(path) => match self.controller.init_engine(&path) {
+                Ok(()) => self.show_engine_loaded(),
+                Err(e) => self.show_message(trf(Text::EngineLoadFailed, &[&e])),
+            },
+            Command::Flip => {
+                ui::UI::set_flipped(!ui::UI::flipped());
+                let top = side(if ui::UI::flipped() {
+                    Color::Red
+                } else {
+                    Color::Black
+                });
+                self.show_message(trf(Text::BoardFlipped, &[&top]));
+            }
+            Command::Theme(name) => match Theme::by_name(&name) {
+                Some(theme) => {
+                    ui::UI::set_theme(theme);
+                    self.config.theme = Some(theme.name.to_string());
+                    self.show_message(trf(Text::ThemeMessage, &[&theme.name]));
+                }
+                None => self.show_message(trf(Text::UnknownTheme, &[&name])),
+            },
+            Command::Accessible => {
+                let accessible = !ui::UI::accessible();
+                ui::UI::set_accessible(accessible);
+                let status = on_off(accessible);
+                self.show_message(trf(Text::AccessibleMode, &[&status]));
+            }
+            Command::Describe(path) => {
+                let text = describe::position(self.controller.game());
+                let Some(path) = path else {
+                    return match clipboard::copy(&text) {
+                        Ok(()) => self.show_message(tr(Text::DescriptionCopied).to_string()),
+                        Err(e) => self.show_message(trf(Text::CopyFailed, &[&e])),
+                    };
+                };
+                match std::fs::write(&path, text) {
+                    Ok(()) => self.show_message(trf(Text::Saved, &[&path.display()])),
+                    Err(e) => self.show_message(trf(Text::SaveFailed, &[&path.display(), &e])),
+                }
+            }
+            Command::Quit => self.running = false,
+            Command::Move(text) => self.play_typed_move(&text),
+        }
+    }
+
+    /// How exported games write their moves
+    fn export_options(&self) -> PgnExportOptions {
+        PgnExportOptions {
+            notation: self.config.export_notation,
+        }
+    }
+
+    /// Save the game to a PGN file in the current directory
+    fn export_pgn(&mut self) {
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("game_{}.pgn", stamp);
+        let pgn = self.controller.game().to_pgn_with(self.export_options());
+        match std::fs::write(&path, pgn.to_string()) {
+            Ok(()) => {
+                state::remember_recent_file(Path::new(&path));
+                self.show_message(trf(Text::Saved, &[&path]));
+            }
+            Err(e) => self.show_message(trf(Text::PgnSaveFailed, &[&e])),
+        }
+    }
+
+    /// Copy the current position's FEN to the clipboard
+    fn copy_fen(&mut self) {
+        let fen = self.controller.game().to_fen();
+        match clipboard::copy(&fen) {
+            Ok(()) => self.show_message(trf(Text::Copied, &[&fen])),
+            Err(e) => self.show_message(trf(Text::FenCopyFailed, &[&e])),
+        }
+    }
+
+    /// Copy the whole game as PGN to the clipboard
+    fn copy_pgn(&mut self) {
+        let pgn = self.controller.game().to_pgn_with(self.export_options());
+        match clipboard::copy(&pgn.to_string()) {
+            Ok(()) => self.show_message(trf(Text::CopiedPgn, &[&pgn.moves.len()])),
+            Err(e) => self.show_message(trf(Text::PgnCopyFailed, &[&e])),
+        }
+    }
+
+    /// Load the position whose FEN is on the clipboard
+    fn paste_fen(&mut self) {
+        let fen = match clipboard::paste() {
+            Ok(text) => text,
+            Err(e) => return self.show_message(trf(Text::FenPasteFailed, &[&e])),
+        };
+        match Game::from_fen(fen.trim()) {
+            Ok(game) => self.replace_game(game, tr(Text::PastedPosition), 0),
+            Err(e) => self.show_message(trf(Text::FenParseFailed, &[&e])),
+        }
+    }
+
     fn handle_selection(&mut self) {
         match self.selection {
             SelectionState::SelectingSource => {
                 // Check if there's a piece at cursor position
-                if let Some(piece) = self.controller.board().get(self.cursor) {
+                if self.spectating() {
+                    self.show_message(tr(Text::Spectating).to_string());
+                } else if let Some(message) = self.turn_sent() {
+                    self.show_message(message);
+                } else if let Some(game) = self
+                    .online_game()
+                    .filter(|game| game.color != self.controller.turn())
+                {
+                    let msg = trf(Text::WaitingForOpponent, &[&game.opponent]);
+                    self.show_message(msg);
+                } else if let Some(piece) = self.controller.board().get(self.cursor) {
                     // Check if it's the current player's piece
-                    if piece.color == self.controller.turn() {
+                    if piece.color == self.controller.turn() || self.controller.game().free_turn() {
                         self.selection = SelectionState::SelectingDestination(self.cursor);
                     } else {
-                        self.show_message(format!(
-                            "Not your piece - it's {}'s turn",
-                            self.controller.turn()
-                        ));
+                        let turn = side(self.controller.turn());
+                        self.show_message(trf(Text::NotYourPiece, &[&turn]));
                     }
                 } else {
-                    self.show_message("No piece at this position".to_string());
+                    self.show_message(tr(Text::NoPieceHere).to_string());
                 }
             }
             SelectionState::SelectingDestination(source) => {
-                // Try to make the move
-                let result = self.controller.human_move(source, self.cursor);
-                match result {
+                self.play_human_move(source, self.cursor);
+                self.selection = SelectionState::SelectingSource;
+            }
+        }
+    }
+
+    /// Try a move for the player and report how it went
+    fn play_human_move(&mut self, from: Position, to: Position) {
+        match self.controller.human_move(from, to) {
+            Ok(()) => {
+                self.show_message(tr(Text::MoveMade).to_string());
+                self.ring_bell();
+                self.send_online_move(from, to);
+                self.send_turn();
+            }
+            Err(MoveError::Illegal(reason)) if ui::UI::tutor() => {
+                self.show_message(trf(
+                    Text::InvalidMoveLesson,
+                    &[&reason, &tutor::lesson(reason)],
+                ));
+            }
+            Err(e) => {
+                self.show_message(trf(Text::InvalidMove, &[&e]));
+            }
+        }
+    }
+
+    /// Play a move typed at the `:` prompt
+    fn play_typed_move(&mut self, text: &str) {
+        if self.spectating() {
+            return self.show_message(tr(Text::Spectating).to_string());
+        }
+        if let Some(message) = self.turn_sent() {
+            return self.show_message(message);
+        }
+        if let Some(game) = self
+            .online_game()
+            .filter(|game| game.color != self.controller.turn())
+        {
+            let msg = trf(Text::WaitingForOpponent, &[&game.opponent]);
+            return self.show_message(msg);
+        }
+        match notation::parse_any(self.controller.game(), text) {
+            Ok((from, to)) => {
+                self.selection = SelectionState::SelectingSource;
+                self.cursor = to;
+                self.play_human_move(from, to);
+            }
+            Err(e) => self.show_message(trf(Text::InvalidMove, &[&e])),
+        }
+    }
+
+    /// Suggest moves for the side to move and mark the best on the board
+    ///
+    /// Several candidates, when `multipv` asks for them, are listed with
+    /// their scores.
+    fn show_hint(&mut self) {
+        match self.controller.hint_candidates() {
+            Ok(candidates) if !candidates.is_empty() => {
+                let game = self.controller.game();
+                let notations: Vec<String> = candidates
+                    .iter()
+                    .map(|candidate| {
+                        let notation =
+                            self.formatter()
+                                .format_move(game, candidate.from, candidate.to);
+                        match candidate.score.filter(|_| candidates.len() > 1) {
+                            Some(score) => format!("{} {:+}", notation, score),
+                            None => notation,
+                        }
+                    })
+                    .collect();
+                let best = candidates[0];
+                self.hint = Some((self.controller.to_fen(), best.from, best.to));
+                self.show_message(trf(Text::HintMessage, &[&notations.join(", ")]));
+            }
+            Ok(_) => self.show_message(tr(Text::NoHint).to_string()),
+            Err(e) => self.show_message(trf(Text::HintUnavailable, &[&e])),
+        }
+    }
+
+    fn online_game(&self) -> Option<OnlineGame> {
+        self.online.as_ref()?.lock().ok()?.game().cloned()
+    }
+
+    /// Play an online game the server has started
+    fn start_online(&mut self, client: Connection, game: OnlineGame) {
+        self.online = Some(Arc::new(Mutex::new(client)));
+        self.start_online_game(game);
+    }
+
+    fn start_online_game(&mut self, game: OnlineGame) {
+        match Game::from_fen(&game.fen) {
+            Ok(start) => {
+                self.controller.set_ai_mode(AiMode::Off);
+                self.controller.load_game(self.configured_game(start));
+                self.selection = SelectionState::SelectingSource;
+                let color = side(game.color);
+                self.show_message(trf(Text::PlayingOnline, &[&color, &game.opponent]));
+            }
+            Err(e) => self.show_message(trf(Text::BadServerPosition, &[&e])),
+        }
+    }
+
+    /// Play a move of a correspondence game
+    fn start_correspondence(&mut self, path: PathBuf, file: TurnFile, name: Option<String>) {
+        match file.to_game() {
+            Ok(game) => {
+                self.controller.set_ai_mode(AiMode::Off);
+                self.controller.load_game(self.configured_game(game));
+                let msg = match self.controller.game().state().result() {
+                    Some(_) => trf(Text::TurnFileGameOver, &[&self.controller.game().state()]),
+                    None => trf(Text::TurnFileToMove, &[&side(self.controller.turn())]),
+                };
+                self.show_message(msg);
+                self.correspondence = Some(Correspondence {
+                    path,
+                    file,
+                    name,
+                    sent: false,
+                });
+            }
+            Err(e) => self.show_message(trf(Text::ErrorMessage, &[&e])),
+        }
+    }
+
+    /// Why no more moves can be played, once this turn's file is written
+    fn turn_sent(&self) -> Option<String> {
+        let c = self.correspondence.as_ref().filter(|c| c.sent)?;
+        Some(trf(Text::TurnWritten, &[&c.path.display()]))
+    }
+
+    /// Write the turn file after the move of a correspondence game
+    fn send_turn(&mut self) {
+        let Some(c) = self.correspondence.as_mut().filter(|c| !c.sent) else {
+            return;
+        };
+        let mut file = c.file.advance(self.controller.game());
+        if let Some(name) = &c.name {
+            let mover = match self.controller.turn() {
+                Color::Red => &mut file.black,
+                Color::Black => &mut file.red,
+            };
+            if mover.is_empty() {
+                *mover = name.clone();
+            }
+        }
+        // The copy kept is what the reply is checked against
+        let copy = Paths::resolve().map(|paths| file.sent_copy(&paths.turns_dir()));
+        let saved = file
+            .save(&c.path)
+            .and_then(|()| copy.map_or(Ok(()), |copy| file.save(&copy)));
+        match saved {
+            Ok(()) => {
+                c.sent = true;
+                c.file = file;
+                let msg = self.turn_sent().unwrap_or_default();
+                self.show_message(msg);
+            }
+            Err(e) => self.show_message(trf(Text::TurnFileFailed, &[&e])),
+        }
+    }
+
+    /// Whether a game on the server is being watched
+    fn spectating(&self) -> bool {
+        self.online
+            .as_ref()
+            .is_some_and(|c| c.lock().is_ok_and(|c| c.watching().is_some()))
+    }
+
+    /// Watch a game on the server, from the position it has reached
+    fn start_spectating(&mut self, client: Connection, game: WatchedGame) {
+        self.online = Some(Arc::new(Mutex::new(client)));
+        self.start_watched_game(game);
+    }
+
+    fn start_watched_game(&mut self, game: WatchedGame) {
+        match game.replay() {
+            Ok(replayed) => {
+                self.controller.set_ai_mode(AiMode::Off);
+                self.controller.load_game(self.configured_game(replayed));
+                self.selection = SelectionState::SelectingSource;
+                self.show_message(trf(Text::WatchingOnline, &[&game.red, &game.black]));
+            }
+            Err(e) => self.show_message(trf(Text::OnlineMessage, &[&e])),
+        }
+    }
+
+    fn send_online_move(&mut self, from: Position, to: Position) {
+        let Some(client) = &self.online else {
+            return;
+        };
+        let sent = match client.lock() {
+            Ok(mut client) if client.game().is_some() => client.send_move(from, to),
+            _ => return,
+        };
+        if let Err(e) = sent {
+            self.show_message(trf(Text::OnlineMessage, &[&e]));
+            self.online = None;
+        }
+    }
+
+    /// Act on one event from the bus
+    fn handle_event(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::Key(code) => self.handle_key(code),
+            AppEvent::Mouse(mouse) => self.handle_mouse(mouse),
+            AppEvent::Resize(width, height) => self.handle_resize(width, height),
+            AppEvent::Tick => self.poll_subsystems(),
+            AppEvent::EngineMove { from, to } => match self.controller.play_engine_move(from, to) {
+                Ok(true) => {
+                    self.show_message(trf(Text::AiPlayed, &[&self.describe_last_move()]));
+                    self.ring_bell();
+                }
+                // Announced before a new game was started
+                Ok(false) => {}
+                Err(e) => self.show_message(trf(Text::AiError, &[&e])),
+            },
+            AppEvent::NetMove(event) => self.handle_online_event(event),
+            AppEvent::NetError(e) => {
+                self.show_message(trf(Text::OnlineMessage, &[&e]));
+                self.online = None;
+            }
+            AppEvent::ClockTick => {
+                if self.controller.check_clock() {
+                    self.show_message(trf(Text::RanOutOfTime, &[&side(self.controller.turn())]));
+                }
+            }
+        }
+    }
+
+    /// Poll the analysis and the kibitzers, and start the AI's move when it
+    /// is its turn
+    fn poll_subsystems(&mut self) {
+        if let Err(e) = self.controller.poll_analysis() {
+            let _ = self.controller.stop_analysis();
+            self.show_message(trf(Text::AnalysisStopped, &[&e]));
+        }
+
+        self.poll_kibitz();
+
+        if let Err(e) = self.controller.trigger_ai_move() {
+            self.show_message(trf(Text::AiError, &[&e]));
+        }
+
+        self.archive_finished_game();
+    }
+
+    fn handle_online_event(&mut self, event: OnlineEvent) {
+        match event {
+            OnlineEvent::OpponentMoved { from, to } => match self.controller.human_move(from, to) {
+                Ok(()) => {
+                    let msg = trf(Text::OpponentPlayed, &[&self.describe_last_move()]);
+                    self.show_message(msg);
+                    self.ring_bell();
+                }
+                Err(e) => self.show_message(trf(Text::OpponentMoveRejected, &[&e])),
+            },
+            OnlineEvent::Moved { from, to } => {
+                let mover = side(self.controller.turn());
+                match self.controller.human_move(from, to) {
                     Ok(()) => {
-                        self.show_message("Move successful".to_string());
-                    }
-                    Err(e) => {
-                        self.show_message(format!("Invalid move: {}", e));
+                        let msg = trf(Text::SidePlayed, &[&mover, &self.describe_last_move()]);
+                        self.show_message(msg);
                     }
+                    Err(e) => self.show_message(trf(Text::OnlineMoveRejected, &[&e])),
                 }
-                self.selection = SelectionState::SelectingSource;
             }
+            OnlineEvent::GameStarted(game) => self.start_online_game(game),
+            OnlineEvent::Watching(game) => self.start_watched_game(game),
+            OnlineEvent::GameEnded { result, reason } => match reason {
+                Some(reason) => {
+                    self.show_message(trf(Text::OnlineGameOverReason, &[&result, &reason]))
+                }
+                None => self.show_message(trf(Text::OnlineGameOver, &[&result])),
+            },
+            OnlineEvent::ServerError(message) => {
+                self.show_message(trf(Text::OnlineMessage, &[&message]))
+            }
+            OnlineEvent::LoggedIn(_) | OnlineEvent::Seeks(_) | OnlineEvent::Games(_) => {}
         }
     }
 
@@ -325,15 +2266,247 @@ impl App {
                 let new_value = config.show_thinking;
                 self.controller.set_ai_config(config);
                 self.ai_menu_state.show_thinking = new_value;
-                self.show_message("Thinking display toggled".to_string());
+                self.show_message(tr(Text::ThinkingToggled).to_string());
                 self.ai_menu_active = false;
                 return;
             }
+            5 => {
+                self.cycle_difficulty(true);
+                return;
+            }
             _ => return,
         }
 
-        self.show_message(format!("AI mode: {:?}", self.controller.ai_mode()));
-        self.ai_menu_active = false;
+        let mode = ui::UI::mode_name(self.controller.ai_mode());
+        self.show_message(trf(Text::AiModeMessage, &[&mode]));
+        self.ai_menu_active = false;
+    }
+
+    /// Step the AI's difficulty through the levels, and back to the
+    /// search settings from the config
+    fn cycle_difficulty(&mut self, up: bool) {
+        let levels: Vec<Option<Difficulty>> = std::iter::once(None)
+            .chain(Difficulty::ALL.into_iter().map(Some))
+            .collect();
+        let mut config = self.controller.ai_config().clone();
+        let current = levels
+            .iter()
+            .position(|&level| level == config.difficulty)
+            .unwrap_or(0);
+        let next = if up {
+            (current + 1) % levels.len()
+        } else {
+            (current + levels.len() - 1) % levels.len()
+        };
+        config.difficulty = levels[next];
+        self.controller.set_ai_config(config);
+        self.ai_menu_state.difficulty = levels[next];
+        let level = ui::UI::difficulty_name(levels[next]);
+        self.show_message(trf(Text::AiDifficulty, &[&level]));
+    }
+
+    /// Replace the current game with one loaded from a recent file
+    fn open_recent_file(&mut self, file: &RecentFile) {
+        if file.kind == FileKind::Fen {
+            let result = cn_chess_tui::fen_io::read_fen_file(&file.path)
+                .map_err(|e| e.to_string())
+                .and_then(|fen| Game::from_fen(&fen).map_err(|e| e.to_string()));
+            match result {
+                Ok(game) => self.replace_game(game, &file.path.display().to_string(), 0),
+                Err(e) => self.show_message(trf(Text::OpenFailed, &[&e])),
+            }
+            state::remember_recent_file(&file.path);
+            return;
+        }
+
+        match read_game_file(&file.path, file.kind) {
+            Ok(mut games) => {
+                state::remember_recent_file(&file.path);
+                self.import_mode = ImportMode::Lenient;
+                let name = file.path.display().to_string();
+                if games.len() > 1 {
+                    self.game_picker_state = GamePickerState::new(name, games);
+                    self.game_picker_active = true;
+                } else {
+                    match import_game(&games.remove(0), self.import_mode) {
+                        Ok((game, warnings)) => self.replace_game(game, &name, warnings.len()),
+                        Err(e) => self.show_message(trf(Text::OpenFailed, &[&e])),
+                    }
+                }
+            }
+            Err(e) => self.show_message(trf(Text::OpenFailed, &[&e])),
+        }
+    }
+
+    /// Load the game highlighted in the game picker
+    fn load_picked_game(&mut self) {
+        let Some(pgn_game) = self.game_picker_state.selected_game() else {
+            return;
+        };
+        let name = format!(
+            "game {} of {}",
+            self.game_picker_state.selected + 1,
+            self.game_picker_state.source
+        );
+        match import_game(pgn_game, self.import_mode) {
+            Ok((game, warnings)) => self.replace_game(game, &name, warnings.len()),
+            Err(e) => self.show_message(trf(Text::LoadFailed, &[&e])),
+        }
+    }
+
+    /// Open the game library at the configured directory
+    fn open_library(&mut self) {
+        let Some(dir) = self.config.get_library_dir() else {
+            self.show_message(tr(Text::NoLibraryDirectory).to_string());
+            return;
+        };
+        match library::list_games(&dir) {
+            Ok(entries) => {
+                self.library_state = LibraryState::new(dir.display().to_string(), entries);
+                self.library_active = true;
+            }
+            Err(e) => self.show_message(trf(Text::LibraryReadFailed, &[&e])),
+        }
+    }
+
+    /// Open review mode at the start of the game, to replay it move by move
+    fn review_from_start(&mut self) {
+        self.review_state = ReviewState::new(self.controller.get_moves().len());
+        self.review_state.ply = 0;
+        self.review_active = true;
+    }
+
+    /// Load the game highlighted in the library and replay it from the start
+    fn replay_library_game(&mut self) {
+        let Some(entry) = self.library_state.selected_entry() else {
+            return;
+        };
+        let name = entry.path.display().to_string();
+        match import_game(&entry.game, ImportMode::Lenient) {
+            Ok((game, warnings)) => {
+                self.replace_game(game, &name, warnings.len());
+                self.review_state = ReviewState::new(self.controller.get_moves().len());
+                self.review_active = true;
+            }
+            Err(e) => self.show_message(trf(Text::LoadFailed, &[&e])),
+        }
+    }
+
+    /// Save the game to the library once it ends, if auto-save is on
+    fn archive_finished_game(&mut self) {
+        if self.controller.state() == GameState::Playing {
+            self.archived = false;
+            return;
+        }
+        if self.archived {
+            return;
+        }
+        self.archived = true;
+        self.record_stats();
+        if !self.config.get_auto_save() {
+            return;
+        }
+        let Some(dir) = self.config.get_library_dir() else {
+            return;
+        };
+        match library::archive_game(&dir, self.controller.game()) {
+            Ok(path) => self.show_message(trf(Text::LibrarySaved, &[&path.display()])),
+            Err(e) => self.show_message(trf(Text::LibrarySaveFailed, &[&e])),
+        }
+    }
+
+    /// Add a finished game against the AI to the player's stats
+    fn record_stats(&self) {
+        let player = match self.controller.ai_mode() {
+            AiMode::PlaysRed => Color::Black,
+            AiMode::PlaysBlack => Color::Red,
+            AiMode::Off | AiMode::PlaysBoth => return,
+        };
+        let Some(result) = self.controller.state().result() else {
+            return;
+        };
+        let ai = self.controller.ai_config().for_side(player.opponent());
+        let engine = ai.engine_path.as_deref();
+        let opening = OpeningBook::builtin()
+            .identify(self.controller.game())
+            .map(|opening| opening.name);
+
+        let mut stats = Stats::load();
+        stats.record(
+            &Stats::opponent_name(ai.difficulty, engine),
+            Stats::opponent_rating(ai.difficulty, engine),
+            opening,
+            Outcome::for_player(result, player),
+        );
+        // Losing the stats is not worth interrupting the game for
+        let _ = stats.save();
+    }
+
+    /// Start playing a newly loaded game
+    fn replace_game(&mut self, game: Game, name: &str, warnings: usize) {
+        let game = self.configured_game(game);
+        self.controller.load_game(game);
+        self.archived = true;
+        self.selection = SelectionState::SelectingSource;
+        if warnings == 0 {
+            self.show_message(trf(Text::Opened, &[&name]));
+        } else {
+            self.show_message(trf(Text::OpenedWithWarnings, &[&name, &warnings]));
+        }
+    }
+
+    /// Apply rule settings from the config to a game
+    fn configured_game(&self, mut game: Game) -> Game {
+        game.set_move_limit(self.config.get_move_limit());
+        game.set_stalemate_rule(self.config.stalemate);
+        game
+    }
+
+    /// Apply the config's theme, key bindings and notation, and start its
+    /// engine
+    fn apply_config(&mut self) {
+        ui::UI::set_theme(self.config.get_theme());
+        ui::UI::set_key_bindings(self.config.keys);
+        ui::UI::set_piece_style(self.config.get_piece_style());
+        ui::UI::set_coordinates(self.config.get_coordinates());
+        ui::UI::set_accessible(self.config.get_accessible());
+        ui::UI::set_lang(self.config.lang);
+        self.move_list.style = self.config.notation;
+        match self.controller.init_configured_engine() {
+            Ok(true) => self.show_engine_loaded(),
+            Ok(false) => {}
+            Err(e) => self.show_message(trf(Text::EngineLoadFailed, &[&e])),
+        }
+    }
+
+    /// Say which protocol the engine just loaded speaks
+    fn show_engine_loaded(&mut self) {
+        let protocol = self.controller.engine_protocol().unwrap_or_default();
+        self.show_message(trf(Text::EngineLoaded, &[&protocol.name()]));
+    }
+
+    /// Formatter for moves in the configured notation
+    fn formatter(&self) -> MoveFormatter {
+        MoveFormatter::new(self.config.notation)
+    }
+
+    /// Describe the move just played, in the configured notation
+    fn describe_last_move(&self) -> String {
+        let game = self.controller.game();
+        let moves = game.get_moves();
+        let Some(mv) = moves.last() else {
+            return String::new();
+        };
+        let before = game.at_ply(moves.len() - 1);
+        self.formatter().format_move(&before, mv.from, mv.to)
+    }
+
+    /// Ring the terminal bell after a capture or check, if enabled
+    fn ring_bell(&self) {
+        if self.config.get_bell() && self.controller.game().last_move_was_forcing() {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|()| stdout.flush());
+        }
     }
 
     fn show_message(&mut self, msg: String) {
@@ -342,6 +2515,11 @@ impl App {
     }
 
     fn draw(&mut self, f: &mut Frame) {
+        if !ui::UI::fits(f.area()) {
+            ui::UI::draw_too_small(f);
+            return;
+        }
+
         // Convert SelectionState to Option<Position>
         let selection = match self.selection {
             SelectionState::SelectingSource => None,
@@ -350,7 +2528,54 @@ impl App {
 
         // Draw the main game UI with cursor and selection
         // (includes game over popup when game is not in Playing state)
-        ui::UI::draw(f, self.controller.game(), self.cursor, selection);
+        if self.review_active {
+            ui::UI::draw_review(
+                f,
+                self.controller.game(),
+                self.cursor,
+                &self.review_state,
+                self.move_list.style,
+            );
+            if let Some(editor) = &self.comment_editor {
+                ui::UI::draw_comment_editor(f, editor);
+            }
+            return;
+        }
+        if self.setup_active {
+            ui::UI::draw_setup(f, &self.setup_state, self.cursor);
+            if let Some(ref msg) = self.message {
+                if self.message_time.elapsed() < Duration::from_secs(2) {
+                    self.draw_message(f, msg);
+                }
+            }
+            return;
+        }
+        // A hint lasts until the position changes
+        let fen = self.controller.to_fen();
+        self.hint = self.hint.take().filter(|(hint_fen, _, _)| *hint_fen == fen);
+        let pv = if let Some((_, from, to)) = self.hint {
+            vec![(from, to)]
+        } else if self.controller.ai_config().show_pv {
+            self.controller.expected_line()
+        } else {
+            Vec::new()
+        };
+        ui::UI::draw_with_pv(
+            f,
+            self.controller.game(),
+            self.cursor,
+            selection,
+            &pv,
+            &mut self.move_list,
+        );
+        if let Some(area) = ui::UI::eval_graph_area(f.area()) {
+            ui::UI::draw_eval_graph(
+                f,
+                area,
+                self.controller.game(),
+                &self.controller.move_stats(),
+            );
+        }
 
         // Draw AI menu if active
         if self.ai_menu_active {
@@ -362,22 +2587,167 @@ impl App {
             );
         }
 
-        // Draw status bar at bottom
+        if self.history_active {
+            ui::UI::draw_history_browser(f, self.controller.game(), &mut self.history_state);
+        }
+
+        if let Some(stats) = &self.stats_screen {
+            ui::UI::draw_stats(f, stats);
+        }
+
+        if self.game_picker_active {
+            ui::UI::draw_game_picker(f, &mut self.game_picker_state);
+        }
+
+        if self.library_active {
+            ui::UI::draw_library(f, &mut self.library_state);
+        }
+
+        if let Some(menu) = &self.new_game_menu {
+            ui::UI::draw_new_game_menu(f, menu);
+        }
+
+        if self.open_menu_active {
+            ui::UI::draw_open_menu(f, &self.open_menu_state);
+        }
+
+        // Draw the AI's last search above the status bar
         let size = f.area();
-        if size.height > 3 {
+        let overlay_active = self.ai_menu_active
+            || self.history_active
+            || self.stats_screen.is_some()
+            || self.game_picker_active
+            || self.library_active
+            || self.open_menu_active
+            || self.new_game_menu.is_some();
+        if self.controller.is_analyzing() && !overlay_active {
+            let info = self.controller.analysis();
+            let line = match info {
+                Some(info) => self
+                    .formatter()
+                    .format_line(self.controller.game(), &info.pv),
+                None => Vec::new(),
+            };
+            // The best line is shown in full above the others
+            let others: Vec<_> = self
+                .controller
+                .analysis_lines()
+                .iter()
+                .skip(1)
+                .map(|pv_line| {
+                    let moves = self
+                        .formatter()
+                        .format_line(self.controller.game(), &pv_line.pv);
+                    (pv_line, moves)
+                })
+                .collect();
+            let hash = self.controller.analysis_hash();
+            let hash_move = hash.and_then(|hash| hash.bestmove.as_ref()).and_then(|mv| {
+                self.formatter()
+                    .format_line(self.controller.game(), std::slice::from_ref(mv))
+                    .pop()
+            });
+            ui::UI::draw_analysis_panel(
+                f,
+                ui::UI::analysis_area(size),
+                info,
+                &line,
+                &others,
+                hash,
+                hash_move.as_deref(),
+            );
+        } else if let Some(kibitz) = self.kibitz.as_ref().filter(|_| !overlay_active) {
+            let game = self.controller.game();
+            let engines: Vec<_> = kibitz
+                .kibitzers()
+                .iter()
+                .map(|kibitzer| {
+                    let lines = kibitzer
+                        .lines()
+                        .iter()
+                        .map(|pv_line| (pv_line, self.formatter().format_line(game, &pv_line.pv)))
+                        .collect();
+                    ui::KibitzColumn {
+                        name: kibitzer.name(),
+                        lines,
+                    }
+                })
+                .collect();
+            ui::UI::draw_kibitz_panel(f, ui::UI::analysis_area(size), &engines);
+        } else if ui::UI::tutor() && !overlay_active {
+            ui::UI::draw_tutor_panel(
+                f,
+                ui::UI::analysis_area(size),
+                self.controller.game(),
+                self.cursor,
+                selection,
+            );
+        } else if self.controller.ai_config().show_thinking && !overlay_active && size.height > 12 {
+            let area = Rect {
+                x: 0,
+                y: size.height - 5,
+                width: size.width,
+                height: 4,
+            };
+            let search = self.controller.last_search();
+            let line = match search {
+                Some((ply, info)) => self
+                    .formatter()
+                    .format_line(&self.controller.game().at_ply(ply), &info.pv),
+                None => Vec::new(),
+            };
+            ui::UI::draw_thinking_panel(
+                f,
+                area,
+                true,
+                self.controller.is_engine_thinking(),
+                search.map(|(_, info)| info),
+                &line,
+            );
+        }
+
+        // Draw status bar at bottom
+        if size.height > 3
+            && !self.history_active
+            && self.stats_screen.is_none()
+            && !self.game_picker_active
+            && !self.library_active
+        {
             let status_bar_area = Rect {
                 x: 0,
                 y: size.height - 1,
                 width: size.width,
                 height: 1,
             };
-            ui::UI::draw_status_bar(
-                f,
-                status_bar_area,
-                self.controller.ai_mode(),
-                self.controller.is_engine_thinking(),
-                &self.controller.ai_config().engine_path,
+            if ui::UI::accessible() {
+                let line = describe::status_line(self.controller.game(), self.cursor, selection);
+                ui::UI::draw_description_line(f, status_bar_area, &line);
+            } else {
+                // The engine of the side to move, which may be its own
+                let turn = self.controller.game().turn();
+                ui::UI::draw_status_bar(
+                    f,
+                    status_bar_area,
+                    self.controller.ai_mode(),
+                    self.controller.is_engine_thinking(),
+                    &self.controller.ai_config().for_side(turn).engine_path,
+                    [Color::Red, Color::Black].map(|color| self.controller.remaining_ms(color)),
+                );
+            }
+        }
+
+        if let Some(line) = &self.command_line {
+            let area = Rect::new(0, size.height.saturating_sub(1), size.width, 1);
+            ui::UI::draw_command_line(f, area, line);
+        }
+
+        if let Some(session) = &self.resume_prompt {
+            let prompt = format!(
+                "Resume the saved game ({} moves)? y / n",
+                session.moves.len()
             );
+            self.draw_message(f, &prompt);
+            return;
         }
 
         // Draw message overlay if active
@@ -391,8 +2761,7 @@ impl App {
     }
 
     fn draw_message(&self, f: &mut Frame, message: &str) {
-        use ratatui::style::Color as RColor;
-
+        let theme = ui::UI::theme();
         let size = f.area();
 
         let msg_area = self.centered_rect(message.len() as u16 + 6, 3, size);
@@ -403,14 +2772,14 @@ impl App {
                     .borders(Borders::ALL)
                     .border_style(
                         Style::default()
-                            .fg(RColor::Cyan)
+                            .fg(theme.primary)
                             .add_modifier(Modifier::BOLD),
                     )
                     .title(Span::styled(
-                        " 提示 Info ",
-                        Style::default().fg(RColor::LightCyan),
+                        format!(" {} ", tr(Text::NoticeTitle)),
+                        Style::default().fg(theme.accent),
                     ))
-                    .style(Style::default().bg(RColor::Black)),
+                    .style(Style::default().bg(theme.popup_bg)),
             )
             .alignment(Alignment::Center);
 
@@ -440,61 +2809,134 @@ impl App {
     }
 }
 
+/// Raw mode, the alternate screen and mouse capture for the game, undone
+/// when the guard is dropped however `run_game` returns
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        install_panic_hook();
+        enable_raw_mode()?;
+        // From here on a failure still restores the terminal
+        let guard = TerminalGuard;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Leave raw mode, the alternate screen and mouse capture, whatever state
+/// the terminal was left in
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        cursor::Show
+    );
+}
+
+/// Restore the terminal before a panic's message is printed, so it lands
+/// on the normal screen with working line breaks instead of being lost
+/// with the alternate screen
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            default_hook(info);
+        }));
+    });
+}
+
+/// How often the engine and the server are polled
+const TICK_RATE: Duration = Duration::from_millis(100);
+/// How often the clocks are checked for a flag fall
+const CLOCK_RATE: Duration = Duration::from_millis(250);
+
 fn run_game(app: &mut App) -> io::Result<()> {
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    app.apply_config();
+
+    let guard = TerminalGuard::new()?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = ratatui::Terminal::new(backend)?;
+    let size = terminal.size()?;
+    app.handle_resize(size.width, size.height);
 
-    // Main loop
-    let tick_rate = Duration::from_millis(100);
-    let mut last_tick = Instant::now();
+    let bus = EventBus::new();
+    bus.spawn_input();
+    bus.spawn_ticker(TICK_RATE, AppEvent::Tick);
+    bus.spawn_ticker(CLOCK_RATE, AppEvent::ClockTick);
+    bus.forward(app.controller.subscribe_ai_moves(), |mv| {
+        AppEvent::EngineMove {
+            from: mv.from,
+            to: mv.to,
+        }
+    });
+    if let Some(online) = &app.online {
+        bus.spawn_online(Arc::downgrade(online), TICK_RATE);
+    }
 
     while app.running {
-        // Draw
         terminal.draw(|f| app.draw(f))?;
 
-        // Handle input with timeout
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
-
-        if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                app.handle_key(key.code);
+        // Wait for something to happen, then take whatever else came in
+        // meanwhile before drawing again
+        let Some(first) = bus.next(TICK_RATE) else {
+            continue;
+        };
+        for event in std::iter::once(first).chain(bus.pending()) {
+            if let AppEvent::Resize(width, height) = event {
+                // Lay the screen out again from scratch rather than
+                // diffing against the old size
+                terminal.resize(Rect::new(0, 0, width, height))?;
+            }
+            app.handle_event(event);
+            if !app.running {
+                break;
             }
         }
+    }
 
-        // Check for engine responses
-        if let Ok(Some(mv)) = app.controller.check_engine_response() {
-            app.show_message(format!("AI played: {:?}", mv));
-        }
+    drop(guard);
 
-        if last_tick.elapsed() >= tick_rate {
-            last_tick = Instant::now();
-        }
+    // Keep an unfinished game for the next launch
+    if let Err(e) = persistence::save_session(app.controller.game(), app.controller.ai_mode()) {
+        eprintln!("Error saving session: {}", e);
     }
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
     Ok(())
 }
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let json = args.iter().any(|a| a == "--json");
+    args.retain(|a| a != "--json");
+    let mut config = Config::load_or_default();
+    if let Err(e) = apply_cli_overrides(&mut args, &mut config) {
+        if json {
+            print_json(&serde_json::json!({ "error": e }));
+        } else {
+            eprintln!("Error: {}", e);
+        }
+        process::exit(1);
+    }
 
-    // No arguments - start new game
+    // No arguments - start new game, offering to resume a saved one
     if args.len() == 1 {
-        let mut app = App::new();
+        let mut app = App::new(config);
+        match persistence::load_session() {
+            Some(Ok(session)) => app.resume_prompt = Some(session),
+            Some(Err(e)) => app.show_message(trf(Text::SavedGameIgnored, &[&e])),
+            None => {}
+        }
         if let Err(e) = run_game(&mut app) {
             eprintln!("Error running game: {}", e);
             process::exit(1);
@@ -507,16 +2949,15 @@ fn main() {
         "--help" | "-h" => {
             print_usage();
         }
-        "--print" => {
-            if args.len() < 3 {
-                eprintln!("Error: --print requires a FEN string");
-                println!();
-                print_usage();
-                process::exit(1);
-            }
-            let fen = &args[2];
-            if let Err(e) = print_fen_position(fen) {
-                eprintln!("Error parsing FEN: {}", e);
+        "--print" | "--validate-fen" | "--best-move" | "--check-move" | "--analyze"
+        | "--convert" | "--fen-history" | "--search-position" | "--dedupe" | "--perft"
+        | "--export-report" | "--annotate" | "--export-xml" | "--stdin" => {
+            if let Err(e) = run_query(&args[1..], json, &config) {
+                if json {
+                    print_json(&serde_json::json!({ "error": e }));
+                } else {
+                    eprintln!("Error: {}", e);
+                }
                 process::exit(1);
             }
         }
@@ -528,7 +2969,7 @@ fn main() {
                 process::exit(1);
             }
             let fen = &args[2];
-            match App::from_fen(fen) {
+            match App::from_fen(fen, config) {
                 Ok(mut app) => {
                     if let Err(e) = run_game(&mut app) {
                         eprintln!("Error running game: {}", e);
@@ -541,6 +2982,79 @@ fn main() {
                 }
             }
         }
+        "--handicap" => {
+            let Some(spec) = args.get(2) else {
+                eprintln!("Error: --handicap requires a handicap");
+                println!();
+                print_usage();
+                process::exit(1);
+            };
+            let Some(handicap) = Handicap::by_spec(spec) else {
+                let specs: Vec<&str> = HANDICAPS.iter().map(|h| h.spec).collect();
+                eprintln!(
+                    "Error: unknown handicap '{}' (expected {})",
+                    spec,
+                    specs.join(", ")
+                );
+                process::exit(1);
+            };
+            let mut app = App::new(config);
+            app.new_game(handicap.game());
+            let name = ui::UI::handicap_name(handicap);
+            app.show_message(trf(Text::NewGame, &[&name]));
+            if let Err(e) = run_game(&mut app) {
+                eprintln!("Error running game: {}", e);
+                process::exit(1);
+            }
+        }
+        "--setup" => {
+            let editor = match args.get(2) {
+                Some(fen) => match fen::fen_to_board(fen) {
+                    Ok((board, turn)) => BoardEditor::new(board, turn),
+                    Err(e) => {
+                        eprintln!("Error parsing FEN: {}", e);
+                        process::exit(1);
+                    }
+                },
+                None => BoardEditor::new(Board::new(), Color::Red),
+            };
+            let mut app = App::new(config);
+            app.start_setup(editor);
+            if let Err(e) = run_game(&mut app) {
+                eprintln!("Error running game: {}", e);
+                process::exit(1);
+            }
+        }
+        "--cli" => {
+            let game = match args.get(2) {
+                Some(fen) => match Game::from_fen(fen) {
+                    Ok(game) => game,
+                    Err(e) => {
+                        eprintln!("Error parsing FEN: {}", e);
+                        process::exit(1);
+                    }
+                },
+                None => Game::new(),
+            };
+            let mut controller = GameController::from_config(game, &config);
+            let stdin = io::stdin();
+            if let Err(e) = cli::run(
+                &mut controller,
+                config.notation,
+                stdin.lock(),
+                &mut io::stdout().lock(),
+            ) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        "--ucci-serve" => {
+            let stdin = io::stdin();
+            if let Err(e) = ucci::server::run(stdin.lock(), &mut io::stdout().lock()) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
         "--file" => {
             if args.len() < 3 {
                 eprintln!("Error: --file requires a file path");
@@ -549,8 +3063,9 @@ fn main() {
                 process::exit(1);
             }
             let path = &args[2];
-            match App::from_file(path) {
+            match App::from_file(path, config) {
                 Ok(mut app) => {
+                    state::remember_recent_file(Path::new(path));
                     if let Err(e) = run_game(&mut app) {
                         eprintln!("Error running game: {}", e);
                         process::exit(1);
@@ -570,8 +3085,14 @@ fn main() {
                 process::exit(1);
             }
             let path = &args[2];
-            match App::from_pgn(path) {
+            let mode = if args.iter().any(|a| a == "--lenient") {
+                ImportMode::Lenient
+            } else {
+                ImportMode::Strict
+            };
+            match App::from_pgn(path, FileKind::Pgn, mode, config) {
                 Ok(mut app) => {
+                    state::remember_recent_file(Path::new(path));
                     if let Err(e) = run_game(&mut app) {
                         eprintln!("Error running game: {}", e);
                         process::exit(1);
@@ -583,17 +3104,173 @@ fn main() {
                 }
             }
         }
+        "--import-xml" => {
+            let Some(path) = args.get(2) else {
+                eprintln!("Error: --import-xml requires a file path");
+                println!();
+                print_usage();
+                process::exit(1);
+            };
+            match App::from_pgn(path, FileKind::Xml, ImportMode::Strict, config) {
+                Ok(mut app) => {
+                    state::remember_recent_file(Path::new(path));
+                    app.review_from_start();
+                    if let Err(e) = run_game(&mut app) {
+                        eprintln!("Error running game: {}", e);
+                        process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error loading XML file: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "--xqf" => {
+            let Some(path) = args.get(2) else {
+                eprintln!("Error: --xqf requires a file path");
+                println!();
+                print_usage();
+                process::exit(1);
+            };
+            match App::from_pgn(path, FileKind::Xqf, ImportMode::Strict, config) {
+                Ok(mut app) => {
+                    state::remember_recent_file(Path::new(path));
+                    if let Err(e) = run_game(&mut app) {
+                        eprintln!("Error running game: {}", e);
+                        process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error loading XQF file: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        "--stats" => {
+            let mut app = App::new(config);
+            app.stats_screen = Some(Stats::load());
+            if let Err(e) = run_game(&mut app) {
+                eprintln!("Error running game: {}", e);
+                process::exit(1);
+            }
+        }
+        "--library" => {
+            let mut app = App::new(config);
+            app.open_library();
+            if let Err(e) = run_game(&mut app) {
+                eprintln!("Error running game: {}", e);
+                process::exit(1);
+            }
+        }
+        "--resume" => {
+            let session = match persistence::load_session() {
+                Some(Ok(session)) => match session.to_game() {
+                    Ok(_) => session,
+                    Err(e) => {
+                        eprintln!("Error loading saved game: {}", e);
+                        process::exit(1);
+                    }
+                },
+                Some(Err(e)) => {
+                    eprintln!("Error loading saved game: {}", e);
+                    process::exit(1);
+                }
+                None => {
+                    eprintln!("Error: no saved game to resume");
+                    process::exit(1);
+                }
+            };
+            let mut app = App::new(config);
+            app.resume(session);
+            if let Err(e) = run_game(&mut app) {
+                eprintln!("Error running game: {}", e);
+                process::exit(1);
+            }
+        }
+        "--online" => {
+            if args.len() < 4 {
+                eprintln!("Error: --online requires a server URL and a name");
+                process::exit(1);
+            }
+            let (client, game) = match connect_online(&args[2], &args[3]) {
+                Ok(Some(start)) => start,
+                Ok(None) => return,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            };
+            let mut app = App::new(config);
+            app.start_online(client, game);
+            if let Err(e) = run_game(&mut app) {
+                eprintln!("Error running game: {}", e);
+                process::exit(1);
+            }
+        }
+        "--spectate" => {
+            if args.len() < 3 {
+                eprintln!("Error: --spectate requires a server URL");
+                process::exit(1);
+            }
+            let (client, game) = match connect_spectate(&args[2]) {
+                Ok(Some(start)) => start,
+                Ok(None) => return,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            };
+            let mut app = App::new(config);
+            app.start_spectating(client, game);
+            if let Err(e) = run_game(&mut app) {
+                eprintln!("Error running game: {}", e);
+                process::exit(1);
+            }
+        }
+        "--turn" => {
+            if args.len() < 3 {
+                eprintln!("Error: --turn requires a turn file");
+                process::exit(1);
+            }
+            let path = PathBuf::from(&args[2]);
+            let file = match open_turn_file(&path) {
+                Ok(file) => file,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            };
+            let mut app = App::new(config);
+            app.start_correspondence(path, file, args.get(3).cloned());
+            if let Err(e) = run_game(&mut app) {
+                eprintln!("Error running game: {}", e);
+                process::exit(1);
+            }
+        }
         "--engine" => {
             if args.len() < 3 {
                 eprintln!("Error: --engine requires a path");
                 process::exit(1);
             }
             let engine_path = &args[2];
-            let mut app = App::new();
+            let mut app = App::new(config);
+            if let Some(name) = args.get(3) {
+                let Some(protocol) = EngineProtocol::by_name(name) else {
+                    eprintln!(
+                        "Error: unknown engine protocol '{}' (auto, ucci or uci)",
+                        name
+                    );
+                    process::exit(1);
+                };
+                let mut ai_config = app.controller.ai_config().clone();
+                ai_config.engine_protocol = protocol;
+                app.controller.set_ai_config(ai_config);
+            }
 
             match app.controller.init_engine(engine_path) {
                 Ok(_) => {
-                    app.show_message("Engine loaded".to_string());
+                    app.show_engine_loaded();
                 }
                 Err(e) => {
                     eprintln!("Error loading engine: {}", e);
@@ -614,3 +3291,112 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new(Config::default());
+        app.screen = Rect::new(0, 0, 120, 40);
+        app
+    }
+
+    #[test]
+    fn test_status_messages_follow_the_language() {
+        let mut app = app();
+        ui::UI::set_lang(Lang::Zh);
+        app.handle_key(KeyCode::Char('i'));
+        assert_eq!(app.message.as_deref(), Some("坐标: 开"));
+
+        ui::UI::set_lang(Lang::En);
+        app.handle_key(KeyCode::Char('i'));
+        assert_eq!(app.message.as_deref(), Some("Coordinates: off"));
+    }
+
+    #[test]
+    fn test_setup_reverts_to_the_position_it_started_from() {
+        let mut app = app();
+        let (from, to) = (Position::from_xy(7, 7), Position::from_xy(4, 7));
+        app.controller.human_move(from, to).unwrap();
+        let played = app.controller.board().clone();
+
+        app.handle_key(KeyCode::Char('s'));
+        app.handle_key(KeyCode::Char('X'));
+        app.handle_key(KeyCode::Char('o'));
+        assert_eq!(app.setup_state.editor.board(), &played);
+
+        // Reverting is an edit like any other
+        app.handle_key(KeyCode::Char(app.config.keys.undo));
+        assert_eq!(app.setup_state.editor.board().pieces().count(), 0);
+    }
+
+    #[test]
+    fn test_ai_move_is_played_when_its_event_is_handled() {
+        let mut app = app();
+        app.controller.set_ai_mode(AiMode::PlaysRed);
+        let moves = app.controller.subscribe_ai_moves();
+
+        // The tick only starts the AI's move
+        app.handle_event(AppEvent::Tick);
+        let mv = moves.try_recv().unwrap();
+        assert_eq!(app.controller.game().move_count(), 0);
+
+        app.handle_event(AppEvent::EngineMove {
+            from: mv.from,
+            to: mv.to,
+        });
+        assert_eq!(app.controller.game().last_move(), Some(mv));
+        assert!(app.message.as_deref().unwrap().starts_with("AI played"));
+    }
+
+    #[test]
+    fn test_stale_ai_move_is_ignored() {
+        let mut app = app();
+        app.handle_event(AppEvent::EngineMove {
+            from: Position::from_xy(7, 7),
+            to: Position::from_xy(4, 7),
+        });
+
+        assert_eq!(app.controller.game().move_count(), 0);
+        assert_eq!(app.message, None);
+    }
+
+    #[test]
+    fn test_opponent_move_is_played_when_its_event_is_handled() {
+        let mut app = app();
+        let (from, to) = (Position::from_xy(7, 7), Position::from_xy(4, 7));
+        app.handle_event(AppEvent::NetMove(OnlineEvent::OpponentMoved { from, to }));
+
+        assert_eq!(
+            app.controller.game().last_move().map(|mv| (mv.from, mv.to)),
+            Some((from, to))
+        );
+        assert!(app
+            .message
+            .as_deref()
+            .unwrap()
+            .starts_with("Opponent played"));
+    }
+
+    #[test]
+    fn test_lost_connection_ends_online_play() {
+        struct Silent;
+        impl Transport for Silent {
+            fn send(&mut self, _text: &str) -> io::Result<()> {
+                Ok(())
+            }
+            fn try_recv(&mut self) -> io::Result<Option<String>> {
+                Ok(None)
+            }
+        }
+
+        let mut app = app();
+        let client: Connection = OnlineClient::new(Box::new(Silent));
+        app.online = Some(Arc::new(Mutex::new(client)));
+        app.handle_event(AppEvent::NetError("closed".to_string()));
+
+        assert!(app.online.is_none());
+        assert_eq!(app.message.as_deref(), Some("Online: closed"));
+    }
+}
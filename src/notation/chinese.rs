@@ -7,7 +7,9 @@
 //! - Red: files numbered right-to-left (from Red's view)
 //! - Black: files numbered left-to-right (from Red's view)
 //! - Directions: 进 (forward), 退 (backward), 平 (horizontal/same rank)
-//! - Uses Chinese numerals: 一二三四五六七八九
+//! - Uses Chinese numerals: 一二三四五六七八九; by the usual convention
+//!   ([`NumeralStyle::MixedStandard`]) Black's moves use Arabic numerals
+//!   instead, e.g. 马8进7
 
 use crate::board::Board;
 use crate::types::{Color, Piece, PieceType, Position};
@@ -75,6 +77,48 @@ pub fn file_number_to_chinese(n: usize) -> &'static str {
     }
 }
 
+/// How the numbers in a Chinese notation move are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumeralStyle {
+    /// Chinese numerals for both sides, e.g. 炮二平五 and 马八进七
+    Traditional,
+    /// Chinese numerals for Red and Arabic numerals for Black, e.g.
+    /// 炮二平五 and 马8进7, as in most books and game records
+    #[default]
+    MixedStandard,
+}
+
+impl NumeralStyle {
+    /// Write a file number or step count (1-9) for a move by `color`
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::notation::chinese::NumeralStyle;
+    /// use cn_chess_tui::types::Color;
+    ///
+    /// assert_eq!(NumeralStyle::MixedStandard.numeral(5, Color::Red), "五");
+    /// assert_eq!(NumeralStyle::MixedStandard.numeral(5, Color::Black), "5");
+    /// assert_eq!(NumeralStyle::Traditional.numeral(5, Color::Black), "五");
+    /// ```
+    pub fn numeral(self, n: usize, color: Color) -> &'static str {
+        match (self, color) {
+            (NumeralStyle::MixedStandard, Color::Black) => match n {
+                1 => "1",
+                2 => "2",
+                3 => "3",
+                4 => "4",
+                5 => "5",
+                6 => "6",
+                7 => "7",
+                8 => "8",
+                9 => "9",
+                _ => "?",
+            },
+            _ => file_number_to_chinese(n),
+        }
+    }
+}
+
 /// Determine the direction of movement for Chinese notation
 ///
 /// # Examples
@@ -191,12 +235,13 @@ pub fn move_to_chinese(piece: Piece, from: Position, to: Position) -> String {
     let direction = get_movement_direction(from, to, piece.color);
     let dir_chinese = direction_to_chinese(direction);
 
-    let to_chinese = if direction == MovementDirection::Horizontal {
-        // For horizontal moves, use destination file number
+    let to_chinese = if from.x != to.x {
+        // Horizontal and diagonal (horse, elephant, advisor) moves name the
+        // destination file
         let to_file = position_to_file_number(to, piece.color);
         file_number_to_chinese(to_file)
     } else {
-        // For forward/backward moves, use number of steps
+        // Moves along the file count steps
         let steps = from.y.abs_diff(to.y);
         file_number_to_chinese(steps)
     };
@@ -317,6 +362,37 @@ pub fn move_to_chinese_with_context(
     piece: Piece,
     from: Position,
     to: Position,
+) -> String {
+    move_to_chinese_with_style(game, piece, from, to, NumeralStyle::Traditional)
+}
+
+/// Convert a move to Chinese notation, writing numbers in `style`
+///
+/// Like [`move_to_chinese_with_context`], which always uses Chinese
+/// numerals. The 前/后 and 一兵/二兵 prefixes are written the same way in
+/// both styles.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::notation::chinese::{move_to_chinese_with_style, NumeralStyle};
+/// use cn_chess_tui::{Game, Piece, PieceType, Position};
+///
+/// let mut game = Game::new();
+/// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+///
+/// let horse = Piece::black(PieceType::Horse);
+/// let (from, to) = (Position::from_xy(7, 0), Position::from_xy(6, 2));
+/// let standard = move_to_chinese_with_style(&game, horse, from, to, NumeralStyle::MixedStandard);
+/// assert_eq!(standard, "马8进7");
+/// let traditional = move_to_chinese_with_style(&game, horse, from, to, NumeralStyle::Traditional);
+/// assert_eq!(traditional, "马八进七");
+/// ```
+pub fn move_to_chinese_with_style(
+    game: &Game,
+    piece: Piece,
+    from: Position,
+    to: Position,
+    style: NumeralStyle,
 ) -> String {
     let piece_name = if piece.piece_type == PieceType::Soldier {
         // Check for soldier ambiguity
@@ -328,30 +404,261 @@ pub fn move_to_chinese_with_context(
             piece_to_chinese(piece).to_string()
         }
     } else {
-        // For other pieces, use basic notation for now
-        // TODO: Implement full ambiguity resolution for advisors/elephants/etc.
+        // Advisors and elephants sharing a file always move in opposite
+        // directions, so only chariots, horses and cannons need telling
+        // apart
         piece_to_chinese(piece).to_string()
     };
 
-    let from_file = position_to_file_number(from, piece.color);
-    let from_chinese = file_number_to_chinese(from_file);
     let direction = get_movement_direction(from, to, piece.color);
     let dir_chinese = direction_to_chinese(direction);
+    // Two chariots, horses or cannons on one file are the front and rear
+    // one, which replaces the file: 前炮平九
+    let from_chinese = match tandem_prefix(game.board(), piece, from) {
+        Some(prefix) => format!("{}{}", prefix, piece_name),
+        None => {
+            let from_file = position_to_file_number(from, piece.color);
+            format!("{}{}", piece_name, style.numeral(from_file, piece.color))
+        }
+    };
 
-    let to_chinese = if direction == MovementDirection::Horizontal {
-        // For horizontal moves, use destination file number
+    let to_chinese = if from.x != to.x {
+        // Horizontal and diagonal (horse, elephant, advisor) moves name the
+        // destination file
         let to_file = position_to_file_number(to, piece.color);
-        file_number_to_chinese(to_file)
+        style.numeral(to_file, piece.color)
     } else {
-        // For forward/backward moves, use number of steps
+        // Moves along the file count steps
         let steps = from.y.abs_diff(to.y);
-        file_number_to_chinese(steps)
+        style.numeral(steps, piece.color)
     };
 
-    format!(
-        "{}{}{}{}",
-        piece_name, from_chinese, dir_chinese, to_chinese
-    )
+    format!("{}{}{}", from_chinese, dir_chinese, to_chinese)
+}
+
+/// 前 or 后 for a chariot, horse or cannon sharing its file with another
+/// of its kind, from the mover's point of view
+fn tandem_prefix(board: &Board, piece: Piece, from: Position) -> Option<&'static str> {
+    if !matches!(
+        piece.piece_type,
+        PieceType::Chariot | PieceType::Horse | PieceType::Cannon
+    ) {
+        return None;
+    }
+    let others = find_pieces_on_same_file(board, piece, from);
+    let [a, b] = others.as_slice() else {
+        return None;
+    };
+    let front = match piece.color {
+        Color::Red => a.y.min(b.y),
+        Color::Black => a.y.max(b.y),
+    };
+    Some(if from.y == front { "前" } else { "后" })
+}
+
+/// Which of several same-type pieces on one file a move refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileRank {
+    /// 前 - the piece closest to the opponent
+    Front,
+    /// 中 - the middle of three
+    Middle,
+    /// 后 - the piece furthest from the opponent
+    Rear,
+    /// 一二三四五 - counted from the front, starting at 1
+    Nth(usize),
+}
+
+/// A Chinese notation move broken into its parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChineseMove {
+    piece_type: PieceType,
+    rank: Option<FileRank>,
+    file: Option<usize>,
+    direction: MovementDirection,
+    amount: usize,
+}
+
+/// Parse a numeral in Chinese, ASCII or full-width digits (1-9)
+fn parse_numeral(c: char) -> Option<usize> {
+    let n = match c {
+        '一' => 1,
+        '二' => 2,
+        '三' => 3,
+        '四' => 4,
+        '五' => 5,
+        '六' => 6,
+        '七' => 7,
+        '八' => 8,
+        '九' => 9,
+        '1'..='9' => c as usize - '0' as usize,
+        '１'..='９' => c as usize - '１' as usize + 1,
+        _ => return None,
+    };
+    Some(n)
+}
+
+/// Parse a piece name, accepting simplified, traditional and variant forms
+fn parse_piece_char(c: char) -> Option<PieceType> {
+    let piece_type = match c {
+        '帅' | '帥' | '将' | '將' => PieceType::General,
+        '仕' | '士' => PieceType::Advisor,
+        '相' | '象' => PieceType::Elephant,
+        '马' | '馬' | '傌' | '碼' => PieceType::Horse,
+        '车' | '車' | '俥' | '硨' => PieceType::Chariot,
+        '炮' | '砲' | '包' => PieceType::Cannon,
+        '兵' | '卒' => PieceType::Soldier,
+        _ => return None,
+    };
+    Some(piece_type)
+}
+
+fn parse_direction(c: char) -> Option<MovementDirection> {
+    match c {
+        '进' | '進' => Some(MovementDirection::Forward),
+        '退' => Some(MovementDirection::Backward),
+        '平' => Some(MovementDirection::Horizontal),
+        _ => None,
+    }
+}
+
+fn parse_file_rank(c: char) -> Option<FileRank> {
+    match c {
+        '前' => Some(FileRank::Front),
+        '中' => Some(FileRank::Middle),
+        '后' | '後' => Some(FileRank::Rear),
+        _ => parse_numeral(c).filter(|&n| n <= 5).map(FileRank::Nth),
+    }
+}
+
+/// Split notation like "炮二平五", "前马进三" or "前兵五进一" into its parts
+fn parse_chinese_parts(notation: &str) -> Option<ChineseMove> {
+    let chars: Vec<char> = notation.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let (piece_type, rank, file, rest) = match chars.as_slice() {
+        // 炮二平五
+        [p, f, rest @ ..] if parse_piece_char(*p).is_some() => {
+            (parse_piece_char(*p)?, None, Some(parse_numeral(*f)?), rest)
+        }
+        // 前兵五进一 (file kept after the rank, as written by this crate)
+        [r, p, f, rest @ ..] if rest.len() == 2 && parse_piece_char(*p).is_some() => (
+            parse_piece_char(*p)?,
+            Some(parse_file_rank(*r)?),
+            Some(parse_numeral(*f)?),
+            rest,
+        ),
+        // 前马进三
+        [r, p, rest @ ..] => (
+            parse_piece_char(*p)?,
+            Some(parse_file_rank(*r)?),
+            None,
+            rest,
+        ),
+        _ => return None,
+    };
+
+    match rest {
+        [d, n] => Some(ChineseMove {
+            piece_type,
+            rank,
+            file,
+            direction: parse_direction(*d)?,
+            amount: parse_numeral(*n)?,
+        }),
+        _ => None,
+    }
+}
+
+/// Check whether the piece at `from` is the one `rank` refers to
+fn matches_file_rank(board: &Board, piece: Piece, from: Position, rank: FileRank) -> bool {
+    let mut same_file = find_pieces_on_same_file(board, piece, from);
+    if same_file.len() < 2 {
+        return false;
+    }
+
+    // Front to back from the mover's point of view
+    same_file.sort_by_key(|p| p.y);
+    if piece.color == Color::Black {
+        same_file.reverse();
+    }
+
+    let idx = match rank {
+        FileRank::Front => 0,
+        FileRank::Rear => same_file.len() - 1,
+        FileRank::Middle if same_file.len() == 3 => 1,
+        FileRank::Middle => return false,
+        FileRank::Nth(n) => n - 1,
+    };
+    same_file.get(idx) == Some(&from)
+}
+
+/// Parse a move in Chinese notation against the current board
+///
+/// Accepts the common forms "炮二平五", "马8进7" (Arabic digits, as often
+/// used for Black), "前马进三" and "前兵五进一", with simplified or
+/// traditional characters. The notation is matched against the legal moves
+/// of `color`; `None` is returned if it is malformed, matches no legal move,
+/// or is ambiguous.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::{
+///     board::Board,
+///     notation::chinese::parse_chinese_move,
+///     types::{Color, Position},
+/// };
+///
+/// let board = Board::new();
+/// assert_eq!(
+///     parse_chinese_move(&board, Color::Red, "炮二平五"),
+///     Some((Position::from_xy(7, 7), Position::from_xy(4, 7)))
+/// );
+/// assert_eq!(
+///     parse_chinese_move(&board, Color::Black, "马8进7"),
+///     Some((Position::from_xy(7, 0), Position::from_xy(6, 2)))
+/// );
+/// ```
+pub fn parse_chinese_move(
+    board: &Board,
+    color: Color,
+    notation: &str,
+) -> Option<(Position, Position)> {
+    let parsed = parse_chinese_parts(notation)?;
+
+    let candidates: Vec<(Position, Position)> = board
+        .legal_moves(color)
+        .into_iter()
+        .filter_map(|(from, to)| {
+            let piece = *board.get(from)?;
+            let matches = piece.piece_type == parsed.piece_type
+                && get_movement_direction(from, to, color) == parsed.direction
+                && parsed
+                    .file
+                    .is_none_or(|f| position_to_file_number(from, color) == f)
+                && parsed
+                    .rank
+                    .is_none_or(|r| matches_file_rank(board, piece, from, r));
+            matches.then_some((from, to))
+        })
+        .collect();
+
+    // Moves along a file count steps; anything that changes file names
+    // the destination file
+    let matches: Vec<_> = candidates
+        .iter()
+        .filter(|&&(from, to)| {
+            if from.x == to.x {
+                from.y.abs_diff(to.y) == parsed.amount
+            } else {
+                position_to_file_number(to, color) == parsed.amount
+            }
+        })
+        .collect();
+
+    match matches.as_slice() {
+        [&(from, to)] => Some((from, to)),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -545,4 +852,29 @@ mod tests {
         let to = Position::from_xy(4, 7); // Backward 2 steps
         assert_eq!(move_to_chinese(piece, from, to), "炮五退二");
     }
+
+    #[test]
+    fn test_tandem_pieces_written_front_and_rear() {
+        // Both red cannons on file 8 (八)
+        let game =
+            Game::from_fen("1Cbakabnr/r8/c6c1/p1p1p1p1p/9/9/P1P1P1P1P/1C7/9/RNBAKABNR w - - 3 3")
+                .unwrap();
+        let cannon = Piece::new(PieceType::Cannon, Color::Red);
+        let front = Position::from_xy(1, 0);
+        let rear = Position::from_xy(1, 7);
+        let to = Position::from_xy(0, 0);
+        let style = NumeralStyle::default();
+        assert_eq!(
+            move_to_chinese_with_style(&game, cannon, front, to, style),
+            "前炮平九"
+        );
+        assert_eq!(
+            move_to_chinese_with_style(&game, cannon, rear, Position::from_xy(4, 7), style),
+            "后炮平五"
+        );
+        assert_eq!(
+            parse_chinese_move(game.board(), Color::Red, "前炮平九"),
+            Some((front, to))
+        );
+    }
 }
@@ -0,0 +1,269 @@
+//! Move formatting for display
+//!
+//! Engines and the analysis commands speak ICCS. [`MoveFormatter`] turns
+//! those moves into the notation the user prefers, keeping the ICCS move
+//! alongside (e.g. `炮二平五 (h7e7)`), so every panel that shows moves or
+//! engine lines writes them the same way. [`parse_any`] reads a move typed
+//! in any of the notations back.
+
+use crate::game::Game;
+use crate::notation::chinese::{move_to_chinese_with_style, parse_chinese_move, NumeralStyle};
+use crate::notation::iccs::{iccs_to_move, move_to_iccs};
+use crate::notation::wxf::{move_to_wxf, parse_wxf_move, resolve_wxf_move};
+use crate::types::Position;
+use serde::Deserialize;
+use std::fmt::{self, Display, Formatter};
+
+/// Notation used to show moves to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveStyle {
+    /// Traditional Chinese notation, e.g. `炮二平五`
+    #[default]
+    Chinese,
+    /// WXF notation, e.g. `C2.5`
+    Wxf,
+    /// ICCS coordinates only, e.g. `h7e7`
+    Iccs,
+}
+
+impl MoveStyle {
+    /// The next style, cycling Chinese, WXF, ICCS
+    pub fn next(self) -> Self {
+        match self {
+            MoveStyle::Chinese => MoveStyle::Wxf,
+            MoveStyle::Wxf => MoveStyle::Iccs,
+            MoveStyle::Iccs => MoveStyle::Chinese,
+        }
+    }
+
+    /// Name shown to the user
+    pub fn name(self) -> &'static str {
+        match self {
+            MoveStyle::Chinese => "中文 Chinese",
+            MoveStyle::Wxf => "WXF",
+            MoveStyle::Iccs => "ICCS",
+        }
+    }
+
+    /// Value of the PGN `Format` tag for games written in this notation
+    pub fn pgn_format(self) -> &'static str {
+        match self {
+            MoveStyle::Chinese => "Chinese",
+            MoveStyle::Wxf => "WXF",
+            MoveStyle::Iccs => "ICCS",
+        }
+    }
+
+    /// Tell which notation a move is written in
+    ///
+    /// Only the form is checked, not whether the move can be played.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::notation::format::MoveStyle;
+    ///
+    /// assert_eq!(MoveStyle::detect("h7e7"), Some(MoveStyle::Iccs));
+    /// assert_eq!(MoveStyle::detect("C2.5"), Some(MoveStyle::Wxf));
+    /// assert_eq!(MoveStyle::detect("炮二平五"), Some(MoveStyle::Chinese));
+    /// assert_eq!(MoveStyle::detect("hello"), None);
+    /// ```
+    pub fn detect(text: &str) -> Option<MoveStyle> {
+        let text = text.trim();
+        if iccs_to_move(text).is_some() {
+            Some(MoveStyle::Iccs)
+        } else if parse_wxf_move(&text.to_ascii_uppercase()).is_some() {
+            Some(MoveStyle::Wxf)
+        } else if !text.is_ascii() {
+            Some(MoveStyle::Chinese)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why a typed move could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMoveError {
+    /// The text is in none of the notations
+    Unrecognized(String),
+    /// No legal move, or more than one, matches the text
+    NoMatch { style: MoveStyle, text: String },
+}
+
+impl Display for ParseMoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMoveError::Unrecognized(text) => {
+                write!(f, "'{}' is not an ICCS, WXF or Chinese move", text)
+            }
+            ParseMoveError::NoMatch { style, text } => {
+                write!(f, "no legal move matches {} '{}'", style.pgn_format(), text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+/// Read a move typed in any [`MoveStyle`], to be played in `game`
+///
+/// ICCS moves are returned as written, so playing them reports why an
+/// illegal one fails. Chinese and WXF moves are matched against the legal
+/// moves and fail with [`ParseMoveError::NoMatch`] when none or several
+/// match.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::notation::format::{parse_any, ParseMoveError};
+/// use cn_chess_tui::{Game, Position};
+///
+/// let game = Game::new();
+/// let cannon = Ok((Position::from_xy(7, 7), Position::from_xy(4, 7)));
+/// assert_eq!(parse_any(&game, "h7e7"), cannon);
+/// assert_eq!(parse_any(&game, "C2.5"), cannon);
+/// assert_eq!(parse_any(&game, "炮二平五"), cannon);
+/// assert!(matches!(parse_any(&game, "炮二进九"), Err(ParseMoveError::NoMatch { .. })));
+/// assert!(matches!(parse_any(&game, "hello"), Err(ParseMoveError::Unrecognized(_))));
+/// ```
+pub fn parse_any(game: &Game, text: &str) -> Result<(Position, Position), ParseMoveError> {
+    let text = text.trim();
+    let style =
+        MoveStyle::detect(text).ok_or_else(|| ParseMoveError::Unrecognized(text.to_string()))?;
+    let parsed = match style {
+        MoveStyle::Iccs => iccs_to_move(text),
+        MoveStyle::Wxf => resolve_wxf_move(game.board(), game.turn(), text),
+        MoveStyle::Chinese => parse_chinese_move(game.board(), game.turn(), text),
+    };
+    parsed.ok_or_else(|| ParseMoveError::NoMatch {
+        style,
+        text: text.to_string(),
+    })
+}
+
+/// Read a move typed in any [`MoveStyle`], as [`parse_any`] does, without
+/// saying why it can't be read
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::notation::format::parse_move;
+/// use cn_chess_tui::{Game, Position};
+///
+/// let game = Game::new();
+/// let cannon = Some((Position::from_xy(7, 7), Position::from_xy(4, 7)));
+/// assert_eq!(parse_move(&game, "h7e7"), cannon);
+/// assert_eq!(parse_move(&game, "炮二平五"), cannon);
+/// ```
+pub fn parse_move(game: &Game, text: &str) -> Option<(Position, Position)> {
+    parse_any(game, text).ok()
+}
+
+/// Formats moves in a [`MoveStyle`]
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::notation::format::{MoveFormatter, MoveStyle};
+/// use cn_chess_tui::Game;
+///
+/// let game = Game::new();
+/// let chinese = MoveFormatter::new(MoveStyle::Chinese);
+/// assert_eq!(chinese.format_iccs(&game, "h7e7"), "炮二平五 (h7e7)");
+///
+/// let wxf = MoveFormatter::new(MoveStyle::Wxf);
+/// assert_eq!(wxf.format_iccs(&game, "h7e7"), "C2.5 (h7e7)");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MoveFormatter {
+    pub style: MoveStyle,
+}
+
+impl MoveFormatter {
+    pub fn new(style: MoveStyle) -> Self {
+        Self { style }
+    }
+
+    /// Format a move about to be played in `game`
+    pub fn format_move(&self, game: &Game, from: Position, to: Position) -> String {
+        let iccs = move_to_iccs(from, to);
+        let Some(&piece) = game.board().get(from) else {
+            return iccs;
+        };
+        let text = match self.style {
+            MoveStyle::Chinese => {
+                move_to_chinese_with_style(game, piece, from, to, NumeralStyle::default())
+            }
+            MoveStyle::Wxf => move_to_wxf(piece, from, to),
+            MoveStyle::Iccs => return iccs,
+        };
+        format!("{} ({})", text, iccs)
+    }
+
+    /// Format an ICCS move about to be played in `game`
+    ///
+    /// Text that is not an ICCS move is returned unchanged.
+    pub fn format_iccs(&self, game: &Game, iccs: &str) -> String {
+        match iccs_to_move(iccs) {
+            Some((from, to)) => self.format_move(game, from, to),
+            None => iccs.to_string(),
+        }
+    }
+
+    /// Format a line of ICCS moves starting from `game`
+    ///
+    /// Each move is played before formatting the next one, so pieces are
+    /// named from the right position. Once a move can't be played, the
+    /// rest of the line is returned as given.
+    pub fn format_line(&self, game: &Game, line: &[String]) -> Vec<String> {
+        let mut replay = game.clone();
+        let mut playable = true;
+        line.iter()
+            .map(|mv| {
+                if !playable {
+                    return mv.clone();
+                }
+                let Some((from, to)) = iccs_to_move(mv) else {
+                    playable = false;
+                    return mv.clone();
+                };
+                let text = self.format_move(&replay, from, to);
+                playable = replay.make_move(from, to).is_ok();
+                text
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iccs_style_is_plain() {
+        let game = Game::new();
+        let formatter = MoveFormatter::new(MoveStyle::Iccs);
+        assert_eq!(formatter.format_iccs(&game, "h7e7"), "h7e7");
+    }
+
+    #[test]
+    fn test_line_names_black_moves() {
+        let game = Game::new();
+        let formatter = MoveFormatter::default();
+        let line = ["h7e7", "h0g2", "b9c7"].map(String::from);
+        assert_eq!(
+            formatter.format_line(&game, &line),
+            ["炮二平五 (h7e7)", "马8进7 (h0g2)", "马八进七 (b9c7)"]
+        );
+    }
+
+    #[test]
+    fn test_line_stops_at_unplayable_move() {
+        let game = Game::new();
+        let formatter = MoveFormatter::default();
+        // a0a5 is a Black chariot move with Red to play
+        let line = ["a0a5", "h7e7", "resign"].map(String::from);
+        assert_eq!(
+            formatter.format_line(&game, &line),
+            ["车1进5 (a0a5)", "h7e7", "resign"]
+        );
+    }
+}
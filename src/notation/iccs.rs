@@ -88,6 +88,25 @@ pub fn iccs_to_move(s: &str) -> Option<(Position, Position)> {
     Some((from, to))
 }
 
+/// Parse a line of ICCS moves, such as an engine's principal variation
+///
+/// Stops at the first move that isn't valid ICCS, since the moves after it
+/// can't be placed on the board.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::notation::iccs::iccs_line_to_moves;
+/// use cn_chess_tui::types::Position;
+///
+/// let line = ["h7e7".to_string(), "h0g2".to_string(), "zz".to_string(), "b9c7".to_string()];
+/// let moves = iccs_line_to_moves(&line);
+/// assert_eq!(moves.len(), 2);
+/// assert_eq!(moves[1], (Position::from_xy(7, 0), Position::from_xy(6, 2)));
+/// ```
+pub fn iccs_line_to_moves(line: &[String]) -> Vec<(Position, Position)> {
+    line.iter().map_while(|mv| iccs_to_move(mv)).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
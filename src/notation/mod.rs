@@ -6,6 +6,7 @@
 //! - WXF: World XiangQi Federation format (e.g., "C2.5")
 
 pub mod chinese;
+pub mod format;
 pub mod iccs;
 pub mod iccs_parse;
 pub mod wxf;
@@ -14,16 +15,20 @@ pub mod wxf;
 // These are public APIs - allow unused_imports for external use
 #[allow(unused_imports)]
 pub use chinese::{
-    move_to_chinese, move_to_chinese_with_context, piece_to_chinese, MovementDirection,
+    move_to_chinese, move_to_chinese_with_context, move_to_chinese_with_style, parse_chinese_move,
+    piece_to_chinese, MovementDirection, NumeralStyle,
 };
 
 // Re-export WXF notation functions
 // These are public APIs - allow unused_imports for external use
 #[allow(unused_imports)]
 pub use wxf::{
-    direction_to_wxf, move_to_wxf, parse_wxf_move, piece_to_wxf_letter, wxf_letter_to_piece_type,
-    wxf_symbol_to_direction,
+    direction_to_wxf, move_to_wxf, parse_wxf_move, piece_to_wxf_letter, resolve_wxf_move,
+    wxf_letter_to_piece_type, wxf_symbol_to_direction,
 };
 
 // Re-export ICCS parse function
 pub use iccs_parse::parse_iccs_move;
+
+// Reading a move in whichever notation it is typed in
+pub use format::{parse_any, ParseMoveError};
@@ -12,7 +12,8 @@
 //! - For forward/backward moves: destination is number of steps (e.g., H2+3)
 
 use super::chinese::{get_movement_direction, position_to_file_number, MovementDirection};
-use crate::types::{Piece, PieceType, Position};
+use crate::board::Board;
+use crate::types::{Color, Piece, PieceType, Position};
 
 /// Convert a piece type to its WXF letter representation
 ///
@@ -122,10 +123,10 @@ pub fn wxf_symbol_to_direction(symbol: &str) -> Option<MovementDirection> {
 /// let to = Position::from_xy(4, 7);   // File 5 for Red (9-4=5)
 /// assert_eq!(move_to_wxf(piece, from, to), "C2.5");
 ///
-/// // H2+3: Horse from file 2 forward 3 steps
+/// // H2+3: Horse from file 2 forward to file 3
 /// let piece = Piece::new(PieceType::Horse, Color::Red);
 /// let from = Position::from_xy(7, 9); // File 2
-/// let to = Position::from_xy(7, 6);   // Forward 3 steps
+/// let to = Position::from_xy(6, 7);   // File 3
 /// assert_eq!(move_to_wxf(piece, from, to), "H2+3");
 ///
 /// // C5-2: Cannon from file 5 backward 2 steps
@@ -141,12 +142,12 @@ pub fn move_to_wxf(piece: Piece, from: Position, to: Position) -> String {
     let direction = get_movement_direction(from, to, piece.color);
     let dir_symbol = direction_to_wxf(direction);
 
-    let destination = if direction == MovementDirection::Horizontal {
-        // For horizontal moves, use destination file number
-        position_to_file_number(to, piece.color)
-    } else {
-        // For forward/backward moves, use number of steps
+    let destination = if from.x == to.x {
+        // Moves along a file give the number of steps
         from.y.abs_diff(to.y)
+    } else {
+        // Any other move, including a horse's, gives the destination file
+        position_to_file_number(to, piece.color)
     };
 
     format!("{}{}{}{}", piece_letter, from_file, dir_symbol, destination)
@@ -221,6 +222,61 @@ pub fn parse_wxf_move(s: &str) -> Option<(PieceType, usize, MovementDirection, u
     Some((piece_type, from_file, direction, destination))
 }
 
+/// Parse a WXF move against the current board
+///
+/// The piece letter may be upper or lower case. As in standard WXF, a move
+/// along a file gives the number of steps and any other move gives the
+/// destination file, so a horse is written "H2+3". The notation is matched
+/// against the legal moves of `color`; `None` is returned if it is
+/// malformed, matches no legal move, or is ambiguous.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::{
+///     board::Board,
+///     notation::wxf::resolve_wxf_move,
+///     types::{Color, Position},
+/// };
+///
+/// let board = Board::new();
+/// assert_eq!(
+///     resolve_wxf_move(&board, Color::Red, "C2.5"),
+///     Some((Position::from_xy(7, 7), Position::from_xy(4, 7)))
+/// );
+/// assert_eq!(
+///     resolve_wxf_move(&board, Color::Black, "h8+7"),
+///     Some((Position::from_xy(7, 0), Position::from_xy(6, 2)))
+/// );
+/// ```
+pub fn resolve_wxf_move(
+    board: &Board,
+    color: Color,
+    notation: &str,
+) -> Option<(Position, Position)> {
+    let (piece_type, file, direction, destination) =
+        parse_wxf_move(&notation.trim().to_ascii_uppercase())?;
+
+    let matches: Vec<(Position, Position)> = board
+        .legal_moves(color)
+        .into_iter()
+        .filter(|&(from, to)| {
+            board.get(from).map(|p| p.piece_type) == Some(piece_type)
+                && position_to_file_number(from, color) == file
+                && get_movement_direction(from, to, color) == direction
+                && if from.x == to.x {
+                    from.y.abs_diff(to.y) == destination
+                } else {
+                    position_to_file_number(to, color) == destination
+                }
+        })
+        .collect();
+
+    match matches.as_slice() {
+        [mv] => Some(*mv),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -412,4 +468,23 @@ mod tests {
             Some((PieceType::Cannon, 5, MovementDirection::Backward, 2))
         );
     }
+
+    #[test]
+    fn test_resolve_wxf_move() {
+        let board = Board::new();
+        // Chariot up one, soldier forward
+        assert_eq!(
+            resolve_wxf_move(&board, Color::Red, "R1+1"),
+            Some((Position::from_xy(8, 9), Position::from_xy(8, 8)))
+        );
+        assert_eq!(
+            resolve_wxf_move(&board, Color::Black, "p3+1"),
+            Some((Position::from_xy(2, 3), Position::from_xy(2, 4)))
+        );
+        // A horse names its destination file, not a step count
+        assert_eq!(resolve_wxf_move(&board, Color::Red, "H2+2"), None);
+        // No piece to move, and not WXF at all
+        assert_eq!(resolve_wxf_move(&board, Color::Red, "C3.5"), None);
+        assert_eq!(resolve_wxf_move(&board, Color::Red, "h7e7"), None);
+    }
 }
@@ -0,0 +1,363 @@
+//! Online client: lobby state and move relay over a [`Transport`]
+
+use crate::game::Game;
+use crate::notation::iccs::{iccs_to_move, move_to_iccs};
+use crate::online::protocol::{ClientMessage, GameInfo, Seek, ServerMessage};
+use crate::types::{Color, Position};
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A connection carrying one text message at a time
+pub trait Transport {
+    fn send(&mut self, text: &str) -> io::Result<()>;
+
+    /// Take the next message if one has arrived, without waiting
+    fn try_recv(&mut self) -> io::Result<Option<String>>;
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn send(&mut self, text: &str) -> io::Result<()> {
+        (**self).send(text)
+    }
+
+    fn try_recv(&mut self) -> io::Result<Option<String>> {
+        (**self).try_recv()
+    }
+}
+
+/// Error type for online play
+#[derive(Debug)]
+pub enum OnlineError {
+    Io(io::Error),
+    /// The server sent something that is not a protocol message
+    InvalidMessage(String),
+    /// The server refused a request
+    Refused(String),
+    /// A move was sent with no game in progress
+    NoGame,
+    Timeout,
+}
+
+impl std::fmt::Display for OnlineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OnlineError::Io(e) => write!(f, "Connection error: {}", e),
+            OnlineError::InvalidMessage(msg) => write!(f, "Invalid message from server: {}", msg),
+            OnlineError::Refused(msg) => write!(f, "Server refused: {}", msg),
+            OnlineError::NoGame => write!(f, "No online game in progress"),
+            OnlineError::Timeout => write!(f, "Server did not answer in time"),
+        }
+    }
+}
+
+impl std::error::Error for OnlineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OnlineError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for OnlineError {
+    fn from(e: io::Error) -> Self {
+        OnlineError::Io(e)
+    }
+}
+
+/// A game being played on the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnlineGame {
+    pub id: u64,
+    /// Side played here
+    pub color: Color,
+    pub opponent: String,
+    /// Start position
+    pub fen: String,
+}
+
+/// A game followed as a spectator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedGame {
+    pub id: u64,
+    pub red: String,
+    pub black: String,
+    /// Start position
+    pub fen: String,
+    /// Moves played before watching began
+    pub moves: Vec<(Position, Position)>,
+}
+
+impl WatchedGame {
+    /// The game as it stood when watching began
+    pub fn replay(&self) -> Result<Game, OnlineError> {
+        let mut game = Game::from_fen(&self.fen)
+            .map_err(|e| OnlineError::InvalidMessage(format!("bad position: {}", e)))?;
+        for &(from, to) in &self.moves {
+            game.make_move(from, to).map_err(|e| {
+                OnlineError::InvalidMessage(format!("bad move {}: {}", move_to_iccs(from, to), e))
+            })?;
+        }
+        Ok(game)
+    }
+}
+
+/// Something the server told the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OnlineEvent {
+    LoggedIn(String),
+    Seeks(Vec<Seek>),
+    GameStarted(OnlineGame),
+    OpponentMoved {
+        from: Position,
+        to: Position,
+    },
+    /// Games in progress, from the last listing
+    Games(Vec<GameInfo>),
+    Watching(WatchedGame),
+    /// A move played in the watched game, by either side
+    Moved {
+        from: Position,
+        to: Position,
+    },
+    GameEnded {
+        result: String,
+        reason: Option<String>,
+    },
+    /// The server refused a request
+    ServerError(String),
+}
+
+/// Client for an online xiangqi server
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::online::{OnlineClient, OnlineEvent, Transport};
+/// use std::collections::VecDeque;
+///
+/// #[derive(Default)]
+/// struct Script {
+///     incoming: VecDeque<String>,
+///     sent: Vec<String>,
+/// }
+///
+/// impl Transport for Script {
+///     fn send(&mut self, text: &str) -> std::io::Result<()> {
+///         self.sent.push(text.to_string());
+///         Ok(())
+///     }
+///     fn try_recv(&mut self) -> std::io::Result<Option<String>> {
+///         Ok(self.incoming.pop_front())
+///     }
+/// }
+///
+/// let mut client = OnlineClient::new(Script::default());
+/// client.login("alice", None).unwrap();
+/// client
+///     .transport_mut()
+///     .incoming
+///     .push_back(r#"{"type":"welcome","name":"alice"}"#.to_string());
+/// assert_eq!(client.poll().unwrap(), [OnlineEvent::LoggedIn("alice".to_string())]);
+/// assert_eq!(client.transport_mut().sent, [r#"{"type":"login","name":"alice"}"#]);
+/// ```
+pub struct OnlineClient<T: Transport> {
+    transport: T,
+    name: Option<String>,
+    seeks: Vec<Seek>,
+    game: Option<OnlineGame>,
+    watching: Option<WatchedGame>,
+}
+
+impl<T: Transport> OnlineClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            name: None,
+            seeks: Vec::new(),
+            game: None,
+            watching: None,
+        }
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Name the server accepted at login
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Seeks from the last listing
+    pub fn seeks(&self) -> &[Seek] {
+        &self.seeks
+    }
+
+    /// The game in progress, if any
+    pub fn game(&self) -> Option<&OnlineGame> {
+        self.game.as_ref()
+    }
+
+    /// The game being watched, as it stood when watching began
+    pub fn watching(&self) -> Option<&WatchedGame> {
+        self.watching.as_ref()
+    }
+
+    fn send(&mut self, message: &ClientMessage) -> Result<(), OnlineError> {
+        let text = serde_json::to_string(message)
+            .map_err(|e| OnlineError::InvalidMessage(e.to_string()))?;
+        self.transport.send(&text)?;
+        Ok(())
+    }
+
+    pub fn login(&mut self, name: &str, token: Option<&str>) -> Result<(), OnlineError> {
+        self.send(&ClientMessage::Login {
+            name: name.to_string(),
+            token: token.map(String::from),
+        })
+    }
+
+    pub fn list_seeks(&mut self) -> Result<(), OnlineError> {
+        self.send(&ClientMessage::ListSeeks)
+    }
+
+    /// Wait in the lobby for an opponent, playing `color` (None for either)
+    pub fn seek(&mut self, color: Option<Color>) -> Result<(), OnlineError> {
+        self.send(&ClientMessage::Seek { color })
+    }
+
+    pub fn accept(&mut self, seek: u64) -> Result<(), OnlineError> {
+        self.send(&ClientMessage::Accept { seek })
+    }
+
+    /// Send a move played here in the current game
+    pub fn send_move(&mut self, from: Position, to: Position) -> Result<(), OnlineError> {
+        let game = self.game.as_ref().ok_or(OnlineError::NoGame)?.id;
+        self.send(&ClientMessage::Move {
+            game,
+            mv: move_to_iccs(from, to),
+        })
+    }
+
+    pub fn resign(&mut self) -> Result<(), OnlineError> {
+        let game = self.game.as_ref().ok_or(OnlineError::NoGame)?.id;
+        self.send(&ClientMessage::Resign { game })
+    }
+
+    pub fn list_games(&mut self) -> Result<(), OnlineError> {
+        self.send(&ClientMessage::ListGames)
+    }
+
+    /// Follow a game in progress without playing in it
+    pub fn watch(&mut self, game: u64) -> Result<(), OnlineError> {
+        self.send(&ClientMessage::Watch { game })
+    }
+
+    /// Read every message that has arrived, without waiting
+    ///
+    /// Messages about other games are dropped.
+    pub fn poll(&mut self) -> Result<Vec<OnlineEvent>, OnlineError> {
+        let mut events = Vec::new();
+        while let Some(text) = self.transport.try_recv()? {
+            let message: ServerMessage = serde_json::from_str(&text)
+                .map_err(|e| OnlineError::InvalidMessage(format!("{}: {}", e, text)))?;
+            events.extend(self.handle(message)?);
+        }
+        Ok(events)
+    }
+
+    /// Poll until at least one event arrives or `timeout` passes
+    pub fn wait(&mut self, timeout: Duration) -> Result<Vec<OnlineEvent>, OnlineError> {
+        let start = Instant::now();
+        loop {
+            let events = self.poll()?;
+            if !events.is_empty() {
+                return Ok(events);
+            }
+            if start.elapsed() >= timeout {
+                return Err(OnlineError::Timeout);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    fn handle(&mut self, message: ServerMessage) -> Result<Option<OnlineEvent>, OnlineError> {
+        let current = self.game.as_ref().map(|g| g.id);
+        let watched = self.watching.as_ref().map(|g| g.id);
+        let event = match message {
+            ServerMessage::Welcome { name } => {
+                self.name = Some(name.clone());
+                OnlineEvent::LoggedIn(name)
+            }
+            ServerMessage::Seeks { seeks } => {
+                self.seeks = seeks.clone();
+                OnlineEvent::Seeks(seeks)
+            }
+            ServerMessage::GameStart {
+                game,
+                color,
+                opponent,
+                fen,
+            } => {
+                let game = OnlineGame {
+                    id: game,
+                    color,
+                    opponent,
+                    fen,
+                };
+                self.game = Some(game.clone());
+                OnlineEvent::GameStarted(game)
+            }
+            ServerMessage::Games { games } => OnlineEvent::Games(games),
+            ServerMessage::Spectate {
+                game,
+                red,
+                black,
+                fen,
+                moves,
+            } => {
+                let moves = moves
+                    .iter()
+                    .map(|mv| parse_move(mv))
+                    .collect::<Result<_, _>>()?;
+                let game = WatchedGame {
+                    id: game,
+                    red,
+                    black,
+                    fen,
+                    moves,
+                };
+                self.watching = Some(game.clone());
+                OnlineEvent::Watching(game)
+            }
+            ServerMessage::Move { game, mv } if Some(game) == current => {
+                let (from, to) = parse_move(&mv)?;
+                OnlineEvent::OpponentMoved { from, to }
+            }
+            ServerMessage::Move { game, mv } if Some(game) == watched => {
+                let (from, to) = parse_move(&mv)?;
+                OnlineEvent::Moved { from, to }
+            }
+            ServerMessage::GameEnd {
+                game,
+                result,
+                reason,
+            } if Some(game) == current || Some(game) == watched => {
+                if Some(game) == current {
+                    self.game = None;
+                } else {
+                    self.watching = None;
+                }
+                OnlineEvent::GameEnded { result, reason }
+            }
+            ServerMessage::Move { .. } | ServerMessage::GameEnd { .. } => return Ok(None),
+            ServerMessage::Error { message } => OnlineEvent::ServerError(message),
+        };
+        Ok(Some(event))
+    }
+}
+
+fn parse_move(mv: &str) -> Result<(Position, Position), OnlineError> {
+    iccs_to_move(mv).ok_or_else(|| OnlineError::InvalidMessage(format!("bad move {}", mv)))
+}
@@ -0,0 +1,203 @@
+//! Text lobby: log in and pick a game before play starts, or pick one to
+//! watch
+
+use crate::online::client::{
+    OnlineClient, OnlineError, OnlineEvent, OnlineGame, Transport, WatchedGame,
+};
+use crate::online::protocol::{GameInfo, Seek};
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+/// How long to wait for the server to answer a login or listing
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(10);
+
+const HELP: &str = "Type a number to accept a game, s to seek one, r to refresh or q to quit";
+
+/// Log in as `name` and let the user accept or post a seek
+///
+/// Returns the game once the server starts it, or None if the user quits.
+pub fn run<T: Transport, R: BufRead, W: Write>(
+    client: &mut OnlineClient<T>,
+    name: &str,
+    input: R,
+    output: &mut W,
+) -> Result<Option<OnlineGame>, OnlineError> {
+    client.login(name, None)?;
+    let name = wait_for(client, Some(ANSWER_TIMEOUT), |event| match event {
+        OnlineEvent::LoggedIn(name) => Some(Ok(name)),
+        OnlineEvent::ServerError(message) => Some(Err(message)),
+        _ => None,
+    })?
+    .map_err(OnlineError::Refused)?;
+    writeln!(output, "Logged in as {}", name)?;
+
+    refresh(client, output)?;
+    for line in input.lines() {
+        let line = line?;
+        let choice = line.trim();
+        let request = match choice {
+            "q" | "quit" => return Ok(None),
+            "r" | "" => {
+                refresh(client, output)?;
+                continue;
+            }
+            "s" => {
+                client.seek(None)?;
+                writeln!(output, "Waiting for an opponent...")?;
+                true
+            }
+            _ => match choice.parse::<usize>().ok().and_then(|n| n.checked_sub(1)) {
+                Some(i) if i < client.seeks().len() => {
+                    let seek = client.seeks()[i].id;
+                    client.accept(seek)?;
+                    true
+                }
+                _ => {
+                    writeln!(output, "{}", HELP)?;
+                    false
+                }
+            },
+        };
+        if request {
+            match wait_for_game(client)? {
+                Ok(game) => return Ok(Some(game)),
+                Err(message) => {
+                    writeln!(output, "Error: {}", message)?;
+                    refresh(client, output)?;
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+const SPECTATE_HELP: &str = "Type a number to watch a game, r to refresh or q to quit";
+
+/// List the games in progress and let the user pick one to watch
+///
+/// Returns the game as it stands once the server has sent it, or None if
+/// the user quits.
+pub fn spectate<T: Transport, R: BufRead, W: Write>(
+    client: &mut OnlineClient<T>,
+    input: R,
+    output: &mut W,
+) -> Result<Option<WatchedGame>, OnlineError> {
+    let mut games = refresh_games(client, output)?;
+    for line in input.lines() {
+        let line = line?;
+        let choice = line.trim();
+        let game = match choice {
+            "q" | "quit" => return Ok(None),
+            "r" | "" => {
+                games = refresh_games(client, output)?;
+                continue;
+            }
+            _ => match choice.parse::<usize>().ok().and_then(|n| n.checked_sub(1)) {
+                Some(i) if i < games.len() => games[i].id,
+                _ => {
+                    writeln!(output, "{}", SPECTATE_HELP)?;
+                    continue;
+                }
+            },
+        };
+        client.watch(game)?;
+        let answer = wait_for(client, Some(ANSWER_TIMEOUT), |event| match event {
+            OnlineEvent::Watching(game) => Some(Ok(game)),
+            OnlineEvent::ServerError(message) => Some(Err(message)),
+            _ => None,
+        })?;
+        match answer {
+            Ok(game) => return Ok(Some(game)),
+            Err(message) => {
+                writeln!(output, "Error: {}", message)?;
+                games = refresh_games(client, output)?;
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Poll until `pick` accepts an event
+///
+/// Waits as long as it takes when `timeout` is None.
+fn wait_for<T: Transport, U>(
+    client: &mut OnlineClient<T>,
+    timeout: Option<Duration>,
+    mut pick: impl FnMut(OnlineEvent) -> Option<U>,
+) -> Result<U, OnlineError> {
+    loop {
+        let events = match client.wait(timeout.unwrap_or(Duration::from_secs(1))) {
+            Err(OnlineError::Timeout) if timeout.is_none() => continue,
+            result => result?,
+        };
+        if let Some(answer) = events.into_iter().find_map(&mut pick) {
+            return Ok(answer);
+        }
+    }
+}
+
+/// Wait for a game to start, or for the server to refuse
+fn wait_for_game<T: Transport>(
+    client: &mut OnlineClient<T>,
+) -> Result<Result<OnlineGame, String>, OnlineError> {
+    wait_for(client, None, |event| match event {
+        OnlineEvent::GameStarted(game) => Some(Ok(game)),
+        OnlineEvent::ServerError(message) => Some(Err(message)),
+        _ => None,
+    })
+}
+
+fn refresh<T: Transport, W: Write>(
+    client: &mut OnlineClient<T>,
+    output: &mut W,
+) -> Result<(), OnlineError> {
+    client.list_seeks()?;
+    let seeks = wait_for(client, Some(ANSWER_TIMEOUT), |event| match event {
+        OnlineEvent::Seeks(seeks) => Some(Ok(seeks)),
+        OnlineEvent::ServerError(message) => Some(Err(message)),
+        _ => None,
+    })?
+    .map_err(OnlineError::Refused)?;
+    write_seeks(&seeks, output)?;
+    Ok(())
+}
+
+fn write_seeks<W: Write>(seeks: &[Seek], output: &mut W) -> std::io::Result<()> {
+    if seeks.is_empty() {
+        writeln!(output, "No one is seeking a game")?;
+    }
+    for (i, seek) in seeks.iter().enumerate() {
+        match seek.color {
+            Some(color) => writeln!(output, "{}. {} (plays {})", i + 1, seek.player, color)?,
+            None => writeln!(output, "{}. {}", i + 1, seek.player)?,
+        }
+    }
+    writeln!(output, "{}", HELP)
+}
+
+fn refresh_games<T: Transport, W: Write>(
+    client: &mut OnlineClient<T>,
+    output: &mut W,
+) -> Result<Vec<GameInfo>, OnlineError> {
+    client.list_games()?;
+    let games = wait_for(client, Some(ANSWER_TIMEOUT), |event| match event {
+        OnlineEvent::Games(games) => Some(Ok(games)),
+        OnlineEvent::ServerError(message) => Some(Err(message)),
+        _ => None,
+    })?
+    .map_err(OnlineError::Refused)?;
+    if games.is_empty() {
+        writeln!(output, "No games in progress")?;
+    }
+    for (i, game) in games.iter().enumerate() {
+        writeln!(
+            output,
+            "{}. {} (Red) vs {} (Black)",
+            i + 1,
+            game.red,
+            game.black
+        )?;
+    }
+    writeln!(output, "{}", SPECTATE_HELP)?;
+    Ok(games)
+}
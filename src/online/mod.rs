@@ -0,0 +1,45 @@
+//! Playing on an online xiangqi server
+//!
+//! The client logs into a lobby, lists the games players are seeking, posts
+//! or accepts one, and relays moves between the server and the local
+//! [`GameController`](crate::GameController). Messages are JSON objects, one
+//! per WebSocket text frame, tagged by a `type` field; moves are in ICCS
+//! (`h7e7`). See [`protocol`] for every message.
+//!
+//! A session goes:
+//!
+//! ```text
+//! -> {"type":"login","name":"alice"}
+//! <- {"type":"welcome","name":"alice"}
+//! -> {"type":"list_seeks"}
+//! <- {"type":"seeks","seeks":[{"id":7,"player":"bob","color":"black"}]}
+//! -> {"type":"accept","seek":7}
+//! <- {"type":"game_start","game":3,"color":"red","opponent":"bob","fen":"rnbakabnr/..."}
+//! -> {"type":"move","game":3,"move":"h7e7"}
+//! <- {"type":"move","game":3,"move":"h0g2"}
+//! <- {"type":"game_end","game":3,"result":"1-0","reason":"resignation"}
+//! ```
+//!
+//! Spectators need not log in. They list the games in progress and watch
+//! one; the server answers with the moves played so far, then passes on
+//! every move and the result as they happen:
+//!
+//! ```text
+//! -> {"type":"list_games"}
+//! <- {"type":"games","games":[{"id":3,"red":"alice","black":"bob"}]}
+//! -> {"type":"watch","game":3}
+//! <- {"type":"spectate","game":3,"red":"alice","black":"bob","fen":"rnbakabnr/...","moves":["h7e7"]}
+//! <- {"type":"move","game":3,"move":"h0g2"}
+//! ```
+//!
+//! The client works over any [`Transport`]; the WebSocket one needs the
+//! `online` feature.
+
+pub mod client;
+pub mod lobby;
+pub mod protocol;
+#[cfg(feature = "online")]
+pub mod ws;
+
+pub use client::{OnlineClient, OnlineError, OnlineEvent, OnlineGame, Transport, WatchedGame};
+pub use protocol::{ClientMessage, GameInfo, Seek, ServerMessage};
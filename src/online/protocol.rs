@@ -0,0 +1,167 @@
+//! Messages exchanged with an online server
+
+use crate::types::Color;
+use serde::{Deserialize, Serialize};
+
+/// A game a player is waiting to start
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Seek {
+    pub id: u64,
+    pub player: String,
+    /// Side the seeking player wants, None for either
+    #[serde(default)]
+    pub color: Option<Color>,
+}
+
+/// A game in progress that can be watched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameInfo {
+    pub id: u64,
+    pub red: String,
+    pub black: String,
+}
+
+/// Messages sent to the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Login {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+    },
+    ListSeeks,
+    /// Wait for an opponent, playing `color` (None for either)
+    Seek {
+        #[serde(default)]
+        color: Option<Color>,
+    },
+    Accept {
+        seek: u64,
+    },
+    Move {
+        game: u64,
+        #[serde(rename = "move")]
+        mv: String,
+    },
+    Resign {
+        game: u64,
+    },
+    /// List the games in progress; allowed without logging in
+    ListGames,
+    /// Follow a game as a spectator; allowed without logging in
+    Watch {
+        game: u64,
+    },
+}
+
+/// Messages received from the server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome {
+        name: String,
+    },
+    Seeks {
+        seeks: Vec<Seek>,
+    },
+    /// A game started; `color` is the side the client plays
+    GameStart {
+        game: u64,
+        color: Color,
+        opponent: String,
+        fen: String,
+    },
+    Games {
+        games: Vec<GameInfo>,
+    },
+    /// A game being watched, as it stands when watching begins: the start
+    /// position and every move since, in ICCS. Later moves arrive as
+    /// `move` messages.
+    Spectate {
+        game: u64,
+        red: String,
+        black: String,
+        fen: String,
+        moves: Vec<String>,
+    },
+    /// A move played in a game, by either side
+    Move {
+        game: u64,
+        #[serde(rename = "move")]
+        mv: String,
+    },
+    GameEnd {
+        game: u64,
+        /// PGN result: "1-0", "0-1" or "1/2-1/2"
+        result: String,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_messages_json() {
+        let login = ClientMessage::Login {
+            name: "alice".to_string(),
+            token: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&login).unwrap(),
+            r#"{"type":"login","name":"alice"}"#
+        );
+        let mv = ClientMessage::Move {
+            game: 3,
+            mv: "h7e7".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&mv).unwrap(),
+            r#"{"type":"move","game":3,"move":"h7e7"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ClientMessage::Seek { color: None }).unwrap(),
+            r#"{"type":"seek","color":null}"#
+        );
+    }
+
+    #[test]
+    fn test_server_messages_json() {
+        let seeks: ServerMessage = serde_json::from_str(
+            r#"{"type":"seeks","seeks":[{"id":7,"player":"bob","color":"black"},{"id":8,"player":"carol"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            seeks,
+            ServerMessage::Seeks {
+                seeks: vec![
+                    Seek {
+                        id: 7,
+                        player: "bob".to_string(),
+                        color: Some(Color::Black),
+                    },
+                    Seek {
+                        id: 8,
+                        player: "carol".to_string(),
+                        color: None,
+                    },
+                ]
+            }
+        );
+        let end: ServerMessage =
+            serde_json::from_str(r#"{"type":"game_end","game":3,"result":"1-0"}"#).unwrap();
+        assert!(matches!(end, ServerMessage::GameEnd { reason: None, .. }));
+
+        let sync: ServerMessage = serde_json::from_str(
+            r#"{"type":"spectate","game":3,"red":"alice","black":"bob","fen":"9/9/9/9/9/9/9/9/9/9 w - - 0 1","moves":["h7e7","h0g2"]}"#,
+        )
+        .unwrap();
+        assert!(matches!(sync, ServerMessage::Spectate { ref moves, .. } if moves.len() == 2));
+    }
+}
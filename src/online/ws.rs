@@ -0,0 +1,68 @@
+//! WebSocket transport for the online client
+//!
+//! Plain `ws://` URLs only; put a TLS proxy in front of a server reached
+//! over the internet.
+
+use crate::online::client::{OnlineError, Transport};
+use std::io;
+use std::net::TcpStream;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Error, Message, WebSocket};
+
+pub struct WebSocketTransport {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+impl WebSocketTransport {
+    /// Connect to a server, e.g. `ws://localhost:9000/lobby`
+    pub fn connect(url: &str) -> Result<Self, OnlineError> {
+        let (socket, _) = tungstenite::connect(url).map_err(into_io)?;
+        // Reads must not block the UI loop
+        if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+            stream.set_nonblocking(true)?;
+        }
+        Ok(Self { socket })
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn send(&mut self, text: &str) -> io::Result<()> {
+        match self.socket.send(Message::Text(text.to_string())) {
+            // Queued; flushed by a later read
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            result => result.map_err(into_io),
+        }
+    }
+
+    fn try_recv(&mut self) -> io::Result<Option<String>> {
+        loop {
+            match self.socket.read() {
+                Ok(Message::Text(text)) => return Ok(Some(text)),
+                Ok(Message::Close(_)) | Err(Error::ConnectionClosed) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "server closed the connection",
+                    ))
+                }
+                // Pings are answered by tungstenite
+                Ok(_) => continue,
+                Err(Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => {
+                    match self.socket.flush() {
+                        Err(Error::Io(e)) if e.kind() != io::ErrorKind::WouldBlock => {
+                            return Err(e)
+                        }
+                        _ => return Ok(None),
+                    }
+                }
+                Err(e) => return Err(into_io(e)),
+            }
+        }
+    }
+}
+
+fn into_io(e: Error) -> io::Error {
+    match e {
+        Error::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
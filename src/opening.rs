@@ -0,0 +1,261 @@
+//! Named openings
+//!
+//! A small built-in book of common openings, each keyed by the moves that
+//! define it. [`OpeningBook::lookup`] names the opening a game is in, and
+//! [`OpeningBook::continuations`] gives the book moves from a position so
+//! the built-in AI can vary its first moves the way players do.
+//!
+//! Each opening also carries its classification code in the scheme Chinese
+//! databases use, the Xiangqi counterpart of chess ECO codes: a letter for
+//! the family and two digits for the line within it.
+//!
+//! | Codes | Family |
+//! |-------|--------|
+//! | A00–A99 | Openings other than the central cannon and the pawn opening (飞相局, 起马局, ...) |
+//! | B00–B99 | Central cannon against defences other than the screen horses (中炮) |
+//! | C00–C99 | Central cannon against the screen horses (中炮对屏风马) |
+//! | D00–D99 | Same- and opposite-direction cannons (顺炮, 列炮) |
+//! | E00–E99 | The pawn opening (仙人指路) |
+
+use crate::board::Board;
+use crate::game::{Game, Move};
+use crate::notation::chinese::parse_chinese_move;
+use crate::types::Color;
+use std::sync::OnceLock;
+
+/// Built-in openings as (code, name, moves in Chinese notation)
+///
+/// Longer lines refine the shorter ones they start with, so a game is named
+/// by the most specific line it follows.
+const BUILTIN: &[(&str, &str, &str)] = &[
+    ("B00", "中炮", "炮二平五"),
+    ("D00", "顺炮", "炮二平五 炮8平5"),
+    (
+        "D10",
+        "顺炮直车",
+        "炮二平五 炮8平5 马二进三 马8进7 车一平二",
+    ),
+    ("D50", "列炮", "炮二平五 炮2平5"),
+    ("C00", "中炮对屏风马", "炮二平五 马8进7 马二进三 马2进3"),
+    (
+        "C20",
+        "中炮过河车对屏风马",
+        "炮二平五 马8进7 马二进三 车9平8 车一平二 马2进3 车二进六",
+    ),
+    (
+        "C60",
+        "五七炮对屏风马",
+        "炮二平五 马8进7 马二进三 车9平8 车一平二 马2进3 马八进九 卒7进1 炮八平七",
+    ),
+    (
+        "B30",
+        "中炮对反宫马",
+        "炮二平五 马2进3 马二进三 炮8平6 马八进七 马8进7",
+    ),
+    ("B20", "中炮对三步虎", "炮二平五 马8进7 马二进三 炮8平9"),
+    ("A10", "飞相局", "相三进五"),
+    ("A30", "飞相对左中炮", "相三进五 炮8平5"),
+    ("E00", "仙人指路", "兵七进一"),
+    ("E40", "对兵局", "兵七进一 卒7进1"),
+    ("E10", "仙人指路对卒底炮", "兵七进一 炮2平3"),
+    ("A40", "起马局", "马二进三"),
+    ("A60", "过宫炮", "炮二平六"),
+    ("A50", "士角炮", "炮二平四"),
+];
+
+/// An opening and the moves that define it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opening {
+    /// Classification code, e.g. `C00`
+    pub eco: &'static str,
+    pub name: &'static str,
+    /// Moves from the initial position
+    pub moves: Vec<Move>,
+}
+
+/// A set of named openings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpeningBook {
+    openings: Vec<Opening>,
+}
+
+impl OpeningBook {
+    /// The built-in book, built on first use
+    pub fn builtin() -> &'static OpeningBook {
+        static BOOK: OnceLock<OpeningBook> = OnceLock::new();
+        BOOK.get_or_init(|| {
+            let mut book = OpeningBook::default();
+            for &(eco, name, line) in BUILTIN {
+                book.add(eco, name, line)
+                    .unwrap_or_else(|| panic!("invalid book line for {}", name));
+            }
+            book
+        })
+    }
+
+    /// Add an opening, with its classification code, from moves in Chinese
+    /// notation
+    ///
+    /// Returns None, leaving the book unchanged, if a move can't be played
+    /// from the initial position.
+    pub fn add(&mut self, eco: &'static str, name: &'static str, line: &str) -> Option<()> {
+        let mut board = Board::new();
+        let mut side = Color::Red;
+        let mut moves = Vec::new();
+        for notation in line.split_whitespace() {
+            let (from, to) = parse_chinese_move(&board, side, notation)?;
+            board.move_piece(from, to);
+            moves.push(Move::new(from, to));
+            side = side.opponent();
+        }
+        self.openings.push(Opening { eco, name, moves });
+        Some(())
+    }
+
+    pub fn openings(&self) -> &[Opening] {
+        &self.openings
+    }
+
+    /// The opening played by a sequence of moves from the initial position
+    ///
+    /// Returns the longest opening whose moves start the sequence; moves
+    /// played after it leave the name unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::game::Move;
+    /// use cn_chess_tui::opening::OpeningBook;
+    /// use cn_chess_tui::types::Position;
+    ///
+    /// let book = OpeningBook::builtin();
+    /// let cannon = Move::new(Position::from_xy(7, 7), Position::from_xy(4, 7));
+    /// let opening = book.lookup(&[cannon]).unwrap();
+    /// assert_eq!((opening.eco, opening.name), ("B00", "中炮"));
+    /// assert!(book.lookup(&[]).is_none());
+    /// ```
+    pub fn lookup(&self, moves: &[Move]) -> Option<&Opening> {
+        self.openings
+            .iter()
+            .filter(|opening| moves.starts_with(&opening.moves))
+            .max_by_key(|opening| opening.moves.len())
+    }
+
+    /// The opening a game is in
+    ///
+    /// Games set up from another position are never in an opening.
+    pub fn identify(&self, game: &Game) -> Option<&Opening> {
+        if !game.starts_from_initial_position() {
+            return None;
+        }
+        self.lookup(&game.get_moves())
+    }
+
+    /// Book moves that continue a sequence, without duplicates
+    pub fn continuations(&self, moves: &[Move]) -> Vec<Move> {
+        let mut next = Vec::new();
+        for opening in &self.openings {
+            if opening.moves.len() > moves.len() && opening.moves.starts_with(moves) {
+                let mv = opening.moves[moves.len()];
+                if !next.contains(&mv) {
+                    next.push(mv);
+                }
+            }
+        }
+        next
+    }
+
+    /// Book moves for the side to move in a game
+    pub fn book_moves(&self, game: &Game) -> Vec<Move> {
+        if !game.starts_from_initial_position() {
+            return Vec::new();
+        }
+        self.continuations(&game.get_moves())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Position;
+
+    fn play(game: &mut Game, moves: &[Move]) {
+        for mv in moves {
+            game.make_move(mv.from, mv.to).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_builtin_lines_are_legal() {
+        let book = OpeningBook::builtin();
+        assert_eq!(book.openings().len(), BUILTIN.len());
+        for opening in book.openings() {
+            let mut game = Game::new();
+            play(&mut game, &opening.moves);
+            assert_eq!(book.identify(&game).unwrap().name, opening.name);
+        }
+    }
+
+    #[test]
+    fn test_lookup_names_the_longest_line() {
+        let book = OpeningBook::builtin();
+        let screen_horse = &book
+            .openings()
+            .iter()
+            .find(|o| o.name == "中炮过河车对屏风马")
+            .unwrap()
+            .moves;
+
+        assert_eq!(book.lookup(&screen_horse[..2]).unwrap().name, "中炮");
+        assert_eq!(book.lookup(&screen_horse[..4]).unwrap().name, "中炮");
+        assert_eq!(
+            book.lookup(screen_horse).unwrap().name,
+            "中炮过河车对屏风马"
+        );
+
+        // Leaving the book keeps the last name
+        let mut game = Game::new();
+        play(&mut game, screen_horse);
+        game.make_move(Position::from_xy(6, 3), Position::from_xy(6, 4))
+            .unwrap();
+        assert_eq!(book.identify(&game).unwrap().name, "中炮过河车对屏风马");
+    }
+
+    #[test]
+    fn test_continuations() {
+        let book = OpeningBook::builtin();
+        let first = book.continuations(&[]);
+        assert_eq!(first.len(), 6);
+
+        let cannon = Move::new(Position::from_xy(7, 7), Position::from_xy(4, 7));
+        let replies = book.continuations(&[cannon]);
+        assert!(replies.contains(&Move::new(Position::from_xy(7, 0), Position::from_xy(6, 2))));
+        assert!(replies.contains(&Move::new(Position::from_xy(7, 2), Position::from_xy(4, 2))));
+
+        // Out of book
+        let edge = Move::new(Position::from_xy(0, 9), Position::from_xy(0, 8));
+        assert!(book.continuations(&[edge]).is_empty());
+    }
+
+    #[test]
+    fn test_codes_follow_the_families() {
+        for opening in OpeningBook::builtin().openings() {
+            let family = match opening.name {
+                name if name.starts_with("顺炮") || name == "列炮" => 'D',
+                name if name.contains("屏风马") => 'C',
+                name if name.starts_with("中炮") => 'B',
+                name if name.contains("仙人指路") || name == "对兵局" => 'E',
+                _ => 'A',
+            };
+            let mut code = opening.eco.chars();
+            assert_eq!(code.next(), Some(family), "{}", opening.name);
+            assert_eq!(code.filter(char::is_ascii_digit).count(), 2);
+        }
+    }
+
+    #[test]
+    fn test_set_up_positions_have_no_opening() {
+        let game = Game::from_fen("4k4/9/9/9/9/9/9/9/9/4K4 w - - 0 1").unwrap();
+        assert!(OpeningBook::builtin().identify(&game).is_none());
+        assert!(OpeningBook::builtin().book_moves(&game).is_empty());
+    }
+}
@@ -0,0 +1,289 @@
+//! Where the program keeps its files
+//!
+//! Every file read or written outside the current directory is located
+//! through [`Paths`], so the layout is decided in one place:
+//!
+//! | Kind | Linux and other Unix | macOS | Windows |
+//! |------|----------------------|-------|---------|
+//! | config | `$XDG_CONFIG_HOME` or `~/.config` | `~/Library/Application Support` | `%APPDATA%` |
+//! | data | `$XDG_DATA_HOME` or `~/.local/share` | `~/Library/Application Support` | `%LOCALAPPDATA%` |
+//! | cache | `$XDG_CACHE_HOME` or `~/.cache` | `~/Library/Caches` | `%LOCALAPPDATA%` |
+//! | state | `$XDG_STATE_HOME` or `~/.local/state` | `~/Library/Application Support` | `%LOCALAPPDATA%` |
+//! | logs | `logs` in the state directory | `~/Library/Logs` | `logs` in the state directory |
+//!
+//! each followed by `cn_chess_tui`. The `XDG_*` variables are honored on
+//! every platform when set to an absolute path. Setting `CN_CHESS_TUI_HOME`
+//! puts everything under one directory instead, for portable or test
+//! installs.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Directory name used under each base directory
+pub const APP_NAME: &str = "cn_chess_tui";
+
+/// Environment variable that overrides every location
+pub const HOME_ENV: &str = "CN_CHESS_TUI_HOME";
+
+/// The program's directories
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::paths::Paths;
+/// use std::path::Path;
+///
+/// let paths = Paths::under(Path::new("/opt/chess"));
+/// assert_eq!(paths.config_file(), Path::new("/opt/chess/config.toml"));
+/// assert_eq!(paths.session_file(), Path::new("/opt/chess/state/session.json"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    /// Files the user edits: `config.toml`
+    pub config: PathBuf,
+    /// Files the user keeps: game archives
+    pub data: PathBuf,
+    /// Files that can be rebuilt at any time
+    pub cache: PathBuf,
+    /// Files the program writes for itself: recent files, saved session
+    pub state: PathBuf,
+    pub logs: PathBuf,
+    /// Where versions before this layout kept state files
+    legacy: Option<PathBuf>,
+}
+
+impl Paths {
+    /// Resolve the directories from the process environment
+    ///
+    /// Returns None when no home directory can be found.
+    pub fn resolve() -> Option<Self> {
+        Self::from_env(|key| std::env::var_os(key))
+    }
+
+    /// Resolve the directories from a given environment
+    pub fn from_env(env: impl Fn(&str) -> Option<OsString>) -> Option<Self> {
+        if let Some(root) = env(HOME_ENV).filter(|root| !root.is_empty()) {
+            return Some(Self::under(Path::new(&root)));
+        }
+
+        let xdg = |key: &str| {
+            env(key)
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+        };
+        let home = env("HOME")
+            .filter(|home| !home.is_empty())
+            .map(PathBuf::from)
+            .or_else(dirs::home_dir)?;
+        let defaults = PlatformDirs::new(&home);
+
+        let state_home = xdg("XDG_STATE_HOME");
+        let logs = match (&state_home, defaults.logs) {
+            (None, Some(logs)) => Some(logs.join(APP_NAME)),
+            _ => None,
+        };
+        let state = state_home.unwrap_or(defaults.state).join(APP_NAME);
+        let config = xdg("XDG_CONFIG_HOME")
+            .unwrap_or(defaults.config)
+            .join(APP_NAME);
+        Some(Self {
+            legacy: Some(config.clone()),
+            config,
+            data: xdg("XDG_DATA_HOME").unwrap_or(defaults.data).join(APP_NAME),
+            cache: xdg("XDG_CACHE_HOME")
+                .unwrap_or(defaults.cache)
+                .join(APP_NAME),
+            logs: logs.unwrap_or_else(|| state.join("logs")),
+            state,
+        })
+    }
+
+    /// Keep everything under one directory
+    pub fn under(root: &Path) -> Self {
+        Self {
+            config: root.to_path_buf(),
+            data: root.join("data"),
+            cache: root.join("cache"),
+            state: root.join("state"),
+            logs: root.join("logs"),
+            legacy: None,
+        }
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config.join("config.toml")
+    }
+
+    /// Recently opened files and other state kept between runs
+    pub fn state_file(&self) -> PathBuf {
+        self.state_path("state.toml")
+    }
+
+    /// The unfinished game saved on quit
+    pub fn session_file(&self) -> PathBuf {
+        self.state_path("session.json")
+    }
+
+    /// Copies of the turn files sent in correspondence games, to check
+    /// the replies against
+    pub fn turns_dir(&self) -> PathBuf {
+        self.state.join("turns")
+    }
+
+    /// Default directory for game archives
+    pub fn archive_dir(&self) -> PathBuf {
+        self.data.join("archives")
+    }
+
+    /// The results of the games played against the AI
+    pub fn stats_file(&self) -> PathBuf {
+        self.data.join("stats.toml")
+    }
+
+    /// A file in the state directory
+    ///
+    /// Older versions kept state next to `config.toml`; a file left there
+    /// is used until it is removed, so upgrading loses nothing.
+    fn state_path(&self, name: &str) -> PathBuf {
+        let path = self.state.join(name);
+        match &self.legacy {
+            Some(legacy) if !path.exists() && legacy.join(name).exists() => legacy.join(name),
+            _ => path,
+        }
+    }
+}
+
+/// Base directories when no `XDG_*` variable is set
+struct PlatformDirs {
+    config: PathBuf,
+    data: PathBuf,
+    cache: PathBuf,
+    state: PathBuf,
+    /// A separate base for logs; otherwise they go in the state directory
+    logs: Option<PathBuf>,
+}
+
+impl PlatformDirs {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn new(home: &Path) -> Self {
+        Self {
+            config: home.join(".config"),
+            data: home.join(".local").join("share"),
+            cache: home.join(".cache"),
+            state: home.join(".local").join("state"),
+            logs: None,
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    fn new(home: &Path) -> Self {
+        let library = home.join("Library");
+        let support = library.join("Application Support");
+        Self {
+            config: support.clone(),
+            data: support.clone(),
+            cache: library.join("Caches"),
+            state: support,
+            logs: Some(library.join("Logs")),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn new(home: &Path) -> Self {
+        let roaming = dirs::config_dir().unwrap_or_else(|| home.join("AppData").join("Roaming"));
+        let local = dirs::data_local_dir().unwrap_or_else(|| home.join("AppData").join("Local"));
+        Self {
+            config: roaming,
+            data: local.clone(),
+            cache: local.clone(),
+            state: local,
+            logs: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), OsString::from(v)))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_override_puts_everything_under_one_root() {
+        let paths = Paths::from_env(env(&[
+            (HOME_ENV, "/srv/chess"),
+            ("XDG_CONFIG_HOME", "/etc/xdg"),
+        ]))
+        .unwrap();
+        assert_eq!(paths, Paths::under(Path::new("/srv/chess")));
+        assert_eq!(paths.archive_dir(), Path::new("/srv/chess/data/archives"));
+        assert_eq!(paths.logs, Path::new("/srv/chess/logs"));
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn test_xdg_defaults_and_overrides() {
+        let paths = Paths::from_env(env(&[("HOME", "/home/ann")])).unwrap();
+        assert_eq!(
+            paths.config_file(),
+            Path::new("/home/ann/.config/cn_chess_tui/config.toml")
+        );
+        assert_eq!(paths.data, Path::new("/home/ann/.local/share/cn_chess_tui"));
+        assert_eq!(paths.cache, Path::new("/home/ann/.cache/cn_chess_tui"));
+        assert_eq!(
+            paths.state,
+            Path::new("/home/ann/.local/state/cn_chess_tui")
+        );
+        assert_eq!(
+            paths.logs,
+            Path::new("/home/ann/.local/state/cn_chess_tui/logs")
+        );
+
+        let paths = Paths::from_env(env(&[
+            ("HOME", "/home/ann"),
+            ("XDG_CONFIG_HOME", "/cfg"),
+            ("XDG_STATE_HOME", "/var/state"),
+            // Relative paths are ignored, as the XDG spec requires
+            ("XDG_CACHE_HOME", "cache"),
+            (HOME_ENV, ""),
+        ]))
+        .unwrap();
+        assert_eq!(paths.config, Path::new("/cfg/cn_chess_tui"));
+        assert_eq!(paths.state, Path::new("/var/state/cn_chess_tui"));
+        assert_eq!(paths.logs, Path::new("/var/state/cn_chess_tui/logs"));
+        assert_eq!(paths.cache, Path::new("/home/ann/.cache/cn_chess_tui"));
+    }
+
+    #[test]
+    fn test_state_files_fall_back_to_old_location() {
+        let home = TempDir::new().unwrap();
+        let config = home.path().join("config");
+        let state = home.path().join("state");
+        let paths = Paths::from_env(env(&[
+            ("HOME", home.path().to_str().unwrap()),
+            ("XDG_CONFIG_HOME", config.to_str().unwrap()),
+            ("XDG_STATE_HOME", state.to_str().unwrap()),
+        ]))
+        .unwrap();
+        let new = state.join(APP_NAME).join("session.json");
+        let old = config.join(APP_NAME).join("session.json");
+        assert_eq!(paths.session_file(), new);
+
+        std::fs::create_dir_all(old.parent().unwrap()).unwrap();
+        std::fs::write(&old, "{}").unwrap();
+        assert_eq!(paths.session_file(), old);
+        assert_eq!(paths.state_file(), state.join(APP_NAME).join("state.toml"));
+
+        // Once saved in the new place, the old file is ignored
+        std::fs::create_dir_all(new.parent().unwrap()).unwrap();
+        std::fs::write(&new, "{}").unwrap();
+        assert_eq!(paths.session_file(), new);
+    }
+}
@@ -0,0 +1,256 @@
+//! Save an unfinished game on quit and resume it on the next launch
+//!
+//! A FEN only records the current position, which loses the move history
+//! needed for undo, repetition and review. A [`Session`] stores the starting
+//! FEN plus every move in ICCS and replays them on load, so the resumed game
+//! is the same game: history, side to move and move counters included.
+//!
+//! Sessions are JSON files, by default `session.json` in the state directory
+//! (see [`paths`](crate::paths)).
+
+use crate::fen::FenError;
+use crate::game::{AiMode, Game};
+use crate::notation::iccs::iccs_to_move;
+use crate::paths::Paths;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Errors loading a session
+#[derive(Debug)]
+pub enum SessionError {
+    Io(std::io::Error),
+    /// The file is not a valid session
+    Parse(String),
+    /// The starting position is invalid
+    Fen(FenError),
+    /// A stored move can't be replayed; `ply` counts from 1
+    IllegalMove {
+        ply: usize,
+        mv: String,
+    },
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io(e) => write!(f, "{}", e),
+            SessionError::Parse(e) => write!(f, "Invalid session file: {}", e),
+            SessionError::Fen(e) => write!(f, "Invalid session position: {}", e),
+            SessionError::IllegalMove { ply, mv } => {
+                write!(f, "Session move {} ({}) is illegal", ply, mv)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<std::io::Error> for SessionError {
+    fn from(e: std::io::Error) -> Self {
+        SessionError::Io(e)
+    }
+}
+
+/// A game in progress, as saved on quit
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::persistence::Session;
+/// use cn_chess_tui::{AiMode, Game, Position};
+///
+/// let mut game = Game::new();
+/// game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7)).unwrap();
+///
+/// let session = Session::from_game(&game, AiMode::PlaysBlack);
+/// assert_eq!(session.moves, ["h7e7"]);
+///
+/// let resumed = session.to_game().unwrap();
+/// assert_eq!(resumed.to_fen(), game.to_fen());
+/// assert_eq!(resumed.get_moves().len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// FEN of the position the game started from
+    pub start_fen: String,
+    /// Moves played from the start, in ICCS
+    pub moves: Vec<String>,
+    #[serde(default = "default_ai_mode")]
+    pub ai_mode: AiMode,
+    /// Seconds since the Unix epoch when the session was saved
+    #[serde(default)]
+    pub saved_at: u64,
+}
+
+fn default_ai_mode() -> AiMode {
+    AiMode::Off
+}
+
+impl Session {
+    /// Capture a game and the AI mode it is played in
+    pub fn from_game(game: &Game, ai_mode: AiMode) -> Self {
+        let saved_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            start_fen: game.at_ply(0).to_fen(),
+            moves: game.get_moves_with_iccs(),
+            ai_mode,
+            saved_at,
+        }
+    }
+
+    /// Rebuild the game by replaying every move from the start position
+    pub fn to_game(&self) -> Result<Game, SessionError> {
+        let mut game = Game::from_fen(&self.start_fen).map_err(SessionError::Fen)?;
+        for (i, mv) in self.moves.iter().enumerate() {
+            let illegal = || SessionError::IllegalMove {
+                ply: i + 1,
+                mv: mv.clone(),
+            };
+            let (from, to) = iccs_to_move(mv).ok_or_else(illegal)?;
+            game.make_move(from, to).map_err(|_| illegal())?;
+        }
+        Ok(game)
+    }
+
+    /// Location of the session file, in the state directory
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Paths::resolve()?.session_file())
+    }
+
+    /// Load a session from a specific file
+    pub fn load_from(path: &Path) -> Result<Self, SessionError> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| SessionError::Parse(e.to_string()))
+    }
+
+    /// Save the session to a specific file, creating parent directories
+    pub fn save_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, contents)
+    }
+}
+
+/// Load the saved session, if there is one
+///
+/// Returns None when no session file exists.
+pub fn load_session() -> Option<Result<Session, SessionError>> {
+    let path = Session::default_path()?;
+    path.exists().then(|| Session::load_from(&path))
+}
+
+/// Save a game to the default session file
+///
+/// Games that are over or have no moves leave nothing to resume, so the
+/// session file is removed instead.
+pub fn save_session(game: &Game, ai_mode: AiMode) -> std::io::Result<()> {
+    let Some(path) = Session::default_path() else {
+        return Ok(());
+    };
+    if game.state().result().is_some() || game.get_moves().is_empty() {
+        return remove_file(&path);
+    }
+    Session::from_game(game, ai_mode).save_to(&path)
+}
+
+/// Delete the default session file
+pub fn clear_session() -> std::io::Result<()> {
+    match Session::default_path() {
+        Some(path) => remove_file(&path),
+        None => Ok(()),
+    }
+}
+
+/// Remove a file; a missing file is not an error
+fn remove_file(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Position;
+    use tempfile::TempDir;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for mv in moves {
+            let (from, to) = iccs_to_move(mv).unwrap();
+            game.make_move(from, to).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_round_trip_through_file() {
+        let mut game = Game::new();
+        play(&mut game, &["h7e7", "h0g2", "b9c7"]);
+        let session = Session::from_game(&game, AiMode::PlaysRed);
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("session.json");
+        session.save_to(&path).unwrap();
+        let loaded = Session::load_from(&path).unwrap();
+        assert_eq!(loaded, session);
+
+        let resumed = loaded.to_game().unwrap();
+        assert_eq!(resumed.get_moves_with_iccs(), game.get_moves_with_iccs());
+        assert_eq!(resumed.turn(), game.turn());
+        assert_eq!(resumed.to_fen(), game.to_fen());
+        assert_eq!(loaded.ai_mode, AiMode::PlaysRed);
+    }
+
+    #[test]
+    fn test_keeps_start_position_and_clock() {
+        let mut game = Game::from_fen("4k4/9/9/9/9/9/9/9/4R4/3K5 w - - 20 30").unwrap();
+        game.make_move(Position::from_xy(4, 8), Position::from_xy(4, 7))
+            .unwrap();
+        let session = Session::from_game(&game, AiMode::Off);
+        assert!(session
+            .start_fen
+            .starts_with("4k4/9/9/9/9/9/9/9/4R4/3K5 w - - 20"));
+
+        let mut resumed = session.to_game().unwrap();
+        assert_eq!(resumed.halfmove_clock(), 21);
+        assert!(resumed.undo_move());
+        assert_eq!(resumed.board(), game.at_ply(0).board());
+    }
+
+    #[test]
+    fn test_rejects_bad_sessions() {
+        let session = Session {
+            start_fen: Game::new().to_fen(),
+            moves: vec!["h7e7".to_string(), "h7e7".to_string()],
+            ai_mode: AiMode::Off,
+            saved_at: 0,
+        };
+        assert!(matches!(
+            session.to_game(),
+            Err(SessionError::IllegalMove { ply: 2, .. })
+        ));
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.json");
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            Session::load_from(&path),
+            Err(SessionError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_fields_default() {
+        let fen = Game::new().to_fen();
+        let json = format!(r#"{{"start_fen": "{}", "moves": []}}"#, fen);
+        let session: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(session.ai_mode, AiMode::Off);
+        assert_eq!(session.saved_at, 0);
+    }
+}
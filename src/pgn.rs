@@ -15,8 +15,50 @@
 //!
 //! h2e2 h9g7 h3g3 i9h9
 //! ```
+//!
+//! Comments are written in braces after a move, and variations (RAVs) in
+//! parentheses after the move they are an alternative to:
+//! `1. h2e2 (1. b2e2 b9c7) h9g7`.
 
+use crate::types::Color;
 use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead, Write};
+
+/// Maximum length of a movetext line in exported PGN
+pub const PGN_LINE_WIDTH: usize = 80;
+
+/// Writes space-separated words, breaking lines before they get too long
+struct LineWrapper<'a, W: Write> {
+    out: &'a mut W,
+    width: usize,
+    column: usize,
+}
+
+impl<'a, W: Write> LineWrapper<'a, W> {
+    fn new(out: &'a mut W, width: usize) -> Self {
+        Self {
+            out,
+            width,
+            column: 0,
+        }
+    }
+
+    fn word(&mut self, word: &str) -> io::Result<()> {
+        let len = word.chars().count();
+        if self.column > 0 {
+            if self.column + 1 + len > self.width {
+                writeln!(self.out)?;
+                self.column = 0;
+            } else {
+                write!(self.out, " ")?;
+                self.column += 1;
+            }
+        }
+        write!(self.out, "{}", word)?;
+        self.column += len;
+        Ok(())
+    }
+}
 
 /// A PGN tag pair in the format [key "value"]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -87,6 +129,13 @@ pub struct PgnMove {
     pub comment: Option<String>,
     /// Move number (for display purposes)
     pub move_number: Option<usize>,
+    /// The side that played the move, when it is not the side whose turn
+    /// alternation gives: the movetext marks it with `N.` before a move of
+    /// Red's or `N...` before one of Black's
+    pub side: Option<Color>,
+    /// Alternatives to this move, each a line of moves played instead of
+    /// it and possibly holding variations of its own
+    pub variations: Vec<Vec<PgnMove>>,
 }
 
 impl PgnMove {
@@ -96,6 +145,8 @@ impl PgnMove {
             notation: notation.into(),
             comment: None,
             move_number: None,
+            side: None,
+            variations: Vec::new(),
         }
     }
 
@@ -112,6 +163,70 @@ impl PgnMove {
         self.move_number = Some(number);
         self
     }
+
+    /// Set the side that played the move
+    pub fn with_side(mut self, side: Color) -> Self {
+        self.side = Some(side);
+        self
+    }
+}
+
+/// A move's thinking time as a `[%emt h:mm:ss]` command, the elapsed-time
+/// counterpart of `[%clk]`, to put in its comment
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::pgn::{move_time_command, split_move_time};
+///
+/// assert_eq!(move_time_command(65_400), "[%emt 0:01:05]");
+/// assert_eq!(
+///     split_move_time("[%emt 0:01:05] Central cannon"),
+///     (Some(65_000), Some("Central cannon".to_string()))
+/// );
+/// assert_eq!(split_move_time("[%emt 1:5]"), (None, Some("[%emt 1:5]".to_string())));
+/// ```
+pub fn move_time_command(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!(
+        "[%emt {}:{:02}:{:02}]",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Take the `[%emt]` command out of a move's comment
+///
+/// Returns its time in milliseconds and the rest of the comment, if any is
+/// left. A comment without a well-formed command is returned whole.
+pub fn split_move_time(comment: &str) -> (Option<u64>, Option<String>) {
+    let rest = |text: &str| Some(text.trim().to_string()).filter(|text| !text.is_empty());
+    let Some(start) = comment.find("[%emt ") else {
+        return (None, rest(comment));
+    };
+    let Some(len) = comment[start..].find(']') else {
+        return (None, rest(comment));
+    };
+    let Some(ms) = parse_move_time(&comment[start + 6..start + len]) else {
+        return (None, rest(comment));
+    };
+    let text = format!("{}{}", &comment[..start], &comment[start + len + 1..]);
+    (Some(ms), rest(&text))
+}
+
+/// Milliseconds in an `h:mm:ss` time, whose seconds may have a fraction
+fn parse_move_time(time: &str) -> Option<u64> {
+    let fields: Vec<&str> = time.trim().split(':').collect();
+    let [hours, minutes, seconds] = fields.as_slice() else {
+        return None;
+    };
+    let hours: u64 = hours.parse().ok()?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    if minutes >= 60 || !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    Some((hours * 3600 + minutes * 60) * 1000 + (seconds * 1000.0).round() as u64)
 }
 
 impl Display for PgnMove {
@@ -268,6 +383,30 @@ impl PgnGame {
         })
     }
 
+    /// Parse every game in a multi-game PGN collection
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::pgn::PgnGame;
+    ///
+    /// let pgn = r#"[Event "Game 1"]
+    ///
+    /// h2e2 h9g7 1-0
+    ///
+    /// [Event "Game 2"]
+    ///
+    /// b2e2 0-1"#;
+    ///
+    /// let games = PgnGame::parse_multi(pgn);
+    /// assert_eq!(games.len(), 2);
+    /// assert_eq!(games[1].get_tag("Event").unwrap(), "Game 2");
+    /// ```
+    pub fn parse_multi(text: &str) -> Vec<Self> {
+        PgnReader::new(text.as_bytes())
+            .filter_map(Result::ok)
+            .collect()
+    }
+
     /// Get a tag value by key
     pub fn get_tag(&self, key: &str) -> Option<&String> {
         self.tags.iter().find(|t| t.key == key).map(|t| &t.value)
@@ -312,46 +451,42 @@ impl PgnGame {
     /// assert!(pgn.contains("h2e2"));
     /// ```
     pub fn to_pgn(&self) -> String {
-        let mut output = String::new();
+        let mut buf = Vec::new();
+        self.write_pgn(&mut buf)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(buf).expect("PGN output is valid UTF-8")
+    }
 
+    /// Write the game in PGN format to a writer
+    ///
+    /// Movetext is wrapped so no line exceeds [`PGN_LINE_WIDTH`] characters.
+    /// Moves are written one at a time, so long games can be streamed to a
+    /// file without building the whole text in memory.
+    pub fn write_pgn<W: Write>(&self, out: &mut W) -> io::Result<()> {
         // Write tags
         for tag in &self.tags {
-            output.push_str(&tag.to_string());
-            output.push('\n');
+            writeln!(out, "{}", tag)?;
         }
 
         // Empty line between tag section and move section
         if !self.tags.is_empty() && !self.moves.is_empty() {
-            output.push('\n');
+            writeln!(out)?;
         }
 
-        // Write moves
+        // Write moves, keeping each move number with its move
+        let mut wrapper = LineWrapper::new(out, PGN_LINE_WIDTH);
+        let mut numbering = Numbering::default();
         for (i, mv) in self.moves.iter().enumerate() {
-            if i > 0 {
-                output.push(' ');
-            }
-
-            // Add move numbers
-            if i % 2 == 0 {
-                let move_num = (i / 2) + 1;
-                output.push_str(&format!("{}. ", move_num));
-            }
-
-            output.push_str(&mv.notation);
-
-            if let Some(comment) = &mv.comment {
-                output.push_str(&format!(" {{ {}}}", comment));
+            let resumed = i > 0 && !self.moves[i - 1].variations.is_empty();
+            for word in move_words(mv, numbering, resumed) {
+                wrapper.word(&word)?;
             }
+            numbering = numbering.after(numbering.side_of(mv));
         }
 
         // Write result
-        if !self.moves.is_empty() {
-            output.push(' ');
-        }
-        output.push_str(self.result.to_pgn_string());
-        output.push('\n');
-
-        output
+        wrapper.word(self.result.to_pgn_string())?;
+        writeln!(wrapper.out)
     }
 
     /// Get standard Chinese Chess PGN tags
@@ -385,6 +520,149 @@ impl Display for PgnGame {
     }
 }
 
+/// Reads games one at a time from a multi-game PGN source
+///
+/// A new game starts at the first line opening with `[` that follows
+/// movetext, outside a `{...}` comment or after a blank line, so large
+/// collections can be scanned without loading the whole file: memory use is
+/// bounded by the largest single game.
+///
+/// A game that can't be read is returned as an error and the reader moves
+/// on to the next game. Errors name the line the game starts on. Games are
+/// rejected for a line that can't be read (e.g. invalid UTF-8), a malformed
+/// tag, a comment left open, or unbalanced variation parentheses; drop them
+/// with `.filter_map(Result::ok)` to keep only the good games.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::pgn::PgnReader;
+///
+/// let text = "[Event \"One\"]\n\nh2e2 {open comment\n\n[Event \"Two\"]\n\nb2e2\n";
+/// let mut reader = PgnReader::new(text.as_bytes());
+/// let error = reader.next().unwrap().unwrap_err();
+/// assert_eq!(error.to_string(), "game at line 1: comment not closed");
+/// let game = reader.next().unwrap().unwrap();
+/// assert_eq!(game.get_tag("Event").unwrap(), "Two");
+/// assert!(reader.next().is_none());
+/// ```
+pub struct PgnReader<R> {
+    lines: io::Lines<R>,
+    pending: Option<String>,
+    /// Lines read so far
+    line: usize,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    /// Create a reader over a buffered source
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            pending: None,
+            line: 0,
+        }
+    }
+
+    /// Number of lines read so far
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = io::Result<PgnGame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = match self.pending {
+            Some(_) => self.line,
+            None => self.line + 1,
+        };
+        let mut text = self.pending.take().map(|l| l + "\n").unwrap_or_default();
+        let mut in_moves = false;
+        let mut in_comment = false;
+        let mut after_blank = false;
+        let mut depth = 0i32;
+        let mut problem: Option<io::Error> = None;
+        let malformed = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, reason);
+
+        // The pending line is a tag, or the start of one
+        if let Some(first) = text.lines().next() {
+            if PgnTag::parse(first.trim()).is_none() {
+                problem = Some(malformed("malformed tag"));
+            }
+        }
+
+        for line in self.lines.by_ref() {
+            self.line += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    // Skip the rest of the game, counting the line as
+                    // movetext so the next tag starts a new one
+                    problem.get_or_insert(e);
+                    in_moves = true;
+                    after_blank = false;
+                    continue;
+                }
+            };
+            let trimmed = line.trim();
+            // A tag after a blank line also ends a comment left open, so
+            // one stray brace doesn't swallow the rest of the file
+            let tag_line = trimmed.starts_with('[')
+                && (!in_comment || (after_blank && PgnTag::parse(trimmed).is_some()));
+            after_blank = trimmed.is_empty();
+
+            if tag_line && in_moves {
+                self.pending = Some(line);
+                break;
+            }
+            if tag_line {
+                if PgnTag::parse(trimmed).is_none() {
+                    problem.get_or_insert_with(|| malformed("malformed tag"));
+                }
+            } else {
+                if !trimmed.is_empty() {
+                    in_moves = true;
+                }
+                for c in trimmed.chars() {
+                    match c {
+                        '{' => in_comment = true,
+                        '}' => in_comment = false,
+                        '(' if !in_comment => depth += 1,
+                        ')' if !in_comment => depth -= 1,
+                        _ => {}
+                    }
+                    if depth < 0 {
+                        problem.get_or_insert_with(|| malformed("unbalanced parentheses"));
+                    }
+                }
+            }
+            text.push_str(&line);
+            text.push('\n');
+        }
+
+        if problem.is_none() && text.trim().is_empty() {
+            return None;
+        }
+        if in_comment {
+            problem.get_or_insert_with(|| malformed("comment not closed"));
+        } else if depth != 0 {
+            problem.get_or_insert_with(|| malformed("unbalanced parentheses"));
+        }
+        if let Some(e) = problem {
+            return Some(Err(io::Error::new(
+                e.kind(),
+                format!("game at line {}: {}", start, e),
+            )));
+        }
+        Some(PgnGame::parse(&text).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("game at line {}: can't be parsed", start),
+            )
+        }))
+    }
+}
+
 /// Helper function to split a string by a delimiter, respecting quoted sections
 ///
 /// # Examples
@@ -427,12 +705,105 @@ pub fn split_quoted(text: &str, delimiter: char) -> Option<Vec<&str>> {
     Some(parts)
 }
 
-/// Parse moves from move text, handling comments and move numbers
+/// Where the move numbers stand before a move
+///
+/// A number covers a move of Red's and Black's reply. When one side moves
+/// twice in a row, as in free analysis, the number moves on all the same.
+#[derive(Debug, Clone, Copy, Default)]
+struct Numbering {
+    /// The number of the move before; 0 at the start
+    number: usize,
+    /// The side that played the move before, if any
+    last: Option<Color>,
+}
+
+impl Numbering {
+    /// The side that played `mv`: as marked, or else the other side's
+    fn side_of(&self, mv: &PgnMove) -> Color {
+        mv.side.unwrap_or(match self.last {
+            Some(Color::Red) => Color::Black,
+            _ => Color::Red,
+        })
+    }
+
+    /// The number of a move by `side`
+    fn number_for(&self, side: Color) -> usize {
+        match (side, self.last) {
+            (Color::Black, Some(Color::Red)) => self.number,
+            _ => self.number + 1,
+        }
+    }
+
+    /// The number a move by `side` is written with
+    ///
+    /// Red's moves carry their number. Black's carry it as `N...` unless
+    /// they answer Red's move of the same number and are not `resumed`,
+    /// at the start of a variation or after one.
+    fn label(&self, side: Color, resumed: bool) -> Option<String> {
+        let number = self.number_for(side);
+        match (side, self.last) {
+            (Color::Red, _) => Some(format!("{}.", number)),
+            (Color::Black, Some(Color::Red)) if !resumed => None,
+            (Color::Black, _) => Some(format!("{}...", number)),
+        }
+    }
+
+    /// The numbering after a move by `side`
+    fn after(self, side: Color) -> Self {
+        Numbering {
+            number: self.number_for(side),
+            last: Some(side),
+        }
+    }
+}
+
+/// Words for a move played from `numbering`, followed by its comment and
+/// variations
+fn move_words(mv: &PgnMove, numbering: Numbering, resumed: bool) -> Vec<String> {
+    let side = numbering.side_of(mv);
+    let mut words = vec![match numbering.label(side, resumed) {
+        Some(label) => format!("{} {}", label, mv.notation),
+        None => mv.notation.clone(),
+    }];
+
+    if let Some(comment) = &mv.comment {
+        words.extend(
+            format!("{{ {}}}", comment)
+                .split_whitespace()
+                .map(String::from),
+        );
+    }
+
+    for variation in &mv.variations {
+        let mut line: Vec<String> = Vec::new();
+        let mut position = numbering;
+        for (i, alternative) in variation.iter().enumerate() {
+            let resumed = i == 0 || !variation[i - 1].variations.is_empty();
+            line.extend(move_words(alternative, position, resumed));
+            position = position.after(position.side_of(alternative));
+        }
+        if let Some(first) = line.first_mut() {
+            first.insert(0, '(');
+        }
+        if let Some(last) = line.last_mut() {
+            last.push(')');
+        }
+        words.extend(line);
+    }
+    words
+}
+
+/// Parse moves from move text, handling comments, variations and move
+/// numbers
 fn parse_moves(text: &str) -> Vec<PgnMove> {
     let mut moves = Vec::new();
+    // Lines left open by the variations being read, outermost first
+    let mut parents: Vec<Vec<PgnMove>> = Vec::new();
     let mut current_move = String::new();
     let mut in_comment = false;
     let mut current_comment = String::new();
+    // The side the last move number marked, for the move after it
+    let mut side = None;
 
     let chars = text.chars().collect::<Vec<_>>();
     let mut i = 0;
@@ -443,26 +814,26 @@ fn parse_moves(text: &str) -> Vec<PgnMove> {
         if in_comment {
             if c == '}' && (i == 0 || chars[i - 1] != '\\') {
                 in_comment = false;
-                // Close the comment
+                // A comment belongs to the move before it
+                attach_comment(&mut moves, &current_comment);
+                current_comment.clear();
             } else {
                 current_comment.push(c);
             }
         } else if c == '{' && (i == 0 || chars[i - 1] != '\\') {
             in_comment = true;
             // Save the current move if any
-            if !current_move.trim().is_empty() {
-                moves.push(PgnMove::new(current_move.trim().to_string()));
-                current_move = String::new();
+            push_move(&mut moves, &mut current_move, &mut side);
+        } else if c == '(' {
+            push_move(&mut moves, &mut current_move, &mut side);
+            parents.push(std::mem::take(&mut moves));
+        } else if c == ')' {
+            push_move(&mut moves, &mut current_move, &mut side);
+            if let Some(parent) = parents.pop() {
+                close_variation(parent, &mut moves);
             }
         } else if c.is_whitespace() {
-            if !current_move.trim().is_empty() {
-                let trimmed = current_move.trim();
-                // Skip move numbers (e.g., "1.", "2.")
-                if !trimmed.ends_with('.') {
-                    moves.push(PgnMove::new(trimmed.to_string()));
-                }
-                current_move = String::new();
-            }
+            push_move(&mut moves, &mut current_move, &mut side);
         } else {
             current_move.push(c);
         }
@@ -470,17 +841,76 @@ fn parse_moves(text: &str) -> Vec<PgnMove> {
         i += 1;
     }
 
-    // Don't forget the last move
-    if !current_move.trim().is_empty() {
-        let trimmed = current_move.trim();
-        if !trimmed.ends_with('.') {
-            moves.push(PgnMove::new(trimmed.to_string()));
-        }
+    // Don't forget the last move, and variations left open
+    push_move(&mut moves, &mut current_move, &mut side);
+    while let Some(parent) = parents.pop() {
+        close_variation(parent, &mut moves);
     }
 
+    forget_expected_sides(&mut moves, None);
     moves
 }
 
+/// Unmark the moves whose number only says what alternating sides would,
+/// keeping the side of those out of turn
+fn forget_expected_sides(moves: &mut [PgnMove], mut last: Option<Color>) {
+    for mv in moves {
+        let expected = match last {
+            Some(Color::Red) => Color::Black,
+            _ => Color::Red,
+        };
+        for variation in &mut mv.variations {
+            forget_expected_sides(variation, last);
+        }
+        last = Some(mv.side.unwrap_or(expected));
+        if mv.side == Some(expected) {
+            mv.side = None;
+        }
+    }
+}
+
+/// Finish reading a variation, which becomes an alternative to the last
+/// move of `parent`; `moves` goes back to holding the parent line
+fn close_variation(parent: Vec<PgnMove>, moves: &mut Vec<PgnMove>) {
+    let variation = std::mem::replace(moves, parent);
+    if let (Some(last), false) = (moves.last_mut(), variation.is_empty()) {
+        last.variations.push(variation);
+    }
+}
+
+/// Add the token being read as a move
+///
+/// A move number (e.g. `1.` or `1...`) is not a move, but marks the side
+/// of the move after it in `side`.
+fn push_move(moves: &mut Vec<PgnMove>, current_move: &mut String, side: &mut Option<Color>) {
+    let trimmed = current_move.trim();
+    if trimmed.ends_with("...") {
+        *side = Some(Color::Black);
+    } else if trimmed.ends_with('.') {
+        *side = Some(Color::Red);
+    } else if !trimmed.is_empty() {
+        let mut mv = PgnMove::new(trimmed.to_string());
+        mv.side = side.take();
+        moves.push(mv);
+    }
+    current_move.clear();
+}
+
+/// Attach a comment to the last move, after any comment it already has
+fn attach_comment(moves: &mut [PgnMove], comment: &str) {
+    let comment = comment.trim();
+    let Some(last) = moves.last_mut() else {
+        return;
+    };
+    if comment.is_empty() {
+        return;
+    }
+    last.comment = Some(match last.comment.take() {
+        Some(previous) => format!("{} {}", previous, comment),
+        None => comment.to_string(),
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -548,4 +978,56 @@ h2e2 h9g7 h3g3"#;
         let parts = split_quoted(r#"Event "Test Game""#, ' ').unwrap();
         assert_eq!(parts, vec!["Event", "\"Test Game\""]);
     }
+
+    #[test]
+    fn test_comments_attach_to_moves() {
+        let game = PgnGame::parse(
+            "[Event \"Test\"]\n\n1. h2e2 {Central cannon} h9g7 {[%eval 0.3]}\n{Quiet} 2. h0g2 1-0",
+        )
+        .unwrap();
+        let notations: Vec<&str> = game.moves.iter().map(|m| m.notation.as_str()).collect();
+        assert_eq!(notations, ["h2e2", "h9g7", "h0g2"]);
+        assert_eq!(game.moves[0].comment.as_deref(), Some("Central cannon"));
+        assert_eq!(game.moves[1].comment.as_deref(), Some("[%eval 0.3] Quiet"));
+        assert_eq!(game.moves[2].comment, None);
+        assert_eq!(game.result, PgnGameResult::RedWins);
+
+        // Written comments read back the same
+        let reparsed = PgnGame::parse(&game.to_pgn()).unwrap();
+        assert_eq!(reparsed.moves, game.moves);
+    }
+
+    #[test]
+    fn test_variations() {
+        let game = PgnGame::parse(
+            "1. h2e2 (1. b2e2 {Left cannon} b9c7 (1... h9g7) 2. b0c2) \
+             (1. c3c4) h9g7 2. h0g2 (2. b0c2 c6c5) 2... i9h9 1-0",
+        )
+        .unwrap();
+        let notations = |moves: &[PgnMove]| -> Vec<String> {
+            moves.iter().map(|m| m.notation.clone()).collect()
+        };
+        assert_eq!(notations(&game.moves), ["h2e2", "h9g7", "h0g2", "i9h9"]);
+        assert_eq!(game.result, PgnGameResult::RedWins);
+
+        let first = &game.moves[0].variations;
+        assert_eq!(first.len(), 2);
+        assert_eq!(notations(&first[0]), ["b2e2", "b9c7", "b0c2"]);
+        assert_eq!(first[0][0].comment.as_deref(), Some("Left cannon"));
+        assert_eq!(notations(&first[0][1].variations[0]), ["h9g7"]);
+        assert_eq!(notations(&first[1]), ["c3c4"]);
+        assert_eq!(notations(&game.moves[2].variations[0]), ["b0c2", "c6c5"]);
+
+        // Written variations read back the same
+        let written = game.to_pgn();
+        assert!(
+            written.contains(
+                "1. h2e2 (1. b2e2 { Left cannon} b9c7 (1... h9g7) 2. b0c2) (1. c3c4) 1... h9g7"
+            ),
+            "{}",
+            written
+        );
+        assert!(written.contains("(2. b0c2 c6c5) 2... i9h9"), "{}", written);
+        assert_eq!(PgnGame::parse(&written).unwrap().moves, game.moves);
+    }
 }
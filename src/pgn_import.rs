@@ -0,0 +1,357 @@
+//! Applying PGN move lists to a game
+//!
+//! PGN files in the wild often contain bad moves: OCR slips, typos, or
+//! coordinates written from the wrong side of the board. The importer checks
+//! every move against the legal move generator and, when a move cannot be
+//! played, suggests nearby legal moves. In lenient mode an unambiguous
+//! suggestion is applied automatically.
+
+use crate::fen::FenError;
+use crate::game::Game;
+use crate::notation::chinese::parse_chinese_move;
+use crate::notation::iccs::{iccs_to_move, move_to_iccs};
+use crate::notation::wxf::resolve_wxf_move;
+use crate::pgn::{split_move_time, PgnGame, PgnMove};
+use crate::types::{Color, Position};
+use std::fmt::{self, Display, Formatter};
+
+/// How the importer handles moves that cannot be applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportMode {
+    /// Stop at the first bad move
+    #[default]
+    Strict,
+    /// Apply unambiguous corrections and skip moves that remain unplayable
+    Lenient,
+}
+
+/// Why a PGN move could not be applied as written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The notation is neither ICCS nor a Chinese move that fits the position
+    Unparseable,
+    /// The move is well-formed but illegal in the position
+    Illegal,
+}
+
+/// A move that could not be applied as written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveIssue {
+    /// Zero-based ply index in the PGN move list
+    pub ply: usize,
+    pub notation: String,
+    pub kind: IssueKind,
+    /// Legal ICCS moves close to the written one, best first
+    pub suggestions: Vec<String>,
+    /// The correction that was applied in lenient mode, if any
+    pub applied: Option<String>,
+}
+
+impl Display for MoveIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            IssueKind::Unparseable => "unrecognized move",
+            IssueKind::Illegal => "illegal move",
+        };
+        write!(f, "ply {}: {} {}", self.ply + 1, what, self.notation)?;
+        if let Some(fix) = &self.applied {
+            write!(f, " (corrected to {})", fix)
+        } else if !self.suggestions.is_empty() {
+            write!(f, " (did you mean {}?)", self.suggestions.join(", "))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Result of importing a PGN game
+#[derive(Debug, Clone)]
+pub struct ImportReport {
+    /// The game after applying every playable move
+    pub game: Game,
+    /// Number of moves applied, including corrected ones
+    pub applied: usize,
+    /// Moves that could not be applied as written
+    pub issues: Vec<MoveIssue>,
+    /// True if import stopped before the end of the move list
+    pub stopped: bool,
+}
+
+impl ImportReport {
+    /// True if every move was applied exactly as written
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Import a PGN game, validating each move against the rules
+///
+/// Each move may be ICCS coordinates (e.g. "h7e7"), Chinese notation
+/// (e.g. "炮二平五") or WXF (e.g. "C2.5"); the format is detected per
+/// move. The starting position comes from the `FEN` tag when present.
+/// Comments and variations are kept; variations are not corrected but cut
+/// short at their first bad move.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::pgn::PgnGame;
+/// use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+///
+/// let mut pgn = PgnGame::new();
+/// pgn.add_move("h7e7");
+/// pgn.add_move("b0c3"); // typo for b0c2
+///
+/// let report = import_pgn(&pgn, ImportMode::Lenient).unwrap();
+/// assert_eq!(report.applied, 2);
+/// assert_eq!(report.issues[0].applied.as_deref(), Some("b0c2"));
+/// ```
+pub fn import_pgn(pgn: &PgnGame, mode: ImportMode) -> Result<ImportReport, FenError> {
+    let mut game = match pgn.get_tag("FEN") {
+        Some(fen) if !fen.is_empty() => Game::from_fen(fen)?,
+        _ => Game::new(),
+    };
+
+    let mut applied = 0;
+    let mut issues = Vec::new();
+    let mut stopped = false;
+
+    for (ply, pgn_move) in pgn.moves.iter().enumerate() {
+        let notation = pgn_move.notation.trim();
+        let variations = variations_to_iccs(&game, pgn_move);
+
+        let side = pgn_move.side.unwrap_or(game.turn());
+        let kind = match parse_move(&game, side, notation) {
+            Some((from, to)) if play_as(&mut game, side, from, to) => {
+                applied += 1;
+                keep_annotations(&mut game, pgn_move, variations);
+                continue;
+            }
+            Some(_) => IssueKind::Illegal,
+            None => IssueKind::Unparseable,
+        };
+
+        let suggestions = suggest_corrections(&game, notation);
+        let mut issue = MoveIssue {
+            ply,
+            notation: notation.to_string(),
+            kind,
+            suggestions,
+            applied: None,
+        };
+
+        if mode == ImportMode::Lenient && issue.suggestions.len() == 1 {
+            let fix = issue.suggestions[0].clone();
+            if let Some((from, to)) = iccs_to_move(&fix) {
+                if game.make_move(from, to).is_ok() {
+                    applied += 1;
+                    keep_annotations(&mut game, pgn_move, variations);
+                    issue.applied = Some(fix);
+                }
+            }
+        }
+
+        let unresolved = issue.applied.is_none();
+        issues.push(issue);
+        if unresolved && mode == ImportMode::Strict {
+            stopped = true;
+            break;
+        }
+    }
+
+    Ok(ImportReport {
+        game,
+        applied,
+        issues,
+        stopped,
+    })
+}
+
+/// Copy the comment and variations of the PGN move just played onto the
+/// game's move
+fn keep_annotations(game: &mut Game, pgn_move: &PgnMove, variations: Vec<Vec<PgnMove>>) {
+    let index = game.get_moves().len() - 1;
+    let (time, comment) = pgn_move
+        .comment
+        .as_deref()
+        .map_or((None, None), split_move_time);
+    game.set_move_time(index, time);
+    game.set_comment(index, comment);
+    for variation in variations {
+        game.add_variation(index, variation);
+    }
+}
+
+/// The variations of a PGN move about to be played in `game`, in ICCS
+///
+/// Each variation is cut short at its first move that cannot be played;
+/// variations left empty are dropped.
+fn variations_to_iccs(game: &Game, pgn_move: &PgnMove) -> Vec<Vec<PgnMove>> {
+    pgn_move
+        .variations
+        .iter()
+        .map(|variation| line_to_iccs(game, variation))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn line_to_iccs(game: &Game, moves: &[PgnMove]) -> Vec<PgnMove> {
+    let mut game = game.clone();
+    let mut line = Vec::new();
+    for pgn_move in moves {
+        let side = pgn_move.side.unwrap_or(game.turn());
+        let Some((from, to)) = parse_move(&game, side, pgn_move.notation.trim()) else {
+            break;
+        };
+        let variations = variations_to_iccs(&game, pgn_move);
+        if !play_as(&mut game, side, from, to) {
+            break;
+        }
+        let mut mv = PgnMove::new(move_to_iccs(from, to));
+        mv.side = pgn_move.side;
+        mv.comment = pgn_move.comment.clone();
+        mv.variations = variations;
+        line.push(mv);
+    }
+    line
+}
+
+/// Read a move as ICCS, falling back to Chinese and WXF notation
+fn parse_move(game: &Game, side: Color, notation: &str) -> Option<(Position, Position)> {
+    iccs_to_move(notation)
+        .or_else(|| parse_chinese_move(game.board(), side, notation))
+        .or_else(|| resolve_wxf_move(game.board(), side, notation))
+}
+
+/// Play a move as `side`, which may be the side not to move when the
+/// move's number marks it so, as in a game from free analysis where a side
+/// moved twice in a row
+///
+/// Returns whether the move was played.
+fn play_as(game: &mut Game, side: Color, from: Position, to: Position) -> bool {
+    if side == game.turn() {
+        return game.make_move(from, to).is_ok();
+    }
+    if game.board().get(from).map(|piece| piece.color) != Some(side) {
+        return false;
+    }
+    let free = game.free_turn();
+    game.set_free_turn(true);
+    let played = game.make_move(from, to).is_ok();
+    game.set_free_turn(free);
+    played
+}
+
+/// Suggest legal moves close to a mistyped ICCS move
+///
+/// A legal move is a near miss if it differs from the written move in one
+/// character, swaps source and destination, or is the written move with
+/// ranks mirrored (coordinates read from the other side of the board).
+/// Returns an empty list when nothing is close.
+pub fn suggest_corrections(game: &Game, notation: &str) -> Vec<String> {
+    let written: Vec<char> = notation.replace('-', "").to_lowercase().chars().collect();
+    if written.len() != 4 {
+        return Vec::new();
+    }
+
+    let mirrored = iccs_to_move(notation).map(|(from, to)| (mirror(from), mirror(to)));
+    let reversed = iccs_to_move(notation).map(|(from, to)| (to, from));
+
+    let mut scored: Vec<(usize, String)> = game
+        .board()
+        .legal_moves(game.turn())
+        .into_iter()
+        .filter_map(|(from, to)| {
+            let iccs = move_to_iccs(from, to);
+            let diff = iccs
+                .chars()
+                .zip(written.iter())
+                .filter(|(a, b)| a != *b)
+                .count();
+            if diff == 1 {
+                Some((0, iccs))
+            } else if mirrored == Some((from, to)) || reversed == Some((from, to)) {
+                Some((1, iccs))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    scored.sort();
+    scored.into_iter().map(|(_, iccs)| iccs).collect()
+}
+
+fn mirror(pos: Position) -> Position {
+    Position::from_xy(pos.x, 9 - pos.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pgn_with(moves: &[&str]) -> PgnGame {
+        let mut pgn = PgnGame::new();
+        for mv in moves {
+            pgn.add_move(*mv);
+        }
+        pgn
+    }
+
+    #[test]
+    fn test_clean_import() {
+        let report = import_pgn(&pgn_with(&["h7e7", "h0g2"]), ImportMode::Strict).unwrap();
+        assert!(report.is_clean());
+        assert_eq!(report.applied, 2);
+        assert_eq!(report.game.get_moves().len(), 2);
+    }
+
+    #[test]
+    fn test_suggest_one_character_typo() {
+        let mut game = Game::new();
+        game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+            .unwrap();
+        // b0c3 is not a horse move; b0c2 and b0a2 are
+        let suggestions = suggest_corrections(&game, "b0c3");
+        assert_eq!(suggestions, vec!["b0c2"]);
+        let suggestions = suggest_corrections(&game, "b0b2");
+        assert_eq!(suggestions, vec!["b0a2", "b0c2"]);
+    }
+
+    #[test]
+    fn test_suggest_mirrored_ranks() {
+        // Red cannon h7e7 written from Black's side as h2e2
+        let suggestions = suggest_corrections(&Game::new(), "h2e2");
+        assert!(suggestions.contains(&"h7e7".to_string()));
+    }
+
+    #[test]
+    fn test_chinese_notation_import() {
+        let report = import_pgn(
+            &pgn_with(&["炮二平五", "马8进7", "h9g7", "車9平8"]),
+            ImportMode::Strict,
+        )
+        .unwrap();
+        assert!(report.is_clean());
+        assert_eq!(
+            report.game.get_moves_with_iccs(),
+            vec!["h7e7", "h0g2", "h9g7", "i0h0"]
+        );
+    }
+
+    #[test]
+    fn test_strict_stops_at_first_issue() {
+        let report = import_pgn(&pgn_with(&["h7e7", "zz", "h0g2"]), ImportMode::Strict).unwrap();
+        assert!(report.stopped);
+        assert_eq!(report.applied, 1);
+        assert_eq!(report.issues[0].kind, IssueKind::Unparseable);
+    }
+
+    #[test]
+    fn test_lenient_skips_ambiguous() {
+        let report = import_pgn(&pgn_with(&["h7e7", "b0b2", "b0c2"]), ImportMode::Lenient).unwrap();
+        assert!(!report.stopped);
+        assert_eq!(report.issues[0].applied, None);
+        assert_eq!(report.issues[0].suggestions.len(), 2);
+        assert_eq!(report.applied, 2);
+    }
+}
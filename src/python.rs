@@ -0,0 +1,265 @@
+//! Python bindings to the rules, notation and FEN/PGN code
+//!
+//! Built with the `python` feature, as an extension module named
+//! `cn_chess_tui`; `maturin build --release` does it with the settings in
+//! `pyproject.toml`. Squares and moves are strings: ICCS coordinates
+//! (`h7e7`), and for moves WXF (`C2.5`) and Chinese notation (`炮二平五`)
+//! as well.
+//!
+//! ```text
+//! >>> from cn_chess_tui import Game, read_pgn
+//! >>> game = Game()
+//! >>> game.make_move("炮二平五")
+//! >>> game.moves("wxf")
+//! ['C2.5']
+//! >>> [len(g.moves()) for g in read_pgn(open("games.pgn").read())]
+//! ```
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::board::Board;
+use crate::fen::{fen_to_board, piece_to_fen};
+use crate::game::{Game, GameResult, GameState};
+use crate::notation::format::{parse_any, MoveStyle};
+use crate::notation::iccs::{iccs_to_position, move_to_iccs};
+use crate::pgn::{PgnGame, PgnGameResult, PgnReader};
+use crate::pgn_import::{import_pgn, ImportMode};
+use crate::types::Color;
+
+fn value_error(e: impl ToString) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// A notation by the name Python passes: chinese, wxf or iccs
+fn style(name: &str) -> PyResult<MoveStyle> {
+    match name.to_ascii_lowercase().as_str() {
+        "chinese" => Ok(MoveStyle::Chinese),
+        "wxf" => Ok(MoveStyle::Wxf),
+        "iccs" => Ok(MoveStyle::Iccs),
+        _ => Err(value_error(format!(
+            "unknown notation '{}', expected chinese, wxf or iccs",
+            name
+        ))),
+    }
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::Red => "red",
+        Color::Black => "black",
+    }
+}
+
+/// A game with its move history
+#[pyclass(name = "Game")]
+#[derive(Clone)]
+pub struct PyGame {
+    game: Game,
+}
+
+#[pymethods]
+impl PyGame {
+    /// A game from `fen`, or the starting position
+    #[new]
+    #[pyo3(signature = (fen=None))]
+    fn new(fen: Option<&str>) -> PyResult<Self> {
+        let game = match fen {
+            Some(fen) => Game::from_fen(fen).map_err(value_error)?,
+            None => Game::new(),
+        };
+        Ok(Self { game })
+    }
+
+    /// Read a single game in PGN; ValueError if a move can't be played
+    #[staticmethod]
+    fn from_pgn(text: &str) -> PyResult<Self> {
+        let pgn = PgnGame::parse(text).ok_or_else(|| value_error("not a PGN game"))?;
+        pgn_game(&pgn)
+    }
+
+    /// Play a move in ICCS, WXF or Chinese notation
+    fn make_move(&mut self, mv: &str) -> PyResult<()> {
+        let (from, to) = parse_any(&self.game, mv).map_err(value_error)?;
+        self.game.make_move(from, to).map_err(value_error)
+    }
+
+    /// Take back the last move; False if there is none
+    fn undo(&mut self) -> bool {
+        self.game.undo_move()
+    }
+
+    /// The legal moves of the side to move, in ICCS
+    fn legal_moves(&self) -> Vec<String> {
+        if self.game.state() != GameState::Playing {
+            return Vec::new();
+        }
+        self.game
+            .board()
+            .legal_moves(self.game.turn())
+            .into_iter()
+            .map(|(from, to)| move_to_iccs(from, to))
+            .collect()
+    }
+
+    /// The moves played, in `notation`: iccs, wxf or chinese
+    #[pyo3(signature = (notation="iccs"))]
+    fn moves(&self, notation: &str) -> PyResult<Vec<String>> {
+        Ok(self.game.move_notations(style(notation)?))
+    }
+
+    /// "red" or "black"
+    #[getter]
+    fn turn(&self) -> &'static str {
+        color_name(self.game.turn())
+    }
+
+    /// How the game stands, e.g. "Playing" or "Checkmate - Red Wins"
+    #[getter]
+    fn state(&self) -> String {
+        self.game.state().to_string()
+    }
+
+    /// The result as in PGN ("1-0", "0-1" or "1/2-1/2"), None while playing
+    #[getter]
+    fn result(&self) -> Option<String> {
+        let result = match self.game.state().result()? {
+            GameResult::RedWins => PgnGameResult::RedWins,
+            GameResult::BlackWins => PgnGameResult::BlackWins,
+            GameResult::Draw => PgnGameResult::Draw,
+        };
+        Some(result.to_pgn_string().to_string())
+    }
+
+    fn is_in_check(&self) -> bool {
+        self.game.is_in_check()
+    }
+
+    fn to_fen(&self) -> String {
+        self.game.to_fen()
+    }
+
+    fn to_pgn(&self) -> String {
+        self.game.to_pgn().to_pgn()
+    }
+
+    /// The current position
+    fn board(&self) -> PyBoard {
+        PyBoard {
+            board: self.game.board().clone(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Game('{}')", self.game.to_fen())
+    }
+}
+
+/// A position without its history
+#[pyclass(name = "Board")]
+#[derive(Clone)]
+pub struct PyBoard {
+    board: Board,
+}
+
+#[pymethods]
+impl PyBoard {
+    /// The board of a FEN position
+    #[staticmethod]
+    fn from_fen(fen: &str) -> PyResult<Self> {
+        let (board, _) = fen_to_board(fen).map_err(value_error)?;
+        Ok(Self { board })
+    }
+
+    /// The FEN letter of the piece on an ICCS square, uppercase for Red;
+    /// None if the square is empty
+    fn piece_at(&self, square: &str) -> PyResult<Option<char>> {
+        let pos = iccs_to_position(square)
+            .ok_or_else(|| value_error(format!("'{}' is not a square", square)))?;
+        Ok(self.board.get(pos).map(|piece| piece_to_fen(*piece)))
+    }
+
+    /// Every piece as (square, FEN letter), rank by rank from Black's side
+    fn pieces(&self) -> Vec<(String, char)> {
+        self.board
+            .pieces()
+            .map(|(pos, piece)| (crate::position_to_iccs(pos), piece_to_fen(piece)))
+            .collect()
+    }
+
+    /// Whether `color` ("red" or "black") is in check
+    fn is_in_check(&self, color: &str) -> PyResult<bool> {
+        let color = match color.to_ascii_lowercase().as_str() {
+            "red" => Color::Red,
+            "black" => Color::Black,
+            _ => return Err(value_error(format!("unknown color '{}'", color))),
+        };
+        Ok(self.board.is_in_check(color))
+    }
+}
+
+/// Replay a parsed PGN game strictly
+fn pgn_game(pgn: &PgnGame) -> PyResult<PyGame> {
+    let report = import_pgn(pgn, ImportMode::Strict).map_err(value_error)?;
+    match report.issues.first() {
+        Some(issue) => Err(value_error(issue)),
+        None => Ok(PyGame { game: report.game }),
+    }
+}
+
+/// Every game of a PGN collection, skipping those that can't be read or
+/// replayed
+#[pyfunction]
+fn read_pgn(text: &str) -> Vec<PyGame> {
+    PgnReader::new(text.as_bytes())
+        .filter_map(Result::ok)
+        .filter_map(|pgn| pgn_game(&pgn).ok())
+        .collect()
+}
+
+/// Rewrite a move played from `fen` in `notation`: iccs, wxf or chinese
+///
+/// The move may be in any notation; ValueError if it isn't legal there.
+#[pyfunction]
+fn convert_move(fen: &str, mv: &str, notation: &str) -> PyResult<String> {
+    let style = style(notation)?;
+    let mut game = PyGame::new(Some(fen))?;
+    game.make_move(mv)?;
+    Ok(game.game.move_notations(style).remove(0))
+}
+
+#[pymodule]
+fn cn_chess_tui(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGame>()?;
+    m.add_class::<PyBoard>()?;
+    m.add_function(wrap_pyfunction!(read_pgn, m)?)?;
+    m.add_function(wrap_pyfunction!(convert_move, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Errors are Python objects, so only the paths that succeed run
+    // without an interpreter
+    #[test]
+    fn test_play_through_the_bindings() {
+        let mut game = PyGame::new(None).unwrap();
+        game.make_move("炮二平五").unwrap();
+        game.make_move("h0g2").unwrap();
+        assert_eq!(game.moves("wxf").unwrap(), ["C2.5", "H8+7"]);
+        assert_eq!(game.turn(), "red");
+        assert_eq!(game.result(), None);
+        assert_eq!(game.board().piece_at("e7").unwrap(), Some('C'));
+
+        let mut mated = PyGame::new(Some("3k5/R8/9/9/9/9/9/9/9/4K3R w - - 0 1")).unwrap();
+        mated.make_move("i9i0").unwrap();
+        assert_eq!(mated.result().as_deref(), Some("1-0"));
+        assert!(mated.legal_moves().is_empty());
+
+        let games = read_pgn(&format!("{}\n\n{}", game.to_pgn(), mated.to_pgn()));
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].moves("chinese").unwrap(), ["炮二平五", "马8进7"]);
+    }
+}
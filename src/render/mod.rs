@@ -0,0 +1,4 @@
+//! Drawing positions outside the terminal UI
+
+pub mod report;
+pub mod svg;
@@ -0,0 +1,351 @@
+//! Human-readable game reports in Markdown or HTML
+//!
+//! A report lists the game's tags, then its moves in Chinese notation with
+//! their comments, breaking for a board diagram every few moves, at each key
+//! moment found by [`key_moments`] and after the last move. Markdown reports
+//! draw the boards as text in code blocks; HTML reports embed them as SVG.
+
+use crate::diagram::{comment_text, format_eval, key_moments, parse_eval, text_diagram};
+use crate::fen::FenError;
+use crate::game::{Game, PgnExportOptions};
+use crate::notation::format::MoveStyle;
+use crate::pgn::PgnGame;
+use crate::pgn_import::{import_pgn, ImportMode};
+use crate::render::svg::{board_svg, escape_xml};
+use crate::types::Color;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Markup a report is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// The format for a file name: `.md` or `.markdown`, `.html` or `.htm`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" => Some(ReportFormat::Markdown),
+            "html" | "htm" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// What goes into a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportOptions {
+    pub format: ReportFormat,
+    /// Moves (counted per side, as in move numbers) between regular
+    /// diagrams; None draws only key moments and the final position
+    pub every: Option<usize>,
+    /// Evaluation swing in centipawns that makes a key moment
+    pub threshold: i32,
+}
+
+impl ReportOptions {
+    pub fn new(format: ReportFormat) -> Self {
+        Self {
+            format,
+            every: Some(10),
+            threshold: 100,
+        }
+    }
+}
+
+/// A written report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameReport {
+    pub text: String,
+    /// Number of board diagrams in the report
+    pub diagrams: usize,
+}
+
+/// One run of moves in the report, ending with a diagram unless it is the
+/// last one
+struct Section {
+    /// Moves as (move number, side, notation, comment)
+    moves: Vec<(usize, Color, String, Option<String>)>,
+    diagram: Option<Diagram>,
+}
+
+struct Diagram {
+    caption: String,
+    game: Game,
+}
+
+/// Write a report of a game
+///
+/// Moves are read as in [`key_moments`], so the game must be legal.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::pgn::PgnGame;
+/// use cn_chess_tui::render::report::{game_report, ReportFormat, ReportOptions};
+///
+/// let mut pgn = PgnGame::parse("1. h7e7 {Central cannon} h0g2 2. h9g7 i0h0").unwrap();
+/// pgn.set_tag("Red", "Hu Ronghua");
+/// let report = game_report(&pgn, &ReportOptions::new(ReportFormat::Markdown)).unwrap();
+/// assert!(report.text.starts_with("# Hu Ronghua vs ?"));
+/// assert!(report.text.contains("**1.** 炮二平五 *Central cannon*"));
+/// // After the commented move, and at the end
+/// assert_eq!(report.diagrams, 2);
+/// ```
+pub fn game_report(pgn: &PgnGame, options: &ReportOptions) -> Result<GameReport, FenError> {
+    let game = import_pgn(pgn, ImportMode::Strict)?.game;
+    let moments = key_moments(pgn, options.threshold)?;
+    let moves = game
+        .to_pgn_with(PgnExportOptions {
+            notation: MoveStyle::Chinese,
+        })
+        .moves;
+    let start = game.at_ply(0);
+    // Move numbers count from 1 with Red, as in key_moments
+    let offset = usize::from(start.turn() == Color::Black);
+
+    // Plies followed by a diagram
+    let mut plies: BTreeSet<usize> = moments.iter().map(|moment| moment.ply).collect();
+    if let Some(every) = options.every.filter(|&every| every > 0) {
+        plies.extend((1..=moves.len()).filter(|ply| (ply + offset) % (2 * every) == 0));
+    }
+    plies.insert(moves.len());
+
+    let mut sections = Vec::new();
+    let mut section = Section {
+        moves: Vec::new(),
+        diagram: None,
+    };
+    let mut side = start.turn();
+    for (i, pgn_move) in moves.iter().enumerate() {
+        let number = (i + offset) / 2 + 1;
+        let comment = pgn_move.comment.as_deref().unwrap_or("");
+        let text = comment_text(comment);
+        section.moves.push((
+            number,
+            side,
+            pgn_move.notation.clone(),
+            (!text.is_empty()).then_some(text),
+        ));
+        let ply = i + 1;
+        if plies.contains(&ply) {
+            let dots = if side == Color::Red { "." } else { "..." };
+            let mut caption = format!("{}{} {}", number, dots, pgn_move.notation);
+            if let Some(eval) = parse_eval(comment) {
+                caption.push_str(&format!(" ({})", format_eval(eval)));
+            }
+            section.diagram = Some(Diagram {
+                caption,
+                game: game.at_ply(ply),
+            });
+            sections.push(std::mem::replace(
+                &mut section,
+                Section {
+                    moves: Vec::new(),
+                    diagram: None,
+                },
+            ));
+        }
+        side = side.opponent();
+    }
+    if moves.is_empty() {
+        section.diagram = Some(Diagram {
+            caption: "Start".to_string(),
+            game: start,
+        });
+        sections.push(section);
+    }
+
+    let diagrams = sections.len();
+    let text = match options.format {
+        ReportFormat::Markdown => markdown(pgn, &sections),
+        ReportFormat::Html => html(pgn, &sections),
+    };
+    Ok(GameReport { text, diagrams })
+}
+
+/// Title of a report, e.g. `Hu Ronghua vs Yang Guanlin`
+fn title(pgn: &PgnGame) -> String {
+    let player = |key| pgn.get_tag(key).map_or("?", String::as_str);
+    format!("{} vs {}", player("Red"), player("Black"))
+}
+
+/// The move number written before a move: always for Red's, and for
+/// Black's when it opens a run of moves
+fn move_prefix(number: usize, side: Color, first: bool) -> Option<String> {
+    match side {
+        Color::Red => Some(format!("{}.", number)),
+        Color::Black if first => Some(format!("{}...", number)),
+        Color::Black => None,
+    }
+}
+
+fn markdown(pgn: &PgnGame, sections: &[Section]) -> String {
+    let mut out = format!("# {}\n\n", escape_markdown(&title(pgn)));
+    if !pgn.tags.is_empty() {
+        out.push_str("| Tag | Value |\n|-----|-------|\n");
+        for tag in &pgn.tags {
+            out.push_str(&format!(
+                "| {} | {} |\n",
+                escape_markdown(&tag.key),
+                escape_markdown(&tag.value)
+            ));
+        }
+        out.push('\n');
+    }
+    out.push_str("## Moves\n\n");
+
+    for section in sections {
+        let mut line = String::new();
+        for (i, (number, side, notation, comment)) in section.moves.iter().enumerate() {
+            if let Some(prefix) = move_prefix(*number, *side, i == 0) {
+                if !line.is_empty() {
+                    out.push_str(&line);
+                    out.push_str("\n\n");
+                    line.clear();
+                }
+                line.push_str(&format!("**{}** ", prefix));
+            } else {
+                line.push(' ');
+            }
+            line.push_str(notation);
+            if let Some(comment) = comment {
+                line.push_str(&format!(" *{}*", escape_markdown(comment)));
+            }
+        }
+        if !line.is_empty() {
+            out.push_str(&line);
+            out.push_str("\n\n");
+        }
+        if let Some(diagram) = &section.diagram {
+            let last_move = diagram.game.get_moves().last().copied();
+            let text = text_diagram(diagram.game.board(), last_move, &diagram.caption);
+            out.push_str(&format!("```text\n{}```\n\n", text));
+        }
+    }
+    out
+}
+
+fn html(pgn: &PgnGame, sections: &[Section]) -> String {
+    let title = escape_xml(&title(pgn));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+         <style>body {{ font-family: sans-serif; max-width: 40em; margin: auto; }} \
+         figure svg {{ max-width: 100%; height: auto; }} \
+         .comment {{ color: #555; font-style: italic; }}</style>\n\
+         </head>\n<body>\n<h1>{0}</h1>\n",
+        title
+    );
+    if !pgn.tags.is_empty() {
+        out.push_str("<table>\n");
+        for tag in &pgn.tags {
+            out.push_str(&format!(
+                "<tr><th>{}</th><td>{}</td></tr>\n",
+                escape_xml(&tag.key),
+                escape_xml(&tag.value)
+            ));
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("<h2>Moves</h2>\n");
+
+    for section in sections {
+        let mut line = String::new();
+        for (i, (number, side, notation, comment)) in section.moves.iter().enumerate() {
+            if let Some(prefix) = move_prefix(*number, *side, i == 0) {
+                if !line.is_empty() {
+                    out.push_str(&format!("<p>{}</p>\n", line));
+                    line.clear();
+                }
+                line.push_str(&format!("<b>{}</b> ", prefix));
+            } else {
+                line.push(' ');
+            }
+            line.push_str(&escape_xml(notation));
+            if let Some(comment) = comment {
+                line.push_str(&format!(
+                    " <span class=\"comment\">{}</span>",
+                    escape_xml(comment)
+                ));
+            }
+        }
+        if !line.is_empty() {
+            out.push_str(&format!("<p>{}</p>\n", line));
+        }
+        if let Some(diagram) = &section.diagram {
+            let last_move = diagram.game.get_moves().last().copied();
+            let svg = board_svg(diagram.game.board(), last_move, Some(&diagram.caption));
+            out.push_str(&format!("<figure>\n{}</figure>\n", svg));
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Escape the characters Markdown would read as formatting
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '|' | '#'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagrams_every_few_moves() {
+        let pgn = PgnGame::parse("1. h7e7 h0g2 2. h9g7 i0h0 3. i9h9 b0c2").unwrap();
+        let options = ReportOptions {
+            every: Some(1),
+            ..ReportOptions::new(ReportFormat::Markdown)
+        };
+        let report = game_report(&pgn, &options).unwrap();
+        assert_eq!(report.diagrams, 3);
+        assert_eq!(report.text.matches("```text").count(), 3);
+        assert!(report.text.contains("3... 马2进3"));
+
+        let options = ReportOptions {
+            every: None,
+            ..options
+        };
+        assert_eq!(game_report(&pgn, &options).unwrap().diagrams, 1);
+    }
+
+    #[test]
+    fn test_html_escapes_and_embeds_svg() {
+        let mut pgn = PgnGame::parse("1. h7e7 {Threat: <mate> [%eval 0.3]} h0g2").unwrap();
+        pgn.set_tag("Event", "A & B");
+        let report = game_report(&pgn, &ReportOptions::new(ReportFormat::Html)).unwrap();
+        assert!(report.text.contains("<td>A &amp; B</td>"));
+        assert!(report.text.contains("Threat: &lt;mate&gt;</span>"));
+        assert!(!report.text.contains("%eval"));
+        assert_eq!(report.text.matches("<svg").count(), report.diagrams);
+        assert!(report.text.contains("1. 炮二平五 (+0.30)"));
+        // The black move after a diagram starts its own paragraph
+        assert!(report.text.contains("<p><b>1...</b> 马8进7</p>"));
+    }
+
+    #[test]
+    fn test_format_from_extension() {
+        assert_eq!(
+            ReportFormat::from_path(Path::new("game.MD")),
+            Some(ReportFormat::Markdown)
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("out/game.htm")),
+            Some(ReportFormat::Html)
+        );
+        assert_eq!(ReportFormat::from_path(Path::new("game.pgn")), None);
+    }
+}
@@ -0,0 +1,176 @@
+//! SVG drawings of a position
+//!
+//! Used by `--print-svg` and the diagrams written by `pgn_diagrams`, so a
+//! position can be embedded in documents and web pages at any size.
+
+use crate::board::Board;
+use crate::game::Move;
+use crate::types::{Color, Position};
+use std::fmt::Write;
+
+/// Side of a board square in SVG user units
+const CELL: usize = 50;
+/// Space around the grid for pieces on the edge
+const MARGIN: usize = 40;
+/// Room under the board for the caption
+const CAPTION_HEIGHT: usize = 40;
+
+/// Draw a position as a standalone SVG image
+///
+/// Red is at the bottom. Pieces are circles with their Chinese character,
+/// in red or black. The squares of `last_move` are marked, and a caption,
+/// if given, is written under the board.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::board::Board;
+/// use cn_chess_tui::render::svg::board_svg;
+///
+/// let svg = board_svg(&Board::new(), None, None);
+/// assert!(svg.starts_with("<svg"));
+/// assert_eq!(svg.matches("<circle").count(), 32);
+/// ```
+pub fn board_svg(board: &Board, last_move: Option<Move>, caption: Option<&str>) -> String {
+    let caption_height = if caption.is_some() { CAPTION_HEIGHT } else { 0 };
+    let width = MARGIN * 2 + CELL * 8;
+    let height = MARGIN * 2 + CELL * 9 + caption_height;
+    let point = |x: usize, y: usize| (MARGIN + x * CELL, MARGIN + y * CELL);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="{}" height="{}" fill="#f2d9a6"/>"##,
+        width, height
+    );
+
+    // Grid: ranks run across, files stop at the river except on the edges
+    svg.push_str(r##"<g stroke="#5c3b1e" stroke-width="1.5">"##);
+    svg.push('\n');
+    for y in 0..10 {
+        let (x1, y1) = point(0, y);
+        let (x2, _) = point(8, y);
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+            x1, y1, x2, y1
+        );
+    }
+    for x in 0..9 {
+        let spans: &[(usize, usize)] = if x == 0 || x == 8 {
+            &[(0, 9)]
+        } else {
+            &[(0, 4), (5, 9)]
+        };
+        for &(top, bottom) in spans {
+            let (x1, y1) = point(x, top);
+            let (_, y2) = point(x, bottom);
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                x1, y1, x1, y2
+            );
+        }
+    }
+    // Palace diagonals
+    for (top, bottom) in [(0, 2), (7, 9)] {
+        for (from, to) in [((3, top), (5, bottom)), ((5, top), (3, bottom))] {
+            let (x1, y1) = point(from.0, from.1);
+            let (x2, y2) = point(to.0, to.1);
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                x1, y1, x2, y2
+            );
+        }
+    }
+    svg.push_str("</g>\n");
+
+    let river_y = MARGIN + CELL * 9 / 2;
+    for (x, text) in [(2, "楚 河"), (6, "汉 界")] {
+        let _ = writeln!(
+            svg,
+            r##"<text x="{}" y="{}" font-size="22" fill="#5c3b1e" text-anchor="middle" dominant-baseline="central">{}</text>"##,
+            MARGIN + x * CELL,
+            river_y,
+            text
+        );
+    }
+
+    if let Some(mv) = last_move {
+        for pos in [mv.from, mv.to] {
+            let (cx, cy) = point(pos.x, pos.y);
+            let half = CELL / 2;
+            let _ = writeln!(
+                svg,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="#2e86de" stroke-width="2" stroke-dasharray="6 4"/>"##,
+                cx - half,
+                cy - half,
+                CELL,
+                CELL
+            );
+        }
+    }
+
+    for y in 0..10 {
+        for x in 0..9 {
+            let Some(piece) = board.get(Position::from_xy(x, y)) else {
+                continue;
+            };
+            let color = match piece.color {
+                Color::Red => "#c0392b",
+                Color::Black => "#1e1e1e",
+            };
+            let (cx, cy) = point(x, y);
+            let _ = writeln!(
+                svg,
+                r##"<circle cx="{}" cy="{}" r="21" fill="#fbeed5" stroke="{}" stroke-width="2"/>"##,
+                cx, cy, color
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-size="24" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                cx, cy, color, piece
+            );
+        }
+    }
+
+    if let Some(caption) = caption {
+        let _ = writeln!(
+            svg,
+            r##"<text x="{}" y="{}" font-size="20" fill="#1e1e1e" text-anchor="middle">{}</text>"##,
+            width / 2,
+            height - CAPTION_HEIGHT / 2,
+            escape_xml(caption)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caption_adds_room_under_the_board() {
+        let plain = board_svg(&Board::new(), None, None);
+        assert!(plain.contains(r#"height="530""#));
+        assert!(!plain.contains("font-size=\"20\""));
+
+        let captioned = board_svg(&Board::new(), None, Some("a < b"));
+        assert!(captioned.contains(r#"height="570""#));
+        assert_eq!(captioned.matches("&lt;").count(), 1);
+    }
+}
@@ -0,0 +1,1232 @@
+//! Machine-readable reports for the command-line interface
+//!
+//! Each non-interactive CLI command builds one of these reports. The binary
+//! prints them as JSON when `--json` is given and as plain text otherwise,
+//! so editors and scripts can drive the rules engine without linking the
+//! library.
+//!
+//! Errors carry a stable [`ErrorCode`] in addition to the human message.
+
+use crate::ai::{evaluate, SearchResult, Searcher};
+use crate::annotate::{annotate, AnnotateError, AnnotateOptions, Evaluator};
+use crate::archive::ArchiveReader;
+use crate::database::{self, PositionQuery};
+use crate::editor::SetupError;
+use crate::fen::{piece_to_fen, validate_position};
+use crate::fen_io::{read_fen_file, write_fen_file};
+use crate::game::{AiMode, Game, GameState};
+use crate::notation::iccs::{iccs_to_move, move_to_iccs, position_to_iccs};
+use crate::persistence::{Session, SessionError};
+use crate::pgn::PgnGame;
+use crate::pgn_import::{import_pgn, ImportMode};
+use crate::render::report::{game_report, ReportFormat, ReportOptions};
+use crate::rng::RandomSource;
+use crate::types::{Color, Position};
+use crate::xml::{write_xml, xml_to_pgn};
+use serde::Serialize;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+
+/// Stable error codes for machine-readable output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    MissingArgument,
+    UnknownArgument,
+    InvalidArgument,
+    InvalidFen,
+    InvalidMove,
+    Io,
+    ParseFailed,
+    UnsupportedFormat,
+    /// The engine failed or could not be started
+    Engine,
+}
+
+/// An error with a stable code and a human-readable message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorReport {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ErrorReport {
+    /// Create a new error report
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ErrorReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ErrorReport {}
+
+/// Snapshot of a position: FEN, side to move, state and the board grid
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PositionReport {
+    pub fen: String,
+    pub turn: &'static str,
+    pub state: &'static str,
+    pub winner: Option<&'static str>,
+    pub in_check: bool,
+    /// Rows from Black's back rank (y = 0) to Red's (y = 9); pieces use FEN
+    /// letters, empty points are null
+    pub board: Vec<Vec<Option<String>>>,
+}
+
+/// Result of a best-move search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BestMoveReport {
+    pub fen: String,
+    pub best_move: Option<String>,
+    pub score: Option<i32>,
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+/// Result of checking a single move for legality
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MoveCheckReport {
+    pub fen: String,
+    #[serde(rename = "move")]
+    pub mv: String,
+    pub legal: bool,
+    pub reason: Option<String>,
+    pub fen_after: Option<String>,
+    pub gives_check: bool,
+}
+
+/// Static and search-based analysis of a position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AnalysisReport {
+    pub position: PositionReport,
+    /// Static evaluation from the side to move's point of view
+    pub eval: i32,
+    pub legal_moves: Vec<String>,
+    pub best_move: BestMoveReport,
+}
+
+/// Result of converting a game file between PGN and XML
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConvertReport {
+    pub input: String,
+    pub output: String,
+    pub from: &'static str,
+    pub to: &'static str,
+    /// Games converted; more than one only from PGN to PGN
+    pub games: usize,
+    /// Moves in all the games
+    pub moves: usize,
+}
+
+/// Result of converting every game file in a directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchConvertReport {
+    pub input: String,
+    pub output: String,
+    pub to: &'static str,
+    pub converted: Vec<ConvertReport>,
+    pub failed: Vec<FailedConversion>,
+}
+
+/// A file a batch conversion could not convert
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailedConversion {
+    pub input: String,
+    pub error: ErrorReport,
+}
+
+/// Result of exporting the per-move FEN list of a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FenHistoryReport {
+    pub input: String,
+    pub output: String,
+    pub positions: usize,
+}
+
+/// Result of writing a Markdown or HTML report of a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameReportExport {
+    pub input: String,
+    pub output: String,
+    pub moves: usize,
+    pub diagrams: usize,
+}
+
+/// Result of annotating a game file with engine evaluations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AnnotationExport {
+    pub input: String,
+    pub output: String,
+    pub annotator: String,
+    pub moves: usize,
+    pub blunders: usize,
+}
+
+/// Games in a PGN collection that reach a position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PositionSearchReport {
+    pub query: String,
+    pub path: String,
+    pub searched: usize,
+    pub skipped: usize,
+    pub matches: Vec<GameMatchReport>,
+}
+
+/// Games in a PGN collection that repeat an earlier one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DedupeReport {
+    pub path: String,
+    pub checked: usize,
+    pub skipped: usize,
+    pub duplicates: Vec<DuplicateReport>,
+    /// Duplicates deleted from their files, 0 unless asked to remove them
+    pub removed: usize,
+}
+
+/// A repeated game and the first copy of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateReport {
+    pub file: String,
+    /// Index of the game in its file, from 0
+    pub game: usize,
+    pub original_file: String,
+    pub original_game: usize,
+    pub red: Option<String>,
+    pub black: Option<String>,
+}
+
+/// One game found by a position search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameMatchReport {
+    pub file: String,
+    /// Index of the game in its file, from 0
+    pub game: usize,
+    /// Half-moves played before the position is first reached
+    pub ply: usize,
+    pub red: Option<String>,
+    pub black: Option<String>,
+    pub event: Option<String>,
+    pub result: Option<String>,
+}
+
+/// Problems found in a FEN position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub fen: String,
+    pub valid: bool,
+    pub problems: Vec<ProblemReport>,
+}
+
+/// One reason a position could not arise in a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProblemReport {
+    /// Stable name of the rule broken, e.g. `illegal_square`
+    pub code: &'static str,
+    pub message: String,
+    /// The point concerned, in ICCS, for a piece on a point it can't reach
+    pub square: Option<String>,
+}
+
+/// Leaf node count of a position, split by first move
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PerftReport {
+    pub fen: String,
+    pub depth: u32,
+    pub nodes: u64,
+    /// Nodes under each legal move, in ICCS, for tracking down a wrong count
+    pub moves: Vec<PerftMove>,
+}
+
+/// Nodes under one first move of a perft count
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PerftMove {
+    #[serde(rename = "move")]
+    pub mv: String,
+    pub nodes: u64,
+}
+
+/// One line of `--stdin` output, for a position read from standard input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PipelineReport {
+    /// Line of the input the FEN was on, from 1
+    pub line: usize,
+    pub fen: String,
+    pub turn: &'static str,
+    pub state: &'static str,
+    pub winner: Option<&'static str>,
+    pub in_check: bool,
+    pub legal_moves: usize,
+    /// The evaluator's best move in ICCS; null without analysis or a legal
+    /// move
+    pub best_move: Option<String>,
+    /// Centipawns from the side to move's point of view, a mate in n
+    /// moves being 10000 - n; null without analysis
+    pub eval: Option<i32>,
+}
+
+/// A line of `--stdin` input that could not be analyzed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PipelineError {
+    pub line: usize,
+    pub error: ErrorReport,
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::Red => "red",
+        Color::Black => "black",
+    }
+}
+
+/// Parse a FEN string into a game, mapping failures to an error report
+pub fn game_from_fen(fen: &str) -> Result<Game, ErrorReport> {
+    Game::from_fen(fen).map_err(|e| ErrorReport::new(ErrorCode::InvalidFen, e.to_string()))
+}
+
+/// Build a position report for a game
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::{report::position_report, Game};
+///
+/// let report = position_report(&Game::new());
+/// assert_eq!(report.turn, "red");
+/// assert_eq!(report.board[9][4].as_deref(), Some("K"));
+/// ```
+pub fn position_report(game: &Game) -> PositionReport {
+    let board = (0..10)
+        .map(|y| {
+            (0..9)
+                .map(|x| {
+                    game.board()
+                        .get(Position::from_xy(x, y))
+                        .map(|p| piece_to_fen(*p).to_string())
+                })
+                .collect()
+        })
+        .collect();
+
+    let (state, winner) = state_names(game.state());
+    PositionReport {
+        fen: game.to_fen(),
+        turn: color_name(game.turn()),
+        state,
+        winner,
+        in_check: game.is_in_check(),
+        board,
+    }
+}
+
+/// A game state's name in reports, and the winner's if there is one
+fn state_names(state: GameState) -> (&'static str, Option<&'static str>) {
+    match state {
+        GameState::Playing => ("playing", None),
+        GameState::Checkmate(color) => ("checkmate", Some(color_name(color))),
+        GameState::Stalemate => ("stalemate", None),
+        GameState::StalemateWin(color) => ("stalemate", Some(color_name(color))),
+        GameState::DrawByRepetition => ("draw_by_repetition", None),
+        GameState::DrawByMoveLimit => ("draw_by_move_limit", None),
+        GameState::MissingGeneral(color) => ("missing_general", Some(color_name(color.opponent()))),
+        GameState::PerpetualCheck(color) => ("perpetual_check", Some(color_name(color))),
+        GameState::Timeout(color) => ("timeout", Some(color_name(color))),
+        GameState::Adjudicated(winner) => ("adjudicated", winner.map(color_name)),
+    }
+}
+
+/// Search for the best move with the built-in AI
+pub fn best_move_report(game: &Game, depth: u32) -> BestMoveReport {
+    let mut searcher = Searcher::new(depth);
+    let result = searcher.search(game.board(), game.turn());
+    move_report(game, &searcher, result)
+}
+
+/// Search with the built-in AI, picking at random among moves within
+/// `variety` centipawns of the best, or `blunder_rate` percent of the time
+/// among every legal move
+///
+/// Uses the same search as the TUI's AI, so the same seed gives the same move.
+pub fn varied_move_report(
+    game: &Game,
+    depth: u32,
+    variety: i32,
+    blunder_rate: u32,
+    rng: &mut dyn RandomSource,
+) -> BestMoveReport {
+    let mut searcher = Searcher::new(depth);
+    let result =
+        searcher.search_with_blunders(game.board(), game.turn(), variety, blunder_rate, rng);
+    move_report(game, &searcher, result)
+}
+
+fn move_report(game: &Game, searcher: &Searcher, result: Option<SearchResult>) -> BestMoveReport {
+    BestMoveReport {
+        fen: game.to_fen(),
+        best_move: result.map(|r| move_to_iccs(r.mv.from, r.mv.to)),
+        score: result.map(|r| r.score),
+        depth: searcher.depth(),
+        nodes: result.map(|r| r.nodes).unwrap_or(0),
+    }
+}
+
+/// Check whether an ICCS move (e.g. "h7e7") is legal in the game's position
+///
+/// A malformed move string is an error; a well-formed but illegal move is
+/// reported with `legal: false` and the reason.
+pub fn check_move_report(game: &Game, mv: &str) -> Result<MoveCheckReport, ErrorReport> {
+    let (from, to) = iccs_to_move(mv).ok_or_else(|| {
+        ErrorReport::new(ErrorCode::InvalidMove, format!("Invalid ICCS move: {}", mv))
+    })?;
+
+    let mut after = game.clone();
+    let (legal, reason, fen_after, gives_check) = match after.make_move(from, to) {
+        Ok(()) => (true, None, Some(after.to_fen()), after.is_in_check()),
+        Err(e) => (false, Some(e.to_string()), None, false),
+    };
+
+    Ok(MoveCheckReport {
+        fen: game.to_fen(),
+        mv: move_to_iccs(from, to),
+        legal,
+        reason,
+        fen_after,
+        gives_check,
+    })
+}
+
+/// Check that a FEN is readable and describes a position that could arise
+/// in a game
+///
+/// An unreadable FEN is an error; a readable one lists every problem.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::report::validation_report;
+///
+/// let report = validation_report("4k4/9/9/9/9/9/9/9/9/3K1K3 w - - 0 1").unwrap();
+/// assert!(!report.valid);
+/// assert_eq!(report.problems[0].code, "general_count");
+/// assert_eq!(report.problems[0].message, "Red has 2 generals");
+/// ```
+pub fn validation_report(fen: &str) -> Result<ValidationReport, ErrorReport> {
+    let problems: Vec<ProblemReport> = validate_position(fen)
+        .map_err(|e| ErrorReport::new(ErrorCode::InvalidFen, e.to_string()))?
+        .into_iter()
+        .map(|problem| {
+            let (code, square) = match problem {
+                SetupError::GeneralCount(..) => ("general_count", None),
+                SetupError::IllegalSquare(pos, _) => {
+                    ("illegal_square", Some(position_to_iccs(pos)))
+                }
+                SetupError::TooMany(..) => ("too_many", None),
+                SetupError::GeneralsFacing => ("generals_facing", None),
+                SetupError::OpponentInCheck(_) => ("opponent_in_check", None),
+            };
+            ProblemReport {
+                code,
+                message: problem.to_string(),
+                square,
+            }
+        })
+        .collect();
+
+    Ok(ValidationReport {
+        fen: fen.trim().to_string(),
+        valid: problems.is_empty(),
+        problems,
+    })
+}
+
+/// Analyze a position: evaluation, legal moves and the best move
+pub fn analysis_report(game: &Game, depth: u32) -> AnalysisReport {
+    let legal_moves = game
+        .board()
+        .legal_moves(game.turn())
+        .into_iter()
+        .map(|(from, to)| move_to_iccs(from, to))
+        .collect();
+
+    AnalysisReport {
+        position: position_report(game),
+        eval: evaluate(game.board(), game.turn()),
+        legal_moves,
+        best_move: best_move_report(game, depth),
+    }
+}
+
+/// Report on the position a FEN describes, with the evaluator's best move
+/// and score if one is given
+pub fn pipeline_report(
+    line: usize,
+    fen: &str,
+    evaluator: Option<&mut dyn Evaluator>,
+) -> Result<PipelineReport, ErrorReport> {
+    let game = game_from_fen(fen)?;
+    let evaluation = evaluator
+        .map(|evaluator| evaluator.evaluate(&game))
+        .transpose()
+        .map_err(|e| ErrorReport::new(ErrorCode::Engine, e.to_string()))?;
+    let (state, winner) = state_names(game.state());
+    Ok(PipelineReport {
+        line,
+        fen: game.to_fen(),
+        turn: color_name(game.turn()),
+        state,
+        winner,
+        in_check: game.is_in_check(),
+        legal_moves: game.board().legal_moves(game.turn()).len(),
+        eval: evaluation.as_ref().map(|e| e.score),
+        best_move: evaluation.and_then(|e| e.best_move),
+    })
+}
+
+/// Read FENs one per line from `input` and write a JSON line about each to
+/// `output`, as [`PipelineReport`]s
+///
+/// Blank lines are skipped. A line that isn't a readable FEN, or that the
+/// evaluator fails on, gets a [`PipelineError`] line and the rest are still
+/// read. Each line is flushed as it is written, so another program can read
+/// the answers while it writes positions. Returns the number of errors.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::report::analyze_lines;
+///
+/// let input = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1\nbad\n";
+/// let mut output = Vec::new();
+/// assert_eq!(analyze_lines(input.as_bytes(), &mut output, None).unwrap(), 1);
+/// let lines: Vec<serde_json::Value> = output
+///     .split(|&b| b == b'\n')
+///     .filter(|line| !line.is_empty())
+///     .map(|line| serde_json::from_slice(line).unwrap())
+///     .collect();
+/// assert_eq!(lines[0]["legal_moves"], 44);
+/// assert_eq!(lines[1]["error"]["code"], "invalid_fen");
+/// ```
+pub fn analyze_lines<R: BufRead, W: Write>(
+    input: R,
+    mut output: W,
+    mut evaluator: Option<&mut dyn Evaluator>,
+) -> std::io::Result<usize> {
+    let mut errors = 0;
+    for (index, text) in input.lines().enumerate() {
+        let text = text?;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let line = index + 1;
+        let evaluator = evaluator.as_mut().map(|e| &mut **e as &mut dyn Evaluator);
+        let json = match pipeline_report(line, &text, evaluator) {
+            Ok(report) => serde_json::to_string(&report),
+            Err(error) => {
+                errors += 1;
+                serde_json::to_string(&PipelineError { line, error })
+            }
+        }
+        .map_err(std::io::Error::other)?;
+        writeln!(output, "{}", json)?;
+        output.flush()?;
+    }
+    Ok(errors)
+}
+
+/// Count the move sequences of `depth` plies from a position
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::{report::perft_report, Game};
+///
+/// let report = perft_report(&Game::new(), 2);
+/// assert_eq!(report.nodes, 1920);
+/// assert_eq!(report.moves.len(), 44);
+/// ```
+pub fn perft_report(game: &Game, depth: u32) -> PerftReport {
+    let board = game.board();
+    let moves: Vec<PerftMove> = match depth {
+        0 => Vec::new(),
+        _ => board
+            .legal_moves(game.turn())
+            .into_iter()
+            .map(|(from, to)| {
+                let mut after = board.clone();
+                after.move_piece(from, to);
+                PerftMove {
+                    mv: move_to_iccs(from, to),
+                    nodes: after.perft(game.turn().opponent(), depth - 1),
+                }
+            })
+            .collect(),
+    };
+
+    PerftReport {
+        fen: game.to_fen(),
+        depth,
+        nodes: match depth {
+            0 => 1,
+            _ => moves.iter().map(|m| m.nodes).sum(),
+        },
+        moves,
+    }
+}
+
+/// Game file formats, by extension
+const FORMATS: [&str; 4] = ["pgn", "xml", "fen", "json"];
+
+fn file_format(path: &str) -> Result<&'static str, ErrorReport> {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    format_by_name(ext.as_deref().unwrap_or_default()).ok_or_else(|| {
+        ErrorReport::new(
+            ErrorCode::UnsupportedFormat,
+            format!(
+                "Unsupported file type (expected .pgn, .xml, .fen or .json): {}",
+                path
+            ),
+        )
+    })
+}
+
+fn format_by_name(name: &str) -> Option<&'static str> {
+    FORMATS
+        .into_iter()
+        .find(|format| format.eq_ignore_ascii_case(name))
+}
+
+/// Read the one game of a file in any of the [`FORMATS`]
+///
+/// A PGN file holding several games is refused, naming how many it holds.
+fn read_game_file(path: &str) -> Result<PgnGame, ErrorReport> {
+    let mut games = read_games_file(path)?;
+    if games.len() > 1 {
+        return Err(ErrorReport::new(
+            ErrorCode::InvalidArgument,
+            format!("{}: holds {} games, expected one", path, games.len()),
+        ));
+    }
+    Ok(games.remove(0))
+}
+
+/// Read every game of a file in any of the [`FORMATS`]
+///
+/// Only a PGN file can hold more than one game; one that can't be read
+/// fails the whole file, and so does a file without any. A `.fen` file
+/// holds one position, optionally followed by `moves` and ICCS moves; a
+/// `.json` file is a saved [`Session`].
+fn read_games_file(path: &str) -> Result<Vec<PgnGame>, ErrorReport> {
+    let io_error = |e: &dyn Display| ErrorReport::new(ErrorCode::Io, format!("{}: {}", path, e));
+    let parse_error =
+        |e: &dyn Display| ErrorReport::new(ErrorCode::ParseFailed, format!("{}: {}", path, e));
+
+    let game = match file_format(path)? {
+        "fen" => {
+            let fen = read_fen_file(path).map_err(|e| io_error(&e))?;
+            let game = if fen.contains(" moves ") {
+                Game::from_fen_with_moves(&fen)
+            } else {
+                Game::from_fen(&fen)
+            };
+            game.map(|game| game.to_pgn())
+                .map_err(|e| ErrorReport::new(ErrorCode::InvalidFen, format!("{}: {}", path, e)))
+        }
+        "json" => match Session::load_from(Path::new(path)) {
+            Ok(session) => session.to_game().map(|game| game.to_pgn()),
+            Err(e) => Err(e),
+        }
+        .map_err(|e| match e {
+            SessionError::Io(e) => io_error(&e),
+            e => parse_error(&e),
+        }),
+        "xml" => {
+            let content = std::fs::read_to_string(path).map_err(|e| io_error(&e))?;
+            xml_to_pgn(&content).ok_or_else(|| {
+                ErrorReport::new(ErrorCode::ParseFailed, format!("Failed to parse {}", path))
+            })
+        }
+        _ => return read_pgn_games(path),
+    };
+    game.map(|game| vec![game])
+}
+
+/// Read every game of a PGN file, one at a time
+fn read_pgn_games(path: &str) -> Result<Vec<PgnGame>, ErrorReport> {
+    let games = ArchiveReader::open(path)
+        .and_then(|archive| archive.iter())
+        .map_err(|e| ErrorReport::new(ErrorCode::Io, format!("{}: {}", path, e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ErrorReport::new(ErrorCode::ParseFailed, format!("{}: {}", path, e)))?;
+    if games.is_empty() {
+        return Err(ErrorReport::new(
+            ErrorCode::ParseFailed,
+            format!("Failed to parse {}", path),
+        ));
+    }
+    Ok(games)
+}
+
+/// Write a game to a file in one of the [`FORMATS`]
+///
+/// A `.fen` file gets the final position and a `.json` file a [`Session`]
+/// with every move; both replay the game, so it must be legal.
+fn write_game_file(game: &PgnGame, path: &str, format: &str) -> Result<(), ErrorReport> {
+    let io_error = |e: std::io::Error| ErrorReport::new(ErrorCode::Io, format!("{}: {}", path, e));
+    match format {
+        "fen" | "json" => {
+            let game = replay(game)?;
+            if format == "fen" {
+                write_fen_file(path, &format!("{}\n", game.to_fen())).map_err(io_error)
+            } else {
+                Session::from_game(&game, AiMode::Off)
+                    .save_to(Path::new(path))
+                    .map_err(io_error)
+            }
+        }
+        _ => File::create(path)
+            .map(BufWriter::new)
+            .and_then(|mut out| {
+                match format {
+                    "xml" => write_xml(game, &mut out)?,
+                    _ => game.write_pgn(&mut out)?,
+                }
+                out.flush()
+            })
+            .map_err(io_error),
+    }
+}
+
+/// Write the games of a PGN file back to back, as PGN
+fn write_pgn_games(games: &[PgnGame], path: &str) -> Result<(), ErrorReport> {
+    File::create(path)
+        .map(BufWriter::new)
+        .and_then(|mut out| {
+            for (i, game) in games.iter().enumerate() {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                game.write_pgn(&mut out)?;
+            }
+            out.flush()
+        })
+        .map_err(|e| ErrorReport::new(ErrorCode::Io, format!("{}: {}", path, e)))
+}
+
+/// Replay a game's moves, failing at the first illegal one
+fn replay(pgn: &PgnGame) -> Result<Game, ErrorReport> {
+    let report = import_pgn(pgn, ImportMode::Strict)
+        .map_err(|e| ErrorReport::new(ErrorCode::InvalidFen, e.to_string()))?;
+    match report.issues.first() {
+        Some(issue) => Err(ErrorReport::new(ErrorCode::InvalidMove, issue.to_string())),
+        None => Ok(report.game),
+    }
+}
+
+/// Convert a game file between PGN, XML, FEN and JSON, picking formats by
+/// extension
+///
+/// A PGN file may hold several games. Converted to PGN they are all kept;
+/// the other formats hold one game, so such a file is refused.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::report::convert_file;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let pgn = dir.path().join("game.pgn");
+/// std::fs::write(&pgn, "1. h7e7 h0g2 *").unwrap();
+/// let fen = dir.path().join("game.fen");
+///
+/// let report = convert_file(pgn.to_str().unwrap(), fen.to_str().unwrap()).unwrap();
+/// assert_eq!((report.from, report.to, report.moves), ("pgn", "fen", 2));
+/// assert!(std::fs::read_to_string(&fen).unwrap().ends_with(" w - - 2 2\n"));
+/// ```
+pub fn convert_file(input: &str, output: &str) -> Result<ConvertReport, ErrorReport> {
+    let from = file_format(input)?;
+    let to = file_format(output)?;
+    let games = read_games_file(input)?;
+    match games.as_slice() {
+        [game] => write_game_file(game, output, to)?,
+        _ if to == "pgn" => write_pgn_games(&games, output)?,
+        _ => {
+            return Err(ErrorReport::new(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "{}: holds {} games, but a {} file holds one; convert it to PGN",
+                    input,
+                    games.len(),
+                    to
+                ),
+            ))
+        }
+    }
+
+    Ok(ConvertReport {
+        input: input.to_string(),
+        output: output.to_string(),
+        from,
+        to,
+        games: games.len(),
+        moves: games.iter().map(|game| game.moves.len()).sum(),
+    })
+}
+
+/// Write a game from any of the [`FORMATS`] to `output` as XML, whatever
+/// the output's extension
+///
+/// The game is replayed first, so one with illegal moves is refused.
+pub fn export_xml(input: &str, output: &str) -> Result<ConvertReport, ErrorReport> {
+    let from = file_format(input)?;
+    let game = read_game_file(input)?;
+    replay(&game)?;
+    write_game_file(&game, output, "xml")?;
+
+    Ok(ConvertReport {
+        input: input.to_string(),
+        output: output.to_string(),
+        from,
+        to: "xml",
+        games: 1,
+        moves: game.moves.len(),
+    })
+}
+
+/// Convert every game file directly in `input` to `format`, writing
+/// `<output>/<name>.<format>`
+///
+/// Files of other types are skipped. A file that fails is reported and the
+/// rest are still converted; only a directory that can't be read or
+/// created is an error.
+pub fn convert_directory(
+    input: &str,
+    output: &str,
+    format: &str,
+) -> Result<BatchConvertReport, ErrorReport> {
+    let to = format_by_name(format).ok_or_else(|| {
+        ErrorReport::new(
+            ErrorCode::UnsupportedFormat,
+            format!(
+                "Unsupported format (expected pgn, xml, fen or json): {}",
+                format
+            ),
+        )
+    })?;
+    let io_error =
+        |path: &str, e: std::io::Error| ErrorReport::new(ErrorCode::Io, format!("{}: {}", path, e));
+    std::fs::create_dir_all(output).map_err(|e| io_error(output, e))?;
+
+    let mut inputs: Vec<_> = std::fs::read_dir(input)
+        .map_err(|e| io_error(input, e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && file_format(&path.to_string_lossy()).is_ok())
+        .collect();
+    inputs.sort();
+
+    let mut report = BatchConvertReport {
+        input: input.to_string(),
+        output: output.to_string(),
+        to,
+        converted: Vec::new(),
+        failed: Vec::new(),
+    };
+    for path in inputs {
+        let source = path.to_string_lossy().into_owned();
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let target = Path::new(output).join(format!("{}.{}", stem, to));
+        match convert_file(&source, &target.to_string_lossy()) {
+            Ok(converted) => report.converted.push(converted),
+            Err(error) => report.failed.push(FailedConversion {
+                input: source,
+                error,
+            }),
+        }
+    }
+    Ok(report)
+}
+
+/// List the games in a PGN file or directory that reach a position
+///
+/// `query` is a FEN, or a material signature such as `RCPrcp`; see
+/// [`PositionQuery::parse`].
+pub fn search_position_report(
+    query: &str,
+    path: &str,
+) -> Result<PositionSearchReport, ErrorReport> {
+    let parsed = PositionQuery::parse(query)
+        .map_err(|e| ErrorReport::new(ErrorCode::InvalidFen, e.to_string()))?;
+    let results = database::search(Path::new(path), &parsed)
+        .map_err(|e| ErrorReport::new(ErrorCode::Io, format!("{}: {}", path, e)))?;
+
+    let tag = |game: &PgnGame, key: &str| game.get_tag(key).cloned();
+    Ok(PositionSearchReport {
+        query: query.trim().to_string(),
+        path: path.to_string(),
+        searched: results.searched,
+        skipped: results.skipped,
+        matches: results
+            .matches
+            .iter()
+            .map(|found| GameMatchReport {
+                file: found.path.to_string_lossy().into_owned(),
+                game: found.index,
+                ply: found.ply,
+                red: tag(&found.game, "Red"),
+                black: tag(&found.game, "Black"),
+                event: tag(&found.game, "Event"),
+                result: tag(&found.game, "Result"),
+            })
+            .collect(),
+    })
+}
+
+/// List the games in a PGN file or directory that repeat an earlier game,
+/// and delete them from their files if `remove` is set
+///
+/// See [`database::find_duplicates`] for what counts as the same game.
+pub fn dedupe_report(path: &str, remove: bool) -> Result<DedupeReport, ErrorReport> {
+    let io_error = |e: std::io::Error| ErrorReport::new(ErrorCode::Io, format!("{}: {}", path, e));
+    let results = database::find_duplicates(Path::new(path)).map_err(io_error)?;
+    let removed = if remove {
+        database::remove_duplicates(&results).map_err(io_error)?
+    } else {
+        0
+    };
+
+    let file = |path: &Path| path.to_string_lossy().into_owned();
+    let name = |name: &String| (!name.is_empty()).then(|| name.clone());
+    Ok(DedupeReport {
+        path: path.to_string(),
+        checked: results.checked,
+        skipped: results.skipped,
+        removed,
+        duplicates: results
+            .duplicates
+            .iter()
+            .map(|duplicate| DuplicateReport {
+                file: file(&duplicate.location.path),
+                game: duplicate.location.index,
+                original_file: file(&duplicate.original.path),
+                original_game: duplicate.original.index,
+                red: name(&duplicate.fingerprint.red),
+                black: name(&duplicate.fingerprint.black),
+            })
+            .collect(),
+    })
+}
+
+/// Read and replay a single-game PGN, XML, FEN or JSON file
+fn read_game(input: &str) -> Result<Game, ErrorReport> {
+    replay(&read_game_file(input)?)
+}
+
+/// Write one line per half-move: the ICCS move, a space, then the FEN after it
+pub fn write_fen_history<W: Write>(game: &Game, out: &mut W) -> std::io::Result<()> {
+    for (mv, fen) in game.fen_history() {
+        writeln!(out, "{} {}", mv, fen)?;
+    }
+    Ok(())
+}
+
+/// Export the FEN after every half-move of a PGN or XML game to a text file
+pub fn export_fen_history(input: &str, output: &str) -> Result<FenHistoryReport, ErrorReport> {
+    let game = read_game(input)?;
+
+    File::create(output)
+        .map(BufWriter::new)
+        .and_then(|mut out| {
+            write_fen_history(&game, &mut out)?;
+            out.flush()
+        })
+        .map_err(|e| ErrorReport::new(ErrorCode::Io, format!("{}: {}", output, e)))?;
+
+    Ok(FenHistoryReport {
+        input: input.to_string(),
+        output: output.to_string(),
+        positions: game.get_moves().len(),
+    })
+}
+
+/// Write a Markdown or HTML report of a game file, picking the markup by
+/// the output's extension
+///
+/// `every` is the number of moves between regular diagrams; key moments
+/// and the final position always get one.
+pub fn export_game_report(
+    input: &str,
+    output: &str,
+    every: Option<usize>,
+) -> Result<GameReportExport, ErrorReport> {
+    let format = ReportFormat::from_path(Path::new(output)).ok_or_else(|| {
+        ErrorReport::new(
+            ErrorCode::UnsupportedFormat,
+            format!(
+                "Unsupported report type (expected .md or .html): {}",
+                output
+            ),
+        )
+    })?;
+    let pgn = read_game_file(input)?;
+    let game = replay(&pgn)?;
+    let options = ReportOptions {
+        every,
+        ..ReportOptions::new(format)
+    };
+    let report = game_report(&pgn, &options)
+        .map_err(|e| ErrorReport::new(ErrorCode::InvalidFen, e.to_string()))?;
+    std::fs::write(output, &report.text)
+        .map_err(|e| ErrorReport::new(ErrorCode::Io, format!("{}: {}", output, e)))?;
+
+    Ok(GameReportExport {
+        input: input.to_string(),
+        output: output.to_string(),
+        moves: game.get_moves().len(),
+        diagrams: report.diagrams,
+    })
+}
+
+/// Annotate a game file with `evaluator` and write it as PGN
+///
+/// `progress` is called as in [`annotate`].
+pub fn annotate_game_file(
+    input: &str,
+    output: &str,
+    evaluator: &mut dyn Evaluator,
+    options: &AnnotateOptions,
+    progress: &mut dyn FnMut(usize, usize),
+) -> Result<AnnotationExport, ErrorReport> {
+    let pgn = read_game_file(input)?;
+    let annotated = annotate(&pgn, evaluator, options, progress).map_err(|e| match e {
+        AnnotateError::Fen(e) => ErrorReport::new(ErrorCode::InvalidFen, e.to_string()),
+        AnnotateError::IllegalMove(issue) => ErrorReport::new(ErrorCode::InvalidMove, issue),
+        AnnotateError::Engine(e) => ErrorReport::new(ErrorCode::Engine, e.to_string()),
+    })?;
+    std::fs::write(output, annotated.pgn.to_pgn())
+        .map_err(|e| ErrorReport::new(ErrorCode::Io, format!("{}: {}", output, e)))?;
+
+    Ok(AnnotationExport {
+        input: input.to_string(),
+        output: output.to_string(),
+        annotator: evaluator.name(),
+        moves: annotated.moves.len(),
+        blunders: annotated.blunders(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_report_json() {
+        let json = serde_json::to_value(position_report(&Game::new())).unwrap();
+        assert_eq!(json["turn"], "red");
+        assert_eq!(json["state"], "playing");
+        assert_eq!(json["board"][0][0], "r");
+        assert!(json["board"][4][0].is_null());
+    }
+
+    #[test]
+    fn test_check_move_report() {
+        let game = Game::new();
+        let legal = check_move_report(&game, "h7e7").unwrap();
+        assert!(legal.legal);
+        assert!(legal.fen_after.is_some());
+
+        let illegal = check_move_report(&game, "h7h1").unwrap();
+        assert!(!illegal.legal);
+        assert!(illegal.reason.is_some());
+
+        let err = check_move_report(&game, "zz").unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidMove);
+    }
+
+    #[test]
+    fn test_pipeline_report_with_native_analysis() {
+        use crate::annotate::NativeEvaluator;
+
+        // Red's chariot mates on a0
+        let fen = "4k4/8R/9/9/9/R8/9/9/9/3K5 w - - 0 1";
+        let mut evaluator = NativeEvaluator::new(2);
+        let report = pipeline_report(3, fen, Some(&mut evaluator)).unwrap();
+        assert_eq!(report.line, 3);
+        assert_eq!(report.turn, "red");
+        assert!(!report.in_check);
+        assert_eq!(report.best_move.as_deref(), Some("a5a0"));
+        // Mate in one
+        assert_eq!(report.eval, Some(crate::diagram::MATE_SCORE - 1));
+
+        let report = pipeline_report(1, fen, None).unwrap();
+        assert_eq!((report.best_move, report.eval), (None, None));
+    }
+
+    #[test]
+    fn test_write_fen_history() {
+        let mut game = Game::new();
+        game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+            .unwrap();
+        game.make_move(Position::from_xy(7, 0), Position::from_xy(6, 2))
+            .unwrap();
+
+        let mut buf = Vec::new();
+        write_fen_history(&game, &mut buf).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("h7e7 "));
+        assert!(lines[0].ends_with(" b - - 1 1"));
+        assert_eq!(lines[1], format!("h0g2 {}", game.to_fen()));
+    }
+
+    #[test]
+    fn test_export_xml() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("game.fen");
+        let start = Game::new().to_fen();
+        std::fs::write(&input, format!("{} moves h7e7 h0g2\n", start)).unwrap();
+        let output = dir.path().join("game.out");
+
+        let result = export_xml(input.to_str().unwrap(), output.to_str().unwrap()).unwrap();
+        assert_eq!((result.from, result.to, result.moves), ("fen", "xml", 2));
+        let xml = std::fs::read_to_string(&output).unwrap();
+        assert_eq!(xml_to_pgn(&xml).unwrap().moves.len(), 2);
+
+        let bad = dir.path().join("bad.pgn");
+        std::fs::write(&bad, "1. h7e7 h7e7 *").unwrap();
+        let error = export_xml(bad.to_str().unwrap(), output.to_str().unwrap()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidMove);
+    }
+
+    #[test]
+    fn test_convert_directory_reports_each_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("games");
+        std::fs::create_dir(&input).unwrap();
+        std::fs::write(input.join("good.pgn"), "1. h7e7 h0g2 *").unwrap();
+        std::fs::write(input.join("bad.pgn"), "1. h7e7 h7e7 *").unwrap();
+        std::fs::write(input.join("notes.txt"), "not a game").unwrap();
+        let output = dir.path().join("out");
+
+        let report =
+            convert_directory(input.to_str().unwrap(), output.to_str().unwrap(), "JSON").unwrap();
+        assert_eq!(report.to, "json");
+        assert_eq!(report.converted.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].input.ends_with("bad.pgn"));
+        assert_eq!(report.failed[0].error.code, ErrorCode::InvalidMove);
+
+        // The JSON session converts back with its moves
+        let back = dir.path().join("back.pgn");
+        let json = output.join("good.json");
+        let result = convert_file(json.to_str().unwrap(), back.to_str().unwrap()).unwrap();
+        assert_eq!(result.moves, 2);
+        assert!(convert_directory(input.to_str().unwrap(), "unused", "svg").is_err());
+    }
+
+    #[test]
+    fn test_convert_multi_game_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("games");
+        std::fs::create_dir(&input).unwrap();
+        let archive = input.join("archive.pgn");
+        std::fs::write(
+            &archive,
+            "[Event \"One\"]\n\n1. h7e7 h0g2 *\n\n[Event \"Two\"]\n\n1. b7e7 b0c2 2. h9g7 1-0\n",
+        )
+        .unwrap();
+        std::fs::write(input.join("single.pgn"), "1. h7e7 h0g2 *").unwrap();
+        let archive = archive.to_str().unwrap();
+
+        // To PGN every game is kept, tags and moves apart
+        let output = dir.path().join("copy.pgn");
+        let result = convert_file(archive, output.to_str().unwrap()).unwrap();
+        assert_eq!((result.games, result.moves), (2, 5));
+        let games = ArchiveReader::open(&output).unwrap().read_all().unwrap();
+        assert!(games.errors.is_empty());
+        let events: Vec<&String> = games
+            .games
+            .iter()
+            .map(|game| game.get_tag("Event").unwrap())
+            .collect();
+        assert_eq!(events, ["One", "Two"]);
+        assert_eq!(games.games[1].moves[2].notation, "h9g7");
+
+        // Single-game formats refuse it, naming the file
+        let xml = dir.path().join("archive.xml");
+        let error = convert_file(archive, xml.to_str().unwrap()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidArgument);
+        assert!(error.message.contains("2 games"), "{}", error.message);
+        let error = export_xml(archive, xml.to_str().unwrap()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidArgument);
+        let report = dir.path().join("archive.md");
+        let error = export_game_report(archive, report.to_str().unwrap(), None).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidArgument);
+
+        // A batch reports the archive and converts the rest
+        let output = dir.path().join("out");
+        let report =
+            convert_directory(input.to_str().unwrap(), output.to_str().unwrap(), "xml").unwrap();
+        assert_eq!(report.converted.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].input.ends_with("archive.pgn"));
+    }
+
+    #[test]
+    fn test_export_game_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("game.pgn");
+        std::fs::write(&input, "1. h7e7 {Central cannon} h0g2 2. h9g7 *").unwrap();
+        let input = input.to_str().unwrap();
+
+        let output = dir.path().join("game.html");
+        let result = export_game_report(input, output.to_str().unwrap(), None).unwrap();
+        assert_eq!((result.moves, result.diagrams), (3, 2));
+        let html = std::fs::read_to_string(&output).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+
+        let error = export_game_report(input, "game.txt", None).unwrap_err();
+        assert_eq!(error.code, ErrorCode::UnsupportedFormat);
+    }
+
+    #[test]
+    fn test_annotate_game_file() {
+        use crate::annotate::NativeEvaluator;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("game.pgn");
+        std::fs::write(&input, "1. h7e7 h0g2 2. h9g7 *").unwrap();
+        let output = dir.path().join("annotated.pgn");
+        let result = annotate_game_file(
+            input.to_str().unwrap(),
+            output.to_str().unwrap(),
+            &mut NativeEvaluator::new(1),
+            &AnnotateOptions::default(),
+            &mut |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(result.moves, 3);
+        assert_eq!(result.annotator, "cn_chess_tui depth 1");
+
+        let pgn = PgnGame::parse(&std::fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(pgn.get_tag("Annotator").unwrap(), "cn_chess_tui depth 1");
+        assert!(pgn
+            .moves
+            .iter()
+            .all(|mv| crate::diagram::parse_eval(mv.comment.as_deref().unwrap()).is_some()));
+    }
+
+    #[test]
+    fn test_error_code_serialization() {
+        let err = ErrorReport::new(ErrorCode::InvalidFen, "bad");
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(json, r#"{"code":"invalid_fen","message":"bad"}"#);
+    }
+}
@@ -0,0 +1,109 @@
+//! Random number source for every stochastic feature
+//!
+//! Nothing in the crate calls a global RNG. Features that need randomness
+//! take a `&mut dyn RandomSource`, so a seed from `config.toml` or `--seed`
+//! reproduces the same behavior in the TUI and in headless commands, and
+//! tests can plug in their own source.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of random numbers
+pub trait RandomSource {
+    /// Next 64 random bits
+    fn next_u64(&mut self) -> u64;
+
+    /// Uniform integer in `0..n`; returns 0 when `n` is 0
+    fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        // Widening multiply keeps the result unbiased enough for game use
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// Pick a random element of a slice
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T>
+    where
+        Self: Sized,
+    {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len())])
+        }
+    }
+}
+
+/// Small, fast, seedable generator (SplitMix64)
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::rng::{RandomSource, SeededRng};
+///
+/// let mut a = SeededRng::new(42);
+/// let mut b = SeededRng::new(42);
+/// assert_eq!(a.next_u64(), b.next_u64());
+/// assert!(a.below(10) < 10);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Create a generator from a seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Create a generator seeded from the system clock
+    pub fn from_entropy() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::new(nanos ^ u64::from(std::process::id()).rotate_left(32))
+    }
+
+    /// Create a seeded generator, or one seeded from the clock if no seed
+    /// is given
+    pub fn from_seed(seed: Option<u64>) -> Self {
+        seed.map(Self::new).unwrap_or_else(Self::from_entropy)
+    }
+}
+
+impl RandomSource for SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = SeededRng::new(7);
+        let mut b = SeededRng::new(7);
+        let xs: Vec<u64> = (0..16).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..16).map(|_| b.next_u64()).collect();
+        assert_eq!(xs, ys);
+
+        let mut c = SeededRng::new(8);
+        assert_ne!(xs[0], c.next_u64());
+    }
+
+    #[test]
+    fn test_below_and_choose() {
+        let mut rng = SeededRng::new(1);
+        assert_eq!(rng.below(0), 0);
+        assert!((0..1000).all(|_| rng.below(3) < 3));
+        assert_eq!(rng.choose::<u8>(&[]), None);
+        assert_eq!(rng.choose(&[5]), Some(&5));
+    }
+}
@@ -0,0 +1,331 @@
+//! Persistent application state
+//!
+//! Unlike `config.toml`, which the user edits, `state.toml` is written by the
+//! application itself. It currently remembers recently opened files for the
+//! quick-open menu.
+
+use crate::archive::ArchiveReader;
+use crate::game::Game;
+use crate::paths::Paths;
+use crate::pgn::PgnGame;
+use crate::xml::xml_to_pgn;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Maximum number of entries kept in the recent files list
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// Kind of game file, decided by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Fen,
+    Pgn,
+    Xml,
+    Xqf,
+}
+
+impl FileKind {
+    /// Guess the file kind from a path's extension
+    ///
+    /// Anything that is not `.pgn`, `.xml` or `.xqf` is treated as a FEN file.
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("pgn") => FileKind::Pgn,
+            Some("xml") => FileKind::Xml,
+            Some("xqf") => FileKind::Xqf,
+            _ => FileKind::Fen,
+        }
+    }
+
+    /// Short uppercase label for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            FileKind::Fen => "FEN",
+            FileKind::Pgn => "PGN",
+            FileKind::Xml => "XML",
+            FileKind::Xqf => "XQF",
+        }
+    }
+}
+
+/// A recently opened file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    pub kind: FileKind,
+}
+
+/// Summary of a game file shown in the quick-open menu
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FilePreview {
+    pub red: Option<String>,
+    pub black: Option<String>,
+    pub result: Option<String>,
+    pub date: Option<String>,
+    /// Number of games when the file is a multi-game collection
+    pub games: Option<usize>,
+    /// Set when the file could not be read or parsed
+    pub error: Option<String>,
+}
+
+impl FilePreview {
+    /// Read a file and extract players, result and date
+    ///
+    /// FEN files have no game tags; their preview only reports the side to
+    /// move in the result field.
+    pub fn load(file: &RecentFile) -> Self {
+        if file.kind == FileKind::Pgn {
+            return Self::load_pgn(&file.path);
+        }
+        let content = match std::fs::read_to_string(&file.path) {
+            Ok(c) => c,
+            Err(e) => {
+                return Self {
+                    error: Some(e.to_string()),
+                    ..Self::default()
+                }
+            }
+        };
+
+        if file.kind == FileKind::Fen {
+            return match Game::from_fen(content.trim()) {
+                Ok(game) => Self {
+                    result: Some(format!("{} to move", game.turn())),
+                    ..Self::default()
+                },
+                Err(e) => Self {
+                    error: Some(e.to_string()),
+                    ..Self::default()
+                },
+            };
+        }
+
+        match xml_to_pgn(&content) {
+            Some(pgn) => Self::from_pgn(&pgn),
+            None => Self {
+                error: Some("Failed to parse file".to_string()),
+                ..Self::default()
+            },
+        }
+    }
+
+    /// Preview a PGN file, streaming it so large archives stay cheap
+    fn load_pgn(path: &Path) -> Self {
+        let games = ArchiveReader::open(path).and_then(|archive| {
+            // Games that can't be read are left out
+            let mut games = archive.iter()?.filter_map(Result::ok);
+            let first = games.next();
+            Ok((first, games.count()))
+        });
+        match games {
+            Ok((Some(pgn), 0)) => Self::from_pgn(&pgn),
+            Ok((Some(_), rest)) => Self {
+                games: Some(rest + 1),
+                ..Self::default()
+            },
+            Ok((None, _)) => Self {
+                error: Some("Failed to parse file".to_string()),
+                ..Self::default()
+            },
+            Err(e) => Self {
+                error: Some(e.to_string()),
+                ..Self::default()
+            },
+        }
+    }
+
+    /// Extract players, result and date from a parsed game
+    ///
+    /// Placeholder values such as "?" and "*" are treated as missing.
+    pub fn from_pgn(pgn: &PgnGame) -> Self {
+        let tag = |key: &str| {
+            pgn.get_tag(key)
+                .filter(|v| !v.is_empty() && !v.contains('?'))
+                .cloned()
+        };
+        Self {
+            red: tag("Red"),
+            black: tag("Black"),
+            result: tag("Result").filter(|r| r != "*"),
+            date: tag("Date"),
+            games: None,
+            error: None,
+        }
+    }
+
+    /// One-line description, e.g. "Xu Yinchuan vs Lü Qin, 1-0, 2005.01.01"
+    pub fn summary(&self) -> String {
+        if let Some(err) = &self.error {
+            return format!("unreadable: {}", err);
+        }
+        if let Some(n) = self.games {
+            return format!("collection of {} games", n);
+        }
+
+        let mut parts = Vec::new();
+        if self.red.is_some() || self.black.is_some() {
+            parts.push(format!(
+                "{} vs {}",
+                self.red.as_deref().unwrap_or("?"),
+                self.black.as_deref().unwrap_or("?")
+            ));
+        }
+        parts.extend(self.result.clone());
+        parts.extend(self.date.clone());
+        parts.join(", ")
+    }
+}
+
+/// Application state persisted between runs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppState {
+    /// Most recently opened first
+    #[serde(default)]
+    pub recent_files: Vec<RecentFile>,
+}
+
+impl AppState {
+    /// Location of the state file, in the state directory
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Paths::resolve()?.state_file())
+    }
+
+    /// Load state from the default location
+    ///
+    /// Returns empty state if the file doesn't exist or is invalid.
+    pub fn load() -> Self {
+        Self::default_path()
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    /// Load state from a specific file
+    pub fn load_from(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save state to the default location
+    pub fn save(&self) -> std::io::Result<()> {
+        match Self::default_path() {
+            Some(path) => self.save_to(&path),
+            None => Ok(()),
+        }
+    }
+
+    /// Save state to a specific file, creating parent directories
+    pub fn save_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = toml::to_string(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, contents)
+    }
+
+    /// Record a file as most recently opened
+    ///
+    /// The path is made absolute so the entry works from any directory.
+    /// An existing entry for the same path moves to the front.
+    pub fn add_recent(&mut self, path: &Path) {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let kind = FileKind::from_path(&path);
+
+        self.recent_files.retain(|f| f.path != path);
+        self.recent_files.insert(0, RecentFile { path, kind });
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+}
+
+/// Record a file in the persisted recent files list
+///
+/// Errors writing the state file are ignored; losing the list is harmless.
+pub fn remember_recent_file(path: &Path) {
+    let mut state = AppState::load();
+    state.add_recent(path);
+    let _ = state.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_kind_from_path() {
+        assert_eq!(FileKind::from_path(Path::new("a.pgn")), FileKind::Pgn);
+        assert_eq!(FileKind::from_path(Path::new("a.XML")), FileKind::Xml);
+        assert_eq!(FileKind::from_path(Path::new("a.xqf")), FileKind::Xqf);
+        assert_eq!(FileKind::from_path(Path::new("a.fen")), FileKind::Fen);
+    }
+
+    #[test]
+    fn test_add_recent_dedups_and_caps() {
+        let mut state = AppState::default();
+        for i in 0..MAX_RECENT_FILES + 2 {
+            state.add_recent(Path::new(&format!("/nonexistent/{}.pgn", i)));
+        }
+        state.add_recent(Path::new("/nonexistent/5.pgn"));
+
+        assert_eq!(state.recent_files.len(), MAX_RECENT_FILES);
+        assert_eq!(
+            state.recent_files[0].path,
+            PathBuf::from("/nonexistent/5.pgn")
+        );
+        assert_eq!(
+            state
+                .recent_files
+                .iter()
+                .filter(|f| f.path.ends_with("5.pgn"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("state.toml");
+
+        let mut state = AppState::default();
+        state.add_recent(Path::new("/nonexistent/game.xml"));
+        state.save_to(&path).unwrap();
+
+        assert_eq!(AppState::load_from(&path), state);
+        assert_eq!(state.recent_files[0].kind, FileKind::Xml);
+    }
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let state = AppState::load_from(Path::new("/nonexistent/state.toml"));
+        assert!(state.recent_files.is_empty());
+    }
+
+    #[test]
+    fn test_pgn_preview() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("game.pgn");
+        fs::write(
+            &path,
+            "[Red \"Hu Ronghua\"]\n[Black \"Yang Guanlin\"]\n[Result \"1-0\"]\n[Date \"1960.01.01\"]\n\n1. h7e7 h0g2 1-0\n",
+        )
+        .unwrap();
+
+        let preview = FilePreview::load(&RecentFile {
+            path,
+            kind: FileKind::Pgn,
+        });
+        assert_eq!(
+            preview.summary(),
+            "Hu Ronghua vs Yang Guanlin, 1-0, 1960.01.01"
+        );
+    }
+}
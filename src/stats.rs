@@ -0,0 +1,366 @@
+//! Results of the games played against the AI, kept between runs
+//!
+//! Every game the player finishes against the built-in AI or an engine is
+//! added to `stats.toml` in the data directory (see
+//! [`Paths::stats_file`](crate::paths::Paths::stats_file)), oldest first.
+//! From the list come the totals per opponent and per opening, the
+//! current and longest winning streaks, and an informal Elo rating that
+//! starts at [`START_RATING`] and moves after each game by how surprising
+//! the result was against the opponent's nominal strength.
+//!
+//! ```
+//! use cn_chess_tui::stats::{Outcome, Stats};
+//! use cn_chess_tui::Difficulty;
+//!
+//! let mut stats = Stats::default();
+//! let opponent = Stats::opponent_name(Some(Difficulty::Beginner), None);
+//! let rating = Stats::opponent_rating(Some(Difficulty::Beginner), None);
+//! stats.record(&opponent, rating, Some("中炮"), Outcome::Win);
+//! stats.record(&opponent, rating, Some("中炮"), Outcome::Loss);
+//!
+//! let by_opponent = stats.by_opponent();
+//! assert_eq!(by_opponent[0].0, "AI (Beginner)");
+//! assert_eq!((by_opponent[0].1.wins, by_opponent[0].1.losses), (1, 1));
+//! assert!(stats.rating < 1500);
+//! ```
+
+use crate::game::{Difficulty, GameResult};
+use crate::paths::Paths;
+use crate::types::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// The rating of a player with no games yet
+pub const START_RATING: i32 = 1500;
+
+/// How far one game can move the rating
+const K_FACTOR: f64 = 32.0;
+
+/// How a game ended for the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Outcome {
+    /// The outcome for the player of `color` in a game with `result`
+    pub fn for_player(result: GameResult, color: Color) -> Self {
+        match (result, color) {
+            (GameResult::Draw, _) => Outcome::Draw,
+            (GameResult::RedWins, Color::Red) | (GameResult::BlackWins, Color::Black) => {
+                Outcome::Win
+            }
+            _ => Outcome::Loss,
+        }
+    }
+
+    /// Points scored: 1 for a win, ½ for a draw
+    fn score(self) -> f64 {
+        match self {
+            Outcome::Win => 1.0,
+            Outcome::Draw => 0.5,
+            Outcome::Loss => 0.0,
+        }
+    }
+}
+
+/// One finished game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// Who the player faced, e.g. `AI (Beginner)` or an engine's file name
+    pub opponent: String,
+    /// The opening's name, when the book knows it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opening: Option<String>,
+    pub outcome: Outcome,
+    /// The player's rating after the game
+    pub rating: i32,
+}
+
+/// Wins, losses and draws
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Tally {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl Tally {
+    fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Loss => self.losses += 1,
+            Outcome::Draw => self.draws += 1,
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Share of the points scored, in percent, counting draws as half
+    pub fn score_percent(&self) -> u32 {
+        match self.games() {
+            0 => 0,
+            games => ((self.wins * 2 + self.draws) * 50 / games) as u32,
+        }
+    }
+}
+
+impl Display for Tally {
+    /// E.g. `+3 -1 =2 (67%)`
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "+{} -{} ={} ({}%)",
+            self.wins,
+            self.losses,
+            self.draws,
+            self.score_percent()
+        )
+    }
+}
+
+/// The player's results, as kept in `stats.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    /// The informal rating after the last game
+    pub rating: i32,
+    /// Every game recorded, oldest first
+    #[serde(default)]
+    pub games: Vec<GameRecord>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats {
+            rating: START_RATING,
+            games: Vec::new(),
+        }
+    }
+}
+
+impl Stats {
+    /// Location of the stats file, in the data directory
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Paths::resolve()?.stats_file())
+    }
+
+    /// Load the stats from the default location
+    ///
+    /// Returns empty stats if the file doesn't exist or is invalid.
+    pub fn load() -> Self {
+        Self::default_path()
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    /// Load the stats from a specific file
+    pub fn load_from(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the stats to the default location
+    pub fn save(&self) -> std::io::Result<()> {
+        match Self::default_path() {
+            Some(path) => self.save_to(&path),
+            None => Ok(()),
+        }
+    }
+
+    /// Save the stats to a specific file, creating parent directories
+    pub fn save_to(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = toml::to_string(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, contents)
+    }
+
+    /// The name games against an engine, or else the built-in AI at a
+    /// difficulty, are filed under
+    pub fn opponent_name(difficulty: Option<Difficulty>, engine: Option<&Path>) -> String {
+        if let Some(name) = engine.and_then(|path| path.file_name()) {
+            return name.to_string_lossy().into_owned();
+        }
+        match difficulty {
+            Some(level) => format!("AI ({})", level),
+            None => "AI (custom)".to_string(),
+        }
+    }
+
+    /// The nominal rating of an opponent: a guess per difficulty, and a
+    /// strong player's for an engine or custom settings
+    pub fn opponent_rating(difficulty: Option<Difficulty>, engine: Option<&Path>) -> i32 {
+        if engine.is_some() {
+            return 2400;
+        }
+        match difficulty {
+            Some(Difficulty::Beginner) => 1000,
+            Some(Difficulty::Intermediate) => 1400,
+            Some(Difficulty::Advanced) => 1800,
+            Some(Difficulty::Max) => 2200,
+            None => 2000,
+        }
+    }
+
+    /// Add a finished game and update the rating
+    pub fn record(
+        &mut self,
+        opponent: &str,
+        opponent_rating: i32,
+        opening: Option<&str>,
+        outcome: Outcome,
+    ) {
+        let expected = 1.0 / (1.0 + 10f64.powf(f64::from(opponent_rating - self.rating) / 400.0));
+        self.rating += (K_FACTOR * (outcome.score() - expected)).round() as i32;
+        self.games.push(GameRecord {
+            opponent: opponent.to_string(),
+            opening: opening.map(str::to_string),
+            outcome,
+            rating: self.rating,
+        });
+    }
+
+    /// Results over all games
+    pub fn total(&self) -> Tally {
+        let mut tally = Tally::default();
+        for game in &self.games {
+            tally.add(game.outcome);
+        }
+        tally
+    }
+
+    /// Results per opponent, by name
+    pub fn by_opponent(&self) -> Vec<(String, Tally)> {
+        Self::tally_by(self.games.iter().map(|game| (Some(&game.opponent), game)))
+    }
+
+    /// Results per opening, by name; games out of the book are left out
+    pub fn by_opening(&self) -> Vec<(String, Tally)> {
+        Self::tally_by(self.games.iter().map(|game| (game.opening.as_ref(), game)))
+    }
+
+    fn tally_by<'a>(
+        games: impl Iterator<Item = (Option<&'a String>, &'a GameRecord)>,
+    ) -> Vec<(String, Tally)> {
+        let mut tallies: BTreeMap<String, Tally> = BTreeMap::new();
+        for (key, game) in games {
+            if let Some(key) = key {
+                tallies.entry(key.clone()).or_default().add(game.outcome);
+            }
+        }
+        tallies.into_iter().collect()
+    }
+
+    /// The outcome of the latest games and how many in a row ended that
+    /// way, or None before the first game
+    pub fn current_streak(&self) -> Option<(Outcome, usize)> {
+        let last = self.games.last()?.outcome;
+        let length = self
+            .games
+            .iter()
+            .rev()
+            .take_while(|game| game.outcome == last)
+            .count();
+        Some((last, length))
+    }
+
+    /// The most wins in a row
+    pub fn longest_winning_streak(&self) -> usize {
+        let mut longest = 0;
+        let mut current = 0;
+        for game in &self.games {
+            if game.outcome == Outcome::Win {
+                current += 1;
+                longest = longest.max(current);
+            } else {
+                current = 0;
+            }
+        }
+        longest
+    }
+
+    /// The highest rating reached, counting the start
+    pub fn peak_rating(&self) -> i32 {
+        self.games
+            .iter()
+            .map(|game| game.rating)
+            .fold(START_RATING, i32::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(stats: &mut Stats, outcome: Outcome) {
+        stats.record("AI (Intermediate)", 1400, Some("中炮"), outcome);
+    }
+
+    #[test]
+    fn test_rating_moves_by_surprise() {
+        let mut stats = Stats::default();
+        stats.record("AI (Beginner)", 1000, None, Outcome::Win);
+        let after_easy_win = stats.rating - START_RATING;
+
+        let mut stats = Stats::default();
+        stats.record("engine", 2400, None, Outcome::Win);
+        let after_upset = stats.rating - START_RATING;
+
+        assert!(after_easy_win > 0 && after_easy_win < 3);
+        assert_eq!(after_upset, 32);
+    }
+
+    #[test]
+    fn test_streaks() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.current_streak(), None);
+        for outcome in [Outcome::Win, Outcome::Win, Outcome::Win, Outcome::Draw] {
+            record(&mut stats, outcome);
+        }
+        record(&mut stats, Outcome::Loss);
+        record(&mut stats, Outcome::Loss);
+
+        assert_eq!(stats.current_streak(), Some((Outcome::Loss, 2)));
+        assert_eq!(stats.longest_winning_streak(), 3);
+        assert_eq!(stats.total().to_string(), "+3 -2 =1 (58%)");
+    }
+
+    #[test]
+    fn test_out_of_book_games_have_no_opening() {
+        let mut stats = Stats::default();
+        record(&mut stats, Outcome::Win);
+        stats.record("AI (Intermediate)", 1400, None, Outcome::Draw);
+
+        let openings = stats.by_opening();
+        assert_eq!(openings.len(), 1);
+        assert_eq!(openings[0].1.games(), 1);
+        assert_eq!(stats.by_opponent()[0].1.games(), 2);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data").join("stats.toml");
+        let mut stats = Stats::default();
+        record(&mut stats, Outcome::Win);
+        stats.save_to(&path).unwrap();
+
+        assert_eq!(Stats::load_from(&path), stats);
+        assert_eq!(
+            Stats::load_from(&dir.path().join("missing.toml")),
+            Stats::default()
+        );
+    }
+}
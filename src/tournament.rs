@@ -0,0 +1,896 @@
+//! Automated matches between engines
+//!
+//! A [`Player`] picks moves for one side: the built-in search
+//! ([`NativePlayer`]) or an external UCCI engine ([`EnginePlayer`]).
+//! [`play_match`] plays a series of games with alternating colors and
+//! returns a [`MatchScore`], which estimates the Elo difference and can run a
+//! sequential probability ratio test (SPRT) to stop a match once the result
+//! is clear. [`play_tournament`] plays such a match for every pairing of a
+//! round robin or gauntlet and ranks the entrants.
+
+use crate::ai::Searcher;
+use crate::endgame;
+pub use crate::game::SearchLimit;
+use crate::game::{Game, GameResult};
+use crate::notation::iccs::iccs_to_move;
+use crate::pgn::{PgnGame, PgnGameResult};
+use crate::types::{Color, Position};
+use crate::ucci::engine::EngineError;
+use crate::ucci::{MoveResult, UcciClient};
+use std::fmt::{self, Display, Formatter};
+
+/// What a player does on its turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reply {
+    Move(Position, Position),
+    Resign,
+    /// Claim a draw; the runner accepts it
+    Draw,
+}
+
+/// Something that can play one side of a game
+pub trait Player {
+    /// Name used in reports
+    fn name(&self) -> String;
+
+    /// Called before each game
+    fn new_game(&mut self) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    /// Choose a reply for the side to move in `game`
+    fn reply(&mut self, game: &Game) -> Result<Reply, EngineError>;
+}
+
+/// The built-in alpha-beta search at a fixed depth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NativePlayer {
+    pub depth: u32,
+}
+
+impl NativePlayer {
+    pub fn new(depth: u32) -> Self {
+        Self { depth }
+    }
+}
+
+impl Player for NativePlayer {
+    fn name(&self) -> String {
+        format!("native depth {}", self.depth)
+    }
+
+    fn reply(&mut self, game: &Game) -> Result<Reply, EngineError> {
+        let mut searcher = Searcher::new(self.depth);
+        Ok(match searcher.search(game.board(), game.turn()) {
+            Some(result) => Reply::Move(result.mv.from, result.mv.to),
+            None => Reply::Resign,
+        })
+    }
+}
+
+/// An external UCCI engine with a set of option values
+pub struct EnginePlayer {
+    client: UcciClient,
+    limit: SearchLimit,
+    options: Vec<(String, String)>,
+}
+
+impl EnginePlayer {
+    /// Start an engine and apply `options` in order
+    pub fn start(
+        path: &str,
+        options: &[(String, String)],
+        limit: SearchLimit,
+    ) -> Result<Self, EngineError> {
+        let mut client = UcciClient::new(path)?;
+        client.initialize()?;
+        for (name, value) in options {
+            client.set_option(name, value)?;
+        }
+        Ok(Self {
+            client,
+            limit,
+            options: options.to_vec(),
+        })
+    }
+
+    /// Stop the engine process
+    pub fn shutdown(self) -> Result<(), EngineError> {
+        self.client.shutdown()
+    }
+}
+
+impl Player for EnginePlayer {
+    fn name(&self) -> String {
+        let name = &self.client.engine_info().name;
+        if self.options.is_empty() {
+            return name.clone();
+        }
+        let options: Vec<String> = self
+            .options
+            .iter()
+            .map(|(option, value)| format!("{}={}", option, value))
+            .collect();
+        format!("{} [{}]", name, options.join(" "))
+    }
+
+    fn new_game(&mut self) -> Result<(), EngineError> {
+        self.client.is_ready()?;
+        Ok(())
+    }
+
+    fn reply(&mut self, game: &Game) -> Result<Reply, EngineError> {
+        self.client.set_position(&game.to_fen(), &[])?;
+        match self.limit {
+            SearchLimit::Depth(depth) => self.client.go_depth(depth)?,
+            SearchLimit::Time(ms) => self.client.go_time(ms)?,
+            SearchLimit::Nodes(nodes) => self.client.go_nodes(nodes)?,
+        }
+        Ok(match self.client.wait()? {
+            MoveResult::Move(mv, _) => match iccs_to_move(&mv) {
+                Some((from, to)) => Reply::Move(from, to),
+                // An unreadable move forfeits the game
+                None => Reply::Resign,
+            },
+            MoveResult::NoMove | MoveResult::Resign => Reply::Resign,
+            MoveResult::Draw => Reply::Draw,
+        })
+    }
+}
+
+/// Why a match game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    /// The rules ended the game (mate, repetition, move limit, ...)
+    Rules,
+    Resignation,
+    DrawClaim,
+    /// The side to move played an illegal move and lost
+    IllegalMove,
+    /// The ply limit was reached and the game was scored a draw
+    Adjudicated,
+    /// The game reached an ending with a known result, see
+    /// [`endgame::classify`]
+    Endgame,
+}
+
+/// A finished match game
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub result: GameResult,
+    pub ending: Ending,
+    pub game: Game,
+}
+
+/// Play one game from `start`
+///
+/// Games still running after `max_plies` half-moves are scored a draw. With
+/// `endgames`, a game is also scored as soon as it reaches an elementary
+/// ending with a known result and the side to move has nothing to capture.
+pub fn play_game(
+    red: &mut dyn Player,
+    black: &mut dyn Player,
+    start: &Game,
+    max_plies: usize,
+    endgames: bool,
+) -> Result<GameRecord, EngineError> {
+    red.new_game()?;
+    black.new_game()?;
+
+    let mut game = start.clone();
+    let mut plies = 0;
+    let (result, ending) = loop {
+        if let Some(result) = game.state().result() {
+            break (result, Ending::Rules);
+        }
+        if plies >= max_plies {
+            break (GameResult::Draw, Ending::Adjudicated);
+        }
+        if endgames && game.board().legal_captures(game.turn()).is_empty() {
+            if let Some(result) = endgame::classify(game.board()).result() {
+                break (result, Ending::Endgame);
+            }
+        }
+
+        let side = game.turn();
+        let player: &mut dyn Player = match side {
+            Color::Red => &mut *red,
+            Color::Black => &mut *black,
+        };
+        match player.reply(&game)? {
+            Reply::Move(from, to) => {
+                if game.make_move(from, to).is_err() {
+                    break (win_for(side.opponent()), Ending::IllegalMove);
+                }
+            }
+            Reply::Resign => break (win_for(side.opponent()), Ending::Resignation),
+            Reply::Draw => break (GameResult::Draw, Ending::DrawClaim),
+        }
+        plies += 1;
+    };
+
+    Ok(GameRecord {
+        result,
+        ending,
+        game,
+    })
+}
+
+fn win_for(color: Color) -> GameResult {
+    match color {
+        Color::Red => GameResult::RedWins,
+        Color::Black => GameResult::BlackWins,
+    }
+}
+
+/// Wins, draws and losses from the first player's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    /// Count a game the first player played as `color`
+    pub fn record(&mut self, result: GameResult, color: Color) {
+        match (result, color) {
+            (GameResult::Draw, _) => self.draws += 1,
+            (GameResult::RedWins, Color::Red) | (GameResult::BlackWins, Color::Black) => {
+                self.wins += 1
+            }
+            _ => self.losses += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points scored, one per win and a half per draw
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    /// Points as a fraction of the games played (0.0 to 1.0)
+    pub fn fraction(&self) -> f64 {
+        match self.games() {
+            0 => 0.5,
+            games => self.points() / games as f64,
+        }
+    }
+
+    /// Estimated Elo difference, or None if the score is 0% or 100%
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::tournament::MatchScore;
+    ///
+    /// let even = MatchScore { wins: 3, draws: 4, losses: 3 };
+    /// assert_eq!(even.elo(), Some(0.0));
+    ///
+    /// let sweep = MatchScore { wins: 5, draws: 0, losses: 0 };
+    /// assert_eq!(sweep.elo(), None);
+    /// ```
+    pub fn elo(&self) -> Option<f64> {
+        let fraction = self.fraction();
+        if self.games() == 0 || fraction <= 0.0 || fraction >= 1.0 {
+            return None;
+        }
+        Some(400.0 * (fraction / (1.0 - fraction)).log10())
+    }
+
+    /// Log-likelihood ratio of `elo1` against `elo0`
+    ///
+    /// Uses the normal approximation of the game results, so it is only
+    /// meaningful after a few games. It stays 0 while every game has had
+    /// the same result, since the spread of results is still unknown.
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let games = self.games() as f64;
+        if games == 0.0 {
+            return 0.0;
+        }
+        let s = self.fraction();
+        let variance = (self.wins as f64 * (1.0 - s).powi(2)
+            + self.draws as f64 * (0.5 - s).powi(2)
+            + self.losses as f64 * s.powi(2))
+            / games;
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let s0 = expected_score(elo0);
+        let s1 = expected_score(elo1);
+        games * (s1 - s0) * (2.0 * s - s0 - s1) / (2.0 * variance)
+    }
+}
+
+impl Display for MatchScore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "+{} ={} -{} ({:.1}%)",
+            self.wins,
+            self.draws,
+            self.losses,
+            self.fraction() * 100.0
+        )
+    }
+}
+
+/// Expected score of a player `elo` points stronger
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Sequential probability ratio test between two Elo hypotheses
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    /// Elo difference under the null hypothesis
+    pub elo0: f64,
+    /// Elo difference under the alternative hypothesis
+    pub elo1: f64,
+    /// False positive rate
+    pub alpha: f64,
+    /// False negative rate
+    pub beta: f64,
+}
+
+/// Outcome of an [`Sprt`] so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtVerdict {
+    /// Accept `elo1`: the first player is stronger
+    Pass,
+    /// Accept `elo0`: no improvement
+    Fail,
+    Continue,
+}
+
+impl Display for SprtVerdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SprtVerdict::Pass => write!(f, "H1 accepted"),
+            SprtVerdict::Fail => write!(f, "H0 accepted"),
+            SprtVerdict::Continue => write!(f, "inconclusive"),
+        }
+    }
+}
+
+impl Sprt {
+    /// A test with 5% error rates in both directions
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// LLR bounds: the test fails below the first and passes above the second
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn verdict(&self, score: &MatchScore) -> SprtVerdict {
+        let llr = score.llr(self.elo0, self.elo1);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtVerdict::Pass
+        } else if llr <= lower {
+            SprtVerdict::Fail
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+}
+
+/// Settings for [`play_match`]
+#[derive(Debug, Clone)]
+pub struct MatchOptions {
+    /// Maximum number of games
+    pub games: u32,
+    /// Plies before a game is adjudicated a draw
+    pub max_plies: usize,
+    /// Score games reaching an elementary ending without playing them out
+    pub endgames: bool,
+    /// Stop early once this test reaches a verdict
+    pub sprt: Option<Sprt>,
+    pub start: Game,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        Self {
+            games: 10,
+            max_plies: 200,
+            endgames: true,
+            sprt: None,
+            start: Game::new(),
+        }
+    }
+}
+
+/// Play a match, `player` against `opponent`, alternating colors
+///
+/// `player` takes Red in the first game. `on_game` is called after every
+/// game with the record and the score so far. The score is from `player`'s
+/// point of view.
+pub fn play_match(
+    player: &mut dyn Player,
+    opponent: &mut dyn Player,
+    options: &MatchOptions,
+    mut on_game: impl FnMut(&GameRecord, &MatchScore),
+) -> Result<MatchScore, EngineError> {
+    let mut score = MatchScore::default();
+    for round in 0..options.games {
+        let (color, record) = if round % 2 == 0 {
+            let record = play_game(
+                player,
+                opponent,
+                &options.start,
+                options.max_plies,
+                options.endgames,
+            )?;
+            (Color::Red, record)
+        } else {
+            let record = play_game(
+                opponent,
+                player,
+                &options.start,
+                options.max_plies,
+                options.endgames,
+            )?;
+            (Color::Black, record)
+        };
+        score.record(record.result, color);
+        on_game(&record, &score);
+
+        if let Some(sprt) = &options.sprt {
+            if sprt.verdict(&score) != SprtVerdict::Continue {
+                break;
+            }
+        }
+    }
+    Ok(score)
+}
+
+/// How the entrants of a tournament are paired
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TournamentFormat {
+    /// Every entrant plays every other
+    #[default]
+    RoundRobin,
+    /// The first entrant plays each of the others, who don't meet
+    Gauntlet,
+}
+
+impl TournamentFormat {
+    /// Pairs of entrant indexes, in the order they play
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::tournament::TournamentFormat;
+    ///
+    /// assert_eq!(
+    ///     TournamentFormat::RoundRobin.pairings(3),
+    ///     [(0, 1), (0, 2), (1, 2)]
+    /// );
+    /// assert_eq!(TournamentFormat::Gauntlet.pairings(3), [(0, 1), (0, 2)]);
+    /// ```
+    pub fn pairings(self, entrants: usize) -> Vec<(usize, usize)> {
+        match self {
+            TournamentFormat::RoundRobin => (0..entrants)
+                .flat_map(|a| (a + 1..entrants).map(move |b| (a, b)))
+                .collect(),
+            TournamentFormat::Gauntlet => (1..entrants).map(|b| (0, b)).collect(),
+        }
+    }
+}
+
+/// A game of a tournament
+#[derive(Debug, Clone)]
+pub struct TournamentGame {
+    /// Index of the pairing in [`TournamentResult::pairings`]
+    pub pairing: usize,
+    /// Number of the game in its pairing, from 1
+    pub number: u32,
+    /// Entrant indexes
+    pub red: usize,
+    pub black: usize,
+    pub record: GameRecord,
+}
+
+/// The match of one pairing, scored for its first entrant
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairingResult {
+    pub first: usize,
+    pub second: usize,
+    pub score: MatchScore,
+    /// Outcome of the SPRT, if the tournament ran one
+    pub verdict: Option<SprtVerdict>,
+}
+
+/// An entrant's place in the standings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub entrant: usize,
+    pub name: String,
+    /// Every game the entrant played; its [`elo`](MatchScore::elo) is the
+    /// performance against the opponents met
+    pub score: MatchScore,
+}
+
+/// Everything a tournament played
+#[derive(Debug, Clone)]
+pub struct TournamentResult {
+    /// Entrant names, by index
+    pub names: Vec<String>,
+    pub pairings: Vec<PairingResult>,
+    pub games: Vec<TournamentGame>,
+}
+
+impl TournamentResult {
+    /// Entrants by points, best first; entrants level on points keep the
+    /// order they were entered in
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .names
+            .iter()
+            .enumerate()
+            .map(|(entrant, name)| Standing {
+                entrant,
+                name: name.clone(),
+                score: MatchScore::default(),
+            })
+            .collect();
+        for game in &self.games {
+            standings[game.red]
+                .score
+                .record(game.record.result, Color::Red);
+            standings[game.black]
+                .score
+                .record(game.record.result, Color::Black);
+        }
+        standings.sort_by(|a, b| b.score.points().total_cmp(&a.score.points()));
+        standings
+    }
+
+    /// Every game as PGN, tagged with `event`, the players' names, the
+    /// round (pairing and game number) and how it ended
+    pub fn to_pgn(&self, event: &str) -> Vec<PgnGame> {
+        self.games
+            .iter()
+            .map(|game| {
+                let mut pgn = game.record.game.to_pgn();
+                pgn.set_tag("Event", event);
+                pgn.set_tag("Round", format!("{}.{}", game.pairing + 1, game.number));
+                pgn.set_tag("Red", &self.names[game.red]);
+                pgn.set_tag("Black", &self.names[game.black]);
+                pgn.result = match game.record.result {
+                    GameResult::RedWins => PgnGameResult::RedWins,
+                    GameResult::BlackWins => PgnGameResult::BlackWins,
+                    GameResult::Draw => PgnGameResult::Draw,
+                };
+                pgn.set_tag("Result", pgn.result.to_pgn_string());
+                let termination = match game.record.ending {
+                    Ending::Rules | Ending::Resignation | Ending::DrawClaim => "normal",
+                    Ending::IllegalMove => "rules infraction",
+                    Ending::Adjudicated | Ending::Endgame => "adjudication",
+                };
+                pgn.set_tag("Termination", termination);
+                pgn
+            })
+            .collect()
+    }
+}
+
+/// Play a tournament between `players`
+///
+/// Each pairing plays a match under `options`, its first entrant taking Red
+/// in the first game; with an SPRT set, a pairing stops as soon as the test
+/// decides it. `on_game` is called after every game with the game and the
+/// score of its pairing so far.
+pub fn play_tournament(
+    players: &mut [Box<dyn Player>],
+    format: TournamentFormat,
+    options: &MatchOptions,
+    mut on_game: impl FnMut(&TournamentGame, &MatchScore),
+) -> Result<TournamentResult, EngineError> {
+    let names = players.iter().map(|player| player.name()).collect();
+    let mut pairings = Vec::new();
+    let mut games = Vec::new();
+    for (pairing, (first, second)) in format.pairings(players.len()).into_iter().enumerate() {
+        // first < second, so they sit on either side of the split
+        let (left, right) = players.split_at_mut(second);
+        let score = play_match(
+            left[first].as_mut(),
+            right[0].as_mut(),
+            options,
+            |record, score| {
+                let number = score.games();
+                let (red, black) = if number % 2 == 1 {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+                let game = TournamentGame {
+                    pairing,
+                    number,
+                    red,
+                    black,
+                    record: record.clone(),
+                };
+                on_game(&game, score);
+                games.push(game);
+            },
+        )?;
+        pairings.push(PairingResult {
+            first,
+            second,
+            score,
+            verdict: options.sprt.map(|sprt| sprt.verdict(&score)),
+        });
+    }
+    Ok(TournamentResult {
+        names,
+        pairings,
+        games,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_limit_is_the_ai_config_one() {
+        let limit: crate::game::SearchLimit = SearchLimit::Nodes(50_000);
+        assert_eq!(limit.to_string(), "50000 nodes/move");
+        assert_eq!(SearchLimit::Time(500).to_string(), "500 ms/move");
+    }
+
+    /// Plays a fixed list of replies, then resigns
+    struct Scripted(Vec<Reply>);
+
+    impl Player for Scripted {
+        fn name(&self) -> String {
+            "scripted".to_string()
+        }
+
+        fn reply(&mut self, _game: &Game) -> Result<Reply, EngineError> {
+            Ok(if self.0.is_empty() {
+                Reply::Resign
+            } else {
+                self.0.remove(0)
+            })
+        }
+    }
+
+    fn mv(from: (usize, usize), to: (usize, usize)) -> Reply {
+        Reply::Move(
+            Position::from_xy(from.0, from.1),
+            Position::from_xy(to.0, to.1),
+        )
+    }
+
+    #[test]
+    fn test_play_game_endings() {
+        let start = Game::new();
+
+        let mut red = Scripted(vec![mv((7, 7), (4, 7))]);
+        let mut black = Scripted(vec![]);
+        let record = play_game(&mut red, &mut black, &start, 100, false).unwrap();
+        assert_eq!(record.result, GameResult::RedWins);
+        assert_eq!(record.ending, Ending::Resignation);
+        assert_eq!(record.game.get_moves_with_iccs(), ["h7e7"]);
+
+        // Moving a Black piece on Red's turn forfeits
+        let mut red = Scripted(vec![mv((0, 0), (0, 1))]);
+        let record = play_game(&mut red, &mut black, &start, 100, false).unwrap();
+        assert_eq!(record.result, GameResult::BlackWins);
+        assert_eq!(record.ending, Ending::IllegalMove);
+
+        let mut red = Scripted(vec![Reply::Draw]);
+        let record = play_game(&mut red, &mut black, &start, 100, false).unwrap();
+        assert_eq!(record.result, GameResult::Draw);
+        assert_eq!(record.ending, Ending::DrawClaim);
+    }
+
+    #[test]
+    fn test_play_game_adjudicates_at_ply_limit() {
+        let mut red = NativePlayer::new(1);
+        let mut black = NativePlayer::new(1);
+        let record = play_game(&mut red, &mut black, &Game::new(), 4, false).unwrap();
+        assert_eq!(record.result, GameResult::Draw);
+        assert_eq!(record.ending, Ending::Adjudicated);
+        assert_eq!(record.game.get_moves().len(), 4);
+    }
+
+    #[test]
+    fn test_play_game_stops_at_mate() {
+        // Red mates with the chariot on the back rank
+        let start = Game::from_fen("4k4/R8/9/9/9/9/9/9/9/3K4R w - - 0 1").unwrap();
+        let mut red = NativePlayer::new(2);
+        let mut black = NativePlayer::new(2);
+        let record = play_game(&mut red, &mut black, &start, 10, false).unwrap();
+        assert_eq!(record.result, GameResult::RedWins);
+        assert_eq!(record.ending, Ending::Rules);
+    }
+
+    #[test]
+    fn test_play_game_scores_known_endings() {
+        let start = Game::from_fen("4k4/R8/9/9/9/9/9/9/9/3K4R w - - 0 1").unwrap();
+        let mut red = NativePlayer::new(1);
+        let mut black = NativePlayer::new(1);
+        let record = play_game(&mut red, &mut black, &start, 10, true).unwrap();
+        assert_eq!(record.result, GameResult::RedWins);
+        assert_eq!(record.ending, Ending::Endgame);
+        assert!(record.game.get_moves().is_empty());
+
+        // Not while a piece can be taken: the general takes the chariot
+        let start = Game::from_fen("4k4/4R4/9/9/9/9/9/9/9/3K5 b - - 0 1").unwrap();
+        let record = play_game(&mut red, &mut black, &start, 10, true).unwrap();
+        assert_eq!(record.result, GameResult::Draw);
+        assert_eq!(record.ending, Ending::Endgame);
+        assert_eq!(record.game.get_moves_with_iccs(), ["e0e1"]);
+    }
+
+    #[test]
+    fn test_match_alternates_colors() {
+        let mut seen = Vec::new();
+        let options = MatchOptions {
+            games: 4,
+            max_plies: 2,
+            ..MatchOptions::default()
+        };
+        let score = play_match(
+            &mut NativePlayer::new(1),
+            &mut NativePlayer::new(1),
+            &options,
+            |record, score| seen.push((record.result, score.games())),
+        )
+        .unwrap();
+        assert_eq!(score.draws, 4);
+        assert_eq!(seen.len(), 4);
+        assert_eq!(seen[3], (GameResult::Draw, 4));
+    }
+
+    #[test]
+    fn test_record_by_color() {
+        let mut score = MatchScore::default();
+        score.record(GameResult::RedWins, Color::Red);
+        score.record(GameResult::RedWins, Color::Black);
+        score.record(GameResult::BlackWins, Color::Black);
+        score.record(GameResult::Draw, Color::Black);
+        assert_eq!(
+            score,
+            MatchScore {
+                wins: 2,
+                draws: 1,
+                losses: 1
+            }
+        );
+        assert_eq!(score.points(), 2.5);
+        assert_eq!(score.to_string(), "+2 =1 -1 (62.5%)");
+    }
+
+    #[test]
+    fn test_elo_estimate() {
+        // 75% is about +191 Elo
+        let score = MatchScore {
+            wins: 3,
+            draws: 0,
+            losses: 1,
+        };
+        assert!((score.elo().unwrap() - 190.85).abs() < 0.01);
+        assert!(MatchScore::default().elo().is_none());
+    }
+
+    #[test]
+    fn test_sprt_verdicts() {
+        let sprt = Sprt::new(0.0, 50.0);
+        let (lower, upper) = sprt.bounds();
+        assert!((lower + 2.944).abs() < 0.001);
+        assert!((upper - 2.944).abs() < 0.001);
+
+        let strong = MatchScore {
+            wins: 60,
+            draws: 20,
+            losses: 20,
+        };
+        assert_eq!(sprt.verdict(&strong), SprtVerdict::Pass);
+
+        let weak = MatchScore {
+            wins: 20,
+            draws: 20,
+            losses: 60,
+        };
+        assert_eq!(sprt.verdict(&weak), SprtVerdict::Fail);
+
+        let early = MatchScore {
+            wins: 2,
+            draws: 1,
+            losses: 1,
+        };
+        assert_eq!(sprt.verdict(&early), SprtVerdict::Continue);
+        assert_eq!(MatchScore::default().llr(0.0, 50.0), 0.0);
+    }
+
+    #[test]
+    fn test_tournament_standings_and_pgn() {
+        // Deeper search beats a player that always resigns
+        let mut players: Vec<Box<dyn Player>> = vec![
+            Box::new(Scripted(vec![])),
+            Box::new(NativePlayer::new(1)),
+            Box::new(NativePlayer::new(1)),
+        ];
+        let options = MatchOptions {
+            games: 2,
+            max_plies: 2,
+            ..MatchOptions::default()
+        };
+        let mut seen = 0;
+        let result = play_tournament(
+            &mut players,
+            TournamentFormat::RoundRobin,
+            &options,
+            |_, _| seen += 1,
+        )
+        .unwrap();
+        assert_eq!(seen, 6);
+        assert_eq!(result.pairings.len(), 3);
+        assert_eq!(result.pairings[0].score.losses, 2);
+        // Colors alternate within a pairing
+        assert_eq!((result.games[0].red, result.games[1].red), (0, 1));
+
+        let standings = result.standings();
+        let order: Vec<usize> = standings.iter().map(|s| s.entrant).collect();
+        assert_eq!(order, [1, 2, 0]);
+        assert_eq!(standings[0].score.points(), 3.0);
+        assert_eq!(standings[2].score.games(), 4);
+
+        let pgn = result.to_pgn("Test");
+        assert_eq!(pgn.len(), 6);
+        assert_eq!(pgn[0].get_tag("Red").map(String::as_str), Some("scripted"));
+        assert_eq!(pgn[0].get_tag("Round").map(String::as_str), Some("1.1"));
+        assert_eq!(pgn[0].result, PgnGameResult::BlackWins);
+        assert_eq!(
+            pgn[5].get_tag("Termination").map(String::as_str),
+            Some("adjudication")
+        );
+        assert_eq!(
+            pgn[5].get_tag("Result").map(String::as_str),
+            Some("1/2-1/2")
+        );
+    }
+
+    #[test]
+    fn test_gauntlet_stops_pairings_by_sprt() {
+        // Each opponent claims a draw in one game and resigns the next
+        let script = || Box::new(Scripted([Reply::Draw, Reply::Resign].repeat(25)));
+        let mut players: Vec<Box<dyn Player>> =
+            vec![Box::new(NativePlayer::new(1)), script(), script()];
+        let options = MatchOptions {
+            games: 50,
+            max_plies: 2,
+            sprt: Some(Sprt::new(0.0, 50.0)),
+            ..MatchOptions::default()
+        };
+        let result = play_tournament(
+            &mut players,
+            TournamentFormat::Gauntlet,
+            &options,
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(result.pairings.len(), 2);
+        for pairing in &result.pairings {
+            assert_eq!(pairing.first, 0);
+            assert_eq!(pairing.verdict, Some(SprtVerdict::Pass));
+            assert!(pairing.score.games() < 50);
+        }
+    }
+}
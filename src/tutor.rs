@@ -0,0 +1,163 @@
+//! Rules of the pieces, written for new players
+//!
+//! The learning mode shows [`piece_rules`] for the piece under the cursor
+//! and answers an illegal move with the [`lesson`] behind the rule it
+//! breaks.
+
+use crate::board::IllegalMoveReason;
+use crate::types::{Color, PieceType};
+
+/// How one kind of piece moves, in a few short sentences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceRules {
+    /// English name of the piece
+    pub name: &'static str,
+    /// The rules, one sentence each
+    pub rules: &'static [&'static str],
+}
+
+/// The rules of a kind of piece
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::tutor::piece_rules;
+/// use cn_chess_tui::PieceType;
+///
+/// let horse = piece_rules(PieceType::Horse);
+/// assert_eq!(horse.name, "Horse");
+/// assert!(horse.rules.iter().any(|rule| rule.contains("leg")));
+/// ```
+pub fn piece_rules(piece_type: PieceType) -> PieceRules {
+    let (name, rules): (_, &[_]) = match piece_type {
+        PieceType::General => (
+            "General",
+            &[
+                "Moves one point along a file or rank.",
+                "Never leaves the palace, the 3x3 box marked with an X.",
+                "May not face the other general on an open file.",
+            ],
+        ),
+        PieceType::Advisor => (
+            "Advisor",
+            &[
+                "Moves one point diagonally.",
+                "Never leaves the palace, so it only has five points.",
+            ],
+        ),
+        PieceType::Elephant => (
+            "Elephant",
+            &[
+                "Moves exactly two points diagonally.",
+                "Is stopped when the point in between (its eye) is taken.",
+                "Never crosses the river.",
+            ],
+        ),
+        PieceType::Horse => (
+            "Horse",
+            &[
+                "Moves one point along a file or rank, then one diagonally outward.",
+                "Is stopped when the first point (its leg) is taken.",
+            ],
+        ),
+        PieceType::Chariot => (
+            "Chariot",
+            &[
+                "Moves any number of points along a file or rank.",
+                "Cannot jump, and captures the first enemy piece in its way.",
+            ],
+        ),
+        PieceType::Cannon => (
+            "Cannon",
+            &[
+                "Moves like a chariot when not capturing.",
+                "Captures by jumping exactly one piece (the screen) of either side.",
+            ],
+        ),
+        PieceType::Soldier => (
+            "Soldier",
+            &[
+                "Moves one point forward and never back.",
+                "Once across the river it may also move one point sideways.",
+            ],
+        ),
+    };
+    PieceRules { name, rules }
+}
+
+/// Name of a side as written in the learning mode
+pub fn side_name(color: Color) -> &'static str {
+    match color {
+        Color::Red => "红 Red",
+        Color::Black => "黑 Black",
+    }
+}
+
+/// The rule behind an illegal move, to follow its reason
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::tutor::lesson;
+/// use cn_chess_tui::IllegalMoveReason;
+///
+/// assert!(lesson(IllegalMoveReason::CannonNeedsScreen).contains("screen"));
+/// ```
+pub fn lesson(reason: IllegalMoveReason) -> &'static str {
+    match reason {
+        IllegalMoveReason::NoPiece => "Select one of your pieces first.",
+        IllegalMoveReason::OwnPiece => "A piece captures by landing on an enemy piece.",
+        IllegalMoveReason::WrongPattern(piece_type) => piece_rules(piece_type).rules[0],
+        IllegalMoveReason::LeavesPalace => {
+            "Generals and advisors stay inside the 3x3 palace marked with an X."
+        }
+        IllegalMoveReason::HorseLegBlocked => {
+            "A horse first steps one point straight; if that point is taken it cannot go that way."
+        }
+        IllegalMoveReason::ElephantEyeBlocked => {
+            "An elephant cannot jump over the point in the middle of its move."
+        }
+        IllegalMoveReason::ElephantCrossesRiver => {
+            "Elephants defend their own half and stop at the river."
+        }
+        IllegalMoveReason::SoldierBackward => "Soldiers only ever move forward or sideways.",
+        IllegalMoveReason::SoldierSidewaysBeforeRiver => {
+            "Before the river a soldier can only step forward."
+        }
+        IllegalMoveReason::PathBlocked => {
+            "Chariots and cannons cannot jump over pieces when moving."
+        }
+        IllegalMoveReason::CannonNeedsScreen => {
+            "To capture, a cannon needs exactly one piece, the screen, between it and its target."
+        }
+        IllegalMoveReason::FlyingGeneral => {
+            "Keep a piece between the two generals whenever they share a file."
+        }
+        IllegalMoveReason::LeavesGeneralInCheck => {
+            "Every move must leave your general safe from capture."
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_piece_has_rules() {
+        for piece_type in [
+            PieceType::General,
+            PieceType::Advisor,
+            PieceType::Elephant,
+            PieceType::Horse,
+            PieceType::Chariot,
+            PieceType::Cannon,
+            PieceType::Soldier,
+        ] {
+            let rules = piece_rules(piece_type);
+            assert!(!rules.rules.is_empty(), "{}", rules.name);
+            assert_eq!(
+                lesson(IllegalMoveReason::WrongPattern(piece_type)),
+                rules.rules[0]
+            );
+        }
+    }
+}
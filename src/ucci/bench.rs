@@ -0,0 +1,216 @@
+//! Fixed-depth benchmark of an engine over a suite of test positions
+//!
+//! [`run_bench`] searches each position of a suite to the same depth and
+//! records the engine's speed and whether it found the expected move.
+//! Running the same suite against several engines compares them; since
+//! every search is followed line by line, it also exercises the client on
+//! engines with long outputs.
+
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::ucci::client::{MoveResult, UcciClient};
+use crate::ucci::engine::EngineError;
+
+/// A test position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchPosition {
+    pub name: &'static str,
+    pub fen: &'static str,
+    /// Moves counted as solving the position, in ICCS; empty for positions
+    /// only timed
+    pub best: &'static [&'static str],
+}
+
+/// The built-in suite: two openings to time, and four tactics with a
+/// single winning move
+pub const SUITE: &[BenchPosition] = &[
+    BenchPosition {
+        name: "Start",
+        fen: "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1",
+        best: &[],
+    },
+    BenchPosition {
+        name: "Central cannon",
+        fen: "rnbakabr1/9/1c4nc1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C1N2/9/RNBAKAB1R w - - 4 3",
+        best: &[],
+    },
+    BenchPosition {
+        name: "Chariot mate",
+        fen: "3k5/R8/9/9/9/9/9/9/9/4K3R w - - 0 1",
+        best: &["i9i0"],
+    },
+    BenchPosition {
+        name: "Horse takes chariot",
+        fen: "4k4/9/9/9/4r4/9/3N5/9/9/3K5 w - - 0 1",
+        best: &["d6e4"],
+    },
+    BenchPosition {
+        name: "Cannon takes chariot",
+        fen: "1r1k5/9/9/1p7/9/9/9/1C7/9/4K4 w - - 0 1",
+        best: &["b7b0"],
+    },
+    BenchPosition {
+        name: "Black chariot mate",
+        fen: "4k3r/9/9/9/9/9/9/9/r8/3K5 b - - 0 1",
+        best: &["i0i9"],
+    },
+];
+
+/// How the engine did on one position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BenchResult {
+    pub name: String,
+    /// The engine's move, None if it had none or offered a draw or resigned
+    pub best_move: Option<String>,
+    /// Whether the move was one of those expected; None for positions
+    /// only timed
+    pub solved: Option<bool>,
+    /// Deepest depth the engine reported
+    pub depth: Option<u32>,
+    /// Nodes from the engine's last report
+    pub nodes: Option<u64>,
+    /// Wall-clock time of the search in milliseconds
+    pub time_ms: u64,
+    /// Info lines read during the search
+    pub info_lines: usize,
+}
+
+impl BenchResult {
+    /// Nodes per second, if the engine reported nodes
+    pub fn nps(&self) -> Option<u64> {
+        self.nodes.map(|nodes| nodes * 1000 / self.time_ms.max(1))
+    }
+}
+
+/// Results of a whole suite
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BenchReport {
+    pub engine: String,
+    pub depth: u32,
+    pub results: Vec<BenchResult>,
+}
+
+impl BenchReport {
+    /// Positions solved, and positions with an expected move
+    pub fn solved(&self) -> (usize, usize) {
+        let scored = self.results.iter().filter_map(|result| result.solved);
+        let (mut solved, mut total) = (0, 0);
+        for ok in scored {
+            solved += usize::from(ok);
+            total += 1;
+        }
+        (solved, total)
+    }
+
+    pub fn total_nodes(&self) -> u64 {
+        self.results.iter().filter_map(|result| result.nodes).sum()
+    }
+
+    pub fn total_time_ms(&self) -> u64 {
+        self.results.iter().map(|result| result.time_ms).sum()
+    }
+
+    /// The report as a table with a total line
+    pub fn to_text(&self) -> String {
+        let mut out = format!("Bench: {}, depth {}\n\n", self.engine, self.depth);
+        out.push_str(&format!(
+            "{:<22} {:<6} {:<6} {:>5} {:>12} {:>8} {:>10} {:>6}\n",
+            "Position", "Move", "Solved", "Depth", "Nodes", "Time ms", "NPS", "Lines"
+        ));
+        let dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        for result in &self.results {
+            let solved = match result.solved {
+                Some(true) => "yes",
+                Some(false) => "no",
+                None => "-",
+            };
+            out.push_str(&format!(
+                "{:<22} {:<6} {:<6} {:>5} {:>12} {:>8} {:>10} {:>6}\n",
+                result.name,
+                dash(result.best_move.clone()),
+                solved,
+                dash(result.depth.map(|depth| depth.to_string())),
+                dash(result.nodes.map(|nodes| nodes.to_string())),
+                result.time_ms,
+                dash(result.nps().map(|nps| nps.to_string())),
+                result.info_lines,
+            ));
+        }
+        let (solved, scored) = self.solved();
+        let time_ms = self.total_time_ms();
+        out.push_str(&format!(
+            "\nSolved {}/{}, {} nodes in {} ms ({} nps)\n",
+            solved,
+            scored,
+            self.total_nodes(),
+            time_ms,
+            self.total_nodes() * 1000 / time_ms.max(1)
+        ));
+        out
+    }
+}
+
+/// Search every position of `suite` to `depth` and collect the results
+///
+/// The engine must be initialized and idle.
+pub fn run_bench(
+    client: &mut UcciClient,
+    suite: &[BenchPosition],
+    depth: u32,
+) -> Result<BenchReport, EngineError> {
+    let mut results = Vec::with_capacity(suite.len());
+    for position in suite {
+        client.set_position(position.fen, &[])?;
+        let started = Instant::now();
+        client.go_depth(depth)?;
+        let result = client.wait()?;
+        let time_ms = started.elapsed().as_millis() as u64;
+        let infos = client.read_info();
+
+        let best_move = match result {
+            MoveResult::Move(mv, _) => Some(mv),
+            MoveResult::NoMove | MoveResult::Draw | MoveResult::Resign => None,
+        };
+        let solved = (!position.best.is_empty()).then(|| {
+            best_move
+                .as_deref()
+                .is_some_and(|mv| position.best.contains(&mv))
+        });
+        results.push(BenchResult {
+            name: position.name.to_string(),
+            best_move,
+            solved,
+            depth: infos.iter().filter_map(|info| info.depth).max(),
+            nodes: infos.iter().rev().find_map(|info| info.nodes),
+            time_ms,
+            info_lines: infos.len(),
+        });
+    }
+    Ok(BenchReport {
+        engine: client.engine_info().name.clone(),
+        depth,
+        results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Searcher;
+    use crate::game::Game;
+    use crate::notation::iccs::move_to_iccs;
+
+    #[test]
+    fn test_suite_answers_are_found() {
+        for position in SUITE {
+            let game = Game::from_fen(position.fen).unwrap();
+            let result = Searcher::new(3).search(game.board(), game.turn()).unwrap();
+            if !position.best.is_empty() {
+                let mv = move_to_iccs(result.mv.from, result.mv.to);
+                assert!(position.best.contains(&mv.as_str()), "{}", position.name);
+            }
+        }
+    }
+}
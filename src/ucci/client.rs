@@ -1,11 +1,13 @@
 //! High-level UCCI client API
 
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use crate::ucci::engine::{EngineError, EngineProcess};
-use crate::ucci::parser::parse_response;
-use crate::ucci::protocol::{GoMode, OptionType, UcciCommand};
+use crate::ucci::parser::{parse_response, ParseError};
+use crate::ucci::protocol::{EngineProtocol, GoMode, OptionType, UcciCommand, UcciResponse};
 use crate::ucci::state::UcciStateMachine;
+use crate::ucci::uci::{self, UciSquares};
 
 // Re-export MoveResult since it's part of the public API
 pub use crate::ucci::protocol::MoveResult;
@@ -17,11 +19,47 @@ pub struct Info {
     pub nodes: Option<u64>,
     pub depth: Option<u32>,
     pub score: Option<i32>,
+    /// Which of the lines searched at once this is, from 1, for engines
+    /// set to search several
+    pub multipv: Option<u32>,
     pub pv: Vec<String>,
     pub currmove: Option<String>,
     pub message: Option<String>,
 }
 
+/// One of the lines an engine searches at once, as last reported
+///
+/// Engines set to search several lines (see
+/// [`UcciClient::set_multipv`]) report each under its index, best first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvLine {
+    /// Position among the lines, from 1 for the best
+    pub index: u32,
+    pub depth: Option<u32>,
+    /// Score for the side to move, in centipawns
+    pub score: Option<i32>,
+    pub pv: Vec<String>,
+}
+
+/// What an engine's hash table holds for a position, as answered to
+/// [`UcciClient::probe`]
+///
+/// Bounds are given as (score, depth): the position is worth at least (or
+/// at most) the score, as found by a search that deep.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PopHashInfo {
+    pub bestmove: Option<String>,
+    pub lowerbound: Option<(i32, u32)>,
+    pub upperbound: Option<(i32, u32)>,
+}
+
+impl PopHashInfo {
+    /// Whether the engine had anything stored for the position
+    pub fn is_hit(&self) -> bool {
+        self.bestmove.is_some() || self.lowerbound.is_some() || self.upperbound.is_some()
+    }
+}
+
 /// Engine information collected during initialization
 #[derive(Debug, Clone)]
 pub struct EngineInfo {
@@ -42,13 +80,28 @@ impl Default for EngineInfo {
     }
 }
 
+/// How long to wait for `ucciok` before trying UCI when detecting the
+/// protocol
+const DETECT_TIMEOUT_MS: u64 = 1000;
+
+/// How long to wait for `pophash` after `probe`
+const PROBE_TIMEOUT_MS: u64 = 500;
+
 /// High-level UCCI client
+///
+/// Also drives UCI engines; see [`initialize_with`](Self::initialize_with).
 pub struct UcciClient {
     engine: EngineProcess,
     state: UcciStateMachine,
     info: EngineInfo,
     options: HashMap<String, EngineOption>,
     last_infos: Vec<Info>,
+    /// The latest info for each line of the search, best first
+    pv_lines: Vec<PvLine>,
+    protocol: EngineProtocol,
+    squares: UciSquares,
+    /// The engine once left `probe` unanswered
+    probe_unsupported: bool,
 }
 
 impl UcciClient {
@@ -61,43 +114,94 @@ impl UcciClient {
             info: EngineInfo::default(),
             options: HashMap::new(),
             last_infos: Vec::new(),
+            pv_lines: Vec::new(),
+            protocol: EngineProtocol::Ucci,
+            squares: UciSquares::default(),
+            probe_unsupported: false,
         })
     }
 
     /// Initialize the engine (send ucci and wait for ucciok)
     pub fn initialize(&mut self) -> Result<(), EngineError> {
-        self.engine.send_command("ucci")?;
+        self.initialize_with(EngineProtocol::Ucci)
+    }
+
+    /// Initialize the engine in a protocol
+    ///
+    /// With [`EngineProtocol::Auto`] the engine is sent `ucci`, then `uci`
+    /// if it hasn't answered within a second; the protocol it answers in
+    /// is used from then on. A UCI engine with a `UCI_Variant` option, such
+    /// as Fairy-Stockfish, is switched to xiangqi and its squares numbered
+    /// from 1.
+    pub fn initialize_with(&mut self, protocol: EngineProtocol) -> Result<(), EngineError> {
+        match protocol {
+            EngineProtocol::Auto => {
+                self.engine.send_command("ucci")?;
+                if !self.read_handshake(Some(DETECT_TIMEOUT_MS))? {
+                    self.protocol = EngineProtocol::Uci;
+                    self.engine.send_command("uci")?;
+                    self.read_handshake(None)?;
+                }
+            }
+            EngineProtocol::Ucci => {
+                self.engine.send_command("ucci")?;
+                self.read_handshake(None)?;
+            }
+            EngineProtocol::Uci => {
+                self.protocol = EngineProtocol::Uci;
+                self.engine.send_command("uci")?;
+                self.read_handshake(None)?;
+            }
+        }
 
-        // Read responses until ucciok
+        if self.protocol == EngineProtocol::Uci && self.options.contains_key("UCI_Variant") {
+            self.squares = UciSquares::ONE_BASED;
+            self.set_option("UCI_Variant", "xiangqi")?;
+        }
+        Ok(())
+    }
+
+    /// Read the engine's id and options up to `ucciok` or `uciok`
+    ///
+    /// The ok line decides the protocol, so an engine slow to answer
+    /// `ucci` is still recognized. Other output, such as a banner, is
+    /// skipped. Returns false if `timeout_ms` passes between lines.
+    fn read_handshake(&mut self, timeout_ms: Option<u64>) -> Result<bool, EngineError> {
         loop {
-            let line = self.engine.read_line()?;
-            let resp = parse_response(&line).map_err(|_| {
-                EngineError::ReadFailed(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "Parse error",
-                ))
-            })?;
+            let line = match timeout_ms {
+                Some(ms) => match self.engine.read_line_timeout(ms) {
+                    Err(EngineError::Timeout) => return Ok(false),
+                    line => line?,
+                },
+                None => self.engine.read_line()?,
+            };
+            match line.trim() {
+                "ucciok" => self.protocol = EngineProtocol::Ucci,
+                "uciok" => self.protocol = EngineProtocol::Uci,
+                _ => {}
+            }
+            let Ok(resp) = self.parse(&line) else {
+                continue;
+            };
 
             match resp {
-                crate::ucci::UcciResponse::UcciOk => {
-                    self.state
-                        .on_response(&crate::ucci::UcciResponse::UcciOk)
-                        .map_err(|e| {
-                            EngineError::WriteFailed(std::io::Error::other(format!(
-                                "State error: {:?}",
-                                e
-                            )))
-                        })?;
-                    break;
+                UcciResponse::UcciOk => {
+                    self.state.on_response(&UcciResponse::UcciOk).map_err(|e| {
+                        EngineError::WriteFailed(std::io::Error::other(format!(
+                            "State error: {:?}",
+                            e
+                        )))
+                    })?;
+                    return Ok(true);
                 }
-                crate::ucci::UcciResponse::Id { field, value } => match field.as_str() {
+                UcciResponse::Id { field, value } => match field.as_str() {
                     "name" => self.info.name = value,
                     "author" => self.info.author = Some(value),
                     "copyright" => self.info.copyright = Some(value),
                     "user" => self.info.user = Some(value),
                     _ => {}
                 },
-                crate::ucci::UcciResponse::Option {
+                UcciResponse::Option {
                     name,
                     type_,
                     min,
@@ -120,8 +224,33 @@ impl UcciClient {
                 _ => {}
             }
         }
+    }
 
-        Ok(())
+    /// The protocol the engine speaks, once initialized
+    pub fn protocol(&self) -> EngineProtocol {
+        self.protocol
+    }
+
+    /// Send a command in the engine's protocol
+    ///
+    /// Commands UCI has no equivalent for are skipped.
+    fn send(&mut self, cmd: &UcciCommand) -> Result<(), EngineError> {
+        let text = match self.protocol {
+            EngineProtocol::Uci => match uci::serialize_command(cmd, self.squares) {
+                Some(text) => text,
+                None => return Ok(()),
+            },
+            _ => cmd.serialize(),
+        };
+        self.engine.send_command(&text)
+    }
+
+    /// Parse a line of output in the engine's protocol
+    fn parse(&self, line: &str) -> Result<UcciResponse, ParseError> {
+        match self.protocol {
+            EngineProtocol::Uci => uci::parse_response(line, self.squares),
+            _ => parse_response(line),
+        }
     }
 
     /// Check if engine is ready
@@ -141,7 +270,28 @@ impl UcciClient {
         self.state
             .transition(&cmd)
             .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
-        self.engine.send_command(&cmd.serialize())
+        self.send(&cmd)
+    }
+
+    /// Ask the engine to search `lines` lines at once, if it has a
+    /// `MultiPV` option, capped at the option's maximum
+    ///
+    /// Returns whether the engine has the option.
+    pub fn set_multipv(&mut self, lines: u32) -> Result<bool, EngineError> {
+        let Some(option) = self
+            .options
+            .values()
+            .find(|option| option.name.eq_ignore_ascii_case("multipv"))
+        else {
+            return Ok(false);
+        };
+        let lines = match option.max {
+            Some(max) => lines.min(max.max(1) as u32),
+            None => lines,
+        };
+        let name = option.name.clone();
+        self.set_option(&name, &lines.max(1).to_string())?;
+        Ok(true)
     }
 
     /// Set the board position
@@ -154,7 +304,7 @@ impl UcciClient {
         self.state
             .transition(&cmd)
             .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
-        self.engine.send_command(&cmd.serialize())
+        self.send(&cmd)
     }
 
     /// Set banned moves (for solving repetition problems)
@@ -166,13 +316,63 @@ impl UcciClient {
         self.state
             .transition(&cmd)
             .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
-        self.engine.send_command(&cmd.serialize())
+        self.send(&cmd)
+    }
+
+    /// Ask the engine what its hash table holds for a position
+    ///
+    /// The engine must be idle. UCI has no `probe`, so UCI engines get an
+    /// error without being asked. An engine that doesn't answer within half
+    /// a second gives [`EngineError::Timeout`] and is not asked again.
+    pub fn probe(&mut self, fen: &str, moves: &[String]) -> Result<PopHashInfo, EngineError> {
+        self.ensure_idle()?;
+        if self.protocol == EngineProtocol::Uci {
+            return Err(EngineError::WriteFailed(std::io::Error::other(
+                "probe is not supported over UCI",
+            )));
+        }
+        if self.probe_unsupported {
+            return Err(EngineError::Timeout);
+        }
+        let cmd = UcciCommand::Probe {
+            fen: fen.to_string(),
+            moves: moves.to_vec(),
+        };
+        self.state
+            .transition(&cmd)
+            .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
+        self.send(&cmd)?;
+
+        let deadline = Instant::now() + Duration::from_millis(PROBE_TIMEOUT_MS);
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let line = match self.engine.read_line_timeout(left.as_millis() as u64) {
+                Err(EngineError::Timeout) => {
+                    self.probe_unsupported = true;
+                    return Err(EngineError::Timeout);
+                }
+                line => line?,
+            };
+            if let Ok(UcciResponse::PopHash {
+                bestmove,
+                lowerbound,
+                upperbound,
+            }) = self.parse(&line)
+            {
+                return Ok(PopHashInfo {
+                    bestmove,
+                    lowerbound,
+                    upperbound,
+                });
+            }
+        }
     }
 
     /// Start searching to a specific depth
     pub fn go_depth(&mut self, depth: u32) -> Result<(), EngineError> {
         self.ensure_idle()?;
         self.last_infos.clear();
+        self.pv_lines.clear();
         let cmd = UcciCommand::Go {
             mode: GoMode::Depth(depth),
             ponder: false,
@@ -181,13 +381,14 @@ impl UcciClient {
         self.state
             .transition(&cmd)
             .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
-        self.engine.send_command(&cmd.serialize())
+        self.send(&cmd)
     }
 
     /// Start searching with a time limit (in milliseconds)
     pub fn go_time(&mut self, time_ms: u64) -> Result<(), EngineError> {
         self.ensure_idle()?;
         self.last_infos.clear();
+        self.pv_lines.clear();
         let cmd = UcciCommand::Go {
             mode: GoMode::Time {
                 time: time_ms,
@@ -203,13 +404,30 @@ impl UcciClient {
         self.state
             .transition(&cmd)
             .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
-        self.engine.send_command(&cmd.serialize())
+        self.send(&cmd)
+    }
+
+    /// Start searching a number of positions
+    pub fn go_nodes(&mut self, nodes: u64) -> Result<(), EngineError> {
+        self.ensure_idle()?;
+        self.last_infos.clear();
+        self.pv_lines.clear();
+        let cmd = UcciCommand::Go {
+            mode: GoMode::Nodes(nodes),
+            ponder: false,
+            draw: false,
+        };
+        self.state
+            .transition(&cmd)
+            .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
+        self.send(&cmd)
     }
 
     /// Start infinite search (until stop)
     pub fn go_infinite(&mut self) -> Result<(), EngineError> {
         self.ensure_idle()?;
         self.last_infos.clear();
+        self.pv_lines.clear();
         let cmd = UcciCommand::Go {
             mode: GoMode::Infinite,
             ponder: false,
@@ -218,7 +436,40 @@ impl UcciClient {
         self.state
             .transition(&cmd)
             .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
-        self.engine.send_command(&cmd.serialize())
+        self.send(&cmd)
+    }
+
+    /// Start pondering to a specific depth
+    ///
+    /// The position should end with the reply the engine expects, as given
+    /// by the `ponder` move of its last best move. Follow up with
+    /// [`ponder_hit`](Self::ponder_hit) if that move is played or
+    /// [`stop`](Self::stop) if it is not.
+    pub fn go_ponder_depth(&mut self, depth: u32) -> Result<(), EngineError> {
+        self.ensure_idle()?;
+        self.last_infos.clear();
+        self.pv_lines.clear();
+        let cmd = UcciCommand::Go {
+            mode: GoMode::Depth(depth),
+            ponder: true,
+            draw: false,
+        };
+        self.state
+            .transition(&cmd)
+            .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
+        self.send(&cmd)
+    }
+
+    /// Tell a pondering engine that the expected move was played
+    ///
+    /// The search goes on as a normal one and ends with a best move for
+    /// the new position.
+    pub fn ponder_hit(&mut self) -> Result<(), EngineError> {
+        let cmd = UcciCommand::PonderHit { draw: false };
+        self.state
+            .transition(&cmd)
+            .map_err(|e| EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e))))?;
+        self.send(&cmd)
     }
 
     /// Stop the current search and get the result
@@ -230,65 +481,168 @@ impl UcciClient {
         }
 
         self.engine.send_command("stop")?;
+        self.read_best_move()
+    }
 
-        // Read info messages until bestmove
+    /// Stop the current search, giving the engine `timeout_ms` to answer
+    ///
+    /// Returns [`EngineError::Timeout`] if no best move arrives in time;
+    /// the engine is then left searching.
+    pub fn stop_within(&mut self, timeout_ms: u64) -> Result<MoveResult, EngineError> {
+        if !self.state.is_thinking() {
+            return Err(EngineError::WriteFailed(std::io::Error::other(
+                "Not in thinking state",
+            )));
+        }
+
+        self.engine.send_command("stop")?;
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
         loop {
-            let line = self.engine.read_line()?;
-            let resp = parse_response(&line).map_err(|_| {
-                EngineError::ReadFailed(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "Parse error",
-                ))
-            })?;
+            let left = deadline.saturating_duration_since(Instant::now());
+            let line = self.engine.read_line_timeout(left.as_millis() as u64)?;
+            if let Some(result) = self.handle_search_line(&line)? {
+                return Ok(result);
+            }
+        }
+    }
 
-            match resp {
-                crate::ucci::UcciResponse::BestMove {
-                    ref mv,
-                    ref ponder,
-                    draw,
-                    resign,
-                } => {
-                    let result = if resign {
-                        MoveResult::Resign
-                    } else if draw {
-                        MoveResult::Draw
-                    } else {
-                        MoveResult::Move(mv.clone(), ponder.clone())
-                    };
-
-                    self.state.on_response(&resp).map_err(|e| {
-                        EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e)))
-                    })?;
+    /// Wait for the current search to finish on its own and get the result
+    ///
+    /// Unlike [`stop`](Self::stop), the engine is left to reach its depth or
+    /// time limit.
+    pub fn wait(&mut self) -> Result<MoveResult, EngineError> {
+        if !self.state.is_thinking() {
+            return Err(EngineError::WriteFailed(std::io::Error::other(
+                "Not in thinking state",
+            )));
+        }
+        self.read_best_move()
+    }
 
-                    return Ok(result);
-                }
-                crate::ucci::UcciResponse::NoBestMove => {
-                    self.state.on_response(&resp).map_err(|e| {
-                        EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e)))
-                    })?;
-                    return Ok(MoveResult::NoMove);
+    /// Collect any output the engine has sent without waiting for more
+    ///
+    /// Info messages are added to those returned by
+    /// [`read_info`](Self::read_info). Returns the result once the engine
+    /// sends its best move, and `Ok(None)` while the search is still running,
+    /// which makes this suitable for following an infinite search from an
+    /// event loop.
+    pub fn poll(&mut self) -> Result<Option<MoveResult>, EngineError> {
+        if !self.state.is_thinking() {
+            return Err(EngineError::WriteFailed(std::io::Error::other(
+                "Not in thinking state",
+            )));
+        }
+        while let Some(line) = self.engine.try_read_line()? {
+            if let Some(result) = self.handle_search_line(&line)? {
+                return Ok(Some(result));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Like [`poll`](Self::poll), but wait up to `timeout_ms` for the
+    /// engine's best move
+    ///
+    /// Returns `Ok(None)` if the search is still running when the time is
+    /// up.
+    pub fn poll_within(&mut self, timeout_ms: u64) -> Result<Option<MoveResult>, EngineError> {
+        if !self.state.is_thinking() {
+            return Err(EngineError::WriteFailed(std::io::Error::other(
+                "Not in thinking state",
+            )));
+        }
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let line = match self.engine.read_line_timeout(left.as_millis() as u64) {
+                Ok(line) => line,
+                Err(EngineError::Timeout) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            if let Some(result) = self.handle_search_line(&line)? {
+                return Ok(Some(result));
+            }
+        }
+    }
+
+    /// Read info messages until bestmove
+    fn read_best_move(&mut self) -> Result<MoveResult, EngineError> {
+        loop {
+            let line = self.engine.read_line()?;
+            if let Some(result) = self.handle_search_line(&line)? {
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Handle one line of output during a search, returning the result if
+    /// the search is over
+    fn handle_search_line(&mut self, line: &str) -> Result<Option<MoveResult>, EngineError> {
+        let resp = self.parse(line).map_err(|_| {
+            EngineError::ReadFailed(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Parse error",
+            ))
+        })?;
+
+        match resp {
+            crate::ucci::UcciResponse::BestMove {
+                ref mv,
+                ref ponder,
+                draw,
+                resign,
+            } => {
+                let result = if resign {
+                    MoveResult::Resign
+                } else if draw {
+                    MoveResult::Draw
+                } else {
+                    MoveResult::Move(mv.clone(), ponder.clone())
+                };
+
+                self.state.on_response(&resp).map_err(|e| {
+                    EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e)))
+                })?;
+
+                Ok(Some(result))
+            }
+            crate::ucci::UcciResponse::NoBestMove => {
+                self.state.on_response(&resp).map_err(|e| {
+                    EngineError::WriteFailed(std::io::Error::other(format!("{:?}", e)))
+                })?;
+                Ok(Some(MoveResult::NoMove))
+            }
+            crate::ucci::UcciResponse::Info {
+                time,
+                nodes,
+                depth,
+                score,
+                multipv,
+                pv,
+                currmove,
+                message,
+            } => {
+                if !pv.is_empty() {
+                    self.update_pv_line(PvLine {
+                        index: multipv.unwrap_or(1).max(1),
+                        depth,
+                        score,
+                        pv: pv.clone(),
+                    });
                 }
-                crate::ucci::UcciResponse::Info {
-                    time,
+                self.last_infos.push(Info {
+                    time_ms: time,
                     nodes,
                     depth,
                     score,
+                    multipv,
                     pv,
                     currmove,
                     message,
-                } => {
-                    self.last_infos.push(Info {
-                        time_ms: time,
-                        nodes,
-                        depth,
-                        score,
-                        pv,
-                        currmove,
-                        message,
-                    });
-                }
-                _ => {}
+                });
+                Ok(None)
             }
+            _ => Ok(None),
         }
     }
 
@@ -297,6 +651,25 @@ impl UcciClient {
         &self.info
     }
 
+    /// The latest info for each line of the current or last search, best
+    /// first; a single line unless [`set_multipv`](Self::set_multipv) asked
+    /// for more
+    pub fn pv_lines(&self) -> &[PvLine] {
+        &self.pv_lines
+    }
+
+    /// Keep `line` as the latest for its index
+    ///
+    /// A line whose better lines have not been reported yet is dropped.
+    fn update_pv_line(&mut self, line: PvLine) {
+        let index = line.index as usize - 1;
+        if index < self.pv_lines.len() {
+            self.pv_lines[index] = line;
+        } else if index == self.pv_lines.len() {
+            self.pv_lines.push(line);
+        }
+    }
+
     /// Read and drain the last info messages received during the previous search
     pub fn read_info(&mut self) -> Vec<Info> {
         std::mem::take(&mut self.last_infos)
@@ -312,6 +685,11 @@ impl UcciClient {
         self.state.is_thinking()
     }
 
+    /// Check if currently pondering
+    pub fn is_pondering(&self) -> bool {
+        self.state.is_pondering()
+    }
+
     /// Check if currently idle
     pub fn is_idle(&self) -> bool {
         self.state.is_idle()
@@ -1,7 +1,8 @@
 //! Engine process spawning and communication
 
 use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
 use std::thread;
 use std::time::Duration;
 
@@ -41,10 +42,13 @@ impl std::error::Error for EngineError {
 }
 
 /// Manages communication with an external UCCI engine process
+///
+/// The engine's output is read on a background thread and handed over one
+/// line at a time, so callers can poll for output without blocking.
 pub struct EngineProcess {
     child: Child,
     stdin: BufWriter<ChildStdin>,
-    stdout: BufReader<ChildStdout>,
+    lines: Receiver<std::io::Result<String>>,
 }
 
 impl EngineProcess {
@@ -70,13 +74,20 @@ impl EngineProcess {
             ))
         })?;
 
-        let stdin = BufWriter::new(stdin);
-        let stdout = BufReader::new(stdout);
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let failed = line.is_err();
+                if sender.send(line).is_err() || failed {
+                    break;
+                }
+            }
+        });
 
         Ok(Self {
             child,
-            stdin,
-            stdout,
+            stdin: BufWriter::new(stdin),
+            lines,
         })
     }
 
@@ -87,29 +98,38 @@ impl EngineProcess {
         Ok(())
     }
 
-    /// Read a single line from the engine
+    /// Read a single line from the engine, waiting until one arrives
     pub fn read_line(&mut self) -> Result<String, EngineError> {
-        let mut line = String::new();
-        self.stdout
-            .read_line(&mut line)
-            .map_err(EngineError::ReadFailed)?;
+        match self.lines.recv() {
+            Ok(line) => Self::received(line),
+            Err(_) => Err(EngineError::UnexpectedEof),
+        }
+    }
 
-        if line.is_empty() {
-            return Err(EngineError::UnexpectedEof);
+    /// Read a line if the engine has already sent one
+    ///
+    /// Returns `Ok(None)` when no output is waiting.
+    pub fn try_read_line(&mut self) -> Result<Option<String>, EngineError> {
+        match self.lines.try_recv() {
+            Ok(line) => Self::received(line).map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(EngineError::UnexpectedEof),
         }
+    }
 
-        // Trim newline but preserve other whitespace
-        Ok(line.trim_end().to_string())
+    /// Read a line, giving up with [`EngineError::Timeout`] after `timeout_ms`
+    pub fn read_line_timeout(&mut self, timeout_ms: u64) -> Result<String, EngineError> {
+        match self.lines.recv_timeout(Duration::from_millis(timeout_ms)) {
+            Ok(line) => Self::received(line),
+            Err(RecvTimeoutError::Timeout) => Err(EngineError::Timeout),
+            Err(RecvTimeoutError::Disconnected) => Err(EngineError::UnexpectedEof),
+        }
     }
 
-    /// Read a line with timeout (NOT YET IMPLEMENTED - currently blocks)
-    ///
-    /// TODO: Implement actual timeout with async I/O or separate thread.
-    /// For now, this blocks indefinitely just like read_line().
-    pub fn read_line_timeout(&mut self, _timeout_ms: u64) -> Result<String, EngineError> {
-        // TODO: Implement actual timeout with async I/O or separate thread
-        // For now, this blocks indefinitely
-        self.read_line()
+    fn received(line: std::io::Result<String>) -> Result<String, EngineError> {
+        // Trim newline but preserve other whitespace
+        line.map(|line| line.trim_end().to_string())
+            .map_err(EngineError::ReadFailed)
     }
 
     /// Check if the engine process is still running
@@ -186,6 +206,10 @@ while read line; do
             echo "info depth 10 score 100"
             echo "bestmove h2e2"
             ;;
+        "wait")
+            sleep 1
+            echo "done"
+            ;;
         "quit")
             echo "bye"
             exit 0
@@ -251,4 +275,26 @@ done
 
         engine.terminate().unwrap();
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_read_without_blocking() {
+        let mock = create_mock_engine();
+        let mut engine = EngineProcess::spawn(mock.to_str().unwrap()).unwrap();
+
+        engine.send_command("wait").unwrap();
+        assert!(engine.try_read_line().unwrap().is_none());
+        assert!(matches!(
+            engine.read_line_timeout(10),
+            Err(EngineError::Timeout)
+        ));
+        assert_eq!(engine.read_line_timeout(5000).unwrap(), "done");
+
+        engine.send_command("quit").unwrap();
+        assert_eq!(engine.read_line().unwrap(), "bye");
+        assert!(matches!(
+            engine.read_line(),
+            Err(EngineError::UnexpectedEof)
+        ));
+    }
 }
@@ -0,0 +1,169 @@
+//! Several engines analyzing the same position side by side
+//!
+//! Each engine in the config's `[[kibitz]]` tables gets its own
+//! [`UcciClient`], searching the current position without a limit. Engines
+//! that offer a `MultiPV` option are asked for several lines at once.
+//! [`Kibitz::poll`] collects what each has found so far without blocking,
+//! and sets them all on a new position when the game moves on.
+
+use crate::ucci::client::{PvLine, UcciClient};
+use crate::ucci::engine::EngineError;
+use crate::ucci::protocol::EngineProtocol;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// An engine to kibitz with, from a `[[kibitz]]` table in `config.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KibitzEngine {
+    pub path: PathBuf,
+    /// Protocol the engine speaks; detected if not set
+    #[serde(default)]
+    pub protocol: EngineProtocol,
+    /// How many lines to search at once, for engines with a `MultiPV`
+    /// option
+    pub multipv: Option<u32>,
+}
+
+/// One engine's analysis
+pub struct Kibitzer {
+    name: String,
+    client: UcciClient,
+    /// FEN of the position being analyzed
+    fen: Option<String>,
+}
+
+impl Kibitzer {
+    /// Start an engine and set it up for `settings.multipv` lines
+    pub fn start(settings: &KibitzEngine) -> Result<Self, EngineError> {
+        let mut client = UcciClient::new(&settings.path.to_string_lossy())?;
+        client.initialize_with(settings.protocol)?;
+        if let Some(lines) = settings.multipv.filter(|&lines| lines > 1) {
+            client.set_multipv(lines)?;
+        }
+        let name = match client.engine_info().name.as_str() {
+            "Unknown" => file_name(&settings.path),
+            name => name.to_string(),
+        };
+        Ok(Kibitzer {
+            name,
+            client,
+            fen: None,
+        })
+    }
+
+    /// The engine's name, as it gives it or else its file's
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The engine's lines for the position, best first; empty until it
+    /// reports one
+    pub fn lines(&self) -> &[PvLine] {
+        self.client.pv_lines()
+    }
+
+    /// Search `fen` until the next position
+    fn analyze(&mut self, fen: &str) -> Result<(), EngineError> {
+        self.stop()?;
+        self.client.set_position(fen, &[])?;
+        self.client.go_infinite()?;
+        self.fen = Some(fen.to_string());
+        Ok(())
+    }
+
+    /// Collect the engine's lines for `fen`, starting over if it is a new
+    /// position; returns whether there is anything new to show
+    fn poll(&mut self, fen: &str) -> Result<bool, EngineError> {
+        if self.fen.as_deref() != Some(fen) {
+            self.analyze(fen)?;
+            return Ok(true);
+        }
+        // An engine may end an infinite search early, e.g. on finding mate;
+        // its last lines stay on show
+        if !self.client.is_thinking() {
+            return Ok(false);
+        }
+        self.client.poll()?;
+        let infos = self.client.read_info();
+        Ok(infos.iter().any(|info| !info.pv.is_empty()))
+    }
+
+    fn stop(&mut self) -> Result<(), EngineError> {
+        if self.client.is_thinking() {
+            self.client.stop()?;
+        }
+        self.client.read_info();
+        Ok(())
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// The engines kibitzing on the game
+#[derive(Default)]
+pub struct Kibitz {
+    kibitzers: Vec<Kibitzer>,
+}
+
+impl Kibitz {
+    /// Start every engine in `engines` that can be started
+    ///
+    /// Engines that fail to start are left out, and returned with their
+    /// errors.
+    pub fn start(engines: &[KibitzEngine]) -> (Self, Vec<(PathBuf, EngineError)>) {
+        let mut kibitzers = Vec::new();
+        let mut failed = Vec::new();
+        for settings in engines {
+            match Kibitzer::start(settings) {
+                Ok(kibitzer) => kibitzers.push(kibitzer),
+                Err(e) => failed.push((settings.path.clone(), e)),
+            }
+        }
+        (Kibitz { kibitzers }, failed)
+    }
+
+    /// The engines, in the order they were configured
+    pub fn kibitzers(&self) -> &[Kibitzer] {
+        &self.kibitzers
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kibitzers.is_empty()
+    }
+
+    /// Collect every engine's lines for `fen`, setting engines on it when
+    /// the position has changed; returns whether any changed
+    ///
+    /// An engine that fails is dropped, and its name returned with the
+    /// error.
+    pub fn poll(&mut self, fen: &str) -> (bool, Vec<(String, EngineError)>) {
+        let mut changed = false;
+        let mut failed = Vec::new();
+        let mut i = 0;
+        while i < self.kibitzers.len() {
+            match self.kibitzers[i].poll(fen) {
+                Ok(updated) => {
+                    changed |= updated;
+                    i += 1;
+                }
+                Err(e) => {
+                    let kibitzer = self.kibitzers.remove(i);
+                    failed.push((kibitzer.name, e));
+                    changed = true;
+                }
+            }
+        }
+        (changed, failed)
+    }
+
+    /// Stop the engines' searches
+    pub fn stop(&mut self) {
+        for kibitzer in &mut self.kibitzers {
+            let _ = kibitzer.stop();
+        }
+    }
+}
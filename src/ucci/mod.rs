@@ -1,14 +1,20 @@
 //! UCCI (Universal Chinese Chess Protocol) implementation
 //!
 //! This module provides a full UCCI 3.0 compliant interface for communicating
-//! with external Chinese chess engines.
+//! with external Chinese chess engines, and speaks UCI to engines that use
+//! it instead (see [`uci`]).
 
+pub mod bench;
 pub mod client;
 pub mod engine;
+pub mod kibitz;
 pub mod parser;
 pub mod protocol;
+pub mod search;
 pub mod serializer;
+pub mod server;
 pub mod state;
+pub mod uci;
 
-pub use client::{Info, MoveResult, UcciClient};
-pub use protocol::{GoMode, UcciCommand, UcciResponse};
+pub use client::{Info, MoveResult, PopHashInfo, PvLine, UcciClient};
+pub use protocol::{EngineProtocol, GoMode, UcciCommand, UcciResponse};
@@ -1,6 +1,9 @@
-//! Parse UCCI responses from engine output
+//! Parse UCCI responses from engine output, and commands sent to an
+//! engine for the engine side of the protocol
 
-use crate::ucci::protocol::{OptionType, UcciResponse};
+use crate::game::Game;
+use crate::ucci::protocol::{GoMode, OptionType, UcciCommand, UcciResponse};
+use std::str::FromStr;
 
 /// Error type for parsing failures
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -11,6 +14,21 @@ pub enum ParseError {
     MissingRequiredField(String),
 }
 
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidFormat(msg) => write!(f, "Invalid engine output: {}", msg),
+            ParseError::UnknownCommand(cmd) => write!(f, "Unknown engine command: {}", cmd),
+            ParseError::InvalidParameter(msg) => write!(f, "Invalid engine parameter: {}", msg),
+            ParseError::MissingRequiredField(field) => {
+                write!(f, "Engine output is missing {}", field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 /// Parse a single line of engine output into a UCCI response
 pub fn parse_response(line: &str) -> Result<UcciResponse, ParseError> {
     let line = line.trim();
@@ -37,6 +55,137 @@ pub fn parse_response(line: &str) -> Result<UcciResponse, ParseError> {
     }
 }
 
+/// Parse a single line sent to an engine into a UCCI command
+///
+/// `position startpos` is read as the FEN of the initial position, and a
+/// `go` without a search limit as `go infinite`.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::ucci::parser::parse_command;
+/// use cn_chess_tui::ucci::{GoMode, UcciCommand};
+///
+/// let cmd = parse_command("go draw depth 8").unwrap();
+/// assert_eq!(
+///     cmd,
+///     UcciCommand::Go { mode: GoMode::Depth(8), ponder: false, draw: true }
+/// );
+/// assert_eq!(parse_command(&cmd.serialize()).unwrap(), cmd);
+/// ```
+pub fn parse_command(line: &str) -> Result<UcciCommand, ParseError> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let Some((&name, args)) = parts.split_first() else {
+        return Err(ParseError::InvalidFormat("Empty line".to_string()));
+    };
+
+    match name {
+        "ucci" => Ok(UcciCommand::Ucci),
+        "isready" => Ok(UcciCommand::IsReady),
+        "stop" => Ok(UcciCommand::Stop),
+        "quit" => Ok(UcciCommand::Quit),
+        "ponderhit" => Ok(UcciCommand::PonderHit {
+            draw: args.first() == Some(&"draw"),
+        }),
+        "setoption" => match args.split_first() {
+            Some((name, value)) => Ok(UcciCommand::SetOption {
+                name: name.to_string(),
+                value: (!value.is_empty()).then(|| value.join(" ")),
+            }),
+            None => Err(ParseError::MissingRequiredField("option name".to_string())),
+        },
+        "position" => {
+            let (fen, moves) = parse_position(args)?;
+            Ok(UcciCommand::Position { fen, moves })
+        }
+        "probe" => {
+            let (fen, moves) = parse_position(args)?;
+            Ok(UcciCommand::Probe { fen, moves })
+        }
+        "banmoves" => Ok(UcciCommand::BanMoves {
+            moves: args.iter().map(|mv| mv.to_string()).collect(),
+        }),
+        "go" => parse_go(args),
+        _ => Err(ParseError::UnknownCommand(name.to_string())),
+    }
+}
+
+/// Read `fen <fen> [moves ...]` or `startpos [moves ...]`
+fn parse_position(args: &[&str]) -> Result<(String, Vec<String>), ParseError> {
+    let (position, moves) = match args.iter().position(|&arg| arg == "moves") {
+        Some(i) => (&args[..i], &args[i + 1..]),
+        None => (args, &[][..]),
+    };
+    let fen = match position {
+        ["startpos"] => Game::new().to_fen(),
+        ["fen", fen @ ..] if !fen.is_empty() => fen.join(" "),
+        _ => return Err(ParseError::MissingRequiredField("position".to_string())),
+    };
+    Ok((fen, moves.iter().map(|mv| mv.to_string()).collect()))
+}
+
+fn parse_go(args: &[&str]) -> Result<UcciCommand, ParseError> {
+    fn value<T: FromStr>(args: &[&str], i: usize) -> Result<T, ParseError> {
+        let raw = args
+            .get(i + 1)
+            .ok_or_else(|| ParseError::MissingRequiredField(format!("{} value", args[i])))?;
+        raw.parse()
+            .map_err(|_| ParseError::InvalidParameter(format!("{} {}", args[i], raw)))
+    }
+
+    let mut ponder = false;
+    let mut draw = false;
+    let mut mode = None;
+    let mut time = None;
+    let mut movestogo = None;
+    let mut increment = None;
+    let mut opptime = None;
+    let mut oppmovestogo = None;
+    let mut oppincrement = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let mut step = 2;
+        match args[i] {
+            "ponder" => {
+                ponder = true;
+                step = 1;
+            }
+            "draw" => {
+                draw = true;
+                step = 1;
+            }
+            "infinite" => {
+                mode = Some(GoMode::Infinite);
+                step = 1;
+            }
+            "depth" => mode = Some(GoMode::Depth(value(args, i)?)),
+            "nodes" => mode = Some(GoMode::Nodes(value(args, i)?)),
+            "time" => time = Some(value(args, i)?),
+            "movestogo" => movestogo = Some(value(args, i)?),
+            "increment" => increment = Some(value(args, i)?),
+            "opptime" => opptime = Some(value(args, i)?),
+            "oppmovestogo" => oppmovestogo = Some(value(args, i)?),
+            "oppincrement" => oppincrement = Some(value(args, i)?),
+            _ => step = 1,
+        }
+        i += step;
+    }
+
+    let mode = match (mode, time) {
+        (Some(mode), _) => mode,
+        (None, Some(time)) => GoMode::Time {
+            time,
+            movestogo,
+            increment,
+            opptime,
+            oppmovestogo,
+            oppincrement,
+        },
+        (None, None) => GoMode::Infinite,
+    };
+    Ok(UcciCommand::Go { mode, ponder, draw })
+}
+
 fn parse_id(line: &str) -> Result<UcciResponse, ParseError> {
     let parts: Vec<&str> = line.splitn(3, ' ').collect();
     if parts.len() < 3 {
@@ -82,37 +231,21 @@ fn parse_option(line: &str) -> Result<UcciResponse, ParseError> {
     let mut i = 4;
     while i < parts.len() {
         match parts[i] {
-            "min" => {
-                if i + 1 < parts.len() {
-                    min = parts[i + 1].parse().ok();
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "min" if i + 1 < parts.len() => {
+                min = parts[i + 1].parse().ok();
+                i += 2;
             }
-            "max" => {
-                if i + 1 < parts.len() {
-                    max = parts[i + 1].parse().ok();
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "max" if i + 1 < parts.len() => {
+                max = parts[i + 1].parse().ok();
+                i += 2;
             }
-            "var" => {
-                if i + 1 < parts.len() {
-                    vars.push(parts[i + 1].to_string());
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "var" if i + 1 < parts.len() => {
+                vars.push(parts[i + 1].to_string());
+                i += 2;
             }
-            "default" => {
-                if i + 1 < parts.len() {
-                    default = Some(parts[i + 1].to_string());
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "default" if i + 1 < parts.len() => {
+                default = Some(parts[i + 1].to_string());
+                i += 2;
             }
             _ => {
                 i += 1;
@@ -145,13 +278,9 @@ fn parse_bestmove(line: &str) -> Result<UcciResponse, ParseError> {
     let mut i = 2;
     while i < parts.len() {
         match parts[i] {
-            "ponder" => {
-                if i + 1 < parts.len() {
-                    ponder = Some(parts[i + 1].to_string());
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "ponder" if i + 1 < parts.len() => {
+                ponder = Some(parts[i + 1].to_string());
+                i += 2;
             }
             "draw" => {
                 draw = true;
@@ -182,6 +311,7 @@ fn parse_info(line: &str) -> Result<UcciResponse, ParseError> {
     let mut nodes = None;
     let mut depth = None;
     let mut score = None;
+    let mut multipv = None;
     let mut pv = Vec::new();
     let mut currmove = None;
     let mut message = None;
@@ -189,37 +319,25 @@ fn parse_info(line: &str) -> Result<UcciResponse, ParseError> {
     let mut i = 1;
     while i < parts.len() {
         match parts[i] {
-            "time" => {
-                if i + 1 < parts.len() {
-                    time = parts[i + 1].parse().ok();
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "time" if i + 1 < parts.len() => {
+                time = parts[i + 1].parse().ok();
+                i += 2;
             }
-            "nodes" => {
-                if i + 1 < parts.len() {
-                    nodes = parts[i + 1].parse().ok();
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "nodes" if i + 1 < parts.len() => {
+                nodes = parts[i + 1].parse().ok();
+                i += 2;
             }
-            "depth" => {
-                if i + 1 < parts.len() {
-                    depth = parts[i + 1].parse().ok();
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "depth" if i + 1 < parts.len() => {
+                depth = parts[i + 1].parse().ok();
+                i += 2;
             }
-            "score" => {
-                if i + 1 < parts.len() {
-                    score = parts[i + 1].parse().ok();
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "score" if i + 1 < parts.len() => {
+                score = parts[i + 1].parse().ok();
+                i += 2;
+            }
+            "multipv" if i + 1 < parts.len() => {
+                multipv = parts[i + 1].parse().ok();
+                i += 2;
             }
             "pv" => {
                 // Collect remaining parts as PV
@@ -229,13 +347,9 @@ fn parse_info(line: &str) -> Result<UcciResponse, ParseError> {
                     i += 1;
                 }
             }
-            "currmove" => {
-                if i + 1 < parts.len() {
-                    currmove = Some(parts[i + 1].to_string());
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "currmove" if i + 1 < parts.len() => {
+                currmove = Some(parts[i + 1].to_string());
+                i += 2;
             }
             "message" => {
                 // Collect remaining as message
@@ -256,6 +370,7 @@ fn parse_info(line: &str) -> Result<UcciResponse, ParseError> {
         nodes,
         depth,
         score,
+        multipv,
         pv,
         currmove,
         message,
@@ -272,41 +387,29 @@ fn parse_pophash(line: &str) -> Result<UcciResponse, ParseError> {
     let mut i = 1;
     while i < parts.len() {
         match parts[i] {
-            "bestmove" => {
-                if i + 1 < parts.len() {
-                    bestmove = Some(parts[i + 1].to_string());
-                    i += 2;
-                } else {
-                    i += 1;
-                }
+            "bestmove" if i + 1 < parts.len() => {
+                bestmove = Some(parts[i + 1].to_string());
+                i += 2;
             }
-            "lowerbound" => {
-                if i + 2 < parts.len() {
-                    if let Ok(beta) = parts[i + 1].parse::<i32>() {
-                        if let Ok(depth) = parts[i + 2].parse::<u32>() {
-                            lowerbound = Some((beta, depth));
-                            i += 3;
-                            continue;
-                        }
+            "lowerbound" if i + 2 < parts.len() => {
+                if let Ok(beta) = parts[i + 1].parse::<i32>() {
+                    if let Ok(depth) = parts[i + 2].parse::<u32>() {
+                        lowerbound = Some((beta, depth));
+                        i += 3;
+                        continue;
                     }
-                    i += 1;
-                } else {
-                    i += 1;
                 }
+                i += 1;
             }
-            "upperbound" => {
-                if i + 2 < parts.len() {
-                    if let Ok(alpha) = parts[i + 1].parse::<i32>() {
-                        if let Ok(depth) = parts[i + 2].parse::<u32>() {
-                            upperbound = Some((alpha, depth));
-                            i += 3;
-                            continue;
-                        }
+            "upperbound" if i + 2 < parts.len() => {
+                if let Ok(alpha) = parts[i + 1].parse::<i32>() {
+                    if let Ok(depth) = parts[i + 2].parse::<u32>() {
+                        upperbound = Some((alpha, depth));
+                        i += 3;
+                        continue;
                     }
-                    i += 1;
-                } else {
-                    i += 1;
                 }
+                i += 1;
             }
             _ => {
                 i += 1;
@@ -373,8 +476,8 @@ mod tests {
             } => {
                 assert_eq!(mv, "h2e2");
                 assert_eq!(ponder, None);
-                assert_eq!(draw, false);
-                assert_eq!(resign, false);
+                assert!(!draw);
+                assert!(!resign);
             }
             _ => panic!("Wrong response type"),
         }
@@ -392,8 +495,8 @@ mod tests {
             } => {
                 assert_eq!(mv, "h2e2");
                 assert_eq!(ponder, Some("h9g7".to_string()));
-                assert_eq!(draw, false);
-                assert_eq!(resign, false);
+                assert!(!draw);
+                assert!(!resign);
             }
             _ => panic!("Wrong response type"),
         }
@@ -407,8 +510,8 @@ mod tests {
                 mv, draw, resign, ..
             } => {
                 assert_eq!(mv, "h2e2");
-                assert_eq!(draw, true);
-                assert_eq!(resign, false);
+                assert!(draw);
+                assert!(!resign);
             }
             _ => panic!("Wrong response type"),
         }
@@ -1,11 +1,46 @@
 //! UCCI protocol command and response types
 
+use serde::Deserialize;
+
+/// Protocol an engine speaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineProtocol {
+    /// Try UCCI, then UCI if the engine doesn't answer
+    #[default]
+    Auto,
+    Ucci,
+    Uci,
+}
+
+impl EngineProtocol {
+    /// Parse a protocol name as written in the config
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(EngineProtocol::Auto),
+            "ucci" => Some(EngineProtocol::Ucci),
+            "uci" => Some(EngineProtocol::Uci),
+            _ => None,
+        }
+    }
+
+    /// Name shown to the user
+    pub fn name(self) -> &'static str {
+        match self {
+            EngineProtocol::Auto => "auto",
+            EngineProtocol::Ucci => "UCCI",
+            EngineProtocol::Uci => "UCI",
+        }
+    }
+}
+
 /// Engine state in UCCI protocol
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EngineState {
-    Boot,     // Before ucci command
-    Idle,     // Waiting for commands
-    Thinking, // Searching for a move
+    Boot,      // Before ucci command
+    Idle,      // Waiting for commands
+    Thinking,  // Searching for a move
+    Pondering, // Searching on the opponent's time
 }
 
 /// UCCI commands sent from interface to engine
@@ -85,6 +120,8 @@ pub enum UcciResponse {
         nodes: Option<u64>,
         depth: Option<u32>,
         score: Option<i32>,
+        /// Which of several lines searched at once this is, from 1
+        multipv: Option<u32>,
         pv: Vec<String>,
         currmove: Option<String>,
         message: Option<String>,
@@ -123,3 +160,10 @@ impl UcciCommand {
         crate::ucci::serializer::serialize_command(self)
     }
 }
+
+impl UcciResponse {
+    /// Serialize this response to UCCI protocol string format
+    pub fn serialize(&self) -> String {
+        crate::ucci::serializer::serialize_response(self)
+    }
+}
@@ -0,0 +1,118 @@
+//! An engine's search followed on a thread of its own
+//!
+//! [`EngineSearch::spawn`] takes a [`UcciClient`] that has just been told
+//! to `go` and reads its output on a new thread until the best move
+//! arrives, so the caller never waits on the engine. The engine is stopped
+//! once the deadline passes. When the search is over the client comes back
+//! with the result, through [`EngineSearch::try_finish`] or
+//! [`EngineSearch::stop`].
+
+use crate::ucci::client::{Info, MoveResult, UcciClient};
+use crate::ucci::engine::EngineError;
+use crate::ucci::protocol::EngineProtocol;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+/// How long to wait for output before checking for a stop or the deadline
+const SLICE_MS: u64 = 50;
+
+/// A search running on its own thread
+pub struct EngineSearch {
+    protocol: EngineProtocol,
+    stop: Arc<AtomicBool>,
+    done: Receiver<SearchEnd>,
+}
+
+/// How a search ended, with the engine that ran it
+pub struct SearchEnd {
+    pub client: UcciClient,
+    /// The search's result; an engine that did not answer a stop gives
+    /// [`MoveResult::NoMove`]
+    pub result: Result<MoveResult, EngineError>,
+    /// The last line the engine reported
+    pub info: Option<Info>,
+}
+
+impl EngineSearch {
+    /// Follow `client`'s running search on a new thread, stopping it at
+    /// `deadline` and giving it `grace_ms` to answer the stop
+    ///
+    /// `on_end` is called on that thread once the search is over, with its
+    /// result or None if it failed. The client can be taken back by then.
+    pub fn spawn(
+        mut client: UcciClient,
+        deadline: Instant,
+        grace_ms: u64,
+        on_end: impl FnOnce(Option<MoveResult>) + Send + 'static,
+    ) -> Self {
+        let protocol = client.protocol();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let (sender, done) = mpsc::channel();
+        thread::spawn(move || {
+            let result = follow(&mut client, deadline, grace_ms, &stopped);
+            let outcome = result.as_ref().ok().cloned();
+            let info = client
+                .read_info()
+                .into_iter()
+                .rev()
+                .find(|i| !i.pv.is_empty());
+            let end = SearchEnd {
+                client,
+                result,
+                info,
+            };
+            // Stopped searches are taken back without a word
+            if sender.send(end).is_ok() && !stopped.load(Ordering::Relaxed) {
+                on_end(outcome);
+            }
+        });
+        EngineSearch {
+            protocol,
+            stop,
+            done,
+        }
+    }
+
+    /// The protocol the searching engine speaks
+    pub fn protocol(&self) -> EngineProtocol {
+        self.protocol
+    }
+
+    /// The search's end, if it is over
+    pub fn try_finish(&self) -> Option<SearchEnd> {
+        self.done.try_recv().ok()
+    }
+
+    /// Stop the search and wait for the engine to come back
+    ///
+    /// Returns None if the thread following it died.
+    pub fn stop(self) -> Option<SearchEnd> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.done.recv().ok()
+    }
+}
+
+/// Read the search's output until its best move, stopping it at `deadline`
+/// or when asked to
+fn follow(
+    client: &mut UcciClient,
+    deadline: Instant,
+    grace_ms: u64,
+    stop: &AtomicBool,
+) -> Result<MoveResult, EngineError> {
+    loop {
+        if stop.load(Ordering::Relaxed) || Instant::now() >= deadline {
+            return match client.stop_within(grace_ms) {
+                Err(EngineError::Timeout) => Ok(MoveResult::NoMove),
+                result => result,
+            };
+        }
+        if let Some(result) = client.poll_within(SLICE_MS)? {
+            return Ok(result);
+        }
+    }
+}
@@ -1,6 +1,6 @@
-//! Serialize UCCI commands to protocol format
+//! Serialize UCCI commands and responses to protocol format
 
-use crate::ucci::protocol::{GoMode, UcciCommand};
+use crate::ucci::protocol::{GoMode, OptionType, UcciCommand, UcciResponse};
 
 /// Serialize a UCCI command to a protocol string
 pub fn serialize_command(cmd: &UcciCommand) -> String {
@@ -29,7 +29,7 @@ pub fn serialize_command(cmd: &UcciCommand) -> String {
         }
 
         UcciCommand::Go { mode, ponder, draw } => {
-            let mut parts = Vec::new();
+            let mut parts = vec!["go".to_string()];
             if *ponder {
                 parts.push("ponder".to_string());
             }
@@ -97,3 +97,126 @@ fn serialize_go_mode(mode: &GoMode) -> String {
         }
     }
 }
+
+/// Serialize a UCCI response to a protocol string, as an engine sends it
+pub fn serialize_response(resp: &UcciResponse) -> String {
+    match resp {
+        UcciResponse::Id { field, value } => format!("id {} {}", field, value),
+
+        UcciResponse::Option {
+            name,
+            type_,
+            min,
+            max,
+            vars,
+            default,
+        } => {
+            let type_ = match type_ {
+                OptionType::Check => "check",
+                OptionType::Spin => "spin",
+                OptionType::Combo => "combo",
+                OptionType::Button => "button",
+                OptionType::String => "string",
+                OptionType::Label => "label",
+            };
+            let mut parts = vec![format!("option {} type {}", name, type_)];
+            if let Some(min) = min {
+                parts.push(format!("min {}", min));
+            }
+            if let Some(max) = max {
+                parts.push(format!("max {}", max));
+            }
+            for var in vars {
+                parts.push(format!("var {}", var));
+            }
+            if let Some(default) = default {
+                parts.push(format!("default {}", default));
+            }
+            parts.join(" ")
+        }
+
+        UcciResponse::UcciOk => "ucciok".to_string(),
+
+        UcciResponse::ReadyOk => "readyok".to_string(),
+
+        UcciResponse::BestMove {
+            mv,
+            ponder,
+            draw,
+            resign,
+        } => {
+            let mut parts = vec![format!("bestmove {}", mv)];
+            if let Some(ponder) = ponder {
+                parts.push(format!("ponder {}", ponder));
+            }
+            if *draw {
+                parts.push("draw".to_string());
+            }
+            if *resign {
+                parts.push("resign".to_string());
+            }
+            parts.join(" ")
+        }
+
+        UcciResponse::NoBestMove => "nobestmove".to_string(),
+
+        UcciResponse::Info {
+            time,
+            nodes,
+            depth,
+            score,
+            multipv,
+            pv,
+            currmove,
+            message,
+        } => {
+            let mut parts = vec!["info".to_string()];
+            if let Some(depth) = depth {
+                parts.push(format!("depth {}", depth));
+            }
+            if let Some(multipv) = multipv {
+                parts.push(format!("multipv {}", multipv));
+            }
+            if let Some(score) = score {
+                parts.push(format!("score {}", score));
+            }
+            if let Some(time) = time {
+                parts.push(format!("time {}", time));
+            }
+            if let Some(nodes) = nodes {
+                parts.push(format!("nodes {}", nodes));
+            }
+            if let Some(currmove) = currmove {
+                parts.push(format!("currmove {}", currmove));
+            }
+            // pv and message take the rest of the line, so only one of
+            // them can be sent and it goes last
+            if !pv.is_empty() {
+                parts.push(format!("pv {}", pv.join(" ")));
+            } else if let Some(message) = message {
+                parts.push(format!("message {}", message));
+            }
+            parts.join(" ")
+        }
+
+        UcciResponse::PopHash {
+            bestmove,
+            lowerbound,
+            upperbound,
+        } => {
+            let mut parts = vec!["pophash".to_string()];
+            if let Some(mv) = bestmove {
+                parts.push(format!("bestmove {}", mv));
+            }
+            if let Some((beta, depth)) = lowerbound {
+                parts.push(format!("lowerbound {} {}", beta, depth));
+            }
+            if let Some((alpha, depth)) = upperbound {
+                parts.push(format!("upperbound {} {}", alpha, depth));
+            }
+            parts.join(" ")
+        }
+
+        UcciResponse::Bye => "bye".to_string(),
+    }
+}
@@ -0,0 +1,278 @@
+//! The engine side of UCCI, backed by the built-in AI
+//!
+//! `cn_chess_tui --ucci-serve` reads UCCI commands on standard input and
+//! answers on standard output, so GUIs and match runners can use the
+//! built-in [`Searcher`] like any other engine:
+//!
+//! ```text
+//! > ucci
+//! < id name cn_chess_tui
+//! < option usemillisec type check default false
+//! < ucciok
+//! > position startpos moves h7e7
+//! > go time 60
+//! < info depth 1 score 2 time 0 nodes 45 pv h0g2
+//! < ...
+//! < bestmove h0g2
+//! ```
+//!
+//! Searches run to completion before the next command is read. Time
+//! controls are kept by deepening one ply at a time and not starting a
+//! depth once half the move's budget is spent. `go infinite` and
+//! `go ponder` search to [`INFINITE_DEPTH`] and hold the best move until
+//! `stop` or `ponderhit`.
+
+use crate::ai::{SearchResult, Searcher};
+use crate::game::{Game, GameState, Move};
+use crate::notation::iccs::{iccs_to_move, move_to_iccs};
+use crate::ucci::parser::parse_command;
+use crate::ucci::protocol::{GoMode, OptionType, UcciCommand, UcciResponse};
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+/// Depth searched for `go infinite` and `go ponder`
+pub const INFINITE_DEPTH: u32 = 4;
+
+/// Deepest search any time or node limit can reach
+const MAX_DEPTH: u32 = 64;
+
+/// Moves assumed left when a time control does not say
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+
+/// A UCCI engine playing with the built-in AI
+#[derive(Debug, Clone)]
+pub struct UcciServer {
+    game: Game,
+    banned: Vec<Move>,
+    /// Times are in milliseconds instead of seconds
+    usemillisec: bool,
+    /// Best move held back by `go infinite` or `go ponder`
+    pending: Option<UcciResponse>,
+}
+
+impl Default for UcciServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UcciServer {
+    /// Create a server set up at the initial position
+    pub fn new() -> Self {
+        Self {
+            game: Game::new(),
+            banned: Vec::new(),
+            usemillisec: false,
+            pending: None,
+        }
+    }
+
+    /// The position searched by the next `go`
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Answer one command
+    ///
+    /// Commands that need no answer, and positions or moves that cannot be
+    /// read, give no responses, as UCCI asks engines to ignore what they
+    /// do not understand.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::ucci::server::UcciServer;
+    /// use cn_chess_tui::ucci::{GoMode, UcciCommand, UcciResponse};
+    ///
+    /// let mut server = UcciServer::new();
+    /// let go = UcciCommand::Go { mode: GoMode::Depth(1), ponder: false, draw: false };
+    /// let responses = server.handle(go);
+    /// assert!(matches!(responses.last(), Some(UcciResponse::BestMove { .. })));
+    /// ```
+    pub fn handle(&mut self, cmd: UcciCommand) -> Vec<UcciResponse> {
+        match cmd {
+            UcciCommand::Ucci => vec![
+                UcciResponse::Id {
+                    field: "name".to_string(),
+                    value: "cn_chess_tui".to_string(),
+                },
+                UcciResponse::Option {
+                    name: "usemillisec".to_string(),
+                    type_: OptionType::Check,
+                    min: None,
+                    max: None,
+                    vars: Vec::new(),
+                    default: Some("false".to_string()),
+                },
+                UcciResponse::UcciOk,
+            ],
+            UcciCommand::IsReady => vec![UcciResponse::ReadyOk],
+            UcciCommand::SetOption { name, value } => {
+                if name == "usemillisec" {
+                    self.usemillisec = value.as_deref() == Some("true");
+                }
+                Vec::new()
+            }
+            UcciCommand::Position { fen, moves } => {
+                if let Some(game) = game_at(&fen, &moves) {
+                    self.game = game;
+                    self.banned.clear();
+                }
+                Vec::new()
+            }
+            UcciCommand::BanMoves { moves } => {
+                self.banned = moves
+                    .iter()
+                    .filter_map(|mv| iccs_to_move(mv))
+                    .map(|(from, to)| Move::new(from, to))
+                    .collect();
+                Vec::new()
+            }
+            UcciCommand::Go { mode, ponder, .. } => {
+                let mut responses = self.search(&mode, ponder);
+                if ponder || mode == GoMode::Infinite {
+                    self.pending = responses.pop();
+                }
+                responses
+            }
+            UcciCommand::Stop | UcciCommand::PonderHit { .. } => {
+                self.pending.take().into_iter().collect()
+            }
+            UcciCommand::Probe { .. } => vec![UcciResponse::PopHash {
+                bestmove: None,
+                lowerbound: None,
+                upperbound: None,
+            }],
+            UcciCommand::Quit => vec![UcciResponse::Bye],
+        }
+    }
+
+    /// Search the current position, deepening until `mode`'s limit
+    ///
+    /// Returns an `info` line per depth followed by `bestmove`, or
+    /// `nobestmove` when there is nothing to play.
+    fn search(&mut self, mode: &GoMode, ponder: bool) -> Vec<UcciResponse> {
+        let started = Instant::now();
+        let (max_depth, budget, max_nodes) = match *mode {
+            _ if ponder => (INFINITE_DEPTH, None, None),
+            GoMode::Depth(depth) => (depth.clamp(1, MAX_DEPTH), None, None),
+            GoMode::Infinite => (INFINITE_DEPTH, None, None),
+            GoMode::Nodes(nodes) => (MAX_DEPTH, None, Some(nodes)),
+            GoMode::Time {
+                time,
+                movestogo,
+                increment,
+                ..
+            } => {
+                let moves = movestogo.map_or(DEFAULT_MOVES_TO_GO, u64::from).max(1);
+                let per_move = time / moves + increment.unwrap_or(0);
+                (MAX_DEPTH, Some(self.duration(per_move)), None)
+            }
+        };
+
+        let mut responses = Vec::new();
+        let mut best: Option<SearchResult> = None;
+        let mut nodes = 0;
+        if self.game.state() == GameState::Playing {
+            for depth in 1..=max_depth {
+                let mut searcher = Searcher::new(depth);
+                searcher.ban_moves(self.banned.clone());
+                let Some(result) = searcher.search(self.game.board(), self.game.turn()) else {
+                    break;
+                };
+                nodes += result.nodes;
+                responses.push(UcciResponse::Info {
+                    time: Some(self.elapsed(started)),
+                    nodes: Some(nodes),
+                    depth: Some(depth),
+                    score: Some(result.score),
+                    multipv: None,
+                    pv: vec![move_to_iccs(result.mv.from, result.mv.to)],
+                    currmove: None,
+                    message: None,
+                });
+                best = Some(result);
+
+                // The next depth takes several times as long as this one
+                let out_of_time = budget.is_some_and(|budget| started.elapsed() * 2 > budget);
+                let out_of_nodes = max_nodes.is_some_and(|max| nodes >= max);
+                if out_of_time || out_of_nodes {
+                    break;
+                }
+            }
+        }
+
+        responses.push(match best {
+            Some(result) => UcciResponse::BestMove {
+                mv: move_to_iccs(result.mv.from, result.mv.to),
+                ponder: None,
+                draw: false,
+                resign: false,
+            },
+            None => UcciResponse::NoBestMove,
+        });
+        responses
+    }
+
+    /// A protocol time as a duration
+    fn duration(&self, time: u64) -> Duration {
+        if self.usemillisec {
+            Duration::from_millis(time)
+        } else {
+            Duration::from_secs(time)
+        }
+    }
+
+    /// Time since `started` in the protocol's unit
+    fn elapsed(&self, started: Instant) -> u64 {
+        let elapsed = started.elapsed();
+        if self.usemillisec {
+            elapsed.as_millis() as u64
+        } else {
+            elapsed.as_secs()
+        }
+    }
+}
+
+/// The game reached by playing ICCS `moves` from `fen`
+///
+/// Returns None if the FEN or any move cannot be played.
+fn game_at(fen: &str, moves: &[String]) -> Option<Game> {
+    let mut game = Game::from_fen(fen).ok()?;
+    for mv in moves {
+        let (from, to) = iccs_to_move(mv)?;
+        game.make_move(from, to).ok()?;
+    }
+    Some(game)
+}
+
+/// Serve UCCI on `input` and `output` until `quit` or the end of input
+///
+/// Each response is written on its own line and flushed at once, as GUIs
+/// wait on them. Lines that are not UCCI commands are ignored.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::ucci::server;
+///
+/// let mut output = Vec::new();
+/// server::run("ucci\nisready\nquit\n".as_bytes(), &mut output).unwrap();
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.contains("ucciok\nreadyok\nbye\n"));
+/// ```
+pub fn run<R: BufRead, W: Write>(input: R, output: &mut W) -> io::Result<()> {
+    let mut server = UcciServer::new();
+    for line in input.lines() {
+        let Ok(cmd) = parse_command(&line?) else {
+            continue;
+        };
+        let quit = cmd == UcciCommand::Quit;
+        for response in server.handle(cmd) {
+            writeln!(output, "{}", response.serialize())?;
+            output.flush()?;
+        }
+        if quit {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -38,7 +38,8 @@ impl UcciStateMachine {
         match &self.state {
             EngineState::Boot => matches!(cmd, UcciCommand::Ucci),
             EngineState::Idle => !matches!(cmd, UcciCommand::PonderHit { .. }),
-            EngineState::Thinking => {
+            EngineState::Thinking => matches!(cmd, UcciCommand::Stop),
+            EngineState::Pondering => {
                 matches!(cmd, UcciCommand::Stop | UcciCommand::PonderHit { .. })
             }
         }
@@ -57,9 +58,17 @@ impl UcciStateMachine {
             UcciCommand::Ucci => {
                 // Stay in Boot until ucciok received
             }
+            UcciCommand::Go { ponder: true, .. } => {
+                self.state = EngineState::Pondering;
+            }
             UcciCommand::Go { .. } => {
                 self.state = EngineState::Thinking;
             }
+            UcciCommand::PonderHit { .. } => {
+                // The predicted move was played, the search carries on as
+                // a normal one
+                self.state = EngineState::Thinking;
+            }
             UcciCommand::Quit => {
                 // Will terminate after bye
             }
@@ -83,7 +92,7 @@ impl UcciStateMachine {
                 self.state = EngineState::Idle;
             }
             UcciResponse::BestMove { .. } | UcciResponse::NoBestMove => {
-                if self.state != EngineState::Thinking {
+                if !self.is_thinking() {
                     return Err(StateError::UnexpectedResponse(
                         "bestmove/nobestmove not in thinking state".to_string(),
                     ));
@@ -105,9 +114,14 @@ impl UcciStateMachine {
         self.state == EngineState::Idle
     }
 
-    /// Check if in thinking state
+    /// Check if a search is running, pondering or not
     pub fn is_thinking(&self) -> bool {
-        self.state == EngineState::Thinking
+        matches!(self.state, EngineState::Thinking | EngineState::Pondering)
+    }
+
+    /// Check if in pondering state
+    pub fn is_pondering(&self) -> bool {
+        self.state == EngineState::Pondering
     }
 
     /// Check if in boot state
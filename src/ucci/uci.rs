@@ -0,0 +1,312 @@
+//! UCI protocol support for xiangqi engines
+//!
+//! Engines such as Pikafish and Fairy-Stockfish speak UCI rather than UCCI.
+//! [`UcciClient`](crate::ucci::UcciClient) talks to them through this
+//! module: commands are written in UCI form and replies are read back into
+//! [`UcciResponse`]s, so the rest of the client works the same for both
+//! protocols.
+//!
+//! UCI engines count ranks from Red's side, where this crate's ICCS moves
+//! count from Black's (see [`crate::notation::iccs`]), and Fairy-Stockfish
+//! numbers them from 1. [`UciSquares`] converts moves both ways.
+
+use crate::notation::iccs::{iccs_to_move, move_to_iccs};
+use crate::types::Position;
+use crate::ucci::parser::{self, ParseError};
+use crate::ucci::protocol::{GoMode, OptionType, UcciCommand, UcciResponse};
+use std::str::FromStr;
+
+/// Score reported for a mate in one, as the built-in search does
+const MATE_SCORE: i32 = 30_000;
+
+/// Square names used by a UCI engine
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::ucci::uci::UciSquares;
+///
+/// // Red's central cannon
+/// assert_eq!(UciSquares::ZERO_BASED.move_to_uci("h7e7"), "h2e2");
+/// assert_eq!(UciSquares::ONE_BASED.move_to_uci("h7e7"), "h3e3");
+/// assert_eq!(UciSquares::ONE_BASED.move_from_uci("a10a9").unwrap(), "a0a1");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UciSquares {
+    /// Number of Red's back rank
+    pub first_rank: usize,
+}
+
+impl UciSquares {
+    /// Ranks 0-9, as Pikafish names them
+    pub const ZERO_BASED: UciSquares = UciSquares { first_rank: 0 };
+    /// Ranks 1-10, as Fairy-Stockfish names them
+    pub const ONE_BASED: UciSquares = UciSquares { first_rank: 1 };
+
+    fn name(self, pos: Position) -> String {
+        let file = (b'a' + pos.x as u8) as char;
+        format!("{}{}", file, 9 - pos.y + self.first_rank)
+    }
+
+    fn parse(self, square: &str) -> Option<Position> {
+        let mut chars = square.chars();
+        let file = chars.next()?;
+        if !('a'..='i').contains(&file) {
+            return None;
+        }
+        let rank = chars
+            .as_str()
+            .parse::<usize>()
+            .ok()?
+            .checked_sub(self.first_rank)?;
+        if rank > 9 {
+            return None;
+        }
+        Some(Position::from_xy((file as u8 - b'a') as usize, 9 - rank))
+    }
+
+    /// Convert an ICCS move to the engine's square names
+    ///
+    /// Text that is not an ICCS move is returned unchanged.
+    pub fn move_to_uci(self, iccs: &str) -> String {
+        match iccs_to_move(iccs) {
+            Some((from, to)) => format!("{}{}", self.name(from), self.name(to)),
+            None => iccs.to_string(),
+        }
+    }
+
+    /// Convert a move from the engine to ICCS
+    pub fn move_from_uci(self, uci: &str) -> Option<String> {
+        // Each square is a file letter and one or two digits
+        let (split, _) = uci
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| c.is_ascii_alphabetic())?;
+        let from = self.parse(&uci[..split])?;
+        let to = self.parse(&uci[split..])?;
+        Some(move_to_iccs(from, to))
+    }
+
+    fn moves_to_uci(self, moves: &[String]) -> Vec<String> {
+        moves.iter().map(|mv| self.move_to_uci(mv)).collect()
+    }
+}
+
+impl Default for UciSquares {
+    fn default() -> Self {
+        Self::ZERO_BASED
+    }
+}
+
+/// Write a command in UCI form
+///
+/// Returns None for commands UCI has no equivalent for (`banmoves` and
+/// `probe`), which are not sent.
+pub fn serialize_command(cmd: &UcciCommand, squares: UciSquares) -> Option<String> {
+    let text = match cmd {
+        UcciCommand::Ucci => "uci".to_string(),
+        UcciCommand::SetOption { name, value } => match value {
+            Some(v) => format!("setoption name {} value {}", name, v),
+            None => format!("setoption name {}", name),
+        },
+        UcciCommand::Position { fen, moves } => {
+            if moves.is_empty() {
+                format!("position fen {}", fen)
+            } else {
+                format!(
+                    "position fen {} moves {}",
+                    fen,
+                    squares.moves_to_uci(moves).join(" ")
+                )
+            }
+        }
+        UcciCommand::BanMoves { .. } | UcciCommand::Probe { .. } => return None,
+        UcciCommand::Go { mode, ponder, .. } => {
+            let mode = match mode {
+                GoMode::Depth(d) => format!("depth {}", d),
+                GoMode::Infinite => "infinite".to_string(),
+                GoMode::Nodes(n) => format!("nodes {}", n),
+                GoMode::Time { time, .. } => format!("movetime {}", time),
+            };
+            if *ponder {
+                format!("go ponder {}", mode)
+            } else {
+                format!("go {}", mode)
+            }
+        }
+        UcciCommand::Stop => "stop".to_string(),
+        UcciCommand::PonderHit { .. } => "ponderhit".to_string(),
+        UcciCommand::IsReady => "isready".to_string(),
+        UcciCommand::Quit => "quit".to_string(),
+    };
+    Some(text)
+}
+
+/// Parse a line of UCI engine output
+///
+/// Moves are converted to ICCS, `uciok` reads as
+/// [`UcciResponse::UcciOk`] and `bestmove (none)` as
+/// [`UcciResponse::NoBestMove`].
+pub fn parse_response(line: &str, squares: UciSquares) -> Result<UcciResponse, ParseError> {
+    let line = line.trim();
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    match parts.first() {
+        None => Err(ParseError::InvalidFormat("Empty line".to_string())),
+        Some(&"id") => parser::parse_response(line),
+        Some(&"option") => parse_option(&parts),
+        Some(&"uciok") => Ok(UcciResponse::UcciOk),
+        Some(&"readyok") => Ok(UcciResponse::ReadyOk),
+        Some(&"bestmove") => parse_bestmove(&parts, squares),
+        Some(&"info") => Ok(parse_info(&parts, squares)),
+        Some(other) => Err(ParseError::UnknownCommand(other.to_string())),
+    }
+}
+
+/// Parse `option name <name> type <type> [default <value>] [min <n>]
+/// [max <n>] [var <value>]...`, where names and values may contain spaces
+fn parse_option(parts: &[&str]) -> Result<UcciResponse, ParseError> {
+    const KEYWORDS: [&str; 6] = ["name", "type", "default", "min", "max", "var"];
+
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    for &word in &parts[1..] {
+        if KEYWORDS.contains(&word) {
+            fields.push((word, String::new()));
+            continue;
+        }
+        let Some((_, value)) = fields.last_mut() else {
+            return Err(ParseError::InvalidFormat(
+                "Invalid option format".to_string(),
+            ));
+        };
+        if !value.is_empty() {
+            value.push(' ');
+        }
+        value.push_str(word);
+    }
+    let field = |key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.clone())
+    };
+
+    let name = field("name")
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| ParseError::MissingRequiredField("option name".to_string()))?;
+    let type_ = match field("type").as_deref() {
+        Some("check") => OptionType::Check,
+        Some("spin") => OptionType::Spin,
+        Some("combo") => OptionType::Combo,
+        Some("button") => OptionType::Button,
+        Some("string") => OptionType::String,
+        other => {
+            return Err(ParseError::InvalidParameter(format!(
+                "Unknown option type: {:?}",
+                other
+            )))
+        }
+    };
+
+    Ok(UcciResponse::Option {
+        name,
+        type_,
+        min: field("min").and_then(|v| v.parse().ok()),
+        max: field("max").and_then(|v| v.parse().ok()),
+        vars: fields
+            .iter()
+            .filter(|(k, _)| *k == "var")
+            .map(|(_, v)| v.clone())
+            .collect(),
+        default: field("default").filter(|v| v != "<empty>"),
+    })
+}
+
+fn parse_bestmove(parts: &[&str], squares: UciSquares) -> Result<UcciResponse, ParseError> {
+    let mv = parts
+        .get(1)
+        .ok_or_else(|| ParseError::MissingRequiredField("move".to_string()))?;
+    if *mv == "(none)" {
+        return Ok(UcciResponse::NoBestMove);
+    }
+    let mv = squares
+        .move_from_uci(mv)
+        .ok_or_else(|| ParseError::InvalidParameter(format!("Invalid move: {}", mv)))?;
+    let ponder = match parts.get(2..4) {
+        Some(["ponder", ponder]) => squares.move_from_uci(ponder),
+        _ => None,
+    };
+    Ok(UcciResponse::BestMove {
+        mv,
+        ponder,
+        draw: false,
+        resign: false,
+    })
+}
+
+fn number<T: FromStr>(parts: &[&str], i: usize) -> Option<T> {
+    parts.get(i)?.parse().ok()
+}
+
+fn parse_info(parts: &[&str], squares: UciSquares) -> UcciResponse {
+    let mut time = None;
+    let mut nodes = None;
+    let mut depth = None;
+    let mut score = None;
+    let mut multipv = None;
+    let mut pv = Vec::new();
+    let mut currmove = None;
+    let mut message = None;
+
+    let mut i = 1;
+    while i < parts.len() {
+        match parts[i] {
+            "time" => time = number(parts, i + 1),
+            "nodes" => nodes = number(parts, i + 1),
+            "multipv" => multipv = number(parts, i + 1),
+            "depth" => depth = number(parts, i + 1),
+            "score" => {
+                score = match parts.get(i + 1) {
+                    Some(&"cp") => number(parts, i + 2),
+                    Some(&"mate") => number(parts, i + 2).map(|n: i32| {
+                        if n > 0 {
+                            MATE_SCORE - n
+                        } else {
+                            -MATE_SCORE - n
+                        }
+                    }),
+                    _ => None,
+                };
+                i += 1;
+            }
+            "currmove" => currmove = parts.get(i + 1).and_then(|mv| squares.move_from_uci(mv)),
+            "pv" => {
+                pv = parts[i + 1..]
+                    .iter()
+                    .map_while(|mv| squares.move_from_uci(mv))
+                    .collect();
+                break;
+            }
+            "string" => {
+                message = Some(parts[i + 1..].join(" "));
+                break;
+            }
+            // Other fields (seldepth, nps, hashfull, wdl, ...) are
+            // skipped a word at a time
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+    }
+
+    UcciResponse::Info {
+        time,
+        nodes,
+        depth,
+        score,
+        multipv,
+        pv,
+        currmove,
+        message,
+    }
+}
@@ -1,39 +1,408 @@
-use crate::game::{AiMode, Game, GameState};
-use crate::types::{move_to_simple_notation, Color, Position};
+use crate::ai::material;
+use crate::board::Board;
+use crate::config::KeyBindings;
+use crate::editor::BoardEditor;
+use crate::fen::piece_to_fen;
+use crate::fen_print::board_thumbnail;
+use crate::game::{AiMode, Difficulty, Game, GameResult, GameState, MoveStats, TimeControl};
+use crate::handicap::{Handicap, HANDICAPS};
+use crate::i18n::{Lang, Text};
+use crate::library::LibraryEntry;
+use crate::notation::format::MoveStyle;
+use crate::opening::OpeningBook;
+use crate::pgn::PgnGame;
+use crate::pgn_import::{import_pgn, ImportMode};
+use crate::state::{FilePreview, RecentFile};
+use crate::stats::{Outcome, Stats};
+use crate::tutor;
+use crate::types::{move_to_simple_notation, Color, Piece, PieceType, Position};
+use crate::ucci::{Info, PopHashInfo, PvLine};
+use crate::widget::{BoardState, BoardWidget, BOARD_ROWS};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color as RColor, Modifier, Style},
+    symbols,
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{Axis, Block, Borders, Chart, Clear, Dataset, GraphType, Paragraph, Wrap},
     Frame,
 };
+use serde::Deserialize;
+use std::cell::Cell;
 use std::path::PathBuf;
+use unicode_width::UnicodeWidthStr;
+
+/// Colors used to draw the board, panels and popups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Name used in `config.toml` and `--theme`
+    pub name: &'static str,
+    pub primary: RColor,
+    pub secondary: RColor,
+    pub accent: RColor,
+    pub gold: RColor,
+    pub grid: RColor,
+    pub river: RColor,
+    pub red_piece: RColor,
+    pub black_piece: RColor,
+    pub cursor: RColor,
+    pub selection: RColor,
+    pub selection_bg: RColor,
+    /// Background of the squares the last move left and reached
+    pub last_move_bg: RColor,
+    /// Backgrounds of the attack map: points only Red attacks, only Black
+    /// attacks, and both
+    pub red_attack_bg: RColor,
+    pub black_attack_bg: RColor,
+    pub contested_bg: RColor,
+    pub check: RColor,
+    /// Background of popups and menus
+    pub popup_bg: RColor,
+}
+
+impl Theme {
+    /// Traditional Chinese inspired colors for dark terminals
+    pub const CLASSIC: Theme = Theme {
+        name: "classic",
+        primary: RColor::Cyan,
+        secondary: RColor::LightBlue,
+        accent: RColor::LightCyan,
+        gold: RColor::Yellow,
+        grid: RColor::DarkGray,
+        river: RColor::LightYellow,
+        red_piece: RColor::Red,
+        black_piece: RColor::Gray,
+        cursor: RColor::Green,
+        selection: RColor::Yellow,
+        selection_bg: RColor::DarkGray,
+        last_move_bg: RColor::Blue,
+        red_attack_bg: RColor::Indexed(52),
+        black_attack_bg: RColor::Indexed(238),
+        contested_bg: RColor::Indexed(54),
+        check: RColor::LightRed,
+        popup_bg: RColor::Black,
+    };
+
+    /// Darker colors for terminals with a light background
+    pub const LIGHT: Theme = Theme {
+        name: "light",
+        primary: RColor::Blue,
+        secondary: RColor::DarkGray,
+        accent: RColor::Blue,
+        gold: RColor::Magenta,
+        grid: RColor::Gray,
+        river: RColor::Blue,
+        red_piece: RColor::Red,
+        black_piece: RColor::Black,
+        cursor: RColor::Green,
+        selection: RColor::Magenta,
+        selection_bg: RColor::Gray,
+        last_move_bg: RColor::LightCyan,
+        red_attack_bg: RColor::Indexed(224),
+        black_attack_bg: RColor::Indexed(252),
+        contested_bg: RColor::Indexed(189),
+        check: RColor::Red,
+        popup_bg: RColor::White,
+    };
+
+    /// Shades of gray only, for terminals without color
+    pub const MONO: Theme = Theme {
+        name: "mono",
+        primary: RColor::White,
+        secondary: RColor::Gray,
+        accent: RColor::White,
+        gold: RColor::White,
+        grid: RColor::DarkGray,
+        river: RColor::Gray,
+        red_piece: RColor::White,
+        black_piece: RColor::DarkGray,
+        cursor: RColor::White,
+        selection: RColor::White,
+        selection_bg: RColor::DarkGray,
+        last_move_bg: RColor::Gray,
+        red_attack_bg: RColor::Indexed(239),
+        black_attack_bg: RColor::Indexed(235),
+        contested_bg: RColor::Indexed(243),
+        check: RColor::White,
+        popup_bg: RColor::Reset,
+    };
+
+    /// Bright colors on black, with a blue selection background
+    pub const HIGH_CONTRAST: Theme = Theme {
+        name: "high-contrast",
+        primary: RColor::White,
+        secondary: RColor::White,
+        accent: RColor::LightYellow,
+        gold: RColor::LightYellow,
+        grid: RColor::White,
+        river: RColor::LightCyan,
+        red_piece: RColor::LightRed,
+        black_piece: RColor::White,
+        cursor: RColor::LightGreen,
+        selection: RColor::LightYellow,
+        selection_bg: RColor::Blue,
+        last_move_bg: RColor::Magenta,
+        red_attack_bg: RColor::Indexed(88),
+        black_attack_bg: RColor::Indexed(240),
+        contested_bg: RColor::Indexed(55),
+        check: RColor::LightMagenta,
+        popup_bg: RColor::Black,
+    };
+
+    /// Okabe-Ito colors that stay distinct with red-green color blindness:
+    /// red pieces are orange and the cursor is blue instead of green
+    pub const COLORBLIND: Theme = Theme {
+        name: "colorblind",
+        primary: RColor::Rgb(86, 180, 233),
+        secondary: RColor::Rgb(0, 114, 178),
+        accent: RColor::Rgb(86, 180, 233),
+        gold: RColor::Rgb(240, 228, 66),
+        grid: RColor::DarkGray,
+        river: RColor::Rgb(240, 228, 66),
+        red_piece: RColor::Rgb(230, 159, 0),
+        black_piece: RColor::Gray,
+        cursor: RColor::Rgb(86, 180, 233),
+        selection: RColor::Rgb(240, 228, 66),
+        selection_bg: RColor::DarkGray,
+        last_move_bg: RColor::Rgb(0, 158, 115),
+        red_attack_bg: RColor::Rgb(115, 80, 0),
+        black_attack_bg: RColor::Rgb(60, 60, 60),
+        contested_bg: RColor::Rgb(0, 70, 110),
+        check: RColor::Rgb(204, 121, 167),
+        popup_bg: RColor::Black,
+    };
+
+    /// Every built-in theme, in the order the theme key cycles through them
+    pub const ALL: [Theme; 5] = [
+        Self::CLASSIC,
+        Self::HIGH_CONTRAST,
+        Self::LIGHT,
+        Self::COLORBLIND,
+        Self::MONO,
+    ];
+
+    /// Look up a built-in theme by name
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::ui::Theme;
+    ///
+    /// assert_eq!(Theme::by_name("light"), Some(Theme::LIGHT));
+    /// assert_eq!(Theme::by_name("dark"), Some(Theme::CLASSIC));
+    /// assert_eq!(Theme::by_name("High-Contrast"), Some(Theme::HIGH_CONTRAST));
+    /// assert_eq!(Theme::by_name("neon"), None);
+    /// ```
+    pub fn by_name(name: &str) -> Option<Theme> {
+        let name = name.to_ascii_lowercase();
+        if name == "default" || name == "dark" {
+            return Some(Self::CLASSIC);
+        }
+        Self::ALL.into_iter().find(|theme| theme.name == name)
+    }
+
+    /// The next built-in theme after this one, wrapping around
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::ui::Theme;
+    ///
+    /// assert_eq!(Theme::CLASSIC.next(), Theme::HIGH_CONTRAST);
+    /// assert_eq!(Theme::MONO.next(), Theme::CLASSIC);
+    /// ```
+    pub fn next(&self) -> Theme {
+        let index = Self::ALL
+            .iter()
+            .position(|theme| theme.name == self.name)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+/// Which side's attacks the attack map shades
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AttackOverlay {
+    #[default]
+    Off,
+    Red,
+    Black,
+    /// Both sides, with points both attack in their own shade
+    Both,
+}
+
+impl AttackOverlay {
+    /// The next setting, cycling off, Red, Black, both
+    pub fn next(self) -> Self {
+        match self {
+            AttackOverlay::Off => AttackOverlay::Red,
+            AttackOverlay::Red => AttackOverlay::Black,
+            AttackOverlay::Black => AttackOverlay::Both,
+            AttackOverlay::Both => AttackOverlay::Off,
+        }
+    }
+
+    /// Name shown to the user
+    pub fn name(self) -> &'static str {
+        match self {
+            AttackOverlay::Off => "off",
+            AttackOverlay::Red => "红 Red",
+            AttackOverlay::Black => "黑 Black",
+            AttackOverlay::Both => "both sides",
+        }
+    }
+
+    pub(crate) fn shows(self, color: Color) -> bool {
+        match self {
+            AttackOverlay::Off => false,
+            AttackOverlay::Red => color == Color::Red,
+            AttackOverlay::Black => color == Color::Black,
+            AttackOverlay::Both => true,
+        }
+    }
+}
+
+/// How pieces are written on the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PieceStyle {
+    /// Chinese characters, e.g. `帅` and `将`
+    #[default]
+    Chinese,
+    /// FEN letters, uppercase for Red and lowercase for Black, e.g. `K` and
+    /// `k`, for terminals without CJK fonts
+    Letters,
+}
+
+impl PieceStyle {
+    /// The other style
+    pub fn next(self) -> Self {
+        match self {
+            PieceStyle::Chinese => PieceStyle::Letters,
+            PieceStyle::Letters => PieceStyle::Chinese,
+        }
+    }
+
+    /// Name shown to the user
+    pub fn name(self) -> &'static str {
+        match self {
+            PieceStyle::Chinese => "中文 Chinese",
+            PieceStyle::Letters => "Letters",
+        }
+    }
+
+    /// Text for a piece in this style
+    pub fn text(self, piece: Piece) -> String {
+        match self {
+            PieceStyle::Chinese => piece.to_string(),
+            PieceStyle::Letters => piece_to_fen(piece).to_string(),
+        }
+    }
+
+    /// Terminal columns a piece takes in this style: two for the
+    /// double-width Chinese characters, one for letters
+    pub fn width(self) -> u16 {
+        self.text(Piece::red(PieceType::General)).width() as u16
+    }
+
+    /// Guess the style the terminal can show from the environment
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+        let locale = var("LC_ALL")
+            .or_else(|| var("LC_CTYPE"))
+            .or_else(|| var("LANG"));
+        Self::detect_from(var("TERM").as_deref(), locale.as_deref())
+    }
+
+    /// Guess the style from `TERM` and the locale
+    ///
+    /// The Linux console has no CJK font, and a locale that is not UTF-8
+    /// cannot show Chinese characters either.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::ui::PieceStyle;
+    ///
+    /// assert_eq!(PieceStyle::detect_from(Some("xterm-256color"), Some("en_US.UTF-8")), PieceStyle::Chinese);
+    /// assert_eq!(PieceStyle::detect_from(Some("linux"), Some("en_US.UTF-8")), PieceStyle::Letters);
+    /// assert_eq!(PieceStyle::detect_from(Some("xterm"), Some("C")), PieceStyle::Letters);
+    /// assert_eq!(PieceStyle::detect_from(None, None), PieceStyle::Chinese);
+    /// ```
+    pub fn detect_from(term: Option<&str>, locale: Option<&str>) -> Self {
+        let console = matches!(term, Some("linux" | "vt100" | "vt220" | "dumb"));
+        let utf8 = locale.is_none_or(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        });
+        if console || !utf8 {
+            PieceStyle::Letters
+        } else {
+            PieceStyle::Chinese
+        }
+    }
+}
+
+thread_local! {
+    static THEME: Cell<Theme> = const { Cell::new(Theme::CLASSIC) };
+    static KEYS: Cell<KeyBindings> = const { Cell::new(KeyBindings::DEFAULT) };
+    static PIECE_STYLE: Cell<PieceStyle> = const { Cell::new(PieceStyle::Chinese) };
+    static BLINDFOLD: Cell<bool> = const { Cell::new(false) };
+    static COORDINATES: Cell<bool> = const { Cell::new(false) };
+    static FLIPPED: Cell<bool> = const { Cell::new(false) };
+    static TUTOR: Cell<bool> = const { Cell::new(false) };
+    static ATTACKS: Cell<AttackOverlay> = const { Cell::new(AttackOverlay::Off) };
+    static ACCESSIBLE: Cell<bool> = const { Cell::new(false) };
+    static LANG: Cell<Lang> = const { Cell::new(Lang::Bilingual) };
+    /// Where the board was last drawn, for mouse clicks
+    static BOARD: Cell<BoardState> = Cell::new(BoardState::default());
+    /// Where the setup palette was last drawn; empty when it wasn't
+    static PALETTE: Cell<Rect> = const { Cell::new(Rect::ZERO) };
+}
 
-// Base board dimensions (9x10 grid)
-const BOARD_COLS: usize = 9;
-const BOARD_ROWS: usize = 10;
+/// The theme set with [`UI::set_theme`]
+fn theme() -> Theme {
+    THEME.with(Cell::get)
+}
 
-// Color scheme - Traditional Chinese inspired
-const C_PRIMARY: RColor = RColor::Cyan;
-const C_SECONDARY: RColor = RColor::LightBlue;
-const C_ACCENT: RColor = RColor::LightCyan;
-const C_GOLD: RColor = RColor::Yellow;
-const C_GRID: RColor = RColor::DarkGray;
-const C_RIVER: RColor = RColor::LightYellow;
+/// The key bindings set with [`UI::set_key_bindings`]
+fn keys() -> KeyBindings {
+    KEYS.with(Cell::get)
+}
 
-// Piece colors
-const C_RED_PIECE: RColor = RColor::Red;
-const C_BLACK_PIECE: RColor = RColor::Gray;
+/// `text` in the language set with [`UI::set_lang`]
+fn tr(text: Text) -> &'static str {
+    LANG.with(Cell::get).text(text)
+}
 
-// Highlight colors
-const C_CURSOR: RColor = RColor::Green;
-const C_SELECTION: RColor = RColor::Yellow;
-const C_SELECTION_BG: RColor = RColor::DarkGray;
-const C_CHECK: RColor = RColor::LightRed;
+/// `text` in the language set with [`UI::set_lang`], with its values
+fn trf(text: Text, args: &[&dyn std::fmt::Display]) -> String {
+    LANG.with(Cell::get).format(text, args)
+}
 
 // Border styles
 const BORDER_ALL: Borders = Borders::ALL;
 
+/// Smallest terminal the game screen is drawn on: the one-row board in its
+/// frame, and the status bar under it
+pub const MIN_TERMINAL_WIDTH: u16 = 22;
+pub const MIN_TERMINAL_HEIGHT: u16 = 13;
+
+/// Terminals shorter than this draw each rank on a single row
+const SHORT_TERMINAL_HEIGHT: u16 = 24;
+
+/// Rows taken by the analysis panel in the full layout sidebar
+const ANALYSIS_PANEL_HEIGHT: u16 = 7;
+
+/// Rows taken by the evaluation graph in the full layout sidebar, when the
+/// history still gets its minimum height
+const EVAL_GRAPH_HEIGHT: u16 = 7;
+
+/// Evaluations beyond this many centipawns are drawn at the graph's edge
+const EVAL_GRAPH_LIMIT: i32 = 1000;
+
 /// Layout zone types for the new UI
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LayoutZone {
@@ -52,8 +421,16 @@ pub struct LayoutConfig {
     pub title_height: u16,
     pub help_height: u16,
     pub cell_width: u16,
+    /// Rows per rank: 2, or 1 on terminals too short for that
     pub cell_height: u16,
+    /// How pieces are written on this board: the configured style, or
+    /// single-column letters when cells are one row high
+    pub piece_style: PieceStyle,
     pub show_river_text: bool,
+    /// Whether ICCS file and rank labels are drawn around the board
+    pub show_coordinates: bool,
+    /// Whether the board is turned around, with Red at the top
+    pub flipped: bool,
     pub popup_width: u16,
     pub popup_height: u16,
 }
@@ -72,8 +449,17 @@ impl LayoutConfig {
             LayoutZone::Full
         };
 
-        let title_height = 3;
-        let help_height = 3;
+        // Below 24 rows two-row cells no longer fit: ranks take one row
+        // each and the river goes, and the title and help bars go too
+        // once even that board doesn't fit between them
+        let short = height < SHORT_TERMINAL_HEIGHT;
+        let bars = if short && height < 6 + (BOARD_ROWS as u16) + 2 {
+            0
+        } else {
+            3
+        };
+        let title_height = bars;
+        let help_height = bars;
 
         // Cell sizing based on terminal width
         let cell_width = if width >= 100 {
@@ -83,12 +469,21 @@ impl LayoutConfig {
         } else {
             2
         };
-        let cell_height = 2;
+        let cell_height = if short { 1 } else { 2 };
+        // Chinese characters fill a two-column cell edge to edge; letters
+        // leave the lines between pieces readable
+        let piece_style = if short {
+            PieceStyle::Letters
+        } else {
+            UI::piece_style()
+        };
 
-        let show_river_text = width >= 60;
+        let show_river_text = width >= 60 && !short;
+        let show_coordinates = UI::coordinates();
+        let flipped = UI::flipped();
 
-        let popup_width = (width * 50 / 100).clamp(30, 50);
-        let popup_height = (height * 40 / 100).clamp(10, 15);
+        let popup_width = (width * 50 / 100).clamp(30, 50).min(width);
+        let popup_height = (height * 40 / 100).clamp(10, 15).min(height);
 
         LayoutConfig {
             layout_zone,
@@ -96,17 +491,14 @@ impl LayoutConfig {
             help_height,
             cell_width,
             cell_height,
+            piece_style,
             show_river_text,
+            show_coordinates,
+            flipped,
             popup_width,
             popup_height,
         }
     }
-
-    fn cell_pos(&self, x: usize, y: usize) -> (u16, u16) {
-        let px = (x as u16) * self.cell_width + (self.cell_width / 2);
-        let py = (y as u16) * self.cell_height;
-        (px, py)
-    }
 }
 
 /// AI menu selection state
@@ -114,279 +506,1566 @@ impl LayoutConfig {
 pub struct AiMenuState {
     pub selected: usize,
     pub show_thinking: bool,
+    /// None while the config's own search settings are used
+    pub difficulty: Option<Difficulty>,
 }
 
-pub struct UI;
+/// The side the player takes in a new game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayerSide {
+    #[default]
+    Red,
+    Black,
+    /// Both sides at one keyboard; the AI does not play
+    Both,
+}
 
-impl UI {
-    pub fn draw(f: &mut Frame, game: &Game, cursor: Position, selection: Option<Position>) {
-        let size = f.area();
-        let config = LayoutConfig::from_terminal_size(size);
+impl PlayerSide {
+    pub const ALL: [PlayerSide; 3] = [PlayerSide::Red, PlayerSide::Black, PlayerSide::Both];
 
-        // Main vertical layout: title + content + help
-        let main_chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(config.title_height),
-                Constraint::Min(0),
-                Constraint::Length(config.help_height),
-            ])
-            .split(size);
+    fn name(self) -> &'static str {
+        match self {
+            PlayerSide::Red => tr(Text::PlayRed),
+            PlayerSide::Black => tr(Text::PlayBlack),
+            PlayerSide::Both => tr(Text::PlayBoth),
+        }
+    }
+}
 
-        // Draw title bar
-        Self::draw_title_bar(f, main_chunks[0], game, &config);
+/// New-game dialog state: a form of side, AI, difficulty, time control
+/// and handicap
+///
+/// `↑`/`↓` pick a field and `←`/`→` change it; see
+/// [`NewGameMenuState::change`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NewGameMenuState {
+    /// The field being changed, from 0 (side) to `FIELDS - 1` (handicap)
+    pub selected: usize,
+    pub side: PlayerSide,
+    /// Whether the AI plays the side the player does not
+    pub ai: bool,
+    /// None while the config's own search settings are used
+    pub difficulty: Option<Difficulty>,
+    /// None for a game without clocks
+    pub time_control: Option<TimeControl>,
+    /// None for the standard start
+    pub handicap: Option<&'static Handicap>,
+}
 
-        // Draw content area based on layout type
-        match config.layout_zone {
-            LayoutZone::Compact => {
-                Self::draw_compact_layout(f, main_chunks[1], game, cursor, selection, &config);
+/// The option after (or before) `current` in `options`, wrapping around
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, forward: bool) -> T {
+    let i = options.iter().position(|&o| o == current).unwrap_or(0);
+    let n = options.len();
+    options[if forward {
+        (i + 1) % n
+    } else {
+        (i + n - 1) % n
+    }]
+}
+
+impl NewGameMenuState {
+    /// Number of fields in the form
+    pub const FIELDS: usize = 5;
+
+    /// A form starting from the current game's settings
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::game::AiMode;
+    /// use cn_chess_tui::ui::{NewGameMenuState, PlayerSide};
+    ///
+    /// let menu = NewGameMenuState::new(AiMode::PlaysRed, None, None);
+    /// assert_eq!((menu.side, menu.ai), (PlayerSide::Black, true));
+    /// assert_eq!(menu.ai_mode(), AiMode::PlaysRed);
+    /// ```
+    pub fn new(
+        ai_mode: AiMode,
+        difficulty: Option<Difficulty>,
+        time_control: Option<TimeControl>,
+    ) -> Self {
+        let side = match ai_mode {
+            AiMode::PlaysRed => PlayerSide::Black,
+            _ => PlayerSide::Red,
+        };
+        Self {
+            side,
+            ai: ai_mode != AiMode::Off,
+            difficulty,
+            time_control,
+            ..Self::default()
+        }
+    }
+
+    /// Step the selected field to its next (or previous) value
+    pub fn change(&mut self, forward: bool) {
+        match self.selected {
+            0 => self.side = cycle(&PlayerSide::ALL, self.side, forward),
+            1 => self.ai = !self.ai,
+            2 => {
+                let levels = [None].into_iter().chain(Difficulty::ALL.map(Some));
+                self.difficulty = cycle(&levels.collect::<Vec<_>>(), self.difficulty, forward);
             }
-            LayoutZone::Standard => {
-                Self::draw_standard_layout(f, main_chunks[1], game, cursor, selection, &config);
+            3 => {
+                let controls = [None].into_iter().chain(TimeControl::PRESETS.map(Some));
+                self.time_control =
+                    cycle(&controls.collect::<Vec<_>>(), self.time_control, forward);
             }
-            LayoutZone::Full => {
-                Self::draw_full_layout(f, main_chunks[1], game, cursor, selection, &config);
+            _ => {
+                let handicaps = [None].into_iter().chain(HANDICAPS.iter().map(Some));
+                self.handicap = cycle(&handicaps.collect::<Vec<_>>(), self.handicap, forward);
             }
         }
+    }
 
-        // Draw help bar
-        Self::draw_help_bar(f, main_chunks[2], &config);
-
-        // Draw game over popup if needed
-        if game.state() != GameState::Playing {
-            Self::draw_game_over_popup(f, size, game.state(), &config);
+    /// Who the AI plays in the game the form describes
+    pub fn ai_mode(&self) -> AiMode {
+        match (self.side, self.ai) {
+            (PlayerSide::Red, true) => AiMode::PlaysBlack,
+            (PlayerSide::Black, true) => AiMode::PlaysRed,
+            _ => AiMode::Off,
         }
     }
 
-    /// Compact layout: board with minimal surrounding info
-    fn draw_compact_layout(
-        f: &mut Frame,
-        area: Rect,
-        game: &Game,
-        cursor: Position,
-        selected: Option<Position>,
-        config: &LayoutConfig,
-    ) {
-        // Split into board + small info panel
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Min(40), Constraint::Length(20)])
-            .split(area);
+    /// The selected handicap, or None for the standard start
+    pub fn selected_handicap(&self) -> Option<&'static Handicap> {
+        self.handicap
+    }
 
-        Self::draw_board(f, chunks[0], game, cursor, selected, config);
-        Self::draw_mini_info(f, chunks[1], game, config);
+    /// Each field's label and shown value
+    fn fields(&self) -> [(&'static str, String); Self::FIELDS] {
+        let ai_plays = self.side != PlayerSide::Both;
+        [
+            (tr(Text::PlayField), self.side.name().to_string()),
+            (
+                tr(Text::AiField),
+                match (ai_plays, self.ai) {
+                    (true, true) => tr(Text::On),
+                    _ => tr(Text::Off),
+                }
+                .to_string(),
+            ),
+            (
+                tr(Text::DifficultyLabel),
+                UI::difficulty_name(self.difficulty).to_string(),
+            ),
+            (
+                tr(Text::ClockField),
+                self.time_control
+                    .map_or(tr(Text::NoClock).to_string(), |tc| {
+                        trf(Text::Minutes, &[&tc])
+                    }),
+            ),
+            (
+                tr(Text::StartField),
+                self.handicap
+                    .map_or(tr(Text::Standard).to_string(), UI::handicap_name),
+            ),
+        ]
     }
+}
 
-    /// Standard layout: board + move history
-    fn draw_standard_layout(
-        f: &mut Frame,
-        area: Rect,
-        game: &Game,
-        cursor: Position,
-        selected: Option<Position>,
-        config: &LayoutConfig,
-    ) {
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Min(50), Constraint::Length(28)])
-            .split(area);
+/// Quick-open menu state: recent files with their previews
+#[derive(Debug, Clone, Default)]
+pub struct OpenMenuState {
+    pub selected: usize,
+    pub entries: Vec<(RecentFile, FilePreview)>,
+}
 
-        Self::draw_board(f, chunks[0], game, cursor, selected, config);
-        Self::draw_move_history(f, chunks[1], game, config);
+impl OpenMenuState {
+    /// Build menu entries, reading a preview of each file
+    pub fn new(files: Vec<RecentFile>) -> Self {
+        let entries = files
+            .into_iter()
+            .map(|file| {
+                let preview = FilePreview::load(&file);
+                (file, preview)
+            })
+            .collect();
+        Self {
+            selected: 0,
+            entries,
+        }
     }
 
-    /// Full layout: board + history + info panel
-    fn draw_full_layout(
-        f: &mut Frame,
-        area: Rect,
-        game: &Game,
-        cursor: Position,
-        selected: Option<Position>,
-        config: &LayoutConfig,
-    ) {
-        // Split into board (left) and sidebar (right)
-        let horizontal_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Min(55), Constraint::Length(35)])
-            .split(area);
+    /// The currently highlighted file, if any
+    pub fn selected_file(&self) -> Option<&RecentFile> {
+        self.entries.get(self.selected).map(|(file, _)| file)
+    }
+}
 
-        // Split sidebar into history (top) and info (bottom)
-        let sidebar_chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(12), Constraint::Length(10)])
-            .split(horizontal_chunks[1]);
+/// Game picker state for multi-game PGN collections
+#[derive(Debug, Clone, Default)]
+pub struct GamePickerState {
+    pub selected: usize,
+    pub games: Vec<PgnGame>,
+    /// File the games came from, shown in the title
+    pub source: String,
+    /// Number of games that fit on screen, updated on every draw
+    pub page_rows: usize,
+}
 
-        Self::draw_board(f, horizontal_chunks[0], game, cursor, selected, config);
-        Self::draw_move_history(f, sidebar_chunks[0], game, config);
-        Self::draw_game_info(f, sidebar_chunks[1], game, config);
+impl GamePickerState {
+    pub fn new(source: impl Into<String>, games: Vec<PgnGame>) -> Self {
+        Self {
+            selected: 0,
+            games,
+            source: source.into(),
+            page_rows: 0,
+        }
     }
 
-    /// Draw the title bar at the top
-    fn draw_title_bar(f: &mut Frame, area: Rect, game: &Game, _config: &LayoutConfig) {
-        let border_style = Style::default().fg(C_PRIMARY);
+    /// Move the selection by `delta`, staying within the list
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.games.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
 
-        let line1 = vec![
-            Span::styled(
-                "◆",
-                Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(
-                " 中国象棋 ",
-                Style::default().fg(C_PRIMARY).add_modifier(Modifier::BOLD),
-            ),
-            Span::styled("Chinese Chess ", Style::default().fg(C_ACCENT)),
-            Span::styled(
-                "◆",
-                Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD),
-            ),
-        ];
+    /// The currently highlighted game, if any
+    pub fn selected_game(&self) -> Option<&PgnGame> {
+        self.games.get(self.selected)
+    }
+}
 
-        let check_indicator = if game.is_in_check() {
-            Span::styled(
-                " 将军! ",
-                Style::default().fg(C_CHECK).add_modifier(Modifier::BOLD),
-            )
-        } else {
-            Span::raw("")
-        };
+/// Game library screen state
+#[derive(Debug, Clone, Default)]
+pub struct LibraryState {
+    pub selected: usize,
+    /// Saved games, most recent first
+    pub entries: Vec<LibraryEntry>,
+    /// Library directory, shown in the title
+    pub dir: String,
+    /// Number of games that fit on screen, updated on every draw
+    pub page_rows: usize,
+}
 
-        let turn_text = match game.turn() {
-            Color::Red => " 红方 ",
-            Color::Black => " 黑方 ",
-        };
-        let turn_style = match game.turn() {
-            Color::Red => Style::default()
-                .fg(C_RED_PIECE)
-                .add_modifier(Modifier::BOLD),
-            Color::Black => Style::default()
-                .fg(C_BLACK_PIECE)
-                .add_modifier(Modifier::BOLD),
-        };
+impl LibraryState {
+    pub fn new(dir: impl Into<String>, entries: Vec<LibraryEntry>) -> Self {
+        Self {
+            selected: 0,
+            entries,
+            dir: dir.into(),
+            page_rows: 0,
+        }
+    }
 
-        let line2 = vec![
-            Span::styled("当前回合: ", Style::default().fg(C_SECONDARY)),
-            Span::styled(turn_text, turn_style),
-            check_indicator,
-            Span::styled(
-                format!("着法: {}", game.get_moves().len()),
-                Style::default().fg(C_GOLD),
-            ),
-        ];
+    /// Move the selection by `delta`, staying within the list
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.entries.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
 
-        let line3 = vec![
-            Span::styled("┈", Style::default().fg(C_GRID)),
-            Span::styled(" q:退出 ", Style::default().fg(C_ACCENT)),
-            Span::styled(" r:重开 ", Style::default().fg(C_ACCENT)),
-            Span::styled(" u:撤销 ", Style::default().fg(C_ACCENT)),
-            Span::styled(" 方向键:移动 Enter:选择 ", Style::default().fg(C_SECONDARY)),
-            Span::styled("┈", Style::default().fg(C_GRID)),
-        ];
+    /// The currently highlighted game, if any
+    pub fn selected_entry(&self) -> Option<&LibraryEntry> {
+        self.entries.get(self.selected)
+    }
+}
 
-        let lines = vec![Line::from(line1), Line::from(line2), Line::from(line3)];
+/// Full-screen move history browser state
+///
+/// One row per full move (Red and Black). `page_rows` is updated on every
+/// draw so paging matches the terminal size.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HistoryBrowserState {
+    /// Index of the first visible row
+    pub top: usize,
+    /// Number of rows that fit on screen
+    pub page_rows: usize,
+}
 
-        f.render_widget(
-            Paragraph::new(lines)
-                .block(
-                    Block::default()
-                        .borders(BORDER_ALL)
-                        .border_style(border_style),
-                )
-                .alignment(Alignment::Center),
-            area,
-        );
+impl HistoryBrowserState {
+    /// Number of rows needed for a history of `move_count` plies
+    pub fn total_rows(move_count: usize) -> usize {
+        move_count.div_ceil(2)
     }
 
-    /// Draw the help bar at the bottom
-    fn draw_help_bar(f: &mut Frame, area: Rect, _config: &LayoutConfig) {
-        let help_text = vec![
-            Line::from(vec![Span::styled(
-                " 快捷键 Help ",
-                Style::default().fg(C_PRIMARY).add_modifier(Modifier::BOLD),
-            )]),
-            Line::from(vec![
-                Span::styled(" ↑↓←→ ", Style::default().fg(C_ACCENT)),
-                Span::styled("移动光标  ", Style::default().fg(C_SECONDARY)),
-                Span::styled(" Enter ", Style::default().fg(C_ACCENT)),
-                Span::styled("选择/确认  ", Style::default().fg(C_SECONDARY)),
-                Span::styled(" u ", Style::default().fg(C_ACCENT)),
-                Span::styled("撤销  ", Style::default().fg(C_SECONDARY)),
-                Span::styled(" r ", Style::default().fg(C_ACCENT)),
-                Span::styled("重开  ", Style::default().fg(C_SECONDARY)),
-                Span::styled(" q/Esc ", Style::default().fg(C_ACCENT)),
-                Span::styled("退出", Style::default().fg(C_SECONDARY)),
-            ]),
-            Line::from(""),
-        ];
+    fn max_top(&self, total_rows: usize) -> usize {
+        total_rows.saturating_sub(self.page_rows.max(1))
+    }
 
-        f.render_widget(
-            Paragraph::new(help_text)
-                .block(
-                    Block::default()
-                        .borders(BORDER_ALL)
-                        .border_style(Style::default().fg(C_SECONDARY)),
-                )
-                .alignment(Alignment::Center),
-            area,
-        );
+    /// Scroll by `delta` rows, staying within the history
+    pub fn scroll(&mut self, delta: isize, total_rows: usize) {
+        let top = self.top.saturating_add_signed(delta);
+        self.top = top.min(self.max_top(total_rows));
     }
 
-    /// Draw the game board
-    fn draw_board(
-        f: &mut Frame,
-        area: Rect,
-        game: &Game,
-        cursor: Position,
-        selected: Option<Position>,
-        config: &LayoutConfig,
-    ) {
-        let board_width = ((BOARD_COLS as u16) * config.cell_width + 2).min(area.width);
-        let board_height = ((BOARD_ROWS as u16) * config.cell_height + 2).min(area.height);
-        let board_area = Self::centered_rect(board_width, board_height, area);
+    /// Scroll down one page
+    pub fn page_down(&mut self, total_rows: usize) {
+        self.scroll(self.page_rows.max(1) as isize, total_rows);
+    }
 
-        let block = Block::default()
-            .borders(BORDER_ALL)
-            .border_style(Style::default().fg(C_SECONDARY))
-            .title(Span::styled(
-                " 棋盘 Board ",
-                Style::default().fg(C_ACCENT).add_modifier(Modifier::BOLD),
-            ));
+    /// Scroll up one page
+    pub fn page_up(&mut self, total_rows: usize) {
+        self.scroll(-(self.page_rows.max(1) as isize), total_rows);
+    }
 
-        f.render_widget(block, board_area);
+    /// Jump to the last page
+    pub fn end(&mut self, total_rows: usize) {
+        self.top = self.max_top(total_rows);
+    }
+}
 
-        let inner = board_area.inner(Margin::new(1, 1));
+/// Position shown while reviewing a game
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReviewState {
+    /// Number of moves played in the position shown
+    pub ply: usize,
+    /// Number of moves in the line being reviewed
+    pub total: usize,
+    /// Variations taken to reach the line, as for [`Game::line`]; empty
+    /// for the game itself
+    pub path: Vec<(usize, usize)>,
+}
 
-        Self::draw_grid(f, inner, config);
-        if config.show_river_text {
-            Self::draw_river(f, inner, config);
-        }
-        Self::draw_cursor_highlight(f, inner, cursor, config);
-        if let Some(sel) = selected {
-            Self::draw_selection_highlight(f, inner, sel, config);
+impl ReviewState {
+    /// Start reviewing at the final position
+    pub fn new(total: usize) -> Self {
+        Self {
+            ply: total,
+            total,
+            path: Vec::new(),
         }
-        Self::draw_pieces(f, inner, game, config);
     }
 
-    /// Draw mini info panel for compact layout
-    fn draw_mini_info(f: &mut Frame, area: Rect, game: &Game, _config: &LayoutConfig) {
-        let turn = match game.turn() {
-            Color::Red => "● 红方",
-            Color::Black => "● 黑方",
-        };
-        let turn_color = match game.turn() {
-            Color::Red => C_RED_PIECE,
-            Color::Black => C_BLACK_PIECE,
+    /// Step forward or back through the line
+    pub fn step(&mut self, delta: isize) {
+        self.ply = self.ply.saturating_add_signed(delta).min(self.total);
+    }
+
+    /// Step into a variation of `game`, the game being reviewed
+    ///
+    /// At the first move of a variation this moves on to the next
+    /// variation of the same move; otherwise it enters the first variation
+    /// played instead of the next move. Returns false if there is none.
+    pub fn enter_variation(&mut self, game: &Game) -> bool {
+        if let Some(&(index, variation)) = self.path.last() {
+            let parent = game.line(&self.path[..self.path.len() - 1]);
+            let siblings = parent.map_or(0, |line| line.variations(index).len());
+            if self.ply == index + 1 && variation + 1 < siblings {
+                self.path.pop();
+                self.path.push((index, variation + 1));
+                self.total = Self::line_length(game, &self.path);
+                return true;
+            }
+        }
+        let has_variation = game
+            .line(&self.path)
+            .is_some_and(|line| !line.variations(self.ply).is_empty());
+        if !has_variation {
+            return false;
+        }
+        self.path.push((self.ply, 0));
+        self.ply += 1;
+        self.total = Self::line_length(game, &self.path);
+        true
+    }
+
+    /// Leave the variation being reviewed for the line it branched from,
+    /// at the position where it branched. Returns false on the main line.
+    pub fn leave_variation(&mut self, game: &Game) -> bool {
+        let Some((index, _)) = self.path.pop() else {
+            return false;
+        };
+        self.ply = index;
+        self.total = Self::line_length(game, &self.path);
+        true
+    }
+
+    fn line_length(game: &Game, path: &[(usize, usize)]) -> usize {
+        game.line(path).map_or(0, |line| line.get_moves().len())
+    }
+}
+
+/// Comment being written for a move while reviewing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommentEditorState {
+    /// Index of the move being annotated (0 is the first move)
+    pub index: usize,
+    /// The move, as shown in the heading
+    pub notation: String,
+    pub text: String,
+}
+
+impl CommentEditorState {
+    /// Edit the comment on move `index`, starting from `text`
+    pub fn new(index: usize, notation: impl Into<String>, text: Option<&str>) -> Self {
+        Self {
+            index,
+            notation: notation.into(),
+            text: text.unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// Command being typed at the `:` prompt
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandLineState {
+    /// The command so far, without the `:`
+    pub text: String,
+    /// Command names matching the last completion, when it was ambiguous
+    pub completions: Vec<&'static str>,
+}
+
+/// Rows of the move history panel a comment may take
+const MAX_COMMENT_ROWS: usize = 4;
+
+/// A move's thinking time as short as the history panel needs it, e.g.
+/// `8s` or `2:05`
+fn move_time(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Scroll position of the move history side panel
+///
+/// One row per full move, like [`HistoryBrowserState`]. The panel follows
+/// the latest move until it is scrolled back, and again once scrolled to
+/// the end. `page_rows` is updated on every draw.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MoveListState {
+    /// Index of the first visible row, or None to follow the latest move
+    pub top: Option<usize>,
+    /// Number of rows that fit in the panel
+    pub page_rows: usize,
+    /// Whether the scroll keys go to the panel
+    pub focused: bool,
+    /// Notation the moves are written in
+    pub style: MoveStyle,
+}
+
+impl MoveListState {
+    fn max_top(&self, total_rows: usize) -> usize {
+        total_rows.saturating_sub(self.page_rows.max(1))
+    }
+
+    /// Index of the first visible row
+    pub fn first_row(&self, total_rows: usize) -> usize {
+        let max_top = self.max_top(total_rows);
+        self.top.map_or(max_top, |top| top.min(max_top))
+    }
+
+    /// Scroll by `delta` rows, following the latest move again at the end
+    pub fn scroll(&mut self, delta: isize, total_rows: usize) {
+        let max_top = self.max_top(total_rows);
+        let top = self.first_row(total_rows).saturating_add_signed(delta);
+        self.top = (top < max_top).then_some(top);
+    }
+
+    /// Scroll down one page
+    pub fn page_down(&mut self, total_rows: usize) {
+        self.scroll(self.page_rows.max(1) as isize, total_rows);
+    }
+
+    /// Scroll up one page
+    pub fn page_up(&mut self, total_rows: usize) {
+        self.scroll(-(self.page_rows.max(1) as isize), total_rows);
+    }
+
+    /// Jump to the first move
+    pub fn home(&mut self, total_rows: usize) {
+        self.top = (self.max_top(total_rows) > 0).then_some(0);
+    }
+
+    /// Follow the latest move
+    pub fn end(&mut self) {
+        self.top = None;
+    }
+}
+
+/// What the move history panel lists
+struct MoveList<'a> {
+    /// Game whose moves are listed
+    game: &'a Game,
+    /// Moves played in the position on the board, marked while reviewing
+    current: Option<usize>,
+    state: &'a mut MoveListState,
+}
+
+/// Piece kinds in the setup picker, one row each with Red then Black
+const PICKER_TYPES: [PieceType; 7] = [
+    PieceType::General,
+    PieceType::Advisor,
+    PieceType::Elephant,
+    PieceType::Horse,
+    PieceType::Chariot,
+    PieceType::Cannon,
+    PieceType::Soldier,
+];
+
+/// Picker entries: each piece, then removing the piece on the square
+const PICKER_ENTRIES: usize = PICKER_TYPES.len() * 2 + 1;
+
+/// Columns of each piece in the setup palette
+const PALETTE_COLUMN: u16 = 7;
+const PALETTE_WIDTH: u16 = PALETTE_COLUMN * 2 + 2;
+const PALETTE_HEIGHT: u16 = PICKER_TYPES.len() as u16 + 2;
+
+/// A piece being dragged with the mouse in setup mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Drag {
+    pub piece: Piece,
+    /// The square it was lifted from, None when taken from the palette
+    pub from: Option<Position>,
+}
+
+/// Position being set up by hand, and the piece picker
+#[derive(Debug, Clone)]
+pub struct SetupState {
+    pub editor: BoardEditor,
+    /// Selected picker entry while the piece picker is open
+    pub picker: Option<usize>,
+    /// The piece being dragged, between mouse press and release
+    pub drag: Option<Drag>,
+}
+
+impl Default for SetupState {
+    fn default() -> Self {
+        Self::new(BoardEditor::new(Board::new(), Color::Red))
+    }
+}
+
+impl SetupState {
+    pub fn new(editor: BoardEditor) -> Self {
+        Self {
+            editor,
+            picker: None,
+            drag: None,
+        }
+    }
+
+    /// Pick up the piece on `from` to drag it elsewhere
+    pub fn drag_from_square(&mut self, from: Position) {
+        self.drag = self.editor.board().get(from).map(|&piece| Drag {
+            piece,
+            from: Some(from),
+        });
+    }
+
+    /// Take a new piece from the palette to drag onto the board
+    pub fn drag_from_palette(&mut self, piece: Piece) {
+        self.drag = Some(Drag { piece, from: None });
+    }
+
+    /// Drop the dragged piece on `to`, or off the board for None
+    ///
+    /// A piece dragged from a square moves there, or is removed when
+    /// dropped off the board; one from the palette is placed. Returns
+    /// whether the board changed.
+    pub fn drop_on(&mut self, to: Option<Position>) -> bool {
+        let Some(drag) = self.drag.take() else {
+            return false;
+        };
+        match (drag.from, to) {
+            (Some(from), Some(to)) => self.editor.move_piece(from, to),
+            (Some(from), None) => self.editor.remove(from),
+            (None, Some(to)) => self.editor.place(to, drag.piece),
+            (None, None) => false,
+        }
+    }
+
+    /// Open the picker on the piece at `pos`, or on the first piece
+    pub fn open_picker(&mut self, pos: Position) {
+        let entry = self.editor.board().get(pos).and_then(|piece| {
+            let row = PICKER_TYPES.iter().position(|&t| t == piece.piece_type)?;
+            Some(row * 2 + usize::from(piece.color == Color::Black))
+        });
+        self.picker = Some(entry.unwrap_or(0));
+    }
+
+    /// Move the picker selection; rows hold a Red and a Black piece
+    pub fn move_picker(&mut self, delta: isize) {
+        if let Some(entry) = self.picker {
+            self.picker = Some(entry.saturating_add_signed(delta).min(PICKER_ENTRIES - 1));
+        }
+    }
+
+    /// The piece the selected picker entry places, None for removal
+    pub fn picked(&self) -> Option<Piece> {
+        let entry = self.picker?;
+        let piece_type = *PICKER_TYPES.get(entry / 2)?;
+        let color = if entry % 2 == 0 {
+            Color::Red
+        } else {
+            Color::Black
+        };
+        Some(Piece::new(piece_type, color))
+    }
+
+    /// The edited position as a game, for drawing
+    ///
+    /// A position missing a general is still shown.
+    pub fn preview(&self) -> Game {
+        Game::from_fen_permissive(&self.editor.to_fen()).unwrap_or_default()
+    }
+}
+
+/// Why the game ended
+fn termination_reason(game: &Game) -> String {
+    match game.state() {
+        GameState::Checkmate(_) => tr(Text::Checkmate).to_string(),
+        GameState::Stalemate => tr(Text::Stalemate).to_string(),
+        GameState::StalemateWin(winner) => trf(Text::Stalemated, &[&side_name(winner.opponent())]),
+        GameState::DrawByRepetition => tr(Text::Repetition).to_string(),
+        GameState::PerpetualCheck(winner) => {
+            trf(Text::PerpetualCheck, &[&side_name(winner.opponent())])
+        }
+        GameState::DrawByMoveLimit => match game.move_limit() {
+            Some(limit) => trf(Text::MoveLimitAfter, &[&limit]),
+            None => tr(Text::MoveLimit).to_string(),
+        },
+        GameState::MissingGeneral(loser) => trf(Text::MissingGeneral, &[&side_name(loser)]),
+        GameState::Timeout(winner) => trf(Text::OutOfTime, &[&side_name(winner.opponent())]),
+        GameState::Adjudicated(Some(winner)) => {
+            trf(Text::AdjudicatedLoss, &[&side_name(winner.opponent())])
+        }
+        GameState::Adjudicated(None) => tr(Text::AdjudicatedDraw).to_string(),
+        GameState::Playing => String::new(),
+    }
+}
+
+/// The piece the learning mode explains: the selected one, or else the
+/// one under the cursor
+fn tutor_square(board: &Board, cursor: Position, selected: Option<Position>) -> Option<Position> {
+    selected
+        .or(Some(cursor))
+        .filter(|&pos| board.get(pos).is_some())
+}
+
+fn side_name(color: Color) -> &'static str {
+    UI::lang().side(color)
+}
+
+/// An engine line's score, depth and moves, for the analysis and kibitz
+/// panels
+fn pv_line_spans(line: &PvLine, moves: &[String]) -> Vec<Span<'static>> {
+    let score = line.score.map(|score| format!("{:+}", score));
+    let depth = line.depth.map(|depth| format!("d{}", depth));
+    vec![
+        Span::styled(
+            format!("{:>5} ", score.unwrap_or_default()),
+            Style::default().fg(theme().gold),
+        ),
+        Span::styled(
+            format!("{:<4}", depth.unwrap_or_default()),
+            Style::default().fg(theme().secondary),
+        ),
+        Span::styled(moves.join(" "), Style::default().fg(theme().accent)),
+    ]
+}
+
+/// One kibitzing engine's column in the kibitz panel
+pub struct KibitzColumn<'a> {
+    pub name: &'a str,
+    /// The engine's lines, best first, with their moves formatted for
+    /// display
+    pub lines: Vec<(&'a PvLine, Vec<String>)>,
+}
+
+pub struct UI;
+
+impl UI {
+    /// Set the colors used by every draw function on this thread
+    pub fn set_theme(theme: Theme) {
+        THEME.with(|t| t.set(theme));
+    }
+
+    /// The colors currently used for drawing
+    pub fn theme() -> Theme {
+        theme()
+    }
+
+    /// Set the keys shown in help text and popups
+    pub fn set_key_bindings(keys: KeyBindings) {
+        KEYS.with(|k| k.set(keys));
+    }
+
+    /// Set how pieces are written on the board
+    pub fn set_piece_style(style: PieceStyle) {
+        PIECE_STYLE.with(|s| s.set(style));
+    }
+
+    /// How pieces are written on the board
+    pub fn piece_style() -> PieceStyle {
+        PIECE_STYLE.with(Cell::get)
+    }
+
+    /// Hide the pieces for blindfold play; the grid, cursor and move
+    /// highlights stay
+    ///
+    /// Pieces are still shown while setting up a position.
+    pub fn set_blindfold(blindfold: bool) {
+        BLINDFOLD.with(|b| b.set(blindfold));
+    }
+
+    pub fn blindfold() -> bool {
+        BLINDFOLD.with(Cell::get)
+    }
+
+    /// Label the files a-i under the board and the ranks 0-9 beside it,
+    /// as in ICCS moves and engine output
+    ///
+    /// Labels are left out when the board has no room for them.
+    pub fn set_coordinates(coordinates: bool) {
+        COORDINATES.with(|c| c.set(coordinates));
+    }
+
+    pub fn coordinates() -> bool {
+        COORDINATES.with(Cell::get)
+    }
+
+    /// Draw the board turned around, with Red at the top
+    pub fn set_flipped(flipped: bool) {
+        FLIPPED.with(|f| f.set(flipped));
+    }
+
+    pub fn flipped() -> bool {
+        FLIPPED.with(Cell::get)
+    }
+
+    /// Learning mode: mark the points the selected piece, or the piece
+    /// under the cursor, can reach, whichever side it belongs to
+    ///
+    /// [`UI::draw_tutor_panel`] explains how that piece moves.
+    pub fn set_tutor(tutor: bool) {
+        TUTOR.with(|t| t.set(tutor));
+    }
+
+    pub fn tutor() -> bool {
+        TUTOR.with(Cell::get)
+    }
+
+    /// Shade the points each side attacks, as computed by
+    /// [`Board::attacked_squares`]
+    pub fn set_attack_overlay(overlay: AttackOverlay) {
+        ATTACKS.with(|a| a.set(overlay));
+    }
+
+    pub fn attack_overlay() -> AttackOverlay {
+        ATTACKS.with(Cell::get)
+    }
+
+    /// Accessible mode: replace the status bar with a line describing the
+    /// game in words, from [`describe::status_line`](crate::describe::status_line),
+    /// for screen readers
+    pub fn set_accessible(accessible: bool) {
+        ACCESSIBLE.with(|a| a.set(accessible));
+    }
+
+    pub fn accessible() -> bool {
+        ACCESSIBLE.with(Cell::get)
+    }
+
+    /// Set the language of the interface's text
+    pub fn set_lang(lang: Lang) {
+        LANG.with(|l| l.set(lang));
+    }
+
+    /// The language of the interface's text
+    pub fn lang() -> Lang {
+        LANG.with(Cell::get)
+    }
+
+    /// An AI mode's short name, as the status bar shows it
+    pub fn mode_name(ai_mode: AiMode) -> &'static str {
+        tr(match ai_mode {
+            AiMode::Off => Text::ModePvp,
+            AiMode::PlaysRed => Text::ModeAiRed,
+            AiMode::PlaysBlack => Text::ModeAiBlack,
+            AiMode::PlaysBoth => Text::ModeAiBoth,
+        })
+    }
+
+    /// A difficulty's name, or the custom settings' when there is none
+    pub fn difficulty_name(difficulty: Option<Difficulty>) -> &'static str {
+        tr(match difficulty {
+            None => Text::Custom,
+            Some(Difficulty::Beginner) => Text::Beginner,
+            Some(Difficulty::Intermediate) => Text::Intermediate,
+            Some(Difficulty::Advanced) => Text::Advanced,
+            Some(Difficulty::Max) => Text::Max,
+        })
+    }
+
+    /// A handicap's name in the interface's language
+    pub fn handicap_name(handicap: &Handicap) -> String {
+        match UI::lang() {
+            Lang::Bilingual => format!("{} {}", handicap.chinese, handicap.english),
+            Lang::En => handicap.english.to_string(),
+            Lang::Zh => handicap.chinese.to_string(),
+        }
+    }
+
+    /// The board point drawn at a terminal cell in the last frame, for
+    /// mouse play and the setup editor alike
+    pub fn board_position_at(column: u16, row: u16) -> Option<Position> {
+        BOARD.with(Cell::get).position_at(column, row)
+    }
+
+    /// The piece the setup palette showed at a terminal cell in the last
+    /// frame
+    pub fn palette_piece_at(column: u16, row: u16) -> Option<Piece> {
+        let inner = PALETTE.with(Cell::get).inner(Margin::new(1, 1));
+        if !inner.contains((column, row).into()) {
+            return None;
+        }
+        let piece_type = *PICKER_TYPES.get(usize::from(row - inner.y))?;
+        Some(if column - inner.x < PALETTE_COLUMN {
+            Piece::red(piece_type)
+        } else {
+            Piece::black(piece_type)
+        })
+    }
+
+    pub fn draw(f: &mut Frame, game: &Game, cursor: Position, selection: Option<Position>) {
+        Self::draw_with_pv(
+            f,
+            game,
+            cursor,
+            selection,
+            &[],
+            &mut MoveListState::default(),
+        );
+    }
+
+    /// Draw the game with the first moves of an engine line marked on the
+    /// board
+    ///
+    /// `pv` starts from the current position; the squares of its first
+    /// moves are numbered in the order they are played. `history` is the
+    /// scroll position of the move history panel.
+    pub fn draw_with_pv(
+        f: &mut Frame,
+        game: &Game,
+        cursor: Position,
+        selection: Option<Position>,
+        pv: &[(Position, Position)],
+        history: &mut MoveListState,
+    ) {
+        let moves = MoveList {
+            game,
+            current: None,
+            state: history,
+        };
+        let config = Self::draw_screen(f, game, cursor, selection, pv, moves);
+
+        // Draw game over popup if needed
+        if game.state() != GameState::Playing {
+            Self::draw_game_over_popup(f, f.area(), game, &config);
+        }
+    }
+
+    /// Draw the title bar, board, side panels and help bar
+    fn draw_screen(
+        f: &mut Frame,
+        game: &Game,
+        cursor: Position,
+        selection: Option<Position>,
+        pv: &[(Position, Position)],
+        moves: MoveList,
+    ) -> LayoutConfig {
+        let size = f.area();
+        let config = LayoutConfig::from_terminal_size(size);
+        let main_chunks = Self::main_chunks(size, &config);
+
+        // Draw title bar
+        Self::draw_title_bar(f, main_chunks[0], game, &config);
+
+        // Draw content area based on layout type
+        let history = match config.layout_zone {
+            LayoutZone::Compact => {
+                Self::draw_compact_layout(f, main_chunks[1], game, cursor, selection, pv, &config);
+                None
+            }
+            LayoutZone::Standard => Some(Self::draw_standard_layout(
+                f,
+                main_chunks[1],
+                game,
+                cursor,
+                selection,
+                pv,
+                &config,
+            )),
+            LayoutZone::Full => Some(Self::draw_full_layout(
+                f,
+                main_chunks[1],
+                game,
+                cursor,
+                selection,
+                pv,
+                &config,
+            )),
+        };
+        if let Some(area) = history {
+            Self::draw_move_history(f, area, moves);
+        }
+
+        // Draw help bar
+        Self::draw_help_bar(f, main_chunks[2], &config);
+
+        config
+    }
+
+    /// Draw a position from a game being reviewed
+    ///
+    /// `game` is the whole game; the board shows the line `state.path`
+    /// leads to as it stood at `state.ply`, which is marked in the move
+    /// history written in `style`. The game over popup is not shown so the
+    /// final position can be studied.
+    pub fn draw_review(
+        f: &mut Frame,
+        game: &Game,
+        cursor: Position,
+        state: &ReviewState,
+        style: MoveStyle,
+    ) {
+        let line = game.line(&state.path).unwrap_or_else(|| game.clone());
+        let position = line.at_ply(state.ply);
+        let moves = MoveList {
+            game: &line,
+            current: Some(state.ply),
+            state: &mut MoveListState {
+                style,
+                ..MoveListState::default()
+            },
+        };
+        Self::draw_screen(f, &position, cursor, None, &[], moves);
+
+        let size = f.area();
+        if size.height < 2 {
+            return;
+        }
+        let bar = Rect {
+            x: 0,
+            y: size.height - 1,
+            width: size.width,
+            height: 1,
+        };
+        let variation = match state.path.split_last() {
+            Some((&(index, variation), parents)) => {
+                let count = game
+                    .line(parents)
+                    .map_or(0, |line| line.variations(index).len());
+                trf(Text::Variation, &[&(variation + 1), &count])
+            }
+            None => String::new(),
+        };
+        let text = trf(Text::ReviewBar, &[&state.ply, &state.total, &variation]);
+        f.render_widget(
+            Paragraph::new(Span::styled(
+                text,
+                Style::default()
+                    .fg(theme().popup_bg)
+                    .bg(theme().primary)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            bar,
+        );
+    }
+
+    /// Draw the position being set up, with the setup keys and whether
+    /// the position is playable
+    pub fn draw_setup(f: &mut Frame, state: &SetupState, cursor: Position) {
+        let preview = state.preview();
+        let moves = MoveList {
+            game: &preview,
+            current: None,
+            state: &mut MoveListState::default(),
+        };
+        // Pieces are placed by sight even when playing blindfold
+        let blindfold = Self::blindfold();
+        Self::set_blindfold(false);
+        let lifted = state.drag.and_then(|drag| drag.from);
+        Self::draw_screen(f, &preview, cursor, lifted, &[], moves);
+        Self::set_blindfold(blindfold);
+        Self::draw_piece_palette(f, state);
+
+        let size = f.area();
+        if size.height < 10 {
+            return;
+        }
+        // Status line plus the keys, which wrap on narrow terminals
+        let area = Rect {
+            x: 0,
+            y: size.height - 5,
+            width: size.width,
+            height: 5,
+        };
+        let side = match state.editor.turn() {
+            Color::Red => Span::styled(tr(Text::SetupRed), Style::default().fg(theme().red_piece)),
+            Color::Black => Span::styled(
+                tr(Text::SetupBlack),
+                Style::default().fg(theme().black_piece),
+            ),
+        };
+        let status = match state.editor.validate() {
+            Ok(()) => Span::styled(tr(Text::SetupReady), Style::default().fg(theme().primary)),
+            Err(e) => Span::styled(format!("✗ {}", e), Style::default().fg(theme().check)),
+        };
+        let lines = vec![
+            Line::from(vec![
+                Span::styled(tr(Text::ToMove), Style::default().fg(theme().secondary)),
+                side,
+                Span::raw("   "),
+                status,
+            ]),
+            Line::from(Span::styled(
+                trf(Text::SetupKeys, &[&keys().undo, &keys().redo]),
+                Style::default().fg(theme().accent),
+            )),
+        ];
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(lines)
+                .block(
+                    Block::default()
+                        .borders(BORDER_ALL)
+                        .border_style(Style::default().fg(theme().primary))
+                        .title(Span::styled(
+                            format!(" {} ", tr(Text::SetupTitle)),
+                            Style::default()
+                                .fg(theme().accent)
+                                .add_modifier(Modifier::BOLD),
+                        ))
+                        .style(Style::default().bg(theme().popup_bg)),
+                )
+                .wrap(Wrap { trim: true }),
+            area,
+        );
+
+        if state.picker.is_some() {
+            Self::draw_piece_picker(f, state);
+        }
+    }
+
+    /// Draw the palette of pieces to drag onto the board, beside the
+    /// board's frame: on the right if it fits, else on the left
+    ///
+    /// Left out when there is room on neither side.
+    fn draw_piece_palette(f: &mut Frame, state: &SetupState) {
+        let grid = BOARD.with(Cell::get).grid();
+        let screen = f.area();
+        let y = grid.y.saturating_sub(1);
+        let x = if grid.right() + 1 + PALETTE_WIDTH <= screen.right() {
+            Some(grid.right() + 1)
+        } else {
+            (grid.x > PALETTE_WIDTH).then(|| grid.x - 1 - PALETTE_WIDTH)
+        };
+        let area = match x {
+            Some(x) if y + PALETTE_HEIGHT <= screen.bottom() => {
+                Rect::new(x, y, PALETTE_WIDTH, PALETTE_HEIGHT)
+            }
+            _ => Rect::ZERO,
+        };
+        PALETTE.with(|p| p.set(area));
+        if area.is_empty() {
+            return;
+        }
+
+        let dragged = state
+            .drag
+            .filter(|drag| drag.from.is_none())
+            .map(|drag| drag.piece);
+        let entry = |piece: Piece| {
+            let color = match piece.color {
+                Color::Red => theme().red_piece,
+                Color::Black => theme().black_piece,
+            };
+            let style = if dragged == Some(piece) {
+                Style::default()
+                    .fg(theme().popup_bg)
+                    .bg(theme().primary)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(color)
+            };
+            Span::styled(format!(" {} {}  ", piece, piece_to_fen(piece)), style)
+        };
+        let lines: Vec<Line> = PICKER_TYPES
+            .iter()
+            .map(|&piece_type| {
+                Line::from(vec![
+                    entry(Piece::red(piece_type)),
+                    entry(Piece::black(piece_type)),
+                ])
+            })
+            .collect();
+
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(BORDER_ALL)
+                    .border_style(Style::default().fg(theme().primary))
+                    .title(Span::styled(
+                        format!(" {} ", tr(Text::Palette)),
+                        Style::default().fg(theme().accent),
+                    ))
+                    .style(Style::default().bg(theme().popup_bg)),
+            ),
+            area,
+        );
+    }
+
+    /// Draw the popup for choosing the piece to place
+    fn draw_piece_picker(f: &mut Frame, state: &SetupState) {
+        let area = Self::centered_rect(28, PICKER_TYPES.len() as u16 + 6, f.area());
+        let selected = state.picker.unwrap_or(0);
+        let entry_style = |entry: usize, color: RColor| {
+            if entry == selected {
+                Style::default()
+                    .fg(theme().popup_bg)
+                    .bg(theme().primary)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(color)
+            }
+        };
+
+        let mut lines = Vec::new();
+        for (row, &piece_type) in PICKER_TYPES.iter().enumerate() {
+            let red = Piece::red(piece_type);
+            let black = Piece::black(piece_type);
+            lines.push(Line::from(vec![
+                Span::raw("   "),
+                Span::styled(
+                    format!(" {} {} ", red, piece_to_fen(red)),
+                    entry_style(row * 2, theme().red_piece),
+                ),
+                Span::raw("    "),
+                Span::styled(
+                    format!(" {} {} ", black, piece_to_fen(black)),
+                    entry_style(row * 2 + 1, theme().black_piece),
+                ),
+            ]));
+        }
+        lines.push(Line::from(vec![
+            Span::raw("   "),
+            Span::styled(
+                format!(" {} ", tr(Text::Remove)),
+                entry_style(PICKER_ENTRIES - 1, theme().secondary),
+            ),
+        ]));
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "[↑↓←→] [Enter] [Esc]",
+            Style::default().fg(theme().secondary),
+        )));
+
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(BORDER_ALL)
+                    .border_style(Style::default().fg(theme().primary))
+                    .title(Span::styled(
+                        format!(" {} ", tr(Text::PiecePicker)),
+                        Style::default().fg(theme().accent),
+                    ))
+                    .style(Style::default().bg(theme().popup_bg)),
+            ),
+            area,
+        );
+    }
+
+    /// Compact layout: board with minimal surrounding info
+    fn draw_compact_layout(
+        f: &mut Frame,
+        area: Rect,
+        game: &Game,
+        cursor: Position,
+        selected: Option<Position>,
+        pv: &[(Position, Position)],
+        config: &LayoutConfig,
+    ) {
+        // Split into board + small info panel
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(40), Constraint::Length(20)])
+            .split(area);
+
+        Self::draw_board(f, chunks[0], game, cursor, selected, pv, config);
+        Self::draw_mini_info(f, chunks[1], game, config);
+    }
+
+    /// Standard layout: board + move history
+    ///
+    /// Returns the area for the move history.
+    fn draw_standard_layout(
+        f: &mut Frame,
+        area: Rect,
+        game: &Game,
+        cursor: Position,
+        selected: Option<Position>,
+        pv: &[(Position, Position)],
+        config: &LayoutConfig,
+    ) -> Rect {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(50), Constraint::Length(28)])
+            .split(area);
+
+        Self::draw_board(f, chunks[0], game, cursor, selected, pv, config);
+        chunks[1]
+    }
+
+    /// Full layout: board + history + info panel
+    ///
+    /// Returns the area for the move history.
+    fn draw_full_layout(
+        f: &mut Frame,
+        area: Rect,
+        game: &Game,
+        cursor: Position,
+        selected: Option<Position>,
+        pv: &[(Position, Position)],
+        config: &LayoutConfig,
+    ) -> Rect {
+        let [board, history, graph, info] = Self::full_layout_chunks(area);
+        Self::draw_board(f, board, game, cursor, selected, pv, config);
+        Self::draw_game_info(f, info, game, config);
+        if !graph.is_empty() {
+            Self::draw_eval_graph(f, graph, game, &[]);
+        }
+        history
+    }
+
+    /// Main vertical layout: title + content + help
+    fn main_chunks(size: Rect, config: &LayoutConfig) -> std::rc::Rc<[Rect]> {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(config.title_height),
+                Constraint::Min(0),
+                Constraint::Length(config.help_height),
+            ])
+            .split(size)
+    }
+
+    /// Board, move history, evaluation graph and game info areas of the
+    /// full layout
+    ///
+    /// The graph is left out (zero height) on screens too short for it.
+    fn full_layout_chunks(area: Rect) -> [Rect; 4] {
+        // Split into board (left) and sidebar (right)
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(55), Constraint::Length(35)])
+            .split(area);
+
+        // Split sidebar into history (top), graph and info (bottom)
+        let graph_height = if area.height >= 12 + EVAL_GRAPH_HEIGHT + 10 {
+            EVAL_GRAPH_HEIGHT
+        } else {
+            0
         };
+        let sidebar_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(12),
+                Constraint::Length(graph_height),
+                Constraint::Length(10),
+            ])
+            .split(horizontal_chunks[1]);
+
+        [
+            horizontal_chunks[0],
+            sidebar_chunks[0],
+            sidebar_chunks[1],
+            sidebar_chunks[2],
+        ]
+    }
+
+    /// Where the evaluation graph goes on a screen of this size, if it is
+    /// shown
+    pub fn eval_graph_area(size: Rect) -> Option<Rect> {
+        let config = LayoutConfig::from_terminal_size(size);
+        if config.layout_zone != LayoutZone::Full {
+            return None;
+        }
+        let [_, _, graph, _] = Self::full_layout_chunks(Self::main_chunks(size, &config)[1]);
+        (!graph.is_empty()).then_some(graph)
+    }
+
+    /// Where the analysis panel goes on a screen of this size
+    ///
+    /// In the full layout the panel takes the lower part of the move history
+    /// column; smaller layouts have no sidebar room, so it sits above the
+    /// status bar instead.
+    pub fn analysis_area(size: Rect) -> Rect {
+        let config = LayoutConfig::from_terminal_size(size);
+        if config.layout_zone == LayoutZone::Full {
+            let [_, history, _, _] = Self::full_layout_chunks(Self::main_chunks(size, &config)[1]);
+            let height = history.height.min(ANALYSIS_PANEL_HEIGHT);
+            return Rect {
+                y: history.bottom() - height,
+                height,
+                ..history
+            };
+        }
+        let height = size.height.saturating_sub(1).min(5);
+        Rect {
+            x: 0,
+            y: size.height.saturating_sub(1) - height,
+            width: size.width,
+            height,
+        }
+    }
 
-        let check = if game.is_in_check() { "将军!" } else { "" };
+    /// Draw the title bar at the top
+    fn draw_title_bar(f: &mut Frame, area: Rect, game: &Game, _config: &LayoutConfig) {
+        let border_style = Style::default().fg(theme().primary);
+
+        let line1 = vec![
+            Span::styled(
+                "◆",
+                Style::default()
+                    .fg(theme().gold)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                tr(Text::AppTitle),
+                Style::default()
+                    .fg(theme().primary)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(tr(Text::AppSubtitle), Style::default().fg(theme().accent)),
+            Span::styled(
+                "◆",
+                Style::default()
+                    .fg(theme().gold)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ];
+
+        let check_indicator = if game.is_in_check() {
+            Span::styled(
+                format!(" {} ", tr(Text::Check)),
+                Style::default()
+                    .fg(theme().check)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::raw("")
+        };
+
+        let turn_text = format!(" {} ", side_name(game.turn()));
+        let turn_style = match game.turn() {
+            Color::Red => Style::default()
+                .fg(theme().red_piece)
+                .add_modifier(Modifier::BOLD),
+            Color::Black => Style::default()
+                .fg(theme().black_piece)
+                .add_modifier(Modifier::BOLD),
+        };
+
+        let line2 = vec![
+            Span::styled(
+                tr(Text::CurrentTurn),
+                Style::default().fg(theme().secondary),
+            ),
+            Span::styled(turn_text, turn_style),
+            check_indicator,
+            Span::styled(
+                trf(Text::MoveCount, &[&game.get_moves().len()]),
+                Style::default().fg(theme().gold),
+            ),
+        ];
+
+        let line3 = vec![
+            Span::styled("┈", Style::default().fg(theme().grid)),
+            Span::styled(
+                trf(Text::TitleKey, &[&keys().quit, &tr(Text::Quit)]),
+                Style::default().fg(theme().accent),
+            ),
+            Span::styled(
+                trf(Text::TitleKey, &[&keys().restart, &tr(Text::Restart)]),
+                Style::default().fg(theme().accent),
+            ),
+            Span::styled(
+                trf(Text::TitleKey, &[&keys().undo, &tr(Text::Undo)]),
+                Style::default().fg(theme().accent),
+            ),
+            Span::styled(
+                trf(Text::TitleKey, &[&keys().redo, &tr(Text::Redo)]),
+                Style::default().fg(theme().accent),
+            ),
+            Span::styled(
+                tr(Text::TitleArrows),
+                Style::default().fg(theme().secondary),
+            ),
+            Span::styled("┈", Style::default().fg(theme().grid)),
+        ];
+
+        let lines = vec![Line::from(line1), Line::from(line2), Line::from(line3)];
+
+        f.render_widget(
+            Paragraph::new(lines)
+                .block(
+                    Block::default()
+                        .borders(BORDER_ALL)
+                        .border_style(border_style),
+                )
+                .alignment(Alignment::Center),
+            area,
+        );
+    }
+
+    /// Draw the help bar at the bottom
+    fn draw_help_bar(f: &mut Frame, area: Rect, _config: &LayoutConfig) {
+        let help_text = vec![
+            Line::from(vec![Span::styled(
+                tr(Text::HelpTitle),
+                Style::default()
+                    .fg(theme().primary)
+                    .add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(vec![
+                Span::styled(" ↑↓←→ ", Style::default().fg(theme().accent)),
+                Span::styled(
+                    format!("{}  ", tr(Text::MoveCursor)),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(" Enter ", Style::default().fg(theme().accent)),
+                Span::styled(
+                    format!("{}  ", tr(Text::Select)),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(
+                    format!(" {} ", keys().undo),
+                    Style::default().fg(theme().accent),
+                ),
+                Span::styled(
+                    format!("{}  ", tr(Text::Undo)),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(
+                    format!(" {} ", keys().redo),
+                    Style::default().fg(theme().accent),
+                ),
+                Span::styled(
+                    format!("{}  ", tr(Text::Redo)),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(
+                    format!(" {} ", keys().restart),
+                    Style::default().fg(theme().accent),
+                ),
+                Span::styled(
+                    format!("{}  ", tr(Text::Restart)),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(" o ", Style::default().fg(theme().accent)),
+                Span::styled(
+                    format!("{}  ", tr(Text::Open)),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(" h ", Style::default().fg(theme().accent)),
+                Span::styled(
+                    format!("{}  ", tr(Text::Hint)),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(" H ", Style::default().fg(theme().accent)),
+                Span::styled(
+                    format!("{}  ", tr(Text::GameRecord)),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(" c ", Style::default().fg(theme().accent)),
+                Span::styled(
+                    format!("{}  ", tr(Text::Theme)),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(
+                    format!(" {}/Esc ", keys().quit),
+                    Style::default().fg(theme().accent),
+                ),
+                Span::styled(tr(Text::Quit), Style::default().fg(theme().secondary)),
+            ]),
+            Line::from(""),
+        ];
+
+        f.render_widget(
+            Paragraph::new(help_text)
+                .block(
+                    Block::default()
+                        .borders(BORDER_ALL)
+                        .border_style(Style::default().fg(theme().secondary)),
+                )
+                .alignment(Alignment::Center),
+            area,
+        );
+    }
+
+    /// Draw the game board
+    fn draw_board(
+        f: &mut Frame,
+        area: Rect,
+        game: &Game,
+        cursor: Position,
+        selected: Option<Position>,
+        pv: &[(Position, Position)],
+        config: &LayoutConfig,
+    ) {
+        let block = Block::default()
+            .borders(BORDER_ALL)
+            .border_style(Style::default().fg(theme().secondary))
+            .title(Span::styled(
+                format!(" {} ", tr(Text::BoardTitle)),
+                Style::default()
+                    .fg(theme().accent)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        let reachable_from = if Self::tutor() {
+            tutor_square(game.board(), cursor, selected)
+        } else {
+            None
+        };
+
+        let board = BoardWidget::new(game.board())
+            .theme(theme())
+            .piece_style(config.piece_style)
+            .cell_size(config.cell_width, config.cell_height)
+            .flipped(config.flipped)
+            .coordinates(config.show_coordinates)
+            .river_text(config.show_river_text)
+            .hide_pieces(Self::blindfold())
+            .last_move(game.last_move())
+            .reachable_from(reachable_from)
+            .attacks(Self::attack_overlay())
+            .pv(pv)
+            .block(block);
+        let mut state = BoardState::new(cursor);
+        state.selected = selected;
+        f.render_stateful_widget(board, area, &mut state);
+        BOARD.with(|b| b.set(state));
+    }
+
+    /// Draw mini info panel for compact layout
+    fn draw_mini_info(f: &mut Frame, area: Rect, game: &Game, _config: &LayoutConfig) {
+        let turn = format!("● {}", side_name(game.turn()));
+        let turn_color = match game.turn() {
+            Color::Red => theme().red_piece,
+            Color::Black => theme().black_piece,
+        };
+
+        let check = if game.is_in_check() {
+            tr(Text::Check)
+        } else {
+            ""
+        };
 
         let lines = vec![
             Line::from(vec![Span::styled(
-                " 信息 Info ",
-                Style::default().fg(C_ACCENT).add_modifier(Modifier::BOLD),
+                format!(" {} ", tr(Text::InfoTitle)),
+                Style::default()
+                    .fg(theme().accent)
+                    .add_modifier(Modifier::BOLD),
             )]),
             Line::from(""),
             Line::from(vec![
-                Span::styled("回合:", Style::default().fg(C_SECONDARY)),
+                Span::styled(tr(Text::TurnLabel), Style::default().fg(theme().secondary)),
                 Span::styled(
                     turn,
                     Style::default().fg(turn_color).add_modifier(Modifier::BOLD),
@@ -394,16 +2073,18 @@ impl UI {
             ]),
             Line::from(""),
             Line::from(vec![
-                Span::styled("步数:", Style::default().fg(C_SECONDARY)),
+                Span::styled(tr(Text::MovesLabel), Style::default().fg(theme().secondary)),
                 Span::styled(
                     format!(" {}", game.get_moves().len()),
-                    Style::default().fg(C_GOLD),
+                    Style::default().fg(theme().gold),
                 ),
             ]),
             Line::from(""),
             Line::from(vec![Span::styled(
                 check,
-                Style::default().fg(C_CHECK).add_modifier(Modifier::BOLD),
+                Style::default()
+                    .fg(theme().check)
+                    .add_modifier(Modifier::BOLD),
             )]),
         ];
 
@@ -412,7 +2093,7 @@ impl UI {
                 .block(
                     Block::default()
                         .borders(BORDER_ALL)
-                        .border_style(Style::default().fg(C_SECONDARY)),
+                        .border_style(Style::default().fg(theme().secondary)),
                 )
                 .alignment(Alignment::Left),
             area,
@@ -420,96 +2101,234 @@ impl UI {
     }
 
     /// Draw the move history panel
-    fn draw_move_history(f: &mut Frame, area: Rect, game: &Game, _config: &LayoutConfig) {
-        let moves = game.get_notated_moves();
-        let mut move_lines: Vec<Line> = vec![
-            Line::from(vec![Span::styled(
-                " 着法记录 History ",
-                Style::default().fg(C_ACCENT).add_modifier(Modifier::BOLD),
-            )]),
-            Line::from(""),
-        ];
+    ///
+    /// Lists the whole game in the panel's notation, one full move per row
+    /// so Red and Black pair up, scrolled to the latest move unless scrolled
+    /// back. While reviewing, the move that led to the position on the
+    /// board is marked and later moves are dimmed.
+    fn draw_move_history(f: &mut Frame, area: Rect, moves: MoveList) {
+        let state = moves.state;
+        let notated = moves.game.move_notations(state.style);
+        let total_rows = HistoryBrowserState::total_rows(notated.len());
+
+        // The comment on the marked move, or else on the latest one, goes
+        // under the list after a blank row
+        let comment = moves
+            .current
+            .unwrap_or(notated.len())
+            .checked_sub(1)
+            .and_then(|i| moves.game.comment(i));
+        let inner_width = area.width.saturating_sub(2).max(1) as usize;
+        let comment_rows = comment.map_or(0, |text| {
+            Span::raw(text)
+                .width()
+                .div_ceil(inner_width)
+                .clamp(1, MAX_COMMENT_ROWS)
+        });
+        let comment_rows = if area.height as usize > comment_rows + 6 {
+            comment_rows
+        } else {
+            0
+        };
 
-        // Show recent moves with numbering
-        let recent_moves: Vec<(usize, String)> = moves
-            .iter()
-            .enumerate()
-            .rev()
-            .take(15)
-            .map(|(i, (piece, mv))| {
-                let notation = move_to_simple_notation(*piece, mv.from, mv.to);
-                (i + 1, notation)
-            })
-            .collect();
+        // Borders take two rows and the heading two more
+        state.page_rows = (area.height as usize)
+            .saturating_sub(4 + comment_rows + usize::from(comment_rows > 0))
+            .max(1);
+        let top = match (state.top, moves.current) {
+            // Keep the reviewed move in view
+            (None, Some(ply)) => {
+                let row = ply.saturating_sub(1) / 2;
+                row.saturating_sub(state.page_rows / 2)
+                    .min(state.max_top(total_rows))
+            }
+            _ => state.first_row(total_rows),
+        };
+
+        let mut heading = vec![Span::styled(
+            format!(" {} ", tr(Text::History)),
+            Style::default()
+                .fg(theme().accent)
+                .add_modifier(Modifier::BOLD),
+        )];
+        if total_rows > state.page_rows {
+            heading.push(Span::styled(
+                format!(
+                    "{}-{}/{}",
+                    top + 1,
+                    (top + state.page_rows).min(total_rows),
+                    total_rows
+                ),
+                Style::default().fg(theme().grid),
+            ));
+        }
+        let mut move_lines: Vec<Line> = vec![Line::from(heading), Line::from("")];
+
+        // Timed games get a column with each move's thinking time
+        let timed = (0..notated.len()).any(|i| moves.game.move_time(i).is_some());
+        for row in top..(top + state.page_rows).min(total_rows) {
+            let mut spans = vec![Span::styled(
+                format!("{:>3}. ", row + 1),
+                Style::default().fg(theme().secondary),
+            )];
+            for (i, color) in [
+                (row * 2, theme().red_piece),
+                (row * 2 + 1, theme().black_piece),
+            ] {
+                let Some(notation) = notated.get(i) else {
+                    continue;
+                };
+                let style = match moves.current {
+                    Some(ply) if i + 1 == ply => Style::default()
+                        .fg(color)
+                        .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+                    Some(ply) if i >= ply => Style::default().fg(theme().grid),
+                    _ => Style::default().fg(color),
+                };
+                spans.push(Span::styled(notation.as_str(), style));
+                if timed {
+                    let time = moves.game.move_time(i).map_or(String::new(), move_time);
+                    spans.push(Span::styled(
+                        format!("{:>5}", time),
+                        Style::default().fg(theme().grid),
+                    ));
+                }
+                if moves.game.comment(i).is_some() {
+                    spans.push(Span::styled("*", Style::default().fg(theme().accent)));
+                }
+                if !moves.game.variations(i).is_empty() {
+                    spans.push(Span::styled("~", Style::default().fg(theme().accent)));
+                }
+                spans.push(Span::raw(" "));
+            }
+            move_lines.push(Line::from(spans));
+        }
 
-        if recent_moves.is_empty() {
+        if total_rows == 0 {
             move_lines.push(Line::from(vec![Span::styled(
-                "  暂无着法",
-                Style::default().fg(C_GRID),
+                format!("  {}", tr(Text::NoMoves)),
+                Style::default().fg(theme().grid),
             )]));
-        } else {
-            for (num, notation) in recent_moves.into_iter().rev() {
-                let color = if num % 2 == 1 {
-                    C_RED_PIECE // Red moves first (odd numbers)
-                } else {
-                    C_BLACK_PIECE
-                };
-                move_lines.push(Line::from(vec![
-                    Span::styled(format!("{:2}. ", num), Style::default().fg(C_SECONDARY)),
-                    Span::styled(notation, Style::default().fg(color)),
-                ]));
-            }
         }
 
+        let (border, title) = if state.focused {
+            (theme().accent, tr(Text::HistoryScroll))
+        } else {
+            (theme().secondary, "")
+        };
         f.render_widget(
             Paragraph::new(move_lines)
                 .block(
                     Block::default()
                         .borders(BORDER_ALL)
-                        .border_style(Style::default().fg(C_SECONDARY)),
+                        .border_style(Style::default().fg(border))
+                        .title_bottom(Span::styled(title, Style::default().fg(theme().grid)))
+                        .title_bottom(
+                            Line::from(Span::styled(
+                                format!(" {} ", state.style.name()),
+                                Style::default().fg(theme().grid),
+                            ))
+                            .right_aligned(),
+                        ),
                 )
                 .alignment(Alignment::Left),
             area,
         );
+
+        if let (Some(text), true) = (comment, comment_rows > 0) {
+            let comment_area = Rect {
+                x: area.x + 1,
+                y: area.y + area.height - 1 - comment_rows as u16,
+                width: area.width.saturating_sub(2),
+                height: comment_rows as u16,
+            };
+            f.render_widget(
+                Paragraph::new(Span::styled(
+                    text,
+                    Style::default()
+                        .fg(theme().accent)
+                        .add_modifier(Modifier::ITALIC),
+                ))
+                .wrap(Wrap { trim: true }),
+                comment_area,
+            );
+        }
     }
 
     /// Draw the game info panel
     fn draw_game_info(f: &mut Frame, area: Rect, game: &Game, _config: &LayoutConfig) {
-        let turn = match game.turn() {
-            Color::Red => "● 红方",
-            Color::Black => "● 黑方",
-        };
+        let turn = format!("● {}", side_name(game.turn()));
         let turn_color = match game.turn() {
-            Color::Red => C_RED_PIECE,
-            Color::Black => C_BLACK_PIECE,
+            Color::Red => theme().red_piece,
+            Color::Black => theme().black_piece,
         };
 
         let check_indicator = if game.is_in_check() {
-            "将军!"
+            tr(Text::Check)
         } else {
-            "正常"
+            tr(Text::NotInCheck)
         };
 
         let (state_text, state_color) = match game.state() {
-            GameState::Playing => ("进行中", C_PRIMARY),
+            GameState::Playing if game.free_turn() => (Text::FreeAnalysis, theme().accent),
+            GameState::Playing => (Text::Playing, theme().primary),
             GameState::Checkmate(c) => {
                 if c == Color::Red {
-                    ("红胜!", C_RED_PIECE)
+                    (Text::RedWinsShort, theme().red_piece)
                 } else {
-                    ("黑胜!", C_BLACK_PIECE)
+                    (Text::BlackWinsShort, theme().black_piece)
                 }
             }
-            GameState::Stalemate => ("和棋", C_GOLD),
+            GameState::Stalemate => (Text::DrawShort, theme().gold),
+            GameState::StalemateWin(Color::Red) => (Text::StalemateRedWins, theme().red_piece),
+            GameState::StalemateWin(Color::Black) => {
+                (Text::StalemateBlackWins, theme().black_piece)
+            }
+            GameState::DrawByRepetition => (Text::RepetitionDraw, theme().gold),
+            GameState::DrawByMoveLimit => (Text::MoveLimitDraw, theme().gold),
+            GameState::MissingGeneral(Color::Red) => (Text::NoRedGeneral, theme().black_piece),
+            GameState::MissingGeneral(Color::Black) => (Text::NoBlackGeneral, theme().red_piece),
+            GameState::PerpetualCheck(Color::Red) => (Text::PerpetualRedWins, theme().red_piece),
+            GameState::PerpetualCheck(Color::Black) => {
+                (Text::PerpetualBlackWins, theme().black_piece)
+            }
+            GameState::Timeout(Color::Red) => (Text::TimeoutRedWins, theme().red_piece),
+            GameState::Timeout(Color::Black) => (Text::TimeoutBlackWins, theme().black_piece),
+            GameState::Adjudicated(Some(Color::Red)) => {
+                (Text::AdjudicatedRedWins, theme().red_piece)
+            }
+            GameState::Adjudicated(Some(Color::Black)) => {
+                (Text::AdjudicatedBlackWins, theme().black_piece)
+            }
+            GameState::Adjudicated(None) => (Text::AdjudicatedDrawShort, theme().gold),
+        };
+        let state_text = tr(state_text);
+
+        // The opening fills the gap under the move count once it is known
+        let opening = match OpeningBook::builtin().identify(game) {
+            Some(opening) => Line::from(vec![
+                Span::styled(
+                    tr(Text::OpeningLabel),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(opening.name, Style::default().fg(theme().accent)),
+            ]),
+            None => Line::from(""),
         };
 
         let lines = vec![
             Line::from(vec![Span::styled(
-                " 游戏信息 Info ",
-                Style::default().fg(C_ACCENT).add_modifier(Modifier::BOLD),
+                format!(" {} ", tr(Text::GameInfoTitle)),
+                Style::default()
+                    .fg(theme().accent)
+                    .add_modifier(Modifier::BOLD),
             )]),
             Line::from(""),
             Line::from(vec![
-                Span::styled("当前回合:", Style::default().fg(C_SECONDARY)),
+                Span::styled(
+                    tr(Text::CurrentTurnLabel),
+                    Style::default().fg(theme().secondary),
+                ),
                 Span::styled(
                     turn,
                     Style::default().fg(turn_color).add_modifier(Modifier::BOLD),
@@ -517,23 +2336,30 @@ impl UI {
             ]),
             Line::from(""),
             Line::from(vec![
-                Span::styled("总步数:", Style::default().fg(C_SECONDARY)),
+                Span::styled(
+                    tr(Text::TotalMovesLabel),
+                    Style::default().fg(theme().secondary),
+                ),
                 Span::styled(
                     format!(" {}", game.get_moves().len()),
-                    Style::default().fg(C_GOLD).add_modifier(Modifier::BOLD),
+                    Style::default()
+                        .fg(theme().gold)
+                        .add_modifier(Modifier::BOLD),
                 ),
             ]),
-            Line::from(""),
+            opening,
             Line::from(vec![
-                Span::styled("将军状态:", Style::default().fg(C_SECONDARY)),
+                Span::styled(tr(Text::CheckLabel), Style::default().fg(theme().secondary)),
                 Span::styled(
                     check_indicator,
-                    Style::default().fg(C_CHECK).add_modifier(Modifier::BOLD),
+                    Style::default()
+                        .fg(theme().check)
+                        .add_modifier(Modifier::BOLD),
                 ),
             ]),
             Line::from(""),
             Line::from(vec![
-                Span::styled("游戏状态:", Style::default().fg(C_SECONDARY)),
+                Span::styled(tr(Text::StateLabel), Style::default().fg(theme().secondary)),
                 Span::styled(
                     state_text,
                     Style::default()
@@ -541,6 +2367,26 @@ impl UI {
                         .add_modifier(Modifier::BOLD),
                 ),
             ]),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(
+                    tr(Text::MobilityLabel),
+                    Style::default().fg(theme().secondary),
+                ),
+                Span::styled(
+                    format!(" {}", game.legal_move_count(Color::Red)),
+                    Style::default()
+                        .fg(theme().red_piece)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" / ", Style::default().fg(theme().secondary)),
+                Span::styled(
+                    format!("{}", game.legal_move_count(Color::Black)),
+                    Style::default()
+                        .fg(theme().black_piece)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            ]),
         ];
 
         f.render_widget(
@@ -548,385 +2394,694 @@ impl UI {
                 .block(
                     Block::default()
                         .borders(BORDER_ALL)
-                        .border_style(Style::default().fg(C_SECONDARY)),
+                        .border_style(Style::default().fg(theme().secondary)),
                 )
                 .alignment(Alignment::Left),
             area,
         );
     }
 
-    fn draw_grid(f: &mut Frame, area: Rect, config: &LayoutConfig) {
-        let grid_style = Style::default().fg(C_GRID);
-        let corner_style = Style::default().fg(C_SECONDARY);
+    pub fn draw_game_over_popup(f: &mut Frame, area: Rect, game: &Game, config: &LayoutConfig) {
+        let popup_area = Self::centered_rect(config.popup_width, config.popup_height, area);
 
-        // Calculate how many rows and cols fit in the available area
-        let max_rows = (area.height / config.cell_height).min(BOARD_ROWS as u16) as usize;
-        let max_cols = (area.width / config.cell_width).min(BOARD_COLS as u16) as usize;
+        let (headline, subtitle, color) = match game.state().result() {
+            Some(GameResult::RedWins) => (Text::RedWins, Text::RedWinsSubtitle, theme().red_piece),
+            Some(GameResult::BlackWins) => (
+                Text::BlackWins,
+                Text::BlackWinsSubtitle,
+                theme().black_piece,
+            ),
+            Some(GameResult::Draw) => (Text::Draw, Text::DrawSubtitle, theme().gold),
+            None => return,
+        };
+        let (headline, subtitle) = (tr(headline), tr(subtitle));
+
+        let key_style = Style::default()
+            .fg(theme().primary)
+            .add_modifier(Modifier::BOLD);
+        // A configured key that lands on a fixed shortcut wins, and the
+        // shortcut is not offered
+        let shortcuts = |items: Vec<(char, Text)>| {
+            let mut spans = Vec::new();
+            for (i, (key, label)) in items.into_iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(" "));
+                }
+                spans.push(Span::styled(format!(" {} ", key), key_style));
+                spans.push(Span::raw(tr(label)));
+            }
+            Line::from(spans)
+        };
+        let unbound = |&(key, _): &(char, Text)| !keys().is_bound(key);
 
-        for y in 0..max_rows {
-            for x in 0..max_cols {
-                let (px, py) = config.cell_pos(x, y);
-                let px = area.x + px;
-                let py = area.y + py;
+        let board = game.board();
+        let material = trf(
+            Text::Material,
+            &[&material(board, Color::Red), &material(board, Color::Black)],
+        );
 
-                // Skip if this position is outside the area bounds
-                if px >= area.x + area.width || py >= area.y + area.height {
-                    continue;
-                }
+        let lines = vec![
+            Line::from(vec![Span::styled(
+                headline,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(vec![Span::styled(
+                subtitle,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(Span::styled(
+                termination_reason(game),
+                Style::default().fg(theme().secondary),
+            )),
+            Line::from(Span::styled(material, Style::default().fg(theme().gold))),
+            Line::from(""),
+            shortcuts(
+                [(keys().quit, Text::Quit), (keys().restart, Text::Restart)]
+                    .into_iter()
+                    .chain([('n', Text::Rematch)].into_iter().filter(unbound))
+                    .collect(),
+            ),
+            shortcuts(
+                [
+                    ('p', Text::SavePgn),
+                    ('f', Text::CopyFen),
+                    ('v', Text::Review),
+                ]
+                .into_iter()
+                .filter(unbound)
+                .collect(),
+            ),
+        ];
 
-                let (c, is_corner) = if x == 0 && y == 0 {
-                    ("┌", true)
-                } else if x == max_cols - 1 && y == 0 && max_cols == BOARD_COLS {
-                    ("┐", true)
-                } else if x == 0 && y == max_rows - 1 && max_rows == BOARD_ROWS {
-                    ("└", true)
-                } else if x == max_cols - 1
-                    && y == max_rows - 1
-                    && max_cols == BOARD_COLS
-                    && max_rows == BOARD_ROWS
-                {
-                    ("┘", true)
-                } else if x == 0 {
-                    ("├", false)
-                } else if x == max_cols - 1 && max_cols == BOARD_COLS {
-                    ("┤", false)
-                } else if y == 0 {
-                    ("┬", false)
-                } else if y == max_rows - 1 && max_rows == BOARD_ROWS {
-                    ("┴", false)
-                } else {
-                    ("┼", false)
-                };
+        f.render_widget(Clear, popup_area);
+        f.render_widget(
+            Paragraph::new(lines)
+                .block(
+                    Block::default()
+                        .borders(BORDER_ALL)
+                        .border_style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                )
+                .alignment(Alignment::Center),
+            popup_area,
+        );
+    }
 
-                let style = if is_corner { corner_style } else { grid_style };
-                f.render_widget(
-                    Paragraph::new(Span::styled(c, style)),
-                    Rect {
-                        x: px,
-                        y: py,
-                        width: 1,
-                        height: 1,
-                    },
-                );
-
-                // Horizontal lines
-                if x < max_cols - 1 && config.cell_width > 1 {
-                    for i in 1..config.cell_width {
-                        let hx = px + i;
-                        f.render_widget(
-                            Paragraph::new(Span::styled("─", grid_style)),
-                            Rect {
-                                x: hx,
-                                y: py,
-                                width: 1,
-                                height: 1,
-                            },
-                        );
-                    }
-                }
+    fn centered_rect(width: u16, height: u16, r: Rect) -> Rect {
+        let patch_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length((r.height.saturating_sub(height)) / 2),
+                Constraint::Length(height),
+                Constraint::Length((r.height.saturating_sub(height)) / 2),
+            ])
+            .split(r);
+
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length((r.width.saturating_sub(width)) / 2),
+                Constraint::Length(width),
+                Constraint::Length((r.width.saturating_sub(width)) / 2),
+            ])
+            .split(patch_layout[1])[1]
+    }
+
+    /// Draw AI mode selection menu overlay
+    pub fn draw_ai_menu(
+        f: &mut Frame,
+        current_mode: AiMode,
+        _show_thinking: bool,
+        menu_state: &AiMenuState,
+    ) {
+        let size = f.area();
+        let width = 35;
+        let height = 12;
+        let menu_area = Self::centered_rect(width, height, size);
+
+        let options: [(&str, AiMode); 4] = [
+            (tr(Text::AiOff), AiMode::Off),
+            (tr(Text::AiPlaysBlack), AiMode::PlaysBlack),
+            (tr(Text::AiPlaysRed), AiMode::PlaysRed),
+            (tr(Text::AiPlaysBoth), AiMode::PlaysBoth),
+        ];
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                tr(Text::AiMenuTitle),
+                Style::default()
+                    .fg(theme().accent)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+        ];
+
+        let row_style = |row: usize| {
+            if menu_state.selected == row {
+                Style::default()
+                    .fg(theme().primary)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme().secondary)
             }
+        };
 
-            // Vertical lines (skip river area)
-            if y < max_rows - 1 {
-                for x in 0..max_cols {
-                    let (px, py) = config.cell_pos(x, y);
-                    let px = area.x + px;
-                    let py = area.y + py + 1;
+        for (i, (text, mode)) in options.iter().enumerate() {
+            let prefix = if *mode == current_mode {
+                "[*] "
+            } else {
+                "[ ] "
+            };
+            lines.push(Line::from(Span::styled(
+                format!("{}{}", prefix, text),
+                row_style(i),
+            )));
+        }
 
-                    if y == 4 {
-                        continue;
-                    } // Skip river
+        let difficulty = UI::difficulty_name(menu_state.difficulty);
 
-                    // Skip if this position is outside the area bounds
-                    if px >= area.x + area.width || py >= area.y + area.height {
-                        continue;
-                    }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{}{}",
+                if menu_state.show_thinking {
+                    "[*] "
+                } else {
+                    "[ ] "
+                },
+                tr(Text::ShowThinking)
+            ),
+            row_style(4),
+        )));
+        lines.push(Line::from(Span::styled(
+            trf(Text::DifficultyField, &[&difficulty]),
+            row_style(5),
+        )));
+        lines.push(Line::from(""));
+        lines.push(Line::from(tr(Text::MenuKeys)));
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(BORDER_ALL)
+                    .border_style(Style::default().fg(theme().primary))
+                    .style(Style::default().bg(theme().popup_bg)),
+            )
+            .alignment(Alignment::Left);
+
+        f.render_widget(Clear, menu_area);
+        f.render_widget(paragraph, menu_area);
+    }
 
-                    f.render_widget(
-                        Paragraph::new(Span::styled("│", grid_style)),
-                        Rect {
-                            x: px,
-                            y: py,
-                            width: 1,
-                            height: 1,
-                        },
-                    );
+    /// Draw the full-screen move history browser
+    ///
+    /// Only the visible page is formatted, so very long games stay cheap to
+    /// draw.
+    pub fn draw_history_browser(f: &mut Frame, game: &Game, state: &mut HistoryBrowserState) {
+        let area = f.area();
+        let move_count = game.move_count();
+        let total_rows = HistoryBrowserState::total_rows(move_count);
+
+        // Borders take two rows and the key help one more
+        state.page_rows = area.height.saturating_sub(3).max(1) as usize;
+        state.top = state.top.min(state.max_top(total_rows));
+
+        let page = state.top / state.page_rows + 1;
+        let pages = total_rows.div_ceil(state.page_rows).max(1);
+        let title = trf(Text::HistoryBrowserTitle, &[&move_count, &page, &pages]);
+
+        let mut lines: Vec<Line> = Vec::with_capacity(state.page_rows + 1);
+        for row in state.top..(state.top + state.page_rows).min(total_rows) {
+            let mut spans = vec![Span::styled(
+                format!("{:>4}. ", row + 1),
+                Style::default().fg(theme().secondary),
+            )];
+            for (i, color) in [
+                (row * 2, theme().red_piece),
+                (row * 2 + 1, theme().black_piece),
+            ] {
+                if let Some((piece, mv)) = game.notated_move(i) {
+                    let notation = move_to_simple_notation(piece, mv.from, mv.to);
+                    spans.push(Span::styled(
+                        format!("{:<14}", notation),
+                        Style::default().fg(color),
+                    ));
                 }
             }
+            lines.push(Line::from(spans));
         }
-    }
 
-    fn draw_river(f: &mut Frame, area: Rect, config: &LayoutConfig) {
-        let river_y = area.y + config.cell_height * 5 - 1;
+        if total_rows == 0 {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", tr(Text::NoMoves)),
+                Style::default().fg(theme().grid),
+            )));
+        }
 
-        // Skip if river is outside area bounds
-        if river_y >= area.y + area.height {
-            return;
+        while lines.len() < state.page_rows {
+            lines.push(Line::from(""));
         }
+        lines.push(Line::from(Span::styled(
+            tr(Text::HistoryBrowserKeys),
+            Style::default().fg(theme().grid),
+        )));
 
-        let chu = " 楚河";
-        let han = "汉界";
+        let paragraph = Paragraph::new(lines).block(
+            Block::default()
+                .borders(BORDER_ALL)
+                .border_style(Style::default().fg(theme().primary))
+                .title(Span::styled(
+                    title,
+                    Style::default()
+                        .fg(theme().accent)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .style(Style::default().bg(theme().popup_bg)),
+        );
 
-        let river_style = Style::default().fg(C_RIVER).add_modifier(Modifier::BOLD);
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
 
-        let left_w = (6 * config.cell_width).min(area.width);
-        let right_w = (6 * config.cell_width).min(area.width);
+    /// Draw the full-screen stats: the rating, the results and streaks,
+    /// and the results per opponent and per opening
+    pub fn draw_stats(f: &mut Frame, stats: &Stats) {
+        let area = f.area();
+        let label = Style::default().fg(theme().secondary);
+        let heading = Style::default()
+            .fg(theme().accent)
+            .add_modifier(Modifier::BOLD);
+
+        let mut lines: Vec<Line> = Vec::new();
+        match stats.current_streak() {
+            None => lines.push(Line::from(Span::styled(
+                format!("  {}", tr(Text::NoStats)),
+                Style::default().fg(theme().grid),
+            ))),
+            Some((outcome, length)) => {
+                let total = stats.total();
+                let outcome = tr(match outcome {
+                    Outcome::Win => Text::StatsWin,
+                    Outcome::Loss => Text::StatsLoss,
+                    Outcome::Draw => Text::StatsDraw,
+                });
+                for text in [
+                    trf(Text::StatsRating, &[&stats.rating, &stats.peak_rating()]),
+                    trf(Text::StatsTotal, &[&total.games(), &total]),
+                    trf(
+                        Text::StatsStreak,
+                        &[&outcome, &length, &stats.longest_winning_streak()],
+                    ),
+                ] {
+                    lines.push(Line::from(Span::styled(format!(" {}", text), label)));
+                }
+                for (title, tallies) in [
+                    (Text::StatsOpponents, stats.by_opponent()),
+                    (Text::StatsOpenings, stats.by_opening()),
+                ] {
+                    if tallies.is_empty() {
+                        continue;
+                    }
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(format!(" {}", tr(title)), heading)));
+                    for (name, tally) in tallies {
+                        lines.push(Line::from(vec![
+                            Span::styled(format!("   {:<18}", tally.to_string()), label),
+                            Span::styled(name, Style::default().fg(theme().primary)),
+                        ]));
+                    }
+                }
+            }
+        }
 
-        f.render_widget(
-            Paragraph::new(chu)
-                .style(river_style)
-                .alignment(Alignment::Left),
-            Rect {
-                x: area.x,
-                y: river_y,
-                width: left_w,
-                height: 1,
-            },
-        );
+        // Borders take two rows and the key help one more
+        let rows = area.height.saturating_sub(3) as usize;
+        lines.truncate(rows);
+        while lines.len() < rows {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            tr(Text::StatsKeys),
+            Style::default().fg(theme().grid),
+        )));
 
-        f.render_widget(
-            Paragraph::new(han)
-                .style(river_style)
-                .alignment(Alignment::Right),
-            Rect {
-                x: area.x + (BOARD_COLS as u16) * config.cell_width - right_w,
-                y: river_y,
-                width: right_w,
-                height: 1,
-            },
+        let paragraph = Paragraph::new(lines).block(
+            Block::default()
+                .borders(BORDER_ALL)
+                .border_style(Style::default().fg(theme().primary))
+                .title(Span::styled(tr(Text::StatsTitle), heading))
+                .style(Style::default().bg(theme().popup_bg)),
         );
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
     }
 
-    fn draw_pieces(f: &mut Frame, area: Rect, game: &Game, config: &LayoutConfig) {
-        let max_rows = (area.height / config.cell_height).min(BOARD_ROWS as u16) as usize;
-        let max_cols = (area.width / config.cell_width).min(BOARD_COLS as u16) as usize;
+    /// Draw the full-screen game picker for multi-game PGN files
+    pub fn draw_game_picker(f: &mut Frame, state: &mut GamePickerState) {
+        let area = f.area();
+        // Borders take two rows and the key help one more
+        let page_rows = area.height.saturating_sub(3).max(1) as usize;
+        state.page_rows = page_rows;
+        let top = (state.selected / page_rows) * page_rows;
+        let title = trf(Text::SelectGameTitle, &[&state.source, &state.games.len()]);
+
+        let mut lines: Vec<Line> = Vec::with_capacity(page_rows + 1);
+        for (i, game) in state.games.iter().enumerate().skip(top).take(page_rows) {
+            let is_selected = i == state.selected;
+            let style = if is_selected {
+                Style::default()
+                    .fg(theme().primary)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme().secondary)
+            };
+            let prefix = if is_selected { ">" } else { " " };
 
-        for (pos, piece) in game.board().pieces() {
-            // Skip pieces outside the visible grid
-            if pos.x >= max_cols || pos.y >= max_rows {
-                continue;
+            let mut summary = FilePreview::from_pgn(game).summary();
+            if let Some(event) = game.get_tag("Event").filter(|e| !e.is_empty() && *e != "?") {
+                summary = if summary.is_empty() {
+                    event.clone()
+                } else {
+                    format!("{}: {}", event, summary)
+                };
             }
 
-            let (px, py) = config.cell_pos(pos.x, pos.y);
-            let px = area.x + px;
-            let py = area.y + py;
-
-            // Skip if this position is outside the area bounds
-            if px >= area.x + area.width || py >= area.y + area.height {
-                continue;
-            }
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{}{:>4}. {} ({} moves)",
+                    prefix,
+                    i + 1,
+                    summary,
+                    game.moves.len()
+                ),
+                style,
+            )));
+        }
 
-            let fg = match piece.color {
-                Color::Red => C_RED_PIECE,
-                Color::Black => C_BLACK_PIECE,
-            };
+        while lines.len() < page_rows {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            tr(Text::SelectGameKeys),
+            Style::default().fg(theme().grid),
+        )));
 
-            let piece_text = piece.to_string();
-            let piece_width = config.cell_width.min(3);
+        let (list_area, preview_area) = Self::split_preview(area);
+        let paragraph = Paragraph::new(lines).block(
+            Block::default()
+                .borders(BORDER_ALL)
+                .border_style(Style::default().fg(theme().primary))
+                .title(Span::styled(
+                    title,
+                    Style::default()
+                        .fg(theme().accent)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .style(Style::default().bg(theme().popup_bg)),
+        );
 
-            f.render_widget(
-                Paragraph::new(piece_text)
-                    .style(Style::default().fg(fg).add_modifier(Modifier::BOLD))
-                    .alignment(Alignment::Center),
-                Rect {
-                    x: px,
-                    y: py,
-                    width: piece_width,
-                    height: 1,
-                },
-            );
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, list_area);
+        if let (Some(area), Some(game)) = (preview_area, state.selected_game()) {
+            Self::draw_final_position(f, area, game);
         }
     }
 
-    fn draw_cursor_highlight(f: &mut Frame, inner: Rect, cursor: Position, config: &LayoutConfig) {
-        let (px, py) = config.cell_pos(cursor.x, cursor.y);
-        let px = inner.x + px;
-        let py = inner.y + py;
-        let w = config.cell_width.min(3);
+    /// Draw the game library: saved games with when they were played,
+    /// players, result and length
+    pub fn draw_library(f: &mut Frame, state: &mut LibraryState) {
+        let area = f.area();
+        // Borders take two rows and the key help one more
+        let page_rows = area.height.saturating_sub(3).max(1) as usize;
+        state.page_rows = page_rows;
+        let top = (state.selected / page_rows) * page_rows;
+        let title = trf(Text::LibraryTitle, &[&state.dir, &state.entries.len()]);
+
+        let mut lines: Vec<Line> = Vec::with_capacity(page_rows + 1);
+        if state.entries.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "No saved games yet; set auto_save = true in config.toml to keep finished games",
+                Style::default().fg(theme().secondary),
+            )));
+        }
+        for (i, entry) in state.entries.iter().enumerate().skip(top).take(page_rows) {
+            let is_selected = i == state.selected;
+            let style = if is_selected {
+                Style::default()
+                    .fg(theme().primary)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme().secondary)
+            };
+            let prefix = if is_selected { ">" } else { " " };
 
-        // Skip if outside area bounds
-        if px >= inner.x + inner.width || py >= inner.y + inner.height {
-            return;
+            let preview = FilePreview {
+                date: None,
+                ..FilePreview::from_pgn(&entry.game)
+            };
+            let mut summary = preview.summary();
+            if let Some(event) = entry
+                .game
+                .get_tag("Event")
+                .filter(|e| !e.is_empty() && *e != "?")
+            {
+                summary = if summary.is_empty() {
+                    event.clone()
+                } else {
+                    format!("{}: {}", event, summary)
+                };
+            }
+            let saved_at = entry.saved_at().unwrap_or_else(|| {
+                entry
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{}{}  {} ({} moves)",
+                    prefix,
+                    saved_at,
+                    summary,
+                    entry.game.moves.len()
+                ),
+                style,
+            )));
         }
 
-        f.render_widget(
+        while lines.len() < page_rows {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            tr(Text::LibraryKeys),
+            Style::default().fg(theme().grid),
+        )));
+
+        let (list_area, preview_area) = Self::split_preview(area);
+        let paragraph = Paragraph::new(lines).block(
             Block::default()
                 .borders(BORDER_ALL)
-                .border_style(Style::default().fg(C_CURSOR).add_modifier(Modifier::BOLD)),
-            Rect {
-                x: px,
-                y: py,
-                width: w,
-                height: 1,
-            },
+                .border_style(Style::default().fg(theme().primary))
+                .title(Span::styled(
+                    title,
+                    Style::default()
+                        .fg(theme().accent)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .style(Style::default().bg(theme().popup_bg)),
         );
-    }
 
-    fn draw_selection_highlight(
-        f: &mut Frame,
-        inner: Rect,
-        selected: Position,
-        config: &LayoutConfig,
-    ) {
-        let (px, py) = config.cell_pos(selected.x, selected.y);
-        let px = inner.x + px;
-        let py = inner.y + py;
-        let w = config.cell_width.min(3);
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, list_area);
+        if let (Some(area), Some(entry)) = (preview_area, state.selected_entry()) {
+            Self::draw_final_position(f, area, &entry.game);
+        }
+    }
 
-        // Skip if outside area bounds
-        if px >= inner.x + inner.width || py >= inner.y + inner.height {
-            return;
+    /// Split a game list's area into the list and, if there is room, a
+    /// pane on the right for a preview of the selected game
+    fn split_preview(area: Rect) -> (Rect, Option<Rect>) {
+        // Nine two-column points, a column of padding each side and borders
+        const PREVIEW_WIDTH: u16 = 22;
+        const PREVIEW_HEIGHT: u16 = 14;
+        if area.width < PREVIEW_WIDTH + 40 || area.height < PREVIEW_HEIGHT {
+            return (area, None);
         }
+        let [list, preview] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(40), Constraint::Length(PREVIEW_WIDTH)])
+            .areas(area);
+        (
+            list,
+            Some(Rect::new(
+                preview.x,
+                preview.y,
+                preview.width,
+                PREVIEW_HEIGHT,
+            )),
+        )
+    }
 
-        f.render_widget(
-            Paragraph::new("")
-                .block(
-                    Block::default().borders(BORDER_ALL).border_style(
-                        Style::default()
-                            .fg(C_SELECTION)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                )
-                .style(Style::default().bg(C_SELECTION_BG)),
-            Rect {
-                x: px,
-                y: py,
-                width: w,
-                height: 1,
-            },
+    /// Draw a small board with the position a game ends in
+    fn draw_final_position(f: &mut Frame, area: Rect, game: &PgnGame) {
+        let style = Self::piece_style();
+        let mut lines: Vec<Line> = match import_pgn(game, ImportMode::Lenient) {
+            Ok(report) => board_thumbnail(report.game.board(), 2, |piece| style.text(piece))
+                .into_iter()
+                .map(|row| {
+                    let spans: Vec<Span> = row
+                        .into_iter()
+                        .map(|cell| {
+                            let fg = match cell.color {
+                                Some(Color::Red) => theme().red_piece,
+                                Some(Color::Black) => theme().black_piece,
+                                None => theme().grid,
+                            };
+                            Span::styled(cell.text, Style::default().fg(fg))
+                        })
+                        .collect();
+                    Line::from(spans)
+                })
+                .collect(),
+            Err(_) => vec![Line::from(Span::styled(
+                tr(Text::NoPreview),
+                Style::default().fg(theme().secondary),
+            ))],
+        };
+        lines.insert(0, Line::from(""));
+
+        let paragraph = Paragraph::new(lines).alignment(Alignment::Center).block(
+            Block::default()
+                .borders(BORDER_ALL)
+                .border_style(Style::default().fg(theme().primary))
+                .title(Span::styled(
+                    format!(" {} ", tr(Text::FinalTitle)),
+                    Style::default().fg(theme().accent),
+                ))
+                .style(Style::default().bg(theme().popup_bg)),
         );
+        f.render_widget(paragraph, area);
     }
 
-    pub fn draw_game_over_popup(
-        f: &mut Frame,
-        area: Rect,
-        state: GameState,
-        config: &LayoutConfig,
-    ) {
-        let popup_area = Self::centered_rect(config.popup_width, config.popup_height, area);
-
-        let (text, color) = match state {
-            GameState::Checkmate(Color::Red) => ("★ 红方胜利!\nRed Wins!", C_RED_PIECE),
-            GameState::Checkmate(Color::Black) => ("★ 黑方胜利!\nBlack Wins!", C_BLACK_PIECE),
-            GameState::Stalemate => ("♦ 和棋!\nDraw", C_GOLD),
-            GameState::Playing => return,
-        };
+    /// Draw the new-game dialog: side, AI, difficulty, clock and handicap
+    pub fn draw_new_game_menu(f: &mut Frame, menu_state: &NewGameMenuState) {
+        let size = f.area();
+        let height = NewGameMenuState::FIELDS as u16 + 6;
+        let menu_area = Self::centered_rect(46, height, size);
 
-        let lines = vec![
-            Line::from(""),
-            Line::from(vec![Span::styled(
-                text,
-                Style::default().fg(color).add_modifier(Modifier::BOLD),
-            )]),
-            Line::from(""),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled(
-                    " q ",
-                    Style::default().fg(C_PRIMARY).add_modifier(Modifier::BOLD),
-                ),
-                Span::raw(": 退出游戏    "),
-                Span::styled(
-                    " r ",
-                    Style::default().fg(C_PRIMARY).add_modifier(Modifier::BOLD),
-                ),
-                Span::raw(": 重新开始"),
-            ]),
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!(" {} ", tr(Text::NewGameTitle)),
+                Style::default()
+                    .fg(theme().accent)
+                    .add_modifier(Modifier::BOLD),
+            )),
             Line::from(""),
         ];
 
-        f.render_widget(Clear, popup_area);
-        f.render_widget(
-            Paragraph::new(lines)
-                .block(
-                    Block::default()
-                        .borders(BORDER_ALL)
-                        .border_style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        for (i, (label, value)) in menu_state.fields().into_iter().enumerate() {
+            // The AI's settings don't apply when the AI is off
+            let unused = (i == 1 && menu_state.side == PlayerSide::Both)
+                || (i == 2 && menu_state.ai_mode() == AiMode::Off);
+            let (prefix, style) = if menu_state.selected == i {
+                (
+                    "> ",
+                    Style::default()
+                        .fg(theme().primary)
+                        .add_modifier(Modifier::BOLD),
                 )
-                .alignment(Alignment::Center),
-            popup_area,
-        );
-    }
+            } else if unused {
+                (
+                    "  ",
+                    Style::default()
+                        .fg(theme().secondary)
+                        .add_modifier(Modifier::DIM),
+                )
+            } else {
+                ("  ", Style::default().fg(theme().secondary))
+            };
+            lines.push(Line::from(Span::styled(
+                format!("{}{:<11}◀ {} ▶", prefix, format!("{}:", label), value),
+                style,
+            )));
+        }
 
-    fn centered_rect(width: u16, height: u16, r: Rect) -> Rect {
-        let patch_layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length((r.height.saturating_sub(height)) / 2),
-                Constraint::Length(height),
-                Constraint::Length((r.height.saturating_sub(height)) / 2),
-            ])
-            .split(r);
+        lines.push(Line::from(""));
+        lines.push(Line::from(tr(Text::NewGameKeys)));
 
-        Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length((r.width.saturating_sub(width)) / 2),
-                Constraint::Length(width),
-                Constraint::Length((r.width.saturating_sub(width)) / 2),
-            ])
-            .split(patch_layout[1])[1]
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(BORDER_ALL)
+                    .border_style(Style::default().fg(theme().primary))
+                    .style(Style::default().bg(theme().popup_bg)),
+            )
+            .alignment(Alignment::Left);
+
+        f.render_widget(Clear, menu_area);
+        f.render_widget(paragraph, menu_area);
     }
 
-    /// Draw AI mode selection menu overlay
-    pub fn draw_ai_menu(
-        f: &mut Frame,
-        current_mode: AiMode,
-        _show_thinking: bool,
-        menu_state: &AiMenuState,
-    ) {
+    /// Draw quick-open menu overlay listing recently opened files
+    pub fn draw_open_menu(f: &mut Frame, menu_state: &OpenMenuState) {
         let size = f.area();
-        let width = 35;
-        let height = 11;
+        let width = size.width.clamp(20, 70);
+        let height = (menu_state.entries.len() as u16 * 2 + 5).clamp(7, size.height.max(7));
         let menu_area = Self::centered_rect(width, height, size);
 
-        let options: [(&str, AiMode); 4] = [
-            ("Off (Player vs Player)", AiMode::Off),
-            ("AI plays Black", AiMode::PlaysBlack),
-            ("AI plays Red", AiMode::PlaysRed),
-            ("AI plays Both (spectate)", AiMode::PlaysBoth),
-        ];
-
         let mut lines = vec![
             Line::from(Span::styled(
-                " AI Mode Selection ",
-                Style::default().fg(C_ACCENT).add_modifier(Modifier::BOLD),
+                format!(" {} ", tr(Text::RecentFiles)),
+                Style::default()
+                    .fg(theme().accent)
+                    .add_modifier(Modifier::BOLD),
             )),
             Line::from(""),
         ];
 
-        for (i, (text, mode)) in options.iter().enumerate() {
-            let is_selected = menu_state.selected == i;
-            let is_current = *mode == current_mode;
+        if menu_state.entries.is_empty() {
+            lines.push(Line::from(Span::styled(
+                tr(Text::NoRecentFiles),
+                Style::default().fg(theme().secondary),
+            )));
+        }
 
-            let prefix = if is_current { "[*] " } else { "[ ] " };
+        for (i, (file, preview)) in menu_state.entries.iter().enumerate() {
+            let is_selected = menu_state.selected == i;
             let style = if is_selected {
-                Style::default().fg(C_PRIMARY).add_modifier(Modifier::BOLD)
+                Style::default()
+                    .fg(theme().primary)
+                    .add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(C_SECONDARY)
+                Style::default().fg(theme().secondary)
             };
+            let prefix = if is_selected { "> " } else { "  " };
+            let name = file
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| file.path.display().to_string());
 
             lines.push(Line::from(Span::styled(
-                format!("{}{}", prefix, text),
+                format!("{}[{}] {}", prefix, file.kind.label(), name),
                 style,
             )));
+            lines.push(Line::from(Span::styled(
+                format!("        {}", preview.summary()),
+                Style::default().fg(theme().grid),
+            )));
         }
 
         lines.push(Line::from(""));
-        lines.push(Line::from(if menu_state.show_thinking {
-            "[*] Show thinking output"
-        } else {
-            "[ ] Show thinking output"
-        }));
-        lines.push(Line::from(""));
-        lines.push(Line::from("[↑↓] Navigate  [Enter] Select  [Esc] Cancel"));
+        lines.push(Line::from(tr(Text::OpenKeys)));
 
         let paragraph = Paragraph::new(lines)
             .block(
                 Block::default()
                     .borders(BORDER_ALL)
-                    .border_style(Style::default().fg(C_PRIMARY))
-                    .style(Style::default().bg(RColor::Black)),
+                    .border_style(Style::default().fg(theme().primary))
+                    .style(Style::default().bg(theme().popup_bg)),
             )
             .alignment(Alignment::Left);
 
@@ -934,41 +3089,104 @@ impl UI {
         f.render_widget(paragraph, menu_area);
     }
 
-    /// Draw status bar showing AI mode and engine status
+    /// Whether a terminal of this size is big enough for the game screen
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::UI;
+    /// use ratatui::layout::Rect;
+    ///
+    /// assert!(UI::fits(Rect::new(0, 0, 80, 24)));
+    /// assert!(!UI::fits(Rect::new(0, 0, 80, 10)));
+    /// ```
+    pub fn fits(size: Rect) -> bool {
+        size.width >= MIN_TERMINAL_WIDTH && size.height >= MIN_TERMINAL_HEIGHT
+    }
+
+    /// Draw the screen shown instead of the game while the terminal is
+    /// smaller than [`MIN_TERMINAL_WIDTH`] by [`MIN_TERMINAL_HEIGHT`]
+    pub fn draw_too_small(f: &mut Frame) {
+        let area = f.area();
+        let lines = vec![
+            Line::from(Span::styled(
+                "Terminal too small",
+                Style::default()
+                    .fg(theme().check)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(format!(
+                "need {}x{}, have {}x{}",
+                MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT, area.width, area.height
+            )),
+            Line::from(Span::styled(
+                format!("{} quit", keys().quit),
+                Style::default().fg(theme().secondary),
+            )),
+        ];
+        let height = (lines.len() as u16).min(area.height);
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, Self::centered_rect(area.width, height, area));
+    }
+
+    /// Draw the accessible mode's description line in place of the status
+    /// bar
+    ///
+    /// The text is plain and left-aligned, without borders, so a screen
+    /// reader reads it as it is.
+    pub fn draw_description_line(f: &mut Frame, area: Rect, text: &str) {
+        let paragraph =
+            Paragraph::new(text.to_string()).style(Style::default().bg(theme().popup_bg));
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+
+    /// Draw status bar showing AI mode, engine status and, in a game with
+    /// a time control, the time left for Red and Black (`-` for a side
+    /// without a clock)
     pub fn draw_status_bar(
         f: &mut Frame,
         area: Rect,
         ai_mode: AiMode,
         engine_thinking: bool,
         engine_path: &Option<PathBuf>,
+        clocks: [Option<u64>; 2],
     ) {
-        let mode_text = match ai_mode {
-            AiMode::Off => "PvP".to_string(),
-            AiMode::PlaysRed => "AI(Red)".to_string(),
-            AiMode::PlaysBlack => "AI(Black)".to_string(),
-            AiMode::PlaysBoth => "AI vs AI".to_string(),
-        };
+        let mode_text = UI::mode_name(ai_mode);
 
         let engine_name = engine_path
             .as_ref()
             .and_then(|p| p.file_name())
             .and_then(|n| n.to_str())
-            .unwrap_or("No engine");
+            .unwrap_or(tr(Text::BuiltIn));
 
-        let status = if engine_thinking {
-            format!(
-                "Mode: {} | AI thinking... | Engine: {}",
-                mode_text, engine_name
-            )
+        let status_text = if engine_thinking {
+            Text::StatusThinking
         } else {
-            format!("Mode: {} | Engine: {}", mode_text, engine_name)
+            Text::Status
         };
+        let mut status = trf(status_text, &[&mode_text, &engine_name]);
+        if clocks.iter().any(Option::is_some) {
+            let clock = |ms: Option<u64>| match ms {
+                Some(ms) => {
+                    let secs = ms.div_ceil(1000);
+                    format!("{}:{:02}", secs / 60, secs % 60)
+                }
+                None => "-".to_string(),
+            };
+            status.push_str(&trf(
+                Text::StatusClocks,
+                &[&clock(clocks[0]), &clock(clocks[1])],
+            ));
+        }
 
         let paragraph = Paragraph::new(status)
             .block(
                 Block::default()
                     .borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM)
-                    .style(Style::default().bg(RColor::Black)),
+                    .style(Style::default().bg(theme().popup_bg)),
             )
             .alignment(Alignment::Center);
 
@@ -976,37 +3194,448 @@ impl UI {
     }
 
     /// Draw thinking information panel
+    /// Draw the AI's last search: depth, score and principal variation
+    ///
+    /// `line` is the variation already formatted for display (see
+    /// [`MoveFormatter`](crate::notation::format::MoveFormatter)).
     pub fn draw_thinking_panel(
         f: &mut Frame,
         area: Rect,
         thinking_enabled: bool,
         engine_thinking: bool,
+        info: Option<&Info>,
+        line: &[String],
+    ) {
+        let block = Block::default()
+            .borders(BORDER_ALL)
+            .border_style(Style::default().fg(theme().secondary))
+            .title(Span::styled(
+                format!(" {} ", tr(Text::ThinkingTitle)),
+                Style::default().fg(theme().primary),
+            ))
+            .style(Style::default().bg(theme().popup_bg));
+
+        let lines = if !thinking_enabled {
+            vec![Line::from(tr(Text::ThinkingDisabled))]
+        } else if engine_thinking {
+            vec![Line::from(tr(Text::EngineCalculating))]
+        } else if let Some(info) = info {
+            let mut stats = Vec::new();
+            if let Some(depth) = info.depth {
+                stats.push(trf(Text::Depth, &[&depth]));
+            }
+            if let Some(score) = info.score {
+                stats.push(trf(Text::Score, &[&format!("{:+}", score)]));
+            }
+            if let Some(nodes) = info.nodes {
+                stats.push(trf(Text::Nodes, &[&nodes]));
+            }
+            if stats.is_empty() {
+                stats.extend(info.message.clone());
+            }
+            vec![
+                Line::from(Span::styled(
+                    stats.join("  "),
+                    Style::default().fg(theme().gold),
+                )),
+                Line::from(vec![
+                    Span::styled(tr(Text::Pv), Style::default().fg(theme().secondary)),
+                    Span::styled(line.join("  "), Style::default().fg(theme().accent)),
+                ]),
+            ]
+        } else {
+            vec![Line::from(tr(Text::NoSearch))]
+        };
+
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(lines).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+    }
+
+    /// Draw the live engine analysis of the current position
+    ///
+    /// `line` is the principal variation formatted for display, and
+    /// `others` the engine's next best lines when it searches several.
+    /// `hash` is what the engine's hash table held for the position, with
+    /// its best move formatted as `hash_move`.
+    pub fn draw_analysis_panel(
+        f: &mut Frame,
+        area: Rect,
+        info: Option<&Info>,
+        line: &[String],
+        others: &[(&PvLine, Vec<String>)],
+        hash: Option<&PopHashInfo>,
+        hash_move: Option<&str>,
+    ) {
+        let block = Block::default()
+            .borders(BORDER_ALL)
+            .border_style(Style::default().fg(theme().secondary))
+            .title(Span::styled(
+                format!(" {} ", tr(Text::AnalysisTitle)),
+                Style::default().fg(theme().primary),
+            ))
+            .style(Style::default().bg(theme().popup_bg));
+
+        let mut lines = match info {
+            Some(info) => {
+                let mut stats = Vec::new();
+                if let Some(depth) = info.depth {
+                    stats.push(trf(Text::AnalysisDepth, &[&depth]));
+                }
+                if let Some(score) = info.score {
+                    stats.push(trf(Text::AnalysisScore, &[&format!("{:+}", score)]));
+                }
+                if let Some(nodes) = info.nodes {
+                    stats.push(trf(Text::AnalysisNodes, &[&nodes]));
+                }
+                vec![
+                    Line::from(Span::styled(
+                        stats.join("  "),
+                        Style::default().fg(theme().gold),
+                    )),
+                    Line::from(Span::styled(
+                        line.join(" "),
+                        Style::default().fg(theme().accent),
+                    )),
+                ]
+            }
+            None => vec![Line::from(tr(Text::EngineAnalyzing))],
+        };
+        for (pv_line, moves) in others {
+            let mut spans = vec![Span::styled(
+                format!("{}. ", pv_line.index),
+                Style::default().fg(theme().secondary),
+            )];
+            spans.extend(pv_line_spans(pv_line, moves));
+            lines.push(Line::from(spans));
+        }
+        if let Some(hash) = hash {
+            let mut entry = vec![tr(Text::Hash).to_string()];
+            if !hash.is_hit() {
+                entry.push(tr(Text::HashMiss).to_string());
+            }
+            if let Some(mv) = hash_move.or(hash.bestmove.as_deref()) {
+                entry.push(mv.to_string());
+            }
+            if let Some((score, depth)) = hash.lowerbound {
+                entry.push(format!("≥{:+} ({})", score, depth));
+            }
+            if let Some((score, depth)) = hash.upperbound {
+                entry.push(format!("≤{:+} ({})", score, depth));
+            }
+            lines.push(Line::from(Span::styled(
+                entry.join(" "),
+                Style::default().fg(theme().secondary),
+            )));
+        }
+
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(lines).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+    }
+
+    /// Draw the kibitzing engines' lines side by side, a column each
+    pub fn draw_kibitz_panel(f: &mut Frame, area: Rect, engines: &[KibitzColumn]) {
+        f.render_widget(Clear, area);
+        if engines.is_empty() {
+            return;
+        }
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Ratio(1, engines.len() as u32);
+                engines.len()
+            ])
+            .split(area);
+
+        for (engine, column) in engines.iter().zip(columns.iter()) {
+            let block = Block::default()
+                .borders(BORDER_ALL)
+                .border_style(Style::default().fg(theme().secondary))
+                .title(Span::styled(
+                    format!(" {} ", trf(Text::KibitzTitle, &[&engine.name])),
+                    Style::default().fg(theme().primary),
+                ))
+                .style(Style::default().bg(theme().popup_bg));
+            let text: Vec<Line> = if engine.lines.is_empty() {
+                vec![Line::from(tr(Text::EngineAnalyzing))]
+            } else {
+                engine
+                    .lines
+                    .iter()
+                    .map(|(pv_line, moves)| Line::from(pv_line_spans(pv_line, moves)))
+                    .collect()
+            };
+            f.render_widget(Paragraph::new(text).block(block), *column);
+        }
+    }
+
+    /// Draw the learning mode panel: how the selected piece, or the piece
+    /// under the cursor, moves, how many points it can reach now, and how
+    /// much of its side's mobility its kind has
+    pub fn draw_tutor_panel(
+        f: &mut Frame,
+        area: Rect,
+        game: &Game,
+        cursor: Position,
+        selected: Option<Position>,
     ) {
-        if !thinking_enabled {
-            let paragraph = Paragraph::new("Thinking display disabled")
-                .block(Block::default().borders(BORDER_ALL).title(" Thinking "));
-            f.render_widget(paragraph, area);
+        let block = Block::default()
+            .borders(BORDER_ALL)
+            .border_style(Style::default().fg(theme().secondary))
+            .title(Span::styled(
+                format!(" {} ", tr(Text::LearnTitle)),
+                Style::default().fg(theme().primary),
+            ))
+            .style(Style::default().bg(theme().popup_bg));
+
+        let board = game.board();
+        let square = tutor_square(board, cursor, selected);
+        let lines = match square.and_then(|pos| Some((pos, *board.get(pos)?))) {
+            Some((pos, piece)) => {
+                let rules = tutor::piece_rules(piece.piece_type);
+                let color = match piece.color {
+                    Color::Red => theme().red_piece,
+                    Color::Black => theme().black_piece,
+                };
+                let mobility = game.mobility(piece.color);
+                let reachable = match mobility.at(pos) {
+                    0 => tr(Text::CannotMove).to_string(),
+                    1 => tr(Text::ReachesOne).to_string(),
+                    n => trf(Text::ReachesMany, &[&n]),
+                };
+                let mut lines = vec![Line::from(vec![
+                    Span::styled(
+                        format!("{} {} ", piece, rules.name),
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("({}) ", tutor::side_name(piece.color)),
+                        Style::default().fg(theme().secondary),
+                    ),
+                    Span::styled(reachable, Style::default().fg(theme().gold)),
+                ])];
+                lines.extend(rules.rules.iter().map(|rule| {
+                    Line::from(Span::styled(
+                        format!("· {}", rule),
+                        Style::default().fg(theme().accent),
+                    ))
+                }));
+                lines.push(Line::from(Span::styled(
+                    trf(
+                        Text::KindMobility,
+                        &[&mobility.by_type(piece.piece_type), &mobility.total()],
+                    ),
+                    Style::default().fg(theme().secondary),
+                )));
+                lines
+            }
+            None => vec![Line::from(Span::styled(
+                tr(Text::LearnHint),
+                Style::default().fg(theme().secondary),
+            ))],
+        };
+
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(lines).block(block).wrap(Wrap { trim: true }),
+            area,
+        );
+    }
+
+    /// Draw how the game has swung, with each side's time, captures and
+    /// legal moves
+    ///
+    /// `stats` has an entry per move played; evaluations are from Red's
+    /// side, so the line rises while Red is better. Moves without an
+    /// evaluation are skipped.
+    pub fn draw_eval_graph(f: &mut Frame, area: Rect, game: &Game, stats: &[MoveStats]) {
+        let block = Block::default()
+            .borders(BORDER_ALL)
+            .border_style(Style::default().fg(theme().secondary))
+            .title(Span::styled(
+                format!(" {} ", tr(Text::EvalTitle)),
+                Style::default().fg(theme().primary),
+            ));
+        let inner = block.inner(area);
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+        if inner.height == 0 {
+            return;
+        }
+
+        // Thinking time and captures per side
+        let mut time_ms = [0u64; 2];
+        let mut captures = [0usize; 2];
+        let captured = game.get_capture_history();
+        for (i, (piece, _)) in game.get_notated_moves().into_iter().enumerate() {
+            let side = match piece.color {
+                Color::Red => 0,
+                Color::Black => 1,
+            };
+            time_ms[side] += stats.get(i).and_then(|s| s.time_ms).unwrap_or(0);
+            captures[side] += usize::from(captured[i]);
+        }
+        let clock = |ms: u64| format!("{}:{:02}", ms / 60_000, ms / 1000 % 60);
+        let latest = stats.iter().rev().find_map(|s| s.eval);
+        let mut summary = vec![
+            Span::styled(
+                format!("{} ", tr(Text::RedShort)),
+                Style::default().fg(theme().red_piece),
+            ),
+            Span::raw(format!(
+                "{}  ",
+                trf(Text::Captures, &[&clock(time_ms[0]), &captures[0]])
+            )),
+            Span::styled(
+                format!("{} ", tr(Text::BlackShort)),
+                Style::default().fg(theme().black_piece),
+            ),
+            Span::raw(trf(Text::Captures, &[&clock(time_ms[1]), &captures[1]])),
+        ];
+        if let Some(eval) = latest {
+            summary.push(Span::styled(
+                format!("  {:+}", eval),
+                Style::default().fg(theme().gold),
+            ));
+        }
+        // Each side's legal moves in the position reached
+        let mobility = Line::from(vec![
+            Span::raw(format!("{} ", tr(Text::MobilityLabel))),
+            Span::styled(
+                format!("{} ", tr(Text::RedShort)),
+                Style::default().fg(theme().red_piece),
+            ),
+            Span::raw(format!("{}  ", game.mobility(Color::Red).total())),
+            Span::styled(
+                format!("{} ", tr(Text::BlackShort)),
+                Style::default().fg(theme().black_piece),
+            ),
+            Span::raw(game.mobility(Color::Black).total().to_string()),
+        ]);
+        let header = inner.height.min(2);
+        f.render_widget(
+            Paragraph::new(vec![Line::from(summary), mobility]),
+            Rect {
+                height: header,
+                ..inner
+            },
+        );
+        if inner.height <= header {
             return;
         }
 
-        if !engine_thinking {
-            let paragraph = Paragraph::new("Engine not thinking")
-                .block(Block::default().borders(BORDER_ALL).title(" Thinking "));
-            f.render_widget(paragraph, area);
+        let graph = Rect {
+            y: inner.y + header,
+            height: inner.height - header,
+            ..inner
+        };
+        let points: Vec<(f64, f64)> = stats
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| {
+                let eval = s.eval?.clamp(-EVAL_GRAPH_LIMIT, EVAL_GRAPH_LIMIT);
+                Some(((i + 1) as f64, eval as f64))
+            })
+            .collect();
+        if points.is_empty() {
+            f.render_widget(
+                Paragraph::new(Span::styled(
+                    tr(Text::NoEvaluations),
+                    Style::default().fg(theme().secondary),
+                )),
+                graph,
+            );
             return;
         }
 
+        // Scale to the largest swing, but keep small ones looking small
+        let limit = points
+            .iter()
+            .map(|&(_, eval)| eval.abs())
+            .fold(200.0, f64::max);
+        let plies = stats.len().max(1) as f64;
+        let zero = [(0.0, 0.0), (plies, 0.0)];
+        let datasets = vec![
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(theme().grid))
+                .data(&zero),
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(theme().gold))
+                .data(&points),
+        ];
+        let chart = Chart::new(datasets)
+            .x_axis(Axis::default().bounds([0.0, plies]))
+            .y_axis(Axis::default().bounds([-limit, limit]));
+        f.render_widget(chart, graph);
+    }
+
+    /// Draw the comment editor over the review screen
+    pub fn draw_comment_editor(f: &mut Frame, state: &CommentEditorState) {
+        let size = f.area();
+        let width = 50.min(size.width);
+        let popup_area = Self::centered_rect(width, 8, size);
+
         let lines = vec![
-            Line::from("Engine is calculating..."),
+            Line::from(Span::styled(
+                trf(Text::CommentTitle, &[&state.notation]),
+                Style::default()
+                    .fg(theme().accent)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(state.text.as_str(), Style::default().fg(theme().primary)),
+                Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+            ]),
             Line::from(""),
-            Line::from("Depth: searching..."),
-            Line::from("Score: evaluating..."),
+            Line::from(Span::styled(
+                tr(Text::CommentKeys),
+                Style::default().fg(theme().secondary),
+            )),
         ];
 
-        let paragraph =
-            Paragraph::new(lines).block(Block::default().borders(BORDER_ALL).title(" Thinking "));
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(BORDER_ALL)
+                    .border_style(Style::default().fg(theme().primary))
+                    .style(Style::default().bg(theme().popup_bg)),
+            )
+            .wrap(Wrap { trim: false });
 
-        f.render_widget(paragraph, area);
+        f.render_widget(Clear, popup_area);
+        f.render_widget(paragraph, popup_area);
+    }
+
+    /// Draw the `:` command prompt in `area`, usually the bottom row, with
+    /// the names offered by an ambiguous completion at the right
+    pub fn draw_command_line(f: &mut Frame, area: Rect, state: &CommandLineState) {
+        let prompt = Line::from(vec![
+            Span::styled(":", Style::default().fg(theme().accent)),
+            Span::styled(state.text.as_str(), Style::default().fg(theme().primary)),
+            Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+        ]);
+        let style = Style::default().bg(theme().popup_bg);
+
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(prompt).style(style), area);
+        if state.completions.len() > 1 {
+            f.render_widget(
+                Paragraph::new(state.completions.join("  "))
+                    .style(style.fg(theme().grid))
+                    .alignment(Alignment::Right),
+                area,
+            );
+        }
     }
 
     /// Draw error popup message
@@ -1018,7 +3647,7 @@ impl UI {
             .block(
                 Block::default()
                     .borders(BORDER_ALL)
-                    .border_style(Style::default().fg(RColor::Red))
+                    .border_style(Style::default().fg(theme().check))
                     .title(" Error "),
             )
             .alignment(Alignment::Center);
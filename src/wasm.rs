@@ -0,0 +1,101 @@
+//! JavaScript bindings to the rules, for web frontends
+//!
+//! Built with the `wasm` feature, usually without the default `tui` one:
+//!
+//! ```text
+//! cargo build --target wasm32-unknown-unknown --no-default-features --features wasm
+//! wasm-bindgen --target web target/wasm32-unknown-unknown/debug/cn_chess_tui.wasm --out-dir pkg
+//! ```
+//!
+//! A game lives on the Rust side as a [`WasmGame`] handle. Moves go in and
+//! come out as strings: ICCS, WXF or Chinese notation in, ICCS out.
+//!
+//! ```text
+//! const game = new_game();
+//! make_move(game, "h7e7");
+//! make_move(game, "马8进7");
+//! legal_moves(game);   // ["a9a8", "a9a7", ...]
+//! to_fen(game);        // "rnbakab1r/9/1c4nc1/..."
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::game::{Game, GameState};
+use crate::notation::format::parse_any;
+use crate::notation::iccs::move_to_iccs;
+
+/// A game held by JavaScript
+#[wasm_bindgen]
+pub struct WasmGame {
+    game: Game,
+}
+
+/// A game from the starting position
+#[wasm_bindgen]
+pub fn new_game() -> WasmGame {
+    WasmGame { game: Game::new() }
+}
+
+/// A game from a FEN position
+#[wasm_bindgen]
+pub fn game_from_fen(fen: &str) -> Result<WasmGame, JsError> {
+    Game::from_fen(fen)
+        .map(|game| WasmGame { game })
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Play a move written in ICCS, WXF or Chinese notation
+///
+/// Throws, leaving the game as it was, if the move can't be read or isn't
+/// legal.
+#[wasm_bindgen]
+pub fn make_move(game: &mut WasmGame, mv: &str) -> Result<(), JsError> {
+    let (from, to) = parse_any(&game.game, mv).map_err(|e| JsError::new(&e.to_string()))?;
+    game.game
+        .make_move(from, to)
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// The legal moves of the side to move, in ICCS; none once the game is over
+#[wasm_bindgen]
+pub fn legal_moves(game: &WasmGame) -> Vec<String> {
+    if game.game.state() != GameState::Playing {
+        return Vec::new();
+    }
+    game.game
+        .board()
+        .legal_moves(game.game.turn())
+        .into_iter()
+        .map(|(from, to)| move_to_iccs(from, to))
+        .collect()
+}
+
+/// The position as FEN
+#[wasm_bindgen]
+pub fn to_fen(game: &WasmGame) -> String {
+    game.game.to_fen()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Errors are JavaScript objects, so only the paths that succeed run
+    // outside a browser
+    #[test]
+    fn test_play_through_the_facade() {
+        let mut game = new_game();
+        assert_eq!(legal_moves(&game).len(), 44);
+        make_move(&mut game, "h7e7").unwrap();
+        make_move(&mut game, "马8进7").unwrap();
+        make_move(&mut game, "H2+3").unwrap();
+        assert_eq!(
+            to_fen(&game),
+            "rnbakab1r/9/1c4nc1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C1N2/9/RNBAKAB1R b - - 3 2"
+        );
+
+        let mut mated = game_from_fen("3k5/R8/9/9/9/9/9/9/9/4K3R w - - 0 1").unwrap();
+        make_move(&mut mated, "i9i0").unwrap();
+        assert!(legal_moves(&mated).is_empty());
+    }
+}
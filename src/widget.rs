@@ -0,0 +1,660 @@
+//! The board as a ratatui widget
+//!
+//! [`BoardWidget`] draws a position the way the game does: the grid and
+//! river, the pieces in Chinese characters or letters, and highlights for
+//! the cursor, the selected piece, the last move, the points a piece can
+//! reach, attacked points and the first moves of an engine line. The game's
+//! own screens draw their boards with it, so other ratatui applications can
+//! embed the same board by depending on this crate.
+//!
+//! As a [`StatefulWidget`] it takes the cursor and selection from a
+//! [`BoardState`] and records where the grid was drawn, so mouse clicks can
+//! be turned back into board positions.
+//!
+//! # Examples
+//! ```
+//! use cn_chess_tui::widget::BoardWidget;
+//! use cn_chess_tui::{Board, Position};
+//! use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+//!
+//! let board = Board::new();
+//! let widget = BoardWidget::new(&board).cursor(Position::from_xy(4, 9));
+//! let (width, height) = widget.size();
+//! let area = Rect::new(0, 0, width, height);
+//! let mut buffer = Buffer::empty(area);
+//! widget.render(area, &mut buffer);
+//! // Black's chariot, its character covering the first file's line
+//! assert_eq!(buffer[(1, 0)].symbol(), "车");
+//! ```
+
+use crate::board::{AttackMap, Board};
+use crate::game::Move;
+use crate::types::{Color, Position};
+use crate::ui::{AttackOverlay, PieceStyle, Theme};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Block, Paragraph, StatefulWidget, Widget},
+};
+use unicode_width::UnicodeWidthStr;
+
+// Base board dimensions (9x10 grid)
+pub(crate) const BOARD_COLS: usize = 9;
+pub(crate) const BOARD_ROWS: usize = 10;
+
+/// Engine line moves marked on the board
+const PV_MARKER_MOVES: usize = 3;
+
+/// A Xiangqi board drawn from a [`Board`]
+///
+/// Built with [`BoardWidget::new`] and the setters below; everything but
+/// the board has a default, giving the classic theme, Chinese pieces in
+/// cells four columns wide and two rows high, the river text and no
+/// highlights. The board is centered in the area it is given, and clipped
+/// when that is too small.
+#[derive(Debug, Clone)]
+pub struct BoardWidget<'a> {
+    board: &'a Board,
+    theme: Theme,
+    piece_style: PieceStyle,
+    cell_width: u16,
+    cell_height: u16,
+    flipped: bool,
+    coordinates: bool,
+    river_text: bool,
+    hide_pieces: bool,
+    last_move: Option<Move>,
+    cursor: Option<Position>,
+    selected: Option<Position>,
+    reachable_from: Option<Position>,
+    attacks: AttackOverlay,
+    pv: &'a [(Position, Position)],
+    block: Option<Block<'a>>,
+}
+
+impl<'a> BoardWidget<'a> {
+    pub fn new(board: &'a Board) -> Self {
+        Self {
+            board,
+            theme: Theme::default(),
+            piece_style: PieceStyle::Chinese,
+            cell_width: 4,
+            cell_height: 2,
+            flipped: false,
+            coordinates: false,
+            river_text: true,
+            hide_pieces: false,
+            last_move: None,
+            cursor: None,
+            selected: None,
+            reachable_from: None,
+            attacks: AttackOverlay::Off,
+            pv: &[],
+            block: None,
+        }
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn piece_style(mut self, style: PieceStyle) -> Self {
+        self.piece_style = style;
+        self
+    }
+
+    /// Columns between files and rows between ranks
+    ///
+    /// One-row cells have no room for the vertical grid lines, so they are
+    /// best drawn with [`PieceStyle::Letters`] and without the river text.
+    pub fn cell_size(mut self, width: u16, height: u16) -> Self {
+        self.cell_width = width.max(1);
+        self.cell_height = height.max(1);
+        self
+    }
+
+    /// Turn the board around, with Red at the top
+    pub fn flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    /// Label files and ranks with ICCS coordinates when there is room
+    pub fn coordinates(mut self, coordinates: bool) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Write 楚河 汉界 across the river
+    pub fn river_text(mut self, river_text: bool) -> Self {
+        self.river_text = river_text;
+        self
+    }
+
+    /// Leave the pieces out, as in blindfold play; highlights still show
+    pub fn hide_pieces(mut self, hide: bool) -> Self {
+        self.hide_pieces = hide;
+        self
+    }
+
+    /// Shade the squares a move left and reached
+    pub fn last_move(mut self, mv: Option<Move>) -> Self {
+        self.last_move = mv;
+        self
+    }
+
+    pub fn cursor(mut self, cursor: impl Into<Option<Position>>) -> Self {
+        self.cursor = cursor.into();
+        self
+    }
+
+    pub fn selected(mut self, selected: Option<Position>) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    /// Shade the points the piece on a square can move to
+    pub fn reachable_from(mut self, from: Option<Position>) -> Self {
+        self.reachable_from = from;
+        self
+    }
+
+    /// Shade the points attacked by the sides `overlay` shows
+    pub fn attacks(mut self, overlay: AttackOverlay) -> Self {
+        self.attacks = overlay;
+        self
+    }
+
+    /// Number the squares of the first moves of an engine line
+    pub fn pv(mut self, pv: &'a [(Position, Position)]) -> Self {
+        self.pv = pv;
+        self
+    }
+
+    /// Draw the board inside a block, usually a bordered and titled one
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// Columns and rows the board takes, with its block but without the
+    /// coordinate labels
+    pub fn size(&self) -> (u16, u16) {
+        let border = if self.block.is_some() { 2 } else { 0 };
+        (
+            (BOARD_COLS as u16) * self.cell_width + border,
+            (BOARD_ROWS as u16) * self.cell_height + border,
+        )
+    }
+
+    /// Draw the board, returning the area of its grid
+    fn draw(mut self, area: Rect, buf: &mut Buffer) -> Rect {
+        let (board_width, board_height) = self.size();
+        // Rank labels take two columns on the left, file labels a row below
+        let labels =
+            self.coordinates && area.width >= board_width + 2 && area.height > board_height;
+        let board_area = if labels {
+            let outer = centered_rect(board_width + 2, board_height + 1, area);
+            Rect::new(outer.x + 2, outer.y, board_width, board_height)
+        } else {
+            centered_rect(
+                board_width.min(area.width),
+                board_height.min(area.height),
+                area,
+            )
+        };
+
+        let inner = match self.block.take() {
+            Some(block) => {
+                block.render(board_area, buf);
+                board_area.inner(Margin::new(1, 1))
+            }
+            None => board_area,
+        };
+
+        self.draw_grid(inner, buf);
+        if self.river_text {
+            self.draw_river(inner, buf);
+        }
+        if self.attacks != AttackOverlay::Off {
+            self.draw_attack_map(inner, buf);
+        }
+        if let Some(mv) = self.last_move {
+            self.draw_last_move_highlight(inner, buf, mv);
+        }
+        if let Some(from) = self.reachable_from {
+            self.draw_reachable_highlight(inner, buf, from);
+        }
+        if let Some(cursor) = self.cursor {
+            self.draw_cursor_highlight(inner, buf, cursor);
+        }
+        if let Some(selected) = self.selected {
+            self.draw_selection_highlight(inner, buf, selected);
+        }
+        if !self.hide_pieces {
+            self.draw_pieces(inner, buf);
+        }
+        self.draw_pv_markers(inner, buf);
+        if labels {
+            self.draw_coordinates(board_area, inner, buf);
+        }
+        inner
+    }
+
+    /// Column and row of the grid `pos` is drawn at
+    fn view_xy(&self, pos: Position) -> (usize, usize) {
+        if self.flipped {
+            (BOARD_COLS - 1 - pos.x, BOARD_ROWS - 1 - pos.y)
+        } else {
+            (pos.x, pos.y)
+        }
+    }
+
+    fn cell_pos(&self, x: usize, y: usize) -> (u16, u16) {
+        let px = (x as u16) * self.cell_width + (self.cell_width / 2);
+        let py = (y as u16) * self.cell_height;
+        (px, py)
+    }
+
+    /// Columns of `inner` covered by something `width` columns wide drawn
+    /// on the intersection at `pos`
+    ///
+    /// The span is centered on the intersection's column, so a double-width
+    /// character sits with its right half over the grid line and a letter
+    /// sits on it exactly. Spans that would cross the right edge of the
+    /// board are moved left to stay inside it. Returns `None` when the
+    /// intersection itself is outside `inner`.
+    fn glyph_rect(&self, inner: Rect, pos: Position, width: u16) -> Option<Rect> {
+        let (x, y) = self.view_xy(pos);
+        let (px, py) = self.cell_pos(x, y);
+        let (px, py) = (inner.x + px, inner.y + py);
+        if px >= inner.right() || py >= inner.bottom() {
+            return None;
+        }
+        let width = width.min(inner.width);
+        let x = px
+            .saturating_sub(width / 2)
+            .min(inner.right() - width)
+            .max(inner.x);
+        Some(Rect::new(x, py, width, 1))
+    }
+
+    /// Draw ICCS file letters under the board and rank numbers to its left
+    fn draw_coordinates(&self, board_area: Rect, inner: Rect, buf: &mut Buffer) {
+        let style = Style::default().fg(self.theme.grid);
+        for (x, file) in ('a'..='i').enumerate() {
+            let (x, _) = self.view_xy(Position::from_xy(x, 0));
+            let (px, _) = self.cell_pos(x, 0);
+            buf.set_string(inner.x + px, board_area.bottom(), file.to_string(), style);
+        }
+        for y in 0..BOARD_ROWS {
+            let (_, row) = self.view_xy(Position::from_xy(0, y));
+            let (_, py) = self.cell_pos(0, row);
+            let rank = BOARD_ROWS - 1 - y;
+            buf.set_string(board_area.x - 2, inner.y + py, rank.to_string(), style);
+        }
+    }
+
+    fn draw_grid(&self, area: Rect, buf: &mut Buffer) {
+        let grid_style = Style::default().fg(self.theme.grid);
+        let corner_style = Style::default().fg(self.theme.secondary);
+
+        // Calculate how many rows and cols fit in the available area
+        let max_rows = (area.height / self.cell_height).min(BOARD_ROWS as u16) as usize;
+        let max_cols = (area.width / self.cell_width).min(BOARD_COLS as u16) as usize;
+
+        for y in 0..max_rows {
+            for x in 0..max_cols {
+                let (px, py) = self.cell_pos(x, y);
+                let px = area.x + px;
+                let py = area.y + py;
+
+                // Skip if this position is outside the area bounds
+                if px >= area.x + area.width || py >= area.y + area.height {
+                    continue;
+                }
+
+                let (c, is_corner) = if x == 0 && y == 0 {
+                    ("┌", true)
+                } else if x == max_cols - 1 && y == 0 && max_cols == BOARD_COLS {
+                    ("┐", true)
+                } else if x == 0 && y == max_rows - 1 && max_rows == BOARD_ROWS {
+                    ("└", true)
+                } else if x == max_cols - 1
+                    && y == max_rows - 1
+                    && max_cols == BOARD_COLS
+                    && max_rows == BOARD_ROWS
+                {
+                    ("┘", true)
+                } else if x == 0 {
+                    ("├", false)
+                } else if x == max_cols - 1 && max_cols == BOARD_COLS {
+                    ("┤", false)
+                } else if y == 0 {
+                    ("┬", false)
+                } else if y == max_rows - 1 && max_rows == BOARD_ROWS {
+                    ("┴", false)
+                } else {
+                    ("┼", false)
+                };
+
+                let style = if is_corner { corner_style } else { grid_style };
+                Paragraph::new(Span::styled(c, style)).render(Rect::new(px, py, 1, 1), buf);
+
+                // Horizontal lines
+                if x < max_cols - 1 && self.cell_width > 1 {
+                    for i in 1..self.cell_width {
+                        let hx = px + i;
+                        Paragraph::new(Span::styled("─", grid_style))
+                            .render(Rect::new(hx, py, 1, 1), buf);
+                    }
+                }
+            }
+
+            // Vertical lines (skip river area), when ranks are more than a
+            // row apart
+            if y < max_rows - 1 && self.cell_height > 1 {
+                for x in 0..max_cols {
+                    let (px, py) = self.cell_pos(x, y);
+                    let px = area.x + px;
+                    let py = area.y + py + 1;
+
+                    if y == 4 {
+                        continue;
+                    } // Skip river
+
+                    // Skip if this position is outside the area bounds
+                    if px >= area.x + area.width || py >= area.y + area.height {
+                        continue;
+                    }
+
+                    Paragraph::new(Span::styled("│", grid_style))
+                        .render(Rect::new(px, py, 1, 1), buf);
+                }
+            }
+        }
+    }
+
+    fn draw_river(&self, area: Rect, buf: &mut Buffer) {
+        let river_y = area.y + self.cell_height * 5 - 1;
+
+        // Skip if river is outside area bounds
+        if river_y >= area.y + area.height {
+            return;
+        }
+
+        let chu = " 楚河";
+        let han = "汉界";
+
+        let river_style = Style::default()
+            .fg(self.theme.river)
+            .add_modifier(Modifier::BOLD);
+
+        let left_w = (6 * self.cell_width).min(area.width);
+        let right_w = (6 * self.cell_width).min(area.width);
+
+        Paragraph::new(chu)
+            .style(river_style)
+            .alignment(Alignment::Left)
+            .render(Rect::new(area.x, river_y, left_w, 1), buf);
+
+        Paragraph::new(han)
+            .style(river_style)
+            .alignment(Alignment::Right)
+            .render(
+                Rect::new(
+                    area.x + (BOARD_COLS as u16) * self.cell_width - right_w,
+                    river_y,
+                    right_w,
+                    1,
+                ),
+                buf,
+            );
+    }
+
+    fn draw_pieces(&self, area: Rect, buf: &mut Buffer) {
+        let max_rows = (area.height / self.cell_height).min(BOARD_ROWS as u16) as usize;
+        let max_cols = (area.width / self.cell_width).min(BOARD_COLS as u16) as usize;
+
+        for (pos, piece) in self.board.pieces() {
+            // Skip pieces outside the visible grid
+            let (x, y) = self.view_xy(pos);
+            if x >= max_cols || y >= max_rows {
+                continue;
+            }
+
+            let piece_text = self.piece_style.text(piece);
+            let Some(rect) = self.glyph_rect(area, pos, piece_text.width() as u16) else {
+                continue;
+            };
+
+            let fg = match piece.color {
+                Color::Red => self.theme.red_piece,
+                Color::Black => self.theme.black_piece,
+            };
+
+            Paragraph::new(piece_text)
+                .style(Style::default().fg(fg).add_modifier(Modifier::BOLD))
+                .render(rect, buf);
+        }
+    }
+
+    /// Number the squares of the first moves of the engine line
+    ///
+    /// Each move's number is written just left of its squares: dim on the
+    /// square the piece leaves, bold on the square it lands on, which is
+    /// also underlined.
+    fn draw_pv_markers(&self, inner: Rect, buf: &mut Buffer) {
+        let width = self.piece_style.width();
+        for (i, &(from, to)) in self.pv.iter().take(PV_MARKER_MOVES).enumerate() {
+            let number = (i + 1).to_string();
+            for (pos, landing) in [(from, false), (to, true)] {
+                let Some(rect) = self.glyph_rect(inner, pos, width) else {
+                    continue;
+                };
+                if rect.x <= inner.x {
+                    continue;
+                }
+                let style = if landing {
+                    Style::default()
+                        .fg(self.theme.gold)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(self.theme.secondary)
+                };
+                buf.set_string(rect.x - 1, rect.y, &number, style);
+                if landing {
+                    buf.set_style(rect, Style::default().add_modifier(Modifier::UNDERLINED));
+                }
+            }
+        }
+    }
+
+    /// Shade the points attacked by the sides the overlay shows: in the
+    /// attacking side's shade, or the contested shade when both attack
+    fn draw_attack_map(&self, inner: Rect, buf: &mut Buffer) {
+        let shown = |color| {
+            self.attacks
+                .shows(color)
+                .then(|| self.board.attacked_squares(color))
+        };
+        let red = shown(Color::Red);
+        let black = shown(Color::Black);
+        let width = self.piece_style.width();
+        for y in 0..BOARD_ROWS {
+            for x in 0..BOARD_COLS {
+                let pos = Position::from_xy(x, y);
+                let attacked =
+                    |map: &Option<AttackMap>| map.as_ref().is_some_and(|m| m.is_attacked(pos));
+                let bg = match (attacked(&red), attacked(&black)) {
+                    (true, true) => self.theme.contested_bg,
+                    (true, false) => self.theme.red_attack_bg,
+                    (false, true) => self.theme.black_attack_bg,
+                    (false, false) => continue,
+                };
+                if let Some(rect) = self.glyph_rect(inner, pos, width) {
+                    buf.set_style(rect, Style::default().bg(bg));
+                }
+            }
+        }
+    }
+
+    /// Shade the points the piece on `from` can move to, capturing ones
+    /// underlined
+    fn draw_reachable_highlight(&self, inner: Rect, buf: &mut Buffer, from: Position) {
+        let width = self.piece_style.width();
+        for to in self.board.legal_moves_from(from) {
+            if let Some(rect) = self.glyph_rect(inner, to, width) {
+                let mut style = Style::default().bg(self.theme.selection_bg);
+                if self.board.get(to).is_some() {
+                    style = style.add_modifier(Modifier::UNDERLINED);
+                }
+                buf.set_style(rect, style);
+            }
+        }
+    }
+
+    /// Shade the squares the last move left and reached
+    fn draw_last_move_highlight(&self, inner: Rect, buf: &mut Buffer, mv: Move) {
+        let width = self.piece_style.width();
+        for pos in [mv.from, mv.to] {
+            if let Some(rect) = self.glyph_rect(inner, pos, width) {
+                buf.set_style(rect, Style::default().bg(self.theme.last_move_bg));
+            }
+        }
+    }
+
+    /// Show the cursor in reverse video over exactly the columns its piece
+    /// takes, so it stays visible whatever the piece and theme colors are
+    fn draw_cursor_highlight(&self, inner: Rect, buf: &mut Buffer, cursor: Position) {
+        let Some(rect) = self.glyph_rect(inner, cursor, self.piece_style.width()) else {
+            return;
+        };
+        buf.set_style(
+            rect,
+            Style::default()
+                .fg(self.theme.cursor)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        );
+    }
+
+    fn draw_selection_highlight(&self, inner: Rect, buf: &mut Buffer, selected: Position) {
+        let Some(rect) = self.glyph_rect(inner, selected, self.piece_style.width()) else {
+            return;
+        };
+        buf.set_style(
+            rect,
+            Style::default()
+                .fg(self.theme.selection)
+                .bg(self.theme.selection_bg)
+                .add_modifier(Modifier::BOLD),
+        );
+    }
+}
+
+impl Widget for BoardWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.draw(area, buf);
+    }
+}
+
+impl StatefulWidget for BoardWidget<'_> {
+    type State = BoardState;
+
+    /// Draw the board with the state's cursor and selection, recording
+    /// where it went
+    fn render(mut self, area: Rect, buf: &mut Buffer, state: &mut BoardState) {
+        self.cursor = state.cursor;
+        self.selected = state.selected;
+        state.cell_width = self.cell_width;
+        state.cell_height = self.cell_height;
+        state.flipped = self.flipped;
+        state.grid = self.draw(area, buf);
+    }
+}
+
+/// Cursor and selection of a [`BoardWidget`] drawn as a [`StatefulWidget`],
+/// and where it was last drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BoardState {
+    pub cursor: Option<Position>,
+    pub selected: Option<Position>,
+    grid: Rect,
+    cell_width: u16,
+    cell_height: u16,
+    flipped: bool,
+}
+
+impl BoardState {
+    /// A state with the cursor on `cursor` and nothing selected
+    pub fn new(cursor: Position) -> Self {
+        Self {
+            cursor: Some(cursor),
+            ..Self::default()
+        }
+    }
+
+    /// Where the grid was drawn at the last render, inside the block;
+    /// empty before the first
+    pub fn grid(&self) -> Rect {
+        self.grid
+    }
+
+    /// The board position drawn at a terminal cell, as of the last render
+    ///
+    /// Each intersection owns the cell-sized block of the grid it sits in,
+    /// so clicks near a piece find it.
+    ///
+    /// # Examples
+    /// ```
+    /// use cn_chess_tui::widget::{BoardState, BoardWidget};
+    /// use cn_chess_tui::{Board, Position};
+    /// use ratatui::{buffer::Buffer, layout::Rect, widgets::StatefulWidget};
+    ///
+    /// let board = Board::new();
+    /// let area = Rect::new(0, 0, 36, 20);
+    /// let mut state = BoardState::default();
+    /// BoardWidget::new(&board).render(area, &mut Buffer::empty(area), &mut state);
+    /// assert_eq!(state.position_at(3, 19), Some(Position::from_xy(0, 9)));
+    /// assert_eq!(state.position_at(36, 0), None);
+    /// ```
+    pub fn position_at(&self, column: u16, row: u16) -> Option<Position> {
+        if !self.grid.contains((column, row).into()) || self.cell_width == 0 {
+            return None;
+        }
+        let x = ((column - self.grid.x) / self.cell_width) as usize;
+        let y = ((row - self.grid.y) / self.cell_height) as usize;
+        if x >= BOARD_COLS || y >= BOARD_ROWS {
+            return None;
+        }
+        Some(if self.flipped {
+            Position::from_xy(BOARD_COLS - 1 - x, BOARD_ROWS - 1 - y)
+        } else {
+            Position::from_xy(x, y)
+        })
+    }
+}
+
+/// A `width` by `height` area in the middle of `r`
+fn centered_rect(width: u16, height: u16, r: Rect) -> Rect {
+    let patch_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length((r.height.saturating_sub(height)) / 2),
+            Constraint::Length(height),
+            Constraint::Length((r.height.saturating_sub(height)) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length((r.width.saturating_sub(width)) / 2),
+            Constraint::Length(width),
+            Constraint::Length((r.width.saturating_sub(width)) / 2),
+        ])
+        .split(patch_layout[1])[1]
+}
@@ -16,18 +16,20 @@
 //!   </tags>
 //!   <moves>
 //!     <move>h2e2</move>
-//!     <move>h9g7</move>
+//!     <move comment="Screen horse">h9g7</move>
 //!     <move>h3g3</move>
 //!   </moves>
 //! </pgn>
 //! ```
+//!
+//! A move's comment, if any, is kept in its `comment` attribute.
 
 use crate::pgn::{PgnGame, PgnGameResult};
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::reader::Reader;
 use quick_xml::writer::Writer;
 use std::fs::File;
-use std::io::{Cursor, Write};
+use std::io::Write;
 
 /// Convert a PgnGame to XML string format
 ///
@@ -51,71 +53,82 @@ use std::io::{Cursor, Write};
 /// assert!(xml.contains("<move>h9g7</move>"));
 /// ```
 pub fn pgn_to_xml(game: &PgnGame) -> String {
-    let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
+    let mut buf = Vec::new();
+    write_xml(game, &mut buf).expect("writing to a Vec cannot fail");
+    String::from_utf8(buf).expect("XML output is valid UTF-8")
+}
+
+/// Write a PgnGame as XML to a writer
+///
+/// Events are written as they are produced, so long games can be streamed
+/// to a file without building the whole document in memory.
+pub fn write_xml<W: Write>(game: &PgnGame, out: W) -> std::io::Result<()> {
+    let mut writer = Writer::new_with_indent(out, b' ', 2);
 
     // Write XML declaration
     let decl = BytesDecl::new("1.0", Some("UTF-8"), None);
-    writer.write_event(Event::Decl(decl)).unwrap();
+    writer.write_event(Event::Decl(decl))?;
 
     // Write root element <pgn>
     let pgn_start = BytesStart::new("pgn");
-    writer.write_event(Event::Start(pgn_start)).unwrap();
+    writer.write_event(Event::Start(pgn_start))?;
 
     // Write <tags> section
     let tags_start = BytesStart::new("tags");
-    writer.write_event(Event::Start(tags_start)).unwrap();
+    writer.write_event(Event::Start(tags_start))?;
 
     for tag in &game.tags {
         let tag_start = BytesStart::new(tag.key.as_str());
-        writer.write_event(Event::Start(tag_start)).unwrap();
+        writer.write_event(Event::Start(tag_start))?;
 
         let text = BytesText::new(tag.value.as_str());
-        writer.write_event(Event::Text(text)).unwrap();
+        writer.write_event(Event::Text(text))?;
 
         let tag_end = BytesEnd::new(tag.key.as_str());
-        writer.write_event(Event::End(tag_end)).unwrap();
+        writer.write_event(Event::End(tag_end))?;
     }
 
     let tags_end = BytesEnd::new("tags");
-    writer.write_event(Event::End(tags_end)).unwrap();
+    writer.write_event(Event::End(tags_end))?;
 
     // Write <moves> section if there are moves
     if !game.moves.is_empty() {
         let moves_start = BytesStart::new("moves");
-        writer.write_event(Event::Start(moves_start)).unwrap();
+        writer.write_event(Event::Start(moves_start))?;
 
         for mv in &game.moves {
-            let move_start = BytesStart::new("move");
-            writer.write_event(Event::Start(move_start)).unwrap();
+            let mut move_start = BytesStart::new("move");
+            if let Some(comment) = &mv.comment {
+                move_start.push_attribute(("comment", comment.as_str()));
+            }
+            writer.write_event(Event::Start(move_start))?;
 
             let text = BytesText::new(mv.notation.as_str());
-            writer.write_event(Event::Text(text)).unwrap();
+            writer.write_event(Event::Text(text))?;
 
             let move_end = BytesEnd::new("move");
-            writer.write_event(Event::End(move_end)).unwrap();
+            writer.write_event(Event::End(move_end))?;
         }
 
         let moves_end = BytesEnd::new("moves");
-        writer.write_event(Event::End(moves_end)).unwrap();
+        writer.write_event(Event::End(moves_end))?;
     }
 
     // Write <result>
     let result_start = BytesStart::new("result");
-    writer.write_event(Event::Start(result_start)).unwrap();
+    writer.write_event(Event::Start(result_start))?;
 
     let result_text = BytesText::new(game.result.to_pgn_string());
-    writer.write_event(Event::Text(result_text)).unwrap();
+    writer.write_event(Event::Text(result_text))?;
 
     let result_end = BytesEnd::new("result");
-    writer.write_event(Event::End(result_end)).unwrap();
+    writer.write_event(Event::End(result_end))?;
 
     // Write root end element </pgn>
     let pgn_end = BytesEnd::new("pgn");
-    writer.write_event(Event::End(pgn_end)).unwrap();
+    writer.write_event(Event::End(pgn_end))?;
 
-    // Extract the written XML
-    let result = writer.into_inner();
-    String::from_utf8(result.into_inner()).unwrap()
+    Ok(())
 }
 
 /// Convert an XML string to a PgnGame using quick-xml parser
@@ -149,6 +162,7 @@ pub fn xml_to_pgn(xml: &str) -> Option<PgnGame> {
     let mut in_result = false;
     let mut current_tag_name: Option<String> = None;
     let mut current_content = String::new();
+    let mut current_comment: Option<String> = None;
 
     let mut buf = Vec::new();
 
@@ -159,6 +173,10 @@ pub fn xml_to_pgn(xml: &str) -> Option<PgnGame> {
                 b"moves" => in_moves = true,
                 b"move" => {
                     current_content.clear();
+                    current_comment = match e.try_get_attribute("comment") {
+                        Ok(Some(attr)) => Some(attr.unescape_value().ok()?.into_owned()),
+                        _ => None,
+                    };
                 }
                 b"result" => {
                     current_content.clear();
@@ -178,6 +196,11 @@ pub fn xml_to_pgn(xml: &str) -> Option<PgnGame> {
                 b"move" => {
                     if in_moves {
                         game.add_move(current_content.trim());
+                        if let (Some(comment), Some(mv)) =
+                            (current_comment.take(), game.moves.last_mut())
+                        {
+                            mv.comment = Some(comment);
+                        }
                     }
                     current_content.clear();
                 }
@@ -200,10 +223,8 @@ pub fn xml_to_pgn(xml: &str) -> Option<PgnGame> {
                     }
                 }
             },
-            Ok(Event::Text(e)) => {
-                if in_tags || in_moves || in_result {
-                    current_content.push_str(e.unescape().ok()?.as_ref());
-                }
+            Ok(Event::Text(e)) if (in_tags || in_moves || in_result) => {
+                current_content.push_str(e.unescape().ok()?.as_ref());
             }
             Ok(Event::Eof) => break,
             Err(e) => {
@@ -369,6 +390,19 @@ mod tests {
         assert_eq!(original.result, parsed.result);
     }
 
+    #[test]
+    fn test_move_comments_roundtrip() {
+        let mut original = PgnGame::new();
+        original.add_move("h2e2");
+        original.add_move("h9g7");
+        original.moves[0].comment = Some("Central cannon <中炮> & \"more\"".to_string());
+
+        let xml = pgn_to_xml(&original);
+        assert!(xml.contains("<move>h9g7</move>"));
+        let parsed = xml_to_pgn(&xml).unwrap();
+        assert_eq!(parsed.moves, original.moves);
+    }
+
     #[test]
     fn test_save_content() {
         use std::fs;
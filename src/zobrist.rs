@@ -0,0 +1,128 @@
+//! Zobrist hashing of positions
+//!
+//! Each (piece, square) pair and the side to move get a fixed random key;
+//! a position's hash is the XOR of the keys present. Equal positions always
+//! hash equal, which is what repetition detection needs, and positions
+//! reached by different move orders (transpositions) are recognized.
+//!
+//! A move changes the hash by XORing out the moving and captured pieces'
+//! old keys and XORing in the new one, so [`Board`] keeps its hash up to
+//! date as pieces move instead of recomputing it (see [`Board::hash`]).
+
+use crate::board::Board;
+use crate::types::{Color, Piece, PieceType, Position};
+
+const SQUARES: usize = 90;
+const PIECE_KINDS: usize = 14;
+
+/// splitmix64, used to fill the key table at compile time
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+const fn build_keys() -> [[u64; SQUARES]; PIECE_KINDS] {
+    let mut keys = [[0; SQUARES]; PIECE_KINDS];
+    let mut state = 0x5851_F42D_4C95_7F2D;
+    let mut kind = 0;
+    while kind < PIECE_KINDS {
+        let mut square = 0;
+        while square < SQUARES {
+            let (next, key) = splitmix64(state);
+            state = next;
+            keys[kind][square] = key;
+            square += 1;
+        }
+        kind += 1;
+    }
+    keys
+}
+
+static PIECE_KEYS: [[u64; SQUARES]; PIECE_KINDS] = build_keys();
+
+/// XORed in when Black is to move
+const BLACK_TO_MOVE: u64 = 0xA3F9_4C1B_67D2_0E85;
+
+fn piece_index(piece: Piece) -> usize {
+    let kind = match piece.piece_type {
+        PieceType::General => 0,
+        PieceType::Advisor => 1,
+        PieceType::Elephant => 2,
+        PieceType::Horse => 3,
+        PieceType::Chariot => 4,
+        PieceType::Cannon => 5,
+        PieceType::Soldier => 6,
+    };
+    match piece.color {
+        Color::Red => kind,
+        Color::Black => kind + 7,
+    }
+}
+
+/// Key for a piece standing on a square
+pub fn piece_key(piece: Piece, pos: Position) -> u64 {
+    PIECE_KEYS[piece_index(piece)][pos.y * 9 + pos.x]
+}
+
+/// Hash a position: the pieces on the board and the side to move
+///
+/// This is the board's own hash with the side to move mixed in, so it
+/// costs nothing to compute.
+///
+/// # Examples
+/// ```
+/// use cn_chess_tui::{zobrist, Board, Color};
+///
+/// let board = Board::new();
+/// assert_eq!(zobrist::hash(&board, Color::Red), zobrist::hash(&board.clone(), Color::Red));
+/// assert_ne!(zobrist::hash(&board, Color::Red), zobrist::hash(&board, Color::Black));
+/// ```
+pub fn hash(board: &Board, turn: Color) -> u64 {
+    match turn {
+        Color::Red => board.hash(),
+        Color::Black => board.hash() ^ BLACK_TO_MOVE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    /// Hash computed from scratch, to check the board's running hash
+    fn full_hash(board: &Board) -> u64 {
+        board
+            .pieces()
+            .fold(0, |acc, (pos, piece)| acc ^ piece_key(piece, pos))
+    }
+
+    #[test]
+    fn test_board_hash_follows_moves() {
+        let mut board = Board::new();
+        let mut side = Color::Red;
+        // Play the first legal move for each side, captures included
+        for _ in 0..40 {
+            let moves = board.legal_moves(side);
+            let Some(&(from, to)) = moves
+                .iter()
+                .find(|&&(_, to)| board.get(to).is_some())
+                .or(moves.first())
+            else {
+                break;
+            };
+            board.move_piece(from, to);
+            assert_eq!(board.hash(), full_hash(&board));
+            side = side.opponent();
+        }
+
+        let pos = Position::from_xy(4, 4);
+        board.place_piece(pos, Piece::red(PieceType::Chariot));
+        board.place_piece(pos, Piece::black(PieceType::Horse));
+        assert_eq!(board.hash(), full_hash(&board));
+        board.remove_piece(pos);
+        assert_eq!(board.hash(), full_hash(&board));
+    }
+}
@@ -1,7 +1,6 @@
 use cn_chess_tui::game::GameController;
 use std::env;
 use std::fs;
-use std::io::Write;
 
 #[test]
 #[cfg(unix)]
@@ -61,3 +60,474 @@ done
         }
     }
 }
+
+#[test]
+#[cfg(unix)]
+fn test_engine_analysis_follows_position() {
+    use cn_chess_tui::Position;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    let dir = tempfile::tempdir().unwrap();
+    let script_path = dir.path().join("mock_analysis_engine.sh");
+    let script_content = r#"#!/bin/bash
+while read line; do
+  case "$line" in
+    ucci)
+      echo "id name MockEngine"
+      echo "ucciok"
+      ;;
+    isready)
+      echo "readyok"
+      ;;
+    position*)
+      fen="$line"
+      ;;
+    "go infinite")
+      case "$fen" in
+        *" w "*) echo "info depth 5 score 20 pv h2e2 h9g7" ;;
+        *) echo "info depth 6 score -15 pv h9g7" ;;
+      esac
+      ;;
+    stop)
+      echo "bestmove h2e2"
+      ;;
+    quit)
+      exit 0
+      ;;
+  esac
+done
+"#;
+    fs::write(&script_path, script_content).unwrap();
+    let mut perms = fs::metadata(&script_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&script_path, perms).unwrap();
+
+    let mut controller = GameController::new();
+    assert!(controller.start_analysis().is_err(), "needs an engine");
+    controller
+        .init_engine(script_path.to_str().unwrap())
+        .unwrap();
+
+    let wait_for_depth = |controller: &mut GameController, depth: u32| {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while controller.analysis().and_then(|info| info.depth) != Some(depth) {
+            assert!(Instant::now() < deadline, "no analysis at depth {}", depth);
+            controller.poll_analysis().unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    };
+
+    controller.start_analysis().unwrap();
+    assert!(controller.is_analyzing());
+    wait_for_depth(&mut controller, 5);
+    assert_eq!(controller.analysis().unwrap().pv, ["h2e2", "h9g7"]);
+    assert_eq!(
+        controller.expected_line(),
+        [
+            (Position::from_xy(7, 2), Position::from_xy(4, 2)),
+            (Position::from_xy(7, 9), Position::from_xy(6, 7)),
+        ]
+    );
+
+    // A move restarts the search on the new position
+    controller
+        .human_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    assert!(controller.expected_line().is_empty());
+    wait_for_depth(&mut controller, 6);
+    assert_eq!(controller.analysis().unwrap().score, Some(-15));
+
+    controller.stop_analysis().unwrap();
+    assert!(!controller.is_analyzing());
+    assert!(controller.analysis().is_none());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_engine_ponders_on_the_expected_reply() {
+    use cn_chess_tui::game::{AiConfig, AiMode};
+    use cn_chess_tui::Position;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    // Plays the central cannon expecting the horse reply, and answers that
+    // with a pawn move once told it was played
+    let dir = tempfile::tempdir().unwrap();
+    let script_path = dir.path().join("mock_ponder_engine.sh");
+    let script_content = r#"#!/bin/bash
+while read line; do
+  case "$line" in
+    ucci)
+      echo "id name MockEngine"
+      echo "ucciok"
+      ;;
+    isready)
+      echo "readyok"
+      ;;
+    ponderhit)
+      hit=1
+      ;;
+    stop)
+      if [ -n "$hit" ]; then
+        echo "bestmove a7a6"
+      else
+        echo "bestmove h8e8 ponder h1g3"
+      fi
+      ;;
+    quit)
+      exit 0
+      ;;
+  esac
+done
+"#;
+    fs::write(&script_path, script_content).unwrap();
+    let mut perms = fs::metadata(&script_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&script_path, perms).unwrap();
+
+    let start = |ponder: bool| {
+        let mut controller = GameController::new();
+        // The engine searches until it is stopped
+        controller.set_ai_config(AiConfig {
+            ponder,
+            engine_timeout_ms: 100,
+            ..AiConfig::default()
+        });
+        controller
+            .init_engine(script_path.to_str().unwrap())
+            .unwrap();
+        controller.set_ai_mode(AiMode::PlaysRed);
+        controller
+    };
+    let ai_move = |controller: &mut GameController| {
+        controller.trigger_ai_move().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(mv) = controller.check_engine_response().unwrap() {
+                return mv;
+            }
+            assert!(Instant::now() < deadline, "no move from the engine");
+        }
+    };
+
+    // Pondering is off by default
+    let mut controller = start(false);
+    ai_move(&mut controller);
+    assert!(!controller.is_pondering());
+
+    // The expected reply is played: the engine carries on searching
+    let mut controller = start(true);
+    assert_eq!(
+        ai_move(&mut controller),
+        (Position::from_xy(7, 7), Position::from_xy(4, 7))
+    );
+    assert!(controller.is_pondering());
+    assert!(!controller.is_engine_thinking());
+    controller
+        .human_move(Position::from_xy(7, 0), Position::from_xy(6, 2))
+        .unwrap();
+    assert!(!controller.is_pondering());
+    assert!(controller.is_engine_thinking());
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mv = loop {
+        if let Some(mv) = controller.check_engine_response().unwrap() {
+            break mv;
+        }
+        assert!(Instant::now() < deadline, "no move from the engine");
+    };
+    assert_eq!(mv, (Position::from_xy(0, 6), Position::from_xy(0, 5)));
+
+    // Another reply is played: the pondering search is thrown away
+    let mut controller = start(true);
+    ai_move(&mut controller);
+    controller
+        .human_move(Position::from_xy(1, 0), Position::from_xy(2, 2))
+        .unwrap();
+    assert!(!controller.is_pondering());
+    assert!(!controller.is_engine_thinking());
+    controller.trigger_ai_move().unwrap();
+    assert!(controller.is_engine_thinking());
+
+    // Taking the move back stops it too
+    let mut controller = start(true);
+    ai_move(&mut controller);
+    assert!(controller.undo_move());
+    assert!(!controller.is_pondering());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_silent_engine_falls_back_to_a_random_move() {
+    use cn_chess_tui::game::{AiConfig, AiMode};
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    // Never gives a move, not even when told to stop
+    let dir = tempfile::tempdir().unwrap();
+    let script_path = dir.path().join("mock_silent_engine.sh");
+    let script_content = r#"#!/bin/bash
+while read line; do
+  case "$line" in
+    ucci)
+      echo "id name MockEngine"
+      echo "ucciok"
+      ;;
+    isready)
+      echo "readyok"
+      ;;
+    quit)
+      exit 0
+      ;;
+  esac
+done
+"#;
+    fs::write(&script_path, script_content).unwrap();
+    let mut perms = fs::metadata(&script_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&script_path, perms).unwrap();
+
+    let mut controller = GameController::new();
+    controller.set_ai_config(AiConfig {
+        engine_timeout_ms: 100,
+        ..AiConfig::default()
+    });
+    controller
+        .init_engine(script_path.to_str().unwrap())
+        .unwrap();
+    controller.set_ai_mode(AiMode::PlaysRed);
+    controller.trigger_ai_move().unwrap();
+
+    // Not stopped before the timeout
+    assert_eq!(controller.check_engine_response().unwrap(), None);
+    assert!(controller.is_engine_thinking());
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let (from, to) = loop {
+        if let Some(mv) = controller.check_engine_response().unwrap() {
+            break mv;
+        }
+        assert!(Instant::now() < deadline, "no fallback move");
+    };
+    assert!(!controller.is_engine_thinking());
+    assert_eq!(controller.get_moves().len(), 1);
+    assert_eq!(controller.get_moves()[0].from, from);
+    assert_eq!(controller.get_moves()[0].to, to);
+    assert!(controller.last_search().is_none());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_side_engine_plays_only_its_side() {
+    use cn_chess_tui::game::{AiConfig, AiMode};
+    use cn_chess_tui::types::{Color, Position};
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    // Always answers with Black's left horse move
+    let dir = tempfile::tempdir().unwrap();
+    let script_path = dir.path().join("mock_black_engine.sh");
+    let script_content = r#"#!/bin/bash
+while read line; do
+  case "$line" in
+    ucci)
+      echo "id name MockEngine"
+      echo "ucciok"
+      ;;
+    isready)
+      echo "readyok"
+      ;;
+    go*)
+      echo "info depth 3 score 5 pv b1c3"
+      echo "bestmove b1c3"
+      ;;
+    quit)
+      exit 0
+      ;;
+  esac
+done
+"#;
+    fs::write(&script_path, script_content).unwrap();
+    let mut perms = fs::metadata(&script_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&script_path, perms).unwrap();
+
+    let mut config = AiConfig {
+        opening_book: false,
+        search_depth: 1,
+        ..AiConfig::default()
+    };
+    config.black.engine_path = Some(script_path.clone());
+    let mut controller = GameController::new();
+    controller.set_ai_config(config);
+    assert!(controller.init_configured_engine().unwrap());
+    assert!(controller.has_engine());
+    controller.set_ai_mode(AiMode::PlaysBoth);
+
+    // Red has no engine of its own or shared one: the built-in AI plays it
+    controller.trigger_ai_move().unwrap();
+    controller.check_engine_response().unwrap().unwrap();
+    assert_eq!(controller.last_search().unwrap().1.depth, Some(1));
+
+    controller.trigger_ai_move().unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let (from, to) = loop {
+        if let Some(mv) = controller.check_engine_response().unwrap() {
+            break mv;
+        }
+        assert!(Instant::now() < deadline, "no move from the engine");
+    };
+    assert_eq!(
+        (from, to),
+        (Position::from_xy(1, 0), Position::from_xy(2, 2))
+    );
+    assert_eq!(controller.turn(), Color::Red);
+    assert_eq!(controller.last_search().unwrap().1.depth, Some(3));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_engine_multipv_analysis_and_hints() {
+    use cn_chess_tui::game::AiConfig;
+    use cn_chess_tui::Position;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, Instant};
+
+    // Searches as many lines as it was told; the hint search settles on
+    // the horse move although its first line was the cannon's
+    let dir = tempfile::tempdir().unwrap();
+    let script_path = dir.path().join("mock_multipv_engine.sh");
+    let script_content = r#"#!/bin/bash
+lines=1
+while read line; do
+  case "$line" in
+    ucci)
+      echo "id name MockEngine"
+      echo "option multipv type spin min 1 max 8 default 1"
+      echo "ucciok"
+      ;;
+    isready)
+      echo "readyok"
+      ;;
+    "setoption multipv "*)
+      lines=${line##* }
+      echo "$lines" >> "$0.log"
+      ;;
+    "go infinite"|"go time "*)
+      echo "info depth 9 multipv 1 score 30 pv h2e2 h9g7"
+      [ "$lines" -ge 2 ] && echo "info depth 9 multipv 2 score 15 pv h0g2"
+      case "$line" in
+        "go time "*) echo "bestmove h0g2" ;;
+      esac
+      ;;
+    stop)
+      echo "bestmove h2e2"
+      ;;
+    quit)
+      exit 0
+      ;;
+  esac
+done
+"#;
+    fs::write(&script_path, script_content).unwrap();
+    let mut perms = fs::metadata(&script_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&script_path, perms).unwrap();
+
+    let mut controller = GameController::new();
+    controller.set_ai_config(AiConfig {
+        multipv: 2,
+        ..AiConfig::default()
+    });
+    controller
+        .init_engine(script_path.to_str().unwrap())
+        .unwrap();
+
+    let cannon = (Position::from_xy(7, 2), Position::from_xy(4, 2));
+    let horse = (Position::from_xy(7, 0), Position::from_xy(6, 2));
+    let hint = controller.hint_candidates().unwrap();
+    let moves: Vec<_> = hint.iter().map(|c| ((c.from, c.to), c.score)).collect();
+    assert_eq!(moves, [(horse, Some(15)), (cannon, Some(30))]);
+
+    controller.start_analysis().unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while controller.analysis_lines().len() < 2 {
+        assert!(Instant::now() < deadline, "no second line");
+        controller.poll_analysis().unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    // The best line is the analysis, whichever line came in last
+    assert_eq!(controller.analysis().unwrap().score, Some(30));
+    assert_eq!(controller.analysis_lines()[1].pv, ["h0g2"]);
+    let hint = controller.hint_candidates().unwrap();
+    assert_eq!((hint[0].from, hint[0].to), cannon);
+
+    // The AI's own searches are back to one line
+    controller.stop_analysis().unwrap();
+    let log_path = format!("{}.log", script_path.display());
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let log = fs::read_to_string(&log_path).unwrap_or_default();
+        if log.lines().count() >= 4 || Instant::now() > deadline {
+            assert_eq!(log.lines().collect::<Vec<_>>(), ["2", "1", "2", "1"]);
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn test_engine_move_is_announced_before_it_is_played() {
+    use cn_chess_tui::game::AiMode;
+    use cn_chess_tui::Position;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let script_path = dir.path().join("mock_announcing_engine.sh");
+    let script_content = r#"#!/bin/bash
+while read line; do
+  case "$line" in
+    ucci)
+      echo "id name MockEngine"
+      echo "ucciok"
+      ;;
+    isready)
+      echo "readyok"
+      ;;
+    go*)
+      echo "bestmove h8e8"
+      ;;
+    quit)
+      exit 0
+      ;;
+  esac
+done
+"#;
+    fs::write(&script_path, script_content).unwrap();
+    let mut perms = fs::metadata(&script_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    fs::set_permissions(&script_path, perms).unwrap();
+
+    let mut controller = GameController::new();
+    controller
+        .init_engine(script_path.to_str().unwrap())
+        .unwrap();
+    controller.set_ai_mode(AiMode::PlaysRed);
+    let moves = controller.subscribe_ai_moves();
+
+    // The move comes from the thread reading the engine, without polling
+    controller.trigger_ai_move().unwrap();
+    let mv = moves.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(
+        (mv.from, mv.to),
+        (Position::from_xy(7, 7), Position::from_xy(4, 7))
+    );
+    assert_eq!(controller.game().move_count(), 0);
+    assert!(controller.has_engine());
+
+    assert!(controller.play_engine_move(mv.from, mv.to).unwrap());
+    assert_eq!(controller.game().last_move(), Some(mv));
+    assert!(!controller.is_engine_thinking());
+}
@@ -5,7 +5,7 @@
 //! - Soldier movement rules
 //! - Flying general rule
 
-use cn_chess_tui::board::Board;
+use cn_chess_tui::board::{Board, IllegalMoveReason};
 use cn_chess_tui::types::{Color, Piece, PieceType, Position};
 
 /// Test that the initial board setup is correct
@@ -60,7 +60,7 @@ fn test_soldier_forward_move_red() {
     let from = Position::from_xy(4, 5);
     let to = Position::from_xy(4, 4);
     assert!(
-        board.is_legal_move(from, to),
+        board.is_legal_move(from, to).is_ok(),
         "Red soldier should move forward from (4,5) to (4,4)"
     );
 }
@@ -78,7 +78,7 @@ fn test_soldier_forward_move_black() {
     let from = Position::from_xy(4, 4);
     let to = Position::from_xy(4, 5);
     assert!(
-        board.is_legal_move(from, to),
+        board.is_legal_move(from, to).is_ok(),
         "Black soldier should move forward from (4,4) to (4,5)"
     );
 }
@@ -95,8 +95,9 @@ fn test_soldier_cannot_move_backward_red() {
     // Should NOT be able to move backward (increasing Y for red)
     let from = Position::from_xy(4, 5);
     let to = Position::from_xy(4, 6);
-    assert!(
-        !board.is_legal_move(from, to),
+    assert_eq!(
+        board.is_legal_move(from, to),
+        Err(IllegalMoveReason::SoldierBackward),
         "Red soldier should NOT move backward from (4,5) to (4,6)"
     );
 }
@@ -113,8 +114,9 @@ fn test_soldier_cannot_move_backward_black() {
     // Should NOT be able to move backward (decreasing Y for black)
     let from = Position::from_xy(4, 4);
     let to = Position::from_xy(4, 3);
-    assert!(
-        !board.is_legal_move(from, to),
+    assert_eq!(
+        board.is_legal_move(from, to),
+        Err(IllegalMoveReason::SoldierBackward),
         "Black soldier should NOT move backward from (4,4) to (4,3)"
     );
 }
@@ -182,8 +184,9 @@ fn test_flying_general_move_is_illegal() {
 
     // Moving the chariot away should cause flying general
     // Try moving chariot from (3,4) to (2,4)
-    assert!(
-        !board.is_legal_move(Position::from_xy(3, 4), Position::from_xy(2, 4)),
+    assert_eq!(
+        board.is_legal_move(Position::from_xy(3, 4), Position::from_xy(2, 4)),
+        Err(IllegalMoveReason::FlyingGeneral),
         "Moving chariot should not cause flying general"
     );
 }
@@ -203,11 +206,11 @@ fn test_soldier_sideways_after_crossing_river() {
     let to_right = Position::from_xy(5, 4);
 
     assert!(
-        board.is_legal_move(from, to_left),
+        board.is_legal_move(from, to_left).is_ok(),
         "Red soldier should move sideways left after crossing river"
     );
     assert!(
-        board.is_legal_move(from, to_right),
+        board.is_legal_move(from, to_right).is_ok(),
         "Red soldier should move sideways right after crossing river"
     );
 }
@@ -225,8 +228,172 @@ fn test_soldier_cannot_move_sideways_before_crossing_river() {
     let from = Position::from_xy(4, 6);
     let to = Position::from_xy(5, 6);
 
-    assert!(
-        !board.is_legal_move(from, to),
+    assert_eq!(
+        board.is_legal_move(from, to),
+        Err(IllegalMoveReason::SoldierSidewaysBeforeRiver),
         "Red soldier should NOT move sideways before crossing river"
     );
 }
+
+/// Test that illegal moves say which rule they break
+#[test]
+fn test_illegal_move_reasons() {
+    let mut board = Board::new();
+    board.place_piece(Position::from_xy(2, 5), Piece::red(PieceType::Elephant));
+    let reason = |board: &Board, from: (usize, usize), to: (usize, usize)| {
+        board
+            .is_legal_move(
+                Position::from_xy(from.0, from.1),
+                Position::from_xy(to.0, to.1),
+            )
+            .unwrap_err()
+    };
+
+    assert_eq!(reason(&board, (4, 4), (4, 5)), IllegalMoveReason::NoPiece);
+    assert_eq!(reason(&board, (0, 9), (0, 6)), IllegalMoveReason::OwnPiece);
+    assert_eq!(
+        reason(&board, (0, 9), (0, 5)),
+        IllegalMoveReason::PathBlocked
+    );
+    assert_eq!(
+        reason(&board, (1, 9), (1, 8)),
+        IllegalMoveReason::WrongPattern(PieceType::Horse)
+    );
+    assert_eq!(
+        reason(&board, (1, 9), (3, 8)),
+        IllegalMoveReason::HorseLegBlocked
+    );
+    assert_eq!(
+        reason(&board, (3, 9), (2, 8)),
+        IllegalMoveReason::LeavesPalace
+    );
+    assert_eq!(
+        reason(&board, (2, 5), (4, 3)),
+        IllegalMoveReason::ElephantCrossesRiver
+    );
+    assert_eq!(
+        reason(&board, (7, 7), (7, 2)),
+        IllegalMoveReason::CannonNeedsScreen
+    );
+
+    board.place_piece(Position::from_xy(1, 8), Piece::red(PieceType::Soldier));
+    assert_eq!(
+        reason(&board, (2, 9), (0, 7)),
+        IllegalMoveReason::ElephantEyeBlocked
+    );
+
+    let mut board = Board::from_pieces(Default::default());
+    board.place_piece(Position::from_xy(4, 9), Piece::red(PieceType::General));
+    board.place_piece(Position::from_xy(4, 7), Piece::red(PieceType::Chariot));
+    board.place_piece(Position::from_xy(4, 5), Piece::black(PieceType::Chariot));
+    board.place_piece(Position::from_xy(4, 0), Piece::black(PieceType::General));
+    assert_eq!(
+        reason(&board, (4, 7), (3, 7)),
+        IllegalMoveReason::LeavesGeneralInCheck
+    );
+}
+
+/// Test per-piece mobility counts in the initial position
+#[test]
+fn test_initial_mobility() {
+    let game = cn_chess_tui::Game::new();
+    let mobility = game.mobility(Color::Red);
+
+    assert_eq!(mobility.total(), game.board().legal_moves(Color::Red).len());
+    assert_eq!(game.legal_move_count(Color::Black), mobility.total());
+    // Each cannon can slide along its rank and file
+    assert_eq!(mobility.at(Position::from_xy(1, 7)), 12);
+    // Soldiers have exactly one move each before crossing the river
+    assert_eq!(mobility.by_type(PieceType::Soldier), 5);
+    assert_eq!(mobility.at(Position::from_xy(4, 4)), 0);
+}
+
+/// Test that a pinned piece has no mobility
+#[test]
+fn test_pinned_piece_mobility() {
+    let game = cn_chess_tui::Game::from_fen("4k4/9/9/9/4r4/9/9/4R4/9/4K4 w - - 0 1").unwrap();
+    let mobility = game.mobility(Color::Red);
+
+    // The chariot may only move along the e-file while pinned
+    assert_eq!(mobility.at(Position::from_xy(4, 7)), 4);
+    assert_eq!(mobility.by_type(PieceType::Chariot), 4);
+}
+
+/// Test that free turn allows consecutive moves but keeps piece rules
+#[test]
+fn test_free_turn_consecutive_moves() {
+    let mut game = cn_chess_tui::Game::new();
+    assert!(game
+        .make_move(Position::from_xy(1, 2), Position::from_xy(4, 2))
+        .is_err());
+
+    game.set_free_turn(true);
+    // Black moves first, then Red twice in a row
+    game.make_move(Position::from_xy(1, 2), Position::from_xy(4, 2))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 9), Position::from_xy(6, 7))
+        .unwrap();
+    assert_eq!(game.turn(), Color::Black);
+
+    // Movement rules still apply: a horse cannot move straight
+    assert!(game
+        .make_move(Position::from_xy(1, 9), Position::from_xy(1, 7))
+        .is_err());
+
+    // Undo hands the turn back to the side that moved
+    game.undo_move();
+    assert_eq!(game.turn(), Color::Red);
+    game.undo_move();
+    game.undo_move();
+    assert_eq!(game.turn(), Color::Black);
+
+    game.set_free_turn(false);
+    assert!(game
+        .make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .is_err());
+}
+
+#[test]
+fn test_redo_restores_captures() {
+    let mut game = cn_chess_tui::Game::new();
+    // Cannon takes the knight on b0, the chariot takes back
+    game.make_move(Position::from_xy(1, 7), Position::from_xy(1, 0))
+        .unwrap();
+    game.make_move(Position::from_xy(0, 0), Position::from_xy(1, 0))
+        .unwrap();
+    let fen = game.to_fen();
+
+    assert!(game.undo_move());
+    assert!(game.undo_move());
+    assert!(game.board().get(Position::from_xy(1, 0)).is_some());
+    assert!(game.redo_move());
+    assert!(game.redo_move());
+    assert!(!game.redo_move());
+    assert_eq!(game.to_fen(), fen);
+    assert_eq!(game.board().get(Position::from_xy(0, 0)), None);
+    assert_eq!(game.turn(), Color::Red);
+}
+
+#[test]
+fn test_new_move_discards_redo() {
+    let mut game = cn_chess_tui::Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 0), Position::from_xy(6, 2))
+        .unwrap();
+    game.undo_move();
+    game.undo_move();
+
+    // Replaying the undone move by hand keeps the rest redoable
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    assert!(game.can_redo());
+
+    // A different move starts a new line
+    game.make_move(Position::from_xy(1, 0), Position::from_xy(2, 2))
+        .unwrap();
+    assert!(!game.can_redo());
+    assert!(!game.redo_move());
+}
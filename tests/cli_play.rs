@@ -0,0 +1,95 @@
+//! Tests for headless play on standard input and output
+
+use cn_chess_tui::cli;
+use cn_chess_tui::notation::format::MoveStyle;
+use cn_chess_tui::{AiMode, GameController};
+
+fn play(controller: &mut GameController, style: MoveStyle, input: &str) -> Vec<String> {
+    let mut output = Vec::new();
+    cli::run(controller, style, input.as_bytes(), &mut output).unwrap();
+    // Leave out the board drawing
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.starts_with(['┌', '├', '│', '└']))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn test_moves_in_every_notation() {
+    let mut controller = GameController::new();
+    let output = play(
+        &mut controller,
+        MoveStyle::Wxf,
+        "# Central cannon\nh7e7\n马8进7\nH2+3\n",
+    );
+    assert_eq!(
+        output,
+        [
+            "Red to move",
+            "1. C2.5 (h7e7)",
+            "Black to move",
+            "1... H8+7 (h0g2)",
+            "Red to move",
+            "2. H2+3 (h9g7)",
+            "Black to move",
+        ]
+    );
+}
+
+#[test]
+fn test_errors_do_not_end_the_session() {
+    let mut controller = GameController::new();
+    let output = play(
+        &mut controller,
+        MoveStyle::Iccs,
+        "hello\nC3.5\n帅五进二\na6a4\nundo\nundo\nh7e7\nquit\nh0g2\n",
+    );
+    assert_eq!(
+        output,
+        [
+            "Red to move",
+            "Error: not a move or command: hello (try help)",
+            "Error: no legal WXF move matches C3.5",
+            "Error: no legal 中文 Chinese move matches 帅五进二",
+            "Error: a6a4: A soldier moves one point at a time",
+            "Error: no move to take back",
+            "Error: no move to take back",
+            "1. h7e7",
+            "Black to move",
+        ]
+    );
+    assert_eq!(controller.get_moves().len(), 1);
+}
+
+#[test]
+fn test_game_over_is_reported() {
+    let mut controller = GameController::from_fen("3k5/8R/9/9/9/9/9/9/9/R3K4 w - - 0 1").unwrap();
+    let output = play(&mut controller, MoveStyle::Iccs, "a9a0\ni1i2\n");
+    assert_eq!(
+        output,
+        [
+            "Red to move",
+            "1. a9a0",
+            "Game over: Checkmate - Red Wins",
+            "Error: i1i2: Game is over: Red Wins",
+        ]
+    );
+}
+
+#[test]
+fn test_ai_answers_and_undo_takes_back_both_moves() {
+    let mut controller = GameController::new();
+    let output = play(&mut controller, MoveStyle::Iccs, "ai black\nh7e7\n");
+    assert_eq!(output[1], "AI: black");
+    assert_eq!(output[2], "1. h7e7");
+    assert!(output[4].starts_with("1... "), "no reply in {:?}", output);
+    assert_eq!(output[5], "Red to move");
+    assert_eq!(controller.ai_mode(), AiMode::PlaysBlack);
+    assert_eq!(controller.get_moves().len(), 2);
+
+    let output = play(&mut controller, MoveStyle::Iccs, "undo\n");
+    assert_eq!(output, ["Red to move", "Red to move"]);
+    assert!(controller.get_moves().is_empty());
+}
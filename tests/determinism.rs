@@ -0,0 +1,106 @@
+//! Seeded randomness reproduces the same behavior in the TUI controller and
+//! in headless commands
+
+use cn_chess_tui::game::{AiConfig, AiMode, Game, GameController};
+use cn_chess_tui::iccs_to_move;
+use cn_chess_tui::report::varied_move_report;
+use cn_chess_tui::rng::SeededRng;
+
+const DEPTH: u32 = 1;
+const VARIETY: i32 = 40;
+const BLUNDER_RATE: u32 = 20;
+const PLIES: usize = 6;
+
+/// Let the AI play both sides through the controller, as the TUI does
+fn controller_game(seed: u64) -> Vec<String> {
+    let mut controller = GameController::new();
+    controller.set_ai_mode(AiMode::PlaysBoth);
+    controller.set_ai_config(AiConfig {
+        search_depth: DEPTH,
+        variety: VARIETY,
+        blunder_rate: BLUNDER_RATE,
+        seed: Some(seed),
+        // Headless play has no book; compare the searches alone
+        opening_book: false,
+        ..AiConfig::default()
+    });
+
+    play(&mut controller)
+}
+
+fn play(controller: &mut GameController) -> Vec<String> {
+    for _ in 0..PLIES {
+        controller.trigger_ai_move().unwrap();
+        controller.check_engine_response().unwrap().unwrap();
+    }
+    controller.game().get_moves_with_iccs()
+}
+
+/// Play the same game through the headless report API
+fn headless_game(seed: u64) -> Vec<String> {
+    let mut game = Game::new();
+    let mut rng = SeededRng::new(seed);
+    for _ in 0..PLIES {
+        let report = varied_move_report(&game, DEPTH, VARIETY, BLUNDER_RATE, &mut rng);
+        let (from, to) = iccs_to_move(&report.best_move.unwrap()).unwrap();
+        game.make_move(from, to).unwrap();
+    }
+    game.get_moves_with_iccs()
+}
+
+#[test]
+fn test_same_seed_same_game() {
+    assert_eq!(controller_game(11), controller_game(11));
+    assert_eq!(headless_game(11), headless_game(11));
+}
+
+#[test]
+fn test_controller_matches_headless() {
+    for seed in [1, 2, 3] {
+        assert_eq!(controller_game(seed), headless_game(seed));
+    }
+}
+
+#[test]
+fn test_seeds_vary_play() {
+    let games: Vec<Vec<String>> = (0..4).map(headless_game).collect();
+    assert!(games.iter().any(|g| *g != games[0]));
+}
+
+#[test]
+fn test_reseeding_restarts_sequence() {
+    let mut controller = GameController::new();
+    let config = AiConfig {
+        search_depth: DEPTH,
+        variety: VARIETY,
+        seed: Some(5),
+        ..AiConfig::default()
+    };
+    controller.set_ai_config(config.clone());
+    controller.set_ai_mode(AiMode::PlaysBoth);
+    controller.trigger_ai_move().unwrap();
+    let first = controller.check_engine_response().unwrap();
+
+    let mut replay = GameController::new();
+    replay.set_ai_config(config);
+    replay.set_ai_mode(AiMode::PlaysBoth);
+    replay.trigger_ai_move().unwrap();
+    assert_eq!(replay.check_engine_response().unwrap(), first);
+}
+
+#[test]
+fn test_new_game_replays_seed() {
+    let mut controller = GameController::new();
+    controller.set_ai_mode(AiMode::PlaysBoth);
+    controller.set_ai_config(AiConfig {
+        search_depth: DEPTH,
+        variety: VARIETY,
+        blunder_rate: BLUNDER_RATE,
+        seed: Some(7),
+        ..AiConfig::default()
+    });
+
+    let first = play(&mut controller);
+    controller.load_game(Game::new());
+    assert_eq!(play(&mut controller), first);
+}
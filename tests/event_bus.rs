@@ -0,0 +1,132 @@
+//! Tests for the channel the main loop takes its events from
+
+use cn_chess_tui::bus::{AppEvent, EventBus};
+use cn_chess_tui::online::{OnlineClient, OnlineEvent, Transport};
+use cn_chess_tui::Position;
+use crossterm::event::KeyCode;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A server connection that hands over prepared messages, then fails;
+/// a None stands for a poll that finds nothing
+struct Script(VecDeque<Option<String>>);
+
+impl Transport for Script {
+    fn send(&mut self, _text: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn try_recv(&mut self) -> io::Result<Option<String>> {
+        self.0
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::ConnectionReset, "closed"))
+    }
+}
+
+#[test]
+fn test_events_arrive_in_the_order_they_were_sent() {
+    let bus = EventBus::new();
+    let sender = bus.sender();
+    let (from, to) = (Position::from_xy(7, 7), Position::from_xy(4, 7));
+    sender.send(AppEvent::Key(KeyCode::Char('u'))).unwrap();
+    sender
+        .send(AppEvent::NetMove(OnlineEvent::OpponentMoved { from, to }))
+        .unwrap();
+    sender.send(AppEvent::ClockTick).unwrap();
+
+    assert_eq!(
+        bus.pending().collect::<Vec<_>>(),
+        [
+            AppEvent::Key(KeyCode::Char('u')),
+            AppEvent::NetMove(OnlineEvent::OpponentMoved { from, to }),
+            AppEvent::ClockTick,
+        ]
+    );
+    assert_eq!(bus.next(Duration::from_millis(10)), None);
+}
+
+#[test]
+fn test_events_can_come_from_other_threads() {
+    let bus = EventBus::new();
+    let sender = bus.sender();
+    thread::spawn(move || sender.send(AppEvent::Resize(80, 25)).unwrap());
+
+    assert_eq!(
+        bus.next(Duration::from_secs(5)),
+        Some(AppEvent::Resize(80, 25))
+    );
+}
+
+#[test]
+fn test_ticker_sends_its_event_repeatedly() {
+    let bus = EventBus::new();
+    bus.spawn_ticker(Duration::from_millis(5), AppEvent::Tick);
+
+    for _ in 0..3 {
+        assert_eq!(bus.next(Duration::from_secs(5)), Some(AppEvent::Tick));
+    }
+}
+
+#[test]
+fn test_ticker_stops_when_the_bus_is_dropped() {
+    let bus = EventBus::new();
+    let ticker = bus.spawn_ticker(Duration::from_millis(5), AppEvent::ClockTick);
+    assert_eq!(bus.next(Duration::from_secs(5)), Some(AppEvent::ClockTick));
+    drop(bus);
+
+    ticker.join().unwrap();
+}
+
+#[test]
+fn test_forwarded_channel_arrives_as_events() {
+    let bus = EventBus::new();
+    let (moves, receiver) = mpsc::channel();
+    let forward = bus.forward(receiver, |(from, to)| AppEvent::EngineMove { from, to });
+    let (from, to) = (Position::from_xy(7, 7), Position::from_xy(4, 7));
+    moves.send((from, to)).unwrap();
+
+    assert_eq!(
+        bus.next(Duration::from_secs(5)),
+        Some(AppEvent::EngineMove { from, to })
+    );
+    drop(moves);
+    forward.join().unwrap();
+}
+
+#[test]
+fn test_online_thread_sends_what_the_server_says() {
+    let bus = EventBus::new();
+    let script = Script(VecDeque::from([
+        Some(r#"{"type":"welcome","name":"alice"}"#.to_string()),
+        None,
+    ]));
+    let client = Arc::new(Mutex::new(OnlineClient::new(script)));
+    let online = bus.spawn_online(Arc::downgrade(&client), Duration::from_millis(5));
+
+    assert_eq!(
+        bus.next(Duration::from_secs(5)),
+        Some(AppEvent::NetMove(OnlineEvent::LoggedIn(
+            "alice".to_string()
+        )))
+    );
+    // The connection then fails, which ends the thread
+    assert!(matches!(
+        bus.next(Duration::from_secs(5)),
+        Some(AppEvent::NetError(_))
+    ));
+    online.join().unwrap();
+}
+
+#[test]
+fn test_online_thread_stops_when_the_client_is_dropped() {
+    let bus = EventBus::new();
+    let client = Arc::new(Mutex::new(OnlineClient::new(Script(VecDeque::new()))));
+    let online = bus.spawn_online(Arc::downgrade(&client), Duration::from_millis(5));
+    drop(client);
+
+    online.join().unwrap();
+    assert_eq!(bus.next(Duration::from_millis(50)), None);
+}
@@ -383,3 +383,68 @@ fn test_export_fen_with_moves_multiple_captures() {
     // the board state after the capture, and then we replay the remaining moves
     assert_eq!(parsed.get_moves().len(), 1);
 }
+
+#[test]
+fn test_halfmove_clock_in_fen() {
+    // Red chariot can capture the black soldier on a4
+    let mut game = Game::from_fen("4k4/9/9/9/p8/9/9/9/R8/3K5 w - - 7 20").unwrap();
+    assert_eq!(game.halfmove_clock(), 7);
+
+    game.make_move(Position::from_xy(0, 8), Position::from_xy(0, 7))
+        .unwrap();
+    assert_eq!(game.halfmove_clock(), 8);
+    assert!(game.to_fen().contains(" b - - 8 "));
+
+    game.make_move(Position::from_xy(4, 0), Position::from_xy(4, 1))
+        .unwrap();
+    game.make_move(Position::from_xy(0, 7), Position::from_xy(0, 4))
+        .unwrap();
+    assert_eq!(game.halfmove_clock(), 0);
+
+    // Undoing the capture restores the clock
+    game.undo_move();
+    assert_eq!(game.halfmove_clock(), 9);
+}
+
+#[test]
+fn test_missing_general_strict_and_permissive() {
+    let no_red = "4k4/9/9/9/9/9/9/9/9/R8 w - - 0 1";
+    assert_eq!(
+        Game::from_fen(no_red).unwrap_err(),
+        FenError::MissingGeneral(Color::Red)
+    );
+
+    let mut game = Game::from_fen_permissive(no_red).unwrap();
+    assert_eq!(
+        game.state(),
+        cn_chess_tui::GameState::MissingGeneral(Color::Red)
+    );
+    assert!(!game.is_in_check());
+    assert!(game
+        .make_move(Position::from_xy(0, 9), Position::from_xy(0, 8))
+        .is_err());
+
+    // An empty board loads too; the side to move is reported
+    let game = Game::from_fen_permissive("9/9/9/9/9/9/9/9/9/9 b - - 0 1").unwrap();
+    assert_eq!(
+        game.state(),
+        cn_chess_tui::GameState::MissingGeneral(Color::Black)
+    );
+}
+
+#[test]
+fn test_general_captured_in_free_analysis() {
+    // Red's chariot checks; with free turn Red moves again and takes the general
+    let mut game = Game::from_fen("3k5/9/9/9/9/9/9/9/9/R3K4 w - - 0 1").unwrap();
+    game.set_free_turn(true);
+    game.make_move(Position::from_xy(0, 9), Position::from_xy(0, 0))
+        .unwrap();
+    game.make_move(Position::from_xy(0, 0), Position::from_xy(3, 0))
+        .unwrap();
+
+    assert_eq!(
+        game.state(),
+        cn_chess_tui::GameState::MissingGeneral(Color::Black)
+    );
+    assert_eq!(game.to_pgn().get_tag("Result").unwrap(), "1-0");
+}
@@ -53,3 +53,210 @@ fn test_should_ai_move_plays_black() {
     );
     assert_eq!(controller.turn(), cn_chess_tui::types::Color::Black);
 }
+
+#[test]
+fn test_last_search_formats_from_its_position() {
+    use cn_chess_tui::game::AiConfig;
+    use cn_chess_tui::notation::format::MoveFormatter;
+
+    let mut controller = GameController::new();
+    controller.set_ai_config(AiConfig {
+        search_depth: 1,
+        opening_book: false,
+        ..AiConfig::default()
+    });
+    controller.set_ai_mode(AiMode::PlaysRed);
+    assert!(controller.last_search().is_none());
+
+    controller.trigger_ai_move().unwrap();
+    let (from, to) = controller.check_engine_response().unwrap().unwrap();
+
+    let (ply, info) = controller.last_search().unwrap();
+    assert_eq!(ply, 0);
+    assert_eq!(info.depth, Some(1));
+    let game = controller.game().at_ply(ply);
+    let line = MoveFormatter::default().format_line(&game, &info.pv);
+    assert_eq!(
+        line,
+        [MoveFormatter::default().format_move(&game, from, to)]
+    );
+    assert!(line[0].ends_with(&format!("({})", info.pv[0])));
+}
+
+#[test]
+fn test_expected_line_follows_the_game() {
+    use cn_chess_tui::game::AiConfig;
+
+    let mut controller = GameController::new();
+    controller.set_ai_config(AiConfig {
+        search_depth: 1,
+        ..AiConfig::default()
+    });
+    assert!(controller.expected_line().is_empty());
+
+    // Searched but not yet played: the whole line is ahead
+    controller.set_ai_mode(AiMode::PlaysRed);
+    controller.trigger_ai_move().unwrap();
+    let line = controller.expected_line();
+    assert_eq!(line.len(), 1);
+
+    // Once the move is on the board it is no longer expected
+    let played = controller.check_engine_response().unwrap().unwrap();
+    assert_eq!(line[0], played);
+    assert!(controller.expected_line().is_empty());
+
+    // Undoing returns to the position the line starts from
+    controller.set_ai_mode(AiMode::Off);
+    assert!(controller.undo_move());
+    assert_eq!(controller.expected_line(), line);
+
+    // A different move leaves the line
+    controller
+        .human_move(Position::from_xy(1, 7), Position::from_xy(4, 7))
+        .unwrap();
+    if played != (Position::from_xy(1, 7), Position::from_xy(4, 7)) {
+        assert!(controller.expected_line().is_empty());
+    }
+}
+
+#[test]
+fn test_redo_waits_for_the_ai() {
+    let mut controller = GameController::new();
+    controller
+        .human_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    assert!(controller.undo_move());
+    assert!(controller.redo_move());
+    assert_eq!(controller.get_moves().len(), 1);
+
+    // No redo while the AI is about to reply
+    assert!(controller.undo_move());
+    controller.set_ai_mode(AiMode::PlaysRed);
+    controller.trigger_ai_move().unwrap();
+    assert!(controller.is_engine_thinking());
+    assert!(!controller.redo_move());
+}
+
+#[test]
+fn test_hint_suggests_without_playing() {
+    let mut controller = GameController::from_fen("3k5/8R/9/9/9/9/9/9/9/R3K4 w - - 0 1").unwrap();
+    let (from, to) = controller.hint().unwrap().expect("a hint");
+    assert!(controller.get_moves().is_empty());
+    assert!(!controller.is_engine_thinking());
+
+    // The built-in AI sees the mate in one
+    controller.human_move(from, to).unwrap();
+    assert_eq!(
+        controller.state(),
+        cn_chess_tui::GameState::Checkmate(cn_chess_tui::Color::Red)
+    );
+    assert_eq!(controller.hint().unwrap(), None);
+}
+
+#[test]
+fn test_move_stats_record_time_and_ai_evaluation() {
+    use cn_chess_tui::game::AiConfig;
+
+    // Black has only its general against two chariots
+    let mut controller = GameController::from_fen("4k4/9/9/9/9/9/9/9/9/R2K4R b - - 0 1").unwrap();
+    controller.set_ai_config(AiConfig {
+        search_depth: 2,
+        opening_book: false,
+        ..AiConfig::default()
+    });
+
+    controller
+        .human_move(Position::from_xy(4, 0), Position::from_xy(5, 0))
+        .unwrap();
+    controller.set_ai_mode(AiMode::PlaysRed);
+    controller.trigger_ai_move().unwrap();
+    controller.check_engine_response().unwrap().unwrap();
+
+    let stats = controller.move_stats();
+    assert_eq!(stats.len(), 2);
+    assert!(stats.iter().all(|s| s.time_ms.is_some()));
+    // No search behind the human move; Red's search says Red is winning
+    assert_eq!(stats[0].eval, None);
+    assert!(stats[1].eval.unwrap() > 0);
+
+    // A different move after an undo replaces the old entry
+    controller.set_ai_mode(AiMode::Off);
+    assert!(controller.undo_move());
+    assert_eq!(controller.move_stats().len(), 1);
+    controller
+        .human_move(Position::from_xy(0, 9), Position::from_xy(0, 1))
+        .unwrap();
+    assert_eq!(controller.move_stats()[1].eval, None);
+
+    controller.load_game(cn_chess_tui::Game::new());
+    assert!(controller.move_stats().is_empty());
+}
+
+#[test]
+fn test_engine_errors_can_be_matched() {
+    use cn_chess_tui::Error;
+
+    let mut controller = GameController::new();
+    match controller.init_engine("/nonexistent/engine") {
+        Err(Error::EngineNotFound(path)) => assert_eq!(path.to_str(), Some("/nonexistent/engine")),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(!controller.has_engine());
+    assert!(matches!(controller.start_analysis(), Err(Error::NoEngine)));
+}
+
+#[test]
+fn test_difficulty_sets_the_search_per_move() {
+    use cn_chess_tui::game::{AiConfig, Difficulty};
+
+    let mut controller = GameController::new();
+    controller.set_ai_config(AiConfig {
+        search_depth: 5,
+        opening_book: false,
+        difficulty: Some(Difficulty::Beginner),
+        seed: Some(1),
+        ..AiConfig::default()
+    });
+    controller.set_ai_mode(AiMode::PlaysBoth);
+    controller.trigger_ai_move().unwrap();
+    controller.check_engine_response().unwrap().unwrap();
+    assert_eq!(controller.last_search().unwrap().1.depth, Some(1));
+
+    // A new level takes effect from the next move
+    let mut config = controller.ai_config().clone();
+    config.difficulty = Some(Difficulty::Intermediate);
+    controller.set_ai_config(config);
+    controller.trigger_ai_move().unwrap();
+    controller.check_engine_response().unwrap().unwrap();
+    assert_eq!(controller.last_search().unwrap().1.depth, Some(2));
+}
+
+#[test]
+fn test_hint_candidates_from_builtin_ai() {
+    use cn_chess_tui::game::AiConfig;
+
+    let mut controller = GameController::from_fen("3k5/8R/9/9/9/9/9/9/9/R3K4 w - - 0 1").unwrap();
+    controller.set_ai_config(AiConfig {
+        multipv: 3,
+        ..AiConfig::default()
+    });
+    let candidates = controller.hint_candidates().unwrap();
+    assert_eq!(candidates.len(), 3);
+    assert_eq!(
+        controller.hint().unwrap(),
+        Some((candidates[0].from, candidates[0].to))
+    );
+
+    // Distinct moves, best first
+    let scores: Vec<i32> = candidates.iter().map(|c| c.score.unwrap()).collect();
+    assert!(
+        scores.windows(2).all(|pair| pair[0] >= pair[1]),
+        "{:?}",
+        scores
+    );
+    assert_ne!(
+        (candidates[0].from, candidates[0].to),
+        (candidates[1].from, candidates[1].to)
+    );
+    assert!(controller.get_moves().is_empty());
+}
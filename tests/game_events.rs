@@ -0,0 +1,76 @@
+//! Tests for following a game through its event channel
+
+use cn_chess_tui::events::GameEvent;
+use cn_chess_tui::{Color, Game, GameController, GameState, Move, Piece, PieceType, Position};
+
+#[test]
+fn test_capture_is_reported_after_the_move() {
+    let mut game = Game::new();
+    let events = game.subscribe();
+    let (from, to) = (Position::from_xy(7, 7), Position::from_xy(7, 0));
+    game.make_move(from, to).unwrap();
+
+    let mv = Move::new(from, to);
+    let horse = Piece::black(PieceType::Horse);
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        [
+            GameEvent::Moved {
+                mv,
+                piece: Piece::red(PieceType::Cannon),
+                captured: Some(horse),
+            },
+            GameEvent::Captured { mv, piece: horse },
+        ]
+    );
+
+    game.undo_move();
+    assert_eq!(events.try_recv(), Ok(GameEvent::Undone(mv)));
+}
+
+#[test]
+fn test_checkmate_reports_check_and_game_over() {
+    let mut game = Game::from_fen("3k5/8R/9/9/9/9/9/9/9/R3K4 w - - 0 1").unwrap();
+    let events = game.subscribe();
+    game.make_move(Position::from_xy(0, 9), Position::from_xy(0, 0))
+        .unwrap();
+
+    let events: Vec<GameEvent> = events.try_iter().collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[1], GameEvent::Check(Color::Black));
+    assert_eq!(
+        events[2],
+        GameEvent::GameOver(GameState::Checkmate(Color::Red))
+    );
+}
+
+#[test]
+fn test_copies_and_dropped_receivers_are_not_sent_events() {
+    let mut game = Game::new();
+    let kept = game.subscribe();
+    drop(game.subscribe());
+
+    let mut copy = game.clone();
+    copy.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    assert!(kept.try_recv().is_err());
+
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    assert_eq!(kept.try_iter().count(), 1);
+}
+
+#[test]
+fn test_controller_keeps_observers_across_loaded_games() {
+    let mut controller = GameController::new();
+    let events = controller.subscribe();
+    controller.load_game(Game::new());
+    controller
+        .human_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    assert!(controller.undo_move());
+
+    let events: Vec<GameEvent> = events.try_iter().collect();
+    assert!(matches!(events[0], GameEvent::Moved { .. }));
+    assert!(matches!(events[1], GameEvent::Undone(_)));
+}
@@ -95,3 +95,193 @@ fn test_game_to_pgn_stalemate() {
     // Verify move is present
     assert!(pgn_string.contains("炮二平五"));
 }
+
+#[test]
+fn test_comments_round_trip_through_pgn_and_xml() {
+    use cn_chess_tui::pgn::PgnGame;
+    use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+    use cn_chess_tui::xml::{pgn_to_xml, xml_to_pgn};
+
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 0), Position::from_xy(6, 2))
+        .unwrap();
+    game.set_comment(0, Some("Central cannon".to_string()));
+
+    // Undo and redo keep the comment
+    game.undo_move();
+    game.undo_move();
+    game.redo_move();
+    game.redo_move();
+    assert_eq!(game.comment(0), Some("Central cannon"));
+
+    let pgn_string = game.to_pgn().to_pgn();
+    assert!(pgn_string.contains("炮二平五 { Central cannon}"));
+
+    let pgn = PgnGame::parse(&pgn_string).unwrap();
+    let imported = import_pgn(&pgn, ImportMode::Strict).unwrap().game;
+    assert_eq!(imported.comment(0), Some("Central cannon"));
+    assert_eq!(imported.comment(1), None);
+
+    let xml = xml_to_pgn(&pgn_to_xml(&imported.to_pgn())).unwrap();
+    let imported = import_pgn(&xml, ImportMode::Strict).unwrap().game;
+    assert_eq!(imported.comment(0), Some("Central cannon"));
+}
+
+#[test]
+fn test_move_times_round_trip_through_pgn() {
+    use cn_chess_tui::pgn::PgnGame;
+    use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 0), Position::from_xy(6, 2))
+        .unwrap();
+    game.set_move_time(0, Some(12_000));
+    game.set_move_time(1, Some(3_725_000));
+    game.set_comment(1, Some("Quick reply".to_string()));
+
+    let pgn_string = game.to_pgn().to_pgn();
+    assert!(pgn_string.contains("炮二平五 { [%emt 0:00:12]}"));
+    assert!(pgn_string.contains("{ [%emt 1:02:05] Quick reply}"));
+
+    let pgn = PgnGame::parse(&pgn_string).unwrap();
+    let imported = import_pgn(&pgn, ImportMode::Strict).unwrap().game;
+    assert_eq!(imported.move_time(0), Some(12_000));
+    assert_eq!(imported.comment(0), None);
+    assert_eq!(imported.move_time(1), Some(3_725_000));
+    assert_eq!(imported.comment(1), Some("Quick reply"));
+}
+
+#[test]
+fn test_variations_round_trip_through_pgn() {
+    use cn_chess_tui::pgn::PgnGame;
+    use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+
+    // The second variation goes wrong at its second move and is cut there
+    let pgn = PgnGame::parse(
+        "1. h7e7 (1. b7e7 {Left cannon} b0c2 (1... h0g2)) (1. g6g5 a0a9) h0g2 2. h9g7",
+    )
+    .unwrap();
+    let game = import_pgn(&pgn, ImportMode::Strict).unwrap().game;
+    assert_eq!(game.get_moves_with_iccs(), ["h7e7", "h0g2", "h9g7"]);
+    assert_eq!(game.variations(0).len(), 2);
+    assert_eq!(game.variations(0)[1].len(), 1);
+
+    let line = game.line(&[(0, 0), (1, 0)]).unwrap();
+    assert_eq!(line.get_moves_with_iccs(), ["b7e7", "h0g2"]);
+    assert_eq!(line.comment(0), Some("Left cannon"));
+
+    let written = game.to_pgn().to_pgn();
+    assert!(
+        written.contains("1. 炮二平五 (1. 炮八平五 { Left cannon} 马2进3 (1... 马8进7)) (1. 兵三进一) 1... 马8进7"),
+        "{}",
+        written
+    );
+    let reread = import_pgn(&PgnGame::parse(&written).unwrap(), ImportMode::Strict)
+        .unwrap()
+        .game;
+    assert_eq!(reread.to_pgn().to_pgn(), written);
+}
+
+#[test]
+fn test_handicap_game_keeps_its_start_in_pgn() {
+    use cn_chess_tui::handicap::Handicap;
+    use cn_chess_tui::pgn::PgnGame;
+    use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+
+    let handicap = Handicap::by_spec("two-horses").unwrap();
+    let mut game = handicap.game();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+
+    let pgn = game.to_pgn();
+    assert_eq!(pgn.get_tag("Handicap").map(String::as_str), Some("让双马"));
+    assert_eq!(pgn.get_tag("FEN"), Some(&handicap.fen()));
+
+    let parsed = PgnGame::parse(&pgn.to_string()).unwrap();
+    let loaded = import_pgn(&parsed, ImportMode::Strict).unwrap().game;
+    assert_eq!(loaded.to_fen(), game.to_fen());
+    assert_eq!(Handicap::of_game(&loaded), Some(handicap));
+
+    // Standard games carry neither tag
+    let pgn = Game::new().to_pgn();
+    assert_eq!(pgn.get_tag("FEN"), None);
+    assert_eq!(pgn.get_tag("Handicap"), None);
+}
+
+#[test]
+fn test_export_notations_round_trip() {
+    use cn_chess_tui::notation::format::MoveStyle;
+    use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+    use cn_chess_tui::{pgn_to_xml, xml_to_pgn, PgnExportOptions, PgnGame};
+
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 0), Position::from_xy(6, 2))
+        .unwrap();
+
+    for (notation, format, first) in [
+        (MoveStyle::Iccs, "ICCS", "h7e7"),
+        (MoveStyle::Wxf, "WXF", "C2.5"),
+        (MoveStyle::Chinese, "Chinese", "炮二平五"),
+    ] {
+        let pgn = game.to_pgn_with(PgnExportOptions { notation });
+        assert_eq!(pgn.get_tag("Format").unwrap(), format);
+        assert_eq!(pgn.moves[0].notation, first);
+
+        let text = PgnGame::parse(&pgn.to_pgn()).unwrap();
+        let xml = xml_to_pgn(&pgn_to_xml(&pgn)).unwrap();
+        assert_eq!(xml.get_tag("Format").unwrap(), format);
+        for read in [text, xml] {
+            let report = import_pgn(&read, ImportMode::Strict).unwrap();
+            assert!(report.is_clean(), "{:?}", report.issues);
+            assert_eq!(report.game.get_moves(), game.get_moves());
+        }
+    }
+}
+
+#[test]
+fn test_free_turn_game_round_trips_through_pgn() {
+    use cn_chess_tui::notation::format::MoveStyle;
+    use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+    use cn_chess_tui::{PgnExportOptions, PgnGame};
+
+    // Red moves twice, then Black twice, then Red
+    let mut game = Game::new();
+    game.set_free_turn(true);
+    for (from, to) in [
+        ((7, 7), (4, 7)),
+        ((7, 9), (6, 7)),
+        ((7, 0), (6, 2)),
+        ((1, 0), (2, 2)),
+        ((1, 9), (2, 7)),
+    ] {
+        game.make_move(
+            Position::from_xy(from.0, from.1),
+            Position::from_xy(to.0, to.1),
+        )
+        .unwrap();
+    }
+    game.set_free_turn(false);
+
+    for (notation, movetext) in [
+        (MoveStyle::Iccs, "1. h7e7 2. h9g7 h0g2 3... b0c2 4. b9c7"),
+        (
+            MoveStyle::Chinese,
+            "1. 炮二平五 2. 马二进三 马8进7 3... 马2进3 4. 马八进七",
+        ),
+    ] {
+        let written = game.to_pgn_with(PgnExportOptions { notation }).to_pgn();
+        assert!(written.contains(movetext), "{}", written);
+
+        let report = import_pgn(&PgnGame::parse(&written).unwrap(), ImportMode::Strict).unwrap();
+        assert!(report.is_clean(), "{:?}", report.issues);
+        assert_eq!(report.game.get_moves(), game.get_moves());
+        assert_eq!(report.game.to_fen(), game.to_fen());
+        assert!(!report.game.free_turn());
+    }
+}
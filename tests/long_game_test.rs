@@ -0,0 +1,133 @@
+//! Tests for very long games (1000+ moves): writers and history paging
+
+use cn_chess_tui::pgn::{PgnGame, PGN_LINE_WIDTH};
+use cn_chess_tui::xml::{write_xml, xml_to_pgn};
+
+fn long_pgn(plies: usize) -> PgnGame {
+    let mut pgn = PgnGame::new();
+    pgn.set_tag("Event", "Engine match");
+    for ply in 0..plies {
+        pgn.add_move(if ply % 2 == 0 { "b9c7" } else { "b0c2" });
+    }
+    pgn
+}
+
+#[test]
+fn test_pgn_lines_wrap_at_line_width() {
+    let pgn = long_pgn(1200);
+    let text = pgn.to_pgn();
+
+    assert!(text
+        .lines()
+        .all(|line| line.chars().count() <= PGN_LINE_WIDTH));
+    assert!(text.contains("600. b9c7 b0c2"));
+    assert_eq!(PgnGame::parse(&text).unwrap().moves.len(), 1200);
+}
+
+#[test]
+fn test_pgn_wraps_long_comments() {
+    let mut pgn = long_pgn(2);
+    pgn.moves[0].comment = Some("a very long comment ".repeat(10));
+    let text = pgn.to_pgn();
+
+    assert!(text
+        .lines()
+        .all(|line| line.chars().count() <= PGN_LINE_WIDTH));
+}
+
+#[test]
+fn test_write_pgn_matches_to_pgn() {
+    let pgn = long_pgn(1001);
+    let mut buf = Vec::new();
+    pgn.write_pgn(&mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), pgn.to_pgn());
+}
+
+#[test]
+fn test_streaming_xml_round_trip() {
+    let pgn = long_pgn(1500);
+    let mut buf = Vec::new();
+    write_xml(&pgn, &mut buf).unwrap();
+
+    let parsed = xml_to_pgn(std::str::from_utf8(&buf).unwrap()).unwrap();
+    assert_eq!(parsed.moves.len(), 1500);
+}
+
+/// Paging the history browser, which needs the terminal interface
+#[cfg(feature = "tui")]
+mod history_browser {
+    use cn_chess_tui::game::{Game, GameState};
+    use cn_chess_tui::types::Position;
+    use cn_chess_tui::ui::{HistoryBrowserState, UI};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    /// Build a game by shuttling both left horses back and forth
+    ///
+    /// The shuttle repeats positions, so the repetition draw is cleared after
+    /// every move to keep the game going.
+    fn long_game(plies: usize) -> Game {
+        let red = [Position::from_xy(1, 9), Position::from_xy(2, 7)];
+        let black = [Position::from_xy(1, 0), Position::from_xy(2, 2)];
+
+        let mut game = Game::new();
+        for ply in 0..plies {
+            let squares = if ply % 2 == 0 { &red } else { &black };
+            let (from, to) = if (ply / 2) % 2 == 0 {
+                (squares[0], squares[1])
+            } else {
+                (squares[1], squares[0])
+            };
+            game.make_move(from, to).unwrap();
+            game.force_state_for_testing(GameState::Playing);
+        }
+        game
+    }
+
+    #[test]
+    fn test_history_browser_paging() {
+        let total_rows = HistoryBrowserState::total_rows(1001);
+        assert_eq!(total_rows, 501);
+
+        let mut state = HistoryBrowserState {
+            top: 0,
+            page_rows: 20,
+        };
+        state.page_down(total_rows);
+        assert_eq!(state.top, 20);
+        state.page_up(total_rows);
+        state.page_up(total_rows);
+        assert_eq!(state.top, 0);
+        state.end(total_rows);
+        assert_eq!(state.top, 481);
+        state.scroll(5, total_rows);
+        assert_eq!(state.top, 481);
+    }
+
+    #[test]
+    fn test_history_browser_draws_last_page() {
+        let game = long_game(1000);
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let mut state = HistoryBrowserState {
+            top: usize::MAX,
+            page_rows: 0,
+        };
+
+        terminal
+            .draw(|f| UI::draw_history_browser(f, &game, &mut state))
+            .unwrap();
+
+        assert_eq!(state.page_rows, 21);
+        assert_eq!(state.top, 500 - 21);
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("1000 moves"));
+        assert!(screen.contains("500."));
+        assert!(!screen.contains("479."));
+    }
+}
@@ -0,0 +1,132 @@
+use cn_chess_tui::ai::Searcher;
+use cn_chess_tui::fen::fen_to_board;
+use cn_chess_tui::game::{Adjudication, AiConfig, AiMode, GameController, GameState};
+use cn_chess_tui::types::{Color, Position};
+
+#[test]
+fn test_finds_mate_in_one() {
+    // Red chariot on a5 mates on a0 while the other chariot guards rank 1
+    let (board, turn) = fen_to_board("4k4/8R/9/9/9/R8/9/9/9/3K5 w - - 0 1").unwrap();
+    let result = Searcher::new(2).search(&board, turn).unwrap();
+    assert_eq!(result.mv.to.y, 0);
+    assert!(
+        result.score > 10_000,
+        "mate should score high: {}",
+        result.score
+    );
+}
+
+#[test]
+fn test_native_ai_plays_without_engine() {
+    let mut controller = GameController::new();
+    controller.set_ai_mode(AiMode::PlaysBlack);
+    controller.set_ai_config(AiConfig {
+        search_depth: 2,
+        ..AiConfig::default()
+    });
+    assert!(controller.uses_native_ai());
+
+    controller
+        .human_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+
+    controller.trigger_ai_move().unwrap();
+    assert!(controller.is_engine_thinking());
+
+    let mv = controller.check_engine_response().unwrap();
+    assert!(mv.is_some());
+    assert!(!controller.is_engine_thinking());
+    assert_eq!(controller.turn(), Color::Red);
+    assert_eq!(controller.get_moves().len(), 2);
+}
+
+#[test]
+fn test_native_ai_waits_for_its_turn() {
+    let mut controller = GameController::new();
+    controller.set_ai_mode(AiMode::PlaysBlack);
+
+    // Red to move: AI should not start
+    controller.trigger_ai_move().unwrap();
+    assert!(!controller.is_engine_thinking());
+}
+
+#[test]
+fn test_native_ai_plays_book_moves() {
+    use cn_chess_tui::opening::OpeningBook;
+
+    let book = OpeningBook::builtin();
+    let mut controller = GameController::new();
+    controller.set_ai_mode(AiMode::PlaysBoth);
+    controller.set_ai_config(AiConfig {
+        seed: Some(7),
+        ..AiConfig::default()
+    });
+
+    // Every move comes from the book until the line runs out
+    while !book.book_moves(controller.game()).is_empty() {
+        let expected = book.book_moves(controller.game());
+        controller.trigger_ai_move().unwrap();
+        let (from, to) = controller.check_engine_response().unwrap().unwrap();
+        assert!(expected.iter().any(|mv| (mv.from, mv.to) == (from, to)));
+        let (_, info) = controller.last_search().unwrap();
+        assert!(info.depth.is_none());
+        assert!(info.message.as_ref().unwrap().starts_with("开局库"));
+    }
+    let opening = book.identify(controller.game()).unwrap();
+    assert_eq!(opening.moves, controller.get_moves());
+
+    // Out of book the search takes over
+    controller.trigger_ai_move().unwrap();
+    controller.check_engine_response().unwrap().unwrap();
+    assert!(controller.last_search().unwrap().1.depth.is_some());
+}
+
+#[test]
+fn test_each_side_searches_to_its_own_depth() {
+    let mut controller = GameController::new();
+    controller.set_ai_mode(AiMode::PlaysBoth);
+    let mut config = AiConfig {
+        opening_book: false,
+        ..AiConfig::default()
+    };
+    config.red.search_depth = Some(1);
+    config.black.search_depth = Some(2);
+    controller.set_ai_config(config);
+
+    for depth in [1, 2, 1] {
+        controller.trigger_ai_move().unwrap();
+        controller.check_engine_response().unwrap().unwrap();
+        let (_, info) = controller.last_search().unwrap();
+        assert_eq!(info.depth, Some(depth));
+    }
+}
+
+#[test]
+fn test_ai_game_adjudicated_at_the_ply_limit() {
+    let mut controller = GameController::new();
+    controller.set_ai_mode(AiMode::PlaysBoth);
+    controller.set_ai_config(AiConfig {
+        opening_book: false,
+        search_depth: 1,
+        adjudication: Adjudication {
+            max_plies: Some(2),
+            ..Adjudication::default()
+        },
+        ..AiConfig::default()
+    });
+
+    for _ in 0..2 {
+        controller.trigger_ai_move().unwrap();
+        controller.check_engine_response().unwrap().unwrap();
+    }
+    assert_eq!(controller.state(), GameState::Adjudicated(None));
+    controller.trigger_ai_move().unwrap();
+    assert_eq!(controller.game().get_moves().len(), 2);
+
+    let pgn = controller.game().to_pgn();
+    assert_eq!(pgn.get_tag("Result").map(String::as_str), Some("1/2-1/2"));
+    assert_eq!(
+        pgn.get_tag("Termination").map(String::as_str),
+        Some("adjudication")
+    );
+}
@@ -1,6 +1,7 @@
 use cn_chess_tui::{
     notation::chinese::*,
     types::{Color, Piece, PieceType, Position},
+    Game,
 };
 
 #[test]
@@ -162,3 +163,81 @@ fn test_full_move_notation() {
     let to = Position::from_xy(5, 0); // File 6 (六) for Black (5+1=6)
     assert_eq!(move_to_chinese(piece, from, to), "将五平六");
 }
+
+#[test]
+fn test_parse_front_and_rear_chariots() {
+    // Two red chariots on file 九 (x=0)
+    let game = Game::from_fen("4k4/9/9/9/9/9/R8/9/R8/3K5 w - - 0 1").unwrap();
+    let board = game.board();
+
+    assert_eq!(
+        parse_chinese_move(board, Color::Red, "前车进一"),
+        Some((Position::from_xy(0, 6), Position::from_xy(0, 5)))
+    );
+    assert_eq!(
+        parse_chinese_move(board, Color::Red, "后车平五"),
+        Some((Position::from_xy(0, 8), Position::from_xy(4, 8)))
+    );
+    // Without 前/后 the chariot is ambiguous
+    assert_eq!(parse_chinese_move(board, Color::Red, "车九平五"), None);
+}
+
+#[test]
+fn test_parse_numbered_soldiers() {
+    // Three black soldiers on Black's file 5, across the river
+    let game = Game::from_fen("4k4/9/9/9/9/4p4/4p4/4p4/9/3K5 b - - 0 1").unwrap();
+    let board = game.board();
+
+    // Front soldier for Black is the one with the largest y
+    assert_eq!(
+        parse_chinese_move(board, Color::Black, "一卒平4"),
+        Some((Position::from_xy(4, 7), Position::from_xy(3, 7)))
+    );
+    assert_eq!(
+        parse_chinese_move(board, Color::Black, "中卒平6"),
+        Some((Position::from_xy(4, 6), Position::from_xy(5, 6)))
+    );
+    // The crate's own output keeps the file after the rank
+    assert_eq!(
+        parse_chinese_move(board, Color::Black, "后卒五平四"),
+        Some((Position::from_xy(4, 5), Position::from_xy(3, 5)))
+    );
+}
+
+#[test]
+fn test_parse_rejects_illegal_or_malformed() {
+    let game = Game::new();
+    assert_eq!(
+        parse_chinese_move(game.board(), Color::Red, "炮二进九"),
+        None
+    );
+    assert_eq!(parse_chinese_move(game.board(), Color::Red, "炮二"), None);
+    assert_eq!(parse_chinese_move(game.board(), Color::Red, "h7e7"), None);
+}
+
+#[test]
+fn test_chinese_pgn_round_trip() {
+    use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+
+    let mut game = Game::new();
+    for (from, to) in [
+        ((7, 7), (4, 7)), // 炮二平五
+        ((7, 0), (6, 2)), // 马8进7
+        ((7, 9), (6, 7)), // 马二进三
+        ((8, 0), (7, 0)), // 车9平8
+        ((8, 9), (7, 9)), // 车一平二
+    ] {
+        game.make_move(
+            Position::from_xy(from.0, from.1),
+            Position::from_xy(to.0, to.1),
+        )
+        .unwrap();
+    }
+
+    let report = import_pgn(&game.to_pgn(), ImportMode::Strict).unwrap();
+    assert!(report.is_clean());
+    assert_eq!(
+        report.game.get_moves_with_iccs(),
+        game.get_moves_with_iccs()
+    );
+}
@@ -144,3 +144,24 @@ fn test_five_soldiers_on_same_file() {
         );
     }
 }
+
+#[test]
+fn test_history_keeps_notation_from_when_moves_were_played() {
+    use cn_chess_tui::notation::format::MoveStyle;
+
+    // Two soldiers across the river on file 5
+    let mut game = Game::from_fen("3k5/9/9/4P4/4P4/9/9/9/9/4K4 w - - 0 1").unwrap();
+    game.make_move(Position::from_xy(4, 3), Position::from_xy(3, 3))
+        .unwrap();
+    game.make_move(Position::from_xy(3, 0), Position::from_xy(3, 1))
+        .unwrap();
+    game.make_move(Position::from_xy(4, 4), Position::from_xy(4, 3))
+        .unwrap();
+
+    // The soldier that moved away is still named as the front one
+    assert_eq!(
+        game.move_notations(MoveStyle::Chinese),
+        ["前兵五平六", "将4进1", "兵五进一"]
+    );
+    assert_eq!(game.to_pgn().moves[0].notation, "前兵五平六");
+}
@@ -0,0 +1,256 @@
+//! Tests for the online client against a scripted server
+
+use cn_chess_tui::online::{
+    lobby, ClientMessage, GameInfo, OnlineClient, OnlineError, OnlineEvent, Seek, ServerMessage,
+    Transport,
+};
+use cn_chess_tui::{Color, GameController, Position};
+use std::collections::VecDeque;
+use std::io;
+
+const START_FEN: &str = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+
+/// Answers each client message with canned server messages
+struct FakeServer {
+    answer: fn(&ClientMessage) -> Vec<ServerMessage>,
+    sent: Vec<ClientMessage>,
+    incoming: VecDeque<String>,
+}
+
+impl FakeServer {
+    fn new(answer: fn(&ClientMessage) -> Vec<ServerMessage>) -> Self {
+        Self {
+            answer,
+            sent: Vec::new(),
+            incoming: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, message: ServerMessage) {
+        self.incoming
+            .push_back(serde_json::to_string(&message).unwrap());
+    }
+}
+
+impl Transport for FakeServer {
+    fn send(&mut self, text: &str) -> io::Result<()> {
+        let message: ClientMessage = serde_json::from_str(text).unwrap();
+        for answer in (self.answer)(&message) {
+            self.push(answer);
+        }
+        self.sent.push(message);
+        Ok(())
+    }
+
+    fn try_recv(&mut self) -> io::Result<Option<String>> {
+        Ok(self.incoming.pop_front())
+    }
+}
+
+fn lobby_server(message: &ClientMessage) -> Vec<ServerMessage> {
+    match message {
+        ClientMessage::Login { name, .. } => vec![ServerMessage::Welcome { name: name.clone() }],
+        ClientMessage::ListSeeks => vec![ServerMessage::Seeks {
+            seeks: vec![Seek {
+                id: 7,
+                player: "bob".to_string(),
+                color: Some(Color::Black),
+            }],
+        }],
+        ClientMessage::Accept { seek: 7 } => vec![ServerMessage::GameStart {
+            game: 3,
+            color: Color::Red,
+            opponent: "bob".to_string(),
+            fen: START_FEN.to_string(),
+        }],
+        ClientMessage::Accept { .. } => vec![ServerMessage::Error {
+            message: "no such seek".to_string(),
+        }],
+        ClientMessage::ListGames => vec![ServerMessage::Games {
+            games: vec![GameInfo {
+                id: 3,
+                red: "alice".to_string(),
+                black: "bob".to_string(),
+            }],
+        }],
+        ClientMessage::Watch { game: 3 } => vec![ServerMessage::Spectate {
+            game: 3,
+            red: "alice".to_string(),
+            black: "bob".to_string(),
+            fen: START_FEN.to_string(),
+            moves: vec!["h7e7".to_string()],
+        }],
+        _ => Vec::new(),
+    }
+}
+
+#[test]
+fn test_lobby_accepts_a_seek() {
+    let mut client = OnlineClient::new(FakeServer::new(lobby_server));
+    let mut output = Vec::new();
+    let game = lobby::run(&mut client, "alice", "x\n1\n".as_bytes(), &mut output)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(game.id, 3);
+    assert_eq!(game.color, Color::Red);
+    assert_eq!(game.opponent, "bob");
+    assert_eq!(client.name(), Some("alice"));
+    assert_eq!(
+        client.transport_mut().sent[2],
+        ClientMessage::Accept { seek: 7 }
+    );
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Logged in as alice"));
+    assert!(output.contains("1. bob (plays Black)"));
+}
+
+#[test]
+fn test_lobby_quits_and_reports_refusals() {
+    let mut client = OnlineClient::new(FakeServer::new(lobby_server));
+    let game = lobby::run(&mut client, "alice", "q\n".as_bytes(), &mut Vec::new()).unwrap();
+    assert_eq!(game, None);
+
+    fn refusing(message: &ClientMessage) -> Vec<ServerMessage> {
+        match message {
+            ClientMessage::Login { .. } => vec![ServerMessage::Error {
+                message: "name taken".to_string(),
+            }],
+            _ => Vec::new(),
+        }
+    }
+    let mut client = OnlineClient::new(FakeServer::new(refusing));
+    let err = lobby::run(&mut client, "alice", "".as_bytes(), &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, OnlineError::Refused(ref msg) if msg == "name taken"));
+}
+
+#[test]
+fn test_moves_are_relayed_both_ways() {
+    let mut client = OnlineClient::new(FakeServer::new(lobby_server));
+    assert!(matches!(
+        client.send_move(Position::from_xy(7, 7), Position::from_xy(4, 7)),
+        Err(OnlineError::NoGame)
+    ));
+    client.accept(7).unwrap();
+    let Some(OnlineEvent::GameStarted(game)) = client.poll().unwrap().pop() else {
+        panic!("game did not start");
+    };
+    let mut controller = GameController::from_fen(&game.fen).unwrap();
+
+    // Our move goes to the server in ICCS
+    let (from, to) = (Position::from_xy(7, 7), Position::from_xy(4, 7));
+    controller.human_move(from, to).unwrap();
+    client.send_move(from, to).unwrap();
+    assert_eq!(
+        client.transport_mut().sent.last(),
+        Some(&ClientMessage::Move {
+            game: 3,
+            mv: "h7e7".to_string(),
+        })
+    );
+
+    // The reply is applied to the local game; other games are ignored
+    client.transport_mut().push(ServerMessage::Move {
+        game: 9,
+        mv: "a0a1".to_string(),
+    });
+    client.transport_mut().push(ServerMessage::Move {
+        game: 3,
+        mv: "h0g2".to_string(),
+    });
+    let events = client.poll().unwrap();
+    assert_eq!(
+        events,
+        [OnlineEvent::OpponentMoved {
+            from: Position::from_xy(7, 0),
+            to: Position::from_xy(6, 2),
+        }]
+    );
+    for event in events {
+        if let OnlineEvent::OpponentMoved { from, to } = event {
+            controller.human_move(from, to).unwrap();
+        }
+    }
+    assert_eq!(controller.get_moves().len(), 2);
+    assert_eq!(controller.turn(), Color::Red);
+
+    client.transport_mut().push(ServerMessage::GameEnd {
+        game: 3,
+        result: "0-1".to_string(),
+        reason: Some("resignation".to_string()),
+    });
+    assert!(matches!(
+        client.poll().unwrap()[..],
+        [OnlineEvent::GameEnded { .. }]
+    ));
+    assert!(client.game().is_none());
+}
+
+#[test]
+fn test_spectator_catches_up_and_follows_the_game() {
+    let mut client = OnlineClient::new(FakeServer::new(lobby_server));
+    let mut output = Vec::new();
+    let watched = lobby::spectate(&mut client, "2\n1\n".as_bytes(), &mut output)
+        .unwrap()
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("1. alice (Red) vs bob (Black)"));
+    assert_eq!(
+        client.transport_mut().sent,
+        [ClientMessage::ListGames, ClientMessage::Watch { game: 3 }]
+    );
+
+    // Moves played before joining are replayed from the start position
+    let mut controller = GameController::from_game(watched.replay().unwrap());
+    assert_eq!(controller.get_moves().len(), 1);
+    assert_eq!(controller.turn(), Color::Black);
+
+    // Spectators can't move, but see both sides' moves as they are played
+    assert!(matches!(
+        client.send_move(Position::from_xy(7, 0), Position::from_xy(6, 2)),
+        Err(OnlineError::NoGame)
+    ));
+    client.transport_mut().push(ServerMessage::Move {
+        game: 9,
+        mv: "a0a1".to_string(),
+    });
+    client.transport_mut().push(ServerMessage::Move {
+        game: 3,
+        mv: "h0g2".to_string(),
+    });
+    client.transport_mut().push(ServerMessage::Move {
+        game: 3,
+        mv: "b9c7".to_string(),
+    });
+    let events = client.poll().unwrap();
+    assert_eq!(events.len(), 2);
+    for event in events {
+        let OnlineEvent::Moved { from, to } = event else {
+            panic!("unexpected event {:?}", event);
+        };
+        controller.human_move(from, to).unwrap();
+    }
+    assert_eq!(controller.get_moves().len(), 3);
+
+    client.transport_mut().push(ServerMessage::GameEnd {
+        game: 3,
+        result: "1-0".to_string(),
+        reason: None,
+    });
+    assert!(matches!(
+        client.poll().unwrap()[..],
+        [OnlineEvent::GameEnded { .. }]
+    ));
+    assert!(client.watching().is_none());
+}
+
+#[test]
+fn test_garbage_from_the_server_is_an_error() {
+    let mut client = OnlineClient::new(FakeServer::new(lobby_server));
+    client
+        .transport_mut()
+        .incoming
+        .push_back("hello".to_string());
+    assert!(matches!(client.poll(), Err(OnlineError::InvalidMessage(_))));
+}
@@ -0,0 +1,98 @@
+//! Perft tests for move generation
+//!
+//! Counting every move sequence to a fixed depth and comparing with known
+//! values catches rule bugs that single-move tests miss: hobbled horses,
+//! blocked elephant eyes, cannon screens, the flying general and the palace.
+
+use cn_chess_tui::board::Board;
+use cn_chess_tui::types::Color;
+use cn_chess_tui::Game;
+
+/// Published perft values for the start position
+const START_POSITION: [(u32, u64); 5] = [
+    (1, 44),
+    (2, 1_920),
+    (3, 79_666),
+    (4, 3_290_240),
+    (5, 133_312_995),
+];
+
+/// Positions full of pins, screens and checks, with their perft(3) counts
+const TACTICAL_POSITIONS: [(&str, u64); 4] = [
+    (
+        "r1ba1a3/4kn3/2n1b4/pNp1p1p1p/4c4/6P2/P1P2R2P/1CcC5/9/2BAKAB2 w - - 0 1",
+        43_929,
+    ),
+    (
+        "1cbak4/9/n2a5/2p1p3p/5cp2/2n2N3/6PCP/3AB4/2C6/3A1K1N1 w - - 0 1",
+        8_620,
+    ),
+    ("5a3/3k5/3aR4/9/5r3/5n3/9/3A1A3/5K3/2BC2B2 w - - 0 1", 9_850),
+    (
+        "CRN1k1b2/3ca4/4ba3/9/2nr5/9/9/4B4/4A4/4KA3 w - - 0 1",
+        14_808,
+    ),
+];
+
+#[test]
+fn test_perft_start_position() {
+    let board = Board::new();
+    for (depth, nodes) in &START_POSITION[..3] {
+        assert_eq!(board.perft(Color::Red, *depth), *nodes, "depth {}", depth);
+    }
+}
+
+#[test]
+#[ignore = "slow; run with cargo test --release -- --ignored"]
+fn test_perft_start_position_deep() {
+    let board = Board::new();
+    for (depth, nodes) in &START_POSITION[3..] {
+        assert_eq!(board.perft(Color::Red, *depth), *nodes, "depth {}", depth);
+    }
+}
+
+#[test]
+fn test_perft_tactical_positions() {
+    for (fen, nodes) in TACTICAL_POSITIONS {
+        let game = Game::from_fen(fen).unwrap();
+        assert_eq!(game.perft(3), nodes, "{}", fen);
+    }
+}
+
+#[test]
+fn test_attack_maps_agree_with_move_generation() {
+    for (fen, _) in TACTICAL_POSITIONS {
+        let board = Game::from_fen(fen).unwrap().board().clone();
+        for color in [Color::Red, Color::Black] {
+            let attacks = board.attacked_squares(color);
+            for (_, to) in board.legal_captures(color) {
+                assert!(attacks.is_attacked(to), "{} {:?}", fen, to);
+            }
+            // Checks come from attacks, unless the generals face each other
+            let enemy = color.opponent();
+            if board.is_in_check(enemy) && !board.generals_facing() {
+                let general = board.find_general(enemy).unwrap();
+                assert!(attacks.is_attacked(general), "{}", fen);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_perft_leaves_board_unchanged() {
+    let game = Game::from_fen(TACTICAL_POSITIONS[0].0).unwrap();
+    let before = game.board().clone();
+    game.perft(2);
+    assert_eq!(game.board(), &before);
+    assert_eq!(game.perft(0), 1);
+}
+
+#[test]
+fn test_perft_report_divides_by_first_move() {
+    let report = cn_chess_tui::report::perft_report(&Game::new(), 3);
+    assert_eq!(report.nodes, 79_666);
+    assert_eq!(report.moves.len(), 44);
+    assert_eq!(report.moves.iter().map(|m| m.nodes).sum::<u64>(), 79_666);
+    // Red's central cannon
+    assert!(report.moves.iter().any(|m| m.mv == "h7e7"));
+}
@@ -1,6 +1,6 @@
 //! Tests for PGN (Portable Game Notation) parsing
 
-use cn_chess_tui::pgn::{split_quoted, PgnGame, PgnGameResult, PgnMove, PgnTag};
+use cn_chess_tui::pgn::{split_quoted, PgnGame, PgnGameResult, PgnMove, PgnReader, PgnTag};
 
 #[test]
 fn test_pgn_tag_parse_simple() {
@@ -389,3 +389,73 @@ fn test_pgn_game_display() {
     assert!(display.contains("h2e2"));
     assert!(display.contains("1-0"));
 }
+
+#[test]
+fn test_parse_multi_game_collection() {
+    let pgn = r#"[Event "Round 1"]
+[Red "A"]
+[Black "B"]
+
+1. h2e2 h9g7
+2. h0g2 1-0
+
+[Event "Round 2"]
+[Red "B"]
+[Black "A"]
+
+1. b2e2 { opening } b9c7 0-1
+[Event "Round 3"]
+h2e2 *
+"#;
+
+    let games = PgnGame::parse_multi(pgn);
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[0].moves.len(), 3);
+    assert_eq!(games[0].result, PgnGameResult::RedWins);
+    assert_eq!(games[1].get_tag("Red").unwrap(), "B");
+    assert_eq!(games[1].moves.len(), 2);
+    assert_eq!(games[1].result, PgnGameResult::BlackWins);
+    assert_eq!(games[2].get_tag("Event").unwrap(), "Round 3");
+    assert_eq!(games[2].moves.len(), 1);
+}
+
+#[test]
+fn test_parse_multi_single_game() {
+    let games = PgnGame::parse_multi("[Event \"Only\"]\n\nh2e2 h9g7\n");
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].moves.len(), 2);
+    assert!(PgnGame::parse_multi("\n\n").is_empty());
+}
+
+#[test]
+fn test_pgn_reader_streams_games() {
+    let text = "[Event \"1\"]\n\nh2e2\n\n[Event \"2\"]\n\nb2e2\n";
+    let reader = PgnReader::new(std::io::Cursor::new(text));
+    let events: Vec<String> = reader
+        .map(|g| g.unwrap().get_tag("Event").unwrap().clone())
+        .collect();
+    assert_eq!(events, vec!["1", "2"]);
+}
+
+#[test]
+fn test_pgn_reader_skips_bad_games() {
+    let mut bytes = b"[Event \"1\"]\n\nh2e2 (b2e2\n\n".to_vec();
+    bytes.extend_from_slice(b"[Event \"2\"]\n[Red \"\xff\"]\n\nb2e2\n\n");
+    bytes.extend_from_slice(b"[Event \"3\n\nh2e2\n\n");
+    bytes.extend_from_slice(b"[Event \"4\"]\n\nh2e2 {Good\n[%eval 0.3]} h9g7\n");
+    let results: Vec<_> = PgnReader::new(std::io::Cursor::new(bytes)).collect();
+
+    assert_eq!(results.len(), 4);
+    let errors: Vec<String> = results[..3]
+        .iter()
+        .map(|r| r.as_ref().unwrap_err().to_string())
+        .collect();
+    assert_eq!(errors[0], "game at line 1: unbalanced parentheses");
+    assert!(errors[1].starts_with("game at line 5: "));
+    assert_eq!(errors[2], "game at line 10: malformed tag");
+
+    // A comment line starting with a bracket does not start a new game
+    let last = results[3].as_ref().unwrap();
+    assert_eq!(last.get_tag("Event").unwrap(), "4");
+    assert_eq!(last.moves.len(), 2);
+}
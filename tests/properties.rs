@@ -0,0 +1,153 @@
+//! Property tests for move legality, FEN, notation and hashing
+//!
+//! Each case plays a random game from the start: at every ply a generated
+//! number picks one of the legal moves. Along the way every position is
+//! checked against invariants that must hold whatever was played, which
+//! reaches far more positions than hand-written tests. proptest shrinks a
+//! failure to the shortest move sequence that shows it and saves its seed
+//! in `properties.proptest-regressions` next to this file, so it is tried
+//! first from then on.
+//!
+//! `PROPTEST_CASES=10000 cargo test --test properties` runs a longer
+//! search.
+
+use cn_chess_tui::fen::fen_to_board;
+use cn_chess_tui::notation::chinese::{move_to_chinese_with_style, parse_chinese_move};
+use cn_chess_tui::notation::format::parse_any;
+use cn_chess_tui::notation::iccs::move_to_iccs;
+use cn_chess_tui::notation::wxf::{move_to_wxf, resolve_wxf_move};
+use cn_chess_tui::{zobrist, Game, GameState};
+use proptest::prelude::*;
+
+/// Plies of the longest random game
+const MAX_PLIES: usize = 80;
+
+/// Play the moves `choices` pick from the start, checking `check` in every
+/// position reached, the start and the last included
+///
+/// The last position may be one the game ended in, e.g. by repetition.
+fn walk(
+    choices: &[u16],
+    mut check: impl FnMut(&Game) -> Result<(), TestCaseError>,
+) -> Result<(), TestCaseError> {
+    let mut game = Game::new();
+    check(&game)?;
+    for &choice in choices {
+        if game.state() != GameState::Playing {
+            break;
+        }
+        let moves = game.board().legal_moves(game.turn());
+        let (from, to) = moves[choice as usize % moves.len()];
+        game.make_move(from, to).map_err(|e| {
+            TestCaseError::fail(format!("{} refused: {}", move_to_iccs(from, to), e))
+        })?;
+        check(&game)?;
+    }
+    Ok(())
+}
+
+fn choices() -> impl Strategy<Value = Vec<u16>> {
+    proptest::collection::vec(any::<u16>(), 0..MAX_PLIES)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    /// Listed moves can all be played and none leaves the mover in check
+    /// or the generals facing each other
+    #[test]
+    fn legal_moves_are_playable_and_safe(choices in choices()) {
+        walk(&choices, |game| {
+            let turn = game.turn();
+            let playing = game.state() == GameState::Playing;
+            for (from, to) in game.board().legal_moves(turn) {
+                let mut board = game.board().clone();
+                board.move_piece(from, to);
+                prop_assert!(!board.is_in_check(turn), "{} leaves {} in check in {}", move_to_iccs(from, to), turn, game.to_fen());
+                prop_assert!(!board.generals_facing(), "{} faces the generals in {}", move_to_iccs(from, to), game.to_fen());
+                // A game that ended, e.g. by repetition, takes no more moves
+                prop_assert_eq!(game.clone().make_move(from, to).is_ok(), playing, "{} in {}", move_to_iccs(from, to), game.to_fen());
+            }
+            Ok(())
+        })?;
+    }
+
+    /// Undoing any legal move gives back exactly the FEN before it
+    #[test]
+    fn undo_restores_fen(choices in choices()) {
+        walk(&choices, |game| {
+            let fen = game.to_fen();
+            for (from, to) in game.board().legal_moves(game.turn()) {
+                let mut after = game.clone();
+                // A move refused because the game ended leaves it as it was
+                if after.make_move(from, to).is_ok() {
+                    prop_assert!(after.undo_move());
+                }
+                prop_assert_eq!(after.to_fen(), fen.clone(), "after undoing {}", move_to_iccs(from, to));
+            }
+            Ok(())
+        })?;
+    }
+
+    /// A position read back from its FEN writes the same FEN and has the
+    /// same legal moves and hash
+    #[test]
+    fn fen_roundtrips(choices in choices()) {
+        walk(&choices, |game| {
+            let fen = game.to_fen();
+            let reloaded = Game::from_fen(&fen).unwrap();
+            prop_assert_eq!(reloaded.to_fen(), fen.clone());
+            let (board, turn) = fen_to_board(&fen).unwrap();
+            prop_assert_eq!(&board, game.board());
+            prop_assert_eq!(turn, game.turn());
+            prop_assert_eq!(board.legal_moves(turn), game.board().legal_moves(turn));
+            prop_assert_eq!(zobrist::hash(&board, turn), zobrist::hash(game.board(), game.turn()));
+            Ok(())
+        })?;
+    }
+
+    /// The hash kept as moves are played matches one computed from scratch
+    #[test]
+    fn position_hash_is_kept_up_to_date(choices in choices()) {
+        walk(&choices, |game| {
+            let kept = *game.position_hash_history().last().unwrap();
+            prop_assert_eq!(kept, zobrist::hash(game.board(), game.turn()), "in {}", game.to_fen());
+            Ok(())
+        })?;
+    }
+
+    /// Every legal move written in ICCS or Chinese notation reads back as
+    /// itself; in WXF, which can't tell tandem pieces apart, it reads back
+    /// as itself or not at all
+    #[test]
+    fn notation_roundtrips(choices in choices()) {
+        walk(&choices, |game| {
+            let board = game.board();
+            let turn = game.turn();
+            let moves = board.legal_moves(turn);
+            for &(from, to) in &moves {
+                let mv = Some((from, to));
+                let piece = *board.get(from).unwrap();
+
+                let iccs = move_to_iccs(from, to);
+                prop_assert_eq!(parse_any(game, &iccs).ok(), mv);
+
+                let chinese = move_to_chinese_with_style(game, piece, from, to, Default::default());
+                prop_assert_eq!(parse_chinese_move(board, turn, &chinese), mv, "{} as {} in {}", iccs, chinese, game.to_fen());
+
+                let wxf = move_to_wxf(piece, from, to);
+                match resolve_wxf_move(board, turn, &wxf) {
+                    Some(read) => prop_assert_eq!(Some(read), mv, "{} as {} in {}", iccs, wxf, game.to_fen()),
+                    None => {
+                        let same_text = moves
+                            .iter()
+                            .filter(|&&(f, t)| move_to_wxf(*board.get(f).unwrap(), f, t) == wxf)
+                            .count();
+                        prop_assert!(same_text > 1, "{} as {} not read back in {}", iccs, wxf, game.to_fen());
+                    }
+                }
+            }
+            Ok(())
+        })?;
+    }
+}
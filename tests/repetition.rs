@@ -0,0 +1,92 @@
+//! Tests for repetition draws, perpetual check and the natural-move limit
+
+use cn_chess_tui::{Color, Game, GameResult, GameState, MoveError, Position, DEFAULT_MOVE_LIMIT};
+
+/// A move as ((from x, from y), (to x, to y))
+type XyMove = ((usize, usize), (usize, usize));
+
+fn play(game: &mut Game, moves: &[XyMove]) {
+    for &(from, to) in moves {
+        game.make_move(
+            Position::from_xy(from.0, from.1),
+            Position::from_xy(to.0, to.1),
+        )
+        .unwrap();
+    }
+}
+
+/// Both left horses out and back: the starting position recurs every 4 plies
+const HORSE_SHUTTLE: [XyMove; 4] = [
+    ((1, 9), (2, 7)),
+    ((1, 0), (2, 2)),
+    ((2, 7), (1, 9)),
+    ((2, 2), (1, 0)),
+];
+
+#[test]
+fn test_threefold_repetition_is_draw() {
+    let mut game = Game::new();
+    play(&mut game, &HORSE_SHUTTLE);
+    assert_eq!(game.state(), GameState::Playing);
+
+    play(&mut game, &HORSE_SHUTTLE);
+    assert_eq!(game.state(), GameState::DrawByRepetition);
+    assert_eq!(game.state().result(), Some(GameResult::Draw));
+    assert_eq!(
+        game.make_move(Position::from_xy(1, 9), Position::from_xy(2, 7)),
+        Err(MoveError::GameOver(GameResult::Draw))
+    );
+}
+
+#[test]
+fn test_perpetual_check_loses() {
+    // Red's chariot checks along rank 0 and 1 while the black general
+    // steps between e0 and e1
+    let mut game = Game::from_fen("4k4/8R/9/9/9/9/9/9/9/3K5 w - - 0 1").unwrap();
+    let cycle = [
+        ((8, 1), (8, 0)),
+        ((4, 0), (4, 1)),
+        ((8, 0), (8, 1)),
+        ((4, 1), (4, 0)),
+    ];
+    play(&mut game, &cycle);
+    assert_eq!(game.state(), GameState::Playing);
+
+    play(&mut game, &cycle);
+    assert_eq!(game.state(), GameState::PerpetualCheck(Color::Black));
+    assert_eq!(game.to_pgn().get_tag("Result").unwrap(), "0-1");
+}
+
+#[test]
+fn test_undo_clears_repetition() {
+    let mut game = Game::new();
+    play(&mut game, &HORSE_SHUTTLE);
+    play(&mut game, &HORSE_SHUTTLE);
+    assert_eq!(game.state(), GameState::DrawByRepetition);
+
+    assert!(game.undo_move());
+    assert_eq!(game.state(), GameState::Playing);
+    // Varying the last move avoids the repetition
+    play(&mut game, &[((2, 2), (4, 1))]);
+    assert_eq!(game.state(), GameState::Playing);
+}
+
+#[test]
+fn test_move_limit_draw() {
+    // Both generals walk without capturing; the clock starts near the limit
+    let fen = format!(
+        "3k5/9/9/9/9/9/9/9/9/5K3 w - - {} 80",
+        DEFAULT_MOVE_LIMIT * 2 - 2
+    );
+    let mut game = Game::from_fen(&fen).unwrap();
+    play(&mut game, &[((5, 9), (5, 8))]);
+    assert_eq!(game.state(), GameState::Playing);
+    play(&mut game, &[((3, 0), (3, 1))]);
+    assert_eq!(game.state(), GameState::DrawByMoveLimit);
+
+    // With the rule disabled the game goes on
+    let mut game = Game::from_fen(&fen).unwrap();
+    game.set_move_limit(None);
+    play(&mut game, &[((5, 9), (5, 8)), ((3, 0), (3, 1))]);
+    assert_eq!(game.state(), GameState::Playing);
+}
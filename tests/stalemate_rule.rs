@@ -0,0 +1,106 @@
+//! Tests for scoring stalemate as a draw or as a loss (困毙)
+
+use cn_chess_tui::report::position_report;
+#[cfg(feature = "tui")]
+use cn_chess_tui::UI;
+use cn_chess_tui::{Color, Game, GameResult, GameState, Position, StalemateRule};
+#[cfg(feature = "tui")]
+use ratatui::{backend::TestBackend, Terminal};
+
+/// Red's chariot steps to e1 and leaves Black's general with no move
+const RED_TO_STALEMATE: &str = "3k5/9/4R4/9/9/9/9/9/9/4K4 w - - 0 1";
+
+/// Red's general on d9 can't move past Black's chariot on e8
+const RED_STALEMATED: &str = "4k4/9/9/9/9/9/9/9/4r4/3K5 w - - 0 1";
+
+fn stalemate_black(rule: StalemateRule) -> Game {
+    let mut game = Game::from_fen(RED_TO_STALEMATE).unwrap();
+    game.set_stalemate_rule(rule);
+    game.make_move(Position::from_xy(4, 2), Position::from_xy(4, 1))
+        .unwrap();
+    game
+}
+
+/// Screen text with spaces removed, since wide characters are followed by
+/// a blank cell
+#[cfg(feature = "tui")]
+fn popup_text(game: &Game) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+    terminal
+        .draw(|f| UI::draw(f, game, Position::from_xy(0, 0), None))
+        .unwrap();
+    terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .filter(|symbol| *symbol != " ")
+        .collect()
+}
+
+#[test]
+fn test_stalemate_is_a_draw_by_default() {
+    let game = stalemate_black(StalemateRule::default());
+    assert_eq!(game.state(), GameState::Stalemate);
+    assert_eq!(game.state().result(), Some(GameResult::Draw));
+    assert!(game.to_pgn().to_pgn().contains("[Result \"1/2-1/2\"]"));
+
+    let report = position_report(&game);
+    assert_eq!(report.state, "stalemate");
+    assert_eq!(report.winner, None);
+
+    #[cfg(feature = "tui")]
+    {
+        let screen = popup_text(&game);
+        assert!(screen.contains("和棋"));
+        assert!(screen.contains("无子可动"));
+        assert!(!screen.contains("困毙"));
+    }
+}
+
+#[test]
+fn test_stalemate_loss_awards_the_win() {
+    let game = stalemate_black(StalemateRule::Loss);
+    assert_eq!(game.state(), GameState::StalemateWin(Color::Red));
+    assert_eq!(game.state().result(), Some(GameResult::RedWins));
+    assert!(game.to_pgn().to_pgn().contains("[Result \"1-0\"]"));
+
+    let report = position_report(&game);
+    assert_eq!(report.state, "stalemate");
+    assert_eq!(report.winner, Some("red"));
+
+    #[cfg(feature = "tui")]
+    {
+        let screen = popup_text(&game);
+        assert!(screen.contains("红方胜利"));
+        assert!(screen.contains("黑方困毙"));
+    }
+}
+
+#[test]
+fn test_stalemated_red_loses() {
+    let mut game = Game::from_fen(RED_STALEMATED).unwrap();
+    assert_eq!(game.state(), GameState::Stalemate);
+
+    game.set_stalemate_rule(StalemateRule::Loss);
+    assert_eq!(game.state(), GameState::StalemateWin(Color::Black));
+    assert_eq!(game.state().result(), Some(GameResult::BlackWins));
+    #[cfg(feature = "tui")]
+    assert!(popup_text(&game).contains("红方困毙"));
+
+    // Switching back rescores the same position
+    game.set_stalemate_rule(StalemateRule::Draw);
+    assert_eq!(game.state(), GameState::Stalemate);
+}
+
+#[test]
+fn test_rule_leaves_other_states_alone() {
+    let mut game = Game::new();
+    game.set_stalemate_rule(StalemateRule::Loss);
+    assert_eq!(game.state(), GameState::Playing);
+
+    game.force_state_for_testing(GameState::DrawByRepetition);
+    game.set_stalemate_rule(StalemateRule::Draw);
+    assert_eq!(game.state(), GameState::DrawByRepetition);
+}
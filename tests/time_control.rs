@@ -0,0 +1,85 @@
+//! Tests for games played on the clock
+
+use cn_chess_tui::game::TimeControl;
+use cn_chess_tui::{Color, GameController, GameResult, GameState, Position};
+
+#[test]
+fn test_increment_added_after_each_move() {
+    let mut controller = GameController::new();
+    assert_eq!(controller.remaining_ms(Color::Red), None);
+
+    controller.set_time_control(Some(TimeControl::minutes(1, 2)));
+    assert_eq!(controller.remaining_ms(Color::Black), Some(60_000));
+    controller
+        .human_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+
+    let red = controller.remaining_ms(Color::Red).unwrap();
+    assert!((61_000..=62_000).contains(&red), "{}", red);
+    assert!(!controller.check_clock());
+}
+
+#[test]
+fn test_flag_ends_the_game() {
+    let mut controller = GameController::new();
+    controller.set_time_control(Some(TimeControl {
+        base_ms: 0,
+        increment_ms: 0,
+    }));
+    assert!(controller.check_clock());
+    assert_eq!(controller.state(), GameState::Timeout(Color::Black));
+    assert_eq!(controller.state().result(), Some(GameResult::BlackWins));
+    assert!(controller
+        .human_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .is_err());
+
+    // Loading a game starts the clocks afresh
+    controller.load_game(cn_chess_tui::Game::new());
+    controller.set_time_control(Some(TimeControl::minutes(5, 0)));
+    assert!(!controller.check_clock());
+}
+
+#[test]
+fn test_side_without_a_clock_never_flags() {
+    let mut controller = GameController::new();
+    controller.set_time_controls([
+        Some(TimeControl::minutes(10, 0)),
+        Some(TimeControl {
+            base_ms: 0,
+            increment_ms: 0,
+        }),
+    ]);
+    assert_eq!(controller.time_control(), None);
+    assert_eq!(
+        controller.side_time_control(Color::Red),
+        Some(TimeControl::minutes(10, 0))
+    );
+
+    // Black has run out, but Red to move has time
+    assert!(!controller.check_clock());
+    controller
+        .human_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    assert!(controller.check_clock());
+    assert_eq!(controller.state().result(), Some(GameResult::RedWins));
+
+    // Only Red on the clock: Black can take all the time it wants
+    controller.load_game(cn_chess_tui::Game::new());
+    controller.set_time_controls([Some(TimeControl::minutes(10, 0)), None]);
+    controller
+        .human_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    assert_eq!(controller.remaining_ms(Color::Black), None);
+    assert!(!controller.check_clock());
+}
+
+#[test]
+fn test_parse_time_control() {
+    assert_eq!("10+5".parse(), Ok(TimeControl::minutes(10, 5)));
+    assert_eq!("30".parse(), Ok(TimeControl::minutes(30, 0)));
+    assert!("0+5".parse::<TimeControl>().is_err());
+    assert!("fast".parse::<TimeControl>().is_err());
+    // Too many minutes or seconds to count in milliseconds
+    assert!("400000000000000+0".parse::<TimeControl>().is_err());
+    assert!("10+20000000000000000".parse::<TimeControl>().is_err());
+}
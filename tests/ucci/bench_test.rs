@@ -0,0 +1,55 @@
+//! Integration tests for the engine benchmark
+
+use cn_chess_tui::ucci::bench::{run_bench, SUITE};
+use cn_chess_tui::ucci::UcciClient;
+
+#[test]
+#[cfg(unix)]
+fn test_bench_reads_long_outputs() {
+    // Thousands of info lines per search, and always the same answer
+    let mock = super::client_test::write_mock_engine(
+        r#"#!/bin/bash
+while read line; do
+    case "$line" in
+        "ucci")
+            echo "id name LongOutput"
+            echo "ucciok"
+            ;;
+        "go depth "*)
+            for i in $(seq 1 3000); do
+                echo "info depth $(( i % 7 + 1 )) score 12 nodes $i pv i9i0 d0d1"
+            done
+            echo "bestmove i9i0"
+            ;;
+        "quit")
+            echo "bye"
+            exit 0
+            ;;
+    esac
+done
+"#,
+    );
+    let mut client = UcciClient::new(mock.to_str().unwrap()).unwrap();
+    client.initialize().unwrap();
+
+    let report = run_bench(&mut client, SUITE, 7).unwrap();
+    assert_eq!(report.engine, "LongOutput");
+    assert_eq!(report.results.len(), SUITE.len());
+    for result in &report.results {
+        assert_eq!(result.info_lines, 3000);
+        assert_eq!(result.depth, Some(7));
+        assert_eq!(result.nodes, Some(3000));
+        assert_eq!(result.best_move.as_deref(), Some("i9i0"));
+    }
+    // Only the chariot mate expects that move
+    assert_eq!(report.solved(), (1, 4));
+    assert_eq!(report.results[0].solved, None);
+    assert_eq!(report.total_nodes(), 3000 * SUITE.len() as u64);
+
+    let text = report.to_text();
+    assert!(text.starts_with("Bench: LongOutput, depth 7"));
+    assert!(text.contains("Solved 1/4"));
+    assert!(client.is_idle());
+
+    client.shutdown().unwrap();
+}
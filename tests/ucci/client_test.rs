@@ -7,9 +7,7 @@ use tempfile::NamedTempFile;
 /// Create a mock engine script that responds to UCCI commands
 #[cfg(unix)]
 fn create_mock_engine() -> tempfile::TempPath {
-    let mut file = NamedTempFile::new().unwrap();
-    writeln!(
-        file,
+    write_mock_engine(
         r#"#!/bin/bash
 # Mock UCCI engine for testing
 
@@ -33,6 +31,9 @@ while read line; do
         "banmoves "*)
             # Accept any banmoves command
             ;;
+        "probe "*)
+            echo "pophash bestmove h2e2 lowerbound 35 12"
+            ;;
         "go depth "*)
             echo "info depth 10 score 100 nodes 1000 time 100"
             echo "bestmove h2e2"
@@ -44,6 +45,12 @@ while read line; do
         "go infinite")
             echo "info depth 1 score 10"
             ;;
+        "go ponder "*)
+            echo "info depth 4 score -20 pv h0g2"
+            ;;
+        "ponderhit")
+            echo "bestmove h9g7"
+            ;;
         "stop")
             echo "bestmove h2e2"
             ;;
@@ -53,9 +60,15 @@ while read line; do
             ;;
     esac
 done
-"#
+"#,
     )
-    .unwrap();
+}
+
+/// Write a mock engine script and make it executable
+#[cfg(unix)]
+pub fn write_mock_engine(script: &str) -> tempfile::TempPath {
+    let mut file = NamedTempFile::new().unwrap();
+    writeln!(file, "{}", script).unwrap();
 
     // Flush and sync to ensure all data is written
     file.as_file().flush().unwrap();
@@ -210,6 +223,33 @@ fn test_client_go_time_stop() {
     client.shutdown().unwrap();
 }
 
+#[test]
+#[cfg(unix)]
+fn test_client_go_depth_wait() {
+    let mock = create_mock_engine();
+    let mut client = UcciClient::new(mock.to_str().unwrap()).unwrap();
+
+    client.initialize().unwrap();
+
+    // Nothing to wait for while idle
+    assert!(client.wait().is_err());
+
+    let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+    client.set_position(fen, &[]).unwrap();
+    client.go_depth(10).unwrap();
+
+    // The search finishes on its own, without a stop
+    let result = client.wait().unwrap();
+    assert_eq!(
+        result,
+        cn_chess_tui::ucci::MoveResult::Move("h2e2".to_string(), None)
+    );
+    assert!(client.is_idle());
+    assert_eq!(client.read_info()[0].depth, Some(10));
+
+    client.shutdown().unwrap();
+}
+
 #[test]
 #[cfg(unix)]
 fn test_client_ban_moves() {
@@ -228,6 +268,67 @@ fn test_client_ban_moves() {
     client.shutdown().unwrap();
 }
 
+#[test]
+#[cfg(unix)]
+fn test_client_probe() {
+    use cn_chess_tui::ucci::PopHashInfo;
+
+    let mock = create_mock_engine();
+    let mut client = UcciClient::new(mock.to_str().unwrap()).unwrap();
+
+    client.initialize().unwrap();
+
+    let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+    let hash = client.probe(fen, &[]).unwrap();
+    assert_eq!(
+        hash,
+        PopHashInfo {
+            bestmove: Some("h2e2".to_string()),
+            lowerbound: Some((35, 12)),
+            upperbound: None,
+        }
+    );
+    assert!(hash.is_hit());
+    assert!(client.is_idle());
+
+    // Not while searching
+    client.go_infinite().unwrap();
+    assert!(client.probe(fen, &[]).is_err());
+    client.stop().unwrap();
+
+    client.shutdown().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_client_probe_unanswered() {
+    use cn_chess_tui::ucci::engine::EngineError;
+
+    let mock = write_mock_engine(
+        r#"#!/bin/bash
+while read line; do
+    case "$line" in
+        "ucci") echo "ucciok" ;;
+        "isready") echo "readyok" ;;
+        "quit") echo "bye"; exit 0 ;;
+    esac
+done
+"#,
+    );
+    let mut client = UcciClient::new(mock.to_str().unwrap()).unwrap();
+    client.initialize().unwrap();
+
+    let fen = "4k4/9/9/9/9/9/9/9/9/4K4 w - - 0 1";
+    assert!(matches!(client.probe(fen, &[]), Err(EngineError::Timeout)));
+    // The engine is not asked again
+    let started = std::time::Instant::now();
+    assert!(matches!(client.probe(fen, &[]), Err(EngineError::Timeout)));
+    assert!(started.elapsed() < std::time::Duration::from_millis(100));
+    assert!(client.is_ready().unwrap());
+
+    client.shutdown().unwrap();
+}
+
 #[test]
 #[cfg(unix)]
 fn test_client_is_ready() {
@@ -241,3 +342,232 @@ fn test_client_is_ready() {
 
     client.shutdown().unwrap();
 }
+
+#[test]
+#[cfg(unix)]
+fn test_client_poll_infinite() {
+    let mock = create_mock_engine();
+    let mut client = UcciClient::new(mock.to_str().unwrap()).unwrap();
+
+    client.initialize().unwrap();
+    assert!(client.poll().is_err());
+
+    let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+    client.set_position(fen, &[]).unwrap();
+    client.go_infinite().unwrap();
+
+    // Polling never blocks; the info shows up once the engine sends it
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let mut infos = Vec::new();
+    while infos.is_empty() && std::time::Instant::now() < deadline {
+        assert_eq!(client.poll().unwrap(), None);
+        infos = client.read_info();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!(infos[0].depth, Some(1));
+    assert_eq!(infos[0].score, Some(10));
+    assert!(client.is_thinking());
+
+    let result = client.stop().unwrap();
+    assert_eq!(
+        result,
+        cn_chess_tui::ucci::MoveResult::Move("h2e2".to_string(), None)
+    );
+    assert!(client.is_idle());
+
+    client.shutdown().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_client_ponder() {
+    let mock = create_mock_engine();
+    let mut client = UcciClient::new(mock.to_str().unwrap()).unwrap();
+
+    client.initialize().unwrap();
+    assert!(client.ponder_hit().is_err(), "nothing to ponder on");
+
+    // Ponder on the reply, then have it played
+    let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+    client.set_position(fen, &["h0g2".to_string()]).unwrap();
+    client.go_ponder_depth(10).unwrap();
+    assert!(client.is_pondering());
+    assert!(client.is_thinking());
+    assert!(client.set_position(fen, &[]).is_err());
+
+    client.ponder_hit().unwrap();
+    assert!(!client.is_pondering());
+    assert!(client.is_thinking());
+    assert_eq!(
+        client.wait().unwrap(),
+        cn_chess_tui::ucci::MoveResult::Move("h9g7".to_string(), None)
+    );
+    assert_eq!(client.read_info()[0].pv, ["h0g2"]);
+    assert!(client.is_idle());
+
+    // Another move was played: the pondering search is stopped
+    client.go_ponder_depth(10).unwrap();
+    client.stop().unwrap();
+    assert!(client.is_idle());
+
+    client.shutdown().unwrap();
+}
+
+/// Create a mock UCI engine numbering ranks from 1, like Fairy-Stockfish
+///
+/// It only finds a move once switched to xiangqi and told of Red's central
+/// cannon (h3e3), and doesn't understand `ucci`.
+#[cfg(unix)]
+fn create_mock_uci_engine() -> tempfile::TempPath {
+    write_mock_engine(
+        r#"#!/bin/bash
+# Mock UCI engine for testing
+variant=chess
+
+while read line; do
+    case "$line" in
+        "uci")
+            echo "id name MockUci"
+            echo "option name UCI_Variant type combo default chess var chess var xiangqi"
+            echo "option name Clear Hash type button"
+            echo "uciok"
+            ;;
+        "isready")
+            echo "readyok"
+            ;;
+        "setoption name UCI_Variant value xiangqi")
+            variant=xiangqi
+            ;;
+        "position fen "*)
+            position="$line"
+            ;;
+        "go depth "*)
+            if [ "$variant" = xiangqi ] && [[ "$position" == *"moves h3e3" ]]; then
+                echo "info depth 3 seldepth 5 score cp 25 nodes 900 nps 1000 time 12 pv h10g8 b1c3"
+                echo "bestmove h10g8 ponder b1c3"
+            else
+                echo "bestmove (none)"
+            fi
+            ;;
+        "quit")
+            exit 0
+            ;;
+        *)
+            echo "Unknown command: '$line'"
+            ;;
+    esac
+done
+"#,
+    )
+}
+
+#[test]
+#[cfg(unix)]
+fn test_client_detects_uci() {
+    use cn_chess_tui::ucci::{EngineProtocol, MoveResult};
+
+    let mock = create_mock_uci_engine();
+    let mut client = UcciClient::new(mock.to_str().unwrap()).unwrap();
+
+    client.initialize_with(EngineProtocol::Auto).unwrap();
+    assert_eq!(client.protocol(), EngineProtocol::Uci);
+    assert_eq!(client.engine_info().name, "MockUci");
+    assert!(client.options().contains_key("Clear Hash"));
+    assert!(client.is_idle());
+
+    // Moves go out and come back in this crate's ICCS
+    let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR b - - 1 1";
+    client.set_position(fen, &["h7e7".to_string()]).unwrap();
+    client.ban_moves(&["h0g2".to_string()]).unwrap();
+    client.go_depth(3).unwrap();
+    assert_eq!(
+        client.wait().unwrap(),
+        MoveResult::Move("h0g2".to_string(), Some("b9c7".to_string()))
+    );
+    let info = client.read_info().pop().unwrap();
+    assert_eq!(info.score, Some(25));
+    assert_eq!(info.pv, ["h0g2", "b9c7"]);
+
+    client.shutdown().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_client_multipv_lines() {
+    // Reports as many lines as it was told to search, then deepens the best
+    let mock = write_mock_engine(
+        r#"#!/bin/bash
+lines=1
+while read line; do
+    case "$line" in
+        "ucci")
+            echo "id name MockEngine"
+            echo "option MultiPV type spin min 1 max 3 default 1"
+            echo "ucciok"
+            ;;
+        "setoption MultiPV "*)
+            lines=${line##* }
+            ;;
+        "go time "*)
+            echo "info depth 6 multipv 1 score 40 pv h2e2 h9g7"
+            [ "$lines" -ge 2 ] && echo "info depth 6 multipv 2 score 25 pv h0g2"
+            [ "$lines" -ge 3 ] && echo "info depth 6 multipv 3 score 10 pv c3c4"
+            echo "info depth 7 multipv 1 score 35 pv h2e2 h9g7 h0g2"
+            echo "bestmove h2e2"
+            ;;
+        "quit")
+            exit 0
+            ;;
+    esac
+done
+"#,
+    );
+    let mut client = UcciClient::new(mock.to_str().unwrap()).unwrap();
+    client.initialize().unwrap();
+
+    // More lines than the option allows are capped at its maximum
+    assert!(client.set_multipv(5).unwrap());
+    let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+    client.set_position(fen, &[]).unwrap();
+    client.go_time(100).unwrap();
+    client.wait().unwrap();
+
+    let lines = client.pv_lines();
+    let summary: Vec<_> = lines
+        .iter()
+        .map(|line| (line.index, line.depth, line.score, line.pv[0].as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (1, Some(7), Some(35), "h2e2"),
+            (2, Some(6), Some(25), "h0g2"),
+            (3, Some(6), Some(10), "c3c4"),
+        ]
+    );
+
+    // A new search starts with no lines, and one is reported once set back
+    client.set_multipv(1).unwrap();
+    client.go_time(100).unwrap();
+    assert!(client.pv_lines().len() <= 1);
+    client.wait().unwrap();
+    assert_eq!(client.pv_lines().len(), 1);
+
+    client.shutdown().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_client_multipv_without_option() {
+    let mock = create_mock_engine();
+    let mut client = UcciClient::new(mock.to_str().unwrap()).unwrap();
+    client.initialize().unwrap();
+
+    assert!(!client.set_multipv(3).unwrap());
+    client.go_depth(10).unwrap();
+    client.wait().unwrap();
+    // The mock's info has no moves, so no line
+    assert!(client.pv_lines().is_empty());
+
+    client.shutdown().unwrap();
+}
@@ -0,0 +1,108 @@
+//! Integration tests for analyzing with several engines at once
+
+use cn_chess_tui::ucci::kibitz::{Kibitz, KibitzEngine};
+use cn_chess_tui::ucci::EngineProtocol;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A mock engine named `name` that sends a second line only once told to
+/// search two
+#[cfg(unix)]
+fn multipv_engine(name: &str, score: i32) -> tempfile::TempPath {
+    super::client_test::write_mock_engine(&format!(
+        r#"#!/bin/bash
+lines=1
+while read line; do
+    case "$line" in
+        "ucci")
+            echo "id name {name}"
+            echo "option multipv type spin min 1 max 8 default 1"
+            echo "ucciok"
+            ;;
+        "setoption multipv "*)
+            lines=${{line##* }}
+            ;;
+        "go infinite")
+            echo "info depth 8 multipv 1 score {score} pv h2e2 h9g7"
+            if [ "$lines" -ge 2 ]; then
+                echo "info depth 8 multipv 2 score 5 pv b0c2"
+            fi
+            ;;
+        "stop")
+            echo "bestmove h2e2"
+            ;;
+        "quit")
+            echo "bye"
+            exit 0
+            ;;
+    esac
+done
+"#
+    ))
+}
+
+fn settings(path: &Path, multipv: Option<u32>) -> KibitzEngine {
+    KibitzEngine {
+        path: path.to_path_buf(),
+        protocol: EngineProtocol::Ucci,
+        multipv,
+    }
+}
+
+/// Poll until every engine has `lines` lines, or give up after a while
+fn wait_for_lines(kibitz: &mut Kibitz, fen: &str, lines: &[usize]) {
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(5) {
+        kibitz.poll(fen);
+        let found: Vec<usize> = kibitz.kibitzers().iter().map(|k| k.lines().len()).collect();
+        if found == lines {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("engines never reported {:?} lines", lines);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_engines_analyze_side_by_side() {
+    let first = multipv_engine("First", 30);
+    let second = multipv_engine("Second", -12);
+    let (mut kibitz, failed) = Kibitz::start(&[settings(&first, Some(2)), settings(&second, None)]);
+    assert!(failed.is_empty());
+
+    let fen = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+    wait_for_lines(&mut kibitz, fen, &[2, 1]);
+
+    let engines = kibitz.kibitzers();
+    assert_eq!(engines[0].name(), "First");
+    assert_eq!(engines[0].lines()[0].score, Some(30));
+    assert_eq!(engines[0].lines()[1].pv, ["b0c2"]);
+    assert_eq!(engines[1].name(), "Second");
+    assert_eq!(engines[1].lines()[0].score, Some(-12));
+    kibitz.stop();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_engines_start_over_on_a_new_position() {
+    let engine = multipv_engine("Only", 30);
+    let (mut kibitz, _) = Kibitz::start(&[settings(&engine, None)]);
+    let start = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w - - 0 1";
+    wait_for_lines(&mut kibitz, start, &[1]);
+
+    let after = "rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C2C4/9/RNBAKABNR b - - 1 1";
+    let (changed, failed) = kibitz.poll(after);
+    assert!(changed && failed.is_empty());
+    assert!(kibitz.kibitzers()[0].lines().is_empty());
+    wait_for_lines(&mut kibitz, after, &[1]);
+}
+
+#[test]
+fn test_missing_engines_are_left_out() {
+    let (kibitz, failed) = Kibitz::start(&[settings(&PathBuf::from("/nonexistent/engine"), None)]);
+    assert!(kibitz.is_empty());
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].0, PathBuf::from("/nonexistent/engine"));
+}
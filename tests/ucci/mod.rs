@@ -1,5 +1,9 @@
+pub mod bench_test;
 pub mod client_test;
+pub mod kibitz_test;
 pub mod parser_test;
 pub mod protocol_test;
 pub mod serializer_test;
+pub mod server_test;
 pub mod state_test;
+pub mod uci_test;
@@ -1,5 +1,6 @@
-use cn_chess_tui::ucci::parser::parse_response;
-use cn_chess_tui::ucci::UcciResponse;
+use cn_chess_tui::ucci::parser::{parse_command, parse_response};
+use cn_chess_tui::ucci::{GoMode, UcciCommand, UcciResponse};
+use cn_chess_tui::Game;
 
 #[test]
 fn test_parse_empty_line() {
@@ -62,3 +63,90 @@ fn test_parse_info_message() {
         _ => panic!("Wrong response type"),
     }
 }
+
+#[test]
+fn test_parse_position_command() {
+    let cmd = parse_command("position startpos moves h7e7 h0g2").unwrap();
+    assert_eq!(
+        cmd,
+        UcciCommand::Position {
+            fen: Game::new().to_fen(),
+            moves: vec!["h7e7".to_string(), "h0g2".to_string()],
+        }
+    );
+
+    let fen = "4k4/9/9/9/9/9/9/9/9/4K4 w - - 0 1";
+    let cmd = parse_command(&format!("probe fen {}", fen)).unwrap();
+    assert_eq!(
+        cmd,
+        UcciCommand::Probe {
+            fen: fen.to_string(),
+            moves: Vec::new(),
+        }
+    );
+    assert!(parse_command("position").is_err());
+}
+
+#[test]
+fn test_parse_go_command() {
+    let cmd = parse_command("go ponder time 300 movestogo 40 increment 5").unwrap();
+    assert_eq!(
+        cmd,
+        UcciCommand::Go {
+            mode: GoMode::Time {
+                time: 300,
+                movestogo: Some(40),
+                increment: Some(5),
+                opptime: None,
+                oppmovestogo: None,
+                oppincrement: None,
+            },
+            ponder: true,
+            draw: false,
+        }
+    );
+    assert_eq!(
+        parse_command("go").unwrap(),
+        UcciCommand::Go {
+            mode: GoMode::Infinite,
+            ponder: false,
+            draw: false,
+        }
+    );
+    assert!(parse_command("go depth deep").is_err());
+}
+
+#[test]
+fn test_parse_other_commands() {
+    assert_eq!(parse_command("ucci").unwrap(), UcciCommand::Ucci);
+    assert_eq!(
+        parse_command("setoption usemillisec true").unwrap(),
+        UcciCommand::SetOption {
+            name: "usemillisec".to_string(),
+            value: Some("true".to_string()),
+        }
+    );
+    assert_eq!(
+        parse_command("banmoves h2e2").unwrap(),
+        UcciCommand::BanMoves {
+            moves: vec!["h2e2".to_string()],
+        }
+    );
+    assert_eq!(
+        parse_command("ponderhit draw").unwrap(),
+        UcciCommand::PonderHit { draw: true }
+    );
+    assert!(parse_command("").is_err());
+    assert!(parse_command("think").is_err());
+}
+
+#[test]
+fn test_parse_info_multipv() {
+    match parse_response("info depth 9 multipv 2 score -15 pv b0c2 h9g7").unwrap() {
+        UcciResponse::Info { multipv, pv, .. } => {
+            assert_eq!(multipv, Some(2));
+            assert_eq!(pv, vec!["b0c2", "h9g7"]);
+        }
+        _ => panic!("Wrong response type"),
+    }
+}
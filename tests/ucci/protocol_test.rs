@@ -46,8 +46,8 @@ fn test_bestmove_response() {
         } => {
             assert_eq!(mv, "h2e2");
             assert_eq!(ponder, Some("h9g7".to_string()));
-            assert_eq!(draw, false);
-            assert_eq!(resign, false);
+            assert!(!draw);
+            assert!(!resign);
         }
         _ => panic!("Wrong response type"),
     }
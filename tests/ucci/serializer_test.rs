@@ -1,4 +1,6 @@
-use cn_chess_tui::ucci::{GoMode, UcciCommand};
+use cn_chess_tui::ucci::parser::parse_response;
+use cn_chess_tui::ucci::protocol::OptionType;
+use cn_chess_tui::ucci::{GoMode, UcciCommand, UcciResponse};
 
 fn serialize(cmd: &UcciCommand) -> String {
     cmd.serialize()
@@ -59,7 +61,7 @@ fn test_serialize_go_depth() {
         ponder: false,
         draw: false,
     };
-    assert_eq!(serialize(&cmd), "depth 10");
+    assert_eq!(serialize(&cmd), "go depth 10");
 }
 
 #[test]
@@ -76,7 +78,7 @@ fn test_serialize_go_time() {
         ponder: false,
         draw: false,
     };
-    assert_eq!(serialize(&cmd), "time 300000 increment 0");
+    assert_eq!(serialize(&cmd), "go time 300000 increment 0");
 }
 
 #[test]
@@ -93,7 +95,7 @@ fn test_serialize_go_ponder() {
         ponder: true,
         draw: false,
     };
-    assert_eq!(serialize(&cmd), "ponder time 295000");
+    assert_eq!(serialize(&cmd), "go ponder time 295000");
 }
 
 #[test]
@@ -159,7 +161,7 @@ fn test_serialize_go_infinite() {
         ponder: false,
         draw: false,
     };
-    assert_eq!(serialize(&cmd), "infinite");
+    assert_eq!(serialize(&cmd), "go infinite");
 }
 
 #[test]
@@ -169,5 +171,60 @@ fn test_serialize_go_nodes() {
         ponder: false,
         draw: false,
     };
-    assert_eq!(serialize(&cmd), "nodes 1000000");
+    assert_eq!(serialize(&cmd), "go nodes 1000000");
+}
+
+#[test]
+fn test_serialize_responses() {
+    let bestmove = UcciResponse::BestMove {
+        mv: "h2e2".to_string(),
+        ponder: Some("h9g7".to_string()),
+        draw: false,
+        resign: false,
+    };
+    assert_eq!(bestmove.serialize(), "bestmove h2e2 ponder h9g7");
+    assert_eq!(UcciResponse::NoBestMove.serialize(), "nobestmove");
+
+    let option = UcciResponse::Option {
+        name: "usemillisec".to_string(),
+        type_: OptionType::Check,
+        min: None,
+        max: None,
+        vars: Vec::new(),
+        default: Some("false".to_string()),
+    };
+    assert_eq!(
+        option.serialize(),
+        "option usemillisec type check default false"
+    );
+}
+
+#[test]
+fn test_serialized_responses_parse_back() {
+    let responses = [
+        UcciResponse::Id {
+            field: "name".to_string(),
+            value: "cn_chess_tui".to_string(),
+        },
+        UcciResponse::UcciOk,
+        UcciResponse::Info {
+            time: Some(12),
+            nodes: Some(3400),
+            depth: Some(3),
+            score: Some(-25),
+            multipv: None,
+            pv: vec!["h2e2".to_string(), "h9g7".to_string()],
+            currmove: None,
+            message: None,
+        },
+        UcciResponse::PopHash {
+            bestmove: Some("h2e2".to_string()),
+            lowerbound: Some((30, 4)),
+            upperbound: None,
+        },
+        UcciResponse::Bye,
+    ];
+    for resp in responses {
+        assert_eq!(parse_response(&resp.serialize()).unwrap(), resp);
+    }
 }
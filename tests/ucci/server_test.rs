@@ -0,0 +1,112 @@
+use cn_chess_tui::notation::iccs::iccs_to_move;
+use cn_chess_tui::ucci::server::{self, UcciServer};
+use cn_chess_tui::ucci::{GoMode, UcciCommand, UcciResponse};
+use cn_chess_tui::{Game, Position};
+
+fn go_depth(depth: u32) -> UcciCommand {
+    UcciCommand::Go {
+        mode: GoMode::Depth(depth),
+        ponder: false,
+        draw: false,
+    }
+}
+
+fn best_move(responses: &[UcciResponse]) -> Option<&str> {
+    match responses.last() {
+        Some(UcciResponse::BestMove { mv, .. }) => Some(mv),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_server_handshake() {
+    let mut output = Vec::new();
+    server::run(
+        "ucci\nsetoption usemillisec true\nnonsense\nisready\nquit\nisready\n".as_bytes(),
+        &mut output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(
+        output,
+        "id name cn_chess_tui\n\
+         option usemillisec type check default false\n\
+         ucciok\n\
+         readyok\n\
+         bye\n"
+    );
+}
+
+#[test]
+fn test_server_plays_legal_moves() {
+    let mut output = Vec::new();
+    server::run(
+        "position startpos moves h7e7\ngo depth 2\n".as_bytes(),
+        &mut output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("info depth 1 score "));
+    assert!(lines[1].starts_with("info depth 2 score "));
+
+    let mv = lines[2].strip_prefix("bestmove ").unwrap();
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    let (from, to) = iccs_to_move(mv).unwrap();
+    game.make_move(from, to).unwrap();
+}
+
+#[test]
+fn test_server_respects_banned_moves() {
+    let mut server = UcciServer::new();
+    let best = best_move(&server.handle(go_depth(1))).unwrap().to_string();
+
+    server.handle(UcciCommand::BanMoves {
+        moves: vec![best.clone()],
+    });
+    let responses = server.handle(go_depth(1));
+    assert_ne!(best_move(&responses), Some(best.as_str()));
+
+    // A new position lifts the ban
+    server.handle(UcciCommand::Position {
+        fen: Game::new().to_fen(),
+        moves: Vec::new(),
+    });
+    let responses = server.handle(go_depth(1));
+    assert_eq!(best_move(&responses), Some(best.as_str()));
+}
+
+#[test]
+fn test_server_holds_infinite_search_until_stop() {
+    let mut server = UcciServer::new();
+    server.handle(UcciCommand::Position {
+        fen: "3k5/9/9/9/9/9/9/9/4R4/4K4 w - - 0 1".to_string(),
+        moves: Vec::new(),
+    });
+    let responses = server.handle(UcciCommand::Go {
+        mode: GoMode::Infinite,
+        ponder: false,
+        draw: false,
+    });
+    assert!(responses
+        .iter()
+        .all(|r| matches!(r, UcciResponse::Info { .. })));
+
+    let responses = server.handle(UcciCommand::Stop);
+    assert!(best_move(&responses).is_some());
+    assert!(server.handle(UcciCommand::Stop).is_empty());
+}
+
+#[test]
+fn test_server_without_moves_has_no_best_move() {
+    let mut server = UcciServer::new();
+    // Red is checkmated by the rook and the facing general
+    server.handle(UcciCommand::Position {
+        fen: "3k5/9/9/9/9/9/9/9/9/r2K5 w - - 0 1".to_string(),
+        moves: Vec::new(),
+    });
+    assert_eq!(server.handle(go_depth(2)), vec![UcciResponse::NoBestMove]);
+}
@@ -59,9 +59,48 @@ fn test_pondering_mode() {
     })
     .unwrap();
     assert!(sm.is_thinking());
+    assert!(sm.is_pondering());
 
-    // PonderHit keeps us in thinking state
+    // PonderHit turns the search into a normal one
     assert!(sm.can_send(&UcciCommand::PonderHit { draw: false }));
+    sm.transition(&UcciCommand::PonderHit { draw: false })
+        .unwrap();
+    assert!(sm.is_thinking());
+    assert!(!sm.is_pondering());
+    assert!(!sm.can_send(&UcciCommand::PonderHit { draw: false }));
+
+    sm.on_response(&UcciResponse::BestMove {
+        mv: "h9g7".to_string(),
+        ponder: None,
+        draw: false,
+        resign: false,
+    })
+    .unwrap();
+    assert!(sm.is_idle());
+}
+
+#[test]
+fn test_stop_while_pondering() {
+    let mut sm = UcciStateMachine::new();
+    sm.transition(&UcciCommand::Ucci).unwrap();
+    sm.on_response(&UcciResponse::UcciOk).unwrap();
+    sm.transition(&UcciCommand::Go {
+        mode: GoMode::Depth(10),
+        ponder: true,
+        draw: false,
+    })
+    .unwrap();
+
+    sm.transition(&UcciCommand::Stop).unwrap();
+    assert!(sm.is_pondering());
+    sm.on_response(&UcciResponse::BestMove {
+        mv: "h9g7".to_string(),
+        ponder: None,
+        draw: false,
+        resign: false,
+    })
+    .unwrap();
+    assert!(sm.is_idle());
 }
 
 #[test]
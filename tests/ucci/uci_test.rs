@@ -0,0 +1,94 @@
+//! Tests for UCI command serialization and response parsing
+
+use cn_chess_tui::ucci::protocol::OptionType;
+use cn_chess_tui::ucci::uci::{parse_response, serialize_command, UciSquares};
+use cn_chess_tui::ucci::{GoMode, UcciCommand, UcciResponse};
+
+#[test]
+fn test_squares_round_trip() {
+    for squares in [UciSquares::ZERO_BASED, UciSquares::ONE_BASED] {
+        for iccs in ["a0i9", "h7e7", "e9e8", "i0a9"] {
+            let uci = squares.move_to_uci(iccs);
+            assert_eq!(squares.move_from_uci(&uci).unwrap(), iccs);
+        }
+    }
+    assert_eq!(UciSquares::ONE_BASED.move_to_uci("a0a9"), "a10a1");
+    assert_eq!(UciSquares::ZERO_BASED.move_from_uci("a10a9"), None);
+    assert_eq!(UciSquares::ONE_BASED.move_from_uci("a0a1"), None);
+    assert_eq!(UciSquares::ONE_BASED.move_from_uci("(none)"), None);
+}
+
+#[test]
+fn test_serialize_uci_commands() {
+    let squares = UciSquares::ZERO_BASED;
+    let cmd = |cmd: UcciCommand| serialize_command(&cmd, squares);
+
+    assert_eq!(cmd(UcciCommand::Ucci).unwrap(), "uci");
+    assert_eq!(
+        cmd(UcciCommand::SetOption {
+            name: "Hash".to_string(),
+            value: Some("64".to_string()),
+        })
+        .unwrap(),
+        "setoption name Hash value 64"
+    );
+    assert_eq!(
+        cmd(UcciCommand::Position {
+            fen: "4k4/9/9/9/9/9/9/9/9/4K4 w - - 0 1".to_string(),
+            moves: vec!["e9e8".to_string()],
+        })
+        .unwrap(),
+        "position fen 4k4/9/9/9/9/9/9/9/9/4K4 w - - 0 1 moves e0e1"
+    );
+    assert_eq!(
+        cmd(UcciCommand::Go {
+            mode: GoMode::Time {
+                time: 500,
+                movestogo: None,
+                increment: None,
+                opptime: None,
+                oppmovestogo: None,
+                oppincrement: None,
+            },
+            ponder: false,
+            draw: false,
+        })
+        .unwrap(),
+        "go movetime 500"
+    );
+    assert_eq!(cmd(UcciCommand::BanMoves { moves: vec![] }), None);
+}
+
+#[test]
+fn test_parse_uci_responses() {
+    let squares = UciSquares::ONE_BASED;
+    let parse = |line: &str| parse_response(line, squares).unwrap();
+
+    assert_eq!(parse("uciok"), UcciResponse::UcciOk);
+    assert_eq!(parse("bestmove (none)"), UcciResponse::NoBestMove);
+    assert_eq!(
+        parse("option name Skill Level type spin default 20 min -20 max 20"),
+        UcciResponse::Option {
+            name: "Skill Level".to_string(),
+            type_: OptionType::Spin,
+            min: Some(-20),
+            max: Some(20),
+            vars: vec![],
+            default: Some("20".to_string()),
+        }
+    );
+    assert_eq!(
+        parse("info depth 9 seldepth 14 multipv 1 score mate -2 wdl 0 0 1000 nodes 51 time 3 pv e10e9 e1e2"),
+        UcciResponse::Info {
+            time: Some(3),
+            nodes: Some(51),
+            depth: Some(9),
+            score: Some(-29_998),
+            multipv: Some(1),
+            pv: vec!["e0e1".to_string(), "e9e8".to_string()],
+            currmove: None,
+            message: None,
+        }
+    );
+    assert!(parse_response("Pikafish 2024 by the Pikafish developers", squares).is_err());
+}
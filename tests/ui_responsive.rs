@@ -1,3 +1,4 @@
+use cn_chess_tui::notation::format::MoveStyle;
 use cn_chess_tui::{Game, Position, UI};
 use ratatui::backend::TestBackend;
 use ratatui::Terminal;
@@ -8,6 +9,17 @@ fn create_terminal(width: u16, height: u16) -> Terminal<TestBackend> {
     Terminal::new(backend).unwrap()
 }
 
+/// Everything drawn on the terminal, row after row
+fn screen_text(terminal: &Terminal<TestBackend>) -> String {
+    terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect()
+}
+
 /// Minimum usable terminal size for the game
 const MIN_USABLE_WIDTH: u16 = 22;
 const MIN_USABLE_HEIGHT: u16 = 22;
@@ -278,3 +290,1126 @@ fn test_square_terminals() {
         assert!(result.is_ok());
     }
 }
+
+#[test]
+fn test_game_picker_pages_to_selection() {
+    use cn_chess_tui::pgn::PgnGame;
+    use cn_chess_tui::ui::GamePickerState;
+
+    let games: Vec<PgnGame> = (1..=50)
+        .map(|i| {
+            let mut game = PgnGame::new();
+            game.set_tag("Event", format!("Round {}", i));
+            game
+        })
+        .collect();
+    let mut state = GamePickerState::new("collection.pgn", games);
+    state.move_selection(45);
+
+    let mut terminal = create_terminal(80, 24);
+    terminal
+        .draw(|f| UI::draw_game_picker(f, &mut state))
+        .unwrap();
+
+    assert_eq!(state.page_rows, 21);
+    let screen = screen_text(&terminal);
+    assert!(screen.contains("50 games"));
+    assert!(screen.contains("Round 46"));
+    assert!(!screen.contains("Round 1 "));
+
+    state.move_selection(100);
+    assert_eq!(state.selected, 49);
+}
+
+#[test]
+fn test_library_lists_saved_games() {
+    use cn_chess_tui::library::{archive_game, list_games};
+    use cn_chess_tui::ui::LibraryState;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    archive_game(dir.path(), &game).unwrap();
+
+    let mut state = LibraryState::new("games", list_games(dir.path()).unwrap());
+    let mut terminal = create_terminal(80, 24);
+    terminal.draw(|f| UI::draw_library(f, &mut state)).unwrap();
+
+    let screen = screen_text(&terminal);
+    assert!(screen.contains("games (1 games)"));
+    assert!(screen.contains("(1 moves)"));
+    assert!(state.selected_entry().is_some());
+
+    let mut empty = LibraryState::new("games", Vec::new());
+    terminal.draw(|f| UI::draw_library(f, &mut empty)).unwrap();
+    let screen = screen_text(&terminal);
+    assert!(screen.contains("No saved games yet"));
+}
+
+#[test]
+fn test_library_previews_final_position() {
+    use cn_chess_tui::library::{archive_game, list_games};
+    use cn_chess_tui::ui::LibraryState;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    archive_game(dir.path(), &game).unwrap();
+    let mut state = LibraryState::new("games", list_games(dir.path()).unwrap());
+
+    let screen = |width, height, state: &mut LibraryState| {
+        let mut terminal = create_terminal(width, height);
+        terminal.draw(|f| UI::draw_library(f, state)).unwrap();
+        screen_text(&terminal)
+    };
+    assert!(screen(100, 30, &mut state).contains("Final"));
+    assert!(!screen(60, 24, &mut state).contains("Final"));
+}
+
+#[test]
+fn test_review_steps_through_finished_game() {
+    use cn_chess_tui::ui::ReviewState;
+
+    // Two chariots mate in one
+    let mut game = Game::from_fen("4k4/R8/9/9/9/9/9/9/9/3K4R w - - 0 1").unwrap();
+    game.make_move(Position::from_xy(8, 9), Position::from_xy(8, 0))
+        .unwrap();
+    assert_ne!(game.state(), cn_chess_tui::GameState::Playing);
+
+    let mut state = ReviewState::new(game.get_moves().len());
+    assert_eq!(state.ply, 1);
+    state.step(-5);
+    assert_eq!(state.ply, 0);
+    state.step(5);
+    assert_eq!(state.ply, 1);
+
+    // The review view never shows the game over popup
+    let mut terminal = create_terminal(80, 24);
+    terminal
+        .draw(|f| {
+            UI::draw_review(
+                f,
+                &game,
+                Position::from_xy(0, 0),
+                &state,
+                MoveStyle::Chinese,
+            )
+        })
+        .unwrap();
+    let screen = screen_text(&terminal);
+    assert!(screen.contains("Review 1/1"));
+    assert!(!screen.contains("Checkmate"));
+}
+
+#[test]
+fn test_configured_keys_win_over_game_over_shortcuts() {
+    use cn_chess_tui::config::KeyBindings;
+    use cn_chess_tui::i18n::Lang;
+
+    // Two chariots mate the black general
+    let game = Game::from_fen("R3k4/R8/9/9/9/9/9/9/9/3K5 b - - 0 1").unwrap();
+    let screen = |keys: KeyBindings| {
+        UI::set_lang(Lang::En);
+        UI::set_key_bindings(keys);
+        let mut terminal = create_terminal(80, 24);
+        terminal
+            .draw(|f| UI::draw(f, &game, Position::from_xy(0, 0), None))
+            .unwrap();
+        UI::set_key_bindings(KeyBindings::DEFAULT);
+        UI::set_lang(Lang::Bilingual);
+        screen_text(&terminal)
+    };
+
+    let screen_default = screen(KeyBindings::DEFAULT);
+    assert!(screen_default.contains(" n Rematch"), "{}", screen_default);
+    assert!(screen_default.contains(" v Review"), "{}", screen_default);
+
+    // Restart on n and quit on v take those keys from rematch and review
+    let remapped = screen(KeyBindings {
+        restart: 'n',
+        quit: 'v',
+        ..KeyBindings::DEFAULT
+    });
+    assert!(remapped.contains(" n Restart"), "{}", remapped);
+    assert!(remapped.contains(" v Quit"), "{}", remapped);
+    assert!(!remapped.contains("Rematch"), "{}", remapped);
+    assert!(!remapped.contains("Review"), "{}", remapped);
+    assert!(remapped.contains(" p "), "{}", remapped);
+}
+
+#[test]
+fn test_every_theme_colors_the_pieces() {
+    use cn_chess_tui::ui::Theme;
+
+    let game = Game::new();
+    for theme in Theme::ALL {
+        UI::set_theme(theme);
+        let mut terminal = create_terminal(80, 24);
+        terminal
+            .draw(|f| UI::draw(f, &game, Position::from_xy(4, 4), None))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let color_of = |symbol: &str| {
+            buffer
+                .content()
+                .iter()
+                .find(|cell| cell.symbol() == symbol)
+                .map(|cell| cell.fg)
+        };
+        assert_eq!(color_of("兵"), Some(theme.red_piece), "{}", theme.name);
+        assert_eq!(color_of("卒"), Some(theme.black_piece), "{}", theme.name);
+    }
+    UI::set_theme(Theme::CLASSIC);
+}
+
+#[test]
+fn test_analysis_panel_placement() {
+    use cn_chess_tui::ucci::{Info, PopHashInfo};
+    use ratatui::layout::Rect;
+
+    let info = Info {
+        time_ms: None,
+        nodes: Some(5000),
+        depth: Some(12),
+        score: Some(35),
+        multipv: None,
+        pv: vec!["h7e7".to_string(), "h0g2".to_string()],
+        currmove: None,
+        message: None,
+    };
+    let line = ["炮二平五".to_string(), "马8进7".to_string()];
+    let hash = PopHashInfo {
+        bestmove: Some("h7e7".to_string()),
+        lowerbound: Some((35, 12)),
+        upperbound: None,
+    };
+
+    for (width, height) in [(120, 40), (80, 25), (40, 24)] {
+        let size = Rect::new(0, 0, width, height);
+        let area = UI::analysis_area(size);
+        assert!(size.contains(area.as_position()));
+        assert!(area.bottom() < height, "status bar stays visible");
+
+        let mut terminal = create_terminal(width, height);
+        terminal
+            .draw(|f| {
+                UI::draw(f, &Game::new(), Position::from_xy(4, 9), None);
+                UI::draw_analysis_panel(
+                    f,
+                    area,
+                    Some(&info),
+                    &line,
+                    &[],
+                    Some(&hash),
+                    Some("炮二平五"),
+                );
+            })
+            .unwrap();
+        let text = screen_text(&terminal).replace(' ', "");
+        assert!(text.contains("分析"));
+        if width == 120 {
+            // Full layout: inside the sidebar, next to the board
+            assert!(area.x > 0 && area.height > 4);
+            assert!(text.contains("深度12"));
+            assert!(text.contains("炮二平五马8进7"));
+            assert!(text.contains("哈希炮二平五≥+35(12)"));
+        }
+    }
+}
+
+#[test]
+fn test_pv_markers_number_the_line() {
+    use cn_chess_tui::ui::MoveListState;
+    use ratatui::style::Modifier;
+
+    let game = Game::new();
+    let pv = [
+        (Position::from_xy(7, 7), Position::from_xy(4, 7)),
+        (Position::from_xy(7, 0), Position::from_xy(6, 2)),
+        (Position::from_xy(7, 9), Position::from_xy(6, 7)),
+        (Position::from_xy(8, 0), Position::from_xy(7, 0)),
+    ];
+    let mut terminal = create_terminal(120, 40);
+    terminal
+        .draw(|f| {
+            UI::draw_with_pv(
+                f,
+                &game,
+                Position::from_xy(0, 0),
+                None,
+                &pv,
+                &mut MoveListState::default(),
+            )
+        })
+        .unwrap();
+    let buffer = terminal.backend().buffer();
+
+    // The evaluation graph's figures are not markers
+    let graph = UI::eval_graph_area(buffer.area).unwrap_or_default();
+    let digits = |digit: &str| {
+        buffer
+            .content()
+            .iter()
+            .enumerate()
+            .filter(|(i, cell)| {
+                let (x, y) = buffer.pos_of(*i);
+                cell.symbol() == digit && !graph.contains((x, y).into())
+            })
+            .count()
+    };
+    // From and to squares of the first three moves only
+    assert_eq!(digits("1"), 2);
+    assert_eq!(digits("2"), 2);
+    assert_eq!(digits("3"), 2);
+    assert_eq!(digits("4"), 0);
+
+    let underlined = buffer
+        .content()
+        .iter()
+        .filter(|cell| cell.modifier.contains(Modifier::UNDERLINED))
+        .count();
+    assert!(underlined >= 3);
+
+    // Without a line the board is unmarked
+    terminal
+        .draw(|f| UI::draw(f, &game, Position::from_xy(0, 0), None))
+        .unwrap();
+    assert!(!terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .any(|cell| cell.modifier.contains(Modifier::UNDERLINED)));
+}
+
+#[test]
+fn test_game_info_names_the_opening() {
+    let screen = |game: &Game| -> String {
+        let mut terminal = create_terminal(120, 40);
+        terminal
+            .draw(|f| UI::draw(f, game, Position::from_xy(0, 0), None))
+            .unwrap();
+        screen_text(&terminal).replace(' ', "")
+    };
+
+    let mut game = Game::new();
+    assert!(!screen(&game).contains("开局"));
+
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    assert!(screen(&game).contains("开局:中炮"));
+    game.make_move(Position::from_xy(7, 2), Position::from_xy(4, 2))
+        .unwrap();
+    assert!(screen(&game).contains("开局:顺炮"));
+}
+
+#[test]
+fn test_last_move_squares_are_shaded() {
+    let bg = UI::theme().last_move_bg;
+    let shaded = |game: &Game| {
+        let mut terminal = create_terminal(120, 40);
+        terminal
+            .draw(|f| UI::draw(f, game, Position::from_xy(0, 0), None))
+            .unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .filter(|cell| cell.bg == bg)
+            .map(|cell| cell.symbol().to_string())
+            .collect::<String>()
+    };
+
+    let mut game = Game::new();
+    assert!(shaded(&game).is_empty());
+
+    // The cannon's new square and the empty point it left
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    let cells = shaded(&game);
+    assert!(cells.contains('炮'));
+    assert!(cells.contains('┼'));
+    assert_eq!(cells.matches('炮').count(), 1);
+}
+
+#[test]
+fn test_learning_mode_marks_and_explains_pieces() {
+    let bg = UI::theme().selection_bg;
+    // Black's horse, with Red to move
+    let horse = Position::from_xy(1, 0);
+    let shaded = |cursor: Position| {
+        let mut terminal = create_terminal(120, 40);
+        terminal
+            .draw(|f| UI::draw(f, &Game::new(), cursor, None))
+            .unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .filter(|cell| cell.bg == bg)
+            .count()
+    };
+    assert_eq!(shaded(horse), 0);
+
+    UI::set_tutor(true);
+    let marked = shaded(horse);
+    // An empty point has nothing to explain or mark
+    let empty = shaded(Position::from_xy(4, 4));
+
+    let mut terminal = create_terminal(120, 40);
+    terminal
+        .draw(|f| UI::draw_tutor_panel(f, f.area(), &Game::new(), horse, None))
+        .unwrap();
+    UI::set_tutor(false);
+    assert!(marked > 0);
+    assert_eq!(empty, 0);
+
+    let text = screen_text(&terminal);
+    assert!(text.contains("Horse"));
+    assert!(text.contains("can reach 2 points"));
+    assert!(text.contains("Its kind: 4 of the side's 44 moves"));
+    assert!(text.contains("its leg"));
+}
+
+#[test]
+fn test_attack_map_shades_each_side() {
+    use cn_chess_tui::ui::AttackOverlay;
+
+    let theme = UI::theme();
+    let shades = |overlay: AttackOverlay| {
+        UI::set_attack_overlay(overlay);
+        let mut terminal = create_terminal(120, 40);
+        terminal
+            .draw(|f| UI::draw(f, &Game::new(), Position::from_xy(0, 0), None))
+            .unwrap();
+        UI::set_attack_overlay(AttackOverlay::Off);
+        let buffer = terminal.backend().buffer().clone();
+        [
+            theme.red_attack_bg,
+            theme.black_attack_bg,
+            theme.contested_bg,
+        ]
+        .map(|bg| buffer.content().iter().filter(|cell| cell.bg == bg).count())
+    };
+
+    assert_eq!(shades(AttackOverlay::Off), [0, 0, 0]);
+    let [red, black, contested] = shades(AttackOverlay::Red);
+    assert!(red > 0);
+    assert_eq!((black, contested), (0, 0));
+    let [red, black, contested] = shades(AttackOverlay::Both);
+    assert!(red > 0 && black > 0);
+    // Each cannon strikes a horse its own side defends
+    assert!(contested > 0);
+}
+
+#[test]
+fn test_setup_screen_and_picker() {
+    use cn_chess_tui::editor::BoardEditor;
+    use cn_chess_tui::ui::SetupState;
+    use cn_chess_tui::{Board, Color, Piece, PieceType};
+
+    let screen = |state: &SetupState| -> String {
+        let mut terminal = create_terminal(100, 40);
+        terminal
+            .draw(|f| UI::draw_setup(f, state, Position::from_xy(4, 4)))
+            .unwrap();
+        screen_text(&terminal).replace(' ', "")
+    };
+
+    let mut state = SetupState::new(BoardEditor::new(Board::new(), Color::Red));
+    let text = screen(&state);
+    assert!(text.contains("摆棋Setup"));
+    assert!(text.contains("局面合法"));
+
+    // No game over popup for a position missing a general
+    state.editor.remove(Position::from_xy(4, 0));
+    let text = screen(&state);
+    assert!(text.contains("Blackhasnogeneral"));
+    assert!(!text.contains("胜利"));
+
+    // The picker opens on the piece under the cursor
+    state.open_picker(Position::from_xy(1, 7));
+    assert_eq!(state.picked(), Some(Piece::red(PieceType::Cannon)));
+    assert!(screen(&state).contains("选子Pieces"));
+    state.move_picker(1);
+    assert_eq!(state.picked(), Some(Piece::black(PieceType::Cannon)));
+    state.move_picker(100);
+    assert_eq!(state.picked(), None);
+    state.move_picker(-100);
+    assert_eq!(state.picked(), Some(Piece::red(PieceType::General)));
+}
+
+#[test]
+fn test_setup_pieces_dragged_with_the_mouse() {
+    use cn_chess_tui::editor::BoardEditor;
+    use cn_chess_tui::ui::SetupState;
+    use cn_chess_tui::{Board, Color, Piece, PieceType};
+
+    let mut state = SetupState::new(BoardEditor::new(Board::new(), Color::Red));
+    let mut terminal = create_terminal(100, 40);
+    terminal
+        .draw(|f| UI::draw_setup(f, &state, Position::from_xy(4, 4)))
+        .unwrap();
+
+    // Every point and palette piece can be found under some cell
+    let cells: Vec<(u16, u16)> = (0..100)
+        .flat_map(|x| (0..40).map(move |y| (x, y)))
+        .collect();
+    let centre = Position::from_xy(4, 4);
+    assert!(cells
+        .iter()
+        .any(|&(x, y)| UI::board_position_at(x, y) == Some(centre)));
+    let horse = Piece::black(PieceType::Horse);
+    let (x, y) = *cells
+        .iter()
+        .find(|&&(x, y)| UI::palette_piece_at(x, y) == Some(horse))
+        .expect("palette beside the board");
+    assert_eq!(UI::board_position_at(x, y), None);
+
+    // From the palette onto the board
+    state.drag_from_palette(horse);
+    assert!(state.drop_on(Some(centre)));
+    assert_eq!(state.editor.board().get(centre), Some(&horse));
+
+    // Between points, in one undoable edit
+    let chariot = Position::from_xy(0, 9);
+    state.drag_from_square(chariot);
+    assert!(state.drop_on(Some(Position::from_xy(0, 5))));
+    assert_eq!(state.editor.board().get(chariot), None);
+    assert!(state.editor.undo());
+    assert_eq!(
+        state.editor.board().get(chariot),
+        Some(&Piece::red(PieceType::Chariot))
+    );
+
+    // Off the board removes it; an empty point lifts nothing
+    state.drag_from_square(chariot);
+    assert!(state.drop_on(None));
+    assert_eq!(state.editor.board().get(chariot), None);
+    state.drag_from_square(chariot);
+    assert_eq!(state.drag, None);
+}
+
+#[test]
+fn test_move_history_scrolls_through_the_whole_game() {
+    use cn_chess_tui::ui::{MoveListState, ReviewState};
+    use ratatui::style::Modifier;
+
+    // Each chariot snakes through three ranks of its own half: 26 full moves
+    let mut game = Game::from_fen("4k4/4a4/r8/9/9/R8/9/9/4A4/4K4 w - - 0 1").unwrap();
+    let path = |first_rank: usize| -> Vec<Position> {
+        (0..3)
+            .flat_map(|r| {
+                let y = first_rank + r;
+                (0..9).map(move |i| Position::from_xy(if r == 1 { 8 - i } else { i }, y))
+            })
+            .collect()
+    };
+    let (red, black) = (path(5), path(2));
+    for i in 1..red.len() {
+        game.make_move(red[i - 1], red[i]).unwrap();
+        game.make_move(black[i - 1], black[i]).unwrap();
+    }
+    assert_eq!(game.get_moves().len(), 52);
+
+    let text =
+        |terminal: &Terminal<TestBackend>| -> String { screen_text(terminal).replace(' ', "") };
+
+    // Follows the latest move, with both sides' moves on one row
+    let mut terminal = create_terminal(120, 40);
+    let mut state = MoveListState::default();
+    terminal
+        .draw(|f| UI::draw_with_pv(f, &game, Position::from_xy(4, 9), None, &[], &mut state))
+        .unwrap();
+    assert!(state.page_rows < 26);
+    let screen = text(&terminal);
+    assert!(screen.contains("26.车二平一车8平9"), "{}", screen);
+    assert!(!screen.contains("1.车九平八"));
+
+    // Scrolled to the first move, then back to following
+    state.home(26);
+    terminal
+        .draw(|f| UI::draw_with_pv(f, &game, Position::from_xy(4, 9), None, &[], &mut state))
+        .unwrap();
+    assert!(text(&terminal).contains("1.车九平八车1平2"));
+    assert!(!text(&terminal).contains("26."));
+    state.page_down(26);
+    state.page_down(26);
+    assert_eq!(state.top, None);
+
+    // Reviewing marks the move that led to the position shown
+    let review = ReviewState {
+        ply: 3,
+        ..ReviewState::new(52)
+    };
+    terminal
+        .draw(|f| {
+            UI::draw_review(
+                f,
+                &game,
+                Position::from_xy(4, 9),
+                &review,
+                MoveStyle::Chinese,
+            )
+        })
+        .unwrap();
+    // The board cursor is reversed too, so look at each row on its own
+    let buffer = terminal.backend().buffer();
+    let marked: Vec<String> = buffer
+        .content()
+        .chunks(buffer.area.width as usize)
+        .map(|row| {
+            row.iter()
+                .filter(|cell| cell.modifier.contains(Modifier::REVERSED))
+                .map(|cell| cell.symbol())
+                .filter(|s| *s != " ")
+                .collect()
+        })
+        .filter(|row: &String| !row.is_empty())
+        .collect();
+    assert!(marked.contains(&"车八平七".to_string()), "{:?}", marked);
+}
+
+#[test]
+fn test_move_history_notation_styles() {
+    use cn_chess_tui::ui::MoveListState;
+
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 2), Position::from_xy(4, 2))
+        .unwrap();
+
+    let mut terminal = create_terminal(120, 40);
+    for (style, row) in [
+        (MoveStyle::Chinese, "1.炮二平五炮8平5"),
+        (MoveStyle::Wxf, "1.C2.5C8.5"),
+        (MoveStyle::Iccs, "1.h7e7h2e2"),
+    ] {
+        let mut state = MoveListState {
+            style,
+            ..MoveListState::default()
+        };
+        terminal
+            .draw(|f| UI::draw_with_pv(f, &game, Position::from_xy(4, 9), None, &[], &mut state))
+            .unwrap();
+        let screen = screen_text(&terminal).replace(' ', "");
+        assert!(screen.contains(row), "{:?}: {}", style, screen);
+    }
+}
+
+#[test]
+fn test_move_history_shows_move_times() {
+    use cn_chess_tui::ui::MoveListState;
+
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 2), Position::from_xy(4, 2))
+        .unwrap();
+    game.set_move_time(0, Some(8_000));
+    game.set_move_time(1, Some(125_000));
+
+    let mut terminal = create_terminal(120, 40);
+    terminal
+        .draw(|f| {
+            UI::draw_with_pv(
+                f,
+                &game,
+                Position::from_xy(4, 9),
+                None,
+                &[],
+                &mut MoveListState::default(),
+            )
+        })
+        .unwrap();
+    let screen = screen_text(&terminal).replace(' ', "");
+    assert!(screen.contains("1.炮二平五8s炮8平52:05"), "{}", screen);
+}
+
+#[test]
+fn test_move_history_shows_comments() {
+    use cn_chess_tui::ui::{CommentEditorState, ReviewState};
+
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 0), Position::from_xy(6, 2))
+        .unwrap();
+    game.set_comment(0, Some("Centralcannonopening".to_string()));
+
+    let text =
+        |terminal: &Terminal<TestBackend>| -> String { screen_text(terminal).replace(' ', "") };
+
+    // Commented moves are marked; the comment shows for the reviewed move
+    let mut terminal = create_terminal(120, 40);
+    let review = ReviewState {
+        ply: 1,
+        ..ReviewState::new(2)
+    };
+    terminal
+        .draw(|f| {
+            UI::draw_review(
+                f,
+                &game,
+                Position::from_xy(4, 9),
+                &review,
+                MoveStyle::Chinese,
+            )
+        })
+        .unwrap();
+    let screen = text(&terminal);
+    assert!(screen.contains("炮二平五*"), "{}", screen);
+    assert!(screen.contains("Centralcannonopening"));
+
+    // Not for a move without one
+    let review = ReviewState {
+        ply: 2,
+        ..ReviewState::new(2)
+    };
+    terminal
+        .draw(|f| {
+            UI::draw_review(
+                f,
+                &game,
+                Position::from_xy(4, 9),
+                &review,
+                MoveStyle::Chinese,
+            );
+            UI::draw_comment_editor(
+                f,
+                &CommentEditorState::new(1, "1... 马8进7", Some("Screen horse")),
+            );
+        })
+        .unwrap();
+    let screen = text(&terminal);
+    assert!(!screen.contains("Centralcannonopening"));
+    assert!(screen.contains("Screenhorse_"));
+}
+
+#[test]
+fn test_review_steps_into_variations() {
+    use cn_chess_tui::pgn::PgnGame;
+    use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+    use cn_chess_tui::ui::ReviewState;
+
+    let pgn = PgnGame::parse(
+        "1. 炮二平五 (1. 炮八平五 马8进7) (1. 兵三进一) 马8进7 (1... 炮8平5 2. 马二进三) 2. 马二进三",
+    )
+    .unwrap();
+    let game = import_pgn(&pgn, ImportMode::Strict).unwrap().game;
+    assert_eq!(game.variations(0).len(), 2);
+    assert_eq!(game.variations(1)[0][0].notation, "h2e2");
+
+    // From the start, into the first variation of the first move
+    let mut state = ReviewState::new(3);
+    state.ply = 0;
+    assert!(state.enter_variation(&game));
+    assert_eq!((state.ply, state.total), (1, 2));
+    assert_eq!(state.path, [(0, 0)]);
+
+    // Again at its first move: on to the second variation, then no more
+    assert!(state.enter_variation(&game));
+    assert_eq!(
+        (state.ply, state.total, state.path.clone()),
+        (1, 1, vec![(0, 1)])
+    );
+    assert!(!state.enter_variation(&game));
+
+    let mut terminal = create_terminal(120, 40);
+    terminal
+        .draw(|f| {
+            UI::draw_review(
+                f,
+                &game,
+                Position::from_xy(4, 9),
+                &state,
+                MoveStyle::Chinese,
+            )
+        })
+        .unwrap();
+    let screen = screen_text(&terminal).replace(' ', "");
+    assert!(screen.contains("1.兵三进一"), "{}", screen);
+    assert!(screen.contains("Variation2/2"), "{}", screen);
+
+    // Back out to where it branched, then into Black's variation
+    assert!(state.leave_variation(&game));
+    assert_eq!((state.ply, state.total), (0, 3));
+    assert!(!state.leave_variation(&game));
+    state.step(1);
+    assert!(state.enter_variation(&game));
+    assert_eq!(
+        (state.ply, state.total, state.path.clone()),
+        (2, 3, vec![(1, 0)])
+    );
+
+    terminal
+        .draw(|f| {
+            UI::draw_review(
+                f,
+                &game,
+                Position::from_xy(4, 9),
+                &state,
+                MoveStyle::Chinese,
+            )
+        })
+        .unwrap();
+    let screen = screen_text(&terminal).replace(' ', "");
+    assert!(screen.contains("1.炮二平五~炮8平5"), "{}", screen);
+    assert!(screen.contains("2.马二进三"), "{}", screen);
+}
+
+#[test]
+fn test_letter_pieces_and_blindfold() {
+    use cn_chess_tui::ui::{PieceStyle, Theme};
+
+    let game = Game::new();
+    let piece_cells = |game: &Game| -> Vec<(String, ratatui::style::Color)> {
+        let mut terminal = create_terminal(100, 40);
+        terminal
+            .draw(|f| UI::draw(f, game, Position::from_xy(4, 4), None))
+            .unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .filter(|cell| {
+                cell.fg == Theme::CLASSIC.red_piece || cell.fg == Theme::CLASSIC.black_piece
+            })
+            .map(|cell| (cell.symbol().to_string(), cell.fg))
+            .collect()
+    };
+
+    UI::set_piece_style(PieceStyle::Letters);
+    let cells = piece_cells(&game);
+    assert!(cells.contains(&("K".to_string(), Theme::CLASSIC.red_piece)));
+    assert!(cells.contains(&("k".to_string(), Theme::CLASSIC.black_piece)));
+    assert!(!cells.iter().any(|(symbol, _)| symbol == "帅"));
+
+    // Blindfold hides the pieces whatever their style
+    UI::set_blindfold(true);
+    let cells = piece_cells(&game);
+    assert!(!cells
+        .iter()
+        .any(|(symbol, _)| symbol == "K" || symbol == "k"));
+
+    UI::set_piece_style(PieceStyle::Chinese);
+    assert!(!piece_cells(&game).iter().any(|(symbol, _)| symbol == "帅"));
+    UI::set_blindfold(false);
+    assert!(piece_cells(&game).iter().any(|(symbol, _)| symbol == "帅"));
+}
+
+#[test]
+fn test_eval_graph_in_full_layout() {
+    use cn_chess_tui::game::MoveStats;
+
+    // Only the full layout has room for the graph
+    use ratatui::layout::Rect;
+
+    assert!(UI::eval_graph_area(Rect::new(0, 0, 80, 24)).is_none());
+    let size = Rect::new(0, 0, 120, 40);
+    let area = UI::eval_graph_area(size).expect("graph in the full layout");
+
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    game.make_move(Position::from_xy(7, 0), Position::from_xy(6, 2))
+        .unwrap();
+    game.make_move(Position::from_xy(4, 7), Position::from_xy(4, 3))
+        .unwrap();
+    let stats = [
+        MoveStats {
+            eval: Some(30),
+            time_ms: Some(1_500),
+        },
+        MoveStats {
+            eval: None,
+            time_ms: Some(61_000),
+        },
+        MoveStats {
+            eval: Some(-120),
+            time_ms: Some(2_000),
+        },
+    ];
+
+    let mut terminal = create_terminal(size.width, size.height);
+    terminal
+        .draw(|f| UI::draw_eval_graph(f, area, &game, &stats))
+        .unwrap();
+    let text = screen_text(&terminal).replace(' ', "");
+    assert!(text.contains("局势Evaluation"), "{}", text);
+    // Red took 3.5s and captured the pawn; Black a minute
+    assert!(text.contains("红0:03吃1黑1:01吃0-120"), "{}", text);
+    let mobility = format!(
+        "红{}黑{}",
+        game.mobility(cn_chess_tui::Color::Red).total(),
+        game.mobility(cn_chess_tui::Color::Black).total()
+    );
+    assert!(text.contains(&mobility), "{}", text);
+    assert!(
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .any(|cell| ('\u{2801}'..='\u{28ff}').contains(&cell.symbol().chars().next().unwrap())),
+        "the evaluations are plotted"
+    );
+}
+
+#[test]
+fn test_command_line_shows_completions() {
+    use cn_chess_tui::command::complete;
+    use cn_chess_tui::ui::CommandLineState;
+    use ratatui::layout::Rect;
+
+    let (text, completions) = complete("e");
+    let state = CommandLineState { text, completions };
+    let mut terminal = create_terminal(60, 20);
+    terminal
+        .draw(|f| UI::draw_command_line(f, Rect::new(0, 19, 60, 1), &state))
+        .unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let row: String = (0..60).map(|x| buffer[(x, 19)].symbol()).collect();
+    assert!(row.starts_with(":e_"));
+    assert!(row.trim_end().ends_with("engine  export"));
+}
+
+#[test]
+fn test_ai_menu_shows_difficulty() {
+    use cn_chess_tui::ui::AiMenuState;
+    use cn_chess_tui::{AiMode, Difficulty};
+
+    let screen = |difficulty| {
+        let state = AiMenuState {
+            selected: 5,
+            show_thinking: false,
+            difficulty,
+        };
+        let mut terminal = create_terminal(60, 20);
+        terminal
+            .draw(|f| UI::draw_ai_menu(f, AiMode::Off, false, &state))
+            .unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        (0..20)
+            .map(|y| (0..60).map(|x| buffer[(x, y)].symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    assert!(screen(Some(Difficulty::Beginner)).contains("Difficulty: ◀ Beginner ▶"));
+    assert!(screen(None).contains("Difficulty: ◀ Custom ▶"));
+}
+
+#[test]
+fn test_new_game_menu_form() {
+    use cn_chess_tui::game::{AiMode, TimeControl};
+    use cn_chess_tui::ui::{NewGameMenuState, PlayerSide};
+
+    let mut state = NewGameMenuState::new(AiMode::PlaysBlack, None, None);
+    assert_eq!(state.side, PlayerSide::Red);
+    state.change(true);
+    assert_eq!(state.ai_mode(), AiMode::PlaysRed);
+    state.selected = 3;
+    state.change(true);
+    assert_eq!(state.time_control, Some(TimeControl::PRESETS[0]));
+    state.selected = 4;
+    state.change(true);
+    assert_eq!(state.selected_handicap().unwrap().spec, "horse");
+    state.change(false);
+    assert!(state.selected_handicap().is_none());
+    state.change(false);
+
+    let mut terminal = create_terminal(60, 20);
+    terminal
+        .draw(|f| UI::draw_new_game_menu(f, &state))
+        .unwrap();
+    // Chinese names are two cells wide, so rows are matched on the English
+    let buffer = terminal.backend().buffer();
+    let rows: Vec<String> = (0..20)
+        .map(|y| (0..60).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+    assert!(rows.iter().any(|row| row.contains("Black ▶")));
+    assert!(rows.iter().any(|row| row.contains("AI:        ◀ On ▶")));
+    assert!(rows.iter().any(|row| row.contains("◀ 5+3 min ▶")));
+    let row = rows
+        .iter()
+        .find(|row| row.contains("Chariot and horse"))
+        .unwrap();
+    assert!(row.contains("> Start:"));
+}
+
+#[test]
+fn test_too_small_screen_names_the_minimum() {
+    use cn_chess_tui::ui::{MIN_TERMINAL_HEIGHT, MIN_TERMINAL_WIDTH};
+    use ratatui::layout::Rect;
+
+    let mut terminal = create_terminal(30, 8);
+    terminal.draw(UI::draw_too_small).unwrap();
+    let screen = terminal.backend().to_string();
+    assert!(screen.contains("Terminal too small"), "{}", screen);
+    assert!(
+        screen.contains(&format!(
+            "need {}x{}, have 30x8",
+            MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT
+        )),
+        "{}",
+        screen
+    );
+
+    // The smallest size that fits still shows the whole board
+    let size = Rect::new(0, 0, MIN_TERMINAL_WIDTH, MIN_TERMINAL_HEIGHT);
+    assert!(UI::fits(size));
+    assert!(!UI::fits(Rect {
+        height: MIN_TERMINAL_HEIGHT - 1,
+        ..size
+    }));
+    let mut terminal = create_terminal(size.width, size.height);
+    terminal
+        .draw(|f| UI::draw(f, &Game::new(), Position::from_xy(4, 9), None))
+        .unwrap();
+    let screen = terminal.backend().to_string();
+    assert!(screen.contains("r─n─b─a─k─a─b─n─r"), "{}", screen);
+    assert!(screen.contains("R─N─B─A─K─A─B─N─R"), "{}", screen);
+
+    // Tiny terminals get as much of the message as fits
+    for (width, height) in [(1, 1), (5, 2), (0, 0)] {
+        create_terminal(width, height)
+            .draw(UI::draw_too_small)
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_english_interface_has_no_chinese_labels() {
+    use cn_chess_tui::i18n::Lang;
+
+    UI::set_lang(Lang::En);
+    let mut terminal = create_terminal(120, 40);
+    terminal
+        .draw(|f| UI::draw(f, &Game::new(), Position::from_xy(4, 9), None))
+        .unwrap();
+    UI::set_lang(Lang::Bilingual);
+    let screen = screen_text(&terminal);
+    assert!(screen.contains("Board"), "{}", screen);
+    assert!(screen.contains("History"), "{}", screen);
+    assert!(!screen.contains("棋盘"), "{}", screen);
+    assert!(!screen.contains("着法记录"), "{}", screen);
+}
+
+#[test]
+fn test_stats_screen_shows_rating_and_results_per_opponent() {
+    use cn_chess_tui::stats::{Outcome, Stats};
+
+    let mut stats = Stats::default();
+    stats.record("AI (Beginner)", 1000, Some("中炮"), Outcome::Win);
+    stats.record("AI (Beginner)", 1000, Some("中炮"), Outcome::Win);
+    stats.record("pikafish", 2400, None, Outcome::Loss);
+
+    let mut terminal = create_terminal(80, 25);
+    terminal.draw(|f| UI::draw_stats(f, &stats)).unwrap();
+    let screen = screen_text(&terminal).replace(' ', "");
+    let rating = format!("Rating{}(peak{})", stats.rating, stats.peak_rating());
+    assert!(screen.contains(&rating), "{}", screen);
+    assert!(screen.contains("Streak:lossx1"), "{}", screen);
+    assert!(screen.contains("+2-0=0(100%)AI(Beginner)"), "{}", screen);
+    assert!(screen.contains("+0-1=0(0%)pikafish"), "{}", screen);
+    assert!(screen.contains("+2-0=0(100%)中炮"), "{}", screen);
+}
+
+#[test]
+fn test_kibitz_panel_shows_each_engine_in_a_column() {
+    use cn_chess_tui::ucci::PvLine;
+    use cn_chess_tui::ui::KibitzColumn;
+    use ratatui::layout::Rect;
+
+    let line = |index, score, pv: &str| PvLine {
+        index,
+        depth: Some(8),
+        score: Some(score),
+        pv: vec![pv.to_string()],
+    };
+    let (best, second, other) = (
+        line(1, 30, "h7e7"),
+        line(2, 5, "h9g7"),
+        line(1, -12, "h7e7"),
+    );
+    let engines = [
+        KibitzColumn {
+            name: "First",
+            lines: vec![
+                (&best, vec!["炮二平五".to_string()]),
+                (&second, vec!["马二进三".to_string()]),
+            ],
+        },
+        KibitzColumn {
+            name: "Second",
+            lines: vec![(&other, vec!["炮二平五".to_string()])],
+        },
+    ];
+
+    let mut terminal = create_terminal(80, 25);
+    terminal
+        .draw(|f| UI::draw_kibitz_panel(f, Rect::new(0, 0, 80, 5), &engines))
+        .unwrap();
+    let rows: Vec<String> = (0..5)
+        .map(|y| {
+            (0..80)
+                .map(|x| terminal.backend().buffer()[(x, y)].symbol())
+                .filter(|symbol| *symbol != " ")
+                .collect::<String>()
+        })
+        .collect();
+    assert!(rows[0].contains("Kibitz:First"), "{:?}", rows);
+    assert!(rows[0].contains("Kibitz:Second"), "{:?}", rows);
+    assert!(rows[1].contains("+30d8炮二平五"), "{:?}", rows);
+    assert!(rows[1].contains("-12d8炮二平五"), "{:?}", rows);
+    assert!(rows[2].contains("+5d8马二进三"), "{:?}", rows);
+}
+
+#[test]
+fn test_analysis_panel_lists_further_lines() {
+    use cn_chess_tui::ucci::{Info, PvLine};
+
+    let info = Info {
+        time_ms: None,
+        nodes: None,
+        depth: Some(10),
+        score: Some(35),
+        multipv: Some(1),
+        pv: vec!["h7e7".to_string()],
+        currmove: None,
+        message: None,
+    };
+    let second = PvLine {
+        index: 2,
+        depth: Some(10),
+        score: Some(20),
+        pv: vec!["h9g7".to_string()],
+    };
+    let others = [(&second, vec!["马二进三".to_string()])];
+
+    let mut terminal = create_terminal(80, 25);
+    let area = UI::analysis_area(ratatui::layout::Rect::new(0, 0, 80, 25));
+    terminal
+        .draw(|f| {
+            UI::draw_analysis_panel(
+                f,
+                area,
+                Some(&info),
+                &["炮二平五".to_string()],
+                &others,
+                None,
+                None,
+            )
+        })
+        .unwrap();
+    let text = screen_text(&terminal).replace(' ', "");
+    assert!(text.contains("炮二平五"), "{}", text);
+    assert!(text.contains("2.+20d10马二进三"), "{}", text);
+}
@@ -1,6 +1,6 @@
-use cn_chess_tui::game::Game;
+use cn_chess_tui::game::{Game, GameState};
 use cn_chess_tui::types::Position;
-use cn_chess_tui::ui::UI;
+use cn_chess_tui::ui::{PieceStyle, UI};
 use insta::assert_snapshot;
 use ratatui::{backend::TestBackend, Terminal};
 
@@ -44,8 +44,8 @@ fn test_initial_position_ui() {
 /// or when users prefer compact windows. Uses cursor at (0, 0) with no
 /// selection to match the standard test behavior.
 ///
-/// Note: The plan specified 40x22, but that size causes a panic in the UI code.
-/// Using 40x26 as the minimum working size that demonstrates compact layout.
+/// Terminals shorter than 24 rows draw a smaller board, covered by
+/// `test_short_terminal`.
 #[test]
 fn test_initial_position_small_terminal() {
     let game = Game::new();
@@ -140,15 +140,16 @@ fn test_after_first_move() {
 /// - Visual alert draws attention to the threatened general
 ///
 /// The FEN string represents a simplified position where Black's king at (4, 0)
-/// is under attack from Red's chariot at (4, 2). Check is a critical state in
+/// is under attack from Red's chariot at (4, 2), with Red's general at (3, 9). Check is a critical state in
 /// Chinese chess where the general is threatened and must be protected on the next move.
 ///
 /// Uses an 80x24 terminal with cursor at (0, 0) and no selection to match
 /// the standard test behavior established in previous snapshot tests.
 #[test]
 fn test_check_state() {
-    // Simplified FEN with only Black's king and Red's chariot in checking position
-    let fen = "4k4/9/4R4/9/9/9/9/9/9/9 b - - 0 1";
+    // Simplified FEN with Black's king in check from Red's chariot; Red's
+    // general stands off the e-file so the generals don't face each other
+    let fen = "4k4/9/4R4/9/9/9/9/9/9/3K5 b - - 0 1";
     let game = Game::from_fen(fen).expect("Invalid FEN string for check state");
 
     let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
@@ -208,9 +209,8 @@ fn test_checkmate_state() {
 /// or when users prefer minimal window sizes. Uses cursor at (0, 0)
 /// with no selection to match standard test behavior.
 ///
-/// Note: 30x24 is the minimum working size that demonstrates compact layout.
-/// The plan specified 22x22, but that size causes a panic in the UI code
-/// due to insufficient space for all UI elements.
+/// Note: 30x24 is the smallest size with two-row cells; shorter terminals
+/// draw one row per rank, as in `test_short_terminal`.
 #[test]
 fn test_compact_layout() {
     let game = Game::new();
@@ -279,6 +279,88 @@ fn test_full_layout() {
     assert_snapshot!("full_layout", terminal.backend());
 }
 
+/// Test snapshot of double-width pieces in the narrowest cells (64x30).
+///
+/// Below 70 columns each board cell is 2 columns wide, the same as a Chinese
+/// character, so neighbouring pieces touch. This verifies that:
+/// - Every piece covers its own intersection
+/// - Pieces on the right-hand file stay inside the board border
+/// - The cursor and selection on edge squares don't shift the row
+#[test]
+fn test_narrow_cells() {
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(8, 9), Position::from_xy(8, 7))
+        .unwrap();
+
+    let mut terminal = Terminal::new(TestBackend::new(64, 30)).unwrap();
+
+    terminal
+        .draw(|f| {
+            let cursor = Position::from_xy(8, 7);
+            UI::draw(f, &game, cursor, Some(Position::from_xy(0, 9)));
+        })
+        .unwrap();
+
+    assert_snapshot!("narrow_cells", terminal.backend());
+}
+
+/// Test snapshot of letter pieces in 2- and 3-column cells (40x26, 80x24).
+///
+/// Letters are a single column wide and should sit exactly on the grid
+/// intersections rather than being centered between them.
+#[test]
+fn test_letter_pieces_in_narrow_cells() {
+    let game = Game::new();
+    UI::set_piece_style(PieceStyle::Letters);
+
+    for (name, width, height) in [("letters_40x26", 40, 26), ("letters_80x24", 80, 24)] {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|f| {
+                let cursor = Position::from_xy(0, 0);
+                UI::draw(f, &game, cursor, None);
+            })
+            .unwrap();
+        assert_snapshot!(name, terminal.backend());
+    }
+
+    UI::set_piece_style(PieceStyle::Chinese);
+}
+
+/// ICCS file letters run under the board and rank numbers up its left side
+#[test]
+fn test_coordinate_labels() {
+    let game = Game::new();
+    UI::set_coordinates(true);
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+    terminal
+        .draw(|f| UI::draw(f, &game, Position::from_xy(4, 9), None))
+        .unwrap();
+    assert_snapshot!("coordinates", terminal.backend());
+
+    UI::set_coordinates(false);
+}
+
+/// A flipped board has Red at the top, with its labels turned around too
+#[test]
+fn test_flipped_board() {
+    let mut game = Game::new();
+    game.make_move(Position::from_xy(7, 7), Position::from_xy(4, 7))
+        .unwrap();
+    UI::set_flipped(true);
+    UI::set_coordinates(true);
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+    terminal
+        .draw(|f| UI::draw(f, &game, Position::from_xy(4, 9), None))
+        .unwrap();
+    assert_snapshot!("flipped", terminal.backend());
+
+    UI::set_coordinates(false);
+    UI::set_flipped(false);
+}
+
 /// Test snapshot consistency - identical game states produce identical UI.
 ///
 /// This test verifies that the same game state rendered multiple times
@@ -327,3 +409,116 @@ fn test_snapshot_consistency() {
         "identical game states should produce identical UI"
     );
 }
+
+/// Test that remapped keys and the chosen theme show up in the UI.
+///
+/// Keys from `config.toml` replace the defaults in the game over popup, and
+/// every color comes from the theme set with `UI::set_theme`.
+#[test]
+fn test_configured_keys_and_theme() {
+    use cn_chess_tui::config::KeyBindings;
+    use cn_chess_tui::ui::Theme;
+
+    UI::set_theme(Theme::LIGHT);
+    UI::set_key_bindings(KeyBindings {
+        undo: 'z',
+        redo: 'w',
+        restart: 'n',
+        quit: 'x',
+    });
+
+    // Two chariots mate the black general
+    let game = Game::from_fen("R3k4/R8/9/9/9/9/9/9/9/3K5 b - - 0 1").unwrap();
+    assert!(matches!(game.state(), GameState::Checkmate(_)));
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    terminal
+        .draw(|f| UI::draw(f, &game, Position::from_xy(0, 0), None))
+        .unwrap();
+
+    UI::set_theme(Theme::CLASSIC);
+    UI::set_key_bindings(KeyBindings::DEFAULT);
+
+    let buffer = terminal.backend().buffer();
+    assert!(buffer
+        .content()
+        .iter()
+        .any(|cell| cell.fg == Theme::LIGHT.primary));
+    assert!(!buffer
+        .content()
+        .iter()
+        .any(|cell| cell.fg == Theme::CLASSIC.primary));
+    assert_snapshot!(terminal.backend());
+}
+
+/// Test snapshot of the one-row board on a short terminal (40x20).
+///
+/// Below 24 rows the board no longer fits with two rows per rank, so each
+/// rank takes a single row, the river text goes and pieces are written as
+/// letters. The whole board must still be visible.
+#[test]
+fn test_short_terminal() {
+    let game = Game::new();
+    let mut terminal = Terminal::new(TestBackend::new(40, 20)).unwrap();
+
+    terminal
+        .draw(|f| UI::draw(f, &game, Position::from_xy(0, 0), None))
+        .unwrap();
+
+    let screen = terminal.backend().to_string();
+    assert!(screen.contains("R─N─B─A─K─A─B─N─R"), "{}", screen);
+    assert!(screen.contains("r─n─b─a─k─a─b─n─r"), "{}", screen);
+    assert_snapshot!("short_terminal", terminal.backend());
+}
+
+/// Test snapshot of a very short terminal (40x14).
+///
+/// With no room for the title and help bars next to the one-row board,
+/// they are left out rather than cutting the board short.
+#[test]
+fn test_very_short_terminal() {
+    let game = Game::new();
+    let mut terminal = Terminal::new(TestBackend::new(40, 14)).unwrap();
+
+    terminal
+        .draw(|f| UI::draw(f, &game, Position::from_xy(4, 9), None))
+        .unwrap();
+
+    let screen = terminal.backend().to_string();
+    assert!(!screen.contains("Help"), "{}", screen);
+    assert!(screen.contains("R─N─B─A─K─A─B─N─R"), "{}", screen);
+    assert_snapshot!("very_short_terminal", terminal.backend());
+}
+
+/// Test snapshot of the board widget embedded on its own.
+///
+/// Another application draws a flipped board in letters with one-row
+/// cells and ICCS labels, with the cursor and selection kept in a
+/// `BoardState`. Clicks on the drawn board map back to positions.
+#[test]
+fn test_board_widget_standalone() {
+    use cn_chess_tui::widget::{BoardState, BoardWidget};
+
+    let game = Game::new();
+    let mut state = BoardState::new(Position::from_xy(4, 9));
+    state.selected = Some(Position::from_xy(7, 7));
+    let mut terminal = Terminal::new(TestBackend::new(24, 12)).unwrap();
+
+    terminal
+        .draw(|f| {
+            let board = BoardWidget::new(game.board())
+                .piece_style(PieceStyle::Letters)
+                .cell_size(2, 1)
+                .river_text(false)
+                .flipped(true)
+                .coordinates(true);
+            f.render_stateful_widget(board, f.area(), &mut state);
+        })
+        .unwrap();
+
+    let screen = terminal.backend().to_string();
+    assert!(screen.contains("R─N─B─A─K─A─B─N─R"), "{}", screen);
+    assert_snapshot!("board_widget_standalone", terminal.backend());
+    // Flipped, Red's back rank is on top with its right chariot first
+    assert_eq!(state.position_at(4, 0), Some(Position::from_xy(8, 9)));
+    assert_eq!(state.position_at(0, 0), None);
+}
@@ -0,0 +1,53 @@
+//! Tests for reading XQF files
+//!
+//! `fixtures/encrypted_v18.xqf` is a version 18 (encrypted) file with
+//! nonzero keys, so the piece list is rotated and every key is in use. It
+//! was written by a separate encoder that follows ElephantEye's XQF2PGN
+//! reader rather than this crate's decoder, so a mistake in the decoder's
+//! key derivation cannot cancel itself out here.
+
+use cn_chess_tui::formats::xqf::read_xqf_file;
+use cn_chess_tui::notation::format::MoveStyle;
+use cn_chess_tui::pgn::PgnGameResult;
+use cn_chess_tui::pgn_import::{import_pgn, ImportMode};
+use std::path::Path;
+
+fn fixture(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+#[test]
+fn test_encrypted_file_decodes_to_known_game() {
+    let game = read_xqf_file(fixture("encrypted_v18.xqf")).unwrap();
+
+    assert_eq!(game.get_tag("Event").unwrap(), "全国象棋个人赛");
+    assert_eq!(game.get_tag("Site").unwrap(), "北京");
+    assert_eq!(game.get_tag("Date").unwrap(), "1980");
+    assert_eq!(game.get_tag("Red").unwrap(), "胡荣华");
+    assert_eq!(game.get_tag("Black").unwrap(), "杨官璘");
+    assert_eq!(game.result, PgnGameResult::Draw);
+    assert_eq!(game.get_tag("FEN"), None);
+
+    // The main line; the variation on the last move is skipped
+    let moves: Vec<&str> = game.moves.iter().map(|m| m.notation.as_str()).collect();
+    assert_eq!(moves, ["h7e7", "h0g2", "h9g7", "i0h0", "i9h9", "b0c2"]);
+    assert_eq!(game.moves[1].comment.as_deref(), Some("屏风马"));
+    assert_eq!(game.moves[5].comment.as_deref(), Some("主变"));
+
+    let report = import_pgn(&game, ImportMode::Strict).unwrap();
+    assert!(report.is_clean(), "{:?}", report.issues);
+    assert_eq!(
+        report.game.move_notations(MoveStyle::Chinese),
+        [
+            "炮二平五",
+            "马8进7",
+            "马二进三",
+            "车9平8",
+            "车一平二",
+            "马2进3"
+        ]
+    );
+}